@@ -499,12 +499,65 @@ fn complexity_benchmarks(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark signature comparisons with plain and interned types.
+///
+/// Matches the parameter list of every registered function against every
+/// other, as overload resolution does, once comparing `DataType`s and once
+/// comparing `TypeRef`s interned in a `TypeTable`.
+fn type_comparison_benchmarks(c: &mut Criterion) {
+    use angelscript_core::{DataType, TypeRef, TypeTable};
+
+    let ctx = Context::with_default_modules().unwrap();
+    let signatures: Vec<Vec<DataType>> = ctx
+        .registry()
+        .functions()
+        .map(|f| f.def.params.iter().map(|p| p.data_type).collect())
+        .collect();
+    let mut table = TypeTable::new();
+    let interned: Vec<Vec<TypeRef>> = signatures
+        .iter()
+        .map(|params| params.iter().map(|&t| table.intern(t)).collect())
+        .collect();
+
+    let mut group = c.benchmark_group("types/signature_matching");
+    group.throughput(Throughput::Elements(
+        (signatures.len() * signatures.len()) as u64,
+    ));
+
+    group.bench_function("data_type", |b| {
+        b.iter(|| {
+            let mut matches = 0usize;
+            for a in &signatures {
+                for b in &signatures {
+                    matches += usize::from(black_box(a) == black_box(b));
+                }
+            }
+            black_box(matches)
+        });
+    });
+
+    group.bench_function("type_ref", |b| {
+        b.iter(|| {
+            let mut matches = 0usize;
+            for a in &interned {
+                for b in &interned {
+                    matches += usize::from(black_box(a) == black_box(b));
+                }
+            }
+            black_box(matches)
+        });
+    });
+
+    group.finish();
+}
+
 criterion_group!(
     benches,
     size_based_benchmarks,
     feature_specific_benchmarks,
     real_world_benchmarks,
-    complexity_benchmarks
+    complexity_benchmarks,
+    type_comparison_benchmarks
 );
 
 criterion_main!(benches);
@@ -0,0 +1,350 @@
+//! Access masks for installed APIs.
+//!
+//! Hosts can install modules under an [`AccessMask`] and give each unit the
+//! set of mask bits it may use. A type or function registered under a mask is
+//! only visible to units whose mask shares at least one bit with it, so e.g.
+//! gameplay scripts can use `Engine::` functions while mod scripts cannot see
+//! filesystem APIs.
+//!
+//! Entities installed without a mask are visible to every unit. References to
+//! hidden entities are reported by [`check_access`] as
+//! [`CompilationError::Inaccessible`].
+
+use angelscript_core::{CompilationError, Span, TypeHash};
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{
+    CallExpr, ClassDecl, ClassMember, Expr, NamespaceDecl, Scope, Script, TypeBase, TypeExpr,
+};
+use angelscript_registry::SymbolRegistry;
+use rustc_hash::FxHashMap;
+use std::ops::{BitAnd, BitOr};
+
+/// A set of access groups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AccessMask(u32);
+
+impl AccessMask {
+    /// Member of every group; the default for units and installed modules.
+    pub const ALL: Self = Self(u32::MAX);
+    /// Member of no group.
+    pub const NONE: Self = Self(0);
+
+    /// Create a mask from raw bits.
+    pub const fn new(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// The raw bits.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Check if the masks share at least one group.
+    pub const fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl Default for AccessMask {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl BitOr for AccessMask {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitAnd for AccessMask {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        Self(self.0 & rhs.0)
+    }
+}
+
+/// The masks of registered types and functions.
+#[derive(Debug, Clone, Default)]
+pub struct AccessMasks {
+    masks: FxHashMap<TypeHash, AccessMask>,
+}
+
+impl AccessMasks {
+    /// Create an empty set; every entity is visible.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the mask of a type or function.
+    pub fn set(&mut self, hash: TypeHash, mask: AccessMask) {
+        if mask == AccessMask::ALL {
+            self.masks.remove(&hash);
+        } else {
+            self.masks.insert(hash, mask);
+        }
+    }
+
+    /// Get the mask of a type or function.
+    pub fn get(&self, hash: TypeHash) -> AccessMask {
+        self.masks.get(&hash).copied().unwrap_or(AccessMask::ALL)
+    }
+
+    /// Check if a unit with mask `unit` may use the entity.
+    pub fn is_visible(&self, hash: TypeHash, unit: AccessMask) -> bool {
+        self.get(hash).intersects(unit)
+    }
+
+    /// Check if no entity is restricted.
+    pub fn is_empty(&self) -> bool {
+        self.masks.is_empty()
+    }
+}
+
+/// Qualified names `name` may refer to from code declared in `namespace`,
+/// innermost namespace first.
+pub(crate) fn candidate_names(
+    namespace: &[String],
+    scope: Option<&Scope<'_>>,
+    name: &str,
+) -> Vec<String> {
+    let written = match scope {
+        Some(scope) if !scope.segments.is_empty() => {
+            let mut path: Vec<&str> = scope.segments.iter().map(|s| s.name).collect();
+            path.push(name);
+            path.join("::")
+        }
+        _ => name.to_string(),
+    };
+    if scope.is_some_and(|s| s.is_absolute) {
+        return vec![written];
+    }
+
+    (0..=namespace.len())
+        .rev()
+        .map(|depth| {
+            let mut path = namespace[..depth].to_vec();
+            path.push(written.clone());
+            path.join("::")
+        })
+        .collect()
+}
+
+/// Report references in `script` to registered types and functions that are
+/// hidden from a unit with mask `unit`.
+pub fn check_access(
+    script: &Script<'_>,
+    registry: &SymbolRegistry,
+    masks: &AccessMasks,
+    unit: AccessMask,
+) -> Vec<CompilationError> {
+    let mut checker = AccessChecker {
+        registry,
+        masks,
+        unit,
+        namespace: Vec::new(),
+        methods: Vec::new(),
+        errors: Vec::new(),
+    };
+    checker.visit_script(script);
+    checker.errors
+}
+
+struct AccessChecker<'a> {
+    registry: &'a SymbolRegistry,
+    masks: &'a AccessMasks,
+    unit: AccessMask,
+    /// Namespace the visited code is declared in.
+    namespace: Vec<String>,
+    /// Method names of the enclosing classes, which shadow global functions.
+    methods: Vec<Vec<String>>,
+    errors: Vec<CompilationError>,
+}
+
+impl AccessChecker<'_> {
+    fn report(&mut self, kind: &str, name: String, span: Span) {
+        self.errors.push(CompilationError::Inaccessible {
+            kind: kind.to_string(),
+            name,
+            span,
+        });
+    }
+}
+
+impl<'ast> Visitor<'ast> for AccessChecker<'_> {
+    fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+        let depth = self.namespace.len();
+        self.namespace
+            .extend(namespace.path.iter().map(|s| s.name.to_string()));
+        visitor::walk_namespace_decl(self, namespace);
+        self.namespace.truncate(depth);
+    }
+
+    fn visit_class_decl(&mut self, class: &ClassDecl<'ast>) {
+        let methods = class
+            .members
+            .iter()
+            .filter_map(|member| match member {
+                ClassMember::Method(method) => Some(method.name.name.to_string()),
+                _ => None,
+            })
+            .collect();
+        self.methods.push(methods);
+        visitor::walk_class_decl(self, class);
+        self.methods.pop();
+    }
+
+    fn visit_type_expr(&mut self, ty: &TypeExpr<'ast>) {
+        if let TypeBase::Named(ident) = ty.base {
+            let found = candidate_names(&self.namespace, ty.scope.as_ref(), ident.name)
+                .iter()
+                .find_map(|name| self.registry.get_by_name(name));
+            if let Some(entry) = found
+                && !self.masks.is_visible(entry.type_hash(), self.unit)
+            {
+                self.report("type", entry.qualified_name().to_string(), ty.span);
+            }
+        }
+        visitor::walk_type_expr(self, ty);
+    }
+
+    fn visit_call_expr(&mut self, expr: &CallExpr<'ast>) {
+        if let Expr::Ident(callee) = expr.callee {
+            let is_method = callee.scope.is_none()
+                && self
+                    .methods
+                    .iter()
+                    .any(|methods| methods.iter().any(|m| m == callee.ident.name));
+            let found = if is_method {
+                None
+            } else {
+                candidate_names(&self.namespace, callee.scope.as_ref(), callee.ident.name)
+                    .into_iter()
+                    .find_map(|name| {
+                        let overloads = self.registry.get_function_overloads(&name)?;
+                        Some((name, overloads))
+                    })
+            };
+            if let Some((name, overloads)) = found
+                && !overloads
+                    .iter()
+                    .any(|&hash| self.masks.is_visible(hash, self.unit))
+            {
+                self.report("function", name, callee.span);
+            }
+        }
+        visitor::walk_call_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{
+        ClassEntry, DataType, FunctionDef, FunctionEntry, FunctionTraits, TypeKind, TypeSource,
+        Visibility,
+    };
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    const GAMEPLAY: AccessMask = AccessMask::new(0b01);
+    const FILESYSTEM: AccessMask = AccessMask::new(0b10);
+
+    fn registry() -> (SymbolRegistry, AccessMasks) {
+        let mut registry = SymbolRegistry::with_primitives();
+        let mut masks = AccessMasks::new();
+
+        let file = TypeHash::from_name("io::File");
+        registry
+            .register_type(
+                ClassEntry::new(
+                    "File",
+                    vec!["io".into()],
+                    "io::File",
+                    file,
+                    TypeKind::reference(),
+                    TypeSource::ffi_untyped(),
+                )
+                .into(),
+            )
+            .unwrap();
+        masks.set(file, FILESYSTEM);
+
+        for (namespace, name, mask) in [("io", "readFile", FILESYSTEM), ("", "log", GAMEPLAY)] {
+            let hash = TypeHash::from_function(name, &[]);
+            let namespace: Vec<String> = if namespace.is_empty() {
+                Vec::new()
+            } else {
+                vec![namespace.into()]
+            };
+            let def = FunctionDef::new(
+                hash,
+                name.to_string(),
+                namespace,
+                Vec::new(),
+                DataType::void(),
+                None,
+                FunctionTraits::default(),
+                true,
+                Visibility::Public,
+            );
+            registry.register_function(FunctionEntry::ffi(def)).unwrap();
+            masks.set(hash, mask);
+        }
+
+        (registry, masks)
+    }
+
+    fn check(source: &str, unit: AccessMask) -> Vec<String> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let (registry, masks) = registry();
+        check_access(&script, &registry, &masks, unit)
+            .iter()
+            .map(|e| match e {
+                CompilationError::Inaccessible { kind, name, .. } => format!("{} {}", kind, name),
+                other => panic!("unexpected error: {}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn masked_entities_are_reported() {
+        let source = "void main() { io::File@ f = null; io::readFile(); log(); }";
+
+        assert!(check(source, AccessMask::ALL).is_empty());
+        assert!(check(source, GAMEPLAY | FILESYSTEM).is_empty());
+        assert_eq!(
+            check(source, GAMEPLAY),
+            vec!["type io::File", "function io::readFile"]
+        );
+        assert_eq!(check(source, FILESYSTEM), vec!["function log"]);
+    }
+
+    #[test]
+    fn names_resolve_through_enclosing_namespaces() {
+        let source = "namespace io { void load() { File@ f = null; readFile(); } }";
+        assert_eq!(
+            check(source, GAMEPLAY),
+            vec!["type io::File", "function io::readFile"]
+        );
+    }
+
+    #[test]
+    fn methods_shadow_global_functions() {
+        let source = "class Logger { void log() { } void run() { log(); } }";
+        assert!(check(source, FILESYSTEM).is_empty());
+    }
+
+    #[test]
+    fn mask_operations() {
+        assert!(AccessMask::ALL.intersects(GAMEPLAY));
+        assert!(!GAMEPLAY.intersects(FILESYSTEM));
+        assert_eq!((GAMEPLAY | FILESYSTEM).bits(), 0b11);
+        assert_eq!((GAMEPLAY & FILESYSTEM), AccessMask::NONE);
+        assert_eq!(AccessMask::default(), AccessMask::ALL);
+    }
+}
@@ -0,0 +1,272 @@
+//! Type deduction for `auto` variables.
+//!
+//! An `auto` variable takes the type of its initializer, or of the value it
+//! reads in a `foreach` loop:
+//!
+//! | Declaration          | Initializer      | Deduced type        |
+//! |----------------------|------------------|---------------------|
+//! | `auto x = 1.5;`      | `const double`   | `double`            |
+//! | `const auto x = 1;`  | `int`            | `const int`         |
+//! | `auto p = player;`   | `Player@`        | `Player@`           |
+//! | `auto p = Player();` | `Player`         | `Player@`           |
+//! | `auto@ p = player;`  | `Player@`        | `Player@`           |
+//! | `const auto@ p = …;` | `Player@`        | `const Player@`     |
+//! | `auto@ const p = …;` | `Player@`        | `Player@ const`     |
+//!
+//! Values are copied, so the initializer's constness only carries over to
+//! the object a handle refers to. Objects of reference types are always
+//! held by handle, and `auto@` is only accepted for types that support
+//! handles. In a declaration of several variables, like
+//! `auto a = 1, b = "x";`, each variable is deduced on its own.
+
+use angelscript_core::{CompilationError, DataType, Span, TypeHash, primitives};
+use angelscript_parser::ast::{ForeachVar, TypeBase, TypeExpr, TypeSuffix};
+use angelscript_registry::SymbolRegistry;
+
+/// Check if a declared type is `auto`, with or without `const` and `@`.
+pub fn is_auto(declared: &TypeExpr<'_>) -> bool {
+    matches!(declared.base, TypeBase::Auto)
+}
+
+/// Deduce the type of the `auto` variable `name` from the type of its
+/// initializer.
+///
+/// # Errors
+///
+/// Returns an error if there is no initializer, the initializer is void or
+/// `null`, or `auto@` is used for a type that does not support handles.
+pub fn deduce_auto(
+    registry: &SymbolRegistry,
+    declared: &TypeExpr<'_>,
+    init: Option<&DataType>,
+    name: &str,
+    span: Span,
+) -> Result<DataType, CompilationError> {
+    let cannot_deduce = |reason: &str| CompilationError::CannotDeduceAuto {
+        name: name.to_string(),
+        reason: reason.to_string(),
+        span,
+    };
+    let Some(init) = init else {
+        return Err(cannot_deduce("an auto variable needs an initializer"));
+    };
+    if init.is_void() {
+        return Err(cannot_deduce("the initializer has no value"));
+    }
+    if init.type_hash == primitives::NULL {
+        return Err(cannot_deduce(
+            "the initializer is null, declare the handle type instead",
+        ));
+    }
+
+    let handle_suffix = declared
+        .suffixes
+        .iter()
+        .map(|TypeSuffix::Handle { is_const }| *is_const)
+        .next();
+    let (supports_handles, is_reference) = handle_support(registry, init);
+    if handle_suffix.is_some() && !supports_handles {
+        return Err(CompilationError::InvalidHandleType {
+            type_name: type_name(registry, init.type_hash),
+            reason: "auto@ needs a type that supports handles".to_string(),
+            span,
+        });
+    }
+
+    let mut deduced = *init;
+    deduced.ref_modifier = Default::default();
+    if handle_suffix.is_some() || init.is_handle || (is_reference && supports_handles) {
+        deduced.is_handle = true;
+        deduced.is_handle_to_const =
+            declared.is_const || init.is_handle_to_const || (!init.is_handle && init.is_const);
+        deduced.is_const = handle_suffix.unwrap_or(false);
+    } else {
+        deduced.is_handle = false;
+        deduced.is_handle_to_const = false;
+        deduced.is_const = declared.is_const;
+    }
+    Ok(deduced)
+}
+
+/// Deduce the type of an `auto` variable of a `foreach` loop from the
+/// return type of its `opForValue` operator.
+///
+/// # Errors
+///
+/// Returns an error if the operator returns nothing or `auto@` is used for
+/// a type that does not support handles.
+pub fn deduce_foreach_var(
+    registry: &SymbolRegistry,
+    var: &ForeachVar<'_>,
+    value: &DataType,
+) -> Result<DataType, CompilationError> {
+    deduce_auto(registry, &var.ty, Some(value), var.name.name, var.span)
+}
+
+/// Whether handles to a type can be taken, and whether it is a reference
+/// type.
+///
+/// Types the registry does not know are declared by the script: enums, or
+/// classes, interfaces and funcdefs, which are reference types.
+fn handle_support(registry: &SymbolRegistry, ty: &DataType) -> (bool, bool) {
+    let Some(entry) = registry.get(ty.type_hash) else {
+        return (!ty.is_enum, !ty.is_enum);
+    };
+    if let Some(class) = entry.as_class() {
+        let is_reference = !class.is_value_type();
+        (class.type_kind.supports_handles(), is_reference)
+    } else {
+        let is_reference = entry.is_interface() || entry.is_funcdef();
+        (is_reference, is_reference)
+    }
+}
+
+fn type_name(registry: &SymbolRegistry, hash: TypeHash) -> String {
+    registry.get(hash).map_or_else(
+        || hash.to_string(),
+        |entry| entry.qualified_name().to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{ClassEntry, TypeKind};
+    use angelscript_parser::Parser;
+    use angelscript_parser::ast::{Item, Stmt};
+    use bumpalo::Bump;
+
+    fn registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+        let player = ClassEntry::ffi("Player", TypeKind::reference());
+        let vec3 = ClassEntry::ffi("Vec3", TypeKind::value::<[f32; 3]>());
+        registry.register_type(player.into()).unwrap();
+        registry.register_type(vec3.into()).unwrap();
+        registry
+    }
+
+    /// Deduce the declarations in the body of `void f() { ... }`, given the
+    /// initializer types in order.
+    fn deduce(body: &str, mut inits: Vec<Option<DataType>>) -> Vec<Result<String, String>> {
+        let registry = registry();
+        let arena = Bump::new();
+        let source = format!("void f() {{ {body} }}");
+        let script = Parser::parse(&source, &arena).unwrap();
+        let Item::Function(func) = &script.items()[0] else {
+            panic!("expected a function");
+        };
+        let mut results = Vec::new();
+        for stmt in func.body.as_ref().unwrap().stmts {
+            let Stmt::VarDecl(decl) = stmt else {
+                continue;
+            };
+            let rest = inits.split_off(decl.vars.len());
+            for (var, init) in decl.vars.iter().zip(&inits) {
+                let deduced =
+                    deduce_auto(&registry, &decl.ty, init.as_ref(), var.name.name, var.span);
+                results.push(
+                    deduced
+                        .map(|t| describe(&registry, &t))
+                        .map_err(|e| e.to_string()),
+                );
+            }
+            inits = rest;
+        }
+        results
+    }
+
+    fn describe(registry: &SymbolRegistry, data_type: &DataType) -> String {
+        let mut name = String::new();
+        if data_type.is_handle_to_const || (!data_type.is_handle && data_type.is_const) {
+            name.push_str("const ");
+        }
+        name.push_str(&type_name(registry, data_type.type_hash));
+        if data_type.is_handle {
+            name.push('@');
+            if data_type.is_const {
+                name.push_str(" const");
+            }
+        }
+        name
+    }
+
+    fn player(is_handle: bool) -> Option<DataType> {
+        let hash = TypeHash::from_name("Player");
+        Some(if is_handle {
+            DataType::with_handle(hash, false)
+        } else {
+            DataType::simple(hash)
+        })
+    }
+
+    #[test]
+    fn deduces_constness_and_handles() {
+        let mut const_double = DataType::simple(primitives::DOUBLE);
+        const_double.is_const = true;
+        let results = deduce(
+            "auto a = x, b = y; const auto c = 1; auto p = q; auto@ h = q; \
+             const auto@ k = q; auto@ const m = q; auto v = Vec3();",
+            vec![
+                Some(const_double),
+                Some(DataType::simple(primitives::INT32)),
+                Some(DataType::simple(primitives::INT32)),
+                player(false),
+                player(true),
+                player(true),
+                player(true),
+                Some(DataType::simple(TypeHash::from_name("Vec3"))),
+            ],
+        );
+        assert_eq!(
+            results,
+            [
+                "double",
+                "int",
+                "const int",
+                "Player@",
+                "Player@",
+                "const Player@",
+                "Player@ const",
+                "Vec3",
+            ]
+            .map(|name| Ok(name.to_string()))
+        );
+    }
+
+    #[test]
+    fn reports_undeducible_initializers() {
+        let results = deduce(
+            "auto a = f(); auto b = null, c; auto@ d = v;",
+            vec![
+                Some(DataType::void()),
+                Some(DataType::simple(primitives::NULL)),
+                None,
+                Some(DataType::simple(TypeHash::from_name("Vec3"))),
+            ],
+        );
+        let errors: Vec<String> = results.into_iter().map(Result::unwrap_err).collect();
+        assert_eq!(errors.len(), 4);
+        assert!(errors[0].ends_with("cannot deduce the type of 'a': the initializer has no value"));
+        assert!(errors[1].contains("'b': the initializer is null"));
+        assert!(errors[2].contains("'c': an auto variable needs an initializer"));
+        assert!(errors[3].contains("cannot create handle to 'Vec3'"));
+    }
+
+    #[test]
+    fn deduces_foreach_variables() {
+        let registry = registry();
+        let arena = Bump::new();
+        let script =
+            Parser::parse("void f() { foreach (const auto@ p : players) {} }", &arena).unwrap();
+        let Item::Function(func) = &script.items()[0] else {
+            panic!("expected a function");
+        };
+        let Stmt::Foreach(foreach) = &func.body.as_ref().unwrap().stmts[0] else {
+            panic!("expected a foreach loop");
+        };
+        let deduced =
+            deduce_foreach_var(&registry, &foreach.vars[0], &player(true).unwrap()).unwrap();
+        assert_eq!(describe(&registry, &deduced), "const Player@");
+        assert!(is_auto(&foreach.vars[0].ty));
+    }
+}
@@ -0,0 +1,516 @@
+//! Branch simplification and loop layout.
+//!
+//! Conditions compile to a value followed by a conditional jump, and `&&`,
+//! `||` and `!` compile to jumps around `PUSH_TRUE`/`PUSH_FALSE` that
+//! materialize the intermediate result. [`optimize_branches`] removes that
+//! detour so conditions branch straight to where they end up:
+//!
+//! | Rewrite              | Before                                   | After                        |
+//! |----------------------|------------------------------------------|------------------------------|
+//! | Negated conditions   | `NOT; JUMP_IF_FALSE a`                   | `JUMP_IF_TRUE a`             |
+//! | Constant conditions  | `JUMP a` where `a: PUSH_FALSE; JUMP_IF_FALSE b` | `JUMP b`              |
+//! | Shared conditions    | `JUMP a` where `a: JUMP_IF_FALSE b`      | `JUMP_IF_FALSE b; JUMP a+1`  |
+//! | Unreachable code     | code no path reaches                     | (removed)                    |
+//!
+//! Afterwards, loops that test their condition at the top are rotated so
+//! the test is repeated at the bottom. Each iteration then takes a single
+//! `LOOP_IF_TRUE` back to the body and the exit falls through:
+//!
+//! ```text
+//! start: cond; JUMP_IF_FALSE end; body; LOOP start; end:
+//! // becomes
+//! cond; JUMP_IF_FALSE end; body: ...; cond; LOOP_IF_TRUE body; end:
+//! ```
+//!
+//! Only conditions of up to [`MAX_ROTATED_CONDITION`] straight-line
+//! instructions are copied.
+
+use super::frame::successors;
+use super::{BytecodeChunk, BytecodeRewriter, InstrId, Instruction};
+use super::{JumpTarget, OpCategory, OpCode, RewriteError};
+
+/// Longest loop condition, in instructions, that is copied to the bottom of
+/// the loop.
+pub const MAX_ROTATED_CONDITION: usize = 8;
+
+/// Simplify the branches of a chunk and lay out its loops for fall-through.
+///
+/// # Errors
+///
+/// Returns an error if the chunk cannot be decoded or re-encoded.
+pub fn optimize_branches(chunk: &BytecodeChunk) -> Result<BytecodeChunk, RewriteError> {
+    let mut rewriter = BytecodeRewriter::new(chunk)?;
+    loop {
+        let mut changed = fold_negations(&mut rewriter)?;
+        changed |= thread_constant_conditions(&mut rewriter)?;
+        changed |= share_conditions(&mut rewriter)?;
+        changed |= remove_unreachable(&mut rewriter);
+        if !changed {
+            break;
+        }
+    }
+    rotate_loops(&mut rewriter)?;
+    rewriter.finish()
+}
+
+/// `NOT; JUMP_IF_FALSE a` becomes `JUMP_IF_TRUE a`, and vice versa.
+fn fold_negations(rewriter: &mut BytecodeRewriter) -> Result<bool, RewriteError> {
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    let mut changed = false;
+    for pair in ids.windows(2) {
+        let (not, jump) = (pair[0], pair[1]);
+        if rewriter.get(not).is_none() || op(rewriter, not) != OpCode::Not {
+            continue;
+        }
+        let Some(inverted) = inverse(op(rewriter, jump)) else {
+            continue;
+        };
+        // Either instruction being a jump target means some path would see
+        // the other one alone
+        if rewriter.is_jump_target(not) || rewriter.is_jump_target(jump) {
+            continue;
+        }
+        retarget_with(rewriter, jump, inverted)?;
+        rewriter.remove(not);
+        changed = true;
+    }
+    Ok(changed)
+}
+
+/// A jump to `PUSH_TRUE`/`PUSH_FALSE` followed by a conditional jump goes
+/// straight to wherever that constant leads.
+fn thread_constant_conditions(rewriter: &mut BytecodeRewriter) -> Result<bool, RewriteError> {
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    let mut changed = false;
+    for (at, &id) in ids.iter().enumerate() {
+        if !matches!(
+            op(rewriter, id),
+            OpCode::Jump | OpCode::JumpIfFalse | OpCode::JumpIfTrue
+        ) {
+            continue;
+        }
+        let Some(JumpTarget::Instr(target)) = rewriter.jump_target(id) else {
+            continue;
+        };
+        let Some(position) = ids[at..].iter().position(|&i| i == target) else {
+            continue;
+        };
+        let Some(&test) = ids.get(at + position + 1) else {
+            continue;
+        };
+
+        let taken =
+            match (op(rewriter, target), op(rewriter, test)) {
+                (OpCode::PushTrue, OpCode::JumpIfTrue)
+                | (OpCode::PushFalse, OpCode::JumpIfFalse) => true,
+                (OpCode::PushTrue, OpCode::JumpIfFalse)
+                | (OpCode::PushFalse, OpCode::JumpIfTrue) => false,
+                _ => continue,
+            };
+        let destination = if taken {
+            rewriter.jump_target(test).unwrap_or(JumpTarget::End)
+        } else {
+            next(&ids, at + position + 1)
+        };
+        let line = line(rewriter, id);
+        rewriter.replace(id, Instruction::jump(op(rewriter, id), destination, line)?);
+        changed = true;
+    }
+    Ok(changed)
+}
+
+/// A `JUMP` to a conditional jump takes the conditional jump itself, then
+/// continues after it.
+fn share_conditions(rewriter: &mut BytecodeRewriter) -> Result<bool, RewriteError> {
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    let entries = table_entries(rewriter, &ids);
+    let mut changed = false;
+    for (at, &id) in ids.iter().enumerate() {
+        if entries[at] || op(rewriter, id) != OpCode::Jump {
+            continue;
+        }
+        let Some(JumpTarget::Instr(target)) = rewriter.jump_target(id) else {
+            continue;
+        };
+        let test_op = op(rewriter, target);
+        if !matches!(test_op, OpCode::JumpIfFalse | OpCode::JumpIfTrue) {
+            continue;
+        }
+        let Some(position) = ids.iter().position(|&i| i == target) else {
+            continue;
+        };
+
+        let line = line(rewriter, id);
+        let destination = rewriter.jump_target(target).unwrap_or(JumpTarget::End);
+        rewriter.replace(id, Instruction::jump(test_op, destination, line)?);
+        rewriter.insert_after(
+            id,
+            [Instruction::jump(OpCode::Jump, next(&ids, position), line)?],
+        );
+        changed = true;
+    }
+    Ok(changed)
+}
+
+/// Remove instructions no path from the start of the chunk reaches.
+/// Remove the instructions no path from the entry reaches.
+pub(super) fn remove_unreachable(rewriter: &mut BytecodeRewriter) -> bool {
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    if ids.is_empty() {
+        return false;
+    }
+    let successors = successors(rewriter, &ids);
+    let mut reachable = vec![false; ids.len()];
+    let mut pending = vec![0];
+    while let Some(at) = pending.pop() {
+        if !std::mem::replace(&mut reachable[at], true) {
+            pending.extend(successors[at].iter().copied());
+        }
+    }
+
+    let mut changed = false;
+    for (&id, reachable) in ids.iter().zip(reachable) {
+        if !reachable {
+            rewriter.remove(id);
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Move loop conditions to the bottom of their loops.
+fn rotate_loops(rewriter: &mut BytecodeRewriter) -> Result<(), RewriteError> {
+    let loops: Vec<InstrId> = rewriter
+        .ids()
+        .filter(|&id| op(rewriter, id) == OpCode::Loop)
+        .collect();
+    for back_edge in loops {
+        let ids: Vec<InstrId> = rewriter.ids().collect();
+        let Some(at) = ids.iter().position(|&id| id == back_edge) else {
+            continue;
+        };
+        let Some(JumpTarget::Instr(start)) = rewriter.jump_target(back_edge) else {
+            continue;
+        };
+        let exit = next(&ids, at);
+        let line = line(rewriter, back_edge);
+
+        // do { body } while (cond): `JUMP_IF_FALSE exit; LOOP start` is a
+        // single `LOOP_IF_TRUE start`
+        if let Some(&test) = at.checked_sub(1).and_then(|before| ids.get(before))
+            && op(rewriter, test) == OpCode::JumpIfFalse
+            && rewriter.jump_target(test) == Some(exit)
+        {
+            if !rewriter.is_jump_target(back_edge) {
+                rewriter.replace(
+                    test,
+                    Instruction::jump(OpCode::LoopIfTrue, JumpTarget::Instr(start), line)?,
+                );
+                rewriter.remove(back_edge);
+            }
+            continue;
+        }
+
+        let Some(from) = ids.iter().position(|&id| id == start) else {
+            continue;
+        };
+        let Some(condition) = loop_condition(rewriter, &ids[from..], exit) else {
+            continue;
+        };
+        let body = next(&ids, from + condition.len());
+
+        let mut bottom: Vec<Instruction> = condition
+            .iter()
+            .filter_map(|&id| rewriter.get(id).cloned())
+            .collect();
+        bottom.push(Instruction::jump(OpCode::LoopIfTrue, body, line)?);
+        let bottom = rewriter.insert_after(back_edge, bottom);
+
+        // `continue` re-tests at the bottom; an empty body loops on the copy
+        rewriter.retarget(start, JumpTarget::Instr(bottom[0]));
+        rewriter.remove(back_edge);
+    }
+    Ok(())
+}
+
+/// The straight-line instructions at the start of `ids` up to a
+/// `JUMP_IF_FALSE exit`.
+fn loop_condition(
+    rewriter: &BytecodeRewriter,
+    ids: &[InstrId],
+    exit: JumpTarget,
+) -> Option<Vec<InstrId>> {
+    for (length, &id) in ids.iter().enumerate().take(MAX_ROTATED_CONDITION + 1) {
+        if length > 0 && rewriter.is_jump_target(id) {
+            return None;
+        }
+        let instruction = rewriter.get(id)?;
+        if instruction.op() == OpCode::JumpIfFalse {
+            return (length > 0 && rewriter.jump_target(id) == Some(exit))
+                .then(|| ids[..length].to_vec());
+        }
+        if instruction.op().category() == OpCategory::ControlFlow {
+            return None;
+        }
+    }
+    None
+}
+
+/// Which positions are entries of a jump table, which are addressed by
+/// position and must stay single jumps.
+fn table_entries(rewriter: &BytecodeRewriter, ids: &[InstrId]) -> Vec<bool> {
+    let mut entries = vec![false; ids.len()];
+    for (at, &id) in ids.iter().enumerate() {
+        if let Some(table) = rewriter.get(id)
+            && let (OpCode::JumpTable, &[_, _, hi, lo]) = (table.op(), table.operands())
+        {
+            let count = usize::from(u16::from_be_bytes([hi, lo])) + 1;
+            for entry in entries.iter_mut().skip(at + 1).take(count) {
+                *entry = true;
+            }
+        }
+    }
+    entries
+}
+
+fn retarget_with(
+    rewriter: &mut BytecodeRewriter,
+    id: InstrId,
+    jump_op: OpCode,
+) -> Result<(), RewriteError> {
+    let target = rewriter.jump_target(id).unwrap_or(JumpTarget::End);
+    let line = line(rewriter, id);
+    rewriter.replace(id, Instruction::jump(jump_op, target, line)?);
+    Ok(())
+}
+
+fn inverse(jump_op: OpCode) -> Option<OpCode> {
+    match jump_op {
+        OpCode::JumpIfFalse => Some(OpCode::JumpIfTrue),
+        OpCode::JumpIfTrue => Some(OpCode::JumpIfFalse),
+        _ => None,
+    }
+}
+
+/// The instruction after position `at`.
+fn next(ids: &[InstrId], at: usize) -> JumpTarget {
+    ids.get(at + 1)
+        .map_or(JumpTarget::End, |&id| JumpTarget::Instr(id))
+}
+
+fn op(rewriter: &BytecodeRewriter, id: InstrId) -> OpCode {
+    rewriter
+        .get(id)
+        .map_or(OpCode::Pop, |instruction| instruction.op())
+}
+
+fn line(rewriter: &BytecodeRewriter, id: InstrId) -> u32 {
+    rewriter.get(id).map_or(0, Instruction::line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::ConstantPool;
+
+    fn get_local(chunk: &mut BytecodeChunk, slot: u8) {
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(slot, 1);
+    }
+
+    #[test]
+    fn negated_condition_inverts_jump() {
+        // if (!a) x();
+        let mut chunk = BytecodeChunk::new();
+        get_local(&mut chunk, 0);
+        chunk.write_op(OpCode::Not, 1);
+        let skip = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op(OpCode::PushOne, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.patch_jump(skip);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let result = optimize_branches(&chunk).unwrap();
+        result.assert_opcodes(&[
+            OpCode::GetLocal,
+            OpCode::JumpIfTrue,
+            OpCode::PushOne,
+            OpCode::Pop,
+            OpCode::ReturnVoid,
+        ]);
+    }
+
+    #[test]
+    fn and_chains_without_materializing_bools() {
+        // if (a && b) x(); as emitted: the `&&` produces a bool that the
+        // `if` then tests
+        let mut chunk = BytecodeChunk::new();
+        get_local(&mut chunk, 0);
+        let short = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        get_local(&mut chunk, 1);
+        let done = chunk.emit_jump(OpCode::Jump, 1);
+        chunk.patch_jump(short);
+        chunk.write_op(OpCode::PushFalse, 1);
+        chunk.patch_jump(done);
+        let skip = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op(OpCode::PushOne, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.patch_jump(skip);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let result = optimize_branches(&chunk).unwrap();
+        result.assert_contains_opcodes(&[
+            OpCode::GetLocal,
+            OpCode::JumpIfFalse,
+            OpCode::GetLocal,
+            OpCode::JumpIfFalse,
+        ]);
+        assert!(!result.opcodes().contains(&OpCode::PushFalse));
+
+        let pool = ConstantPool::new();
+        let disasm = result.disassemble(&pool);
+        let targets: Vec<&str> = disasm
+            .lines()
+            .filter(|line| line.contains("JUMP_IF_FALSE"))
+            .filter_map(|line| line.split("-> ").nth(1))
+            .collect();
+        assert_eq!(targets.len(), 2, "{disasm}");
+        assert_eq!(targets[0], targets[1], "{disasm}");
+    }
+
+    #[test]
+    fn or_of_constant_true_skips_test() {
+        // `x || ...` reaching `PUSH_TRUE; JUMP_IF_FALSE` never takes the jump
+        let mut chunk = BytecodeChunk::new();
+        get_local(&mut chunk, 0);
+        let short = chunk.emit_jump(OpCode::JumpIfTrue, 1);
+        get_local(&mut chunk, 1);
+        let done = chunk.emit_jump(OpCode::Jump, 1);
+        chunk.patch_jump(short);
+        chunk.write_op(OpCode::PushTrue, 1);
+        chunk.patch_jump(done);
+        let skip = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op(OpCode::PushOne, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.patch_jump(skip);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let result = optimize_branches(&chunk).unwrap();
+        assert!(!result.opcodes().contains(&OpCode::PushTrue));
+        result.assert_contains_opcodes(&[
+            OpCode::GetLocal,
+            OpCode::JumpIfTrue,
+            OpCode::GetLocal,
+            OpCode::JumpIfFalse,
+            OpCode::PushOne,
+        ]);
+    }
+
+    #[test]
+    fn while_loop_tests_at_bottom() {
+        // while (i < n) i++;
+        let mut chunk = BytecodeChunk::new();
+        let start = chunk.len();
+        get_local(&mut chunk, 0);
+        get_local(&mut chunk, 1);
+        chunk.write_op(OpCode::Lt, 1);
+        let exit = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        get_local(&mut chunk, 0);
+        chunk.write_op(OpCode::PreInc, 1);
+        chunk.write_op(OpCode::SetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.emit_loop(start, 1);
+        chunk.patch_jump(exit);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let result = optimize_branches(&chunk).unwrap();
+        result.assert_opcodes(&[
+            OpCode::GetLocal,
+            OpCode::GetLocal,
+            OpCode::Lt,
+            OpCode::JumpIfFalse,
+            OpCode::GetLocal,
+            OpCode::PreInc,
+            OpCode::SetLocal,
+            OpCode::Pop,
+            OpCode::GetLocal,
+            OpCode::GetLocal,
+            OpCode::Lt,
+            OpCode::LoopIfTrue,
+            OpCode::ReturnVoid,
+        ]);
+
+        // The bottom test loops back to the body, the entry test exits
+        let pool = ConstantPool::new();
+        let disasm = result.disassemble(&pool);
+        let body = disasm
+            .lines()
+            .find(|line| line.contains("PRE_INC"))
+            .map(|line| &line[..4])
+            .unwrap();
+        let back = disasm
+            .lines()
+            .find(|line| line.contains("LOOP_IF_TRUE"))
+            .unwrap();
+        // The body starts with the GET_LOCAL before PRE_INC
+        let body_start = format!("{:04}", body.parse::<usize>().unwrap() - 2);
+        assert!(back.ends_with(&format!("-> {body_start}")), "{disasm}");
+    }
+
+    #[test]
+    fn do_while_uses_single_back_edge() {
+        // do { i++; } while (i < n);
+        let mut chunk = BytecodeChunk::new();
+        let start = chunk.len();
+        get_local(&mut chunk, 0);
+        chunk.write_op(OpCode::PreInc, 1);
+        chunk.write_op(OpCode::SetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        get_local(&mut chunk, 0);
+        get_local(&mut chunk, 1);
+        chunk.write_op(OpCode::Lt, 1);
+        let exit = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.emit_loop(start, 1);
+        chunk.patch_jump(exit);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let result = optimize_branches(&chunk).unwrap();
+        result.assert_contains_opcodes(&[OpCode::Lt, OpCode::LoopIfTrue, OpCode::ReturnVoid]);
+        assert!(!result.opcodes().contains(&OpCode::Loop));
+        assert!(!result.opcodes().contains(&OpCode::JumpIfFalse));
+    }
+
+    #[test]
+    fn long_conditions_are_not_copied() {
+        let mut chunk = BytecodeChunk::new();
+        let start = chunk.len();
+        get_local(&mut chunk, 0);
+        for _ in 0..MAX_ROTATED_CONDITION {
+            chunk.write_op(OpCode::PushOne, 1);
+            chunk.write_op(OpCode::Add, 1);
+        }
+        let exit = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op(OpCode::PushOne, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.emit_loop(start, 1);
+        chunk.patch_jump(exit);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let result = optimize_branches(&chunk).unwrap();
+        assert!(result.opcodes().contains(&OpCode::Loop));
+        assert!(!result.opcodes().contains(&OpCode::LoopIfTrue));
+    }
+
+    #[test]
+    fn unreachable_code_is_removed() {
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::ReturnVoid, 1);
+        chunk.write_op(OpCode::PushOne, 2);
+        chunk.write_op(OpCode::Pop, 2);
+
+        let result = optimize_branches(&chunk).unwrap();
+        result.assert_opcodes(&[OpCode::ReturnVoid]);
+    }
+}
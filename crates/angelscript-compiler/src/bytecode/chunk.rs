@@ -1,9 +1,21 @@
 //! Bytecode chunk for compiled functions.
 //!
 //! A `BytecodeChunk` contains the compiled bytecode for a single function,
-//! along with line number information for debugging.
+//! along with a [`LineTable`] mapping it back to the source for runtime
+//! errors, debuggers and profilers.
+//!
+//! Every write takes the source line. Emitters that know the exact span of
+//! the code they emit call [`BytecodeChunk::set_span`] first, and writes on
+//! that span's line record it in full:
+//!
+//! ```ignore
+//! chunk.set_span(expr.span);
+//! chunk.write_op(OpCode::Add, expr.span.line);
+//! ```
 
-use super::OpCode;
+use angelscript_core::Span;
+
+use super::{LineTable, OpCode};
 
 /// A chunk of compiled bytecode for a single function.
 ///
@@ -13,9 +25,10 @@ use super::OpCode;
 pub struct BytecodeChunk {
     /// The bytecode instructions.
     code: Vec<u8>,
-    /// Line numbers for debugging (parallel to code).
-    /// Each entry corresponds to a byte in `code`.
-    lines: Vec<u32>,
+    /// Source span of each run of bytes in `code`.
+    line_table: LineTable,
+    /// Span given to writes on its line.
+    span: Option<Span>,
 }
 
 impl BytecodeChunk {
@@ -28,60 +41,55 @@ impl BytecodeChunk {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             code: Vec::with_capacity(capacity),
-            lines: Vec::with_capacity(capacity),
+            ..Self::default()
         }
     }
 
+    /// Set the span of the code written next.
+    ///
+    /// Writes on `span.line` record the whole span; writes on other lines
+    /// record just their line, with column 0.
+    pub fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
+
+    /// Record the source of the bytes written next.
+    fn mark(&mut self, line: u32) {
+        let span = match self.span {
+            Some(span) if span.line == line => span,
+            _ => Span::point(line, 0),
+        };
+        self.line_table.push(self.code.len() as u32, span);
+    }
+
     /// Write an opcode.
     pub fn write_op(&mut self, op: OpCode, line: u32) {
+        self.mark(line);
         self.code.push(op as u8);
-        self.lines.push(line);
     }
 
     /// Write a byte operand.
     pub fn write_byte(&mut self, byte: u8, line: u32) {
+        self.mark(line);
         self.code.push(byte);
-        self.lines.push(line);
     }
 
     /// Write a 16-bit operand (big-endian).
     pub fn write_u16(&mut self, value: u16, line: u32) {
-        self.code.push((value >> 8) as u8);
-        self.lines.push(line);
-        self.code.push(value as u8);
-        self.lines.push(line);
+        self.mark(line);
+        self.code.extend_from_slice(&value.to_be_bytes());
     }
 
     /// Write a 32-bit operand (big-endian).
     pub fn write_u32(&mut self, value: u32, line: u32) {
-        self.code.push((value >> 24) as u8);
-        self.lines.push(line);
-        self.code.push((value >> 16) as u8);
-        self.lines.push(line);
-        self.code.push((value >> 8) as u8);
-        self.lines.push(line);
-        self.code.push(value as u8);
-        self.lines.push(line);
+        self.mark(line);
+        self.code.extend_from_slice(&value.to_be_bytes());
     }
 
     /// Write a 64-bit operand (big-endian).
     pub fn write_u64(&mut self, value: u64, line: u32) {
-        self.code.push((value >> 56) as u8);
-        self.lines.push(line);
-        self.code.push((value >> 48) as u8);
-        self.lines.push(line);
-        self.code.push((value >> 40) as u8);
-        self.lines.push(line);
-        self.code.push((value >> 32) as u8);
-        self.lines.push(line);
-        self.code.push((value >> 24) as u8);
-        self.lines.push(line);
-        self.code.push((value >> 16) as u8);
-        self.lines.push(line);
-        self.code.push((value >> 8) as u8);
-        self.lines.push(line);
-        self.code.push(value as u8);
-        self.lines.push(line);
+        self.mark(line);
+        self.code.extend_from_slice(&value.to_be_bytes());
     }
 
     /// Get current code offset (for jump patching).
@@ -129,19 +137,61 @@ impl BytecodeChunk {
         self.write_u16(offset as u16, line);
     }
 
+    /// Emit a read of a local slot, in the wide form past slot 255.
+    pub fn emit_get_local(&mut self, slot: u16, line: u32) {
+        match u8::try_from(slot) {
+            Ok(slot) => {
+                self.write_op(OpCode::GetLocal, line);
+                self.write_byte(slot, line);
+            }
+            Err(_) => {
+                self.write_op(OpCode::GetLocalWide, line);
+                self.write_u16(slot, line);
+            }
+        }
+    }
+
+    /// Emit a store of the top of the stack to a local slot, in the wide
+    /// form past slot 255. The value stays on the stack.
+    pub fn emit_set_local(&mut self, slot: u16, line: u32) {
+        match u8::try_from(slot) {
+            Ok(slot) => {
+                self.write_op(OpCode::SetLocal, line);
+                self.write_byte(slot, line);
+            }
+            Err(_) => {
+                self.write_op(OpCode::SetLocalWide, line);
+                self.write_u16(slot, line);
+            }
+        }
+    }
+
     /// Get the bytecode.
     pub fn code(&self) -> &[u8] {
         &self.code
     }
 
-    /// Get the line numbers.
-    pub fn lines(&self) -> &[u32] {
-        &self.lines
+    /// Get the table mapping offsets to source spans.
+    pub fn line_table(&self) -> &LineTable {
+        &self.line_table
+    }
+
+    /// Move every recorded span by `delta` lines.
+    pub fn shift_lines(&mut self, delta: i64) {
+        self.line_table.shift_lines(delta);
+    }
+
+    /// Get the source span of the byte at a given offset.
+    pub fn span_at(&self, offset: usize) -> Option<Span> {
+        if offset >= self.code.len() {
+            return None;
+        }
+        self.line_table.span_at(offset as u32)
     }
 
     /// Get the line number for a given offset.
     pub fn line_at(&self, offset: usize) -> Option<u32> {
-        self.lines.get(offset).copied()
+        self.span_at(offset).map(|span| span.line)
     }
 
     /// Get the length of the bytecode.
@@ -288,6 +338,26 @@ mod tests {
         assert_eq!(chunk.line_at(1), Some(5));
     }
 
+    #[test]
+    fn spans_are_recorded_per_run() {
+        let mut chunk = BytecodeChunk::new();
+        let add = Span::new(3, 9, 5);
+        chunk.write_op(OpCode::PushOne, 2);
+        chunk.set_span(add);
+        chunk.write_op(OpCode::Constant, 3);
+        chunk.write_byte(0, 3);
+        chunk.write_op(OpCode::Add, 3);
+        // Another line keeps only the line
+        chunk.write_op(OpCode::ReturnVoid, 4);
+
+        assert_eq!(chunk.span_at(0), Some(Span::point(2, 0)));
+        assert_eq!(chunk.span_at(1), Some(add));
+        assert_eq!(chunk.span_at(3), Some(add));
+        assert_eq!(chunk.span_at(4), Some(Span::point(4, 0)));
+        assert_eq!(chunk.span_at(5), None);
+        assert_eq!(chunk.line_table().len(), 3);
+    }
+
     #[test]
     fn emit_and_patch_jump() {
         let mut chunk = BytecodeChunk::new();
@@ -0,0 +1,483 @@
+//! Common subexpression elimination.
+//!
+//! Within a basic block, an expression computed twice from the same locals,
+//! with nothing in between that could change its value, only needs to be
+//! computed once. [`eliminate_common_subexpressions`] keeps the first
+//! computation in a temporary local and loads it for the repeats:
+//!
+//! ```text
+//! GET_LOCAL 0; GET_FIELD 1; GET_FIELD 0; ...; GET_LOCAL 0; GET_FIELD 1; GET_FIELD 0
+//! // becomes
+//! GET_LOCAL 0; GET_FIELD 1; GET_FIELD 0; SET_LOCAL t; ...; GET_LOCAL t
+//! ```
+//!
+//! Expressions are built from constants, local, global and field loads,
+//! arithmetic, comparisons, conversions and calls of functions the caller
+//! declares pure. A store to a local ends the expressions reading it; any
+//! other instruction that may write memory ends those reading fields,
+//! globals or calling functions. Repeats are only found within a block, so
+//! every path reaching a repeat has computed the temporary first.
+
+use angelscript_core::TypeHash;
+
+use super::{BytecodeChunk, BytecodeRewriter, Constant, ConstantPool, InstrId, Instruction};
+use super::{OpCategory, OpCode, RewriteError};
+
+/// Compute repeated expressions once, keeping them in new locals after the
+/// last slot the function uses (and at least `params`).
+///
+/// Calls are only part of an expression if `is_pure` accepts the called
+/// function's hash.
+///
+/// # Errors
+///
+/// Returns an error if the chunk cannot be decoded or re-encoded.
+pub fn eliminate_common_subexpressions(
+    chunk: &BytecodeChunk,
+    pool: &ConstantPool,
+    params: u16,
+    is_pure: impl Fn(TypeHash) -> bool,
+) -> Result<BytecodeChunk, RewriteError> {
+    let mut rewriter = BytecodeRewriter::new(chunk)?;
+    let mut temp = rewriter
+        .ids()
+        .filter_map(|id| local_access(&rewriter, id))
+        .map(|(slot, _)| slot + 1)
+        .max()
+        .unwrap_or(0)
+        .max(params);
+
+    let mut changed = false;
+    loop {
+        let ids: Vec<InstrId> = rewriter.ids().collect();
+        let Some(common) = find_common(&rewriter, &ids, pool, &is_pure) else {
+            break;
+        };
+        let line = |rewriter: &BytecodeRewriter, id| rewriter.get(id).map_or(0, Instruction::line);
+
+        let last = ids[common.first.end];
+        let store = local_instruction(OpCode::SetLocal, temp, line(&rewriter, last));
+        rewriter.insert_after(last, [store]);
+        for repeat in &common.repeats {
+            let load =
+                local_instruction(OpCode::GetLocal, temp, line(&rewriter, ids[repeat.start]));
+            rewriter.replace(ids[repeat.start], load);
+            for &id in &ids[repeat.start + 1..=repeat.end] {
+                rewriter.remove(id);
+            }
+        }
+        temp += 1;
+        changed = true;
+    }
+
+    if changed {
+        rewriter.finish()
+    } else {
+        Ok(chunk.clone())
+    }
+}
+
+/// A value computed by the instructions at positions `start..=end`.
+#[derive(Debug, Clone)]
+struct Expr {
+    start: usize,
+    end: usize,
+    /// Encoded instructions, equal for equal expressions.
+    key: Vec<u8>,
+    /// Locals read.
+    locals: Vec<u16>,
+    /// Whether fields or globals are read or functions called.
+    memory: bool,
+}
+
+/// An expression and its later repeats in the same block.
+#[derive(Debug)]
+struct Common {
+    first: Expr,
+    repeats: Vec<Expr>,
+}
+
+/// What an instruction does, as far as expressions are concerned.
+enum Effect {
+    /// Pops `pops` values and pushes one computed from them.
+    Value {
+        pops: usize,
+        local: Option<u16>,
+        memory: bool,
+    },
+    /// Stores the top of the stack in a local, leaving it on the stack.
+    Store(u16),
+    Pop,
+    /// Anything else.
+    Other {
+        writes_memory: bool,
+        ends_block: bool,
+    },
+}
+
+/// The repeated expression covering the most instructions, if any.
+fn find_common(
+    rewriter: &BytecodeRewriter,
+    ids: &[InstrId],
+    pool: &ConstantPool,
+    is_pure: &impl Fn(TypeHash) -> bool,
+) -> Option<Common> {
+    let mut stack: Vec<Option<Expr>> = Vec::new();
+    let mut available: Vec<Common> = Vec::new();
+    let mut best: Option<Common> = None;
+    let mut keep = |common: Common| {
+        let size = |common: &Common| common.first.end - common.first.start;
+        if !common.repeats.is_empty() && best.as_ref().is_none_or(|best| size(&common) > size(best))
+        {
+            best = Some(common);
+        }
+    };
+
+    for (position, &id) in ids.iter().enumerate() {
+        if rewriter.is_jump_target(id) {
+            stack.clear();
+            available.drain(..).for_each(&mut keep);
+        }
+        let Some(instruction) = rewriter.get(id) else {
+            continue;
+        };
+
+        match effect(rewriter, id, pool, is_pure) {
+            Effect::Value {
+                pops,
+                local,
+                memory,
+            } => {
+                let operands = stack
+                    .len()
+                    .checked_sub(pops)
+                    .map(|base| stack.split_off(base));
+                if operands.is_none() {
+                    stack.clear();
+                }
+                let expr = operands
+                    .and_then(|operands| combine(operands, position, instruction, local, memory));
+                if let Some(expr) = &expr
+                    && expr.end > expr.start
+                {
+                    match available.iter_mut().find(|c| c.first.key == expr.key) {
+                        Some(common) => common.repeats.push(expr.clone()),
+                        None => available.push(Common {
+                            first: expr.clone(),
+                            repeats: Vec::new(),
+                        }),
+                    }
+                }
+                stack.push(expr);
+            }
+            Effect::Store(slot) => {
+                if let Some(top) = stack.last_mut() {
+                    *top = None;
+                }
+                let (ended, kept) = available
+                    .drain(..)
+                    .partition(|common| common.first.locals.contains(&slot));
+                available = kept;
+                ended.into_iter().for_each(&mut keep);
+            }
+            Effect::Pop => {
+                stack.pop();
+            }
+            Effect::Other {
+                writes_memory,
+                ends_block,
+            } => {
+                stack.clear();
+                let (ended, kept) = available
+                    .drain(..)
+                    .partition(|common| ends_block || (writes_memory && common.first.memory));
+                available = kept;
+                ended.into_iter().for_each(&mut keep);
+            }
+        }
+    }
+    available.into_iter().for_each(&mut keep);
+    best
+}
+
+/// The expression computed by `instruction` from `operands`, if they are
+/// expressions directly preceding it.
+fn combine(
+    operands: Vec<Option<Expr>>,
+    position: usize,
+    instruction: &Instruction,
+    local: Option<u16>,
+    memory: bool,
+) -> Option<Expr> {
+    let mut expr = Expr {
+        start: position,
+        end: position,
+        key: Vec::new(),
+        locals: local.into_iter().collect(),
+        memory,
+    };
+    let mut next = None;
+    for operand in operands {
+        let operand = operand?;
+        if next.is_some_and(|next| operand.start != next) {
+            return None;
+        }
+        if next.is_none() {
+            expr.start = operand.start;
+        }
+        next = Some(operand.end + 1);
+        expr.key.extend(operand.key);
+        expr.locals.extend(operand.locals);
+        expr.memory |= operand.memory;
+    }
+    if next.is_some_and(|next| next != position) {
+        return None;
+    }
+    expr.key.push(instruction.op() as u8);
+    expr.key.extend_from_slice(instruction.operands());
+    Some(expr)
+}
+
+fn effect(
+    rewriter: &BytecodeRewriter,
+    id: InstrId,
+    pool: &ConstantPool,
+    is_pure: &impl Fn(TypeHash) -> bool,
+) -> Effect {
+    let value = |pops, memory| Effect::Value {
+        pops,
+        local: None,
+        memory,
+    };
+    let Some(instruction) = rewriter.get(id) else {
+        return Effect::Pop;
+    };
+    if let Some((slot, store)) = local_access(rewriter, id) {
+        return match store {
+            true => Effect::Store(slot),
+            false => Effect::Value {
+                pops: 0,
+                local: Some(slot),
+                memory: false,
+            },
+        };
+    }
+
+    let op = instruction.op();
+    match op {
+        OpCode::Pop => Effect::Pop,
+        OpCode::GetThis => value(0, false),
+        OpCode::GetGlobal => value(0, true),
+        OpCode::GetField => value(1, true),
+        OpCode::Add
+        | OpCode::Sub
+        | OpCode::Mul
+        | OpCode::Div
+        | OpCode::Mod
+        | OpCode::Pow
+        | OpCode::BitAnd
+        | OpCode::BitOr
+        | OpCode::BitXor
+        | OpCode::Shl
+        | OpCode::Shr
+        | OpCode::Ushr
+        | OpCode::Eq
+        | OpCode::Lt
+        | OpCode::Le
+        | OpCode::Gt
+        | OpCode::Ge => value(2, false),
+        OpCode::Neg
+        | OpCode::BitNot
+        | OpCode::Not
+        | OpCode::PreInc
+        | OpCode::PreDec
+        | OpCode::InstanceOf
+        | OpCode::RefCast => value(1, false),
+        OpCode::Call | OpCode::CallMethod => match instruction.operands() {
+            &[hi, lo, args] if called_is_pure(pool, [hi, lo], is_pure) => {
+                let receiver = usize::from(op == OpCode::CallMethod);
+                value(usize::from(args) + receiver, true)
+            }
+            _ => Effect::Other {
+                writes_memory: true,
+                ends_block: false,
+            },
+        },
+        _ if op.category() == OpCategory::Constant => value(0, false),
+        _ if op.category() == OpCategory::Conversion && op != OpCode::ValueToHandle => {
+            value(1, false)
+        }
+        _ => Effect::Other {
+            writes_memory: !matches!(op.category(), OpCategory::Stack | OpCategory::Comparison),
+            ends_block: matches!(
+                op,
+                OpCode::Return | OpCode::ReturnVoid | OpCode::TryBegin | OpCode::TryEnd
+            ) || op.category() == OpCategory::ControlFlow,
+        },
+    }
+}
+
+fn called_is_pure(
+    pool: &ConstantPool,
+    index: [u8; 2],
+    is_pure: &impl Fn(TypeHash) -> bool,
+) -> bool {
+    match pool.get(u32::from(u16::from_be_bytes(index))) {
+        Some(&Constant::TypeHash(function)) => is_pure(function),
+        _ => false,
+    }
+}
+
+/// Slot and whether the instruction stores (rather than loads) a local.
+fn local_access(rewriter: &BytecodeRewriter, id: InstrId) -> Option<(u16, bool)> {
+    let instruction = rewriter.get(id)?;
+    match (instruction.op(), instruction.operands()) {
+        (OpCode::GetLocal, &[slot]) => Some((u16::from(slot), false)),
+        (OpCode::SetLocal, &[slot]) => Some((u16::from(slot), true)),
+        (OpCode::GetLocalWide, &[hi, lo]) => Some((u16::from_be_bytes([hi, lo]), false)),
+        (OpCode::SetLocalWide, &[hi, lo]) => Some((u16::from_be_bytes([hi, lo]), true)),
+        _ => None,
+    }
+}
+
+/// A narrow `op` or its wide form for `slot`.
+fn local_instruction(op: OpCode, slot: u16, line: u32) -> Instruction {
+    let wide = match op {
+        OpCode::SetLocal => OpCode::SetLocalWide,
+        _ => OpCode::GetLocalWide,
+    };
+    let result = match u8::try_from(slot) {
+        Ok(byte) => Instruction::with_operands(op, &[byte], line),
+        Err(_) => Instruction::with_operands(wide, &slot.to_be_bytes(), line),
+    };
+    result.expect("local accesses have fixed operand sizes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_field(chunk: &mut BytecodeChunk, local: u8, fields: &[u16]) {
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(local, 1);
+        for &field in fields {
+            chunk.write_op(OpCode::GetField, 1);
+            chunk.write_u16(field, 1);
+        }
+    }
+
+    fn eliminate(chunk: &BytecodeChunk, pool: &ConstantPool) -> String {
+        let pure = TypeHash::from_name("length");
+        let result = eliminate_common_subexpressions(chunk, pool, 1, |f| f == pure).unwrap();
+        result.disassemble(pool)
+    }
+
+    /// Number of `op` instructions whose operands end with `operand`.
+    fn count(disasm: &str, op: &str, operand: &str) -> usize {
+        disasm
+            .lines()
+            .filter(|line| line.contains(op) && line.ends_with(operand))
+            .count()
+    }
+
+    #[test]
+    fn reuses_repeated_field_chains() {
+        // a.pos.x * a.pos.x + a.pos.x
+        let mut chunk = BytecodeChunk::new();
+        get_field(&mut chunk, 0, &[1, 0]);
+        get_field(&mut chunk, 0, &[1, 0]);
+        chunk.write_op(OpCode::Mul, 1);
+        get_field(&mut chunk, 0, &[1, 0]);
+        chunk.write_op(OpCode::Add, 1);
+        chunk.write_op(OpCode::Return, 1);
+        let pool = ConstantPool::new();
+
+        let result = eliminate_common_subexpressions(&chunk, &pool, 1, |_| false).unwrap();
+        #[rustfmt::skip]
+        result.assert_opcodes(&[
+            OpCode::GetLocal, OpCode::GetField, OpCode::GetField, OpCode::SetLocal,
+            OpCode::GetLocal, OpCode::Mul, OpCode::GetLocal, OpCode::Add, OpCode::Return,
+        ]);
+        // The temporary goes after the parameter
+        let disasm = result.disassemble(&pool);
+        assert_eq!(count(&disasm, "SET_LOCAL", " 1"), 1, "{disasm}");
+        assert_eq!(count(&disasm, "GET_LOCAL", " 1"), 2, "{disasm}");
+    }
+
+    #[test]
+    fn stores_end_reuse() {
+        // x = a.v + 1; a.v = 0; y = a.v + 1; z = x + 1; x = 2; w = x + 1
+        let mut chunk = BytecodeChunk::new();
+        let plus_one = |chunk: &mut BytecodeChunk, store: u8| {
+            chunk.write_op(OpCode::PushOne, 1);
+            chunk.write_op(OpCode::Add, 1);
+            chunk.write_op(OpCode::SetLocal, 1);
+            chunk.write_byte(store, 1);
+            chunk.write_op(OpCode::Pop, 1);
+        };
+        get_field(&mut chunk, 0, &[0]);
+        plus_one(&mut chunk, 1);
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::PushZero, 1);
+        chunk.write_op(OpCode::SetField, 1);
+        chunk.write_u16(0, 1);
+        get_field(&mut chunk, 0, &[0]);
+        plus_one(&mut chunk, 2);
+        for store in [3, 4] {
+            chunk.write_op(OpCode::GetLocal, 1);
+            chunk.write_byte(1, 1);
+            plus_one(&mut chunk, store);
+            if store == 3 {
+                chunk.write_op(OpCode::PushZero, 1);
+                chunk.write_op(OpCode::SetLocal, 1);
+                chunk.write_byte(1, 1);
+                chunk.write_op(OpCode::Pop, 1);
+            }
+        }
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let pool = ConstantPool::new();
+        let result = eliminate_common_subexpressions(&chunk, &pool, 1, |_| false).unwrap();
+        assert_eq!(result.code(), chunk.code());
+    }
+
+    #[test]
+    fn reuses_pure_calls_only() {
+        let mut pool = ConstantPool::new();
+        let mut chunk = BytecodeChunk::new();
+        for function in ["length", "length", "random", "random"] {
+            let index = pool.add_type_hash(TypeHash::from_name(function));
+            chunk.write_op(OpCode::GetLocal, 1);
+            chunk.write_byte(0, 1);
+            chunk.write_op(OpCode::CallMethod, 1);
+            chunk.write_u16(index as u16, 1);
+            chunk.write_byte(0, 1);
+        }
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let disasm = eliminate(&chunk, &pool);
+        assert_eq!(disasm.matches("CALL_METHOD").count(), 3, "{disasm}");
+        assert_eq!(count(&disasm, "SET_LOCAL", " 1"), 1, "{disasm}");
+    }
+
+    #[test]
+    fn does_not_reuse_across_blocks() {
+        // a.x; if (c) { a.x }
+        let mut chunk = BytecodeChunk::new();
+        get_field(&mut chunk, 0, &[0]);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(1, 1);
+        let skip = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        get_field(&mut chunk, 0, &[0]);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.patch_jump(skip);
+        get_field(&mut chunk, 0, &[0]);
+        chunk.write_op(OpCode::Return, 1);
+
+        let pool = ConstantPool::new();
+        let result = eliminate_common_subexpressions(&chunk, &pool, 2, |_| false).unwrap();
+        assert_eq!(result.code(), chunk.code());
+    }
+}
@@ -0,0 +1,278 @@
+//! Human-readable bytecode listings.
+//!
+//! The disassembler walks a [`BytecodeChunk`], decoding each instruction's
+//! operands and annotating constant pool references and jump targets.
+//! Source lines can optionally be interleaved above the instructions they
+//! produced.
+//!
+//! ```text
+//!    1 | int x = 42;
+//! 0000    1 CONSTANT            0  ; 42
+//! 0002    | SET_LOCAL           0
+//! 0004    2 RETURN_VOID
+//! ```
+
+use std::fmt::{self, Write};
+
+use super::{BytecodeChunk, Constant, ConstantPool, OpCode};
+
+impl BytecodeChunk {
+    /// Produce a listing of this chunk with constant pool annotations.
+    pub fn disassemble(&self, constants: &ConstantPool) -> String {
+        self.disassemble_inner(constants, None)
+    }
+
+    /// Produce a listing of this chunk with source lines interleaved.
+    ///
+    /// Each source line is printed once, before the first instruction
+    /// generated from it. `source` must be the text the chunk was compiled from.
+    pub fn disassemble_with_source(&self, constants: &ConstantPool, source: &str) -> String {
+        self.disassemble_inner(constants, Some(source))
+    }
+
+    fn disassemble_inner(&self, constants: &ConstantPool, source: Option<&str>) -> String {
+        let source_lines: Vec<&str> = source.map(|s| s.lines().collect()).unwrap_or_default();
+        let mut out = String::new();
+        let mut offset = 0;
+        let mut prev_line = None;
+
+        while offset < self.len() {
+            let line = self.line_at(offset).unwrap_or(0);
+
+            if prev_line != Some(line)
+                && let Some(text) = (line as usize)
+                    .checked_sub(1)
+                    .and_then(|i| source_lines.get(i))
+            {
+                let _ = writeln!(out, "{:>4} | {}", line, text.trim_end());
+            }
+
+            offset = self.disassemble_instruction(&mut out, offset, prev_line, constants);
+            prev_line = Some(line);
+        }
+
+        out
+    }
+
+    /// Write a single instruction at `offset` and return the offset of the next one.
+    fn disassemble_instruction(
+        &self,
+        out: &mut String,
+        offset: usize,
+        prev_line: Option<u32>,
+        constants: &ConstantPool,
+    ) -> usize {
+        let line = self.line_at(offset).unwrap_or(0);
+        let _ = write!(out, "{:04} ", offset);
+        if prev_line == Some(line) {
+            let _ = write!(out, "   | ");
+        } else {
+            let _ = write!(out, "{:>4} ", line);
+        }
+
+        let Some(op) = self.read_op(offset) else {
+            let _ = writeln!(out, "<invalid {:#04x}>", self.code()[offset]);
+            return offset + 1;
+        };
+
+        let next = offset + 1 + op.operand_size();
+        if next > self.len() {
+            let _ = writeln!(out, "{} <truncated>", op.name());
+            return self.len();
+        }
+
+        let operands = self.format_operands(op, offset + 1, next, constants);
+        if operands.is_empty() {
+            let _ = writeln!(out, "{}", op.name());
+        } else {
+            let _ = writeln!(out, "{:<19} {}", op.name(), operands);
+        }
+
+        next
+    }
+
+    /// Format the operands of `op`, which start at `at`.
+    fn format_operands(
+        &self,
+        op: OpCode,
+        at: usize,
+        next: usize,
+        constants: &ConstantPool,
+    ) -> String {
+        let byte = |o: usize| self.read_byte(o).unwrap_or(0);
+        let word = |o: usize| self.read_u16(o).unwrap_or(0);
+        let constant = |index: u32| match constants.get(index) {
+            Some(c) => format!("{:<4} ; {}", index, c),
+            None => format!("{:<4} ; <missing>", index),
+        };
+
+        match op {
+            OpCode::Constant => constant(byte(at) as u32),
+            OpCode::ConstantWide
+            | OpCode::GetGlobal
+            | OpCode::SetGlobal
+            | OpCode::DerivedToBase
+            | OpCode::ClassToInterface
+            | OpCode::InstanceOf
+            | OpCode::Cast
+            | OpCode::RefCast
+            | OpCode::FuncPtr
+            | OpCode::NewDelegate => constant(word(at) as u32),
+            OpCode::PopN
+            | OpCode::Pick
+            | OpCode::GetLocal
+            | OpCode::SetLocal
+            | OpCode::CallFuncPtr => byte(at).to_string(),
+            OpCode::GetLocalWide
+            | OpCode::SetLocalWide
+            | OpCode::GetField
+            | OpCode::SetField
+            | OpCode::NewClosure
+            | OpCode::InitListBegin => word(at).to_string(),
+            OpCode::Jump | OpCode::JumpIfFalse | OpCode::JumpIfTrue | OpCode::TryBegin => {
+                format!("-> {:04}", next + word(at) as usize)
+            }
+            OpCode::Loop | OpCode::LoopIfTrue => {
+                format!("-> {:04}", next.saturating_sub(word(at) as usize))
+            }
+            OpCode::JumpTable => format!("entries={} {}", word(at + 2), constant(word(at) as u32)),
+            OpCode::Call
+            | OpCode::CallMethod
+            | OpCode::New
+            | OpCode::NewFactory
+            | OpCode::Format => {
+                format!("args={} {}", byte(at + 2), constant(word(at) as u32))
+            }
+            OpCode::CallVirtual => format!("slot={} args={}", word(at), byte(at + 2)),
+            OpCode::Extension => format!("op={} {}", byte(at + 2), word(at)),
+            OpCode::CallInterface => format!(
+                "slot={} args={} {}",
+                word(at + 2),
+                byte(at + 4),
+                constant(word(at) as u32)
+            ),
+            _ => String::new(),
+        }
+    }
+}
+
+impl fmt::Display for Constant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Constant::Int(v) => write!(f, "{}", v),
+            Constant::Uint(v) => write!(f, "{}u", v),
+            Constant::Float32(v) => write!(f, "{:?}f", v),
+            Constant::Float64(v) => write!(f, "{:?}", v),
+            Constant::StringData(bytes) => write!(f, "{:?}", String::from_utf8_lossy(bytes)),
+            Constant::TypeHash(h) => write!(f, "hash {}", h),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::TypeHash;
+
+    fn sample() -> (BytecodeChunk, ConstantPool) {
+        let mut pool = ConstantPool::new();
+        let mut chunk = BytecodeChunk::new();
+
+        let idx = pool.add_int(42);
+        chunk.write_op(OpCode::Constant, 1);
+        chunk.write_byte(idx as u8, 1);
+        chunk.write_op(OpCode::SetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::ReturnVoid, 2);
+
+        (chunk, pool)
+    }
+
+    #[test]
+    fn lists_instructions() {
+        let (chunk, pool) = sample();
+        let listing = chunk.disassemble(&pool);
+        let lines: Vec<&str> = listing.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("0000    1 CONSTANT"));
+        assert!(lines[0].ends_with("; 42"));
+        assert!(lines[1].starts_with("0002    | SET_LOCAL"));
+        assert!(lines[2].starts_with("0004    2 RETURN_VOID"));
+    }
+
+    #[test]
+    fn interleaves_source() {
+        let (chunk, pool) = sample();
+        let listing = chunk.disassemble_with_source(&pool, "int x = 42;\nreturn;\n");
+        let lines: Vec<&str> = listing.lines().collect();
+
+        assert_eq!(lines[0], "   1 | int x = 42;");
+        assert!(lines[1].contains("CONSTANT"));
+        assert!(lines[2].contains("SET_LOCAL"));
+        assert_eq!(lines[3], "   2 | return;");
+        assert!(lines[4].contains("RETURN_VOID"));
+    }
+
+    #[test]
+    fn shows_jump_targets() {
+        let mut chunk = BytecodeChunk::new();
+        let pool = ConstantPool::new();
+
+        let loop_start = chunk.current_offset();
+        let jump = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.emit_loop(loop_start, 1);
+        chunk.patch_jump(jump);
+
+        let listing = chunk.disassemble(&pool);
+        assert!(listing.contains("JUMP_IF_FALSE       -> 0007"));
+        assert!(listing.contains("LOOP                -> 0000"));
+    }
+
+    #[test]
+    fn shows_call_operands() {
+        let mut pool = ConstantPool::new();
+        let mut chunk = BytecodeChunk::new();
+        let idx = pool.add_type_hash(TypeHash::from_name("print"));
+        chunk.write_op(OpCode::Call, 1);
+        chunk.write_u16(idx as u16, 1);
+        chunk.write_byte(2, 1);
+
+        let listing = chunk.disassemble(&pool);
+        assert!(listing.contains("CALL"));
+        assert!(listing.contains("args=2"));
+        assert!(listing.contains("; hash 0x"));
+    }
+
+    #[test]
+    fn reports_invalid_and_truncated() {
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_byte(0xFF, 1);
+        chunk.write_op(OpCode::GetLocalWide, 1);
+        chunk.write_byte(0, 1);
+
+        let listing = chunk.disassemble(&ConstantPool::new());
+        assert!(listing.contains("<invalid 0xff>"));
+        assert!(listing.contains("GET_LOCAL_WIDE <truncated>"));
+    }
+
+    #[test]
+    fn missing_constant() {
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::Constant, 1);
+        chunk.write_byte(7, 1);
+
+        let listing = chunk.disassemble(&ConstantPool::new());
+        assert!(listing.contains("; <missing>"));
+    }
+
+    #[test]
+    fn constant_display() {
+        assert_eq!(Constant::Int(-3).to_string(), "-3");
+        assert_eq!(Constant::Uint(3).to_string(), "3u");
+        assert_eq!(Constant::Float32(1.5).to_string(), "1.5f");
+        assert_eq!(Constant::Float64(2.0).to_string(), "2.0");
+        assert_eq!(Constant::StringData(b"hi".to_vec()).to_string(), "\"hi\"");
+    }
+}
@@ -0,0 +1,397 @@
+//! Stack frame layout.
+//!
+//! The emitter gives every local its own slot, so a function with many
+//! short-lived temporaries gets a frame far larger than it ever uses at
+//! once. [`compact_locals`] computes which slots are live at each
+//! instruction and renumbers the slots so that locals whose live ranges do
+//! not overlap share one.
+//!
+//! Parameters keep their slots, as the caller places arguments there. A
+//! slot is only reused once its value can no longer be read, so frame
+//! cleanup that releases objects per slot must run per live range rather
+//! than at function exit.
+
+use super::RewriteError;
+use super::{BytecodeChunk, BytecodeRewriter, InstrId, Instruction, JumpTarget, OpCode};
+
+/// Mapping from the emitted local slots to the compacted frame.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FrameLayout {
+    slots: Vec<Option<u16>>,
+    frame_size: u16,
+}
+
+impl FrameLayout {
+    /// The compacted slot of an emitted slot, or `None` if the function
+    /// never accesses it.
+    pub fn slot(&self, original: u16) -> Option<u16> {
+        self.slots.get(original as usize).copied().flatten()
+    }
+
+    /// Number of slots the compacted frame needs.
+    pub fn frame_size(&self) -> u16 {
+        self.frame_size
+    }
+}
+
+/// Share local slots between locals that are never live at the same time.
+///
+/// The first `params` slots hold the parameters and are left in place.
+///
+/// # Errors
+///
+/// Returns an error if the chunk cannot be decoded or re-encoded.
+pub fn compact_locals(
+    chunk: &BytecodeChunk,
+    params: u16,
+) -> Result<(BytecodeChunk, FrameLayout), RewriteError> {
+    let mut rewriter = BytecodeRewriter::new(chunk)?;
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    let accesses: Vec<Option<Access>> = ids.iter().map(|&id| access(&rewriter, id)).collect();
+
+    let slot_count = accesses
+        .iter()
+        .flatten()
+        .map(|access| access.slot as usize + 1)
+        .max()
+        .unwrap_or(0)
+        .max(params as usize);
+    let successors = successors(&rewriter, &ids);
+    let (entry, live_out) = liveness(&accesses, &successors, slot_count);
+    let interference = interference(&accesses, &entry, &live_out, slot_count, params);
+
+    // Greedy colouring in order of first use; parameters are pre-coloured
+    let mut slots: Vec<Option<u16>> = vec![None; slot_count];
+    for param in 0..params {
+        slots[param as usize] = Some(param);
+    }
+    for access in accesses.iter().flatten() {
+        let slot = access.slot as usize;
+        if slots[slot].is_some() {
+            continue;
+        }
+        let taken: Vec<u16> = interference[slot]
+            .iter(slot_count)
+            .filter_map(|other| slots[other])
+            .collect();
+        slots[slot] = (0..).find(|color| !taken.contains(color));
+    }
+
+    for (&id, access) in ids.iter().zip(&accesses) {
+        if let Some(access) = access
+            && let Some(slot) = slots[access.slot as usize]
+            && slot != access.slot
+        {
+            let line = rewriter.get(id).map_or(0, Instruction::line);
+            rewriter.replace(id, access.encode(slot, line));
+        }
+    }
+
+    let frame_size = slots
+        .iter()
+        .flatten()
+        .map(|&slot| slot + 1)
+        .max()
+        .unwrap_or(0)
+        .max(params);
+    Ok((rewriter.finish()?, FrameLayout { slots, frame_size }))
+}
+
+/// A load from or store to a local slot.
+#[derive(Debug, Clone, Copy)]
+struct Access {
+    slot: u16,
+    store: bool,
+}
+
+impl Access {
+    /// Encode the access for another slot, using the narrow form if it fits.
+    fn encode(self, slot: u16, line: u32) -> Instruction {
+        let (narrow, wide) = if self.store {
+            (OpCode::SetLocal, OpCode::SetLocalWide)
+        } else {
+            (OpCode::GetLocal, OpCode::GetLocalWide)
+        };
+        let result = match u8::try_from(slot) {
+            Ok(byte) => Instruction::with_operands(narrow, &[byte], line),
+            Err(_) => Instruction::with_operands(wide, &slot.to_be_bytes(), line),
+        };
+        result.expect("local accesses have fixed operand sizes")
+    }
+}
+
+fn access(rewriter: &BytecodeRewriter, id: InstrId) -> Option<Access> {
+    let instruction = rewriter.get(id)?;
+    let (slot, store) = match (instruction.op(), instruction.operands()) {
+        (OpCode::GetLocal, &[slot]) => (u16::from(slot), false),
+        (OpCode::SetLocal, &[slot]) => (u16::from(slot), true),
+        (OpCode::GetLocalWide, &[hi, lo]) => (u16::from_be_bytes([hi, lo]), false),
+        (OpCode::SetLocalWide, &[hi, lo]) => (u16::from_be_bytes([hi, lo]), true),
+        _ => return None,
+    };
+    Some(Access { slot, store })
+}
+
+/// Positions control can reach directly after each instruction.
+pub(super) fn successors(rewriter: &BytecodeRewriter, ids: &[InstrId]) -> Vec<Vec<usize>> {
+    let position = |target: JumpTarget| match target {
+        JumpTarget::Instr(id) => ids.iter().position(|&i| i == id),
+        JumpTarget::End => None,
+    };
+
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+    for (at, &id) in ids.iter().enumerate() {
+        let Some(instruction) = rewriter.get(id) else {
+            continue;
+        };
+        let falls_through = !matches!(
+            instruction.op(),
+            OpCode::Jump | OpCode::Loop | OpCode::Return | OpCode::ReturnVoid
+        );
+        if falls_through && at + 1 < ids.len() {
+            successors[at].push(at + 1);
+        }
+        if let Some(target) = rewriter.jump_target(id).and_then(position) {
+            successors[at].push(target);
+        }
+
+        match (instruction.op(), instruction.operands()) {
+            // Any table entry, the first of which is the next instruction
+            (OpCode::JumpTable, &[_, _, hi, lo]) => {
+                let entries = usize::from(u16::from_be_bytes([hi, lo])) + 1;
+                successors[at].extend((at + 2..=at + entries).filter(|&e| e < ids.len()));
+            }
+            // Anything in the try block may throw to the catch block
+            (OpCode::TryBegin, _) => {
+                if let Some(catch) = rewriter.jump_target(id).and_then(position) {
+                    for body in &mut successors[at + 1..catch.max(at + 1)] {
+                        body.push(catch);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    successors
+}
+
+/// Slots live on entry, and after each instruction.
+fn liveness(
+    accesses: &[Option<Access>],
+    successors: &[Vec<usize>],
+    slot_count: usize,
+) -> (SlotSet, Vec<SlotSet>) {
+    let mut live_in = vec![SlotSet::new(slot_count); accesses.len()];
+    let mut live_out = vec![SlotSet::new(slot_count); accesses.len()];
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for at in (0..accesses.len()).rev() {
+            let mut out = SlotSet::new(slot_count);
+            for &next in &successors[at] {
+                out.union(&live_in[next]);
+            }
+            let mut live = out.clone();
+            match accesses[at] {
+                Some(Access { slot, store: true }) => live.remove(slot as usize),
+                Some(Access { slot, store: false }) => live.insert(slot as usize),
+                None => {}
+            }
+            if live != live_in[at] {
+                live_in[at] = live;
+                changed = true;
+            }
+            live_out[at] = out;
+        }
+    }
+    let entry = live_in
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| SlotSet::new(slot_count));
+    (entry, live_out)
+}
+
+/// Pairs of slots that must not share a frame slot.
+fn interference(
+    accesses: &[Option<Access>],
+    entry: &SlotSet,
+    live_out: &[SlotSet],
+    slot_count: usize,
+    params: u16,
+) -> Vec<SlotSet> {
+    let mut edges = vec![SlotSet::new(slot_count); slot_count];
+    let mut add = |a: usize, b: usize| {
+        if a != b {
+            edges[a].insert(b);
+            edges[b].insert(a);
+        }
+    };
+
+    // Storing a slot clobbers whatever shares it, so it must not share with
+    // anything still live
+    for (at, access) in accesses.iter().enumerate() {
+        if let Some(Access { slot, store: true }) = access {
+            for live in live_out[at].iter(slot_count) {
+                add(*slot as usize, live);
+            }
+        }
+    }
+
+    // The caller stores the parameters on entry, which clobbers anything
+    // sharing their slots that is live on entry (read before any store)
+    let live_on_entry: Vec<usize> = entry.iter(slot_count).collect();
+    for param in 0..params as usize {
+        for &live in &live_on_entry {
+            add(param, live);
+        }
+    }
+    for (i, &a) in live_on_entry.iter().enumerate() {
+        for &b in &live_on_entry[i + 1..] {
+            add(a, b);
+        }
+    }
+    edges
+}
+
+/// Fixed-size set of slots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SlotSet(Vec<u64>);
+
+impl SlotSet {
+    fn new(slot_count: usize) -> Self {
+        Self(vec![0; slot_count.div_ceil(64)])
+    }
+
+    fn insert(&mut self, slot: usize) {
+        self.0[slot / 64] |= 1 << (slot % 64);
+    }
+
+    fn remove(&mut self, slot: usize) {
+        self.0[slot / 64] &= !(1 << (slot % 64));
+    }
+
+    fn union(&mut self, other: &SlotSet) {
+        for (word, other) in self.0.iter_mut().zip(&other.0) {
+            *word |= other;
+        }
+    }
+
+    fn iter(&self, slot_count: usize) -> impl Iterator<Item = usize> + '_ {
+        (0..slot_count).filter(|&slot| self.0[slot / 64] & (1 << (slot % 64)) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(chunk: &mut BytecodeChunk, slot: u8) {
+        chunk.write_op(OpCode::PushOne, 1);
+        chunk.write_op(OpCode::SetLocal, 1);
+        chunk.write_byte(slot, 1);
+        chunk.write_op(OpCode::Pop, 1);
+    }
+
+    fn load(chunk: &mut BytecodeChunk, slot: u8) {
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(slot, 1);
+        chunk.write_op(OpCode::Pop, 1);
+    }
+
+    /// Slots accessed by the chunk, in order.
+    fn slots(chunk: &BytecodeChunk) -> Vec<u8> {
+        let mut slots = Vec::new();
+        let mut offset = 0;
+        while let Some(op) = chunk.read_op(offset) {
+            if matches!(op, OpCode::GetLocal | OpCode::SetLocal) {
+                slots.push(chunk.read_byte(offset + 1).unwrap());
+            }
+            offset += 1 + op.operand_size();
+        }
+        slots
+    }
+
+    #[test]
+    fn sequential_locals_share_a_slot() {
+        let mut chunk = BytecodeChunk::new();
+        store(&mut chunk, 0);
+        load(&mut chunk, 0);
+        store(&mut chunk, 1);
+        load(&mut chunk, 1);
+        store(&mut chunk, 2);
+        load(&mut chunk, 2);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let (compacted, layout) = compact_locals(&chunk, 0).unwrap();
+        assert_eq!(layout.frame_size(), 1);
+        assert_eq!(layout.slot(2), Some(0));
+        assert_eq!(slots(&compacted), [0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn overlapping_locals_keep_separate_slots() {
+        let mut chunk = BytecodeChunk::new();
+        store(&mut chunk, 0);
+        store(&mut chunk, 1);
+        load(&mut chunk, 0);
+        store(&mut chunk, 2);
+        load(&mut chunk, 1);
+        load(&mut chunk, 2);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let (compacted, layout) = compact_locals(&chunk, 0).unwrap();
+        // Slot 2 is stored after slot 0 is last read
+        assert_eq!(layout.frame_size(), 2);
+        assert_eq!(slots(&compacted), [0, 1, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn locals_live_across_a_loop_are_not_reused_inside_it() {
+        let mut chunk = BytecodeChunk::new();
+        store(&mut chunk, 0);
+        let loop_start = chunk.current_offset();
+        store(&mut chunk, 1);
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(1, 1);
+        let exit = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.emit_loop(loop_start, 1);
+        chunk.patch_jump(exit);
+        load(&mut chunk, 0);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let (_, layout) = compact_locals(&chunk, 0).unwrap();
+        assert_eq!(layout.frame_size(), 2);
+        assert_ne!(layout.slot(0), layout.slot(1));
+    }
+
+    #[test]
+    fn parameters_keep_their_slots() {
+        let mut chunk = BytecodeChunk::new();
+        store(&mut chunk, 2);
+        load(&mut chunk, 2);
+        store(&mut chunk, 3);
+        load(&mut chunk, 3);
+        load(&mut chunk, 0);
+        load(&mut chunk, 1);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let (compacted, layout) = compact_locals(&chunk, 2).unwrap();
+        assert_eq!(layout.slot(0), Some(0));
+        assert_eq!(layout.slot(1), Some(1));
+        assert_eq!(layout.slot(3), Some(2));
+        assert_eq!(layout.frame_size(), 3);
+        assert_eq!(slots(&compacted), [2, 2, 2, 2, 0, 1]);
+    }
+
+    #[test]
+    fn locals_read_before_store_do_not_share_with_parameters() {
+        let mut chunk = BytecodeChunk::new();
+        load(&mut chunk, 1);
+        store(&mut chunk, 1);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let (_, layout) = compact_locals(&chunk, 1).unwrap();
+        assert_eq!(layout.slot(1), Some(1));
+    }
+}
@@ -0,0 +1,141 @@
+//! Mapping from bytecode offsets back to source spans.
+//!
+//! Consecutive instructions usually come from the same expression, so a
+//! [`LineTable`] stores one entry per run of bytes with the same span rather
+//! than one per byte. Looking up an offset is a binary search for the run
+//! containing it:
+//!
+//! ```ignore
+//! let span = function.bytecode.span_at(pc);
+//! eprintln!("error at {}:{}", span.line, span.col);
+//! ```
+//!
+//! Spans recorded from a line alone have column 0, as columns are 1-indexed.
+
+use angelscript_core::Span;
+
+/// Start of a run of bytecode with the same source span.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineEntry {
+    /// Offset of the run's first byte.
+    pub offset: u32,
+    /// Source of every byte in the run.
+    pub span: Span,
+}
+
+/// Run-length encoded source spans of a chunk.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LineTable {
+    entries: Vec<LineEntry>,
+}
+
+impl LineTable {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that the bytes from `offset` on come from `span`.
+    ///
+    /// Offsets must not decrease. A span equal to the current run's extends
+    /// it, and a second span at the same offset replaces the first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is before the start of the current run.
+    pub fn push(&mut self, offset: u32, span: Span) {
+        if let Some(last) = self.entries.last_mut() {
+            assert!(
+                offset >= last.offset,
+                "line table offset {offset} is before {}",
+                last.offset
+            );
+            if last.span == span {
+                return;
+            }
+            if last.offset == offset {
+                last.span = span;
+                // The replacement may now continue the run before it
+                let len = self.entries.len();
+                if len > 1 && self.entries[len - 2].span == span {
+                    self.entries.pop();
+                }
+                return;
+            }
+        }
+        self.entries.push(LineEntry { offset, span });
+    }
+
+    /// Span of the run containing `offset`, or `None` before the first run.
+    ///
+    /// The table does not know where the code ends; offsets past it map to
+    /// the last run.
+    pub fn span_at(&self, offset: u32) -> Option<Span> {
+        let runs = self.entries.partition_point(|entry| entry.offset <= offset);
+        runs.checked_sub(1).map(|run| self.entries[run].span)
+    }
+
+    /// Move every span by `delta` lines, for code whose source moved as a
+    /// whole. Spans without a line stay without one.
+    pub fn shift_lines(&mut self, delta: i64) {
+        for entry in &mut self.entries {
+            if entry.span.line != 0 {
+                entry.span.line = (i64::from(entry.span.line) + delta).max(1) as u32;
+            }
+        }
+    }
+
+    /// The runs, in offset order.
+    pub fn entries(&self) -> &[LineEntry] {
+        &self.entries
+    }
+
+    /// Number of runs.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the table has no runs.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_merge_and_look_up() {
+        let mut table = LineTable::new();
+        let (a, b) = (Span::new(1, 5, 3), Span::new(2, 1, 8));
+        table.push(0, a);
+        table.push(2, a);
+        table.push(4, b);
+        table.push(9, b);
+        assert_eq!(table.len(), 2);
+
+        assert_eq!(table.span_at(0), Some(a));
+        assert_eq!(table.span_at(3), Some(a));
+        assert_eq!(table.span_at(4), Some(b));
+        assert_eq!(table.span_at(100), Some(b));
+        assert_eq!(LineTable::new().span_at(0), None);
+    }
+
+    #[test]
+    fn span_at_the_same_offset_replaces() {
+        let mut table = LineTable::new();
+        let (a, b) = (Span::point(1, 1), Span::point(2, 1));
+        table.push(0, a);
+        table.push(3, b);
+        table.push(3, a);
+        assert_eq!(table.entries(), &[LineEntry { offset: 0, span: a }]);
+        table.push(3, b);
+        assert_eq!(table.span_at(3), Some(b));
+        assert_eq!(table.len(), 2);
+
+        table.shift_lines(4);
+        assert_eq!(table.span_at(0), Some(Span::point(5, 1)));
+        assert_eq!(table.span_at(3), Some(Span::point(6, 1)));
+    }
+}
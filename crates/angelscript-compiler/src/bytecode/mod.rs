@@ -2,14 +2,43 @@
 //!
 //! This module contains the core bytecode types:
 //!
-//! - [`OpCode`] - The instruction set for the VM
-//! - [`BytecodeChunk`] - Compiled bytecode for a function
+//! - [`OpCode`] - The instruction set for the VM, grouped by [`OpCategory`]
+//! - [`BytecodeChunk`] - Compiled bytecode for a function, with a
+//!   [`LineTable`] mapping its offsets back to source spans
 //! - [`Constant`] and [`ConstantPool`] - Module-level constant storage
+//!
+//! Chunks can be rendered as text with [`BytecodeChunk::disassemble`],
+//! edited with [`BytecodeRewriter`] and optimized with [`optimize`].
+//! [`eliminate_common_subexpressions`] computes repeated expressions once.
+//! [`compact_locals`] shares local slots between locals that are never
+//! live at the same time. [`emit_switch`] lowers `switch` dispatch to a jump table or a binary
+//! search, and [`emit_string_switch`] dispatches on precomputed string hashes. [`optimize_branches`] threads conditions through `&&`, `||`
+//! and `!` and moves loop conditions to the bottom of their loops.
 
+mod branch;
 mod chunk;
 mod constant;
+mod cse;
+mod disasm;
+mod frame;
+mod line_table;
 mod opcode;
+mod peephole;
+mod rewrite;
+mod switch;
+mod tail_call;
 
+pub use branch::{MAX_ROTATED_CONDITION, optimize_branches};
 pub use chunk::BytecodeChunk;
 pub use constant::{Constant, ConstantPool};
-pub use opcode::OpCode;
+pub use cse::eliminate_common_subexpressions;
+pub use frame::{FrameLayout, compact_locals};
+pub use line_table::{LineEntry, LineTable};
+pub use opcode::{OpCategory, OpCode};
+pub use peephole::{OptimizationLevel, optimize};
+pub use rewrite::{BytecodeRewriter, InstrId, Instruction, JumpTarget, RewriteError};
+pub use switch::{
+    MIN_HASHED_CASES, StringSwitchOps, SwitchDispatch, SwitchStrategy, emit_string_switch,
+    emit_switch, string_switch_hash,
+};
+pub use tail_call::eliminate_tail_calls;
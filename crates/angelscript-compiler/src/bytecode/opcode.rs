@@ -82,9 +82,18 @@ pub enum OpCode {
     SetField,
     /// Push 'this' reference.
     GetThis,
+    /// Load the value a reference points to.
+    /// Stack: [ref] -> [value]
+    LoadRef,
+    /// Store through a reference, leaving the value on the stack.
+    /// Stack: [ref, value] -> [value]
+    StoreRef,
 
     // =========================================================================
     // Arithmetic (generic - VM determines types from stack values)
+    //
+    // Integer overflow wraps or raises an exception as the module's
+    // `integer_overflow` mode says (see `crate::overflow`).
     // =========================================================================
     /// Add two numeric values (i8/i16/i32/i64/u8/u16/u32/u64/f32/f64).
     Add,
@@ -92,9 +101,11 @@ pub enum OpCode {
     Sub,
     /// Multiply two numeric values.
     Mul,
-    /// Divide two numeric values (signed/unsigned handled by VM).
+    /// Divide two numeric values (signed/unsigned handled by VM). Integer
+    /// division truncates towards zero and raises an exception on zero.
     Div,
-    /// Modulo of two integer values.
+    /// Remainder of two numeric values, with the sign of the dividend.
+    /// Raises an exception on an integer zero.
     Mod,
     /// Negate numeric value.
     Neg,
@@ -112,7 +123,8 @@ pub enum OpCode {
     BitXor,
     /// Bitwise NOT.
     BitNot,
-    /// Shift left.
+    /// Shift left. Shift amounts outside the operand's width wrap or
+    /// raise an exception like overflow.
     Shl,
     /// Arithmetic shift right (signed).
     Shr,
@@ -154,6 +166,17 @@ pub enum OpCode {
     /// Jump backward (for loops).
     /// Operand: u16 offset (big-endian)
     Loop,
+    /// Jump backward if top of stack is true (bottom-tested loops).
+    /// Operand: u16 offset (big-endian)
+    LoopIfTrue,
+    /// Indexed jump for dense `switch` statements.
+    /// Stack: [value] -> []
+    /// Operands: u16 constant index (lowest case value), u16 entry count
+    ///
+    /// Followed by entry count + 1 `JUMP` instructions. If `value - low` is
+    /// below the entry count, execution continues at that entry, otherwise
+    /// at the last one (the `default` label).
+    JumpTable,
 
     // =========================================================================
     // Function Calls
@@ -181,7 +204,7 @@ pub enum OpCode {
     // Object Creation
     // =========================================================================
     /// Allocate object and call constructor.
-    /// Operands: u8/u16 constant index (type hash), u8 arg count
+    /// Operands: u16 constant index (constructor hash), u8 arg count
     New,
     /// Call factory function.
     /// Operands: u8/u16 constant index (factory hash), u8 arg count
@@ -279,6 +302,12 @@ pub enum OpCode {
     /// Explicit cast (may fail at runtime).
     /// Operand: u8/u16 constant index (target type hash)
     Cast,
+    /// Reference cast (`cast<T>(handle)`): keeps the handle if the object is
+    /// a T, derives from T or implements T, and replaces it with null
+    /// otherwise.
+    /// Stack: [handle] -> [handle or null]
+    /// Operand: u16 constant index (target type hash)
+    RefCast,
 
     // =========================================================================
     // Function Pointers
@@ -286,6 +315,15 @@ pub enum OpCode {
     /// Create function pointer.
     /// Operand: u8/u16 constant index (function hash)
     FuncPtr,
+    /// Create a delegate binding the object on the stack to a method.
+    /// Stack: [object] -> [delegate]
+    /// Operand: u16 constant index (method hash)
+    NewDelegate,
+    /// Create a closure from the captured values on the stack, in capture
+    /// order.
+    /// Stack: [captures...] -> [closure]
+    /// Operand: u16 closure index (into the module's closures)
+    NewClosure,
     /// Call through function pointer.
     /// Operand: u8 arg count
     CallFuncPtr,
@@ -299,6 +337,16 @@ pub enum OpCode {
     /// End init list.
     InitListEnd,
 
+    // =========================================================================
+    // Strings
+    // =========================================================================
+    /// Build a string from a printf-style template, sized once up front.
+    /// `%s` takes a string, `%d` an int64, `%u` a uint64, `%f` a double and
+    /// `%b` a bool argument; `%%` is a literal `%`.
+    /// Stack: [args...] -> [string]
+    /// Operands: u16 constant index (template), u8 arg count
+    Format,
+
     // =========================================================================
     // Increment/Decrement
     // =========================================================================
@@ -335,13 +383,110 @@ pub enum OpCode {
     TryBegin,
     /// End try block - pops exception handler (try completed without exception).
     TryEnd,
+
+    // =========================================================================
+    // Extensions
+    // =========================================================================
+    /// Host-defined instruction, dispatched to the handler the embedder
+    /// registered for the extension op. Extension ops 0-255 are reserved for
+    /// embedders and never emitted by the compiler itself.
+    /// Operands: u16 immediate + u8 extension op
+    Extension,
+}
+
+impl OpCode {
+    /// Number of extension ops available to embedders.
+    pub const EXTENSION_OPS: usize = 256;
+}
+
+/// Coarse grouping of opcodes, used for profiling and diagnostics.
+///
+/// Categories mirror the sections of the [`OpCode`] instruction set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OpCategory {
+    /// Constant pushes (`CONSTANT`, `PUSH_NULL`, ...).
+    Constant,
+    /// Stack manipulation (`POP`, `DUP`, `PICK`, `SWAP`).
+    Stack,
+    /// Local variable loads and stores.
+    Local,
+    /// Global variable loads and stores.
+    Global,
+    /// Object field access and `this`.
+    Field,
+    /// Arithmetic, including increment/decrement.
+    Arithmetic,
+    /// Bitwise operations and shifts.
+    Bitwise,
+    /// Comparisons and logical NOT.
+    Comparison,
+    /// Jumps and loops.
+    ControlFlow,
+    /// Function, method and function pointer calls, and returns.
+    Call,
+    /// Object creation, init lists and string formatting.
+    Object,
+    /// Primitive and handle conversions.
+    Conversion,
+    /// Runtime type checks and casts.
+    TypeCheck,
+    /// Handle creation and reference counting.
+    Handle,
+    /// Exception handler setup.
+    Exception,
+    /// Host-defined extension instructions.
+    Extension,
+}
+
+impl OpCategory {
+    /// All categories, in declaration order.
+    pub const ALL: [OpCategory; 16] = [
+        OpCategory::Constant,
+        OpCategory::Stack,
+        OpCategory::Local,
+        OpCategory::Global,
+        OpCategory::Field,
+        OpCategory::Arithmetic,
+        OpCategory::Bitwise,
+        OpCategory::Comparison,
+        OpCategory::ControlFlow,
+        OpCategory::Call,
+        OpCategory::Object,
+        OpCategory::Conversion,
+        OpCategory::TypeCheck,
+        OpCategory::Handle,
+        OpCategory::Exception,
+        OpCategory::Extension,
+    ];
+
+    /// Get the name of this category for reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OpCategory::Constant => "constant",
+            OpCategory::Stack => "stack",
+            OpCategory::Local => "local",
+            OpCategory::Global => "global",
+            OpCategory::Field => "field",
+            OpCategory::Arithmetic => "arithmetic",
+            OpCategory::Bitwise => "bitwise",
+            OpCategory::Comparison => "comparison",
+            OpCategory::ControlFlow => "control_flow",
+            OpCategory::Call => "call",
+            OpCategory::Object => "object",
+            OpCategory::Conversion => "conversion",
+            OpCategory::TypeCheck => "type_check",
+            OpCategory::Handle => "handle",
+            OpCategory::Exception => "exception",
+            OpCategory::Extension => "extension",
+        }
+    }
 }
 
 impl OpCode {
     /// Convert from u8, returning None for invalid values.
     pub fn from_u8(value: u8) -> Option<Self> {
         // Safety: We check bounds before transmuting
-        if value <= OpCode::TryEnd as u8 {
+        if value <= OpCode::Extension as u8 {
             // SAFETY: OpCode is repr(u8) and we've verified the value is in range
             Some(unsafe { std::mem::transmute::<u8, OpCode>(value) })
         } else {
@@ -349,6 +494,11 @@ impl OpCode {
         }
     }
 
+    /// Check if this is a jump whose offset counts backward.
+    pub fn jumps_backward(&self) -> bool {
+        matches!(self, OpCode::Loop | OpCode::LoopIfTrue)
+    }
+
     /// Get the size of operands for this opcode in bytes.
     ///
     /// This does NOT include the opcode byte itself.
@@ -364,6 +514,8 @@ impl OpCode {
             | OpCode::Dup
             | OpCode::Swap
             | OpCode::GetThis
+            | OpCode::LoadRef
+            | OpCode::StoreRef
             | OpCode::Add
             | OpCode::Sub
             | OpCode::Mul
@@ -446,26 +598,153 @@ impl OpCode {
             | OpCode::JumpIfFalse   // i16 offset
             | OpCode::JumpIfTrue    // i16 offset
             | OpCode::Loop          // u16 offset
+            | OpCode::LoopIfTrue    // u16 offset
             | OpCode::DerivedToBase     // u16 constant index
             | OpCode::ClassToInterface  // u16 constant index
             | OpCode::InstanceOf        // u16 constant index
             | OpCode::Cast              // u16 constant index
+            | OpCode::RefCast           // u16 constant index
             | OpCode::FuncPtr           // u16 constant index
+            | OpCode::NewDelegate       // u16 constant index
+            | OpCode::NewClosure        // u16 closure index
             | OpCode::InitListBegin     // u16 size
             | OpCode::TryBegin => 2, // i16 offset
 
+            // 4-byte operand (u16 + u16)
+            OpCode::JumpTable => 4, // u16 low constant + u16 entry count
+
             // 3-byte operand (u16 + u8)
             OpCode::Call        // u16 constant index + u8 arg count
             | OpCode::CallMethod    // u16 constant index + u8 arg count
             | OpCode::CallVirtual   // u16 vtable slot + u8 arg count
             | OpCode::New           // u16 constant index + u8 arg count
-            | OpCode::NewFactory => 3, // u16 constant index + u8 arg count
+            | OpCode::NewFactory    // u16 constant index + u8 arg count
+            | OpCode::Format        // u16 constant index + u8 arg count
+            | OpCode::Extension => 3, // u16 immediate + u8 extension op
 
             // 5-byte operand (u16 + u16 + u8)
             OpCode::CallInterface => 5, // u16 iface hash constant + u16 slot + u8 arg count
         }
     }
 
+    /// Get the category this opcode belongs to.
+    pub fn category(&self) -> OpCategory {
+        match self {
+            OpCode::Constant
+            | OpCode::ConstantWide
+            | OpCode::PushNull
+            | OpCode::PushTrue
+            | OpCode::PushFalse
+            | OpCode::PushZero
+            | OpCode::PushOne => OpCategory::Constant,
+
+            OpCode::Pop | OpCode::PopN | OpCode::Dup | OpCode::Pick | OpCode::Swap => {
+                OpCategory::Stack
+            }
+
+            OpCode::GetLocal | OpCode::SetLocal | OpCode::GetLocalWide | OpCode::SetLocalWide => {
+                OpCategory::Local
+            }
+
+            OpCode::GetGlobal | OpCode::SetGlobal => OpCategory::Global,
+
+            OpCode::GetField
+            | OpCode::SetField
+            | OpCode::GetThis
+            | OpCode::LoadRef
+            | OpCode::StoreRef => OpCategory::Field,
+
+            OpCode::Add
+            | OpCode::Sub
+            | OpCode::Mul
+            | OpCode::Div
+            | OpCode::Mod
+            | OpCode::Neg
+            | OpCode::Pow
+            | OpCode::PreInc
+            | OpCode::PreDec
+            | OpCode::PostInc
+            | OpCode::PostDec => OpCategory::Arithmetic,
+
+            OpCode::BitAnd
+            | OpCode::BitOr
+            | OpCode::BitXor
+            | OpCode::BitNot
+            | OpCode::Shl
+            | OpCode::Shr
+            | OpCode::Ushr => OpCategory::Bitwise,
+
+            OpCode::Eq | OpCode::Lt | OpCode::Le | OpCode::Gt | OpCode::Ge | OpCode::Not => {
+                OpCategory::Comparison
+            }
+
+            OpCode::Jump
+            | OpCode::JumpIfFalse
+            | OpCode::JumpIfTrue
+            | OpCode::Loop
+            | OpCode::LoopIfTrue
+            | OpCode::JumpTable => OpCategory::ControlFlow,
+
+            OpCode::Call
+            | OpCode::CallMethod
+            | OpCode::CallVirtual
+            | OpCode::CallInterface
+            | OpCode::CallFuncPtr
+            | OpCode::FuncPtr
+            | OpCode::NewDelegate
+            | OpCode::NewClosure
+            | OpCode::Return
+            | OpCode::ReturnVoid => OpCategory::Call,
+
+            OpCode::New
+            | OpCode::NewFactory
+            | OpCode::InitListBegin
+            | OpCode::InitListEnd
+            | OpCode::Format => OpCategory::Object,
+
+            OpCode::I8toI16
+            | OpCode::I8toI32
+            | OpCode::I8toI64
+            | OpCode::I16toI32
+            | OpCode::I16toI64
+            | OpCode::I32toI64
+            | OpCode::U8toU16
+            | OpCode::U8toU32
+            | OpCode::U8toU64
+            | OpCode::U16toU32
+            | OpCode::U16toU64
+            | OpCode::U32toU64
+            | OpCode::I64toI32
+            | OpCode::I64toI16
+            | OpCode::I64toI8
+            | OpCode::I32toI16
+            | OpCode::I32toI8
+            | OpCode::I16toI8
+            | OpCode::I32toF32
+            | OpCode::I32toF64
+            | OpCode::I64toF32
+            | OpCode::I64toF64
+            | OpCode::F32toI32
+            | OpCode::F32toI64
+            | OpCode::F64toI32
+            | OpCode::F64toI64
+            | OpCode::F32toF64
+            | OpCode::F64toF32
+            | OpCode::HandleToConst
+            | OpCode::DerivedToBase
+            | OpCode::ClassToInterface
+            | OpCode::ValueToHandle => OpCategory::Conversion,
+
+            OpCode::InstanceOf | OpCode::Cast | OpCode::RefCast => OpCategory::TypeCheck,
+
+            OpCode::HandleOf | OpCode::AddRef | OpCode::Release => OpCategory::Handle,
+
+            OpCode::TryBegin | OpCode::TryEnd => OpCategory::Exception,
+
+            OpCode::Extension => OpCategory::Extension,
+        }
+    }
+
     /// Get the name of this opcode for debugging.
     pub fn name(&self) -> &'static str {
         match self {
@@ -489,6 +768,8 @@ impl OpCode {
             OpCode::GetField => "GET_FIELD",
             OpCode::SetField => "SET_FIELD",
             OpCode::GetThis => "GET_THIS",
+            OpCode::LoadRef => "LOAD_REF",
+            OpCode::StoreRef => "STORE_REF",
             OpCode::Add => "ADD",
             OpCode::Sub => "SUB",
             OpCode::Mul => "MUL",
@@ -513,6 +794,8 @@ impl OpCode {
             OpCode::JumpIfFalse => "JUMP_IF_FALSE",
             OpCode::JumpIfTrue => "JUMP_IF_TRUE",
             OpCode::Loop => "LOOP",
+            OpCode::LoopIfTrue => "LOOP_IF_TRUE",
+            OpCode::JumpTable => "JUMP_TABLE",
             OpCode::Call => "CALL",
             OpCode::CallMethod => "CALL_METHOD",
             OpCode::CallVirtual => "CALL_VIRTUAL",
@@ -555,10 +838,14 @@ impl OpCode {
             OpCode::ValueToHandle => "VALUE_TO_HANDLE",
             OpCode::InstanceOf => "INSTANCE_OF",
             OpCode::Cast => "CAST",
+            OpCode::RefCast => "REF_CAST",
             OpCode::FuncPtr => "FUNC_PTR",
+            OpCode::NewDelegate => "NEW_DELEGATE",
+            OpCode::NewClosure => "NEW_CLOSURE",
             OpCode::CallFuncPtr => "CALL_FUNC_PTR",
             OpCode::InitListBegin => "INIT_LIST_BEGIN",
             OpCode::InitListEnd => "INIT_LIST_END",
+            OpCode::Format => "FORMAT",
             OpCode::PreInc => "PRE_INC",
             OpCode::PreDec => "PRE_DEC",
             OpCode::PostInc => "POST_INC",
@@ -569,6 +856,7 @@ impl OpCode {
             OpCode::Release => "RELEASE",
             OpCode::TryBegin => "TRY_BEGIN",
             OpCode::TryEnd => "TRY_END",
+            OpCode::Extension => "EXTENSION",
         }
     }
 }
@@ -609,8 +897,17 @@ mod tests {
         assert_eq!(OpCode::from_u8(try_begin_val), Some(OpCode::TryBegin));
         assert_eq!(OpCode::from_u8(try_end_val), Some(OpCode::TryEnd));
 
-        // TryEnd should be the last opcode
-        assert_eq!(OpCode::from_u8(try_end_val + 1), None);
+        assert_eq!(OpCode::from_u8(try_end_val + 1), Some(OpCode::Extension));
+    }
+
+    #[test]
+    fn extension_opcode() {
+        // Extension should be the last opcode
+        let extension_val = OpCode::Extension as u8;
+        assert_eq!(OpCode::from_u8(extension_val), Some(OpCode::Extension));
+        assert_eq!(OpCode::from_u8(extension_val + 1), None);
+        assert_eq!(OpCode::Extension.operand_size(), 3);
+        assert_eq!(OpCode::Extension.category(), OpCategory::Extension);
     }
 
     #[test]
@@ -634,5 +931,27 @@ mod tests {
         assert_eq!(OpCode::Call.operand_size(), 3);
         assert_eq!(OpCode::CallMethod.operand_size(), 3);
         assert_eq!(OpCode::New.operand_size(), 3);
+
+        // 4-byte operand
+        assert_eq!(OpCode::JumpTable.operand_size(), 4);
+    }
+
+    #[test]
+    fn opcode_categories() {
+        assert_eq!(OpCode::Constant.category(), OpCategory::Constant);
+        assert_eq!(OpCode::Add.category(), OpCategory::Arithmetic);
+        assert_eq!(OpCode::PostInc.category(), OpCategory::Arithmetic);
+        assert_eq!(OpCode::JumpIfFalse.category(), OpCategory::ControlFlow);
+        assert_eq!(OpCode::CallInterface.category(), OpCategory::Call);
+        assert_eq!(OpCode::F64toF32.category(), OpCategory::Conversion);
+        assert_eq!(OpCode::TryEnd.category(), OpCategory::Exception);
+    }
+
+    #[test]
+    fn category_names_are_unique() {
+        let mut names: Vec<_> = OpCategory::ALL.iter().map(|c| c.name()).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), OpCategory::ALL.len());
     }
 }
@@ -0,0 +1,485 @@
+//! Peephole optimization of emitted bytecode.
+//!
+//! [`optimize`] decodes a chunk with a [`BytecodeRewriter`] and applies
+//! local rewrites until none applies any more:
+//!
+//! | Rewrite                 | Before                          | After              |
+//! |-------------------------|---------------------------------|--------------------|
+//! | Push/pop pairs          | `PUSH_ZERO; POP`                | (removed)          |
+//! | Jump chains             | `JUMP a` where `a: JUMP b`      | `JUMP b`           |
+//! | Jumps to the next instr | `JUMP next`                     | (removed)          |
+//! | Increments              | `PUSH_ONE; ADD`                 | `PRE_INC`          |
+//! | Dead stores             | `SET_LOCAL x; POP; ...; SET_LOCAL x` | `POP; ...; SET_LOCAL x` |
+//! | Unreachable code        | `RETURN; PUSH_ONE`              | `RETURN`           |
+//!
+//! Instructions are never merged across a jump target, so every path into
+//! the rewritten code sees the same stack. `SET_LOCAL` stores the top of the
+//! stack without popping it, and `PRE_INC`/`PRE_DEC` replace the top of the
+//! stack with its successor/predecessor. Unreachable code is what no path
+//! from the entry reaches, like statements after a `return` (see
+//! [`reachability`](crate::reachability)).
+
+use super::branch::remove_unreachable;
+use super::{BytecodeChunk, BytecodeRewriter, Constant, ConstantPool, InstrId, Instruction};
+use super::{JumpTarget, OpCategory, OpCode, RewriteError};
+
+/// How much the compiler optimizes emitted bytecode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum OptimizationLevel {
+    /// Keep bytecode as emitted, e.g. for stepping through it in a debugger.
+    None,
+    /// Remove redundant stack traffic and jumps, use increment opcodes,
+    /// turn self tail calls into loops (see [`eliminate_tail_calls`]) and
+    /// evaluate calls of pure native and script functions with constant
+    /// arguments (see [`fold_constexpr_calls`]).
+    ///
+    /// [`eliminate_tail_calls`]: super::eliminate_tail_calls
+    /// [`fold_constexpr_calls`]: crate::constexpr::fold_constexpr_calls
+    #[default]
+    Basic,
+    /// Also remove stores to locals that are overwritten before being read,
+    /// compute repeated expressions once (see
+    /// [`eliminate_common_subexpressions`]) and share slots between locals
+    /// (see [`compact_locals`]).
+    ///
+    /// [`eliminate_common_subexpressions`]: super::eliminate_common_subexpressions
+    /// [`compact_locals`]: super::compact_locals
+    Aggressive,
+}
+
+/// Optimize a chunk. `pool` is the module's constant pool, used to recognize
+/// constant operands.
+///
+/// # Errors
+///
+/// Returns an error if the chunk cannot be decoded or re-encoded.
+pub fn optimize(
+    chunk: &BytecodeChunk,
+    pool: &ConstantPool,
+    level: OptimizationLevel,
+) -> Result<BytecodeChunk, RewriteError> {
+    if level == OptimizationLevel::None {
+        return Ok(chunk.clone());
+    }
+
+    let mut rewriter = BytecodeRewriter::new(chunk)?;
+    loop {
+        let mut changed = merge_jump_chains(&mut rewriter)?;
+        changed |= collapse_push_pop(&mut rewriter);
+        changed |= use_increments(&mut rewriter, pool);
+        changed |= remove_unreachable(&mut rewriter);
+        if level >= OptimizationLevel::Aggressive {
+            changed |= remove_dead_stores(&mut rewriter);
+        }
+        if !changed {
+            break;
+        }
+    }
+    rewriter.finish()
+}
+
+/// Pushes without side effects, which a following `POP` cancels.
+fn is_pure_push(op: OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::Constant
+            | OpCode::ConstantWide
+            | OpCode::PushNull
+            | OpCode::PushTrue
+            | OpCode::PushFalse
+            | OpCode::PushZero
+            | OpCode::PushOne
+            | OpCode::Dup
+            | OpCode::Pick
+            | OpCode::GetLocal
+            | OpCode::GetLocalWide
+            | OpCode::GetThis
+    )
+}
+
+fn collapse_push_pop(rewriter: &mut BytecodeRewriter) -> bool {
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    let mut changed = false;
+    let mut i = 0;
+    while i + 1 < ids.len() {
+        let (push, pop) = (ids[i], ids[i + 1]);
+        if is_pure_push(op(rewriter, push))
+            && op(rewriter, pop) == OpCode::Pop
+            && !rewriter.is_jump_target(pop)
+        {
+            rewriter.remove(pop);
+            rewriter.remove(push);
+            changed = true;
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    changed
+}
+
+fn merge_jump_chains(rewriter: &mut BytecodeRewriter) -> Result<bool, RewriteError> {
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    let position = |id: InstrId| ids.iter().position(|&i| i == id);
+    let mut changed = false;
+
+    for (at, &id) in ids.iter().enumerate() {
+        let jump_op = op(rewriter, id);
+        if !matches!(
+            jump_op,
+            OpCode::Jump
+                | OpCode::JumpIfFalse
+                | OpCode::JumpIfTrue
+                | OpCode::Loop
+                | OpCode::LoopIfTrue
+        ) {
+            continue;
+        }
+        let Some(original) = rewriter.jump_target(id) else {
+            continue;
+        };
+
+        // Follow unconditional jumps, stopping at cycles
+        let mut target = original;
+        for _ in 0..ids.len() {
+            match target {
+                JumpTarget::Instr(next)
+                    if next != id && matches!(op(rewriter, next), OpCode::Jump | OpCode::Loop) =>
+                {
+                    match rewriter.jump_target(next) {
+                        Some(further) if further != target => target = further,
+                        _ => break,
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        // `LOOP`s only jump backwards and the other jumps only forwards
+        let lands = match target {
+            JumpTarget::Instr(to) => position(to),
+            JumpTarget::End => Some(ids.len()),
+        };
+        let reachable = match lands {
+            Some(to) if jump_op.jumps_backward() => to <= at,
+            Some(to) => to > at,
+            None => false,
+        };
+        if target != original && reachable {
+            let line = rewriter.get(id).map_or(0, Instruction::line);
+            rewriter.replace(id, Instruction::jump(jump_op, target, line)?);
+            changed = true;
+        }
+    }
+
+    // A jump to the next instruction does nothing, unless it is an entry of
+    // a jump table, which is addressed by position
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    let mut table_entries = 0;
+    for (at, &id) in ids.iter().enumerate() {
+        if table_entries > 0 {
+            table_entries -= 1;
+            continue;
+        }
+        if let Some(table) = rewriter.get(id)
+            && let (OpCode::JumpTable, &[_, _, hi, lo]) = (table.op(), table.operands())
+        {
+            // One entry per case plus the default entry
+            table_entries = usize::from(u16::from_be_bytes([hi, lo])) + 1;
+            continue;
+        }
+        let next = ids
+            .get(at + 1)
+            .map_or(JumpTarget::End, |&next| JumpTarget::Instr(next));
+        if op(rewriter, id) == OpCode::Jump && rewriter.jump_target(id) == Some(next) {
+            rewriter.remove(id);
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+fn use_increments(rewriter: &mut BytecodeRewriter, pool: &ConstantPool) -> bool {
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    let mut changed = false;
+    let mut i = 0;
+    while i + 1 < ids.len() {
+        let (one, arith) = (ids[i], ids[i + 1]);
+        let replacement = match op(rewriter, arith) {
+            OpCode::Add => Some(OpCode::PreInc),
+            OpCode::Sub => Some(OpCode::PreDec),
+            _ => None,
+        };
+        match replacement {
+            Some(increment)
+                if is_int_one(rewriter, one, pool) && !rewriter.is_jump_target(arith) =>
+            {
+                let line = rewriter.get(arith).map_or(0, Instruction::line);
+                rewriter.replace(arith, Instruction::simple(increment, line));
+                rewriter.remove(one);
+                changed = true;
+                i += 2;
+            }
+            _ => i += 1,
+        }
+    }
+    changed
+}
+
+fn is_int_one(rewriter: &BytecodeRewriter, id: InstrId, pool: &ConstantPool) -> bool {
+    let Some(instruction) = rewriter.get(id) else {
+        return false;
+    };
+    let index = match (instruction.op(), instruction.operands()) {
+        (OpCode::PushOne, _) => return true,
+        (OpCode::Constant, &[index]) => u32::from(index),
+        (OpCode::ConstantWide, &[hi, lo]) => u32::from(u16::from_be_bytes([hi, lo])),
+        _ => return false,
+    };
+    matches!(pool.get(index), Some(Constant::Int(1) | Constant::Uint(1)))
+}
+
+fn remove_dead_stores(rewriter: &mut BytecodeRewriter) -> bool {
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    for (at, &store) in ids.iter().enumerate() {
+        let Some(slot) = local_slot(rewriter, store, true) else {
+            continue;
+        };
+        let Some(&pop) = ids.get(at + 1) else {
+            continue;
+        };
+        if op(rewriter, pop) != OpCode::Pop || rewriter.is_jump_target(pop) {
+            continue;
+        }
+
+        // The store is dead if straight-line code overwrites the slot
+        // before anything can read it
+        for &next in &ids[at + 2..] {
+            if local_slot(rewriter, next, true) == Some(slot) {
+                rewriter.remove(store);
+                return true;
+            }
+            if local_slot(rewriter, next, false) == Some(slot)
+                || !is_straight_line(op(rewriter, next))
+            {
+                break;
+            }
+        }
+    }
+    false
+}
+
+/// Instructions that neither transfer control nor read locals indirectly.
+fn is_straight_line(op: OpCode) -> bool {
+    matches!(
+        op.category(),
+        OpCategory::Constant
+            | OpCategory::Stack
+            | OpCategory::Local
+            | OpCategory::Global
+            | OpCategory::Arithmetic
+            | OpCategory::Bitwise
+            | OpCategory::Comparison
+            | OpCategory::Conversion
+    )
+}
+
+/// Slot stored to (`store`) or loaded from (`!store`) by a local access.
+fn local_slot(rewriter: &BytecodeRewriter, id: InstrId, store: bool) -> Option<u16> {
+    let instruction = rewriter.get(id)?;
+    match (instruction.op(), instruction.operands(), store) {
+        (OpCode::SetLocal, &[slot], true) | (OpCode::GetLocal, &[slot], false) => {
+            Some(u16::from(slot))
+        }
+        (OpCode::SetLocalWide, &[hi, lo], true) | (OpCode::GetLocalWide, &[hi, lo], false) => {
+            Some(u16::from_be_bytes([hi, lo]))
+        }
+        _ => None,
+    }
+}
+
+fn op(rewriter: &BytecodeRewriter, id: InstrId) -> OpCode {
+    rewriter
+        .get(id)
+        .map_or(OpCode::Pop, |instruction| instruction.op())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn optimized(chunk: &BytecodeChunk, level: OptimizationLevel) -> Vec<OpCode> {
+        optimize(chunk, &ConstantPool::new(), level)
+            .unwrap()
+            .opcodes()
+    }
+
+    #[test]
+    fn none_keeps_bytecode() {
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::PushZero, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        assert_eq!(
+            optimized(&chunk, OptimizationLevel::None),
+            [OpCode::PushZero, OpCode::Pop]
+        );
+    }
+
+    #[test]
+    fn collapses_push_pop_pairs() {
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.write_op(OpCode::PushTrue, 2);
+        chunk.write_op(OpCode::Dup, 2);
+        chunk.write_op(OpCode::Pop, 2);
+        chunk.write_op(OpCode::Return, 2);
+
+        assert_eq!(
+            optimized(&chunk, OptimizationLevel::Basic),
+            [OpCode::PushTrue, OpCode::Return]
+        );
+    }
+
+    #[test]
+    fn removes_code_after_return() {
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::PushOne, 1);
+        chunk.write_op(OpCode::Return, 1);
+        chunk.write_op(OpCode::PushZero, 2);
+        chunk.write_op(OpCode::Return, 2);
+
+        assert_eq!(
+            optimized(&chunk, OptimizationLevel::Basic),
+            [OpCode::PushOne, OpCode::Return]
+        );
+        assert_eq!(optimized(&chunk, OptimizationLevel::None), chunk.opcodes());
+    }
+
+    #[test]
+    fn keeps_pop_that_is_a_jump_target() {
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::PushTrue, 1);
+        let skip = chunk.emit_jump(OpCode::JumpIfTrue, 1);
+        chunk.write_op(OpCode::PushZero, 2);
+        chunk.patch_jump(skip);
+        chunk.write_op(OpCode::Pop, 3);
+        chunk.write_op(OpCode::ReturnVoid, 3);
+
+        assert_eq!(optimized(&chunk, OptimizationLevel::Basic), chunk.opcodes());
+    }
+
+    #[test]
+    fn merges_jump_chains() {
+        // if (c) { } else { } with both branches jumping to a shared exit
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::PushTrue, 1);
+        let to_trampoline = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op(OpCode::PushOne, 2);
+        chunk.write_op(OpCode::Return, 2);
+        chunk.patch_jump(to_trampoline);
+        let to_exit = chunk.emit_jump(OpCode::Jump, 3);
+        chunk.write_op(OpCode::PushZero, 4);
+        chunk.write_op(OpCode::Return, 4);
+        chunk.patch_jump(to_exit);
+        chunk.write_op(OpCode::ReturnVoid, 5);
+
+        let result = optimize(&chunk, &ConstantPool::new(), OptimizationLevel::Basic).unwrap();
+        let rewriter = BytecodeRewriter::new(&result).unwrap();
+        let ids: Vec<InstrId> = rewriter.ids().collect();
+        let exit = *ids.last().unwrap();
+        assert_eq!(rewriter.jump_target(ids[1]), Some(JumpTarget::Instr(exit)));
+    }
+
+    #[test]
+    fn removes_jump_to_next_instruction() {
+        let mut chunk = BytecodeChunk::new();
+        let jump = chunk.emit_jump(OpCode::Jump, 1);
+        chunk.patch_jump(jump);
+        chunk.write_op(OpCode::ReturnVoid, 2);
+
+        assert_eq!(
+            optimized(&chunk, OptimizationLevel::Basic),
+            [OpCode::ReturnVoid]
+        );
+    }
+
+    #[test]
+    fn uses_increment_opcodes() {
+        let mut pool = ConstantPool::new();
+        let one = pool.add_int(1);
+
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::PushOne, 1);
+        chunk.write_op(OpCode::Add, 1);
+        chunk.write_op(OpCode::Constant, 2);
+        chunk.write_byte(one as u8, 2);
+        chunk.write_op(OpCode::Sub, 2);
+        chunk.write_op(OpCode::Return, 2);
+
+        let result = optimize(&chunk, &pool, OptimizationLevel::Basic).unwrap();
+        assert_eq!(
+            result.opcodes(),
+            [
+                OpCode::GetLocal,
+                OpCode::PreInc,
+                OpCode::PreDec,
+                OpCode::Return
+            ]
+        );
+    }
+
+    #[test]
+    fn removes_dead_stores_when_aggressive() {
+        // x = 1; x = 2; return x;
+        let mut chunk = BytecodeChunk::new();
+        for (value, line) in [(OpCode::PushOne, 1), (OpCode::PushZero, 2)] {
+            chunk.write_op(value, line);
+            chunk.write_op(OpCode::SetLocal, line);
+            chunk.write_byte(0, line);
+            chunk.write_op(OpCode::Pop, line);
+        }
+        chunk.write_op(OpCode::GetLocal, 3);
+        chunk.write_byte(0, 3);
+        chunk.write_op(OpCode::Return, 3);
+
+        assert_eq!(optimized(&chunk, OptimizationLevel::Basic), chunk.opcodes());
+        assert_eq!(
+            optimized(&chunk, OptimizationLevel::Aggressive),
+            [
+                OpCode::PushZero,
+                OpCode::SetLocal,
+                OpCode::Pop,
+                OpCode::GetLocal,
+                OpCode::Return
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_stores_that_are_read() {
+        // x = 1; y = x; x = 2;
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::PushOne, 1);
+        chunk.write_op(OpCode::SetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::Pop, 1);
+        chunk.write_op(OpCode::GetLocal, 2);
+        chunk.write_byte(0, 2);
+        chunk.write_op(OpCode::SetLocal, 2);
+        chunk.write_byte(1, 2);
+        chunk.write_op(OpCode::Pop, 2);
+        chunk.write_op(OpCode::PushZero, 3);
+        chunk.write_op(OpCode::SetLocal, 3);
+        chunk.write_byte(0, 3);
+        chunk.write_op(OpCode::ReturnVoid, 3);
+
+        assert_eq!(
+            optimized(&chunk, OptimizationLevel::Aggressive),
+            chunk.opcodes()
+        );
+    }
+}
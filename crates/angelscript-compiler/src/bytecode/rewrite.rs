@@ -0,0 +1,737 @@
+//! Safe rewriting of compiled bytecode.
+//!
+//! Instrumentation tools (profilers, coverage, tracing) need to splice
+//! instructions into compiled functions. Editing the raw byte vector breaks
+//! every jump that crosses the edit, so [`BytecodeRewriter`] decodes a chunk
+//! into instructions whose jump operands refer to other instructions rather
+//! than byte distances. Edits are made on the instruction list and the jump
+//! distances are recomputed by [`BytecodeRewriter::finish`].
+//!
+//! Instructions keep their source span through the rewrite. New ones are
+//! usually built from a line alone; those placed next to an instruction on
+//! the same line, or replacing one, take its full span.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // Count every entry into the function
+//! let mut rewriter = BytecodeRewriter::new(&function.bytecode)?;
+//! let first = rewriter.ids().next().unwrap();
+//! rewriter.insert_before(first, [
+//!     Instruction::load_constant(&mut module.constants, Constant::Int(id), 0),
+//!     Instruction::with_operands(OpCode::Call, &[0, 0, 1], 0)?,
+//! ]);
+//! function.bytecode = rewriter.finish()?;
+//! ```
+
+use angelscript_core::Span;
+
+use super::{BytecodeChunk, Constant, ConstantPool, OpCode};
+
+/// Identifies an instruction within a [`BytecodeRewriter`].
+///
+/// Ids stay valid across edits; ids of removed instructions forward to the
+/// instruction that followed them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InstrId(usize);
+
+/// Destination of a jump instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumpTarget {
+    /// The given instruction.
+    Instr(InstrId),
+    /// The end of the chunk.
+    End,
+}
+
+/// A decoded instruction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    op: OpCode,
+    operands: Vec<u8>,
+    target: Option<JumpTarget>,
+    span: Span,
+}
+
+impl Instruction {
+    /// An instruction without operands.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `op` takes operands.
+    pub fn simple(op: OpCode, line: u32) -> Self {
+        assert_eq!(op.operand_size(), 0, "{} takes operands", op.name());
+        Self {
+            op,
+            operands: Vec::new(),
+            target: None,
+            span: Span::point(line, 0),
+        }
+    }
+
+    /// An instruction with encoded operands.
+    ///
+    /// Jumps must be created with [`Instruction::jump`] instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `operands` does not match the opcode's operand
+    /// size or `op` is a jump.
+    pub fn with_operands(op: OpCode, operands: &[u8], line: u32) -> Result<Self, RewriteError> {
+        if is_jump(op) {
+            return Err(RewriteError::JumpWithoutTarget(op));
+        }
+        if operands.len() != op.operand_size() {
+            return Err(RewriteError::OperandSize {
+                op,
+                expected: op.operand_size(),
+                actual: operands.len(),
+            });
+        }
+        Ok(Self {
+            op,
+            operands: operands.to_vec(),
+            target: None,
+            span: Span::point(line, 0),
+        })
+    }
+
+    /// A jump, loop or try instruction to `target`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `op` is not a jump.
+    pub fn jump(op: OpCode, target: JumpTarget, line: u32) -> Result<Self, RewriteError> {
+        if !is_jump(op) {
+            return Err(RewriteError::NotAJump(op));
+        }
+        Ok(Self {
+            op,
+            operands: Vec::new(),
+            target: Some(target),
+            span: Span::point(line, 0),
+        })
+    }
+
+    /// Load a constant, adding it to `pool` if needed.
+    ///
+    /// Uses `CONSTANT` for the first 256 entries and `CONSTANT_WIDE` beyond.
+    pub fn load_constant(pool: &mut ConstantPool, constant: Constant, line: u32) -> Self {
+        let index = pool.add(constant);
+        let (op, operands) = match u8::try_from(index) {
+            Ok(byte) => (OpCode::Constant, vec![byte]),
+            Err(_) => (OpCode::ConstantWide, (index as u16).to_be_bytes().to_vec()),
+        };
+        Self {
+            op,
+            operands,
+            target: None,
+            span: Span::point(line, 0),
+        }
+    }
+
+    /// An `EXTENSION` instruction running the host handler of extension op
+    /// `op` with `immediate` as its operand.
+    pub fn extension(op: u8, immediate: u16, line: u32) -> Self {
+        let mut operands = immediate.to_be_bytes().to_vec();
+        operands.push(op);
+        Self {
+            op: OpCode::Extension,
+            operands,
+            target: None,
+            span: Span::point(line, 0),
+        }
+    }
+
+    /// The opcode.
+    pub fn op(&self) -> OpCode {
+        self.op
+    }
+
+    /// Encoded operands; empty for jumps, whose target is kept separately.
+    pub fn operands(&self) -> &[u8] {
+        &self.operands
+    }
+
+    /// Destination of a jump instruction.
+    pub fn target(&self) -> Option<JumpTarget> {
+        self.target
+    }
+
+    /// Source line of the instruction.
+    pub fn line(&self) -> u32 {
+        self.span.line
+    }
+
+    /// Source span of the instruction; column 0 if only the line is known.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Give the instruction a source span.
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = span;
+        self
+    }
+
+    /// Take `anchor`'s span if this instruction only has `anchor`'s line.
+    fn inherit(mut self, anchor: &Instruction) -> Self {
+        if self.span == Span::point(anchor.span.line, 0) {
+            self.span = anchor.span;
+        }
+        self
+    }
+}
+
+/// Errors that can occur when rewriting bytecode.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum RewriteError {
+    /// The chunk contains a byte that is not an opcode
+    #[error("Invalid opcode at offset {0}")]
+    InvalidOpcode(usize),
+
+    /// An instruction's operands run past the end of the chunk
+    #[error("Truncated instruction at offset {0}")]
+    Truncated(usize),
+
+    /// A jump lands inside another instruction or outside the chunk
+    #[error("Jump at offset {0} does not land on an instruction")]
+    InvalidJumpTarget(usize),
+
+    /// A jump is too far to encode
+    #[error("Jump distance {0} exceeds u16::MAX")]
+    JumpTooFar(usize),
+
+    /// A jump instruction was created without a target
+    #[error("{} requires a jump target", .0.name())]
+    JumpWithoutTarget(OpCode),
+
+    /// A jump target was given for an instruction that does not jump
+    #[error("{} is not a jump instruction", .0.name())]
+    NotAJump(OpCode),
+
+    /// Operands do not match the opcode
+    #[error("{} takes {expected} operand byte(s), got {actual}", .op.name())]
+    OperandSize {
+        /// The opcode.
+        op: OpCode,
+        /// Operand size of the opcode.
+        expected: usize,
+        /// Number of operand bytes supplied.
+        actual: usize,
+    },
+}
+
+#[derive(Debug)]
+enum Slot {
+    Live(Instruction),
+    /// Removed; jumps to it go to the given target instead.
+    Removed(JumpTarget),
+}
+
+/// Editable view of a bytecode chunk.
+#[derive(Debug)]
+pub struct BytecodeRewriter {
+    slots: Vec<Slot>,
+    order: Vec<InstrId>,
+}
+
+impl BytecodeRewriter {
+    /// Decode a chunk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chunk contains invalid or truncated
+    /// instructions, or jumps that do not land on an instruction.
+    pub fn new(chunk: &BytecodeChunk) -> Result<Self, RewriteError> {
+        let code = chunk.code();
+        let mut decoded = Vec::new();
+        let mut offset = 0;
+        while offset < code.len() {
+            let op = chunk
+                .read_op(offset)
+                .ok_or(RewriteError::InvalidOpcode(offset))?;
+            let next = offset + 1 + op.operand_size();
+            if next > code.len() {
+                return Err(RewriteError::Truncated(offset));
+            }
+            decoded.push((offset, op, &code[offset + 1..next]));
+            offset = next;
+        }
+
+        let index_at = |target: usize, from: usize| -> Result<JumpTarget, RewriteError> {
+            if target == code.len() {
+                return Ok(JumpTarget::End);
+            }
+            decoded
+                .binary_search_by_key(&target, |&(at, _, _)| at)
+                .map(|i| JumpTarget::Instr(InstrId(i)))
+                .map_err(|_| RewriteError::InvalidJumpTarget(from))
+        };
+
+        let mut slots = Vec::with_capacity(decoded.len());
+        for &(at, op, operands) in &decoded {
+            let span = chunk.span_at(at).unwrap_or_default();
+            let next = at + 1 + operands.len();
+            let instruction = if is_jump(op) {
+                let distance = u16::from_be_bytes([operands[0], operands[1]]) as usize;
+                let target = if op.jumps_backward() {
+                    next.checked_sub(distance)
+                        .ok_or(RewriteError::InvalidJumpTarget(at))?
+                } else {
+                    next + distance
+                };
+                Instruction {
+                    op,
+                    operands: Vec::new(),
+                    target: Some(index_at(target, at)?),
+                    span,
+                }
+            } else {
+                Instruction {
+                    op,
+                    operands: operands.to_vec(),
+                    target: None,
+                    span,
+                }
+            };
+            slots.push(Slot::Live(instruction));
+        }
+
+        Ok(Self {
+            order: (0..slots.len()).map(InstrId).collect(),
+            slots,
+        })
+    }
+
+    /// Ids of the instructions, in program order.
+    pub fn ids(&self) -> impl Iterator<Item = InstrId> + '_ {
+        self.order.iter().copied()
+    }
+
+    /// The instructions with their ids, in program order.
+    pub fn instructions(&self) -> impl Iterator<Item = (InstrId, &Instruction)> + '_ {
+        self.order.iter().map(|&id| (id, self.live(id)))
+    }
+
+    /// Get an instruction, or `None` if it was removed.
+    pub fn get(&self, id: InstrId) -> Option<&Instruction> {
+        match self.slots.get(id.0)? {
+            Slot::Live(instruction) => Some(instruction),
+            Slot::Removed(_) => None,
+        }
+    }
+
+    /// Destination of the jump at `id`, following removed instructions.
+    ///
+    /// Returns `None` if `id` is not a live jump.
+    pub fn jump_target(&self, id: InstrId) -> Option<JumpTarget> {
+        self.get(id)?.target.map(|target| self.resolve(target))
+    }
+
+    /// Check if any jump lands on `id`.
+    pub fn is_jump_target(&self, id: InstrId) -> bool {
+        self.order
+            .iter()
+            .any(|&jump| self.jump_target(jump) == Some(JumpTarget::Instr(id)))
+    }
+
+    /// Number of instructions.
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    /// Check if there are no instructions.
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Insert instructions before `id`, returning their ids.
+    ///
+    /// Jumps to `id` land on the first inserted instruction, so code inserted
+    /// at the start of a block runs however the block is entered.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was removed.
+    pub fn insert_before(
+        &mut self,
+        id: InstrId,
+        instructions: impl IntoIterator<Item = Instruction>,
+    ) -> Vec<InstrId> {
+        let position = self.position(id);
+        let ids = self.splice(position, Some(id), instructions);
+        if let Some(&first) = ids.first() {
+            self.redirect(JumpTarget::Instr(id), JumpTarget::Instr(first));
+        }
+        ids
+    }
+
+    /// Insert instructions after `id`, returning their ids.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was removed.
+    pub fn insert_after(
+        &mut self,
+        id: InstrId,
+        instructions: impl IntoIterator<Item = Instruction>,
+    ) -> Vec<InstrId> {
+        let position = self.position(id) + 1;
+        self.splice(position, Some(id), instructions)
+    }
+
+    /// Append instructions at the end of the chunk, returning their ids.
+    ///
+    /// Jumps to the end of the chunk land on the first appended instruction.
+    pub fn append(&mut self, instructions: impl IntoIterator<Item = Instruction>) -> Vec<InstrId> {
+        let ids = self.splice(self.order.len(), None, instructions);
+        if let Some(&first) = ids.first() {
+            self.redirect(JumpTarget::End, JumpTarget::Instr(first));
+        }
+        ids
+    }
+
+    /// Replace an instruction, keeping its id and the jumps to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was removed.
+    pub fn replace(&mut self, id: InstrId, instruction: Instruction) {
+        let replaced = self.live_mut(id);
+        *replaced = instruction.inherit(replaced);
+    }
+
+    /// Remove an instruction. Jumps to it land on the following instruction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` was already removed.
+    pub fn remove(&mut self, id: InstrId) -> Instruction {
+        let position = self.position(id);
+        self.order.remove(position);
+        let next = self
+            .order
+            .get(position)
+            .map_or(JumpTarget::End, |&next| JumpTarget::Instr(next));
+        match std::mem::replace(&mut self.slots[id.0], Slot::Removed(next)) {
+            Slot::Live(instruction) => instruction,
+            Slot::Removed(_) => unreachable!("position() only finds live instructions"),
+        }
+    }
+
+    /// Point every jump to `from` at `to` instead.
+    pub fn retarget(&mut self, from: InstrId, to: JumpTarget) {
+        self.redirect(JumpTarget::Instr(from), to);
+    }
+
+    /// Encode the instructions into a new chunk.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a jump is too far to encode, or a backward jump
+    /// was made with an opcode that can only jump forward (or vice versa).
+    pub fn finish(self) -> Result<BytecodeChunk, RewriteError> {
+        let mut offsets = vec![0usize; self.slots.len()];
+        let mut offset = 0;
+        for &id in &self.order {
+            offsets[id.0] = offset;
+            offset += 1 + self.live(id).op.operand_size();
+        }
+        let end = offset;
+
+        let mut chunk = BytecodeChunk::with_capacity(end);
+        for &id in &self.order {
+            let instruction = self.live(id);
+            let line = instruction.span.line;
+            chunk.set_span(instruction.span);
+            chunk.write_op(instruction.op, line);
+
+            let Some(target) = instruction.target else {
+                for &byte in &instruction.operands {
+                    chunk.write_byte(byte, line);
+                }
+                continue;
+            };
+
+            let next = offsets[id.0] + 3;
+            let destination = match self.resolve(target) {
+                JumpTarget::Instr(target) => offsets[target.0],
+                JumpTarget::End => end,
+            };
+            let distance = if instruction.op.jumps_backward() {
+                next.checked_sub(destination)
+            } else {
+                destination.checked_sub(next)
+            }
+            .ok_or(RewriteError::InvalidJumpTarget(offsets[id.0]))?;
+            let distance =
+                u16::try_from(distance).map_err(|_| RewriteError::JumpTooFar(distance))?;
+            chunk.write_u16(distance, line);
+        }
+        Ok(chunk)
+    }
+
+    fn splice(
+        &mut self,
+        position: usize,
+        anchor: Option<InstrId>,
+        instructions: impl IntoIterator<Item = Instruction>,
+    ) -> Vec<InstrId> {
+        let anchor = anchor.map(|id| self.live(id).clone());
+        let ids: Vec<InstrId> = instructions
+            .into_iter()
+            .map(|instruction| {
+                let instruction = match &anchor {
+                    Some(anchor) => instruction.inherit(anchor),
+                    None => instruction,
+                };
+                self.slots.push(Slot::Live(instruction));
+                InstrId(self.slots.len() - 1)
+            })
+            .collect();
+        self.order.splice(position..position, ids.iter().copied());
+        ids
+    }
+
+    fn redirect(&mut self, from: JumpTarget, to: JumpTarget) {
+        let jumps: Vec<InstrId> = self
+            .order
+            .iter()
+            .copied()
+            .filter(|&id| self.live(id).target.map(|t| self.resolve(t)) == Some(from))
+            .collect();
+        for id in jumps {
+            self.live_mut(id).target = Some(to);
+        }
+    }
+
+    /// Follow removed instructions to the instruction that replaced them.
+    fn resolve(&self, mut target: JumpTarget) -> JumpTarget {
+        while let JumpTarget::Instr(id) = target
+            && let Slot::Removed(next) = self.slots[id.0]
+        {
+            target = next;
+        }
+        target
+    }
+
+    fn position(&self, id: InstrId) -> usize {
+        self.order
+            .iter()
+            .position(|&i| i == id)
+            .unwrap_or_else(|| panic!("instruction {:?} was removed", id))
+    }
+
+    fn live(&self, id: InstrId) -> &Instruction {
+        self.get(id)
+            .unwrap_or_else(|| panic!("instruction {:?} was removed", id))
+    }
+
+    fn live_mut(&mut self, id: InstrId) -> &mut Instruction {
+        match &mut self.slots[id.0] {
+            Slot::Live(instruction) => instruction,
+            Slot::Removed(_) => panic!("instruction {:?} was removed", id),
+        }
+    }
+}
+
+fn is_jump(op: OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::Jump
+            | OpCode::JumpIfFalse
+            | OpCode::JumpIfTrue
+            | OpCode::Loop
+            | OpCode::LoopIfTrue
+            | OpCode::TryBegin
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `while (true) { if (cond) break; }` shaped chunk.
+    fn looping_chunk() -> BytecodeChunk {
+        let mut chunk = BytecodeChunk::new();
+        let start = chunk.current_offset();
+        chunk.write_op(OpCode::PushTrue, 1);
+        let exit = chunk.emit_jump(OpCode::JumpIfTrue, 1);
+        chunk.write_op(OpCode::PushOne, 2);
+        chunk.write_op(OpCode::Pop, 2);
+        chunk.emit_loop(start, 3);
+        chunk.patch_jump(exit);
+        chunk.write_op(OpCode::ReturnVoid, 4);
+        chunk
+    }
+
+    fn roundtrip(chunk: &BytecodeChunk) -> BytecodeChunk {
+        BytecodeRewriter::new(chunk).unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn roundtrip_preserves_bytes() {
+        let chunk = looping_chunk();
+        let rewritten = roundtrip(&chunk);
+        assert_eq!(rewritten.code(), chunk.code());
+        assert_eq!(rewritten.line_table(), chunk.line_table());
+    }
+
+    #[test]
+    fn spans_survive_edits() {
+        let mut chunk = BytecodeChunk::new();
+        let call = Span::new(7, 12, 9);
+        chunk.set_span(call);
+        chunk.write_op(OpCode::PushOne, 7);
+        chunk.write_op(OpCode::Pop, 7);
+        chunk.write_op(OpCode::ReturnVoid, 8);
+
+        let mut rewriter = BytecodeRewriter::new(&chunk).unwrap();
+        let ids: Vec<InstrId> = rewriter.ids().collect();
+        assert_eq!(rewriter.get(ids[0]).unwrap().span(), call);
+        rewriter.replace(ids[0], Instruction::simple(OpCode::PushZero, 7));
+        rewriter.insert_after(ids[1], [Instruction::simple(OpCode::PushOne, 7)]);
+        let other = Span::new(7, 1, 2);
+        rewriter.insert_after(
+            ids[1],
+            [Instruction::simple(OpCode::Pop, 7).with_span(other)],
+        );
+        rewriter.insert_before(ids[2], [Instruction::simple(OpCode::PushTrue, 9)]);
+
+        let rewritten = rewriter.finish().unwrap();
+        assert_eq!(rewritten.span_at(0), Some(call));
+        assert_eq!(rewritten.span_at(2), Some(other));
+        assert_eq!(rewritten.span_at(3), Some(call));
+        // Another line than the neighbour's keeps just the line
+        assert_eq!(rewritten.span_at(4), Some(Span::point(9, 0)));
+        assert_eq!(rewritten.span_at(5), Some(Span::point(8, 0)));
+    }
+
+    #[test]
+    fn insert_before_loop_head_relabels_jumps() {
+        let chunk = looping_chunk();
+        let mut rewriter = BytecodeRewriter::new(&chunk).unwrap();
+        let head = rewriter.ids().next().unwrap();
+        rewriter.insert_before(
+            head,
+            [
+                Instruction::simple(OpCode::PushZero, 1),
+                Instruction::simple(OpCode::Pop, 1),
+            ],
+        );
+        let rewritten = rewriter.finish().unwrap();
+
+        rewritten.assert_opcodes(&[
+            OpCode::PushZero,
+            OpCode::Pop,
+            OpCode::PushTrue,
+            OpCode::JumpIfTrue,
+            OpCode::PushOne,
+            OpCode::Pop,
+            OpCode::Loop,
+            OpCode::ReturnVoid,
+        ]);
+        // The loop now jumps back to the inserted instructions
+        assert_eq!(rewritten.read_u16(9), Some(11));
+        // The exit jump still skips the loop body
+        assert_eq!(rewritten.read_u16(4), Some(5));
+    }
+
+    #[test]
+    fn insert_after_jump_grows_distance() {
+        let chunk = looping_chunk();
+        let mut rewriter = BytecodeRewriter::new(&chunk).unwrap();
+        let ids: Vec<_> = rewriter.ids().collect();
+        rewriter.insert_after(ids[2], [Instruction::simple(OpCode::Dup, 2)]);
+        let rewritten = rewriter.finish().unwrap();
+
+        assert_eq!(rewritten.read_u16(2), Some(6));
+        assert_eq!(rewritten.read_u16(8), Some(10));
+    }
+
+    #[test]
+    fn remove_forwards_jumps() {
+        let chunk = looping_chunk();
+        let mut rewriter = BytecodeRewriter::new(&chunk).unwrap();
+        let ids: Vec<_> = rewriter.ids().collect();
+        let removed = rewriter.remove(ids[0]);
+        assert_eq!(removed.op(), OpCode::PushTrue);
+        assert!(rewriter.get(ids[0]).is_none());
+
+        let rewritten = rewriter.finish().unwrap();
+        rewritten.assert_opcodes(&[
+            OpCode::JumpIfTrue,
+            OpCode::PushOne,
+            OpCode::Pop,
+            OpCode::Loop,
+            OpCode::ReturnVoid,
+        ]);
+        // The loop now targets the jump, formerly the second instruction
+        assert_eq!(rewritten.read_u16(6), Some(8));
+    }
+
+    #[test]
+    fn constants_choose_encoding() {
+        let mut pool = ConstantPool::new();
+        for i in 0..256 {
+            pool.add_int(i);
+        }
+
+        let narrow = Instruction::load_constant(&mut pool, Constant::Int(7), 1);
+        assert_eq!(narrow.op(), OpCode::Constant);
+        assert_eq!(narrow.operands(), &[7]);
+
+        let wide = Instruction::load_constant(&mut pool, Constant::Int(1000), 1);
+        assert_eq!(wide.op(), OpCode::ConstantWide);
+        assert_eq!(wide.operands(), &[1, 0]);
+    }
+
+    #[test]
+    fn extension_instructions_round_trip() {
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::ReturnVoid, 1);
+        let mut rewriter = BytecodeRewriter::new(&chunk).unwrap();
+        let first = rewriter.ids().next().unwrap();
+        rewriter.insert_before(first, [Instruction::extension(3, 500, 1)]);
+
+        let rewritten = rewriter.finish().unwrap();
+        rewritten.assert_opcodes(&[OpCode::Extension, OpCode::ReturnVoid]);
+        assert_eq!(rewritten.read_u16(1), Some(500));
+        assert_eq!(rewritten.read_byte(3), Some(3));
+        assert!(
+            rewritten
+                .disassemble(&ConstantPool::new())
+                .contains("EXTENSION           op=3 500")
+        );
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::Jump, 1);
+        chunk.write_u16(1, 1);
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(0, 1);
+        assert_eq!(
+            BytecodeRewriter::new(&chunk).unwrap_err(),
+            RewriteError::InvalidJumpTarget(0)
+        );
+
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::GetLocal, 1);
+        assert_eq!(
+            BytecodeRewriter::new(&chunk).unwrap_err(),
+            RewriteError::Truncated(0)
+        );
+
+        assert!(matches!(
+            Instruction::with_operands(OpCode::GetLocal, &[], 1),
+            Err(RewriteError::OperandSize { expected: 1, .. })
+        ));
+        assert!(matches!(
+            Instruction::with_operands(OpCode::Jump, &[0, 0], 1),
+            Err(RewriteError::JumpWithoutTarget(OpCode::Jump))
+        ));
+    }
+}
@@ -0,0 +1,706 @@
+//! Lowering of `switch` dispatch.
+//!
+//! A switch compares one integer against many constant case values. Rather
+//! than testing each case in turn, [`emit_switch`] picks a strategy from the
+//! case values:
+//!
+//! - **Jump table** when the values are dense: a single `JUMP_TABLE`
+//!   indexes a run of `JUMP` instructions by `value - low`, so dispatch is
+//!   O(1).
+//! - **Binary search** otherwise: a ladder of `LT` comparisons halves the
+//!   candidates until a few remain, which are tested with `EQ`, so dispatch
+//!   is O(log n).
+//!
+//! Either way the switch value is consumed by the dispatch and every case
+//! label (and the `default` label) is a forward jump patched by the caller
+//! once the label's body is reached:
+//!
+//! ```ignore
+//! // switch value is on the stack
+//! let dispatch = emit_switch(&mut chunk, &mut constants, &case_values, line);
+//! for (index, case) in cases.iter().enumerate() {
+//!     dispatch.patch_case(&mut chunk, index);
+//!     // ... emit the case body
+//! }
+//! dispatch.patch_default(&mut chunk);
+//! ```
+//!
+//! The `JUMP` entries following a `JUMP_TABLE` are addressed by position, so
+//! nothing may be inserted between them.
+//!
+//! String switches ([`emit_string_switch`]) hash the switch value once with
+//! [`string_switch_hash`] and dispatch on the hashes of the case labels, which
+//! are computed at compile time. Only the labels whose hash matches are then
+//! compared with `opEquals`, so a miss usually costs no string comparison.
+//! Switches with fewer than [`MIN_HASHED_CASES`] labels skip the hash call
+//! and compare the labels in turn, which is cheaper for so few.
+
+use angelscript_core::TypeHash;
+use rustc_hash::FxHashMap;
+
+use super::{BytecodeChunk, ConstantPool, OpCode};
+
+/// Minimum number of case values for a jump table.
+const MIN_TABLE_CASES: usize = 4;
+
+/// Case values tested one by one at the leaves of a binary search.
+const LINEAR_CASES: usize = 3;
+
+/// Minimum number of string labels for a hash dispatch.
+pub const MIN_HASHED_CASES: usize = 4;
+
+/// How a switch dispatches to its cases.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwitchStrategy {
+    /// Indexed `JUMP_TABLE`.
+    JumpTable,
+    /// Comparison ladder.
+    BinarySearch,
+    /// `opEquals` with each string label in turn.
+    Sequential,
+}
+
+impl SwitchStrategy {
+    /// Choose the strategy for a set of unique case values.
+    ///
+    /// A jump table is used once there are enough cases and at least half of
+    /// the table's entries would be case labels.
+    pub fn for_values(values: &[i64]) -> Self {
+        let (Some(&low), Some(&high)) = (values.iter().min(), values.iter().max()) else {
+            return SwitchStrategy::BinarySearch;
+        };
+        let span = high.abs_diff(low).saturating_add(1);
+        if values.len() >= MIN_TABLE_CASES
+            && span <= values.len() as u64 * 2
+            && span < u16::MAX as u64
+        {
+            SwitchStrategy::JumpTable
+        } else {
+            SwitchStrategy::BinarySearch
+        }
+    }
+}
+
+/// Pending jumps of an emitted switch dispatch.
+#[derive(Debug, Clone)]
+pub struct SwitchDispatch {
+    strategy: SwitchStrategy,
+    /// Jump to patch for each case value, in the order given.
+    cases: Vec<usize>,
+    /// Jumps to patch for the `default` label.
+    default: Vec<usize>,
+}
+
+impl SwitchDispatch {
+    /// The strategy used.
+    pub fn strategy(&self) -> SwitchStrategy {
+        self.strategy
+    }
+
+    /// Point the case value at `index` to the current position.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn patch_case(&self, chunk: &mut BytecodeChunk, index: usize) {
+        chunk.patch_jump(self.cases[index]);
+    }
+
+    /// Point the `default` label to the current position.
+    ///
+    /// Without a `default` label, patch it at the end of the switch.
+    pub fn patch_default(&self, chunk: &mut BytecodeChunk) {
+        for &jump in &self.default {
+            chunk.patch_jump(jump);
+        }
+    }
+}
+
+/// Functions a string switch calls at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StringSwitchOps {
+    /// Function taking the string and returning its [`string_switch_hash`].
+    pub hash: TypeHash,
+    /// The string type's `opEquals` method.
+    pub equals: TypeHash,
+}
+
+/// Hash of a string case label or switch value: 32-bit FNV-1a over the
+/// string's bytes.
+///
+/// The host's hash function for [`StringSwitchOps::hash`] must compute the
+/// same value.
+pub fn string_switch_hash(bytes: &[u8]) -> i64 {
+    let hash = bytes.iter().fold(0x811c_9dc5_u32, |hash, &byte| {
+        (hash ^ u32::from(byte)).wrapping_mul(0x0100_0193)
+    });
+    i64::from(hash)
+}
+
+/// Emit the dispatch of a switch whose string value is on top of the stack.
+///
+/// `values` are the case labels in label order, without duplicates. Labels
+/// whose hashes collide share a dispatch target and are told apart by
+/// `opEquals`. With fewer than [`MIN_HASHED_CASES`] labels the dispatch is
+/// [`SwitchStrategy::Sequential`] and `ops.hash` is not called.
+pub fn emit_string_switch(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    values: &[Vec<u8>],
+    ops: StringSwitchOps,
+    line: u32,
+) -> SwitchDispatch {
+    if values.len() < MIN_HASHED_CASES {
+        // The string stays on the stack until a label matches
+        let labels: Vec<usize> = (0..values.len()).collect();
+        let mut hits = Vec::new();
+        emit_equals(chunk, constants, values, &labels, ops, line, &mut hits);
+        let miss = chunk.emit_jump(OpCode::Jump, line);
+        let (cases, default) = emit_exits(chunk, values.len(), hits, vec![miss], line);
+        return SwitchDispatch {
+            strategy: SwitchStrategy::Sequential,
+            cases,
+            default,
+        };
+    }
+
+    // Labels grouped by hash, in label order
+    let mut buckets: Vec<(i64, Vec<usize>)> = Vec::new();
+    for (index, value) in values.iter().enumerate() {
+        let hash = string_switch_hash(value);
+        match buckets.iter_mut().find(|(h, _)| *h == hash) {
+            Some((_, labels)) => labels.push(index),
+            None => buckets.push((hash, vec![index])),
+        }
+    }
+
+    // The string stays on the stack under its hash until a label matches
+    chunk.write_op(OpCode::Dup, line);
+    let hash_index = constants.add_type_hash(ops.hash);
+    chunk.write_op(OpCode::Call, line);
+    chunk.write_u16(hash_index as u16, line);
+    chunk.write_byte(1, line);
+    let hashes: Vec<i64> = buckets.iter().map(|(hash, _)| *hash).collect();
+    let by_hash = emit_switch(chunk, constants, &hashes, line);
+
+    let mut hits = Vec::new();
+    let mut misses = Vec::new();
+    for (bucket, (_, labels)) in buckets.iter().enumerate() {
+        by_hash.patch_case(chunk, bucket);
+        emit_equals(chunk, constants, values, labels, ops, line, &mut hits);
+        misses.push(chunk.emit_jump(OpCode::Jump, line));
+    }
+    misses.extend(by_hash.default);
+    let (cases, default) = emit_exits(chunk, values.len(), hits, misses, line);
+
+    SwitchDispatch {
+        strategy: by_hash.strategy,
+        cases,
+        default,
+    }
+}
+
+/// Compare the string on the stack with each of `labels`, keeping it,
+/// recording the jump taken for each match.
+fn emit_equals(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    values: &[Vec<u8>],
+    labels: &[usize],
+    ops: StringSwitchOps,
+    line: u32,
+    hits: &mut Vec<(usize, usize)>,
+) {
+    for &index in labels {
+        chunk.write_op(OpCode::Dup, line);
+        emit_constant(chunk, constants.add_string(values[index].clone()), line);
+        let equals_index = constants.add_type_hash(ops.equals);
+        chunk.write_op(OpCode::CallMethod, line);
+        chunk.write_u16(equals_index as u16, line);
+        chunk.write_byte(1, line);
+        hits.push((chunk.emit_jump(OpCode::JumpIfTrue, line), index));
+    }
+}
+
+/// Patch the jumps out of a dispatch that kept the switch value on the
+/// stack: each pops it before jumping on to its label. Returns the jumps
+/// to patch for each case and for `default`.
+fn emit_exits(
+    chunk: &mut BytecodeChunk,
+    count: usize,
+    hits: Vec<(usize, usize)>,
+    misses: Vec<usize>,
+    line: u32,
+) -> (Vec<usize>, Vec<usize>) {
+    let mut cases = vec![0; count];
+    for (jump, index) in hits {
+        chunk.patch_jump(jump);
+        chunk.write_op(OpCode::Pop, line);
+        cases[index] = chunk.emit_jump(OpCode::Jump, line);
+    }
+    for jump in misses {
+        chunk.patch_jump(jump);
+    }
+    chunk.write_op(OpCode::Pop, line);
+    (cases, vec![chunk.emit_jump(OpCode::Jump, line)])
+}
+
+/// Emit the dispatch of a switch whose value is on top of the stack.
+///
+/// `values` are the case values in label order, as evaluated by
+/// [`ConstEvaluator::case_values`](crate::ConstEvaluator::case_values),
+/// which reports duplicates before lowering.
+///
+/// # Panics
+///
+/// Panics if `values` contains duplicates.
+pub fn emit_switch(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    values: &[i64],
+    line: u32,
+) -> SwitchDispatch {
+    let mut by_value = FxHashMap::default();
+    for (index, &value) in values.iter().enumerate() {
+        assert!(
+            by_value.insert(value, index).is_none(),
+            "duplicate case value {value}"
+        );
+    }
+
+    match SwitchStrategy::for_values(values) {
+        SwitchStrategy::JumpTable => emit_jump_table(chunk, constants, values, &by_value, line),
+        // `for_values` never picks `Sequential`, which is only for strings
+        SwitchStrategy::BinarySearch | SwitchStrategy::Sequential => {
+            emit_binary_search(chunk, constants, values, line)
+        }
+    }
+}
+
+fn emit_jump_table(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    values: &[i64],
+    by_value: &FxHashMap<i64, usize>,
+    line: u32,
+) -> SwitchDispatch {
+    let low = values.iter().copied().min().unwrap_or(0);
+    let high = values.iter().copied().max().unwrap_or(0);
+    let span = high.abs_diff(low) + 1;
+
+    let low_index = constants.add_int(low);
+    chunk.write_op(OpCode::JumpTable, line);
+    chunk.write_u16(low_index as u16, line);
+    chunk.write_u16(span as u16, line);
+
+    let mut cases = vec![0; values.len()];
+    let mut default = Vec::new();
+    for offset in 0..span {
+        let jump = chunk.emit_jump(OpCode::Jump, line);
+        match by_value.get(&low.wrapping_add(offset as i64)) {
+            Some(&index) => cases[index] = jump,
+            None => default.push(jump),
+        }
+    }
+    default.push(chunk.emit_jump(OpCode::Jump, line));
+
+    SwitchDispatch {
+        strategy: SwitchStrategy::JumpTable,
+        cases,
+        default,
+    }
+}
+
+fn emit_binary_search(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    values: &[i64],
+    line: u32,
+) -> SwitchDispatch {
+    let mut sorted: Vec<(i64, usize)> = values.iter().copied().zip(0..).collect();
+    sorted.sort_unstable();
+
+    // The ladder keeps the value on the stack; each exit pops it
+    let mut hits = Vec::new();
+    let mut misses = Vec::new();
+    emit_search(chunk, constants, &sorted, line, &mut hits, &mut misses);
+
+    let (cases, default) = emit_exits(chunk, values.len(), hits, misses, line);
+
+    SwitchDispatch {
+        strategy: SwitchStrategy::BinarySearch,
+        cases,
+        default,
+    }
+}
+
+/// Emit the comparisons for `sorted`, recording the jump taken for each
+/// matching case and the jumps taken when none matches.
+fn emit_search(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    sorted: &[(i64, usize)],
+    line: u32,
+    hits: &mut Vec<(usize, usize)>,
+    misses: &mut Vec<usize>,
+) {
+    if sorted.len() <= LINEAR_CASES {
+        for &(value, index) in sorted {
+            emit_compare(chunk, constants, value, OpCode::Eq, line);
+            hits.push((chunk.emit_jump(OpCode::JumpIfTrue, line), index));
+        }
+        misses.push(chunk.emit_jump(OpCode::Jump, line));
+        return;
+    }
+
+    let (lower, upper) = sorted.split_at(sorted.len() / 2);
+    emit_compare(chunk, constants, upper[0].0, OpCode::Lt, line);
+    let to_lower = chunk.emit_jump(OpCode::JumpIfTrue, line);
+    emit_search(chunk, constants, upper, line, hits, misses);
+    chunk.patch_jump(to_lower);
+    emit_search(chunk, constants, lower, line, hits, misses);
+}
+
+/// Compare the switch value with `value`, keeping the switch value.
+fn emit_compare(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    value: i64,
+    compare: OpCode,
+    line: u32,
+) {
+    chunk.write_op(OpCode::Dup, line);
+    emit_constant(chunk, constants.add_int(value), line);
+    chunk.write_op(compare, line);
+}
+
+fn emit_constant(chunk: &mut BytecodeChunk, index: u32, line: u32) {
+    match u8::try_from(index) {
+        Ok(byte) => {
+            chunk.write_op(OpCode::Constant, line);
+            chunk.write_byte(byte, line);
+        }
+        Err(_) => {
+            chunk.write_op(OpCode::ConstantWide, line);
+            chunk.write_u16(index as u16, line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::Constant;
+
+    /// Emit a switch whose case bodies are single `RETURN_VOID`s and
+    /// return the chunk with the offset of each body, default last.
+    fn lower(values: &[i64]) -> (BytecodeChunk, ConstantPool, Vec<usize>, SwitchStrategy) {
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        let dispatch = emit_switch(&mut chunk, &mut constants, values, 1);
+        let mut bodies = Vec::new();
+        for index in 0..values.len() {
+            dispatch.patch_case(&mut chunk, index);
+            bodies.push(chunk.current_offset());
+            chunk.write_op(OpCode::ReturnVoid, 1);
+        }
+        dispatch.patch_default(&mut chunk);
+        bodies.push(chunk.current_offset());
+        chunk.write_op(OpCode::ReturnVoid, 1);
+        (chunk, constants, bodies, dispatch.strategy())
+    }
+
+    /// Run the dispatch for `value`, returning the body offset reached and
+    /// the stack depth there.
+    fn dispatch(chunk: &BytecodeChunk, constants: &ConstantPool, value: i64) -> (usize, usize) {
+        let int = |index: u32| match constants.get(index) {
+            Some(Constant::Int(v)) => *v,
+            other => panic!("not an int constant: {other:?}"),
+        };
+        let mut stack = vec![value];
+        let mut pc = 0;
+        loop {
+            let op = chunk.read_op(pc).unwrap();
+            let next = pc + 1 + op.operand_size();
+            let word = |at: usize| chunk.read_u16(at).unwrap() as usize;
+            pc = match op {
+                OpCode::Dup => {
+                    stack.push(*stack.last().unwrap());
+                    next
+                }
+                OpCode::Pop => {
+                    stack.pop();
+                    next
+                }
+                OpCode::Constant => {
+                    stack.push(int(chunk.read_byte(pc + 1).unwrap() as u32));
+                    next
+                }
+                OpCode::Eq | OpCode::Lt => {
+                    let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+                    let result = if op == OpCode::Eq { a == b } else { a < b };
+                    stack.push(result as i64);
+                    next
+                }
+                OpCode::JumpIfTrue => match stack.pop().unwrap() {
+                    0 => next,
+                    _ => next + word(pc + 1),
+                },
+                OpCode::Jump => next + word(pc + 1),
+                OpCode::JumpTable => {
+                    let low = int(word(pc + 1) as u32);
+                    let entries = word(pc + 3) as u64;
+                    let entry = stack.pop().unwrap().wrapping_sub(low) as u64;
+                    next + 3 * entry.min(entries) as usize
+                }
+                OpCode::ReturnVoid => return (pc, stack.len()),
+                other => panic!("unexpected {}", other.name()),
+            };
+        }
+    }
+
+    #[test]
+    fn dense_values_use_jump_table() {
+        let values = [3, 1, 2, 0, 5];
+        let (chunk, constants, bodies, strategy) = lower(&values);
+        assert_eq!(strategy, SwitchStrategy::JumpTable);
+        assert_eq!(chunk.read_op(0), Some(OpCode::JumpTable));
+
+        for (index, &value) in values.iter().enumerate() {
+            assert_eq!(dispatch(&chunk, &constants, value), (bodies[index], 0));
+        }
+        // The hole and values outside the table go to default
+        let default = *bodies.last().unwrap();
+        for value in [4, -1, 6, i64::MIN, i64::MAX] {
+            assert_eq!(dispatch(&chunk, &constants, value), (default, 0));
+        }
+    }
+
+    #[test]
+    fn sparse_values_use_binary_search() {
+        let values = [1000, -7, 42, 1 << 40, 9, 77, 12, 500, -300];
+        let (chunk, constants, bodies, strategy) = lower(&values);
+        assert_eq!(strategy, SwitchStrategy::BinarySearch);
+        assert!(!chunk.opcodes().contains(&OpCode::JumpTable));
+
+        for (index, &value) in values.iter().enumerate() {
+            assert_eq!(dispatch(&chunk, &constants, value), (bodies[index], 0));
+        }
+        let default = *bodies.last().unwrap();
+        for value in [0, 8, 10, 41, 43, 499, 1001, -8, i64::MAX] {
+            assert_eq!(dispatch(&chunk, &constants, value), (default, 0));
+        }
+    }
+
+    #[test]
+    fn binary_search_compares_logarithmically() {
+        let values: Vec<i64> = (0..64).map(|i| i * 100).collect();
+        let (chunk, _, _, _) = lower(&values);
+        let compares = chunk
+            .opcodes()
+            .iter()
+            .filter(|&&op| op == OpCode::Lt)
+            .count();
+        // One `LT` per inner node of the search tree
+        assert!(compares < values.len() / 2, "{compares} comparisons");
+    }
+
+    #[test]
+    fn strategy_selection() {
+        assert_eq!(
+            SwitchStrategy::for_values(&[]),
+            SwitchStrategy::BinarySearch
+        );
+        // Too few cases for a table
+        assert_eq!(
+            SwitchStrategy::for_values(&[0, 1, 2]),
+            SwitchStrategy::BinarySearch
+        );
+        assert_eq!(
+            SwitchStrategy::for_values(&[10, 12, 14, 16]),
+            SwitchStrategy::JumpTable
+        );
+        assert_eq!(
+            SwitchStrategy::for_values(&[10, 12, 14, 18]),
+            SwitchStrategy::BinarySearch
+        );
+        assert_eq!(
+            SwitchStrategy::for_values(&[i64::MIN, 0, 1, i64::MAX]),
+            SwitchStrategy::BinarySearch
+        );
+    }
+
+    #[test]
+    fn without_cases_only_default_remains() {
+        let (chunk, constants, bodies, _) = lower(&[]);
+        assert_eq!(dispatch(&chunk, &constants, 5), (bodies[0], 0));
+    }
+
+    #[test]
+    fn optimizer_keeps_table_entries() {
+        use crate::bytecode::{OptimizationLevel, optimize};
+
+        // Cases without bodies make entries jump to the next instruction
+        let values = [0, 1, 2, 3];
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        let dispatch = emit_switch(&mut chunk, &mut constants, &values, 1);
+        for index in 0..values.len() {
+            dispatch.patch_case(&mut chunk, index);
+        }
+        dispatch.patch_default(&mut chunk);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let optimized = optimize(&chunk, &constants, OptimizationLevel::Aggressive).unwrap();
+        optimized.assert_opcodes(&[
+            OpCode::JumpTable,
+            OpCode::Jump,
+            OpCode::Jump,
+            OpCode::Jump,
+            OpCode::Jump,
+            OpCode::Jump,
+            OpCode::ReturnVoid,
+        ]);
+    }
+
+    /// Lower a string switch and run it for `value`, returning the index of
+    /// the label reached (`values.len()` for default), the stack depth
+    /// there and the number of `opEquals` calls made.
+    fn string_dispatch(values: &[&str], value: &str) -> (usize, usize, usize) {
+        string_switch(values, value).0
+    }
+
+    /// [`string_dispatch`] with the strategy used.
+    fn string_switch(values: &[&str], value: &str) -> ((usize, usize, usize), SwitchStrategy) {
+        #[derive(Clone, PartialEq)]
+        enum Value {
+            Int(i64),
+            Str(Vec<u8>),
+        }
+        let ops = StringSwitchOps {
+            hash: TypeHash::from_name("switch_hash"),
+            equals: TypeHash::from_name("string::opEquals"),
+        };
+
+        let values: Vec<Vec<u8>> = values.iter().map(|v| v.as_bytes().to_vec()).collect();
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        let dispatch = emit_string_switch(&mut chunk, &mut constants, &values, ops, 1);
+        let mut bodies = Vec::new();
+        for index in 0..values.len() {
+            dispatch.patch_case(&mut chunk, index);
+            bodies.push(chunk.current_offset());
+            chunk.write_op(OpCode::ReturnVoid, 1);
+        }
+        dispatch.patch_default(&mut chunk);
+        bodies.push(chunk.current_offset());
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let constant = |index: usize| match constants.get(index as u32) {
+            Some(Constant::Int(v)) => Value::Int(*v),
+            Some(Constant::StringData(s)) => Value::Str(s.clone()),
+            other => panic!("unexpected constant {other:?}"),
+        };
+        let int = |value: Value| match value {
+            Value::Int(v) => v,
+            Value::Str(_) => panic!("not an int"),
+        };
+        let mut stack = vec![Value::Str(value.as_bytes().to_vec())];
+        let mut compares = 0;
+        let mut pc = 0;
+        loop {
+            let op = chunk.read_op(pc).unwrap();
+            let next = pc + 1 + op.operand_size();
+            let word = |at: usize| chunk.read_u16(at).unwrap() as usize;
+            pc = match op {
+                OpCode::Dup => {
+                    stack.push(stack.last().unwrap().clone());
+                    next
+                }
+                OpCode::Pop => {
+                    stack.pop();
+                    next
+                }
+                OpCode::Constant => {
+                    stack.push(constant(chunk.read_byte(pc + 1).unwrap() as usize));
+                    next
+                }
+                OpCode::Call => {
+                    let Some(Value::Str(s)) = stack.pop() else {
+                        panic!("hash of a non-string")
+                    };
+                    stack.push(Value::Int(string_switch_hash(&s)));
+                    next
+                }
+                OpCode::CallMethod => {
+                    compares += 1;
+                    let (b, a) = (stack.pop().unwrap(), stack.pop().unwrap());
+                    stack.push(Value::Int((a == b) as i64));
+                    next
+                }
+                OpCode::Eq | OpCode::Lt => {
+                    let (b, a) = (int(stack.pop().unwrap()), int(stack.pop().unwrap()));
+                    let result = if op == OpCode::Eq { a == b } else { a < b };
+                    stack.push(Value::Int(result as i64));
+                    next
+                }
+                OpCode::JumpIfTrue => match int(stack.pop().unwrap()) {
+                    0 => next,
+                    _ => next + word(pc + 1),
+                },
+                OpCode::Jump => next + word(pc + 1),
+                OpCode::JumpTable => {
+                    let low = int(constant(word(pc + 1)));
+                    let entries = word(pc + 3) as u64;
+                    let entry = int(stack.pop().unwrap()).wrapping_sub(low) as u64;
+                    next + 3 * entry.min(entries) as usize
+                }
+                OpCode::ReturnVoid => {
+                    let label = bodies.iter().position(|&b| b == pc).unwrap();
+                    return ((label, stack.len(), compares), dispatch.strategy());
+                }
+                other => panic!("unexpected {}", other.name()),
+            };
+        }
+    }
+
+    #[test]
+    fn string_switch_compares_only_on_hash_match() {
+        let labels = ["go", "stop", "look", "take", "drop", "quit"];
+        for (index, label) in labels.iter().enumerate() {
+            assert_eq!(string_dispatch(&labels, label), (index, 0, 1), "{label}");
+        }
+        assert_eq!(string_dispatch(&labels, "dance"), (labels.len(), 0, 0));
+        assert_eq!(string_dispatch(&labels, ""), (labels.len(), 0, 0));
+    }
+
+    #[test]
+    fn string_switch_handles_hash_collisions() {
+        // Known 32-bit FNV-1a collision
+        assert_eq!(
+            string_switch_hash(b"costarring"),
+            string_switch_hash(b"liquid")
+        );
+        let labels = ["costarring", "liquid", "other", "more"];
+        assert_eq!(string_dispatch(&labels, "costarring"), (0, 0, 1));
+        assert_eq!(string_dispatch(&labels, "liquid"), (1, 0, 2));
+        assert_eq!(string_dispatch(&labels, "other"), (2, 0, 1));
+    }
+
+    #[test]
+    fn small_string_switch_compares_in_turn() {
+        let labels = ["yes", "no", "maybe"];
+        for (index, label) in labels.iter().enumerate() {
+            assert_eq!(
+                string_switch(&labels, label),
+                ((index, 0, index + 1), SwitchStrategy::Sequential),
+                "{label}"
+            );
+        }
+        assert_eq!(string_dispatch(&labels, "never"), (labels.len(), 0, 3));
+        assert_eq!(string_dispatch(&[], "never"), (0, 0, 0));
+
+        let (_, strategy) = string_switch(&["a", "b", "c", "d"], "a");
+        assert_ne!(strategy, SwitchStrategy::Sequential);
+    }
+}
@@ -0,0 +1,212 @@
+//! Self tail call elimination.
+//!
+//! A function that returns the result of calling itself does not need a new
+//! frame for the call: its own frame is dead once the arguments are on the
+//! stack. [`eliminate_tail_calls`] replaces such calls with stores of the
+//! arguments into the parameter slots and a jump back to the start of the
+//! function, so recursive tree walks and state machines run in constant
+//! stack space:
+//!
+//! ```text
+//! GET_LOCAL 0; PUSH_ONE; SUB; CALL self 1; RETURN
+//! // becomes
+//! GET_LOCAL 0; PUSH_ONE; SUB; SET_LOCAL 0; POP; LOOP start; RETURN
+//! ```
+//!
+//! Calls inside a try block are left alone, as the handler must be popped
+//! before the function returns.
+
+use angelscript_core::TypeHash;
+
+use super::{BytecodeChunk, BytecodeRewriter, Constant, ConstantPool, InstrId, Instruction};
+use super::{JumpTarget, OpCode, RewriteError};
+
+/// Turn self tail calls of `function`, which takes `params` parameters,
+/// into jumps.
+///
+/// # Errors
+///
+/// Returns an error if the chunk cannot be decoded or re-encoded.
+pub fn eliminate_tail_calls(
+    chunk: &BytecodeChunk,
+    pool: &ConstantPool,
+    function: TypeHash,
+    params: u16,
+) -> Result<BytecodeChunk, RewriteError> {
+    let mut rewriter = BytecodeRewriter::new(chunk)?;
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    let Some(&start) = ids.first() else {
+        return Ok(chunk.clone());
+    };
+    if ids.iter().any(|&id| op(&rewriter, id) == OpCode::TryBegin) {
+        return Ok(chunk.clone());
+    }
+
+    let tail_calls: Vec<InstrId> = ids
+        .windows(2)
+        .filter(|pair| {
+            is_self_call(&rewriter, pair[0], pool, function, params)
+                && matches!(op(&rewriter, pair[1]), OpCode::Return | OpCode::ReturnVoid)
+        })
+        .map(|pair| pair[0])
+        .collect();
+
+    for call in tail_calls {
+        let line = rewriter.get(call).map_or(0, Instruction::line);
+
+        // Arguments are on the stack in order, so the last is stored first
+        let mut rebind = Vec::with_capacity(usize::from(params) * 2 + 1);
+        for slot in (0..params).rev() {
+            rebind.push(store_local(slot, line));
+            rebind.push(Instruction::simple(OpCode::Pop, line));
+        }
+        rebind.push(Instruction::jump(
+            OpCode::Loop,
+            JumpTarget::Instr(start),
+            line,
+        )?);
+
+        // Jumps to the call land on the first store
+        let mut rebind = rebind.into_iter();
+        if let Some(first) = rebind.next() {
+            rewriter.replace(call, first);
+        }
+        rewriter.insert_after(call, rebind);
+    }
+
+    rewriter.finish()
+}
+
+fn is_self_call(
+    rewriter: &BytecodeRewriter,
+    id: InstrId,
+    pool: &ConstantPool,
+    function: TypeHash,
+    params: u16,
+) -> bool {
+    let Some(instruction) = rewriter.get(id) else {
+        return false;
+    };
+    let (OpCode::Call, &[hi, lo, args]) = (instruction.op(), instruction.operands()) else {
+        return false;
+    };
+    u16::from(args) == params
+        && pool.get(u32::from(u16::from_be_bytes([hi, lo]))) == Some(&Constant::TypeHash(function))
+}
+
+fn store_local(slot: u16, line: u32) -> Instruction {
+    let result = match u8::try_from(slot) {
+        Ok(byte) => Instruction::with_operands(OpCode::SetLocal, &[byte], line),
+        Err(_) => Instruction::with_operands(OpCode::SetLocalWide, &slot.to_be_bytes(), line),
+    };
+    result.expect("local stores have fixed operand sizes")
+}
+
+fn op(rewriter: &BytecodeRewriter, id: InstrId) -> OpCode {
+    rewriter
+        .get(id)
+        .map_or(OpCode::Pop, |instruction| instruction.op())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(chunk: &mut BytecodeChunk, pool: &mut ConstantPool, target: TypeHash, args: u8) {
+        let index = pool.add_type_hash(target);
+        chunk.write_op(OpCode::Call, 1);
+        chunk.write_u16(index as u16, 1);
+        chunk.write_byte(args, 1);
+    }
+
+    /// `int sum(int n, int acc) { if (n == 0) return acc; return sum(n - 1, acc + n); }`
+    fn sum(pool: &mut ConstantPool, hash: TypeHash) -> BytecodeChunk {
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::PushZero, 1);
+        chunk.write_op(OpCode::Eq, 1);
+        let recurse = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.write_op(OpCode::GetLocal, 2);
+        chunk.write_byte(1, 2);
+        chunk.write_op(OpCode::Return, 2);
+        chunk.patch_jump(recurse);
+        chunk.write_op(OpCode::GetLocal, 3);
+        chunk.write_byte(0, 3);
+        chunk.write_op(OpCode::PushOne, 3);
+        chunk.write_op(OpCode::Sub, 3);
+        chunk.write_op(OpCode::GetLocal, 3);
+        chunk.write_byte(1, 3);
+        chunk.write_op(OpCode::GetLocal, 3);
+        chunk.write_byte(0, 3);
+        chunk.write_op(OpCode::Add, 3);
+        call(&mut chunk, pool, hash, 2);
+        chunk.write_op(OpCode::Return, 3);
+        chunk
+    }
+
+    #[test]
+    fn self_tail_call_becomes_loop() {
+        let hash = TypeHash::from_name("sum");
+        let mut pool = ConstantPool::new();
+        let chunk = sum(&mut pool, hash);
+
+        let result = eliminate_tail_calls(&chunk, &pool, hash, 2).unwrap();
+        result.assert_contains_opcodes(&[
+            OpCode::Add,
+            OpCode::SetLocal,
+            OpCode::Pop,
+            OpCode::SetLocal,
+            OpCode::Pop,
+            OpCode::Loop,
+            OpCode::Return,
+        ]);
+        assert!(!result.opcodes().contains(&OpCode::Call));
+
+        // The last argument goes to the last parameter, then back to the start
+        let disasm = result.disassemble(&pool);
+        let stores: Vec<&str> = disasm
+            .lines()
+            .filter(|line| line.contains("SET_LOCAL") || line.contains("LOOP"))
+            .collect();
+        assert!(stores[0].trim_end().ends_with('1'), "{disasm}");
+        assert!(stores[1].trim_end().ends_with('0'), "{disasm}");
+        assert!(stores[2].contains("-> 0000"), "{disasm}");
+    }
+
+    #[test]
+    fn other_calls_are_kept() {
+        let hash = TypeHash::from_name("sum");
+        let mut pool = ConstantPool::new();
+
+        // Calling another function
+        let chunk = sum(&mut pool, TypeHash::from_name("other"));
+        let result = eliminate_tail_calls(&chunk, &pool, hash, 2).unwrap();
+        assert_eq!(result.code(), chunk.code());
+
+        // Using the result before returning
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::PushOne, 1);
+        call(&mut chunk, &mut pool, hash, 1);
+        chunk.write_op(OpCode::PushOne, 1);
+        chunk.write_op(OpCode::Add, 1);
+        chunk.write_op(OpCode::Return, 1);
+        let result = eliminate_tail_calls(&chunk, &pool, hash, 1).unwrap();
+        assert_eq!(result.code(), chunk.code());
+    }
+
+    #[test]
+    fn calls_in_try_blocks_are_kept() {
+        let hash = TypeHash::from_name("f");
+        let mut pool = ConstantPool::new();
+        let mut chunk = BytecodeChunk::new();
+        let handler = chunk.emit_jump(OpCode::TryBegin, 1);
+        call(&mut chunk, &mut pool, hash, 0);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+        chunk.patch_jump(handler);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let result = eliminate_tail_calls(&chunk, &pool, hash, 0).unwrap();
+        assert_eq!(result.code(), chunk.code());
+    }
+}
@@ -0,0 +1,308 @@
+//! Reference casts.
+//!
+//! `cast<T>(handle)` converts a handle to another class or interface. Casts
+//! the compiler can prove, to a base class or an implemented interface,
+//! need no check. Otherwise the object's class may provide the cast with an
+//! `opCast` or `opImplCast` method returning a `T@`, and failing that the
+//! cast is checked at runtime by `REF_CAST`, which gives null if the object
+//! is not a `T`:
+//!
+//! ```text
+//! Derived@ d = cast<Derived>(base);  // GET_LOCAL base; REF_CAST Derived
+//! ```
+//!
+//! Implicit conversions of handles, as in `Base@ b = wrapper;`, only use
+//! the proven casts and `opImplCast`.
+//!
+//! Compilation checks explicit casts between registered types; casts from
+//! or to classes declared by the script are not checked, as those are not
+//! in the registry until the module is built.
+
+use angelscript_core::{
+    ClassEntry, CompilationError, DataType, Operator, Span, TypeHash, primitives,
+};
+use angelscript_registry::SymbolRegistry;
+
+use crate::bytecode::{BytecodeChunk, ConstantPool, OpCode};
+
+/// How a handle is cast to a class or interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefCast {
+    /// The handle already refers to the type, or to an interface deriving
+    /// from it.
+    Identity,
+    /// To a base class (`DERIVED_TO_BASE`).
+    ToBase,
+    /// To an interface the class implements (`CLASS_TO_INTERFACE`).
+    ToInterface,
+    /// Through an `opCast` or `opImplCast` method of the object's class.
+    Operator(TypeHash),
+    /// Checked at runtime (`REF_CAST`), giving null on failure.
+    Checked,
+}
+
+impl RefCast {
+    /// Find how to cast a handle of type `from` to `to`.
+    ///
+    /// `implicit` casts only succeed if they cannot fail: to a base class,
+    /// an implemented interface or through `opImplCast`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either type is not a class or interface, if an
+    /// implicit cast would need a runtime check, or if several cast
+    /// operators return the type.
+    pub fn resolve(
+        registry: &SymbolRegistry,
+        from: &DataType,
+        to: TypeHash,
+        implicit: bool,
+        span: Span,
+    ) -> Result<Self, CompilationError> {
+        let invalid = || CompilationError::InvalidCast {
+            from: type_name(registry, from.type_hash),
+            to: format!("{}@", type_name(registry, to)),
+            span,
+        };
+        let is_reference = |hash: TypeHash| {
+            registry
+                .get(hash)
+                .is_some_and(|entry| entry.is_class() || entry.is_interface())
+        };
+        if !is_reference(to) {
+            return Err(invalid());
+        }
+        if from.type_hash == to || from.type_hash == primitives::NULL {
+            return Ok(Self::Identity);
+        }
+        if !is_reference(from.type_hash) {
+            return Err(invalid());
+        }
+
+        if let Some(class) = registry.get(from.type_hash).and_then(|e| e.as_class()) {
+            if registry
+                .base_class_chain(class.type_hash)
+                .iter()
+                .any(|base| base.type_hash == to)
+            {
+                return Ok(Self::ToBase);
+            }
+            if registry.all_interfaces(class.type_hash).contains(&to) {
+                return Ok(Self::ToInterface);
+            }
+
+            let operators: &[Operator] = if implicit {
+                &[Operator::ImplCast]
+            } else {
+                &[Operator::Cast, Operator::ImplCast]
+            };
+            for &op in operators {
+                match cast_operators(registry, class, op, to)[..] {
+                    [] => continue,
+                    [found] => return Ok(Self::Operator(found)),
+                    ref found => {
+                        return Err(CompilationError::AmbiguousConversion {
+                            from: type_name(registry, from.type_hash),
+                            to: format!("{}@", type_name(registry, to)),
+                            candidates: format!("{} {} methods", found.len(), op),
+                            span,
+                        });
+                    }
+                }
+            }
+        } else if base_interfaces(registry, from.type_hash).contains(&to) {
+            return Ok(Self::Identity);
+        }
+
+        if implicit {
+            return Err(invalid());
+        }
+        Ok(Self::Checked)
+    }
+
+    /// Emit the cast of the handle on top of the stack to `to`.
+    pub fn emit(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        to: TypeHash,
+        line: u32,
+    ) {
+        let (op, hash) = match *self {
+            Self::Identity => return,
+            Self::ToBase => (OpCode::DerivedToBase, to),
+            Self::ToInterface => (OpCode::ClassToInterface, to),
+            Self::Checked => (OpCode::RefCast, to),
+            Self::Operator(method) => (OpCode::CallMethod, method),
+        };
+        let index = constants.add_type_hash(hash);
+        chunk.write_op(op, line);
+        chunk.write_u16(index as u16, line);
+        if op == OpCode::CallMethod {
+            chunk.write_byte(0, line);
+        }
+    }
+}
+
+/// Cast operators of a class or its bases returning a handle to `to`.
+fn cast_operators(
+    registry: &SymbolRegistry,
+    class: &ClassEntry,
+    op: Operator,
+    to: TypeHash,
+) -> Vec<TypeHash> {
+    let name = op.to_string();
+    let mut found = Vec::new();
+    for class in std::iter::once(class).chain(registry.base_class_chain(class.type_hash)) {
+        let registered = class
+            .behaviors
+            .conversions()
+            .iter()
+            .filter(|entry| entry.op == op && entry.target_type == to)
+            .map(|entry| entry.func_hash);
+        let declared = class.find_methods(&name).iter().copied().filter(|&hash| {
+            registry
+                .get_function(hash)
+                .is_some_and(|f| f.def.return_type.type_hash == to && f.def.return_type.is_handle)
+        });
+        for hash in registered.chain(declared) {
+            if !found.contains(&hash) {
+                found.push(hash);
+            }
+        }
+    }
+    found
+}
+
+/// Every interface an interface derives from, directly or not.
+fn base_interfaces(registry: &SymbolRegistry, interface: TypeHash) -> Vec<TypeHash> {
+    let mut found = Vec::new();
+    let mut pending = vec![interface];
+    while let Some(hash) = pending.pop() {
+        let Some(entry) = registry.get(hash).and_then(|e| e.as_interface()) else {
+            continue;
+        };
+        for &base in &entry.base_interfaces {
+            if !found.contains(&base) {
+                found.push(base);
+                pending.push(base);
+            }
+        }
+    }
+    found
+}
+
+fn type_name(registry: &SymbolRegistry, hash: TypeHash) -> String {
+    registry.get(hash).map_or_else(
+        || hash.to_string(),
+        |entry| entry.qualified_name().to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{
+        ConversionEntry, FunctionDef, FunctionEntry, FunctionTraits, InterfaceEntry, TypeKind,
+        Visibility,
+    };
+
+    fn hash(name: &str) -> TypeHash {
+        TypeHash::from_name(name)
+    }
+
+    fn handle(name: &str) -> DataType {
+        DataType::with_handle(hash(name), false)
+    }
+
+    /// `Derived : Base, IShape`; `IShape : INamed`; `Wrapper` with an
+    /// `opCast` to `Derived@` and an `opImplCast` to `Base@`.
+    fn registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+        let named = InterfaceEntry::ffi("INamed");
+        let shape = InterfaceEntry::ffi("IShape").with_base(named.type_hash);
+        registry.register_type(named.into()).unwrap();
+        registry.register_type(shape.into()).unwrap();
+        let base = ClassEntry::ffi("Base", TypeKind::reference());
+        let derived = ClassEntry::ffi("Derived", TypeKind::reference())
+            .with_base(base.type_hash)
+            .with_interface(hash("IShape"));
+        registry.register_type(base.into()).unwrap();
+        registry.register_type(derived.into()).unwrap();
+
+        let mut wrapper = ClassEntry::ffi("Wrapper", TypeKind::reference())
+            .with_method("opCast", hash("Wrapper::opCast"));
+        wrapper.behaviors.add_conversion(ConversionEntry {
+            op: Operator::ImplCast,
+            target_type: hash("Base"),
+            func_hash: hash("Wrapper::opImplCast"),
+        });
+        let def = FunctionDef::new(
+            hash("Wrapper::opCast"),
+            "opCast".to_string(),
+            vec![],
+            vec![],
+            handle("Derived"),
+            Some(wrapper.type_hash),
+            FunctionTraits::default(),
+            true,
+            Visibility::Public,
+        );
+        registry.register_function(FunctionEntry::ffi(def)).unwrap();
+        registry.register_type(wrapper.into()).unwrap();
+        registry
+    }
+
+    #[test]
+    fn resolves_casts() {
+        let registry = registry();
+        let span = Span::new(1, 1, 1);
+        let resolve = |from: &str, to: &str, implicit| {
+            RefCast::resolve(&registry, &handle(from), hash(to), implicit, span)
+        };
+
+        assert_eq!(resolve("Derived", "Derived", true), Ok(RefCast::Identity));
+        assert_eq!(resolve("Derived", "Base", true), Ok(RefCast::ToBase));
+        assert_eq!(resolve("Derived", "INamed", true), Ok(RefCast::ToInterface));
+        assert_eq!(resolve("IShape", "INamed", true), Ok(RefCast::Identity));
+        assert_eq!(resolve("Base", "Derived", false), Ok(RefCast::Checked));
+        assert_eq!(resolve("INamed", "Derived", false), Ok(RefCast::Checked));
+        assert_eq!(
+            resolve("Wrapper", "Derived", false),
+            Ok(RefCast::Operator(hash("Wrapper::opCast")))
+        );
+        assert_eq!(
+            resolve("Wrapper", "Base", true),
+            Ok(RefCast::Operator(hash("Wrapper::opImplCast")))
+        );
+
+        // Downcasts and opCast are explicit only
+        assert_eq!(
+            resolve("Base", "Derived", true).unwrap_err().to_string(),
+            "at 1:1: cannot cast 'Base' to 'Derived@'"
+        );
+        assert!(resolve("Wrapper", "Derived", true).is_err());
+        let int = DataType::simple(primitives::INT32);
+        assert!(RefCast::resolve(&registry, &int, hash("Base"), false, span).is_err());
+    }
+
+    #[test]
+    fn emits_checked_cast() {
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        RefCast::Identity.emit(&mut chunk, &mut constants, hash("Base"), 1);
+        RefCast::Checked.emit(&mut chunk, &mut constants, hash("Derived"), 1);
+        RefCast::Operator(hash("Wrapper::opCast")).emit(
+            &mut chunk,
+            &mut constants,
+            hash("Derived"),
+            1,
+        );
+        chunk.assert_opcodes(&[OpCode::RefCast, OpCode::CallMethod]);
+        let disasm = chunk.disassemble(&constants);
+        assert!(
+            disasm.lines().next().unwrap().contains("REF_CAST"),
+            "{disasm}"
+        );
+    }
+}
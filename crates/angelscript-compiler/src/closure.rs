@@ -0,0 +1,687 @@
+//! Lambda captures.
+//!
+//! A lambda may use the local variables and parameters of the functions it
+//! is written in. Each one it uses is captured when the lambda is created,
+//! so the lambda can run after the function returned:
+//!
+//! ```angelscript
+//! funcdef void Callback();
+//!
+//! Callback@ counter(Player@ target, int step) {
+//!     int count = 0;
+//!     return function() { count += step; target.score = count; };
+//! }
+//! ```
+//!
+//! Value types (`count`, `step`) are captured by copy: the lambda works on
+//! its own copy, and changes are not seen by the function. Reference types
+//! (`target`) are captured by handle, keeping the object alive for as long
+//! as the lambda. Locals declared `auto` are captured by copy unless declared
+//! `auto@`.
+//!
+//! A capture list names the captured variables explicitly, and `@` asks for
+//! a handle. With a list, using any other variable of an enclosing function
+//! is an error:
+//!
+//! ```angelscript
+//! function[count, @target]() { target.score = count; }
+//! ```
+//!
+//! Parameters passed by `&out` or `&inout` reference live in the caller,
+//! so their lifetime cannot be extended and they cannot be captured.
+//!
+//! Functions declared inside a function body capture the same way. They are
+//! visible to the rest of the enclosing block, including lambdas and later
+//! local functions, which capture them by handle:
+//!
+//! ```angelscript
+//! int total(array<int>@ values, int bonus) {
+//!     int score(int v) { return v * 2 + bonus; }
+//!     int sum = 0;
+//!     for (uint i = 0; i < values.length(); i++) sum += score(values[i]);
+//!     return sum;
+//! }
+//! ```
+//!
+//! The captures of every lambda and local function are recorded in a
+//! [`CompiledClosure`]; the code creating it pushes the captured values in
+//! capture order and executes `NEW_CLOSURE` (see [`emit_new_closure`]), and
+//! the VM allocates a function object holding them. A local function is
+//! created where it is declared.
+
+use angelscript_core::{CompilationError, Span, TypeHash};
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{
+    ClassDecl, ForStmt, ForeachStmt, FunctionDecl, GlobalVarDecl, IdentExpr, Item, LambdaExpr,
+    NamespaceDecl, PropertyAccessor, RefKind, Script, Stmt, TypeBase, TypeExpr, VarDeclStmt,
+    VirtualPropertyDecl,
+};
+use angelscript_registry::SymbolRegistry;
+use rustc_hash::FxHashMap;
+
+use crate::CompiledClosure;
+use crate::bytecode::{BytecodeChunk, OpCode};
+use crate::interfaces::{qualify, resolve};
+
+/// How a lambda holds a captured variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureMode {
+    /// A copy of the value.
+    Copy,
+    /// A handle to the object.
+    Handle,
+}
+
+/// A variable captured by a lambda.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+    /// Name of the variable.
+    pub name: String,
+    /// How the lambda holds it.
+    pub mode: CaptureMode,
+}
+
+/// Find the captures of every lambda in a script.
+///
+/// Reports captures of `&out` and `&inout` parameters, handle captures of
+/// value types, capture list entries naming no variable, and variables used
+/// by a lambda with a capture list that does not name them.
+pub fn collect_closures(
+    script: &Script<'_>,
+    registry: &SymbolRegistry,
+) -> (Vec<CompiledClosure>, Vec<CompilationError>) {
+    let mut script_types = FxHashMap::default();
+    script_type_kinds(script.items(), "", &mut script_types);
+    let mut pass = ClosurePass {
+        registry,
+        script_types: &script_types,
+        namespace: Vec::new(),
+        function: String::new(),
+        scopes: Vec::new(),
+        lambdas: Vec::new(),
+        property_type: None,
+        closures: Vec::new(),
+        errors: Vec::new(),
+    };
+    pass.visit_script(script);
+    (pass.closures, pass.errors)
+}
+
+/// Emit the creation of closure `index` from its captured values on top of
+/// the stack.
+pub fn emit_new_closure(index: usize, chunk: &mut BytecodeChunk, line: u32) {
+    chunk.write_op(OpCode::NewClosure, line);
+    chunk.write_u16(index as u16, line);
+}
+
+/// How a variable is stored, deciding how it can be captured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Storage {
+    Value,
+    Handle,
+    /// A parameter referring to a variable of the caller.
+    Reference,
+}
+
+struct Local {
+    name: String,
+    storage: Storage,
+    /// Number of lambdas enclosing the declaration.
+    depth: usize,
+}
+
+struct OpenLambda {
+    closure: usize,
+    /// Names of the capture list, if the lambda has one.
+    list: Option<Vec<String>>,
+    /// Variables already captured or reported.
+    seen: Vec<String>,
+}
+
+struct ClosurePass<'a> {
+    registry: &'a SymbolRegistry,
+    /// Kinds of the types declared by the script, by qualified name.
+    script_types: &'a FxHashMap<String, Storage>,
+    namespace: Vec<String>,
+    function: String,
+    scopes: Vec<Vec<Local>>,
+    lambdas: Vec<OpenLambda>,
+    /// Type of the virtual property whose accessors are being visited.
+    property_type: Option<Storage>,
+    closures: Vec<CompiledClosure>,
+    errors: Vec<CompilationError>,
+}
+
+impl ClosurePass<'_> {
+    fn storage(&self, ty: &TypeExpr<'_>, ref_kind: RefKind) -> Storage {
+        type_storage(
+            self.registry,
+            self.script_types,
+            &self.namespace.join("::"),
+            ty,
+            ref_kind,
+        )
+    }
+
+    fn declare(&mut self, name: &str, storage: Storage) {
+        let depth = self.lambdas.len();
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(Local {
+                name: name.to_string(),
+                storage,
+                depth,
+            });
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Local> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|local| local.name == name)
+    }
+
+    /// Capture a variable by the lambda at `index` of the open lambdas.
+    fn capture(&mut self, index: usize, name: &str, storage: Storage, span: Span) {
+        let lambda = &mut self.lambdas[index];
+        if lambda.seen.iter().any(|seen| seen == name) {
+            return;
+        }
+        lambda.seen.push(name.to_string());
+
+        let invalid = |reason: &str| CompilationError::InvalidCapture {
+            name: name.to_string(),
+            reason: reason.to_string(),
+            span,
+        };
+        if lambda
+            .list
+            .as_ref()
+            .is_some_and(|list| !list.iter().any(|n| n == name))
+        {
+            self.errors.push(invalid("it is not in the capture list"));
+            return;
+        }
+        match capture_mode(storage, false) {
+            Ok(mode) => self.closures[lambda.closure].captures.push(Capture {
+                name: name.to_string(),
+                mode,
+            }),
+            Err(reason) => self.errors.push(invalid(reason)),
+        }
+    }
+
+    /// Record a function declared in a function body as a closure, and
+    /// declare it for the rest of the block.
+    fn visit_local_function(&mut self, func: &FunctionDecl<'_>) {
+        let name = func.name.name;
+        if self
+            .scopes
+            .last()
+            .is_some_and(|scope| scope.iter().any(|local| local.name == name))
+        {
+            self.errors.push(CompilationError::DuplicateDefinition {
+                name: name.to_string(),
+                span: func.name.span,
+            });
+        }
+
+        let closure = self.closures.len();
+        self.closures.push(CompiledClosure {
+            function: self.function.clone(),
+            name: Some(format!("{}${}", self.function, name)),
+            captures: Vec::new(),
+            span: func.span,
+        });
+        self.lambdas.push(OpenLambda {
+            closure,
+            list: None,
+            seen: Vec::new(),
+        });
+        let depth = self.lambdas.len();
+        let params = func
+            .params
+            .iter()
+            .filter_map(|param| {
+                Some(Local {
+                    name: param.name?.name.to_string(),
+                    storage: self.storage(&param.ty.ty, param.ty.ref_kind),
+                    depth,
+                })
+            })
+            .collect();
+        self.with_scope(params, |pass| {
+            for param in func.params {
+                if let Some(default) = param.default {
+                    pass.visit_expr(default);
+                }
+            }
+            if let Some(body) = &func.body {
+                pass.visit_block(body);
+            }
+        });
+        self.lambdas.pop();
+        // Declared after the body, so recursive calls are not captures
+        self.declare(name, Storage::Handle);
+    }
+
+    fn with_scope(&mut self, locals: Vec<Local>, f: impl FnOnce(&mut Self)) {
+        self.scopes.push(locals);
+        f(self);
+        self.scopes.pop();
+    }
+}
+
+fn capture_mode(storage: Storage, by_handle: bool) -> Result<CaptureMode, &'static str> {
+    match storage {
+        Storage::Reference => Err("references to the caller's variables cannot outlive the call"),
+        Storage::Value if by_handle => Err("values cannot be captured by handle"),
+        Storage::Value => Ok(CaptureMode::Copy),
+        Storage::Handle => Ok(CaptureMode::Handle),
+    }
+}
+
+/// How a value of type `ty`, declared in `namespace` and passed as
+/// `ref_kind`, is stored.
+pub(crate) fn type_storage(
+    registry: &SymbolRegistry,
+    script_types: &FxHashMap<String, Storage>,
+    namespace: &str,
+    ty: &TypeExpr<'_>,
+    ref_kind: RefKind,
+) -> Storage {
+    if matches!(ref_kind, RefKind::Ref | RefKind::RefOut | RefKind::RefInOut) {
+        return Storage::Reference;
+    }
+    if ty.has_handle() {
+        return Storage::Handle;
+    }
+    let TypeBase::Named(ident) = ty.base else {
+        return Storage::Value;
+    };
+    let name = match ty.scope {
+        Some(scope) if !scope.is_empty() => format!("{}::{}", scope, ident.name),
+        _ => ident.name.to_string(),
+    };
+    if let Some(found) = resolve(namespace, &name, |n| script_types.contains_key(n)) {
+        return script_types[&found];
+    }
+    let registered = resolve(namespace, &name, |n| {
+        registry.get(TypeHash::from_name(n)).is_some()
+    })
+    .and_then(|found| registry.get(TypeHash::from_name(&found)));
+    match registered {
+        Some(entry) if entry.as_enum().is_some() => Storage::Value,
+        Some(entry) => match entry.as_class() {
+            Some(class) if class.is_value_type() => Storage::Value,
+            _ => Storage::Handle,
+        },
+        None => Storage::Value,
+    }
+}
+
+/// Record the kinds of the types a script declares: classes, interfaces
+/// and funcdefs are used through handles, enums by value.
+pub(crate) fn script_type_kinds(
+    items: &[Item<'_>],
+    namespace: &str,
+    out: &mut FxHashMap<String, Storage>,
+) {
+    for item in items {
+        let (name, storage) = match item {
+            Item::Namespace(ns) => {
+                let path: Vec<&str> = ns.path.iter().map(|s| s.name).collect();
+                script_type_kinds(ns.items, &qualify(namespace, &path.join("::")), out);
+                continue;
+            }
+            Item::Class(class) => (class.name.name, Storage::Handle),
+            Item::Interface(interface) => (interface.name.name, Storage::Handle),
+            Item::Funcdef(funcdef) => (funcdef.name.name, Storage::Handle),
+            Item::Enum(decl) => (decl.name.name, Storage::Value),
+            _ => continue,
+        };
+        out.insert(qualify(namespace, name), storage);
+    }
+}
+
+impl<'ast> Visitor<'ast> for ClosurePass<'_> {
+    fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+        let depth = self.namespace.len();
+        self.namespace
+            .extend(namespace.path.iter().map(|s| s.name.to_string()));
+        visitor::walk_namespace_decl(self, namespace);
+        self.namespace.truncate(depth);
+    }
+
+    fn visit_class_decl(&mut self, class: &ClassDecl<'ast>) {
+        self.namespace.push(class.name.name.to_string());
+        visitor::walk_class_decl(self, class);
+        self.namespace.pop();
+    }
+
+    fn visit_function_decl(&mut self, func: &FunctionDecl<'ast>) {
+        self.function = qualify(&self.namespace.join("::"), func.name.name);
+        let params = func
+            .params
+            .iter()
+            .filter_map(|param| {
+                Some(Local {
+                    name: param.name?.name.to_string(),
+                    storage: self.storage(&param.ty.ty, param.ty.ref_kind),
+                    depth: 0,
+                })
+            })
+            .collect();
+        self.with_scope(params, |pass| visitor::walk_function_decl(pass, func));
+    }
+
+    fn visit_virtual_property_decl(&mut self, prop: &VirtualPropertyDecl<'ast>) {
+        self.property_type = Some(self.storage(&prop.ty.ty, RefKind::None));
+        visitor::walk_virtual_property_decl(self, prop);
+        self.property_type = None;
+    }
+
+    fn visit_property_accessor(&mut self, accessor: &PropertyAccessor<'ast>) {
+        self.function = qualify(&self.namespace.join("::"), "accessor");
+        // Setters receive the new value as `value`
+        let value = self.property_type.map(|storage| Local {
+            name: "value".to_string(),
+            storage,
+            depth: 0,
+        });
+        self.with_scope(value.into_iter().collect(), |pass| {
+            visitor::walk_property_accessor(pass, accessor)
+        });
+    }
+
+    fn visit_global_var_decl(&mut self, var: &GlobalVarDecl<'ast>) {
+        self.function.clear();
+        self.with_scope(Vec::new(), |pass| visitor::walk_global_var_decl(pass, var));
+    }
+
+    fn visit_block(&mut self, block: &angelscript_parser::ast::Block<'ast>) {
+        self.with_scope(Vec::new(), |pass| visitor::walk_block(pass, block));
+    }
+
+    fn visit_for_stmt(&mut self, stmt: &ForStmt<'ast>) {
+        self.with_scope(Vec::new(), |pass| visitor::walk_for_stmt(pass, stmt));
+    }
+
+    fn visit_foreach_stmt(&mut self, stmt: &ForeachStmt<'ast>) {
+        // The iterated expression cannot see the loop variables
+        self.visit_expr(stmt.expr);
+        let vars = stmt
+            .vars
+            .iter()
+            .map(|var| Local {
+                name: var.name.name.to_string(),
+                storage: self.storage(&var.ty, RefKind::None),
+                depth: self.lambdas.len(),
+            })
+            .collect();
+        self.with_scope(vars, |pass| pass.visit_stmt(stmt.body));
+    }
+
+    fn visit_stmt(&mut self, stmt: &Stmt<'ast>) {
+        match stmt {
+            Stmt::Function(func) => self.visit_local_function(func),
+            _ => visitor::walk_stmt(self, stmt),
+        }
+    }
+
+    fn visit_var_decl_stmt(&mut self, stmt: &VarDeclStmt<'ast>) {
+        let storage = self.storage(&stmt.ty, RefKind::None);
+        for var in stmt.vars {
+            if let Some(init) = var.init {
+                self.visit_expr(init);
+            }
+            self.declare(var.name.name, storage);
+        }
+    }
+
+    fn visit_ident_expr(&mut self, expr: &IdentExpr<'ast>) {
+        if expr.scope.is_some() {
+            return;
+        }
+        let Some(local) = self.lookup(expr.ident.name) else {
+            return;
+        };
+        let (depth, storage) = (local.depth, local.storage);
+        // Every lambda between the declaration and the use captures it
+        for index in depth..self.lambdas.len() {
+            self.capture(index, expr.ident.name, storage, expr.span);
+        }
+    }
+
+    fn visit_lambda_expr(&mut self, expr: &LambdaExpr<'ast>) {
+        let closure = self.closures.len();
+        self.closures.push(CompiledClosure {
+            function: self.function.clone(),
+            name: None,
+            captures: Vec::new(),
+            span: expr.span,
+        });
+
+        let mut list = None;
+        if let Some(entries) = expr.captures {
+            let mut names = Vec::new();
+            for entry in entries {
+                let name = entry.name.name;
+                names.push(name.to_string());
+                let Some(local) = self.lookup(name) else {
+                    self.errors.push(CompilationError::UnknownVariable {
+                        name: name.to_string(),
+                        span: entry.span,
+                    });
+                    continue;
+                };
+                let (depth, storage) = (local.depth, local.storage);
+                match capture_mode(storage, entry.by_handle) {
+                    Ok(mode) => self.closures[closure].captures.push(Capture {
+                        name: name.to_string(),
+                        mode,
+                    }),
+                    Err(reason) => self.errors.push(CompilationError::InvalidCapture {
+                        name: name.to_string(),
+                        reason: reason.to_string(),
+                        span: entry.span,
+                    }),
+                }
+                // Enclosing lambdas must capture it to pass it on
+                for index in depth..self.lambdas.len() {
+                    self.capture(index, name, storage, entry.span);
+                }
+            }
+            list = Some(names);
+        }
+
+        self.lambdas.push(OpenLambda {
+            closure,
+            seen: list.clone().unwrap_or_default(),
+            list,
+        });
+        let depth = self.lambdas.len();
+        let params = expr
+            .params
+            .iter()
+            .filter_map(|param| {
+                let storage = param
+                    .ty
+                    .as_ref()
+                    .map_or(Storage::Value, |ty| self.storage(&ty.ty, ty.ref_kind));
+                Some(Local {
+                    name: param.name?.name.to_string(),
+                    storage,
+                    depth,
+                })
+            })
+            .collect();
+        self.with_scope(params, |pass| visitor::walk_lambda_expr(pass, expr));
+        self.lambdas.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn closures(source: &str) -> (Vec<CompiledClosure>, Vec<String>) {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let (closures, errors) = collect_closures(&script, &SymbolRegistry::with_primitives());
+        let errors = errors
+            .into_iter()
+            .map(|error| match error {
+                CompilationError::InvalidCapture { name, reason, .. } => {
+                    format!("{}: {}", name, reason)
+                }
+                CompilationError::UnknownVariable { name, .. } => format!("{}: unknown", name),
+                CompilationError::DuplicateDefinition { name, .. } => {
+                    format!("{}: duplicate", name)
+                }
+                other => panic!("unexpected error {other}"),
+            })
+            .collect();
+        (closures, errors)
+    }
+
+    fn captures(closure: &CompiledClosure) -> Vec<(&str, CaptureMode)> {
+        closure
+            .captures
+            .iter()
+            .map(|c| (c.name.as_str(), c.mode))
+            .collect()
+    }
+
+    #[test]
+    fn values_by_copy_and_objects_by_handle() {
+        let source = "
+            class Player { int score; }
+            enum Team { Red, Blue }
+            void setup(Player@ target, int step, Player other) {
+                int count = 0;
+                Team team = Red;
+                auto run = function() {
+                    count += step;
+                    target.score = count;
+                    other.score = team;
+                    int local = 1;
+                    local++;
+                };
+                auto pure = function(int count) { return count; };
+            }
+        ";
+        let (closures, errors) = closures(source);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(closures.len(), 2);
+        assert_eq!(closures[0].function, "setup");
+        assert_eq!(
+            captures(&closures[0]),
+            [
+                ("count", CaptureMode::Copy),
+                ("step", CaptureMode::Copy),
+                ("target", CaptureMode::Handle),
+                ("other", CaptureMode::Handle),
+                ("team", CaptureMode::Copy),
+            ]
+        );
+        assert!(closures[1].captures.is_empty());
+    }
+
+    #[test]
+    fn nested_lambdas_pass_captures_on() {
+        let source = "
+            namespace game {
+                void run(int a) {
+                    int b = 2;
+                    auto outer = function() {
+                        int c = 3;
+                        auto inner = function() { return a + b + c; };
+                    };
+                }
+            }
+        ";
+        let (closures, errors) = closures(source);
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(closures[0].function, "game::run");
+        assert_eq!(
+            captures(&closures[0]),
+            [("a", CaptureMode::Copy), ("b", CaptureMode::Copy)]
+        );
+        assert_eq!(
+            captures(&closures[1]),
+            [
+                ("a", CaptureMode::Copy),
+                ("b", CaptureMode::Copy),
+                ("c", CaptureMode::Copy)
+            ]
+        );
+    }
+
+    #[test]
+    fn capture_lists_and_invalid_captures() {
+        let source = "
+            class Player { }
+            void run(Player@ target, int &out result, int &inout total, int count) {
+                auto listed = function[count, @target]() { return count; };
+                auto missing = function[count]() { return count + total; };
+                auto bad = function[@count, ghost]() { };
+                auto escape = function() { result = 1; result = 2; };
+            }
+        ";
+        let (closures, errors) = closures(source);
+        assert_eq!(
+            captures(&closures[0]),
+            [
+                ("count", CaptureMode::Copy),
+                ("target", CaptureMode::Handle)
+            ]
+        );
+        assert_eq!(
+            errors,
+            [
+                "total: it is not in the capture list",
+                "count: values cannot be captured by handle",
+                "ghost: unknown",
+                "result: references to the caller's variables cannot outlive the call",
+            ]
+        );
+    }
+
+    #[test]
+    fn local_functions_capture_and_are_captured() {
+        let source = "
+            class Player { }
+            void run(Player@ target, int bonus) {
+                int score(int v) { return score(v - 1) + bonus; }
+                void award() { target.add(score(1)); }
+                auto later = function() { award(); };
+                int award() { return 0; }
+            }
+        ";
+        let (closures, errors) = closures(source);
+        assert_eq!(errors, ["award: duplicate"]);
+        assert_eq!(closures[0].name.as_deref(), Some("run$score"));
+        assert_eq!(captures(&closures[0]), [("bonus", CaptureMode::Copy)]);
+        assert_eq!(
+            captures(&closures[1]),
+            [
+                ("target", CaptureMode::Handle),
+                ("score", CaptureMode::Handle)
+            ]
+        );
+        assert_eq!(closures[2].name, None);
+        assert_eq!(captures(&closures[2]), [("award", CaptureMode::Handle)]);
+    }
+
+    #[test]
+    fn emits_new_closure() {
+        let mut chunk = BytecodeChunk::new();
+        emit_new_closure(3, &mut chunk, 1);
+        chunk.assert_opcodes(&[OpCode::NewClosure]);
+        assert_eq!(chunk.read_u16(1), Some(3));
+    }
+}
@@ -0,0 +1,267 @@
+//! String concatenation chains.
+//!
+//! `"hp: " + hp + "/" + max` parses as `(("hp: " + hp) + "/") + max`.
+//! Compiled operator by operator, it creates a temporary string for every
+//! `+` and one more for every number converted to text. A [`Concat`] instead
+//! compiles the whole chain to a single `FORMAT`, folding the literals into
+//! a printf-style template so the VM sizes the result once:
+//!
+//! ```text
+//! "hp: " + hp + "/" + max   // GET_LOCAL hp; I32_TO_I64; GET_LOCAL max; I32_TO_I64; FORMAT "hp: %d/%d" 2
+//! ```
+//!
+//! Arguments are widened to the 64-bit type of their placeholder first.
+//! Chains are only lowered when the first `+` already concatenates strings
+//! (`1 + 2 + "x"` adds the numbers first) and every operand is a string or
+//! primitive; operands of other types go through their `opAdd` overloads.
+
+use angelscript_core::{DataType, TypeHash, primitives};
+use angelscript_parser::ast::{BinaryOp, Expr, LiteralKind};
+
+use crate::bytecode::{BytecodeChunk, ConstantPool, OpCode};
+
+/// Most arguments a single `FORMAT` takes.
+pub const MAX_FORMAT_ARGS: usize = u8::MAX as usize;
+
+/// Split a chain of `+` into its operands, left to right.
+///
+/// Only the left side of each `+` continues the chain, following the
+/// grammar: in `a + (b + c)` the parenthesized sum is one operand.
+pub fn concat_operands<'a, 'ast>(expr: &'a Expr<'ast>) -> Vec<&'a Expr<'ast>> {
+    let mut operands = Vec::new();
+    let mut current = expr;
+    while let Expr::Binary(binary) = current
+        && binary.op == BinaryOp::Add
+    {
+        operands.push(binary.right);
+        current = binary.left;
+    }
+    operands.push(current);
+    operands.reverse();
+    operands
+}
+
+/// An operand of a concatenation chain.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConcatOperand<'a> {
+    /// A string literal, folded into the template.
+    Text(&'a [u8]),
+    /// A value computed at runtime.
+    Value(DataType),
+}
+
+impl<'a> ConcatOperand<'a> {
+    /// The operand for an expression of type `ty`, keeping string literals
+    /// as text.
+    pub fn of(expr: &'a Expr<'_>, ty: DataType) -> Self {
+        match expr {
+            Expr::Literal(literal) => match &literal.kind {
+                LiteralKind::String(bytes) => Self::Text(bytes),
+                _ => Self::Value(ty),
+            },
+            _ => Self::Value(ty),
+        }
+    }
+}
+
+/// How an argument is formatted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatArg {
+    /// `%s`, a string.
+    String,
+    /// `%d`, a signed integer widened to int64.
+    Int(Option<OpCode>),
+    /// `%u`, an unsigned integer widened to uint64.
+    UInt(Option<OpCode>),
+    /// `%f`, a floating-point number widened to double.
+    Float(Option<OpCode>),
+    /// `%b`, a bool.
+    Bool,
+}
+
+impl FormatArg {
+    /// The argument for a value of type `ty`, if it can be formatted.
+    fn of(ty: TypeHash, string_type: TypeHash) -> Option<Self> {
+        Some(match ty {
+            _ if ty == string_type => Self::String,
+            primitives::INT8 => Self::Int(Some(OpCode::I8toI64)),
+            primitives::INT16 => Self::Int(Some(OpCode::I16toI64)),
+            primitives::INT32 => Self::Int(Some(OpCode::I32toI64)),
+            primitives::INT64 => Self::Int(None),
+            primitives::UINT8 => Self::UInt(Some(OpCode::U8toU64)),
+            primitives::UINT16 => Self::UInt(Some(OpCode::U16toU64)),
+            primitives::UINT32 => Self::UInt(Some(OpCode::U32toU64)),
+            primitives::UINT64 => Self::UInt(None),
+            primitives::FLOAT => Self::Float(Some(OpCode::F32toF64)),
+            primitives::DOUBLE => Self::Float(None),
+            primitives::BOOL => Self::Bool,
+            _ => return None,
+        })
+    }
+
+    fn placeholder(self) -> &'static [u8] {
+        match self {
+            Self::String => b"%s",
+            Self::Int(_) => b"%d",
+            Self::UInt(_) => b"%u",
+            Self::Float(_) => b"%f",
+            Self::Bool => b"%b",
+        }
+    }
+
+    fn widening(self) -> Option<OpCode> {
+        match self {
+            Self::Int(op) | Self::UInt(op) | Self::Float(op) => op,
+            Self::String | Self::Bool => None,
+        }
+    }
+}
+
+/// A concatenation chain lowered to a `FORMAT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Concat {
+    /// The printf-style template.
+    pub template: Vec<u8>,
+    /// The operands passed as arguments, by index in the chain, with how
+    /// each is formatted.
+    pub args: Vec<(usize, FormatArg)>,
+}
+
+impl Concat {
+    /// Lower a chain of at least two operands, given the script's string
+    /// type. Returns `None` if the chain is not a string concatenation of
+    /// strings and primitives, or has more than [`MAX_FORMAT_ARGS`]
+    /// runtime operands.
+    pub fn resolve(operands: &[ConcatOperand<'_>], string_type: TypeHash) -> Option<Self> {
+        let is_string = |operand: &ConcatOperand<'_>| match operand {
+            ConcatOperand::Text(_) => true,
+            ConcatOperand::Value(ty) => ty.type_hash == string_type && !ty.is_handle,
+        };
+        if operands.len() < 2 || !operands[..2].iter().any(is_string) {
+            return None;
+        }
+
+        let mut template = Vec::new();
+        let mut args = Vec::new();
+        for (index, operand) in operands.iter().enumerate() {
+            match operand {
+                ConcatOperand::Text(text) => {
+                    for &byte in *text {
+                        if byte == b'%' {
+                            template.push(b'%');
+                        }
+                        template.push(byte);
+                    }
+                }
+                ConcatOperand::Value(ty) => {
+                    if ty.is_handle {
+                        return None;
+                    }
+                    let arg = FormatArg::of(ty.type_hash, string_type)?;
+                    template.extend_from_slice(arg.placeholder());
+                    args.push((index, arg));
+                }
+            }
+        }
+        (args.len() <= MAX_FORMAT_ARGS).then_some(Self { template, args })
+    }
+
+    /// Emit the chain. `emit_operand` emits the operand with the given index
+    /// in the chain; it is only called for the arguments, in order.
+    pub fn emit(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        line: u32,
+        mut emit_operand: impl FnMut(usize, &mut BytecodeChunk, &mut ConstantPool),
+    ) {
+        for &(index, arg) in &self.args {
+            emit_operand(index, chunk, constants);
+            if let Some(widening) = arg.widening() {
+                chunk.write_op(widening, line);
+            }
+        }
+        let template = constants.add_string(self.template.clone());
+        chunk.write_op(OpCode::Format, line);
+        chunk.write_u16(template as u16, line);
+        chunk.write_byte(self.args.len() as u8, line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn value(ty: TypeHash) -> ConcatOperand<'static> {
+        ConcatOperand::Value(DataType::simple(ty))
+    }
+
+    #[test]
+    fn splits_left_associative_chains() {
+        let arena = Bump::new();
+        let expr = Parser::new("\"a\" + b + (c + d) + e * 2", &arena)
+            .parse_expr(0)
+            .unwrap();
+        let operands = concat_operands(expr);
+        assert_eq!(operands.len(), 4);
+        assert!(matches!(operands[2], Expr::Paren(_)));
+        assert!(matches!(operands[3], Expr::Binary(_)));
+        assert!(matches!(
+            ConcatOperand::of(operands[0], DataType::simple(primitives::STRING)),
+            ConcatOperand::Text(b"a")
+        ));
+    }
+
+    #[test]
+    fn folds_literals_into_the_template() {
+        let operands = [
+            ConcatOperand::Text(b"hp: "),
+            value(primitives::INT32),
+            ConcatOperand::Text(b"/100%"),
+            value(primitives::STRING),
+            value(primitives::DOUBLE),
+        ];
+        let concat = Concat::resolve(&operands, primitives::STRING).unwrap();
+        assert_eq!(concat.template, b"hp: %d/100%%%s%f");
+
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        let mut emitted = Vec::new();
+        concat.emit(&mut chunk, &mut constants, 1, |index, chunk, _| {
+            emitted.push(index);
+            chunk.write_op(OpCode::GetLocal, 1);
+            chunk.write_byte(index as u8, 1);
+        });
+        assert_eq!(emitted, [1, 3, 4]);
+        chunk.assert_opcodes(&[
+            OpCode::GetLocal,
+            OpCode::I32toI64,
+            OpCode::GetLocal,
+            OpCode::GetLocal,
+            OpCode::Format,
+        ]);
+        let disasm = chunk.disassemble(&constants);
+        let format = disasm.lines().find(|line| line.contains("FORMAT")).unwrap();
+        assert!(format.contains("args=3"), "{format}");
+    }
+
+    #[test]
+    fn leaves_other_chains_alone() {
+        let numbers_first = [
+            value(primitives::INT32),
+            value(primitives::INT32),
+            ConcatOperand::Text(b"x"),
+        ];
+        assert_eq!(Concat::resolve(&numbers_first, primitives::STRING), None);
+
+        let object = [ConcatOperand::Text(b"x"), value(TypeHash::from_name("Obj"))];
+        assert_eq!(Concat::resolve(&object, primitives::STRING), None);
+
+        let too_many: Vec<_> = std::iter::once(ConcatOperand::Text(b"x"))
+            .chain(std::iter::repeat_n(value(primitives::BOOL), 256))
+            .collect();
+        assert_eq!(Concat::resolve(&too_many, primitives::STRING), None);
+    }
+}
@@ -0,0 +1,1283 @@
+//! Compile-time evaluation of constant expressions.
+//!
+//! [`ConstEvaluator`] folds expressions built from literals, named constants
+//! and operators into a single [`ConstValue`] before bytecode is emitted:
+//!
+//! - arithmetic, bitwise and comparison operators on numbers (`60 * 60 * 24`)
+//! - logical operators and ternaries, short-circuiting like at runtime
+//! - concatenation and comparison of string literals (`"v" + "1.0"`)
+//! - references to enum values and `const` globals with constant initializers
+//! - the name of an enumerator, `toString(Color::Red)` (see
+//!   [`enum_intrinsics`](crate::enum_intrinsics))
+//! - constants registered by the application
+//!   ([`GlobalPropertyImpl::Constant`]), which every unit of a context sees
+//!
+//! Integer overflow wraps or is an error as the module's [`IntegerOverflow`]
+//! mode says, like it does at runtime.
+//!
+//! The emitter uses it to replace constant operands with a single constant
+//! pool entry and to drop branches whose condition is known
+//! ([`ConstEvaluator::condition`]). The same evaluator computes enum values,
+//! where a constant is required, and default arguments, where it is not.
+//!
+//! Default arguments are evaluated in the scope of the function declaring
+//! them, so `void f(int x = MAX)` inside `namespace game` refers to
+//! `game::MAX` wherever `f` is called from. Constant defaults are recorded
+//! in [`ScriptConstants::defaults`] and pushed by the caller with
+//! [`CompiledDefaults::emit_missing`].
+//!
+//! Integer arithmetic is checked: a constant expression that divides by zero
+//! or overflows `int64` is a compilation error instead of a runtime one.
+//!
+//! Case labels of `switch` statements must be constant and unique; their
+//! values ([`ConstEvaluator::case_values`]) decide how the dispatch is
+//! lowered (see [`emit_switch`](crate::bytecode::emit_switch)). Enumerators
+//! may be named without their enum in case labels.
+//!
+//! A `const` global with a constant initializer is a compile-time constant
+//! wherever one is needed, like a registered constant:
+//!
+//! ```text
+//! const int SLOTS = MAX_PLAYERS * 2;   // MAX_PLAYERS registered by the host
+//! enum Slot { First = SLOTS, Last }    // enum value
+//! void fill(int count = SLOTS) {}      // default argument
+//! case SLOTS:                          // case label
+//! ```
+//!
+//! Array sizes are checked with [`ConstEvaluator::array_size`]. The values
+//! of a module's constant globals are kept in
+//! [`CompiledModule::const_globals`](crate::CompiledModule::const_globals).
+
+use angelscript_core::{CompilationError, ConstantValue, GlobalPropertyImpl, Span};
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{
+    BinaryExpr, BinaryOp, CallExpr, ClassMember, EnumDecl, Expr, FunctionParam, Item, LiteralKind,
+    NamespaceDecl, PrimitiveType, Script, SwitchStmt, TypeBase, TypeExpr, UnaryExpr, UnaryOp,
+};
+use angelscript_registry::SymbolRegistry;
+use rustc_hash::FxHashMap;
+
+use crate::access::candidate_names;
+use crate::bytecode::{BytecodeChunk, Constant, ConstantPool, OpCode};
+use crate::enum_intrinsics;
+use crate::overflow::IntegerOverflow;
+use crate::{CompiledDefaults, CompiledEnum};
+
+/// Value of a constant expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    /// Integer of any width.
+    Int(i64),
+    /// 32-bit float.
+    Float(f32),
+    /// 64-bit float.
+    Double(f64),
+    /// Boolean.
+    Bool(bool),
+    /// Raw string literal bytes.
+    String(Vec<u8>),
+}
+
+impl ConstValue {
+    /// The boolean value, if this is a boolean.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Self::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The integer value, if this is an integer.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Self::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The constant pool entry holding this value.
+    ///
+    /// Booleans are stored as the integers `0` and `1`.
+    pub fn to_constant(&self) -> Constant {
+        match self {
+            Self::Int(value) => Constant::Int(*value),
+            Self::Float(value) => Constant::Float32(*value),
+            Self::Double(value) => Constant::Float64(*value),
+            Self::Bool(value) => Constant::Int(i64::from(*value)),
+            Self::String(bytes) => Constant::StringData(bytes.clone()),
+        }
+    }
+
+    /// Emit the instruction pushing this value, adding it to `constants` if
+    /// it has no dedicated opcode.
+    pub fn emit(&self, chunk: &mut BytecodeChunk, constants: &mut ConstantPool, line: u32) {
+        let op = match self {
+            Self::Bool(true) => OpCode::PushTrue,
+            Self::Bool(false) => OpCode::PushFalse,
+            Self::Int(0) => OpCode::PushZero,
+            Self::Int(1) => OpCode::PushOne,
+            _ => {
+                let index = constants.add(self.to_constant());
+                match u8::try_from(index) {
+                    Ok(byte) => {
+                        chunk.write_op(OpCode::Constant, line);
+                        chunk.write_byte(byte, line);
+                    }
+                    Err(_) => {
+                        chunk.write_op(OpCode::ConstantWide, line);
+                        chunk.write_u16(index as u16, line);
+                    }
+                }
+                return;
+            }
+        };
+        chunk.write_op(op, line);
+    }
+
+    /// Convert the value to a primitive type, as an implicit conversion
+    /// would. Integers are truncated to the width of the target.
+    ///
+    /// Returns `None` if the value cannot be converted to the type.
+    pub fn convert(&self, ty: PrimitiveType) -> Option<ConstValue> {
+        let value = match (self, ty) {
+            (Self::Bool(value), PrimitiveType::Bool) => Self::Bool(*value),
+            (Self::Bool(_) | Self::String(_), _) | (_, PrimitiveType::Bool) => return None,
+            (_, PrimitiveType::Float) => Self::Float(self.to_f64()? as f32),
+            (_, PrimitiveType::Double) => Self::Double(self.to_f64()?),
+            (_, PrimitiveType::Void) => return None,
+            (Self::Int(value), _) => Self::Int(truncate(*value, ty)),
+            (Self::Float(value), _) => Self::Int(truncate(*value as i64, ty)),
+            (Self::Double(value), _) => Self::Int(truncate(*value as i64, ty)),
+        };
+        Some(value)
+    }
+
+    /// The value of a constant registered by the application. Unsigned
+    /// 64-bit values above `i64::MAX` wrap, like the integers of a script.
+    pub fn from_registered(value: &ConstantValue) -> Self {
+        match *value {
+            ConstantValue::Bool(value) => Self::Bool(value),
+            ConstantValue::Int8(value) => Self::Int(i64::from(value)),
+            ConstantValue::Int16(value) => Self::Int(i64::from(value)),
+            ConstantValue::Int32(value) => Self::Int(i64::from(value)),
+            ConstantValue::Int64(value) => Self::Int(value),
+            ConstantValue::Uint8(value) => Self::Int(i64::from(value)),
+            ConstantValue::Uint16(value) => Self::Int(i64::from(value)),
+            ConstantValue::Uint32(value) => Self::Int(i64::from(value)),
+            ConstantValue::Uint64(value) => Self::Int(value as i64),
+            ConstantValue::Float(value) => Self::Float(value),
+            ConstantValue::Double(value) => Self::Double(value),
+        }
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(value) => Some(*value as f64),
+            Self::Float(value) => Some(f64::from(*value)),
+            Self::Double(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Truncate an integer to the width of an integer type.
+fn truncate(value: i64, ty: PrimitiveType) -> i64 {
+    match ty {
+        PrimitiveType::Int8 => i64::from(value as i8),
+        PrimitiveType::Int16 => i64::from(value as i16),
+        PrimitiveType::Int => i64::from(value as i32),
+        PrimitiveType::UInt8 => i64::from(value as u8),
+        PrimitiveType::UInt16 => i64::from(value as u16),
+        PrimitiveType::UInt => i64::from(value as u32),
+        _ => value,
+    }
+}
+
+/// Evaluates constant expressions against a table of named constants.
+///
+/// # Example
+///
+/// ```ignore
+/// let mut eval = ConstEvaluator::new();
+/// eval.define("SECONDS_PER_DAY", ConstValue::Int(86_400));
+/// // `SECONDS_PER_DAY * 7` folds to 604800
+/// assert_eq!(eval.eval(expr)?, Some(ConstValue::Int(604_800)));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConstEvaluator {
+    /// Named constants by qualified name.
+    values: FxHashMap<String, ConstValue>,
+    /// Namespace names are resolved from, innermost last.
+    namespace: Vec<String>,
+    /// Enums whose values are defined.
+    enums: Vec<CompiledEnum>,
+    /// What integer overflow folds to.
+    overflow: IntegerOverflow,
+}
+
+/// Values of the case labels of a switch, in label order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaseValues {
+    /// Integer and enum labels.
+    Int(Vec<i64>),
+    /// String labels.
+    String(Vec<Vec<u8>>),
+}
+
+impl ConstEvaluator {
+    /// Create an evaluator without named constants.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define a named constant by its qualified name (e.g. `Color::Red`).
+    pub fn define(&mut self, name: impl Into<String>, value: ConstValue) {
+        self.values.insert(name.into(), value);
+    }
+
+    /// Fold integer overflow with the semantics of `overflow` (see
+    /// [`overflow`](crate::overflow)).
+    pub fn set_integer_overflow(&mut self, overflow: IntegerOverflow) {
+        self.overflow = overflow;
+    }
+
+    /// Define the constant global properties registered in `registry` by
+    /// their qualified names.
+    pub fn define_registered(&mut self, registry: &SymbolRegistry) {
+        for global in registry.globals() {
+            if let GlobalPropertyImpl::Constant(value) = &global.implementation {
+                self.define(
+                    global.qualified_name.clone(),
+                    ConstValue::from_registered(value),
+                );
+            }
+        }
+    }
+
+    /// Look up a named constant by its qualified name.
+    pub fn get(&self, name: &str) -> Option<&ConstValue> {
+        self.values.get(name)
+    }
+
+    /// Set the namespace unqualified names are resolved from.
+    pub fn set_namespace(&mut self, namespace: Vec<String>) {
+        self.namespace = namespace;
+    }
+
+    /// Evaluate an expression.
+    ///
+    /// Returns `Ok(None)` if the expression is not constant, and an error if
+    /// it is constant but cannot be evaluated (e.g. divides by zero).
+    pub fn eval(&self, expr: &Expr<'_>) -> Result<Option<ConstValue>, CompilationError> {
+        match expr {
+            Expr::Literal(literal) => Ok(match &literal.kind {
+                LiteralKind::Int(value) => Some(ConstValue::Int(*value)),
+                LiteralKind::Float(value) => Some(ConstValue::Float(*value)),
+                LiteralKind::Double(value) => Some(ConstValue::Double(*value)),
+                LiteralKind::Bool(value) => Some(ConstValue::Bool(*value)),
+                LiteralKind::String(bytes) => Some(ConstValue::String(bytes.clone())),
+                LiteralKind::Null => None,
+            }),
+            Expr::Ident(ident) if ident.type_args.is_empty() => {
+                Ok(
+                    candidate_names(&self.namespace, ident.scope.as_ref(), ident.ident.name)
+                        .iter()
+                        .find_map(|name| self.values.get(name))
+                        .cloned(),
+                )
+            }
+            Expr::Paren(paren) => self.eval(paren.expr),
+            Expr::Unary(unary) => self.eval_unary(unary),
+            Expr::Binary(binary) => self.eval_binary(binary),
+            Expr::Ternary(ternary) => match self.condition(ternary.condition)? {
+                Some(true) => self.eval(ternary.then_expr),
+                Some(false) => self.eval(ternary.else_expr),
+                None => Ok(None),
+            },
+            Expr::Call(call) => Ok(self.eval_intrinsic(call)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Fold a call of the `toString` enum intrinsic whose argument names an
+    /// enumerator (see [`enum_intrinsics`](crate::enum_intrinsics)).
+    fn eval_intrinsic(&self, call: &CallExpr<'_>) -> Option<ConstValue> {
+        let Expr::Ident(callee) = call.callee else {
+            return None;
+        };
+        if callee.scope.is_some() || callee.ident.name != enum_intrinsics::TO_STRING {
+            return None;
+        }
+        let [arg] = call.args else {
+            return None;
+        };
+        let Expr::Ident(ident) = arg.value else {
+            return None;
+        };
+        candidate_names(&self.namespace, ident.scope.as_ref(), ident.ident.name)
+            .iter()
+            .find_map(|name| {
+                let (enum_name, _) = name.rsplit_once("::")?;
+                let decl = self.enums.iter().find(|e| e.name == enum_name)?;
+                let value = self.values.get(name)?.as_int()?;
+                Some(ConstValue::String(decl.display(value).into_bytes()))
+            })
+    }
+
+    /// Evaluate a branch condition.
+    ///
+    /// Returns `Ok(None)` if the condition is not a constant boolean, in
+    /// which case both branches must be emitted.
+    pub fn condition(&self, expr: &Expr<'_>) -> Result<Option<bool>, CompilationError> {
+        Ok(self.eval(expr)?.and_then(|value| value.as_bool()))
+    }
+
+    /// Evaluate the size of an array, as in `int[SLOTS]`.
+    ///
+    /// The size must be a constant integer that fits in a `uint`.
+    pub fn array_size(&self, expr: &Expr<'_>) -> Result<u32, CompilationError> {
+        let Some(ConstValue::Int(size)) = self.eval(expr)? else {
+            return Err(CompilationError::NotConstant { span: expr.span() });
+        };
+        u32::try_from(size).map_err(|_| CompilationError::ConstantOverflow {
+            type_name: "uint".to_string(),
+            span: expr.span(),
+        })
+    }
+
+    /// Evaluate the case labels of a switch, skipping `default`.
+    ///
+    /// Labels must be constant, all integers or all strings, and unique. An
+    /// unqualified name that is no constant may name an enumerator of an
+    /// enum visible from the current namespace, as in `case Red:`.
+    pub fn case_values(&self, stmt: &SwitchStmt<'_>) -> Result<CaseValues, CompilationError> {
+        let labels = stmt.cases.iter().flat_map(|case| case.values.iter());
+        let mut ints = Vec::new();
+        let mut strings = Vec::new();
+        for &label in labels {
+            let value = match self.eval(label)? {
+                Some(value) => value,
+                None => self
+                    .enumerator(label)
+                    .ok_or(CompilationError::NotConstant { span: label.span() })?,
+            };
+            let (duplicate, shown) = match value {
+                ConstValue::Int(value) if strings.is_empty() => {
+                    let duplicate = ints.contains(&value);
+                    ints.push(value);
+                    (duplicate, value.to_string())
+                }
+                ConstValue::String(bytes) if ints.is_empty() => {
+                    let shown = format!("\"{}\"", String::from_utf8_lossy(&bytes));
+                    let duplicate = strings.contains(&bytes);
+                    strings.push(bytes);
+                    (duplicate, shown)
+                }
+                _ => {
+                    return Err(CompilationError::TypeMismatch {
+                        message: "case labels must all be integers or all be strings".to_string(),
+                        span: label.span(),
+                    });
+                }
+            };
+            if duplicate {
+                return Err(CompilationError::DuplicateCase {
+                    value: shown,
+                    span: label.span(),
+                });
+            }
+        }
+        Ok(if strings.is_empty() {
+            CaseValues::Int(ints)
+        } else {
+            CaseValues::String(strings)
+        })
+    }
+
+    /// Value of an unqualified enumerator name, if exactly one visible enum
+    /// declares it.
+    fn enumerator(&self, expr: &Expr<'_>) -> Option<ConstValue> {
+        let Expr::Ident(ident) = expr else {
+            return None;
+        };
+        if ident.scope.is_some() {
+            return None;
+        }
+        let namespace = self.namespace.join("::");
+        let mut found = self.enums.iter().filter_map(|decl| {
+            let enum_name = &decl.name;
+            let parent = enum_name.rsplit_once("::").map_or("", |(parent, _)| parent);
+            let visible = parent.is_empty()
+                || namespace == parent
+                || namespace.starts_with(&format!("{parent}::"));
+            visible
+                .then(|| {
+                    self.values
+                        .get(&format!("{}::{}", enum_name, ident.ident.name))
+                })
+                .flatten()
+        });
+        let value = found.next()?;
+        found.next().is_none().then(|| value.clone())
+    }
+
+    /// Evaluate the values of an enum's enumerators, in declaration order.
+    ///
+    /// Enumerators without an initializer take the previous value plus one,
+    /// starting at zero. Initializers may refer to earlier enumerators by
+    /// their unqualified name. The values are also defined as named
+    /// constants, qualified with `enum_name`.
+    pub fn enum_values(
+        &mut self,
+        enum_name: &str,
+        decl: &EnumDecl<'_>,
+    ) -> Result<Vec<(String, i64)>, CompilationError> {
+        let mut scope = self.clone();
+        scope.namespace = enum_name.split("::").map(str::to_string).collect();
+
+        let mut values = Vec::with_capacity(decl.enumerators.len());
+        let mut next = 0i64;
+        for enumerator in decl.enumerators {
+            let value = match enumerator.value {
+                Some(expr) => match scope.eval(expr)? {
+                    Some(ConstValue::Int(value)) => value,
+                    _ => return Err(CompilationError::NotConstant { span: expr.span() }),
+                },
+                None => next,
+            };
+            if i32::try_from(value).is_err() {
+                return Err(CompilationError::ConstantOverflow {
+                    type_name: "int".to_string(),
+                    span: enumerator.span,
+                });
+            }
+
+            let name = format!("{}::{}", enum_name, enumerator.name.name);
+            scope.define(name.clone(), ConstValue::Int(value));
+            self.define(name.clone(), ConstValue::Int(value));
+            values.push((name, value));
+            next = value + 1;
+        }
+        self.enums.push(CompiledEnum {
+            name: enum_name.to_string(),
+            values: values.clone(),
+        });
+        Ok(values)
+    }
+
+    fn eval_unary(&self, unary: &UnaryExpr<'_>) -> Result<Option<ConstValue>, CompilationError> {
+        let Some(operand) = self.eval(unary.operand)? else {
+            return Ok(None);
+        };
+        let value = match (unary.op, operand) {
+            (UnaryOp::Plus, value @ (ConstValue::Int(_) | ConstValue::Float(_))) => value,
+            (UnaryOp::Plus, value @ ConstValue::Double(_)) => value,
+            (UnaryOp::Neg, ConstValue::Int(value)) => ConstValue::Int(
+                self.overflow
+                    .apply(value.checked_neg(), || value.wrapping_neg())
+                    .ok_or_else(|| overflow(unary.span))?,
+            ),
+            (UnaryOp::Neg, ConstValue::Float(value)) => ConstValue::Float(-value),
+            (UnaryOp::Neg, ConstValue::Double(value)) => ConstValue::Double(-value),
+            (UnaryOp::LogicalNot, ConstValue::Bool(value)) => ConstValue::Bool(!value),
+            (UnaryOp::BitwiseNot, ConstValue::Int(value)) => ConstValue::Int(!value),
+            _ => return Ok(None),
+        };
+        Ok(Some(value))
+    }
+
+    fn eval_binary(&self, binary: &BinaryExpr<'_>) -> Result<Option<ConstValue>, CompilationError> {
+        use BinaryOp::*;
+
+        // The right operand of a short-circuiting operator is never evaluated
+        // when the left one decides the result, so it need not be constant
+        if matches!(binary.op, LogicalAnd | LogicalOr) {
+            let Some(left) = self.condition(binary.left)? else {
+                return Ok(None);
+            };
+            if left == (binary.op == LogicalOr) {
+                return Ok(Some(ConstValue::Bool(left)));
+            }
+            return Ok(self.condition(binary.right)?.map(ConstValue::Bool));
+        }
+
+        let (Some(left), Some(right)) = (self.eval(binary.left)?, self.eval(binary.right)?) else {
+            return Ok(None);
+        };
+        let span = binary.span;
+        let value = match (left, right) {
+            (ConstValue::Int(a), ConstValue::Int(b)) => {
+                match int_op(binary.op, a, b, self.overflow, span)? {
+                    Some(value) => value,
+                    None => return Ok(None),
+                }
+            }
+            (ConstValue::Bool(a), ConstValue::Bool(b)) => match binary.op {
+                Equal => ConstValue::Bool(a == b),
+                NotEqual | LogicalXor => ConstValue::Bool(a != b),
+                _ => return Ok(None),
+            },
+            (ConstValue::String(a), ConstValue::String(b)) => match binary.op {
+                Add => ConstValue::String([a, b].concat()),
+                Equal => ConstValue::Bool(a == b),
+                NotEqual => ConstValue::Bool(a != b),
+                _ => return Ok(None),
+            },
+            (a @ ConstValue::Double(_), b) | (a, b @ ConstValue::Double(_)) => {
+                let (Some(a), Some(b)) = (a.to_f64(), b.to_f64()) else {
+                    return Ok(None);
+                };
+                match float_op(binary.op, a, b) {
+                    Some(Ok(value)) => ConstValue::Double(value),
+                    Some(Err(value)) => ConstValue::Bool(value),
+                    None => return Ok(None),
+                }
+            }
+            (a, b) => {
+                let (Some(a), Some(b)) = (a.to_f64(), b.to_f64()) else {
+                    return Ok(None);
+                };
+                match float_op(binary.op, a, b) {
+                    Some(Ok(value)) => ConstValue::Float(value as f32),
+                    Some(Err(value)) => ConstValue::Bool(value),
+                    None => return Ok(None),
+                }
+            }
+        };
+        Ok(Some(value))
+    }
+}
+
+fn overflow(span: Span) -> CompilationError {
+    CompilationError::ConstantOverflow {
+        type_name: "int64".to_string(),
+        span,
+    }
+}
+
+/// Apply an integer operator, checking for division by zero and handling
+/// overflow as `mode` does.
+fn int_op(
+    op: BinaryOp,
+    a: i64,
+    b: i64,
+    mode: IntegerOverflow,
+    span: Span,
+) -> Result<Option<ConstValue>, CompilationError> {
+    use BinaryOp::*;
+
+    let checked = |value: Option<i64>, wrapping: fn(i64, i64) -> i64| {
+        mode.apply(value, || wrapping(a, b))
+            .map(ConstValue::Int)
+            .ok_or_else(|| overflow(span))
+    };
+    let value = match op {
+        Add => checked(a.checked_add(b), i64::wrapping_add)?,
+        Sub => checked(a.checked_sub(b), i64::wrapping_sub)?,
+        Mul => checked(a.checked_mul(b), i64::wrapping_mul)?,
+        Div | Mod if b == 0 => return Err(CompilationError::ConstantDivisionByZero { span }),
+        Div => checked(a.checked_div(b), i64::wrapping_div)?,
+        Mod => checked(a.checked_rem(b), i64::wrapping_rem)?,
+        Pow => match u32::try_from(b) {
+            Ok(exp) => checked(a.checked_pow(exp), |a, b| a.wrapping_pow(b as u32))?,
+            // A negative power of an integer truncates towards zero
+            Err(_) => ConstValue::Int(match a {
+                1 => 1,
+                -1 if b % 2 == 0 => 1,
+                -1 => -1,
+                0 => return Err(CompilationError::ConstantDivisionByZero { span }),
+                _ => 0,
+            }),
+        },
+        BitwiseAnd => ConstValue::Int(a & b),
+        BitwiseOr => ConstValue::Int(a | b),
+        BitwiseXor => ConstValue::Int(a ^ b),
+        ShiftLeft | ShiftRight | ShiftRightUnsigned => {
+            // Wrapping shifts only use the low bits of the amount
+            let shift = u32::try_from(b).ok();
+            let value = match op {
+                ShiftLeft => checked(shift.and_then(|s| a.checked_shl(s)), |a, b| {
+                    a.wrapping_shl(b as u32)
+                }),
+                ShiftRight => checked(shift.and_then(|s| a.checked_shr(s)), |a, b| {
+                    a.wrapping_shr(b as u32)
+                }),
+                _ => checked(
+                    shift.and_then(|s| (a as u64).checked_shr(s).map(|v| v as i64)),
+                    |a, b| (a as u64).wrapping_shr(b as u32) as i64,
+                ),
+            };
+            value?
+        }
+        Equal => ConstValue::Bool(a == b),
+        NotEqual => ConstValue::Bool(a != b),
+        Less => ConstValue::Bool(a < b),
+        LessEqual => ConstValue::Bool(a <= b),
+        Greater => ConstValue::Bool(a > b),
+        GreaterEqual => ConstValue::Bool(a >= b),
+        _ => return Ok(None),
+    };
+    Ok(Some(value))
+}
+
+/// Apply a floating-point operator. Comparisons return `Err` with the
+/// boolean result so callers can tell them from arithmetic.
+///
+/// `float` operands are evaluated in `f64` too; rounding the result back
+/// gives the same value for everything but `**`.
+fn float_op(op: BinaryOp, a: f64, b: f64) -> Option<Result<f64, bool>> {
+    use BinaryOp::*;
+
+    Some(match op {
+        Add => Ok(a + b),
+        Sub => Ok(a - b),
+        Mul => Ok(a * b),
+        Div => Ok(a / b),
+        Mod => Ok(a % b),
+        Pow => Ok(a.powf(b)),
+        Equal => Err(a == b),
+        NotEqual => Err(a != b),
+        Less => Err(a < b),
+        LessEqual => Err(a <= b),
+        Greater => Err(a > b),
+        GreaterEqual => Err(a >= b),
+        _ => return None,
+    })
+}
+
+/// Constants of a script, as computed before its functions are compiled.
+#[derive(Debug, Default)]
+pub struct ScriptConstants {
+    /// Evaluator with the enum values and constant globals of the script,
+    /// and the registered constants.
+    pub evaluator: ConstEvaluator,
+    /// Constant globals declared by the script with their values, in
+    /// declaration order.
+    pub globals: Vec<(String, ConstValue)>,
+    /// Enums declared by the script with their enumerator values.
+    pub enums: Vec<CompiledEnum>,
+    /// Default arguments of the script's functions and methods that declare
+    /// any, in declaration order.
+    pub defaults: Vec<CompiledDefaults>,
+}
+
+/// Default argument of a parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DefaultArg {
+    /// Constant, converted to the parameter's type where it is primitive.
+    Constant(ConstValue),
+    /// Any other expression, emitted at each call site.
+    Expression,
+}
+
+/// Evaluate the enum values, constant globals and default arguments of a
+/// script, which may refer to the constants registered in `registry`.
+///
+/// Enum initializers must be constant. Globals and default arguments may be
+/// any expression, but constant ones must evaluate without error.
+pub fn evaluate_constants(
+    script: &Script<'_>,
+    registry: &SymbolRegistry,
+    overflow: IntegerOverflow,
+) -> (ScriptConstants, Vec<CompilationError>) {
+    let mut pass = ConstantPass {
+        constants: ScriptConstants::default(),
+        namespace: Vec::new(),
+        errors: Vec::new(),
+    };
+    pass.constants.evaluator.set_integer_overflow(overflow);
+    pass.constants.evaluator.define_registered(registry);
+    pass.items(script.items());
+
+    // Case labels may name constants declared anywhere in the script
+    let mut cases = CaseCheck {
+        evaluator: pass.constants.evaluator.clone(),
+        errors: Vec::new(),
+    };
+    cases.evaluator.set_namespace(Vec::new());
+    cases.visit_script(script);
+    pass.errors.extend(cases.errors);
+
+    (pass.constants, pass.errors)
+}
+
+/// Checks the case labels of every switch in a script.
+struct CaseCheck {
+    evaluator: ConstEvaluator,
+    errors: Vec<CompilationError>,
+}
+
+impl<'ast> Visitor<'ast> for CaseCheck {
+    fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+        let outer = self.evaluator.namespace.clone();
+        let mut inner = outer.clone();
+        inner.extend(namespace.path.iter().map(|s| s.name.to_string()));
+        self.evaluator.set_namespace(inner);
+        visitor::walk_namespace_decl(self, namespace);
+        self.evaluator.set_namespace(outer);
+    }
+
+    fn visit_switch_stmt(&mut self, stmt: &SwitchStmt<'ast>) {
+        if let Err(error) = self.evaluator.case_values(stmt) {
+            self.errors.push(error);
+        }
+        visitor::walk_switch_stmt(self, stmt);
+    }
+}
+
+struct ConstantPass {
+    constants: ScriptConstants,
+    namespace: Vec<String>,
+    errors: Vec<CompilationError>,
+}
+
+impl ConstantPass {
+    fn items(&mut self, items: &[Item<'_>]) {
+        for item in items {
+            self.constants
+                .evaluator
+                .set_namespace(self.namespace.clone());
+            match item {
+                Item::Namespace(ns) => {
+                    let depth = self.namespace.len();
+                    self.namespace
+                        .extend(ns.path.iter().map(|s| s.name.to_string()));
+                    self.items(ns.items);
+                    self.namespace.truncate(depth);
+                }
+                Item::Enum(decl) => {
+                    let name = self.qualify(decl.name.name);
+                    match self.constants.evaluator.enum_values(&name, decl) {
+                        Ok(values) => self.constants.enums.push(CompiledEnum { name, values }),
+                        Err(error) => self.errors.push(error),
+                    }
+                }
+                Item::GlobalVar(var) => {
+                    let Some(init) = var.init else { continue };
+                    match self.constants.evaluator.eval(init) {
+                        Ok(Some(value)) if var.ty.is_const => {
+                            if let Some(value) = converted(&var.ty, value) {
+                                let name = self.qualify(var.name.name);
+                                self.constants.evaluator.define(name.clone(), value.clone());
+                                self.constants.globals.push((name, value));
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(error) => self.errors.push(error),
+                    }
+                }
+                Item::Function(func) => {
+                    let name = self.qualify(func.name.name);
+                    self.defaults(name, func.params);
+                }
+                Item::Class(class) => {
+                    let class_name = self.qualify(class.name.name);
+                    for member in class.members {
+                        if let ClassMember::Method(method) = member {
+                            let name = format!("{}::{}", class_name, method.name.name);
+                            self.defaults(name, method.params);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn defaults(&mut self, function: String, params: &[FunctionParam<'_>]) {
+        if params.iter().all(|p| p.default.is_none()) {
+            return;
+        }
+        let mut defaults = Vec::with_capacity(params.len());
+        for param in params {
+            let Some(default) = param.default else {
+                defaults.push(None);
+                continue;
+            };
+            let arg = match self.eval_default(param, default) {
+                Ok(Some(value)) => match converted(&param.ty.ty, value) {
+                    Some(value) => DefaultArg::Constant(value),
+                    None => DefaultArg::Expression,
+                },
+                Ok(None) => DefaultArg::Expression,
+                Err(error) => {
+                    self.errors.push(error);
+                    DefaultArg::Expression
+                }
+            };
+            defaults.push(Some(arg));
+        }
+        self.constants.defaults.push(CompiledDefaults {
+            function,
+            params: defaults,
+        });
+    }
+
+    /// Evaluate a default argument. Enumerators of an enum-typed parameter
+    /// may be named without their enum, as in `Mode mode = Run`.
+    fn eval_default(
+        &self,
+        param: &FunctionParam<'_>,
+        default: &Expr<'_>,
+    ) -> Result<Option<ConstValue>, CompilationError> {
+        let evaluator = &self.constants.evaluator;
+        if let Some(value) = evaluator.eval(default)? {
+            return Ok(Some(value));
+        }
+        let ty = &param.ty.ty;
+        let TypeBase::Named(name) = ty.base else {
+            return Ok(None);
+        };
+        let Some(enum_decl) = candidate_names(&self.namespace, ty.scope.as_ref(), name.name)
+            .iter()
+            .find_map(|candidate| self.constants.enums.iter().find(|e| &e.name == candidate))
+        else {
+            return Ok(None);
+        };
+        let mut scope = evaluator.clone();
+        scope.set_namespace(enum_decl.name.split("::").map(str::to_string).collect());
+        scope.eval(default)
+    }
+
+    fn qualify(&self, name: &str) -> String {
+        let mut path = self.namespace.clone();
+        path.push(name.to_string());
+        path.join("::")
+    }
+}
+
+/// Convert a constant's value to its declared primitive type.
+fn converted(ty: &TypeExpr<'_>, value: ConstValue) -> Option<ConstValue> {
+    match ty.base {
+        TypeBase::Primitive(primitive) if ty.suffixes.is_empty() => value.convert(primitive),
+        _ => Some(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::SwitchStrategy;
+    use angelscript_core::GlobalPropertyEntry;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    /// Evaluate the initializer of the last global in `source`.
+    fn eval(source: &str) -> Result<Option<ConstValue>, CompilationError> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let (constants, errors) = evaluate_constants(
+            &script,
+            &SymbolRegistry::with_primitives(),
+            IntegerOverflow::Trap,
+        );
+        if let Some(error) = errors.into_iter().next() {
+            return Err(error);
+        }
+        let Some(Item::GlobalVar(var)) = script.items().last() else {
+            panic!("expected a global variable");
+        };
+        constants.evaluator.eval(var.init.unwrap())
+    }
+
+    #[test]
+    fn folds_arithmetic() {
+        assert_eq!(
+            eval("int x = 60 * 60 * 24;"),
+            Ok(Some(ConstValue::Int(86_400)))
+        );
+        assert_eq!(
+            eval("int x = (1 + 2) << 4 | 1;"),
+            Ok(Some(ConstValue::Int(49)))
+        );
+        assert_eq!(eval("int x = -7 % 3;"), Ok(Some(ConstValue::Int(-1))));
+        assert_eq!(eval("int x = 2 ** 10;"), Ok(Some(ConstValue::Int(1024))));
+        assert_eq!(
+            eval("double x = 1 + 0.5;"),
+            Ok(Some(ConstValue::Double(1.5)))
+        );
+        assert_eq!(
+            eval("float x = 1 + 0.5f;"),
+            Ok(Some(ConstValue::Float(1.5)))
+        );
+        assert_eq!(
+            eval("bool x = 3 > 2 && 1 != 1;"),
+            Ok(Some(ConstValue::Bool(false)))
+        );
+        assert_eq!(eval("int x = true ? 1 : 2;"), Ok(Some(ConstValue::Int(1))));
+    }
+
+    #[test]
+    fn folds_string_concatenation() {
+        assert_eq!(
+            eval("string x = \"v\" + \"1.0\";"),
+            Ok(Some(ConstValue::String(b"v1.0".to_vec())))
+        );
+    }
+
+    #[test]
+    fn non_constant_operands() {
+        assert_eq!(eval("int x = y + 1;"), Ok(None));
+        // The right operand is never evaluated
+        assert_eq!(
+            eval("bool x = false && y;"),
+            Ok(Some(ConstValue::Bool(false)))
+        );
+    }
+
+    #[test]
+    fn errors_in_constant_expressions() {
+        assert!(matches!(
+            eval("int x = 1 / (2 - 2);"),
+            Err(CompilationError::ConstantDivisionByZero { .. })
+        ));
+        assert!(matches!(
+            eval("int64 x = 9223372036854775807 + 1;"),
+            Err(CompilationError::ConstantOverflow { .. })
+        ));
+        assert!(matches!(
+            eval("void f(int a = 1 % 0) {} int x = 0;"),
+            Err(CompilationError::ConstantDivisionByZero { .. })
+        ));
+    }
+
+    #[test]
+    fn integer_overflow_modes() {
+        let mut eval = ConstEvaluator::new();
+        let arena = Bump::new();
+        let fold = |eval: &ConstEvaluator, source: &str| {
+            let script = Parser::parse(source, &arena).unwrap();
+            let Item::GlobalVar(var) = &script.items()[0] else {
+                panic!("expected a global");
+            };
+            eval.eval(var.init.unwrap())
+        };
+        let cases = [
+            ("int64 x = 9223372036854775807 + 1;", i64::MIN),
+            ("int64 x = -(-9223372036854775807 - 1);", i64::MIN),
+            ("int64 x = (-9223372036854775807 - 1) / -1;", i64::MIN),
+            ("int64 x = (-9223372036854775807 - 1) % -1;", 0),
+            ("int64 x = 3 ** 41;", 3i64.wrapping_pow(41)),
+            ("int64 x = 1 << 65;", 2),
+            ("int64 x = -8 >>> 64;", -8),
+        ];
+
+        for (source, _) in cases {
+            assert!(
+                matches!(
+                    fold(&eval, source),
+                    Err(CompilationError::ConstantOverflow { .. })
+                ),
+                "{source}"
+            );
+        }
+        eval.set_integer_overflow(IntegerOverflow::Wrap);
+        for (source, wrapped) in cases {
+            assert_eq!(
+                fold(&eval, source),
+                Ok(Some(ConstValue::Int(wrapped))),
+                "{source}"
+            );
+        }
+        // Division by zero is an error in both modes
+        assert!(matches!(
+            fold(&eval, "int x = 1 % 0;"),
+            Err(CompilationError::ConstantDivisionByZero { .. })
+        ));
+        assert_eq!(
+            fold(&eval, "int x = -7 / 2;"),
+            Ok(Some(ConstValue::Int(-3)))
+        );
+        assert_eq!(
+            fold(&eval, "int x = -7 % 2;"),
+            Ok(Some(ConstValue::Int(-1)))
+        );
+        assert_eq!(
+            fold(&eval, "int x = -8 >> 1;"),
+            Ok(Some(ConstValue::Int(-4)))
+        );
+    }
+
+    #[test]
+    fn enum_and_global_constants() {
+        let source = "namespace game {\n\
+                      const int BASE = 10;\n\
+                      enum Flags { A = BASE, B, C = B * 2, D = 1 << 4 }\n\
+                      }\n\
+                      int x = game::Flags::C + game::BASE;";
+        assert_eq!(eval(source), Ok(Some(ConstValue::Int(32))));
+
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let (constants, _) = evaluate_constants(
+            &script,
+            &SymbolRegistry::with_primitives(),
+            IntegerOverflow::Trap,
+        );
+        let flags = &constants.enums[0];
+        assert_eq!(flags.name, "game::Flags");
+        assert_eq!(flags.value("C"), Some(22));
+        let values: Vec<i64> = flags.values.iter().map(|(_, v)| *v).collect();
+        assert_eq!(values, [10, 11, 22, 16]);
+    }
+
+    #[test]
+    fn registered_and_global_constants() {
+        let mut registry = SymbolRegistry::with_primitives();
+        registry
+            .register_global(GlobalPropertyEntry::constant(
+                "MAX_PLAYERS",
+                ConstantValue::Uint8(8),
+            ))
+            .unwrap();
+        registry
+            .register_global(
+                GlobalPropertyEntry::constant("GRAVITY", ConstantValue::Double(9.81))
+                    .with_namespace(vec!["physics".to_string()]),
+            )
+            .unwrap();
+        let source = "const int SLOTS = MAX_PLAYERS * 2;\n\
+                      enum Slot { First = SLOTS, Last }\n\
+                      void fill(int count = SLOTS, double g = physics::GRAVITY) {\n\
+                      switch (count) { case SLOTS: case MAX_PLAYERS: break; }\n\
+                      }\n\
+                      int size = SLOTS;\n\
+                      int negative = -SLOTS;";
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let (constants, errors) = evaluate_constants(&script, &registry, IntegerOverflow::Trap);
+        assert!(errors.is_empty(), "{errors:?}");
+
+        assert_eq!(
+            constants.globals,
+            [("SLOTS".to_string(), ConstValue::Int(16))]
+        );
+        assert_eq!(constants.enums[0].value("Last"), Some(17));
+        assert_eq!(
+            constants.defaults[0].params,
+            [
+                Some(DefaultArg::Constant(ConstValue::Int(16))),
+                Some(DefaultArg::Constant(ConstValue::Double(9.81))),
+            ]
+        );
+
+        let [.., Item::GlobalVar(size), Item::GlobalVar(negative)] = script.items() else {
+            panic!("expected globals");
+        };
+        assert_eq!(constants.evaluator.array_size(size.init.unwrap()), Ok(16));
+        assert!(matches!(
+            constants.evaluator.array_size(negative.init.unwrap()),
+            Err(CompilationError::ConstantOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn enum_initializers_must_be_constant() {
+        assert!(matches!(
+            eval("enum E { A = f() } int x = 0;"),
+            Err(CompilationError::NotConstant { .. })
+        ));
+        assert!(matches!(
+            eval("enum E { A = 2147483647, B } int x = 0;"),
+            Err(CompilationError::ConstantOverflow { .. })
+        ));
+    }
+
+    #[test]
+    fn default_arguments_resolve_in_callee_scope() {
+        let source = "namespace game {\n\
+                      const int MAX = 8;\n\
+                      enum Mode { Idle, Run }\n\
+                      void spawn(int count = MAX * 2, Mode mode = Run, float scale = 1) {}\n\
+                      class Player { void hit(int damage, int times = MAX, bool crit = f()) {} }\n\
+                      }\n\
+                      void plain(int x) {}";
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let (constants, errors) = evaluate_constants(
+            &script,
+            &SymbolRegistry::with_primitives(),
+            IntegerOverflow::Trap,
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let [spawn, hit] = constants.defaults.as_slice() else {
+            panic!("expected two functions with defaults");
+        };
+        assert_eq!(spawn.function, "game::spawn");
+        assert_eq!(
+            spawn.params,
+            [
+                Some(DefaultArg::Constant(ConstValue::Int(16))),
+                Some(DefaultArg::Constant(ConstValue::Int(1))),
+                Some(DefaultArg::Constant(ConstValue::Float(1.0))),
+            ]
+        );
+        assert_eq!(hit.function, "game::Player::hit");
+        assert_eq!(
+            hit.params,
+            [
+                None,
+                Some(DefaultArg::Constant(ConstValue::Int(8))),
+                Some(DefaultArg::Expression),
+            ]
+        );
+
+        let mut chunk = BytecodeChunk::new();
+        let mut pool = ConstantPool::new();
+        assert!(spawn.emit_missing(&mut chunk, &mut pool, 1, 1));
+        chunk.assert_opcodes(&[OpCode::PushOne, OpCode::Constant]);
+        assert_eq!(pool.get(0), Some(&Constant::Float32(1.0)));
+
+        // Non-constant defaults are left to the emitter
+        let mut chunk = BytecodeChunk::new();
+        assert!(!hit.emit_missing(&mut chunk, &mut pool, 1, 1));
+        assert!(chunk.is_empty());
+        assert!(hit.emit_missing(&mut chunk, &mut pool, 3, 1));
+        assert!(chunk.is_empty());
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(
+            ConstValue::Int(300).convert(PrimitiveType::UInt8),
+            Some(ConstValue::Int(44))
+        );
+        assert_eq!(
+            ConstValue::Double(2.9).convert(PrimitiveType::Int),
+            Some(ConstValue::Int(2))
+        );
+        assert_eq!(ConstValue::Bool(true).convert(PrimitiveType::Int), None);
+        assert_eq!(ConstValue::Bool(true).to_constant(), Constant::Int(1));
+    }
+
+    /// Case values of the first switch in `source`, evaluated in its
+    /// namespace.
+    fn case_values(source: &str) -> Result<CaseValues, CompilationError> {
+        struct First {
+            check: CaseCheck,
+            result: Option<Result<CaseValues, CompilationError>>,
+        }
+        impl<'ast> Visitor<'ast> for First {
+            fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+                let mut inner = self.check.evaluator.namespace.clone();
+                inner.extend(namespace.path.iter().map(|s| s.name.to_string()));
+                self.check.evaluator.set_namespace(inner);
+                visitor::walk_namespace_decl(self, namespace);
+            }
+            fn visit_switch_stmt(&mut self, stmt: &SwitchStmt<'ast>) {
+                if self.result.is_none() {
+                    self.result = Some(self.check.evaluator.case_values(stmt));
+                }
+            }
+        }
+
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let (constants, _) = evaluate_constants(
+            &script,
+            &SymbolRegistry::with_primitives(),
+            IntegerOverflow::Trap,
+        );
+        let mut first = First {
+            check: CaseCheck {
+                evaluator: constants.evaluator,
+                errors: Vec::new(),
+            },
+            result: None,
+        };
+        first.check.evaluator.set_namespace(Vec::new());
+        first.visit_script(&script);
+        first.result.expect("no switch in source")
+    }
+
+    #[test]
+    fn dense_enum_cases_use_a_jump_table() {
+        let values = case_values(
+            r#"
+            namespace vm {
+                enum Op { Push, Pop, Add, Sub, Jump = 5 }
+                void run(Op op) {
+                    switch (op) {
+                        case Push: break;
+                        case Op::Pop: break;
+                        case Add: case Sub: break;
+                        case Jump: break;
+                        default: break;
+                    }
+                }
+            }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(values, CaseValues::Int(vec![0, 1, 2, 3, 5]));
+        let CaseValues::Int(values) = values else {
+            unreachable!()
+        };
+        assert_eq!(
+            SwitchStrategy::for_values(&values),
+            SwitchStrategy::JumpTable
+        );
+
+        let sparse = case_values(
+            "void f(int x) { switch (x) { case 1: case 100: case 1000: case 10000: break; } }",
+        );
+        let Ok(CaseValues::Int(sparse)) = sparse else {
+            panic!("{sparse:?}");
+        };
+        assert_eq!(
+            SwitchStrategy::for_values(&sparse),
+            SwitchStrategy::BinarySearch
+        );
+    }
+
+    #[test]
+    fn string_cases() {
+        assert_eq!(
+            case_values(r#"void f(string s) { switch (s) { case "go": case "stop": break; } }"#),
+            Ok(CaseValues::String(vec![b"go".to_vec(), b"stop".to_vec()]))
+        );
+    }
+
+    #[test]
+    fn invalid_case_labels() {
+        let errors = |source: &str| {
+            let arena = Bump::new();
+            let script = Parser::parse(source, &arena).unwrap();
+            evaluate_constants(
+                &script,
+                &SymbolRegistry::with_primitives(),
+                IntegerOverflow::Trap,
+            )
+            .1
+        };
+
+        let duplicate =
+            errors("const int A = 2; void f(int x) { switch (x) { case 2: case A: break; } }");
+        assert!(
+            matches!(&duplicate[..], [CompilationError::DuplicateCase { value, .. }] if value == "2"),
+            "{duplicate:?}"
+        );
+
+        let not_constant = errors("void f(int x, int y) { switch (x) { case y: break; } }");
+        assert!(
+            matches!(&not_constant[..], [CompilationError::NotConstant { .. }]),
+            "{not_constant:?}"
+        );
+
+        let mixed = errors(r#"void f(int x) { switch (x) { case 1: case "a": break; } }"#);
+        assert!(
+            matches!(&mixed[..], [CompilationError::TypeMismatch { .. }]),
+            "{mixed:?}"
+        );
+
+        // Same enumerator name in two visible enums
+        let ambiguous = errors(
+            "enum A { Red } enum B { Red } void f(int x) { switch (x) { case Red: break; } }",
+        );
+        assert!(
+            matches!(&ambiguous[..], [CompilationError::NotConstant { .. }]),
+            "{ambiguous:?}"
+        );
+    }
+}
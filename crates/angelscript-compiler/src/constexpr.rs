@@ -0,0 +1,782 @@
+//! Compile-time evaluation of pure script functions.
+//!
+//! A script function is evaluated at compile time when it is called with
+//! constant arguments and its body only computes with its parameters:
+//!
+//! - parameters and return value are primitives passed by value,
+//! - the body only uses constants, locals, arithmetic, comparisons,
+//!   conversions and branches,
+//! - every call is to another such function or a native pure function.
+//!
+//! So a lookup table builder or math helper like
+//!
+//! ```text
+//! int fib(int n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); }
+//! const int F15 = fib(15);  // CONSTANT 610
+//! ```
+//!
+//! costs nothing in shipped bytecode. Evaluation runs for at most a budget
+//! of instructions ([`DEFAULT_BUDGET`] by default), so a call that loops
+//! forever or takes too long is left for the runtime. A call that would fail
+//! at runtime, such as a division by zero, or gives an integer out of range
+//! of its return type is left too, so the script still fails when it runs.
+
+use angelscript_core::{DataType, Dynamic, RefModifier, TypeHash, primitives};
+use angelscript_registry::SymbolRegistry;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::CompiledFunction;
+use crate::bytecode::{BytecodeChunk, Constant, ConstantPool, OpCode, RewriteError};
+use crate::pure;
+
+/// Instructions a folded call may run by default.
+pub const DEFAULT_BUDGET: usize = 100_000;
+
+/// Script functions that can be evaluated at compile time.
+pub struct ConstexprFunctions<'a> {
+    registry: &'a SymbolRegistry,
+    functions: FxHashMap<TypeHash, Constexpr>,
+    constants: ConstantPool,
+    budget: usize,
+}
+
+/// A function that can be evaluated at compile time.
+struct Constexpr {
+    bytecode: BytecodeChunk,
+    params: Vec<TypeHash>,
+    return_type: TypeHash,
+}
+
+impl<'a> ConstexprFunctions<'a> {
+    /// Find the functions of a module that can be evaluated at compile time.
+    ///
+    /// `constants` is the module's constant pool; calls evaluate at most
+    /// `budget` instructions.
+    pub fn collect(
+        functions: &[CompiledFunction],
+        constants: &ConstantPool,
+        registry: &'a SymbolRegistry,
+        budget: usize,
+    ) -> Self {
+        let mut candidates = FxHashMap::default();
+        let mut callees = FxHashMap::default();
+        for function in functions {
+            let params: Vec<TypeHash> = function
+                .signature
+                .params
+                .iter()
+                .map(|param| param.type_hash)
+                .collect();
+            if !function.signature.params.iter().all(is_value)
+                || !is_value(&function.signature.return_type)
+            {
+                continue;
+            }
+            let Some(calls) = calls(&function.bytecode, constants) else {
+                continue;
+            };
+            let hash = TypeHash::from_function(&function.name, &params);
+            callees.insert(hash, calls);
+            candidates.insert(
+                hash,
+                Constexpr {
+                    bytecode: function.bytecode.clone(),
+                    params,
+                    return_type: function.signature.return_type.type_hash,
+                },
+            );
+        }
+
+        // Drop functions calling anything else until the set is closed
+        loop {
+            let dropped: Vec<TypeHash> = callees
+                .iter()
+                .filter(|(_, calls)| {
+                    calls.iter().any(|callee| {
+                        !callees.contains_key(callee) && !is_native_pure(registry, *callee)
+                    })
+                })
+                .map(|(&hash, _)| hash)
+                .collect();
+            if dropped.is_empty() {
+                break;
+            }
+            for hash in dropped {
+                callees.remove(&hash);
+                candidates.remove(&hash);
+            }
+        }
+
+        Self {
+            registry,
+            functions: candidates,
+            constants: constants.clone(),
+            budget,
+        }
+    }
+
+    /// Check if `function` can be evaluated at compile time.
+    pub fn contains(&self, function: TypeHash) -> bool {
+        self.functions.contains_key(&function)
+    }
+
+    /// Evaluate a call, giving `None` if it fails or exceeds the budget.
+    pub fn evaluate(&self, function: TypeHash, args: &[Dynamic]) -> Option<Dynamic> {
+        let target = self.functions.get(&function)?;
+        if args.len() != target.params.len() {
+            return None;
+        }
+        let args = args
+            .iter()
+            .zip(&target.params)
+            .map(|(arg, &param)| Value::from_dynamic(arg, param))
+            .collect::<Option<Vec<_>>>()?;
+        self.run(target, args).map(Value::into_dynamic)
+    }
+
+    /// Run a function with an explicit frame stack, so deep recursion is
+    /// only limited by the budget.
+    fn run(&self, function: &Constexpr, args: Vec<Value>) -> Option<Value> {
+        let mut budget = self.budget;
+        let mut stack: Vec<Value> = Vec::new();
+        let mut frames = vec![Frame::new(function, args, 0)];
+
+        loop {
+            budget = budget.checked_sub(1)?;
+            let frame = frames.last_mut()?;
+            let function = frame.function;
+            let code = &function.bytecode;
+            let op = code.read_op(frame.ip)?;
+            let at = frame.ip + 1;
+            frame.ip = at + op.operand_size();
+            let u8_at = |offset: usize| code.read_byte(at + offset);
+            let u16_at = || code.read_u16(at);
+
+            match op {
+                OpCode::Constant | OpCode::ConstantWide => {
+                    let index = if op == OpCode::Constant {
+                        u16::from(u8_at(0)?)
+                    } else {
+                        u16_at()?
+                    };
+                    stack.push(Value::from_constant(self.constants.get(u32::from(index))?)?);
+                }
+                OpCode::PushTrue => stack.push(Value::Bool(true)),
+                OpCode::PushFalse => stack.push(Value::Bool(false)),
+                OpCode::PushZero => stack.push(Value::Int(0)),
+                OpCode::PushOne => stack.push(Value::Int(1)),
+
+                OpCode::Pop => {
+                    stack.pop()?;
+                }
+                OpCode::PopN => {
+                    let count = usize::from(u8_at(0)?);
+                    stack.truncate(stack.len().checked_sub(count)?);
+                }
+                OpCode::Dup => stack.push(*stack.last()?),
+                OpCode::Pick => {
+                    let depth = usize::from(u8_at(0)?);
+                    let value = stack[stack.len().checked_sub(depth + 1)?];
+                    stack.push(value);
+                }
+                OpCode::Swap => {
+                    let len = stack.len();
+                    if len < 2 {
+                        return None;
+                    }
+                    stack.swap(len - 1, len - 2);
+                }
+
+                OpCode::GetLocal | OpCode::GetLocalWide => {
+                    let slot = local_slot(op, u8_at(0)?, u16_at())?;
+                    stack.push((*frame.locals.get(slot)?)?);
+                }
+                OpCode::SetLocal | OpCode::SetLocalWide => {
+                    let slot = local_slot(op, u8_at(0)?, u16_at())?;
+                    if frame.locals.len() <= slot {
+                        frame.locals.resize(slot + 1, None);
+                    }
+                    frame.locals[slot] = Some(*stack.last()?);
+                }
+
+                OpCode::Neg | OpCode::BitNot | OpCode::Not | OpCode::PreInc | OpCode::PreDec => {
+                    let value = stack.pop()?;
+                    stack.push(unary(op, value)?);
+                }
+                op if is_binary(op) => {
+                    let right = stack.pop()?;
+                    let left = stack.pop()?;
+                    stack.push(binary(op, left, right)?);
+                }
+                op if op.category() == crate::bytecode::OpCategory::Conversion => {
+                    let value = stack.pop()?;
+                    stack.push(convert(op, value)?);
+                }
+
+                OpCode::Jump => frame.ip = jump(frame.ip, u16_at()?)?,
+                OpCode::JumpIfFalse | OpCode::JumpIfTrue => {
+                    let Value::Bool(condition) = stack.pop()? else {
+                        return None;
+                    };
+                    if condition == (op == OpCode::JumpIfTrue) {
+                        frame.ip = jump(frame.ip, u16_at()?)?;
+                    }
+                }
+                OpCode::Loop | OpCode::LoopIfTrue => {
+                    let take = op == OpCode::Loop || stack.pop()? == Value::Bool(true);
+                    if take {
+                        frame.ip = frame.ip.checked_sub(usize::from(u16_at()?))?;
+                    }
+                }
+                OpCode::JumpTable => {
+                    let low = Value::from_constant(self.constants.get(u32::from(u16_at()?))?)?;
+                    let count = code.read_u16(at + 2)?;
+                    let entry = match (stack.pop()?, low) {
+                        (Value::Int(value), Value::Int(low)) => value.checked_sub(low),
+                        (Value::Uint(value), Value::Uint(low)) => {
+                            value.checked_sub(low).and_then(|i| i64::try_from(i).ok())
+                        }
+                        _ => return None,
+                    };
+                    let entry = entry
+                        .and_then(|entry| u16::try_from(entry).ok())
+                        .filter(|&entry| entry < count)
+                        .unwrap_or(count);
+                    // Each entry is a 3-byte JUMP
+                    frame.ip += 3 * usize::from(entry);
+                }
+
+                OpCode::Call => {
+                    let Some(&Constant::TypeHash(callee)) =
+                        self.constants.get(u32::from(u16_at()?))
+                    else {
+                        return None;
+                    };
+                    let argc = usize::from(u8_at(2)?);
+                    let args = stack.split_off(stack.len().checked_sub(argc)?);
+                    if let Some(target) = self.functions.get(&callee) {
+                        let base = stack.len();
+                        frames.push(Frame::new(target, args, base));
+                    } else {
+                        let args = args.into_iter().map(Value::into_dynamic).collect();
+                        let (return_type, result) = pure::call_native(self.registry, callee, args)?;
+                        stack.push(Value::from_dynamic(&result, return_type)?);
+                    }
+                }
+                OpCode::Return => {
+                    let value = stack.pop()?;
+                    let frame = frames.pop()?;
+                    let value = value.to_type(frame.function.return_type)?;
+                    if frames.is_empty() {
+                        return Some(value);
+                    }
+                    stack.truncate(frame.base);
+                    stack.push(value);
+                }
+
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Replace calls of compile-time evaluable script functions and native pure
+/// functions with constant arguments by their results. Results are added to
+/// `constants`.
+///
+/// # Errors
+///
+/// Returns an error if the chunk cannot be decoded or re-encoded.
+pub fn fold_constexpr_calls(
+    chunk: &BytecodeChunk,
+    constants: &mut ConstantPool,
+    functions: &ConstexprFunctions<'_>,
+) -> Result<BytecodeChunk, RewriteError> {
+    pure::fold_calls(chunk, constants, |function, args| {
+        match functions.functions.get(&function) {
+            Some(target) => Some((target.return_type, functions.evaluate(function, &args)?)),
+            None => pure::call_native(functions.registry, function, args),
+        }
+    })
+}
+
+/// A call being evaluated.
+struct Frame<'f> {
+    function: &'f Constexpr,
+    ip: usize,
+    locals: Vec<Option<Value>>,
+    /// Stack height below the call's values.
+    base: usize,
+}
+
+impl<'f> Frame<'f> {
+    fn new(function: &'f Constexpr, args: Vec<Value>, base: usize) -> Self {
+        Self {
+            function,
+            ip: 0,
+            locals: args.into_iter().map(Some).collect(),
+            base,
+        }
+    }
+}
+
+/// A value on the evaluation stack.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value {
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    Float(f32),
+    Double(f64),
+}
+
+impl Value {
+    fn from_constant(constant: &Constant) -> Option<Self> {
+        Some(match *constant {
+            Constant::Int(value) => Self::Int(value),
+            Constant::Uint(value) => Self::Uint(value),
+            Constant::Float32(value) => Self::Float(value),
+            Constant::Float64(value) => Self::Double(value),
+            Constant::StringData(_) | Constant::TypeHash(_) => return None,
+        })
+    }
+
+    fn from_dynamic(value: &Dynamic, type_hash: TypeHash) -> Option<Self> {
+        let value = match *value {
+            Dynamic::Bool(value) => Self::Bool(value),
+            Dynamic::Int(value) => Self::Int(value),
+            Dynamic::Float(value) => Self::Double(value),
+            _ => return None,
+        };
+        value.to_type(type_hash)
+    }
+
+    /// The value as a `type_hash` value, if it is one.
+    fn to_type(self, type_hash: TypeHash) -> Option<Self> {
+        match (type_hash, self) {
+            (primitives::BOOL, Self::Bool(_))
+            | (primitives::FLOAT, Self::Float(_))
+            | (primitives::DOUBLE, Self::Double(_)) => Some(self),
+            (primitives::FLOAT, Self::Double(value)) => Some(Self::Float(value as f32)),
+            (primitives::INT8, Self::Int(value)) => i8::try_from(value).ok().map(|_| self),
+            (primitives::INT16, Self::Int(value)) => i16::try_from(value).ok().map(|_| self),
+            (primitives::INT32, Self::Int(value)) => i32::try_from(value).ok().map(|_| self),
+            (primitives::INT64, Self::Int(_)) => Some(self),
+            (
+                primitives::UINT8 | primitives::UINT16 | primitives::UINT32 | primitives::UINT64,
+                Self::Int(value),
+            ) => u64::try_from(value)
+                .ok()
+                .map(Self::Uint)?
+                .to_type(type_hash),
+            (primitives::UINT8, Self::Uint(value)) => u8::try_from(value).ok().map(|_| self),
+            (primitives::UINT16, Self::Uint(value)) => u16::try_from(value).ok().map(|_| self),
+            (primitives::UINT32, Self::Uint(value)) => u32::try_from(value).ok().map(|_| self),
+            (primitives::UINT64, Self::Uint(_)) => Some(self),
+            _ => None,
+        }
+    }
+
+    fn into_dynamic(self) -> Dynamic {
+        match self {
+            Self::Bool(value) => Dynamic::Bool(value),
+            Self::Int(value) => Dynamic::Int(value),
+            Self::Uint(value) => Dynamic::Int(value as i64),
+            Self::Float(value) => Dynamic::Float(f64::from(value)),
+            Self::Double(value) => Dynamic::Float(value),
+        }
+    }
+}
+
+/// Check if a parameter or return type is a primitive passed by value.
+fn is_value(data_type: &DataType) -> bool {
+    matches!(
+        data_type.type_hash,
+        primitives::BOOL
+            | primitives::INT8
+            | primitives::INT16
+            | primitives::INT32
+            | primitives::INT64
+            | primitives::UINT8
+            | primitives::UINT16
+            | primitives::UINT32
+            | primitives::UINT64
+            | primitives::FLOAT
+            | primitives::DOUBLE
+    ) && !data_type.is_handle
+        && data_type.ref_modifier == RefModifier::None
+}
+
+fn is_native_pure(registry: &SymbolRegistry, function: TypeHash) -> bool {
+    registry
+        .get_function(function)
+        .is_some_and(|entry| entry.def.is_pure() && !entry.def.is_method() && entry.def.is_native)
+}
+
+/// The functions called by a body that only uses instructions the evaluator
+/// supports, or `None` if it uses others.
+fn calls(chunk: &BytecodeChunk, constants: &ConstantPool) -> Option<FxHashSet<TypeHash>> {
+    let mut calls = FxHashSet::default();
+    let mut offset = 0;
+    while offset < chunk.len() {
+        let op = chunk.read_op(offset)?;
+        let supported = matches!(
+            op,
+            OpCode::Constant
+                | OpCode::ConstantWide
+                | OpCode::PushTrue
+                | OpCode::PushFalse
+                | OpCode::PushZero
+                | OpCode::PushOne
+                | OpCode::Pop
+                | OpCode::PopN
+                | OpCode::Dup
+                | OpCode::Pick
+                | OpCode::Swap
+                | OpCode::GetLocal
+                | OpCode::GetLocalWide
+                | OpCode::SetLocal
+                | OpCode::SetLocalWide
+                | OpCode::Neg
+                | OpCode::BitNot
+                | OpCode::Not
+                | OpCode::PreInc
+                | OpCode::PreDec
+                | OpCode::Jump
+                | OpCode::JumpIfFalse
+                | OpCode::JumpIfTrue
+                | OpCode::Loop
+                | OpCode::LoopIfTrue
+                | OpCode::JumpTable
+                | OpCode::Call
+                | OpCode::Return
+        ) || is_binary(op)
+            || convert(op, Value::Int(0)).is_some()
+            || convert(op, Value::Uint(0)).is_some()
+            || convert(op, Value::Float(0.0)).is_some()
+            || convert(op, Value::Double(0.0)).is_some();
+        if !supported {
+            return None;
+        }
+        if op == OpCode::Call {
+            let Some(&Constant::TypeHash(callee)) =
+                constants.get(u32::from(chunk.read_u16(offset + 1)?))
+            else {
+                return None;
+            };
+            calls.insert(callee);
+        }
+        offset += 1 + op.operand_size();
+    }
+    Some(calls)
+}
+
+fn local_slot(op: OpCode, byte: u8, wide: Option<u16>) -> Option<usize> {
+    match op {
+        OpCode::GetLocal | OpCode::SetLocal => Some(usize::from(byte)),
+        _ => wide.map(usize::from),
+    }
+}
+
+/// The target of a forward jump by `offset` from `next`.
+fn jump(next: usize, offset: u16) -> Option<usize> {
+    next.checked_add_signed(isize::from(offset as i16))
+}
+
+fn is_binary(op: OpCode) -> bool {
+    matches!(
+        op,
+        OpCode::Add
+            | OpCode::Sub
+            | OpCode::Mul
+            | OpCode::Div
+            | OpCode::Mod
+            | OpCode::Pow
+            | OpCode::BitAnd
+            | OpCode::BitOr
+            | OpCode::BitXor
+            | OpCode::Shl
+            | OpCode::Shr
+            | OpCode::Ushr
+            | OpCode::Eq
+            | OpCode::Lt
+            | OpCode::Le
+            | OpCode::Gt
+            | OpCode::Ge
+    )
+}
+
+fn unary(op: OpCode, value: Value) -> Option<Value> {
+    Some(match (op, value) {
+        (OpCode::Neg, Value::Int(value)) => Value::Int(value.checked_neg()?),
+        (OpCode::Neg, Value::Float(value)) => Value::Float(-value),
+        (OpCode::Neg, Value::Double(value)) => Value::Double(-value),
+        (OpCode::BitNot, Value::Int(value)) => Value::Int(!value),
+        (OpCode::BitNot, Value::Uint(value)) => Value::Uint(!value),
+        (OpCode::Not, Value::Bool(value)) => Value::Bool(!value),
+        // The optimizer turns `+ 1` and `- 1` into increments of the value
+        (OpCode::PreInc | OpCode::PreDec, _) => {
+            let one = match value {
+                Value::Int(_) => Value::Int(1),
+                Value::Uint(_) => Value::Uint(1),
+                Value::Float(_) => Value::Float(1.0),
+                Value::Double(_) => Value::Double(1.0),
+                Value::Bool(_) => return None,
+            };
+            let op = if op == OpCode::PreInc {
+                OpCode::Add
+            } else {
+                OpCode::Sub
+            };
+            return binary(op, value, one);
+        }
+        _ => return None,
+    })
+}
+
+fn binary(op: OpCode, left: Value, right: Value) -> Option<Value> {
+    // `PUSH_ZERO` and `PUSH_ONE` also push unsigned values
+    let (left, right) = match (left, right) {
+        (Value::Int(left), Value::Uint(_)) => (Value::Uint(u64::try_from(left).ok()?), right),
+        (Value::Uint(_), Value::Int(right)) => (left, Value::Uint(u64::try_from(right).ok()?)),
+        _ => (left, right),
+    };
+    if let Some(ordering) = compare(op, left, right) {
+        return Some(Value::Bool(ordering));
+    }
+    Some(match (left, right) {
+        (Value::Int(l), Value::Int(r)) => Value::Int(match op {
+            OpCode::Add => l.checked_add(r)?,
+            OpCode::Sub => l.checked_sub(r)?,
+            OpCode::Mul => l.checked_mul(r)?,
+            OpCode::Div => l.checked_div(r)?,
+            OpCode::Mod => l.checked_rem(r)?,
+            OpCode::Pow => l.checked_pow(u32::try_from(r).ok()?)?,
+            OpCode::BitAnd => l & r,
+            OpCode::BitOr => l | r,
+            OpCode::BitXor => l ^ r,
+            OpCode::Shl => l.checked_shl(u32::try_from(r).ok()?)?,
+            OpCode::Shr => l.checked_shr(u32::try_from(r).ok()?)?,
+            OpCode::Ushr => (l as u64).checked_shr(u32::try_from(r).ok()?)? as i64,
+            _ => return None,
+        }),
+        (Value::Uint(l), Value::Uint(r)) => Value::Uint(match op {
+            OpCode::Add => l.checked_add(r)?,
+            OpCode::Sub => l.checked_sub(r)?,
+            OpCode::Mul => l.checked_mul(r)?,
+            OpCode::Div => l.checked_div(r)?,
+            OpCode::Mod => l.checked_rem(r)?,
+            OpCode::Pow => l.checked_pow(u32::try_from(r).ok()?)?,
+            OpCode::BitAnd => l & r,
+            OpCode::BitOr => l | r,
+            OpCode::BitXor => l ^ r,
+            OpCode::Shl => l.checked_shl(u32::try_from(r).ok()?)?,
+            OpCode::Shr | OpCode::Ushr => l.checked_shr(u32::try_from(r).ok()?)?,
+            _ => return None,
+        }),
+        (Value::Float(l), Value::Float(r)) => Value::Float(match op {
+            OpCode::Add => l + r,
+            OpCode::Sub => l - r,
+            OpCode::Mul => l * r,
+            OpCode::Div => l / r,
+            OpCode::Mod => l % r,
+            OpCode::Pow => l.powf(r),
+            _ => return None,
+        }),
+        (Value::Double(l), Value::Double(r)) => Value::Double(match op {
+            OpCode::Add => l + r,
+            OpCode::Sub => l - r,
+            OpCode::Mul => l * r,
+            OpCode::Div => l / r,
+            OpCode::Mod => l % r,
+            OpCode::Pow => l.powf(r),
+            _ => return None,
+        }),
+        _ => return None,
+    })
+}
+
+fn compare(op: OpCode, left: Value, right: Value) -> Option<bool> {
+    let ordering = match (left, right) {
+        (Value::Bool(l), Value::Bool(r)) if op == OpCode::Eq => return Some(l == r),
+        (Value::Int(l), Value::Int(r)) => l.partial_cmp(&r),
+        (Value::Uint(l), Value::Uint(r)) => l.partial_cmp(&r),
+        (Value::Float(l), Value::Float(r)) => l.partial_cmp(&r),
+        (Value::Double(l), Value::Double(r)) => l.partial_cmp(&r),
+        _ => return None,
+    };
+    let result = |test: fn(std::cmp::Ordering) -> bool| ordering.is_some_and(test);
+    Some(match op {
+        OpCode::Eq => result(|o| o.is_eq()),
+        OpCode::Lt => result(|o| o.is_lt()),
+        OpCode::Le => result(|o| o.is_le()),
+        OpCode::Gt => result(|o| o.is_gt()),
+        OpCode::Ge => result(|o| o.is_ge()),
+        _ => return None,
+    })
+}
+
+/// Apply a primitive conversion.
+fn convert(op: OpCode, value: Value) -> Option<Value> {
+    use OpCode::*;
+    Some(match (op, value) {
+        (I8toI16 | I8toI32 | I8toI64 | I16toI32 | I16toI64 | I32toI64, Value::Int(_)) => value,
+        (U8toU16 | U8toU32 | U8toU64 | U16toU32 | U16toU64 | U32toU64, Value::Uint(_)) => value,
+        (I64toI32, Value::Int(v)) => Value::Int(i64::from(v as i32)),
+        (I64toI16 | I32toI16, Value::Int(v)) => Value::Int(i64::from(v as i16)),
+        (I64toI8 | I32toI8 | I16toI8, Value::Int(v)) => Value::Int(i64::from(v as i8)),
+        (I32toF32 | I64toF32, Value::Int(v)) => Value::Float(v as f32),
+        (I32toF64 | I64toF64, Value::Int(v)) => Value::Double(v as f64),
+        (F32toI32, Value::Float(v)) => Value::Int(i64::from(v as i32)),
+        (F32toI64, Value::Float(v)) => Value::Int(v as i64),
+        (F64toI32, Value::Double(v)) => Value::Int(i64::from(v as i32)),
+        (F64toI64, Value::Double(v)) => Value::Int(v as i64),
+        (F32toF64, Value::Float(v)) => Value::Double(f64::from(v)),
+        (F64toF32, Value::Double(v)) => Value::Float(v as f32),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FunctionSignature;
+
+    fn int() -> DataType {
+        DataType::simple(primitives::INT32)
+    }
+
+    fn function(name: &str, bytecode: BytecodeChunk) -> CompiledFunction {
+        CompiledFunction {
+            name: name.to_string(),
+            signature: FunctionSignature::new(vec![int()], int()),
+            bytecode,
+        }
+    }
+
+    fn hash(name: &str) -> TypeHash {
+        TypeHash::from_function(name, &[primitives::INT32])
+    }
+
+    fn call(chunk: &mut BytecodeChunk, constants: &mut ConstantPool, name: &str) {
+        let index = constants.add_type_hash(hash(name));
+        chunk.write_op(OpCode::Call, 1);
+        chunk.write_u16(index as u16, 1);
+        chunk.write_byte(1, 1);
+    }
+
+    fn push(chunk: &mut BytecodeChunk, constants: &mut ConstantPool, value: i64) {
+        let index = constants.add(Constant::Int(value));
+        chunk.write_op(OpCode::Constant, 1);
+        chunk.write_byte(index as u8, 1);
+    }
+
+    fn get_local(chunk: &mut BytecodeChunk, slot: u8) {
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(slot, 1);
+    }
+
+    /// `int fib(int n) { if (n < 2) return n; return fib(n - 1) + fib(n - 2); }`
+    fn fib(constants: &mut ConstantPool) -> CompiledFunction {
+        let mut chunk = BytecodeChunk::new();
+        get_local(&mut chunk, 0);
+        push(&mut chunk, constants, 2);
+        chunk.write_op(OpCode::Lt, 1);
+        let recurse = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        get_local(&mut chunk, 0);
+        chunk.write_op(OpCode::Return, 1);
+        chunk.patch_jump(recurse);
+        get_local(&mut chunk, 0);
+        chunk.write_op(OpCode::PushOne, 1);
+        chunk.write_op(OpCode::Sub, 1);
+        call(&mut chunk, constants, "fib");
+        get_local(&mut chunk, 0);
+        push(&mut chunk, constants, 2);
+        chunk.write_op(OpCode::Sub, 1);
+        call(&mut chunk, constants, "fib");
+        chunk.write_op(OpCode::Add, 1);
+        chunk.write_op(OpCode::Return, 1);
+        function("fib", chunk)
+    }
+
+    /// `int spin(int n) { while (true) {} return n; }`
+    fn spin() -> CompiledFunction {
+        let mut chunk = BytecodeChunk::new();
+        chunk.emit_loop(0, 1);
+        get_local(&mut chunk, 0);
+        chunk.write_op(OpCode::Return, 1);
+        function("spin", chunk)
+    }
+
+    /// `int tick(int n) { return counter + n; }`
+    fn tick(constants: &mut ConstantPool) -> CompiledFunction {
+        let mut chunk = BytecodeChunk::new();
+        let index = constants.add_type_hash(TypeHash::from_name("counter"));
+        chunk.write_op(OpCode::GetGlobal, 1);
+        chunk.write_byte(index as u8, 1);
+        get_local(&mut chunk, 0);
+        chunk.write_op(OpCode::Add, 1);
+        chunk.write_op(OpCode::Return, 1);
+        function("tick", chunk)
+    }
+
+    /// `int half(int n) { return 10 / n; }`
+    fn half(constants: &mut ConstantPool) -> CompiledFunction {
+        let mut chunk = BytecodeChunk::new();
+        push(&mut chunk, constants, 10);
+        get_local(&mut chunk, 0);
+        chunk.write_op(OpCode::Div, 1);
+        chunk.write_op(OpCode::Return, 1);
+        function("half", chunk)
+    }
+
+    #[test]
+    fn folds_recursive_script_calls() {
+        let registry = SymbolRegistry::with_primitives();
+        let mut constants = ConstantPool::new();
+        let functions = [fib(&mut constants), tick(&mut constants)];
+        let constexpr =
+            ConstexprFunctions::collect(&functions, &constants, &registry, DEFAULT_BUDGET);
+        assert!(constexpr.contains(hash("fib")));
+        assert!(!constexpr.contains(hash("tick")));
+
+        // const int F15 = fib(15);
+        let mut chunk = BytecodeChunk::new();
+        push(&mut chunk, &mut constants, 15);
+        call(&mut chunk, &mut constants, "fib");
+        chunk.write_op(OpCode::Return, 1);
+        let folded = fold_constexpr_calls(&chunk, &mut constants, &constexpr).unwrap();
+        folded.assert_opcodes(&[OpCode::Constant, OpCode::Return]);
+        let index = folded.read_byte(1).unwrap();
+        assert_eq!(constants.get(u32::from(index)), Some(&Constant::Int(610)));
+    }
+
+    #[test]
+    fn leaves_calls_over_budget_or_failing() {
+        let registry = SymbolRegistry::with_primitives();
+        let mut constants = ConstantPool::new();
+        let functions = [fib(&mut constants), spin(), half(&mut constants)];
+        let constexpr = ConstexprFunctions::collect(&functions, &constants, &registry, 1_000);
+
+        let mut chunk = BytecodeChunk::new();
+        // fib(30) runs millions of instructions
+        push(&mut chunk, &mut constants, 30);
+        call(&mut chunk, &mut constants, "fib");
+        chunk.write_op(OpCode::Pop, 1);
+        // spin(1) never returns
+        chunk.write_op(OpCode::PushOne, 1);
+        call(&mut chunk, &mut constants, "spin");
+        chunk.write_op(OpCode::Pop, 1);
+        // half(0) divides by zero
+        chunk.write_op(OpCode::PushZero, 1);
+        call(&mut chunk, &mut constants, "half");
+        chunk.write_op(OpCode::Return, 1);
+
+        let folded = fold_constexpr_calls(&chunk, &mut constants, &constexpr).unwrap();
+        assert_eq!(folded.code(), chunk.code());
+        assert_eq!(
+            constexpr.evaluate(hash("half"), &[Dynamic::Int(2)]),
+            Some(Dynamic::Int(5))
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! Delegates.
+//!
+//! A delegate binds a method to an object so it can be stored in a funcdef
+//! handle, e.g. to register a member function as a callback:
+//!
+//! ```text
+//! funcdef void Callback(int);
+//! Callback@ cb = Callback(player.takeDamage);  // GET_LOCAL player; NEW_DELEGATE Player::takeDamage
+//! ```
+//!
+//! The method must have the funcdef's parameter and return types. At
+//! runtime `NEW_DELEGATE` allocates a function object holding a reference to
+//! the bound object (see [`FunctionObject`]), so the object lives as long as
+//! the delegate.
+//!
+//! [`FunctionObject`]: angelscript_core::FunctionObject
+
+use angelscript_core::{CompilationError, DataType, FunctionEntry, Span, TypeHash};
+use angelscript_registry::SymbolRegistry;
+
+use crate::bytecode::{BytecodeChunk, ConstantPool, OpCode};
+
+/// A method bound to an object for a funcdef.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delegate {
+    /// The funcdef the delegate is created for.
+    pub funcdef: TypeHash,
+    /// The bound method.
+    pub method: TypeHash,
+}
+
+impl Delegate {
+    /// Find the method `name` of an `object` to bind for `funcdef`.
+    ///
+    /// Overloads, including those inherited from base classes, are matched
+    /// against the funcdef's signature. A const object can only be bound to
+    /// const methods.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `funcdef` is not a funcdef, the object is not of
+    /// a class, the class has no method `name`, or no overload can be bound.
+    pub fn resolve(
+        registry: &SymbolRegistry,
+        funcdef: TypeHash,
+        object: &DataType,
+        name: &str,
+        span: Span,
+    ) -> Result<Self, CompilationError> {
+        let invalid = |reason: String| CompilationError::InvalidDelegate {
+            method: name.to_string(),
+            type_name: type_name(registry, object.type_hash),
+            funcdef: type_name(registry, funcdef),
+            reason,
+            span,
+        };
+        let Some(signature) = registry.get(funcdef).and_then(|e| e.as_funcdef()) else {
+            return Err(invalid(format!(
+                "'{}' is not a funcdef",
+                type_name(registry, funcdef)
+            )));
+        };
+        let Some(class) = registry.get(object.type_hash).and_then(|e| e.as_class()) else {
+            return Err(invalid(format!(
+                "'{}' is not a class",
+                type_name(registry, object.type_hash)
+            )));
+        };
+
+        // Overrides come before the methods they override
+        let methods: Vec<&FunctionEntry> = std::iter::once(class)
+            .chain(registry.base_class_chain(class.type_hash))
+            .flat_map(|class| class.find_methods(name))
+            .filter_map(|&hash| registry.get_function(hash))
+            .collect();
+        if methods.is_empty() {
+            return Err(CompilationError::UnknownMethod {
+                method: name.to_string(),
+                type_name: class.qualified_name.clone(),
+                span,
+            });
+        }
+
+        let matching: Vec<&FunctionEntry> = methods
+            .into_iter()
+            .filter(|method| {
+                method.def.return_type == signature.return_type
+                    && method.def.params.len() == signature.params.len()
+                    && method
+                        .def
+                        .params
+                        .iter()
+                        .zip(&signature.params)
+                        .all(|(param, expected)| param.data_type == *expected)
+            })
+            .collect();
+        let is_const = object.is_const || object.is_handle_to_const;
+        match matching
+            .iter()
+            .find(|method| !is_const || method.def.is_const())
+        {
+            Some(method) => Ok(Self {
+                funcdef,
+                method: method.def.func_hash,
+            }),
+            None if matching.is_empty() => Err(invalid(
+                "no overload has the funcdef's parameter and return types".to_string(),
+            )),
+            None => Err(invalid(
+                "the object is const and the method is not".to_string(),
+            )),
+        }
+    }
+
+    /// Emit the creation of the delegate from the object on top of the
+    /// stack.
+    pub fn emit(&self, chunk: &mut BytecodeChunk, constants: &mut ConstantPool, line: u32) {
+        let index = constants.add_type_hash(self.method);
+        chunk.write_op(OpCode::NewDelegate, line);
+        chunk.write_u16(index as u16, line);
+    }
+}
+
+fn type_name(registry: &SymbolRegistry, hash: TypeHash) -> String {
+    registry.get(hash).map_or_else(
+        || hash.to_string(),
+        |entry| entry.qualified_name().to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{
+        ClassEntry, FuncdefEntry, FunctionDef, FunctionTraits, Param, TypeKind, TypeSource,
+        Visibility, primitives,
+    };
+
+    fn hash(name: &str) -> TypeHash {
+        TypeHash::from_name(name)
+    }
+
+    fn method(
+        registry: &mut SymbolRegistry,
+        class: &str,
+        name: &str,
+        param: TypeHash,
+        traits: FunctionTraits,
+    ) {
+        let def = FunctionDef::new(
+            TypeHash::from_method(hash(class), name, &[param]),
+            name.to_string(),
+            vec![],
+            vec![Param::new("x", DataType::simple(param))],
+            DataType::void(),
+            Some(hash(class)),
+            traits,
+            true,
+            Visibility::Public,
+        );
+        registry.register_function(FunctionEntry::ffi(def)).unwrap();
+    }
+
+    /// `funcdef void Callback(int)`; `Player` with `takeDamage(int)`,
+    /// `takeDamage(float)` and a const `inspect(int)`; `Boss : Player`.
+    fn registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+        let callback = FuncdefEntry::new(
+            "Callback".to_string(),
+            vec![],
+            "Callback".to_string(),
+            hash("Callback"),
+            TypeSource::ffi_untyped(),
+            vec![DataType::simple(primitives::INT32)],
+            DataType::void(),
+        );
+        registry.register_type(callback.into()).unwrap();
+
+        let player = ClassEntry::ffi("Player", TypeKind::reference())
+            .with_method(
+                "takeDamage",
+                TypeHash::from_method(hash("Player"), "takeDamage", &[primitives::FLOAT]),
+            )
+            .with_method(
+                "takeDamage",
+                TypeHash::from_method(hash("Player"), "takeDamage", &[primitives::INT32]),
+            )
+            .with_method(
+                "inspect",
+                TypeHash::from_method(hash("Player"), "inspect", &[primitives::INT32]),
+            );
+        let boss = ClassEntry::ffi("Boss", TypeKind::reference()).with_base(player.type_hash);
+        registry.register_type(player.into()).unwrap();
+        registry.register_type(boss.into()).unwrap();
+        for (name, param, traits) in [
+            ("takeDamage", primitives::FLOAT, FunctionTraits::new()),
+            ("takeDamage", primitives::INT32, FunctionTraits::new()),
+            ("inspect", primitives::INT32, FunctionTraits::const_method()),
+        ] {
+            method(&mut registry, "Player", name, param, traits);
+        }
+        registry
+    }
+
+    #[test]
+    fn binds_matching_overload() {
+        let registry = registry();
+        let span = Span::new(1, 1, 1);
+        let boss = DataType::with_handle(hash("Boss"), false);
+        let delegate =
+            Delegate::resolve(&registry, hash("Callback"), &boss, "takeDamage", span).unwrap();
+        assert_eq!(
+            delegate.method,
+            TypeHash::from_method(hash("Player"), "takeDamage", &[primitives::INT32])
+        );
+
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        delegate.emit(&mut chunk, &mut constants, 1);
+        chunk.assert_opcodes(&[OpCode::NewDelegate]);
+    }
+
+    #[test]
+    fn rejects_unbindable_methods() {
+        let registry = registry();
+        let span = Span::new(1, 1, 1);
+        let player = DataType::with_handle(hash("Player"), false);
+        let const_player = DataType::with_handle(hash("Player"), true);
+        let resolve = |object: &DataType, name: &str| {
+            Delegate::resolve(&registry, hash("Callback"), object, name, span)
+                .map_err(|e| e.to_string())
+        };
+
+        assert!(resolve(&const_player, "inspect").is_ok());
+        assert_eq!(
+            resolve(&const_player, "takeDamage").unwrap_err(),
+            "at 1:1: cannot bind 'Player::takeDamage' to funcdef 'Callback': \
+             the object is const and the method is not"
+        );
+        assert_eq!(
+            resolve(&player, "heal").unwrap_err(),
+            "at 1:1: unknown method 'heal' on type 'Player'"
+        );
+        assert!(Delegate::resolve(&registry, hash("Player"), &player, "inspect", span).is_err());
+    }
+}
@@ -0,0 +1,257 @@
+//! Differential testing of the optimization pipeline.
+//!
+//! Random well-typed programs are run unoptimized and after each
+//! optimization level, and must give the same results. Until the VM has
+//! alternative dispatch backends to compare, the programs run on the
+//! compile-time evaluator ([`ConstexprFunctions`]), which executes the
+//! same bytecode the optimizer rewrites.
+//!
+//! A program is an `int64 f(int64 a, int64 b)` built from assignments to
+//! locals, `if`/`else`, counted loops and integer expressions, including
+//! repeated subexpressions for the common subexpression pass to find. On
+//! a mismatch the seed and both disassemblies are reported, so the failing
+//! program can be replayed with [`program`].
+
+use angelscript_core::{DataType, Dynamic, TypeHash, primitives};
+use angelscript_registry::SymbolRegistry;
+
+use crate::bytecode::{BytecodeChunk, Constant, ConstantPool, OpCode, OptimizationLevel};
+use crate::{CompiledFunction, CompilerOptions, ConstexprFunctions, FunctionSignature};
+
+/// Locals the programs assign, after the two parameters.
+const LOCALS: std::ops::Range<u8> = 2..6;
+/// Loop counters, one per nesting level.
+const COUNTERS: u8 = 6;
+const MAX_DEPTH: u8 = 2;
+const BUDGET: usize = 1_000_000;
+
+/// A xorshift generator, so failures replay from their seed.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    fn range(&mut self, low: i64, high: i64) -> i64 {
+        low + self.below((high - low + 1) as u64) as i64
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Int(i64),
+    Local(u8),
+    Neg(Box<Expr>),
+    Binary(OpCode, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn random(rng: &mut Rng, depth: u8) -> Self {
+        if depth == 0 || rng.below(3) == 0 {
+            return match rng.below(2) {
+                0 => Self::Int(rng.range(-3, 20)),
+                _ => Self::Local(rng.below(u64::from(COUNTERS + MAX_DEPTH)) as u8),
+            };
+        }
+        let operand = |rng: &mut Rng| Box::new(Self::random(rng, depth - 1));
+        match rng.below(10) {
+            0 => Self::Neg(operand(rng)),
+            // The same subexpression twice
+            1 => {
+                let repeated = operand(rng);
+                Self::Binary(OpCode::Add, repeated.clone(), repeated)
+            }
+            // Shifts and divisions by constants, so they cannot fail
+            2 => Self::Binary(
+                OpCode::Shl,
+                operand(rng),
+                Box::new(Self::Int(rng.range(0, 3))),
+            ),
+            3 => {
+                let op = [OpCode::Div, OpCode::Mod][rng.below(2) as usize];
+                Self::Binary(op, operand(rng), Box::new(Self::Int(rng.range(1, 7))))
+            }
+            _ => {
+                let ops = [
+                    OpCode::Add,
+                    OpCode::Sub,
+                    OpCode::Mul,
+                    OpCode::BitAnd,
+                    OpCode::BitOr,
+                    OpCode::BitXor,
+                ];
+                let op = ops[rng.below(ops.len() as u64) as usize];
+                Self::Binary(op, operand(rng), operand(rng))
+            }
+        }
+    }
+
+    fn emit(&self, chunk: &mut BytecodeChunk, constants: &mut ConstantPool) {
+        match self {
+            Self::Int(0) => chunk.write_op(OpCode::PushZero, 1),
+            Self::Int(1) => chunk.write_op(OpCode::PushOne, 1),
+            Self::Int(value) => {
+                let index = constants.add(Constant::Int(*value));
+                chunk.write_op(OpCode::Constant, 1);
+                chunk.write_byte(index as u8, 1);
+            }
+            Self::Local(slot) => chunk.emit_get_local(u16::from(*slot), 1),
+            Self::Neg(operand) => {
+                operand.emit(chunk, constants);
+                chunk.write_op(OpCode::Neg, 1);
+            }
+            Self::Binary(op, left, right) => {
+                left.emit(chunk, constants);
+                right.emit(chunk, constants);
+                chunk.write_op(*op, 1);
+            }
+        }
+    }
+}
+
+/// Generate the program for `seed`.
+fn program(seed: u64, constants: &mut ConstantPool) -> CompiledFunction {
+    let mut rng = Rng::new(seed);
+    let mut chunk = BytecodeChunk::new();
+    for slot in LOCALS.chain(COUNTERS..COUNTERS + MAX_DEPTH) {
+        Expr::Int(rng.range(0, 9)).emit(&mut chunk, constants);
+        chunk.emit_set_local(u16::from(slot), 1);
+        chunk.write_op(OpCode::Pop, 1);
+    }
+    block(&mut rng, &mut chunk, constants, 0);
+    Expr::random(&mut rng, 3).emit(&mut chunk, constants);
+    chunk.write_op(OpCode::Return, 1);
+
+    let int64 = DataType::simple(primitives::INT64);
+    CompiledFunction {
+        name: "f".to_string(),
+        signature: FunctionSignature::new(vec![int64, int64], int64),
+        bytecode: chunk,
+    }
+}
+
+fn block(rng: &mut Rng, chunk: &mut BytecodeChunk, constants: &mut ConstantPool, depth: u8) {
+    for _ in 0..rng.range(1, 5) {
+        match rng.below(6) {
+            0 if depth < MAX_DEPTH => {
+                // if (l cmp r) { ... } else { ... }
+                Expr::random(rng, 2).emit(chunk, constants);
+                Expr::random(rng, 2).emit(chunk, constants);
+                let compare = [OpCode::Eq, OpCode::Lt, OpCode::Le, OpCode::Gt, OpCode::Ge];
+                chunk.write_op(compare[rng.below(5) as usize], 1);
+                if rng.below(2) == 0 {
+                    chunk.write_op(OpCode::Not, 1);
+                }
+                let otherwise = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+                block(rng, chunk, constants, depth + 1);
+                let end = chunk.emit_jump(OpCode::Jump, 1);
+                chunk.patch_jump(otherwise);
+                block(rng, chunk, constants, depth + 1);
+                chunk.patch_jump(end);
+            }
+            1 if depth < MAX_DEPTH => {
+                // for (counter = n; counter > 0; counter--) { ... }
+                let counter = u16::from(COUNTERS + depth);
+                Expr::Int(rng.range(0, 4)).emit(chunk, constants);
+                chunk.emit_set_local(counter, 1);
+                chunk.write_op(OpCode::Pop, 1);
+                let start = chunk.current_offset();
+                chunk.emit_get_local(counter, 1);
+                chunk.write_op(OpCode::PushZero, 1);
+                chunk.write_op(OpCode::Gt, 1);
+                let exit = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+                block(rng, chunk, constants, depth + 1);
+                chunk.emit_get_local(counter, 1);
+                chunk.write_op(OpCode::PushOne, 1);
+                chunk.write_op(OpCode::Sub, 1);
+                chunk.emit_set_local(counter, 1);
+                chunk.write_op(OpCode::Pop, 1);
+                chunk.emit_loop(start, 1);
+                chunk.patch_jump(exit);
+            }
+            _ => {
+                let slot = rng.range(i64::from(LOCALS.start), i64::from(LOCALS.end) - 1);
+                Expr::random(rng, 3).emit(chunk, constants);
+                chunk.emit_set_local(slot as u16, 1);
+                chunk.write_op(OpCode::Pop, 1);
+            }
+        }
+    }
+}
+
+/// Optimize a copy of `function` with `options` and run it on `args`.
+fn run(
+    function: &CompiledFunction,
+    constants: &ConstantPool,
+    registry: &SymbolRegistry,
+    options: &CompilerOptions,
+    args: [i64; 2],
+) -> (Option<Dynamic>, String) {
+    let mut function = function.clone();
+    let mut constants = constants.clone();
+    let constexpr = ConstexprFunctions::collect(&[], &constants, registry, BUDGET);
+    let errors =
+        crate::optimize_function(&mut function, &mut constants, registry, &constexpr, options);
+    assert!(errors.is_empty(), "{errors:?}");
+
+    let disasm = function.bytecode.disassemble(&constants);
+    let functions = ConstexprFunctions::collect(
+        std::slice::from_ref(&function),
+        &constants,
+        registry,
+        BUDGET,
+    );
+    let hash = TypeHash::from_function("f", &[primitives::INT64, primitives::INT64]);
+    assert!(functions.contains(hash), "unsupported bytecode:\n{disasm}");
+    let args = args.map(Dynamic::Int);
+    (functions.evaluate(hash, &args), disasm)
+}
+
+#[test]
+fn optimized_programs_match_unoptimized() {
+    let registry = SymbolRegistry::with_primitives();
+    let levels = [
+        (OptimizationLevel::None, false),
+        (OptimizationLevel::Basic, false),
+        (OptimizationLevel::Basic, true),
+        (OptimizationLevel::Aggressive, true),
+    ];
+    let mut ran = 0;
+    for seed in 0..300 {
+        let mut constants = ConstantPool::new();
+        let function = program(seed, &mut constants);
+        for args in [[0, 0], [3, -7], [1 << 20, 12]] {
+            let options = |(optimization_level, optimize_branches)| CompilerOptions {
+                optimization_level,
+                optimize_branches,
+                ..CompilerOptions::default()
+            };
+            let (expected, reference) =
+                run(&function, &constants, &registry, &options(levels[0]), args);
+            ran += usize::from(expected.is_some());
+            for level in &levels[1..] {
+                let (actual, optimized) =
+                    run(&function, &constants, &registry, &options(*level), args);
+                assert_eq!(
+                    actual, expected,
+                    "seed {seed}, args {args:?}, {level:?}\n\
+                     unoptimized:\n{reference}\noptimized:\n{optimized}"
+                );
+            }
+        }
+    }
+    // Most programs run to completion rather than overflowing
+    assert!(ran > 600, "only {ran} runs completed");
+}
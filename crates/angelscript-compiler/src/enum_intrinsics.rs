@@ -0,0 +1,117 @@
+//! Enum reflection intrinsics.
+//!
+//! The compiler provides two functions for every script enum, so scripts can
+//! display and read enum values without hand-written tables:
+//!
+//! ```angelscript
+//! enum Color { Red, Green = 4, Blue }
+//!
+//! // Provided by the compiler
+//! string toString(Color value);
+//! bool parse(const string &in name, Color &out value);
+//!
+//! void main() {
+//!     print(toString(Color::Blue));       // "Blue"
+//!     Color c;
+//!     if (parse("Green", c)) { ... }      // c == Color::Green
+//! }
+//! ```
+//!
+//! `toString` returns the unqualified name of the first enumerator with the
+//! value, or the value in decimal if no enumerator has it (e.g. combined
+//! flags). `parse` accepts the unqualified or qualified name of an
+//! enumerator and returns `false`, leaving `value` unchanged, for any other
+//! text. Both are overloaded on the enum type, so a script declaring its own
+//! `toString(Color)` replaces the intrinsic for that enum.
+//!
+//! Calls whose argument names an enumerator fold to a string constant (see
+//! [`ConstEvaluator`](crate::ConstEvaluator)). The host reads the same
+//! tables through [`CompiledEnum::display`] and [`CompiledEnum::parse`].
+
+use crate::CompiledEnum;
+
+/// Name of the intrinsic returning the name of an enum value.
+pub const TO_STRING: &str = "toString";
+
+/// Name of the intrinsic reading an enum value from its name.
+pub const PARSE: &str = "parse";
+
+/// The declarations of the intrinsics provided for an enum, for overload
+/// resolution and documentation.
+pub fn declarations(decl: &CompiledEnum) -> [String; 2] {
+    [
+        format!("string {}({} value)", TO_STRING, decl.name),
+        format!(
+            "bool {}(const string &in name, {} &out value)",
+            PARSE, decl.name
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ConstValue;
+    use crate::const_eval::evaluate_constants;
+    use crate::overflow::IntegerOverflow;
+    use angelscript_parser::ast::Parser;
+    use angelscript_registry::SymbolRegistry;
+    use bumpalo::Bump;
+
+    fn color() -> CompiledEnum {
+        CompiledEnum {
+            name: "game::Color".into(),
+            values: vec![
+                ("game::Color::Red".into(), 0),
+                ("game::Color::Crimson".into(), 0),
+                ("game::Color::Green".into(), 4),
+            ],
+        }
+    }
+
+    #[test]
+    fn names_and_values() {
+        let color = color();
+        assert_eq!(color.name_of(0), Some("Red"));
+        assert_eq!(color.display(4), "Green");
+        assert_eq!(color.display(5), "5");
+        assert_eq!(color.parse("Crimson"), Some(0));
+        assert_eq!(color.parse("game::Color::Green"), Some(4));
+        assert_eq!(color.parse("Blue"), None);
+        assert_eq!(
+            declarations(&color),
+            [
+                "string toString(game::Color value)",
+                "bool parse(const string &in name, game::Color &out value)"
+            ]
+        );
+    }
+
+    #[test]
+    fn to_string_of_enumerators_folds() {
+        let source = "
+            namespace game {
+                enum Color { Red, Crimson = 0, Green = 4 }
+                const string GREEN = toString(Color::Green);
+            }
+            const string RED = toString(game::Color::Crimson);
+            const string OTHER = toString(game::GREEN);
+        ";
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let (constants, errors) = evaluate_constants(
+            &script,
+            &SymbolRegistry::with_primitives(),
+            IntegerOverflow::Trap,
+        );
+        assert!(errors.is_empty());
+        let string = |s: &str| ConstValue::String(s.as_bytes().to_vec());
+        assert_eq!(
+            constants.globals,
+            [
+                ("game::GREEN".to_string(), string("Green")),
+                ("RED".to_string(), string("Red")),
+            ]
+        );
+    }
+}
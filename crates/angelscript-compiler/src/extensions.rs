@@ -0,0 +1,79 @@
+//! Gating of experimental syntax behind `#pragma extension(...)`.
+//!
+//! The parser accepts extension syntax everywhere so that it can report a
+//! clear error; this pass rejects it in sections that have not enabled the
+//! extension:
+//!
+//! ```angelscript
+//! #pragma extension(null_coalesce)
+//!
+//! Enemy@ target = focused ?? nearest;
+//! ```
+
+use angelscript_core::CompilationError;
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{BinaryExpr, BinaryOp, Script};
+use angelscript_parser::directives::{Extension, SectionOptions};
+
+/// Report every use of an extension the section has not enabled.
+pub fn check_extensions(script: &Script<'_>, options: &SectionOptions) -> Vec<CompilationError> {
+    let mut pass = ExtensionPass {
+        options,
+        errors: Vec::new(),
+    };
+    pass.visit_script(script);
+    pass.errors
+}
+
+struct ExtensionPass<'o> {
+    options: &'o SectionOptions,
+    errors: Vec<CompilationError>,
+}
+
+impl<'ast> Visitor<'ast> for ExtensionPass<'_> {
+    fn visit_binary_expr(&mut self, expr: &BinaryExpr<'ast>) {
+        if expr.op == BinaryOp::NullCoalesce && !self.options.has_extension(Extension::NullCoalesce)
+        {
+            self.errors.push(CompilationError::InvalidOperation {
+                message: format!(
+                    "'??' requires '#pragma extension({})'",
+                    Extension::NullCoalesce.name()
+                ),
+                span: expr.span,
+            });
+        }
+        visitor::walk_binary_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn check(source: &str, options: &SectionOptions) -> Vec<CompilationError> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        check_extensions(&script, options)
+    }
+
+    #[test]
+    fn null_coalesce_requires_extension() {
+        let source = "class A {}\nA@ pick(A@ a, A@ b) {\n    return a ?? b;\n}";
+
+        let errors = check(source, &SectionOptions::default());
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            CompilationError::InvalidOperation { message, span }
+                if message.contains("null_coalesce") && span.line == 3
+        ));
+
+        let enabled = SectionOptions {
+            strict: false,
+            extensions: vec![Extension::NullCoalesce],
+        };
+        assert!(check(source, &enabled).is_empty());
+    }
+}
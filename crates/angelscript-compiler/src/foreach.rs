@@ -0,0 +1,360 @@
+//! Lowering of `foreach` loops.
+//!
+//! A container supports `foreach` through its iterator operators, which
+//! script classes declare as methods and FFI types register with
+//! [`ClassEntry::with_iterator`](angelscript_core::ClassEntry::with_iterator):
+//!
+//! | Operator        | Signature                 | Role                             |
+//! |-----------------|---------------------------|----------------------------------|
+//! | `opForBegin`    | `iter opForBegin()`       | first iterator                   |
+//! | `opForEnd`      | `bool opForEnd(iter)`     | `true` once past the last value  |
+//! | `opForNext`     | `iter opForNext(iter)`    | iterator of the next value       |
+//! | `opForValue`    | `T opForValue(iter)`      | the value, for one variable      |
+//! | `opForValue{N}` | `T opForValueN(iter)`     | value `N`, for several variables |
+//!
+//! `foreach (k, v : dict)` reads `opForValue0` into `k` and `opForValue1`
+//! into `v`; a single variable falls back to `opForValue0`.
+//! [`ForeachProtocol::resolve`] finds the operators for a number of
+//! variables and [`ForeachLoop`] emits the loop around the body:
+//!
+//! ```text
+//!        GET_LOCAL c; CALL_METHOD opForBegin 0; SET_LOCAL it; POP
+//! start: GET_LOCAL c; GET_LOCAL it; CALL_METHOD opForEnd 1; JUMP_IF_TRUE exit
+//!        GET_LOCAL c; GET_LOCAL it; CALL_METHOD opForValue0 1; SET_LOCAL k; POP
+//!        ...
+//!        body
+//! next:  GET_LOCAL c; GET_LOCAL it; CALL_METHOD opForNext 1; SET_LOCAL it; POP
+//!        LOOP start
+//! exit:
+//! ```
+
+use angelscript_core::{ClassEntry, CompilationError, DataType, Operator, Span, TypeHash};
+use angelscript_registry::SymbolRegistry;
+
+use crate::bytecode::{BytecodeChunk, ConstantPool, OpCode};
+
+/// Iterator operators of a container type, for a number of variables.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeachProtocol {
+    /// `opForBegin`.
+    pub begin: TypeHash,
+    /// `opForEnd`.
+    pub end: TypeHash,
+    /// `opForNext`.
+    pub next: TypeHash,
+    /// `opForValue` or `opForValue{N}`, one per variable.
+    pub values: Vec<TypeHash>,
+}
+
+impl ForeachProtocol {
+    /// Find the iterator operators of `container` for `vars` variables.
+    ///
+    /// Operators registered on the type or declared as methods of it or one
+    /// of its base classes are used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the type is not a class, or lacks one of the
+    /// operators.
+    pub fn resolve(
+        registry: &SymbolRegistry,
+        container: TypeHash,
+        vars: usize,
+        span: Span,
+    ) -> Result<Self, CompilationError> {
+        let Some(class) = registry.get(container).and_then(|entry| entry.as_class()) else {
+            return Err(CompilationError::InvalidOperation {
+                message: "foreach requires a container type".to_string(),
+                span,
+            });
+        };
+        let missing = |op: Operator| CompilationError::InvalidOperation {
+            message: format!(
+                "type '{}' cannot be used in foreach (missing {})",
+                class.qualified_name, op
+            ),
+            span,
+        };
+        let find = |op: Operator| operator(registry, class, op).ok_or_else(|| missing(op));
+
+        let values = if vars == 1 {
+            vec![
+                operator(registry, class, Operator::ForValue)
+                    .or_else(|| operator(registry, class, Operator::ForValueN(0)))
+                    .ok_or_else(|| missing(Operator::ForValue))?,
+            ]
+        } else {
+            let count = u8::try_from(vars)
+                .ok()
+                .filter(|&count| count > 0)
+                .ok_or_else(|| CompilationError::InvalidOperation {
+                    message: format!("foreach takes 1 to 255 variables, not {vars}"),
+                    span,
+                })?;
+            (0..count)
+                .map(|index| find(Operator::ForValueN(index)))
+                .collect::<Result<_, _>>()?
+        };
+
+        Ok(Self {
+            begin: find(Operator::ForBegin)?,
+            end: find(Operator::ForEnd)?,
+            next: find(Operator::ForNext)?,
+            values,
+        })
+    }
+
+    /// The types of the values read into the variables, used to deduce
+    /// `auto` variables. Unregistered operators give `void`.
+    pub fn value_types(&self, registry: &SymbolRegistry) -> Vec<DataType> {
+        self.values
+            .iter()
+            .map(|&value| {
+                registry
+                    .get_function(value)
+                    .map_or_else(DataType::void, |entry| entry.def.return_type)
+            })
+            .collect()
+    }
+}
+
+/// The first implementation of an operator on a class or its bases.
+pub(crate) fn operator(
+    registry: &SymbolRegistry,
+    class: &ClassEntry,
+    op: Operator,
+) -> Option<TypeHash> {
+    let name = op.to_string();
+    std::iter::once(class)
+        .chain(registry.base_class_chain(class.type_hash))
+        .find_map(|class| {
+            class
+                .behaviors
+                .get_operator(op)
+                .and_then(|hashes| hashes.first())
+                .or_else(|| class.find_methods(&name).first())
+                .copied()
+        })
+}
+
+/// A `foreach` loop whose header has been emitted.
+#[derive(Debug)]
+#[must_use = "the loop must be finished after its body"]
+pub struct ForeachLoop {
+    next: TypeHash,
+    container: u16,
+    iterator: u16,
+    start: usize,
+    exit: usize,
+    line: u32,
+}
+
+impl ForeachLoop {
+    /// Emit the loop header: the iterator initialization, the end test and
+    /// the reads of the values into `vars`.
+    ///
+    /// The container is read from local `container` and the iterator kept
+    /// in local `iterator`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `vars` and `protocol.values` differ in length.
+    pub fn begin(
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        protocol: &ForeachProtocol,
+        container: u16,
+        iterator: u16,
+        vars: &[u16],
+        line: u32,
+    ) -> Self {
+        assert_eq!(
+            vars.len(),
+            protocol.values.len(),
+            "one value operator per variable"
+        );
+
+        chunk.emit_get_local(container, line);
+        emit_call(chunk, constants, protocol.begin, 0, line);
+        chunk.emit_set_local(iterator, line);
+        chunk.write_op(OpCode::Pop, line);
+
+        let start = chunk.current_offset();
+        emit_iterator_call(chunk, constants, protocol.end, container, iterator, line);
+        let exit = chunk.emit_jump(OpCode::JumpIfTrue, line);
+
+        for (&value, &var) in protocol.values.iter().zip(vars) {
+            emit_iterator_call(chunk, constants, value, container, iterator, line);
+            chunk.emit_set_local(var, line);
+            chunk.write_op(OpCode::Pop, line);
+        }
+
+        Self {
+            next: protocol.next,
+            container,
+            iterator,
+            start,
+            exit,
+            line,
+        }
+    }
+
+    /// Emit the advance to the next value and the jump back, after the
+    /// body. Patch `continue` jumps before calling this, and `break` jumps
+    /// after.
+    pub fn finish(self, chunk: &mut BytecodeChunk, constants: &mut ConstantPool) {
+        let line = self.line;
+        emit_iterator_call(
+            chunk,
+            constants,
+            self.next,
+            self.container,
+            self.iterator,
+            line,
+        );
+        chunk.emit_set_local(self.iterator, line);
+        chunk.write_op(OpCode::Pop, line);
+        chunk.emit_loop(self.start, line);
+        chunk.patch_jump(self.exit);
+    }
+}
+
+/// `container.method(iterator)`
+fn emit_iterator_call(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    method: TypeHash,
+    container: u16,
+    iterator: u16,
+    line: u32,
+) {
+    chunk.emit_get_local(container, line);
+    chunk.emit_get_local(iterator, line);
+    emit_call(chunk, constants, method, 1, line);
+}
+
+fn emit_call(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    method: TypeHash,
+    args: u8,
+    line: u32,
+) {
+    let index = constants.add_type_hash(method);
+    chunk.write_op(OpCode::CallMethod, line);
+    chunk.write_u16(index as u16, line);
+    chunk.write_byte(args, line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::TypeKind;
+
+    fn hash(name: &str) -> TypeHash {
+        TypeHash::from_name(name)
+    }
+
+    fn registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+
+        // FFI dictionary iterating keys and values
+        let dict = ClassEntry::ffi("dictionary", TypeKind::reference()).with_iterator(
+            hash("dict::begin"),
+            hash("dict::end"),
+            hash("dict::next"),
+            &[hash("dict::key"), hash("dict::value")],
+        );
+        registry.register_type(dict.into()).unwrap();
+
+        // Script class declaring the operators as methods, on its base
+        let base = ClassEntry::ffi("Base", TypeKind::reference())
+            .with_method("opForBegin", hash("Base::opForBegin"))
+            .with_method("opForEnd", hash("Base::opForEnd"))
+            .with_method("opForNext", hash("Base::opForNext"))
+            .with_method("opForValue", hash("Base::opForValue"));
+        let base_hash = base.type_hash;
+        registry.register_type(base.into()).unwrap();
+        let list = ClassEntry::ffi("List", TypeKind::reference()).with_base(base_hash);
+        registry.register_type(list.into()).unwrap();
+        registry
+    }
+
+    #[test]
+    fn resolves_multi_value_iteration() {
+        let registry = registry();
+        let span = Span::new(1, 1, 1);
+
+        let protocol =
+            ForeachProtocol::resolve(&registry, TypeHash::from_name("dictionary"), 2, span)
+                .unwrap();
+        assert_eq!(protocol.begin, hash("dict::begin"));
+        assert_eq!(
+            protocol.values,
+            vec![hash("dict::key"), hash("dict::value")]
+        );
+
+        // A single variable takes the first value
+        let keys = ForeachProtocol::resolve(&registry, TypeHash::from_name("dictionary"), 1, span)
+            .unwrap();
+        assert_eq!(keys.values, vec![hash("dict::key")]);
+        let error = ForeachProtocol::resolve(&registry, TypeHash::from_name("dictionary"), 3, span)
+            .unwrap_err();
+        assert!(error.to_string().contains("missing opForValue2"), "{error}");
+    }
+
+    #[test]
+    fn resolves_inherited_script_methods() {
+        let registry = registry();
+        let protocol = ForeachProtocol::resolve(
+            &registry,
+            TypeHash::from_name("List"),
+            1,
+            Span::new(1, 1, 1),
+        )
+        .unwrap();
+        assert_eq!(protocol.next, hash("Base::opForNext"));
+        assert_eq!(protocol.values, vec![hash("Base::opForValue")]);
+
+        let int = angelscript_core::primitives::INT32;
+        assert!(ForeachProtocol::resolve(&registry, int, 1, Span::new(1, 1, 1)).is_err());
+    }
+
+    #[test]
+    fn emits_loop_around_body() {
+        let protocol = ForeachProtocol {
+            begin: hash("begin"),
+            end: hash("end"),
+            next: hash("next"),
+            values: vec![hash("key"), hash("value")],
+        };
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+
+        let foreach = ForeachLoop::begin(&mut chunk, &mut constants, &protocol, 0, 1, &[2, 3], 1);
+        chunk.write_op(OpCode::PushOne, 2);
+        chunk.write_op(OpCode::Pop, 2);
+        foreach.finish(&mut chunk, &mut constants);
+        chunk.write_op(OpCode::ReturnVoid, 3);
+
+        #[rustfmt::skip]
+        chunk.assert_opcodes(&[
+            OpCode::GetLocal, OpCode::CallMethod, OpCode::SetLocal, OpCode::Pop,
+            OpCode::GetLocal, OpCode::GetLocal, OpCode::CallMethod, OpCode::JumpIfTrue,
+            OpCode::GetLocal, OpCode::GetLocal, OpCode::CallMethod, OpCode::SetLocal, OpCode::Pop,
+            OpCode::GetLocal, OpCode::GetLocal, OpCode::CallMethod, OpCode::SetLocal, OpCode::Pop,
+            OpCode::PushOne, OpCode::Pop,
+            OpCode::GetLocal, OpCode::GetLocal, OpCode::CallMethod, OpCode::SetLocal, OpCode::Pop,
+            OpCode::Loop,
+            OpCode::ReturnVoid,
+        ]);
+
+        // The end test exits past the loop, and the loop returns to it
+        let disasm = chunk.disassemble(&constants);
+        let exit = disasm.lines().find(|l| l.contains("JUMP_IF_TRUE")).unwrap();
+        let ret = disasm.lines().find(|l| l.contains("RETURN_VOID")).unwrap();
+        assert!(exit.ends_with(&format!("-> {}", &ret[..4])), "{disasm}");
+        let back = disasm.lines().find(|l| l.contains("LOOP")).unwrap();
+        assert!(back.ends_with("-> 0009"), "{disasm}");
+    }
+}
@@ -0,0 +1,542 @@
+//! Reuse of compiled functions across builds.
+//!
+//! After a small edit most of a script compiles to the same bytecode as
+//! before. [`Fingerprints`] summarize what each function's code depends on,
+//! and a [`BuildCache`] keeps the functions of the last build with their
+//! fingerprints, so the next build only compiles functions whose fingerprint
+//! changed:
+//!
+//! ```ignore
+//! let fingerprints = Fingerprints::of(source, &script);
+//! let mut compiler = Compiler::new(&registry, unit_id, options);
+//! if let Some(cache) = &cache {
+//!     compiler = compiler.with_cache(cache, &fingerprints);
+//! }
+//! let key = compiler.cache_key();
+//! let result = compiler.compile(&script);
+//! cache = Some(BuildCache::new(&result.module, fingerprints, key));
+//! ```
+//!
+//! A function's fingerprint covers:
+//!
+//! - the tokens of its body with their positions relative to its opening
+//!   brace, since its line table must still match the source;
+//! - the tokens of everything outside function bodies (signatures, classes,
+//!   globals, enums...), which any function may use;
+//! - the fingerprints of the functions it calls by name, since their bodies
+//!   can be inlined or evaluated at compile time.
+//!
+//! Overloads share a name and are fingerprinted, and reused, together. A
+//! reused function must also have the same lambdas in the module's closure
+//! table, since `NEW_CLOSURE` refers to them by index, and the build must
+//! have the same [`CacheKey`]: options, pragmas, access mask and registry
+//! generation. Its bytecode is kept as it was after
+//! inlining, optimization and the plugins' `after_emit`, and the new
+//! module's constant pool starts from the cached one so its constant indices
+//! stay valid.
+//!
+//! Moving a whole function up or down does not change its fingerprint; the
+//! reused bytecode's line table is shifted to the new lines. The global
+//! initializers are fingerprinted with the positions of every declaration.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{CallExpr, Expr, FunctionDecl, Script};
+use angelscript_parser::{Lexer, Span, Token, TokenKind};
+use bumpalo::Bump;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use angelscript_parser::directives::{SectionOptions, Suppressions};
+
+use crate::bytecode::ConstantPool;
+use crate::{
+    AccessMask, CompiledClosure, CompiledFunction, CompiledModule, CompilerOptions, plugin,
+};
+
+/// Name under which global variable initializers are fingerprinted.
+const GLOBAL_INITS: &str = "";
+
+/// What the compiled code of each function of a script depends on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Fingerprints {
+    functions: FxHashMap<String, u64>,
+    /// Lines of the opening braces of the bodies of each name.
+    lines: FxHashMap<String, Vec<u32>>,
+}
+
+impl Fingerprints {
+    /// Fingerprint the functions of `script`, parsed from `source`.
+    pub fn of(source: &str, script: &Script<'_>) -> Self {
+        let arena = Bump::new();
+        let mut lexer = Lexer::new(source, &arena);
+        let mut tokens = Vec::new();
+        loop {
+            let token = lexer.next_token();
+            if token.kind == TokenKind::Eof {
+                break;
+            }
+            tokens.push(token);
+        }
+
+        let mut bodies = Vec::new();
+        plugin::function_bodies(script.items(), "", &mut bodies);
+
+        let mut in_body = vec![false; tokens.len()];
+        let mut body_hashes: FxHashMap<&str, DefaultHasher> = FxHashMap::default();
+        let mut lines: FxHashMap<String, Vec<u32>> = FxHashMap::default();
+        let mut calls: FxHashMap<&str, FxHashSet<String>> = FxHashMap::default();
+        for (name, decl) in &bodies {
+            let hasher = body_hashes.entry(name).or_default();
+            if let Some(range) = decl.body.and_then(|body| body_range(&tokens, body.span)) {
+                let line = tokens[range.start].span.line;
+                for token in &tokens[range.clone()] {
+                    hash_token(token, hasher);
+                    (token.span.line - line, token.span.col).hash(hasher);
+                }
+                lines.entry(name.clone()).or_default().push(line);
+                in_body[range].fill(true);
+            }
+            calls.entry(name).or_default().extend(called_names(decl));
+        }
+
+        let mut declarations = DefaultHasher::new();
+        let mut global_inits = DefaultHasher::new();
+        for (token, _) in tokens.iter().zip(&in_body).filter(|(_, inside)| !**inside) {
+            hash_token(token, &mut declarations);
+            hash_token(token, &mut global_inits);
+            token.span.hash(&mut global_inits);
+        }
+        let declarations = declarations.finish();
+
+        let bodies: FxHashMap<&str, u64> = body_hashes
+            .into_iter()
+            .map(|(name, hasher)| (name, hasher.finish()))
+            .collect();
+        // Calls are matched by the last segment of the name, so a call may
+        // depend on more functions than it can reach
+        let mut by_simple_name: FxHashMap<&str, Vec<&str>> = FxHashMap::default();
+        for &name in bodies.keys() {
+            by_simple_name
+                .entry(simple_name(name))
+                .or_default()
+                .push(name);
+        }
+
+        let mut functions = FxHashMap::default();
+        functions.insert(GLOBAL_INITS.to_string(), global_inits.finish());
+        for &name in bodies.keys() {
+            let mut reached = vec![name];
+            let mut seen: FxHashSet<&str> = FxHashSet::from_iter([name]);
+            let mut next = 0;
+            while let Some(&current) = reached.get(next) {
+                next += 1;
+                for called in calls.get(current).into_iter().flatten() {
+                    for &callee in by_simple_name.get(called.as_str()).into_iter().flatten() {
+                        if seen.insert(callee) {
+                            reached.push(callee);
+                        }
+                    }
+                }
+            }
+            reached.sort_unstable();
+
+            let mut hasher = DefaultHasher::new();
+            declarations.hash(&mut hasher);
+            for function in reached {
+                function.hash(&mut hasher);
+                bodies[function].hash(&mut hasher);
+            }
+            functions.insert(name.to_string(), hasher.finish());
+        }
+        Self { functions, lines }
+    }
+
+    /// Fingerprint of the functions named `name`, or `None` if the script
+    /// has no body with that name.
+    pub fn get(&self, name: &str) -> Option<u64> {
+        self.functions.get(name).copied()
+    }
+
+    /// Number of fingerprinted names, including the global initializers.
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    /// Check if nothing was fingerprinted.
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+}
+
+/// Everything besides the script's tokens that decides what its functions
+/// compile to.
+///
+/// Pragmas are skipped by the lexer, so the options and suppressions they
+/// select are not covered by the [`Fingerprints`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheKey {
+    /// Options set by the host.
+    pub options: CompilerOptions,
+    /// Language options selected by the section's pragmas.
+    pub section_options: SectionOptions,
+    /// Warnings suppressed by the section's pragmas and comments.
+    pub suppressions: Suppressions,
+    /// Access mask of the unit, if its access is restricted.
+    pub access: Option<AccessMask>,
+    /// Generation of the registry, access masks and plugins.
+    pub registry_generation: u64,
+}
+
+/// The functions of a build, kept to be reused by the next one.
+#[derive(Debug, Clone)]
+pub struct BuildCache {
+    key: CacheKey,
+    fingerprints: Fingerprints,
+    /// Compiled functions by the name they are fingerprinted under.
+    functions: FxHashMap<String, Vec<CompiledFunction>>,
+    global_inits: Vec<CompiledFunction>,
+    closures: Vec<CompiledClosure>,
+    constants: ConstantPool,
+}
+
+impl BuildCache {
+    /// Keep the functions of `module`, compiled with `key` from a script
+    /// with `fingerprints`.
+    pub fn new(module: &CompiledModule, fingerprints: Fingerprints, key: CacheKey) -> Self {
+        let mut functions: FxHashMap<String, Vec<CompiledFunction>> = FxHashMap::default();
+        for function in &module.functions {
+            functions
+                .entry(owner(&function.name).to_string())
+                .or_default()
+                .push(function.clone());
+        }
+        Self {
+            key,
+            fingerprints,
+            functions,
+            global_inits: module.global_inits.clone(),
+            closures: module.closures.clone(),
+            constants: module.constants.clone(),
+        }
+    }
+
+    /// Names whose cached functions are still what compiling a script with
+    /// `fingerprints` and `closures` with `key` would produce, sorted.
+    ///
+    /// The empty name stands for the global initializers.
+    pub fn reusable(
+        &self,
+        fingerprints: &Fingerprints,
+        closures: &[CompiledClosure],
+        key: &CacheKey,
+    ) -> Vec<String> {
+        if *key != self.key {
+            return Vec::new();
+        }
+        let mut names: Vec<String> = fingerprints
+            .functions
+            .iter()
+            .filter(|&(name, fingerprint)| {
+                self.fingerprints.get(name) == Some(*fingerprint)
+                    && self.line_shift(name, fingerprints).is_some()
+                    && closure_indices(&self.closures, name) == closure_indices(closures, name)
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// The cached functions fingerprinted under `name`, with their spans
+    /// moved to where the bodies are in the script with `fingerprints`.
+    ///
+    /// Empty if the bodies did not all move by the same number of lines.
+    pub fn reuse(&self, name: &str, fingerprints: &Fingerprints) -> Vec<CompiledFunction> {
+        let Some(shift) = self.line_shift(name, fingerprints) else {
+            return Vec::new();
+        };
+        let cached = match name {
+            GLOBAL_INITS => &self.global_inits[..],
+            _ => self.functions.get(name).map_or(&[][..], Vec::as_slice),
+        };
+        cached
+            .iter()
+            .map(|function| {
+                let mut function = function.clone();
+                function.bytecode.shift_lines(shift);
+                function
+            })
+            .collect()
+    }
+
+    /// How many lines the bodies named `name` moved, if they all moved
+    /// together.
+    fn line_shift(&self, name: &str, fingerprints: &Fingerprints) -> Option<i64> {
+        let empty = Vec::new();
+        let old = self.fingerprints.lines.get(name).unwrap_or(&empty);
+        let new = fingerprints.lines.get(name).unwrap_or(&empty);
+        if old.len() != new.len() {
+            return None;
+        }
+        let mut shifts = old
+            .iter()
+            .zip(new)
+            .map(|(&old, &new)| i64::from(new) - i64::from(old));
+        let shift = shifts.next().unwrap_or(0);
+        shifts.all(|other| other == shift).then_some(shift)
+    }
+
+    /// The constant pool the cached functions refer to.
+    pub fn constants(&self) -> &ConstantPool {
+        &self.constants
+    }
+}
+
+/// Name a compiled function is fingerprinted under: local functions,
+/// compiled to `outer$name`, belong to `outer`.
+fn owner(name: &str) -> &str {
+    name.split_once('$').map_or(name, |(outer, _)| outer)
+}
+
+fn simple_name(name: &str) -> &str {
+    name.rsplit_once("::").map_or(name, |(_, simple)| simple)
+}
+
+/// Indices of the closures declared by the functions named `name`.
+fn closure_indices(closures: &[CompiledClosure], name: &str) -> Vec<usize> {
+    closures
+        .iter()
+        .enumerate()
+        .filter(|(_, closure)| owner(&closure.function) == name)
+        .map(|(index, _)| index)
+        .collect()
+}
+
+fn hash_token(token: &Token<'_>, hasher: &mut impl Hasher) {
+    token.kind.hash(hasher);
+    token.lexeme.hash(hasher);
+}
+
+/// Indices of the tokens of the block starting at `span`, braces included.
+fn body_range(tokens: &[Token<'_>], span: Span) -> Option<std::ops::Range<usize>> {
+    let open = tokens.iter().position(|token| {
+        token.kind == TokenKind::LeftBrace
+            && token.span.line == span.line
+            && token.span.col == span.col
+    })?;
+    let mut depth = 0usize;
+    for (index, token) in tokens.iter().enumerate().skip(open) {
+        match token.kind {
+            TokenKind::LeftBrace => depth += 1,
+            TokenKind::RightBrace => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open..index + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Last segments of the names a function calls directly.
+fn called_names(decl: &FunctionDecl<'_>) -> FxHashSet<String> {
+    let mut pass = Calls {
+        names: FxHashSet::default(),
+    };
+    if let Some(body) = &decl.body {
+        pass.visit_block(body);
+    }
+    pass.names
+}
+
+struct Calls {
+    names: FxHashSet<String>,
+}
+
+impl<'ast> Visitor<'ast> for Calls {
+    fn visit_call_expr(&mut self, expr: &CallExpr<'ast>) {
+        if let Expr::Ident(callee) = expr.callee {
+            self.names.insert(callee.ident.name.to_string());
+        }
+        visitor::walk_call_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::{BytecodeChunk, OpCode};
+    use crate::{Compiler, FunctionSignature};
+    use angelscript_core::{DataType, UnitId};
+    use angelscript_parser::ast::Parser;
+    use angelscript_parser::directives::Directives;
+    use angelscript_registry::SymbolRegistry;
+
+    const SOURCE: &str = "
+        int twice(int x) { return x * 2; }
+        int quad(int x) { return twice(twice(x)); }
+        void log(int x) { }
+        void log(float x) { }
+        namespace game { void tick() { log(1); } }
+    ";
+
+    fn fingerprints(source: &str) -> Fingerprints {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        Fingerprints::of(source, &script)
+    }
+
+    fn changed(before: &str, after: &str) -> Vec<String> {
+        let (before, after) = (fingerprints(before), fingerprints(after));
+        let mut names: Vec<String> = after
+            .functions
+            .keys()
+            .filter(|name| before.get(name) != after.get(name))
+            .cloned()
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn edits_change_the_function_and_its_callers() {
+        assert_eq!(fingerprints(SOURCE).len(), 5);
+        assert!(changed(SOURCE, SOURCE).is_empty());
+
+        // Moving functions only changes the global initializers, whose
+        // positions are not kept relative to anything
+        let moved = SOURCE.replace("int twice", "// doubles\n\n        int twice");
+        assert_eq!(changed(SOURCE, &moved), [""]);
+        // Layout inside a body does
+        let spaced = SOURCE.replace("x * 2", "x  * 2");
+        assert_eq!(changed(SOURCE, &spaced), ["quad", "twice"]);
+
+        let edited = SOURCE.replace("x * 2", "x + x");
+        assert_eq!(changed(SOURCE, &edited), ["quad", "twice"]);
+
+        // Overloads change together
+        let edited = SOURCE.replace("void log(float x) { }", "void log(float x) { x++; }");
+        assert_eq!(changed(SOURCE, &edited), ["game::tick", "log"]);
+    }
+
+    #[test]
+    fn declaration_edits_change_everything() {
+        let edited = format!("int lives = 3;\n{SOURCE}");
+        assert_eq!(
+            changed(SOURCE, &edited),
+            ["", "game::tick", "log", "quad", "twice"]
+        );
+    }
+
+    fn function(name: &str, op: OpCode) -> CompiledFunction {
+        let mut bytecode = BytecodeChunk::new();
+        bytecode.write_op(op, 1);
+        bytecode.write_op(OpCode::Return, 1);
+        CompiledFunction {
+            name: name.to_string(),
+            signature: FunctionSignature::new(vec![], DataType::void()),
+            bytecode,
+        }
+    }
+
+    #[test]
+    fn unchanged_functions_are_reused() {
+        let registry = SymbolRegistry::with_primitives();
+        let options = CompilerOptions::new();
+        let mut module = CompiledModule::default();
+        module.constants.add_int(42);
+        module.functions = vec![
+            function("twice", OpCode::PushOne),
+            function("quad", OpCode::PushZero),
+            function("log", OpCode::PushTrue),
+            function("log", OpCode::PushFalse),
+        ];
+        let key = Compiler::new(&registry, UnitId::new(0), options.clone()).cache_key();
+        let cache = BuildCache::new(&module, fingerprints(SOURCE), key);
+
+        let edited = SOURCE.replace("return twice(twice(x));", "return twice(x) * 2;");
+        let arena = Bump::new();
+        let script = Parser::parse(&edited, &arena).unwrap();
+        let fingerprints = Fingerprints::of(&edited, &script);
+        let result = Compiler::new(&registry, UnitId::new(0), options.clone())
+            .with_cache(&cache, &fingerprints)
+            .compile(&script);
+        assert!(result.is_success(), "{:?}", result.errors);
+        assert_eq!(result.reused, ["", "game::tick", "log", "twice"]);
+
+        let names: Vec<_> = result
+            .module
+            .functions
+            .iter()
+            .map(|f| f.name.as_str())
+            .collect();
+        assert_eq!(names, ["log", "log", "twice"]);
+        assert_eq!(
+            result.module.functions[0].bytecode.code()[0],
+            OpCode::PushTrue as u8
+        );
+        assert_eq!(result.module.constants.len(), 1);
+
+        // Moved functions are reused on their new lines
+        let moved = format!("\n\n{SOURCE}");
+        let script = Parser::parse(&moved, &arena).unwrap();
+        let fingerprints = Fingerprints::of(&moved, &script);
+        let result = Compiler::new(&registry, UnitId::new(0), options.clone())
+            .with_cache(&cache, &fingerprints)
+            .compile(&script);
+        assert_eq!(result.reused, ["game::tick", "log", "quad", "twice"]);
+        assert_eq!(result.module.functions[0].bytecode.line_at(0), Some(3));
+
+        // Other options compile everything again
+        let other = options.clone().with_inline_size(0);
+        let result = Compiler::new(&registry, UnitId::new(0), other)
+            .with_cache(&cache, &fingerprints)
+            .compile(&script);
+        assert!(result.reused.is_empty());
+        assert!(result.module.functions.is_empty());
+    }
+
+    #[test]
+    fn pragmas_access_and_registry_are_part_of_the_key() {
+        let registry = SymbolRegistry::with_primitives();
+        let options = CompilerOptions::new();
+        let module = CompiledModule {
+            functions: vec![function("twice", OpCode::PushOne)],
+            ..CompiledModule::default()
+        };
+        let key = Compiler::new(&registry, UnitId::new(0), options.clone()).cache_key();
+        let cache = BuildCache::new(&module, fingerprints(SOURCE), key);
+
+        let arena = Bump::new();
+        let script = Parser::parse(SOURCE, &arena).unwrap();
+        let fingerprints = Fingerprints::of(SOURCE, &script);
+        let compiler = || {
+            Compiler::new(&registry, UnitId::new(0), options.clone())
+                .with_cache(&cache, &fingerprints)
+        };
+        assert!(!compiler().compile(&script).reused.is_empty());
+
+        let strict = SectionOptions {
+            strict: true,
+            ..SectionOptions::default()
+        };
+        let masks = crate::AccessMasks::new();
+        let builds = [
+            compiler().with_section_options(strict),
+            compiler().with_suppressions(
+                Directives::scan("// as-ignore[W0001]\nint x;")
+                    .suppressions()
+                    .clone(),
+            ),
+            compiler().with_access(&masks, AccessMask::new(1)),
+            compiler().with_registry_generation(1),
+        ];
+        for build in builds {
+            assert!(build.compile(&script).reused.is_empty());
+        }
+    }
+}
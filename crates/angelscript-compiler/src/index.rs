@@ -0,0 +1,370 @@
+//! Lowering of index expressions.
+//!
+//! A type supports `obj[i]` through its index operators, which script
+//! classes declare as methods and FFI types register as operators:
+//!
+//! | Operator      | Signature                  | Role                         |
+//! |---------------|----------------------------|------------------------------|
+//! | `opIndex`     | `T &opIndex(idx)`          | reference to the element     |
+//! | `get_opIndex` | `T get_opIndex(idx)`       | read the element             |
+//! | `set_opIndex` | `void set_opIndex(idx, T)` | write the element            |
+//!
+//! An `opIndex` returning a value rather than a reference is read-only, and
+//! only used for reads. [`IndexAccess`] lowers reads, writes and compound
+//! assignments of an element, with the object and index on the stack:
+//!
+//! ```text
+//! arr[i] += 1   // arr; i; CALL_METHOD opIndex 1; DUP; LOAD_REF; PUSH_ONE; ADD; STORE_REF; POP
+//! map[k] += 1   // map; k; PICK 1; PICK 1; CALL_METHOD get_opIndex 1; PUSH_ONE; ADD;
+//!               //   CALL_METHOD set_opIndex 2
+//! ```
+
+use angelscript_core::{CompilationError, Operator, RefModifier, Span, TypeHash};
+use angelscript_parser::ast::AssignOp;
+use angelscript_registry::SymbolRegistry;
+
+use crate::bytecode::{BytecodeChunk, ConstantPool, OpCode};
+use crate::foreach::operator;
+use crate::property::{AssignValue, compound_opcode, emit_call};
+
+/// Operator calls for reading and writing an element of an object.
+///
+/// All emitters expect the object and the index on top of the stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexAccess {
+    /// Indexed type name, for diagnostics.
+    pub type_name: String,
+    /// `opIndex` returning a reference, used for reads and writes alike.
+    pub reference: Option<TypeHash>,
+    /// `get_opIndex`, or an `opIndex` returning a value.
+    pub getter: Option<TypeHash>,
+    /// `set_opIndex`.
+    pub setter: Option<TypeHash>,
+}
+
+impl IndexAccess {
+    /// Find the index operators of `container`.
+    ///
+    /// Operators registered on the type or declared as methods of it or one
+    /// of its base classes are used. An `opIndex` whose function is not
+    /// registered yet is assumed to return a reference.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the type is not a class, or has no index
+    /// operators.
+    pub fn resolve(
+        registry: &SymbolRegistry,
+        container: TypeHash,
+        span: Span,
+    ) -> Result<Self, CompilationError> {
+        let Some(class) = registry.get(container).and_then(|entry| entry.as_class()) else {
+            return Err(CompilationError::InvalidOperation {
+                message: "only objects can be indexed".to_string(),
+                span,
+            });
+        };
+
+        let mut access = Self {
+            type_name: class.qualified_name.clone(),
+            reference: None,
+            getter: operator(registry, class, Operator::IndexGet),
+            setter: operator(registry, class, Operator::IndexSet),
+        };
+        if let Some(op_index) = operator(registry, class, Operator::Index) {
+            let by_value = registry
+                .get_function(op_index)
+                .is_some_and(|entry| entry.def.return_type.ref_modifier == RefModifier::None);
+            match by_value {
+                true => access.getter = access.getter.or(Some(op_index)),
+                false => access.reference = Some(op_index),
+            }
+        }
+
+        if access.reference.is_none() && access.getter.is_none() && access.setter.is_none() {
+            return Err(CompilationError::InvalidOperation {
+                message: format!("type '{}' cannot be indexed", access.type_name),
+                span,
+            });
+        }
+        Ok(access)
+    }
+
+    /// `[obj, idx] -> [value]`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the elements cannot be read.
+    pub fn emit_get(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        span: Span,
+    ) -> Result<(), CompilationError> {
+        chunk.set_span(span);
+        if let Some(reference) = self.reference {
+            emit_call(chunk, constants, reference, 1, span.line);
+            chunk.write_op(OpCode::LoadRef, span.line);
+            return Ok(());
+        }
+        let getter = self
+            .getter
+            .ok_or_else(|| self.missing("write-only", span))?;
+        emit_call(chunk, constants, getter, 1, span.line);
+        Ok(())
+    }
+
+    /// `[obj, idx] -> []` or `[obj, idx] -> [value]`, for plain and
+    /// compound assignments alike. `rhs` emits the right-hand side
+    /// (`[] -> [value]`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the elements lack an operator the assignment
+    /// needs.
+    pub fn emit_assign<F>(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        op: AssignOp,
+        rhs: F,
+        value: AssignValue,
+        span: Span,
+    ) -> Result<(), CompilationError>
+    where
+        F: FnOnce(&mut BytecodeChunk, &mut ConstantPool),
+    {
+        chunk.set_span(span);
+        let opcode = compound_opcode(op);
+
+        if let Some(reference) = self.reference {
+            emit_call(chunk, constants, reference, 1, span.line);
+            if let Some(opcode) = opcode {
+                chunk.write_op(OpCode::Dup, span.line);
+                chunk.write_op(OpCode::LoadRef, span.line);
+                rhs(chunk, constants);
+                // The right-hand side may have set spans of its own
+                chunk.set_span(span);
+                chunk.write_op(opcode, span.line);
+            } else {
+                rhs(chunk, constants);
+                chunk.set_span(span);
+            }
+            chunk.write_op(OpCode::StoreRef, span.line);
+            if value == AssignValue::Discard {
+                chunk.write_op(OpCode::Pop, span.line);
+            }
+            return Ok(());
+        }
+
+        let getter = match opcode {
+            Some(_) => Some(
+                self.getter
+                    .ok_or_else(|| self.missing("write-only", span))?,
+            ),
+            None => None,
+        };
+        let setter = self.setter.ok_or_else(|| self.missing("read-only", span))?;
+
+        if let (Some(opcode), Some(getter)) = (opcode, getter) {
+            // Keep the object and index for the setter
+            for _ in 0..2 {
+                chunk.write_op(OpCode::Pick, span.line);
+                chunk.write_byte(1, span.line);
+            }
+            emit_call(chunk, constants, getter, 1, span.line);
+            rhs(chunk, constants);
+            chunk.set_span(span);
+            chunk.write_op(opcode, span.line);
+        } else {
+            rhs(chunk, constants);
+            chunk.set_span(span);
+        }
+        value.emit_keep(chunk, span.line);
+        emit_call(chunk, constants, setter, 2, span.line);
+        value.emit_restore(chunk, span.line);
+        Ok(())
+    }
+
+    fn missing(&self, kind: &str, span: Span) -> CompilationError {
+        CompilationError::InvalidOperation {
+            message: format!("elements of type '{}' are {}", self.type_name, kind),
+            span,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{
+        ClassEntry, DataType, FunctionDef, FunctionEntry, FunctionTraits, Param, TypeKind,
+        Visibility, primitives,
+    };
+
+    fn hash(name: &str) -> TypeHash {
+        TypeHash::from_name(name)
+    }
+
+    fn span() -> Span {
+        Span::new(1, 1, 1)
+    }
+
+    fn register_op_index(registry: &mut SymbolRegistry, name: &str, returns: DataType) {
+        let def = FunctionDef::new(
+            hash(name),
+            "opIndex".to_string(),
+            Vec::new(),
+            vec![Param::new("i", DataType::simple(primitives::UINT32))],
+            returns,
+            Some(hash("array")),
+            FunctionTraits::default(),
+            true,
+            Visibility::Public,
+        );
+        registry.register_function(FunctionEntry::ffi(def)).unwrap();
+    }
+
+    fn registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+
+        // FFI array returning references to its elements
+        let mut array = ClassEntry::ffi("array", TypeKind::reference());
+        array
+            .behaviors
+            .add_operator(Operator::Index, hash("array::opIndex"));
+        registry.register_type(array.into()).unwrap();
+        register_op_index(
+            &mut registry,
+            "array::opIndex",
+            DataType::with_ref_inout(primitives::INT32),
+        );
+
+        // FFI view returning copies
+        let mut view = ClassEntry::ffi("view", TypeKind::reference());
+        view.behaviors
+            .add_operator(Operator::Index, hash("view::opIndex"));
+        registry.register_type(view.into()).unwrap();
+        register_op_index(
+            &mut registry,
+            "view::opIndex",
+            DataType::simple(primitives::INT32),
+        );
+
+        // Script class declaring accessors, on its base
+        let base = ClassEntry::ffi("Base", TypeKind::reference())
+            .with_method("get_opIndex", hash("Base::get_opIndex"))
+            .with_method("set_opIndex", hash("Base::set_opIndex"));
+        let base_hash = base.type_hash;
+        registry.register_type(base.into()).unwrap();
+        let map = ClassEntry::ffi("Map", TypeKind::reference()).with_base(base_hash);
+        registry.register_type(map.into()).unwrap();
+        registry
+    }
+
+    #[test]
+    fn resolves_references_and_accessors() {
+        let registry = registry();
+
+        let array = IndexAccess::resolve(&registry, hash("array"), span()).unwrap();
+        assert_eq!(array.reference, Some(hash("array::opIndex")));
+        assert_eq!(array.getter, None);
+
+        let view = IndexAccess::resolve(&registry, hash("view"), span()).unwrap();
+        assert_eq!(view.reference, None);
+        assert_eq!(view.getter, Some(hash("view::opIndex")));
+
+        let map = IndexAccess::resolve(&registry, hash("Map"), span()).unwrap();
+        assert_eq!(map.getter, Some(hash("Base::get_opIndex")));
+        assert_eq!(map.setter, Some(hash("Base::set_opIndex")));
+
+        assert!(IndexAccess::resolve(&registry, primitives::INT32, span()).is_err());
+    }
+
+    #[test]
+    fn compound_assignment_through_reference() {
+        let registry = registry();
+        let array = IndexAccess::resolve(&registry, hash("array"), span()).unwrap();
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        array
+            .emit_assign(
+                &mut chunk,
+                &mut constants,
+                AssignOp::AddAssign,
+                |chunk, _| chunk.write_op(OpCode::PushOne, 1),
+                AssignValue::Discard,
+                span(),
+            )
+            .unwrap();
+        chunk.assert_opcodes(&[
+            OpCode::CallMethod,
+            OpCode::Dup,
+            OpCode::LoadRef,
+            OpCode::PushOne,
+            OpCode::Add,
+            OpCode::StoreRef,
+            OpCode::Pop,
+        ]);
+
+        // Reads go through the same reference
+        let mut chunk = BytecodeChunk::new();
+        array.emit_get(&mut chunk, &mut constants, span()).unwrap();
+        chunk.assert_opcodes(&[OpCode::CallMethod, OpCode::LoadRef]);
+    }
+
+    #[test]
+    fn compound_assignment_through_accessors() {
+        let registry = registry();
+        let map = IndexAccess::resolve(&registry, hash("Map"), span()).unwrap();
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        map.emit_assign(
+            &mut chunk,
+            &mut constants,
+            AssignOp::MulAssign,
+            |chunk, _| chunk.write_op(OpCode::PushOne, 1),
+            AssignValue::Keep { temp: 2 },
+            span(),
+        )
+        .unwrap();
+        #[rustfmt::skip]
+        chunk.assert_opcodes(&[
+            OpCode::Pick, OpCode::Pick, OpCode::CallMethod,
+            OpCode::PushOne, OpCode::Mul,
+            OpCode::SetLocal, OpCode::CallMethod, OpCode::GetLocal,
+        ]);
+
+        let disasm = chunk.disassemble(&constants);
+        assert!(disasm.contains("args=2"), "{disasm}");
+    }
+
+    #[test]
+    fn read_only_elements_are_errors() {
+        let registry = registry();
+        let view = IndexAccess::resolve(&registry, hash("view"), span()).unwrap();
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+
+        let error = view
+            .emit_assign(
+                &mut chunk,
+                &mut constants,
+                AssignOp::Assign,
+                |_, _| {},
+                AssignValue::Discard,
+                span(),
+            )
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("elements of type 'view' are read-only"),
+            "{error}"
+        );
+        assert!(chunk.is_empty());
+
+        view.emit_get(&mut chunk, &mut constants, span()).unwrap();
+        chunk.assert_opcodes(&[OpCode::CallMethod]);
+    }
+}
@@ -0,0 +1,502 @@
+//! Init list matching.
+//!
+//! An init list builds an object of the type it initializes, which is the
+//! declared type of a variable or the type of the parameter it is passed
+//! to:
+//!
+//! ```text
+//! Vec2 v = {1, 2};                  // Vec2(float, float)
+//! takeVec({1, 2});                  // same, for the parameter
+//! Line l = {{0, 0}, {1, 1}};        // Line(Vec2, Vec2), each Vec2 from a list
+//! array<Vec2> a = {{0, 0}, {1, 1}}; // list factory, each element from a list
+//! ```
+//!
+//! A type's list behaviors are tried first: `list_construct` for value
+//! types and `list_factory` for reference types, matched against their
+//! [`ListPattern`]. Otherwise a value type is constructed with the elements
+//! as constructor arguments, choosing the overload like a call does. Nested
+//! lists are matched recursively against the element or parameter type
+//! they initialize.
+//!
+//! A list behavior receives the elements in a list buffer:
+//!
+//! ```text
+//! INIT_LIST_BEGIN 2; <elements>; INIT_LIST_END; NEW_FACTORY array<Vec2>::$list 1
+//! ```
+//!
+//! while a constructor receives them as arguments: `<args>; NEW Vec2::Vec2 2`.
+
+use angelscript_core::{
+    ClassEntry, CompilationError, DataType, ListPattern, RefModifier, Span, TypeHash,
+};
+use angelscript_parser::ast::{Expr, InitElement, InitListExpr};
+use angelscript_registry::SymbolRegistry;
+
+use crate::bytecode::{BytecodeChunk, ConstantPool, OpCode};
+use crate::overload::{Conversion, is_better, rank_implicit_conversion};
+
+/// Types of the elements of an init list, as written.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitShape {
+    /// An expression of the given type.
+    Value(DataType),
+    /// A nested init list.
+    List(Vec<InitShape>),
+}
+
+impl InitShape {
+    /// The shape of an init list, given the type of each expression in it.
+    pub fn of(list: &InitListExpr<'_>, type_of: &mut impl FnMut(&Expr<'_>) -> DataType) -> Self {
+        Self::List(
+            list.elements
+                .iter()
+                .map(|element| match element {
+                    InitElement::Expr(expr) => Self::Value(type_of(expr)),
+                    InitElement::InitList(nested) => Self::of(nested, type_of),
+                })
+                .collect(),
+        )
+    }
+}
+
+/// How an init list, or one of its elements, is built.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InitList {
+    /// An expression, converted to the type it initializes.
+    Value(Conversion),
+    /// A value type constructed with the elements as arguments.
+    Construct {
+        /// The selected constructor.
+        constructor: TypeHash,
+        /// How each argument is built.
+        args: Vec<InitList>,
+    },
+    /// An object built by a list behavior from a buffer of the elements.
+    Behavior {
+        /// The `list_construct` or `list_factory` function.
+        func_hash: TypeHash,
+        /// Whether the behavior is a factory returning a handle.
+        is_factory: bool,
+        /// How each element is built.
+        elements: Vec<InitList>,
+    },
+    /// An element of a list behavior with a repeated tuple pattern, as in
+    /// `{"hp", 100}` of `dictionary@ d = {{"hp", 100}}`. Its fields are
+    /// stored in the buffer in order.
+    Tuple(Vec<InitList>),
+}
+
+impl InitList {
+    /// Match an init list of the given shape against the type it
+    /// initializes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an element cannot be converted to the type it
+    /// initializes, the type cannot be built from a list, or several
+    /// constructors fit equally well.
+    pub fn resolve(
+        registry: &SymbolRegistry,
+        target: &DataType,
+        shape: &InitShape,
+        span: Span,
+    ) -> Result<Self, CompilationError> {
+        let elements = match shape {
+            InitShape::Value(value) => {
+                return match rank_implicit_conversion(registry, target, value, span)? {
+                    Some(conversion) => Ok(Self::Value(conversion)),
+                    None => Err(CompilationError::TypeMismatch {
+                        message: format!(
+                            "cannot convert '{}' to '{}' in init list",
+                            type_name(registry, value.type_hash),
+                            type_name(registry, target.type_hash)
+                        ),
+                        span,
+                    }),
+                };
+            }
+            InitShape::List(elements) => elements,
+        };
+        let invalid = |reason: &str| CompilationError::InvalidInitList {
+            type_name: type_name(registry, target.type_hash),
+            reason: reason.to_string(),
+            span,
+        };
+        let Some(class) = registry.get(target.type_hash).and_then(|e| e.as_class()) else {
+            return Err(invalid("only objects are built from lists"));
+        };
+
+        let behaviors = &class.behaviors;
+        let list_behaviors = behaviors
+            .list_constructs
+            .iter()
+            .map(|b| (b, false))
+            .chain(behaviors.list_factories.iter().map(|b| (b, true)));
+        for (behavior, is_factory) in list_behaviors {
+            if let Some(elements) = match_pattern(registry, &behavior.pattern, elements, span)? {
+                return Ok(Self::Behavior {
+                    func_hash: behavior.func_hash,
+                    is_factory,
+                    elements,
+                });
+            }
+        }
+
+        if !class.is_value_type() {
+            return Err(invalid("the type has no list factory matching the list"));
+        }
+        construct(registry, class, elements, span)?
+            .ok_or_else(|| invalid("no constructor or list constructor matches the list"))
+    }
+
+    /// Emit the building of the list, with `emit_value` emitting the
+    /// converted expressions in source order.
+    pub fn emit(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        line: u32,
+        emit_value: &mut impl FnMut(&mut BytecodeChunk, &mut ConstantPool, Conversion),
+    ) {
+        match self {
+            Self::Value(conversion) => emit_value(chunk, constants, *conversion),
+            Self::Tuple(fields) => {
+                for field in fields {
+                    field.emit(chunk, constants, line, emit_value);
+                }
+            }
+            Self::Construct { constructor, args } => {
+                for arg in args {
+                    arg.emit(chunk, constants, line, emit_value);
+                }
+                emit_new(
+                    chunk,
+                    constants,
+                    OpCode::New,
+                    *constructor,
+                    args.len(),
+                    line,
+                );
+            }
+            Self::Behavior {
+                func_hash,
+                is_factory,
+                elements,
+            } => {
+                chunk.write_op(OpCode::InitListBegin, line);
+                chunk.write_u16(elements.len() as u16, line);
+                for element in elements {
+                    element.emit(chunk, constants, line, emit_value);
+                }
+                chunk.write_op(OpCode::InitListEnd, line);
+                let op = if *is_factory {
+                    OpCode::NewFactory
+                } else {
+                    OpCode::New
+                };
+                emit_new(chunk, constants, op, *func_hash, 1, line);
+            }
+        }
+    }
+}
+
+/// Match the elements of a list against a list behavior's pattern.
+fn match_pattern(
+    registry: &SymbolRegistry,
+    pattern: &ListPattern,
+    elements: &[InitShape],
+    span: Span,
+) -> Result<Option<Vec<InitList>>, CompilationError> {
+    let element_types: Vec<TypeHash> = match pattern {
+        ListPattern::Repeat(element) => vec![*element; elements.len()],
+        ListPattern::Fixed(types) if types.len() == elements.len() => types.clone(),
+        ListPattern::Fixed(_) => return Ok(None),
+        ListPattern::RepeatTuple(types) => {
+            // Each element is a tuple of the pattern's types
+            let mut tuples = Vec::with_capacity(elements.len());
+            for element in elements {
+                let InitShape::List(fields) = element else {
+                    return Ok(None);
+                };
+                if fields.len() != types.len() {
+                    return Ok(None);
+                }
+                let Some(fields) = match_all(registry, types, fields, span)? else {
+                    return Ok(None);
+                };
+                tuples.push(fields);
+            }
+            return Ok(Some(tuples.into_iter().map(InitList::Tuple).collect()));
+        }
+    };
+    match_all(registry, &element_types, elements, span)
+}
+
+/// Match each element against the type at the same position, or `None` if
+/// any does not match.
+fn match_all(
+    registry: &SymbolRegistry,
+    types: &[TypeHash],
+    elements: &[InitShape],
+    span: Span,
+) -> Result<Option<Vec<InitList>>, CompilationError> {
+    let mut matched = Vec::with_capacity(elements.len());
+    for (&element_type, element) in types.iter().zip(elements) {
+        match InitList::resolve(registry, &DataType::simple(element_type), element, span) {
+            Ok(element) => matched.push(element),
+            Err(error @ CompilationError::AmbiguousOverload { .. }) => return Err(error),
+            Err(_) => return Ok(None),
+        }
+    }
+    Ok(Some(matched))
+}
+
+/// Select the constructor of a value type taking the elements as
+/// arguments, or `None` if none can.
+fn construct(
+    registry: &SymbolRegistry,
+    class: &ClassEntry,
+    elements: &[InitShape],
+    span: Span,
+) -> Result<Option<InitList>, CompilationError> {
+    let mut viable: Vec<(TypeHash, Vec<Conversion>, Vec<InitList>)> = Vec::new();
+    for &hash in &class.behaviors.constructors {
+        let Some(function) = registry.get_function(hash) else {
+            continue;
+        };
+        let params = &function.def.params;
+        if params.len() != elements.len() {
+            continue;
+        }
+        let mut args = Vec::with_capacity(params.len());
+        for (param, element) in params.iter().zip(elements) {
+            // A nested list builds a temporary, so the parameter is read only
+            let mut param_type = param.data_type;
+            if param_type.ref_modifier == RefModifier::In {
+                param_type.ref_modifier = RefModifier::None;
+            }
+            match InitList::resolve(registry, &param_type, element, span) {
+                Ok(arg) => args.push(arg),
+                Err(error @ CompilationError::AmbiguousOverload { .. }) => return Err(error),
+                Err(_) => break,
+            }
+        }
+        if args.len() == params.len() {
+            // A nested list ranks like an exact match of its type
+            let conversions = args.iter().map(rank).collect();
+            viable.push((hash, conversions, args));
+        }
+    }
+
+    let best: Vec<usize> = (0..viable.len())
+        .filter(|&a| (0..viable.len()).all(|b| a == b || !is_better(&viable[b].1, &viable[a].1)))
+        .collect();
+    match best[..] {
+        [] => Ok(None),
+        [found] => {
+            let (constructor, _, args) = viable.swap_remove(found);
+            Ok(Some(InitList::Construct { constructor, args }))
+        }
+        _ => Err(CompilationError::AmbiguousOverload {
+            name: class.qualified_name.clone(),
+            candidates: best
+                .iter()
+                .filter_map(|&i| registry.get_function(viable[i].0))
+                .map(|f| f.def.qualified_name().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            span,
+        }),
+    }
+}
+
+fn rank(arg: &InitList) -> Conversion {
+    match arg {
+        InitList::Value(conversion) => *conversion,
+        _ => Conversion::Exact,
+    }
+}
+
+fn emit_new(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    op: OpCode,
+    func_hash: TypeHash,
+    arg_count: usize,
+    line: u32,
+) {
+    let index = constants.add_type_hash(func_hash);
+    chunk.write_op(op, line);
+    chunk.write_u16(index as u16, line);
+    chunk.write_byte(arg_count as u8, line);
+}
+
+fn type_name(registry: &SymbolRegistry, hash: TypeHash) -> String {
+    registry.get(hash).map_or_else(
+        || hash.to_string(),
+        |entry| entry.qualified_name().to_string(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{
+        FunctionDef, FunctionEntry, FunctionTraits, ListBehavior, Param, TypeKind, Visibility,
+        primitives,
+    };
+    use angelscript_parser::Parser;
+    use angelscript_parser::ast::Item;
+    use bumpalo::Bump;
+
+    fn hash(name: &str) -> TypeHash {
+        TypeHash::from_name(name)
+    }
+
+    fn constructor(registry: &mut SymbolRegistry, class: &mut ClassEntry, params: &[TypeHash]) {
+        let hash = TypeHash::from_constructor(class.type_hash, params);
+        let def = FunctionDef::new(
+            hash,
+            class.name.clone(),
+            vec![],
+            params
+                .iter()
+                .map(|&param| Param::new("p", DataType::simple(param)))
+                .collect(),
+            DataType::void(),
+            Some(class.type_hash),
+            FunctionTraits::constructor(),
+            true,
+            Visibility::Public,
+        );
+        registry.register_function(FunctionEntry::ffi(def)).unwrap();
+        class.behaviors.add_constructor(hash);
+    }
+
+    /// Value types `Vec2(float, float)` and `Line(Vec2, Vec2)`, value type
+    /// `Rgb` with a `{int, int, int}` list constructor and reference type
+    /// `Path` with a list factory of `Vec2`s.
+    fn registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+        let mut vec2 = ClassEntry::ffi("Vec2", TypeKind::value::<[f32; 2]>());
+        constructor(
+            &mut registry,
+            &mut vec2,
+            &[primitives::FLOAT, primitives::FLOAT],
+        );
+        let mut line = ClassEntry::ffi("Line", TypeKind::value::<[f32; 4]>());
+        constructor(&mut registry, &mut line, &[hash("Vec2"), hash("Vec2")]);
+        let mut rgb = ClassEntry::ffi("Rgb", TypeKind::value::<[u8; 3]>());
+        rgb.behaviors.add_list_construct(ListBehavior::new(
+            hash("Rgb::$list"),
+            ListPattern::Fixed(vec![primitives::INT32; 3]),
+        ));
+        let mut path = ClassEntry::ffi("Path", TypeKind::reference());
+        path.behaviors.add_list_factory(ListBehavior::new(
+            hash("Path::$list"),
+            ListPattern::Repeat(hash("Vec2")),
+        ));
+        for class in [vec2, line, rgb, path] {
+            registry.register_type(class.into()).unwrap();
+        }
+        registry
+    }
+
+    fn ints(count: usize) -> InitShape {
+        InitShape::List(vec![
+            InitShape::Value(DataType::simple(primitives::INT32));
+            count
+        ])
+    }
+
+    fn resolve(target: &str, shape: &InitShape) -> Result<InitList, String> {
+        let target = DataType::simple(hash(target));
+        InitList::resolve(&registry(), &target, shape, Span::new(1, 1, 1))
+            .map_err(|e| e.to_string())
+    }
+
+    fn opcodes(list: &InitList) -> Vec<OpCode> {
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        list.emit(&mut chunk, &mut constants, 1, &mut |chunk, _, _| {
+            chunk.write_op(OpCode::PushZero, 1)
+        });
+        chunk.opcodes()
+    }
+
+    #[test]
+    fn constructs_value_types_from_nested_lists() {
+        let arena = Bump::new();
+        let script = Parser::parse("Line l = {{0, 0}, {1, 1}};", &arena).unwrap();
+        let Item::GlobalVar(var) = &script.items()[0] else {
+            panic!("expected a global variable");
+        };
+        let Some(Expr::InitList(list)) = var.init else {
+            panic!("expected an init list");
+        };
+        let shape = InitShape::of(list, &mut |_| DataType::simple(primitives::INT32));
+
+        let line = resolve("Line", &shape).unwrap();
+        let vec2 = TypeHash::from_constructor(hash("Vec2"), &[primitives::FLOAT; 2]);
+        let InitList::Construct { args, .. } = &line else {
+            panic!("{line:?}");
+        };
+        assert_eq!(
+            args[0],
+            InitList::Construct {
+                constructor: vec2,
+                args: vec![InitList::Value(Conversion::Numeric); 2],
+            }
+        );
+        use OpCode::{New, PushZero};
+        assert_eq!(
+            opcodes(&line),
+            [PushZero, PushZero, New, PushZero, PushZero, New, New]
+        );
+    }
+
+    #[test]
+    fn prefers_list_behaviors() {
+        let rgb = resolve("Rgb", &ints(3)).unwrap();
+        assert!(matches!(
+            rgb,
+            InitList::Behavior {
+                is_factory: false,
+                ..
+            }
+        ));
+
+        let path = resolve("Path", &InitShape::List(vec![ints(2); 3])).unwrap();
+        let InitList::Behavior {
+            is_factory: true,
+            elements,
+            ..
+        } = &path
+        else {
+            panic!("{path:?}");
+        };
+        assert_eq!(elements.len(), 3);
+        assert_eq!(opcodes(&path)[0], OpCode::InitListBegin);
+        assert_eq!(opcodes(&path).last(), Some(&OpCode::NewFactory));
+    }
+
+    #[test]
+    fn rejects_unmatched_lists() {
+        assert_eq!(
+            resolve("Vec2", &ints(3)).unwrap_err(),
+            "at 1:1: cannot initialize 'Vec2' from an init list: \
+             no constructor or list constructor matches the list"
+        );
+        assert!(resolve("Rgb", &ints(2)).is_err());
+        assert!(
+            resolve("Path", &ints(2))
+                .unwrap_err()
+                .ends_with("the type has no list factory matching the list")
+        );
+        assert!(
+            resolve("int", &ints(1))
+                .unwrap_err()
+                .ends_with("only objects are built from lists")
+        );
+    }
+}
@@ -0,0 +1,455 @@
+//! Inlining of small script functions.
+//!
+//! Calling a getter or a one-line math helper costs more than running its
+//! body. [`inline_calls`] replaces calls of such functions with their body:
+//! the arguments are stored into locals of the caller past its own, and the
+//! body runs with its locals moved there. The result the body returns is
+//! left on the stack, as the call would have left it:
+//!
+//! ```text
+//! int twice(int x) { return x * 2; }   // GET_LOCAL 0; CONSTANT 2; MUL; RETURN
+//! y = twice(y);                        // GET_LOCAL 0; CALL twice 1
+//! // becomes
+//! GET_LOCAL 0; SET_LOCAL 1; POP; GET_LOCAL 1; CONSTANT 2; MUL
+//! ```
+//!
+//! Only global functions are inlined, so calls never need virtual dispatch.
+//! Their body must run straight through to a single return at its end
+//! without calling themselves or using try blocks. Which functions qualify
+//! depends on the optimization level:
+//!
+//! - [`Basic`] inlines functions declared with `[inline]` metadata of up to
+//!   [`MAX_HINTED_SIZE`] instructions;
+//! - [`Aggressive`] also inlines any function of up to
+//!   [`CompilerOptions::inline_size`] instructions.
+//!
+//! Calls in inlined bodies are kept as calls, so inlining never grows code
+//! more than one level deep.
+//!
+//! [`Basic`]: crate::bytecode::OptimizationLevel::Basic
+//! [`Aggressive`]: crate::bytecode::OptimizationLevel::Aggressive
+//! [`CompilerOptions::inline_size`]: crate::CompilerOptions::inline_size
+
+use angelscript_core::TypeHash;
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{ClassDecl, FunctionDecl, NamespaceDecl, Script};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::CompiledFunction;
+use crate::bytecode::{
+    BytecodeChunk, BytecodeRewriter, Constant, ConstantPool, InstrId, Instruction, OpCategory,
+    OpCode, OptimizationLevel, RewriteError,
+};
+
+/// Largest body, in instructions, inlined without a hint by default.
+pub const DEFAULT_INLINE_SIZE: usize = 8;
+
+/// Largest body, in instructions, inlined because of an `[inline]` hint.
+pub const MAX_HINTED_SIZE: usize = 64;
+
+/// Qualified names of the global functions a script declares `[inline]`.
+pub fn inline_hints(script: &Script<'_>) -> FxHashSet<String> {
+    let mut hints = Hints {
+        namespace: Vec::new(),
+        names: FxHashSet::default(),
+    };
+    hints.visit_script(script);
+    hints.names
+}
+
+struct Hints {
+    namespace: Vec<String>,
+    names: FxHashSet<String>,
+}
+
+impl<'ast> Visitor<'ast> for Hints {
+    fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+        let depth = self.namespace.len();
+        self.namespace
+            .extend(namespace.path.iter().map(|s| s.name.to_string()));
+        visitor::walk_namespace_decl(self, namespace);
+        self.namespace.truncate(depth);
+    }
+
+    // Methods are never inlined
+    fn visit_class_decl(&mut self, _class: &ClassDecl<'ast>) {}
+
+    fn visit_function_decl(&mut self, function: &FunctionDecl<'ast>) {
+        if function.modifiers.inline {
+            let mut name = self.namespace.join("::");
+            if !name.is_empty() {
+                name.push_str("::");
+            }
+            name.push_str(function.name.name);
+            self.names.insert(name);
+        }
+    }
+}
+
+/// Script functions whose calls are inlined.
+#[derive(Debug, Default)]
+pub struct InlineFunctions {
+    functions: FxHashMap<TypeHash, Inline>,
+}
+
+/// The body of an inlined function.
+#[derive(Debug)]
+struct Inline {
+    /// Instructions without the final return.
+    body: Vec<Instruction>,
+    params: u16,
+}
+
+impl InlineFunctions {
+    /// Find the functions of a module to inline at `level`. `hints` are the
+    /// names of the functions declared `[inline]`, and `max_size` is the
+    /// largest body inlined without a hint.
+    pub fn collect(
+        functions: &[CompiledFunction],
+        pool: &ConstantPool,
+        hints: &FxHashSet<String>,
+        level: OptimizationLevel,
+        max_size: usize,
+    ) -> Self {
+        let mut inline = FxHashMap::default();
+        if level == OptimizationLevel::None {
+            return Self { functions: inline };
+        }
+        for function in functions {
+            let limit = if hints.contains(&function.name) {
+                MAX_HINTED_SIZE
+            } else if level >= OptimizationLevel::Aggressive {
+                max_size
+            } else {
+                continue;
+            };
+            let params: Vec<TypeHash> = function
+                .signature
+                .params
+                .iter()
+                .map(|param| param.type_hash)
+                .collect();
+            let hash = TypeHash::from_function(&function.name, &params);
+            if let Some(body) = inline_body(&function.bytecode, pool, hash, limit) {
+                inline.insert(
+                    hash,
+                    Inline {
+                        body,
+                        params: params.len() as u16,
+                    },
+                );
+            }
+        }
+        Self { functions: inline }
+    }
+
+    /// Check if calls of `function` are inlined.
+    pub fn contains(&self, function: TypeHash) -> bool {
+        self.functions.contains_key(&function)
+    }
+
+    /// Check if no function is inlined.
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+}
+
+/// The body of a function without its final return, if it can be inlined.
+fn inline_body(
+    chunk: &BytecodeChunk,
+    pool: &ConstantPool,
+    function: TypeHash,
+    limit: usize,
+) -> Option<Vec<Instruction>> {
+    let rewriter = BytecodeRewriter::new(chunk).ok()?;
+    let mut body: Vec<Instruction> = rewriter
+        .instructions()
+        .map(|(_, instruction)| instruction.clone())
+        .collect();
+    let last = body.pop()?;
+    if !matches!(last.op(), OpCode::Return | OpCode::ReturnVoid) || body.len() > limit {
+        return None;
+    }
+    let straight = body.iter().all(|instruction| {
+        let op = instruction.op();
+        op.category() != OpCategory::ControlFlow
+            && !matches!(
+                op,
+                OpCode::Return
+                    | OpCode::ReturnVoid
+                    | OpCode::GetThis
+                    | OpCode::TryBegin
+                    | OpCode::TryEnd
+                    | OpCode::Extension
+            )
+    });
+    let recursive = body
+        .iter()
+        .any(|instruction| called(instruction, pool).is_some_and(|(f, _)| f == function));
+    (straight && !recursive).then_some(body)
+}
+
+/// Replace the calls in `chunk` of functions in `inline` with their bodies.
+/// `locals` is the number of local slots the caller uses, including its
+/// parameters; inlined bodies use the slots after them.
+///
+/// # Errors
+///
+/// Returns an error if the chunk cannot be decoded or re-encoded.
+pub fn inline_calls(
+    chunk: &BytecodeChunk,
+    pool: &ConstantPool,
+    inline: &InlineFunctions,
+    locals: u16,
+) -> Result<BytecodeChunk, RewriteError> {
+    if inline.is_empty() {
+        return Ok(chunk.clone());
+    }
+    let mut rewriter = BytecodeRewriter::new(chunk)?;
+    let ids: Vec<InstrId> = rewriter.ids().collect();
+    let base = ids
+        .iter()
+        .filter_map(|&id| rewriter.get(id).and_then(local_slot))
+        .map(|slot| slot.saturating_add(1))
+        .fold(locals, u16::max);
+
+    for id in ids {
+        let Some(instruction) = rewriter.get(id) else {
+            continue;
+        };
+        let Some((function, args)) = called(instruction, pool) else {
+            continue;
+        };
+        let Some(callee) = inline.functions.get(&function) else {
+            continue;
+        };
+        if u16::from(args) != callee.params {
+            continue;
+        }
+        let line = instruction.line();
+        let Some(expanded) = expand(callee, base, line) else {
+            continue;
+        };
+
+        // Jumps to the call land on the first inlined instruction
+        let mut expanded = expanded.into_iter();
+        match expanded.next() {
+            Some(first) => {
+                rewriter.replace(id, first);
+                rewriter.insert_after(id, expanded);
+            }
+            None => {
+                rewriter.remove(id);
+            }
+        }
+    }
+    rewriter.finish()
+}
+
+/// Argument stores followed by the body moved to the slots from `base`.
+fn expand(callee: &Inline, base: u16, line: u32) -> Option<Vec<Instruction>> {
+    let mut expanded = Vec::with_capacity(usize::from(callee.params) * 2 + callee.body.len());
+    // Arguments are on the stack in order, so the last is stored first
+    for param in (0..callee.params).rev() {
+        expanded.push(local(OpCode::SetLocal, base.checked_add(param)?, line));
+        expanded.push(Instruction::simple(OpCode::Pop, line));
+    }
+    for instruction in &callee.body {
+        expanded.push(match local_slot(instruction) {
+            Some(slot) => {
+                let op = match instruction.op() {
+                    OpCode::SetLocal | OpCode::SetLocalWide => OpCode::SetLocal,
+                    _ => OpCode::GetLocal,
+                };
+                local(op, base.checked_add(slot)?, line)
+            }
+            None => {
+                Instruction::with_operands(instruction.op(), instruction.operands(), line).ok()?
+            }
+        });
+    }
+    Some(expanded)
+}
+
+/// A load or store of a local slot, using the narrow form if it fits.
+fn local(op: OpCode, slot: u16, line: u32) -> Instruction {
+    let result = match (u8::try_from(slot), op) {
+        (Ok(byte), _) => Instruction::with_operands(op, &[byte], line),
+        (Err(_), OpCode::SetLocal) => {
+            Instruction::with_operands(OpCode::SetLocalWide, &slot.to_be_bytes(), line)
+        }
+        (Err(_), _) => Instruction::with_operands(OpCode::GetLocalWide, &slot.to_be_bytes(), line),
+    };
+    result.expect("local accesses have fixed operand sizes")
+}
+
+fn local_slot(instruction: &Instruction) -> Option<u16> {
+    match (instruction.op(), instruction.operands()) {
+        (OpCode::GetLocal | OpCode::SetLocal, &[slot]) => Some(u16::from(slot)),
+        (OpCode::GetLocalWide | OpCode::SetLocalWide, &[hi, lo]) => {
+            Some(u16::from_be_bytes([hi, lo]))
+        }
+        _ => None,
+    }
+}
+
+/// The function and argument count of a call.
+fn called(instruction: &Instruction, pool: &ConstantPool) -> Option<(TypeHash, u8)> {
+    let (OpCode::Call, &[hi, lo, args]) = (instruction.op(), instruction.operands()) else {
+        return None;
+    };
+    match pool.get(u32::from(u16::from_be_bytes([hi, lo])))? {
+        Constant::TypeHash(function) => Some((*function, args)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FunctionSignature;
+    use angelscript_core::{DataType, primitives};
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    /// `int twice(int x) { return x * 2; }`
+    fn twice() -> CompiledFunction {
+        let mut bytecode = BytecodeChunk::new();
+        bytecode.write_op(OpCode::GetLocal, 1);
+        bytecode.write_byte(0, 1);
+        bytecode.write_op(OpCode::PushOne, 1);
+        bytecode.write_op(OpCode::PushOne, 1);
+        bytecode.write_op(OpCode::Add, 1);
+        bytecode.write_op(OpCode::Mul, 1);
+        bytecode.write_op(OpCode::Return, 1);
+        CompiledFunction {
+            name: "twice".to_string(),
+            signature: FunctionSignature::new(
+                vec![DataType::simple(primitives::INT32)],
+                DataType::simple(primitives::INT32),
+            ),
+            bytecode,
+        }
+    }
+
+    /// `y = twice(y);` in a function with one parameter and a local `y`.
+    fn caller(pool: &mut ConstantPool) -> BytecodeChunk {
+        let hash = TypeHash::from_function("twice", &[primitives::INT32]);
+        let index = pool.add_type_hash(hash);
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::GetLocal, 5);
+        chunk.write_byte(1, 5);
+        chunk.write_op(OpCode::Call, 5);
+        chunk.write_u16(index as u16, 5);
+        chunk.write_byte(1, 5);
+        chunk.write_op(OpCode::SetLocal, 5);
+        chunk.write_byte(1, 5);
+        chunk.write_op(OpCode::Pop, 5);
+        chunk.write_op(OpCode::ReturnVoid, 6);
+        chunk
+    }
+
+    #[test]
+    fn inlines_calls_after_the_caller_locals() {
+        let mut pool = ConstantPool::new();
+        let chunk = caller(&mut pool);
+        let hints = FxHashSet::from_iter(["twice".to_string()]);
+        let inline = InlineFunctions::collect(
+            &[twice()],
+            &pool,
+            &hints,
+            OptimizationLevel::Basic,
+            DEFAULT_INLINE_SIZE,
+        );
+        assert!(inline.contains(TypeHash::from_function("twice", &[primitives::INT32])));
+
+        let result = inline_calls(&chunk, &pool, &inline, 1).unwrap();
+        result.assert_opcodes(&[
+            OpCode::GetLocal,
+            OpCode::SetLocal,
+            OpCode::Pop,
+            OpCode::GetLocal,
+            OpCode::PushOne,
+            OpCode::PushOne,
+            OpCode::Add,
+            OpCode::Mul,
+            OpCode::SetLocal,
+            OpCode::Pop,
+            OpCode::ReturnVoid,
+        ]);
+        // The argument and the body use slot 2, past the caller's `y`
+        let disasm = result.disassemble(&pool);
+        let locals: Vec<&str> = disasm.lines().filter(|l| l.contains("_LOCAL")).collect();
+        assert!(locals[1].trim_end().ends_with('2'), "{disasm}");
+        assert!(locals[2].trim_end().ends_with('2'), "{disasm}");
+    }
+
+    #[test]
+    fn level_and_size_select_functions() {
+        let pool = ConstantPool::new();
+        let none = FxHashSet::default();
+        let collect = |level, size| InlineFunctions::collect(&[twice()], &pool, &none, level, size);
+        assert!(collect(OptimizationLevel::Basic, DEFAULT_INLINE_SIZE).is_empty());
+        assert!(!collect(OptimizationLevel::Aggressive, DEFAULT_INLINE_SIZE).is_empty());
+        assert!(collect(OptimizationLevel::Aggressive, 4).is_empty());
+
+        let hinted = FxHashSet::from_iter(["twice".to_string()]);
+        let inline =
+            InlineFunctions::collect(&[twice()], &pool, &hinted, OptimizationLevel::None, 0);
+        assert!(inline.is_empty());
+    }
+
+    #[test]
+    fn branching_and_recursive_bodies_are_kept() {
+        let mut pool = ConstantPool::new();
+        let hash = TypeHash::from_function("twice", &[primitives::INT32]);
+
+        let mut branching = twice();
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(0, 1);
+        let skip = chunk.emit_jump(OpCode::JumpIfFalse, 1);
+        chunk.patch_jump(skip);
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::Return, 1);
+        branching.bytecode = chunk;
+
+        let mut recursive = twice();
+        let index = pool.add_type_hash(hash);
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::Call, 1);
+        chunk.write_u16(index as u16, 1);
+        chunk.write_byte(1, 1);
+        chunk.write_op(OpCode::Return, 1);
+        recursive.bytecode = chunk;
+
+        for function in [branching, recursive] {
+            let inline = InlineFunctions::collect(
+                &[function],
+                &pool,
+                &FxHashSet::default(),
+                OptimizationLevel::Aggressive,
+                DEFAULT_INLINE_SIZE,
+            );
+            assert!(inline.is_empty());
+        }
+    }
+
+    #[test]
+    fn hints_are_collected_by_qualified_name() {
+        let arena = Bump::new();
+        let source = "
+            [inline] int twice(int x) { return x * 2; }
+            int plain() { return 1; }
+            namespace math { [inline] float half(float x) { return x / 2; } }
+            class Player { [inline] int hp() { return 1; } }
+        ";
+        let script = Parser::parse(source, &arena).unwrap();
+        let mut hints: Vec<String> = inline_hints(&script).into_iter().collect();
+        hints.sort();
+        assert_eq!(hints, ["math::half", "twice"]);
+    }
+}
@@ -0,0 +1,395 @@
+//! Interface implementation checks.
+//!
+//! A script class may implement any number of interfaces, and interfaces
+//! may extend other interfaces. Implementing an interface implements every
+//! interface it extends, so a class must provide the methods of all of
+//! them:
+//!
+//! ```angelscript
+//! interface Named { string name() const; }
+//! interface Actor : Named { void tick(float dt); }
+//! interface Prop : Named { void place(); }
+//!
+//! // Implements Actor, Prop and Named, which both of them extend
+//! class Crate : Actor, Prop {
+//!     string name() const { return "crate"; }
+//!     void tick(float dt) {}
+//!     void place() {}
+//! }
+//! ```
+//!
+//! An interface reached along several paths is required once, and a method
+//! declared by several interfaces is implemented by a single method. Methods
+//! and interfaces inherited from a base class count as the class's own.
+//! Each missing method is reported with the interface declaring it.
+//!
+//! Signatures are compared by parameter types, return type and constness as
+//! written in the source.
+
+use angelscript_core::CompilationError;
+use angelscript_parser::ast::{
+    ClassMember, FunctionParam, IdentExpr, InterfaceDecl, InterfaceMember, Item, Script,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::partial::MergedClass;
+
+/// The script interfaces of a unit, with their inheritance.
+#[derive(Debug, Default)]
+pub struct InterfaceSet {
+    interfaces: FxHashMap<String, ScriptInterface>,
+}
+
+/// An interface with its bases resolved.
+#[derive(Debug)]
+struct ScriptInterface {
+    /// Qualified names of the interfaces extended, in declaration order.
+    bases: Vec<String>,
+    /// Signatures of the methods declared by the interface itself.
+    methods: Vec<String>,
+}
+
+/// What a class gets from its declaration and its base classes.
+#[derive(Debug, Default)]
+struct Inherited {
+    /// Classes visited, to stop at inheritance cycles.
+    classes: FxHashSet<String>,
+    /// Interfaces listed by the classes.
+    interfaces: Vec<String>,
+    /// Signatures of the methods declared by the classes.
+    methods: FxHashSet<String>,
+}
+
+/// A method a class must implement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequiredMethod {
+    /// Qualified name of the interface declaring the method.
+    pub interface: String,
+    /// Method signature, e.g. `void tick(float)`.
+    pub signature: String,
+}
+
+impl InterfaceSet {
+    /// Collect the interfaces declared in `sections`.
+    pub fn collect(sections: &[&Script<'_>]) -> Self {
+        let mut declared = Vec::new();
+        for script in sections {
+            collect_interfaces(script.items(), "", &mut declared);
+        }
+
+        let names: FxHashSet<String> = declared.iter().map(|(name, _)| name.clone()).collect();
+        let interfaces = declared
+            .into_iter()
+            .map(|(name, decl)| {
+                let namespace = namespace_of(&name);
+                let bases = decl
+                    .bases
+                    .iter()
+                    .filter_map(|base| resolve(namespace, base.name, |n| names.contains(n)))
+                    .collect();
+                let methods = decl
+                    .members
+                    .iter()
+                    .filter_map(|member| match member {
+                        InterfaceMember::Method(method) => Some(signature(
+                            &method.return_type.to_string(),
+                            method.name.name,
+                            method.params,
+                            method.is_const,
+                        )),
+                        InterfaceMember::VirtualProperty(_) => None,
+                    })
+                    .collect();
+                (name, ScriptInterface { bases, methods })
+            })
+            .collect();
+        Self { interfaces }
+    }
+
+    /// Check if `name` is the qualified name of a script interface.
+    pub fn contains(&self, name: &str) -> bool {
+        self.interfaces.contains_key(name)
+    }
+
+    /// The interface and every interface it extends, directly or not.
+    ///
+    /// Each interface is listed once, depth first in declaration order.
+    pub fn inherited(&self, interface: &str) -> Vec<String> {
+        let mut out = Vec::new();
+        self.visit(interface, &mut out);
+        out
+    }
+
+    fn visit(&self, interface: &str, out: &mut Vec<String>) {
+        if out.iter().any(|seen| seen == interface) {
+            return;
+        }
+        let Some(entry) = self.interfaces.get(interface) else {
+            return;
+        };
+        out.push(interface.to_string());
+        for base in &entry.bases {
+            self.visit(base, out);
+        }
+    }
+
+    /// The methods a class implementing `interfaces` must provide, each
+    /// signature once.
+    pub fn required_methods<'a>(
+        &self,
+        interfaces: impl IntoIterator<Item = &'a str>,
+    ) -> Vec<RequiredMethod> {
+        let mut all = Vec::new();
+        for interface in interfaces {
+            self.visit(interface, &mut all);
+        }
+
+        let mut required: Vec<RequiredMethod> = Vec::new();
+        for interface in all {
+            for method in &self.interfaces[&interface].methods {
+                if !required.iter().any(|r| &r.signature == method) {
+                    required.push(RequiredMethod {
+                        interface: interface.clone(),
+                        signature: method.clone(),
+                    });
+                }
+            }
+        }
+        required
+    }
+
+    /// Report the interface methods each class leaves unimplemented.
+    pub fn check_classes(&self, classes: &[MergedClass<'_>]) -> Vec<CompilationError> {
+        let by_name: FxHashMap<&str, &MergedClass<'_>> = classes
+            .iter()
+            .map(|class| (class.qualified_name.as_str(), class))
+            .collect();
+
+        let mut errors = Vec::new();
+        for class in classes {
+            let mut found = Inherited::default();
+            self.collect_class(class, &by_name, &mut found);
+
+            for required in self.required_methods(found.interfaces.iter().map(String::as_str)) {
+                if !found.methods.contains(&required.signature) {
+                    errors.push(CompilationError::MissingInterfaceMethod {
+                        class: class.qualified_name.clone(),
+                        interface: required.interface,
+                        signature: required.signature,
+                        span: class.span(),
+                    });
+                }
+            }
+        }
+        errors
+    }
+
+    /// Gather the interfaces and method signatures of a class and its base
+    /// classes.
+    fn collect_class(
+        &self,
+        class: &MergedClass<'_>,
+        classes: &FxHashMap<&str, &MergedClass<'_>>,
+        found: &mut Inherited,
+    ) {
+        found.classes.insert(class.qualified_name.clone());
+        for member in &class.members {
+            if let ClassMember::Method(func) = member
+                && let Some(return_type) = &func.return_type
+                && !func.is_destructor
+            {
+                found.methods.insert(signature(
+                    &return_type.to_string(),
+                    func.name.name,
+                    func.params,
+                    func.is_const,
+                ));
+            }
+        }
+
+        let namespace = namespace_of(&class.qualified_name);
+        for base in &class.inheritance {
+            let name = base_name(base);
+            if let Some(interface) = resolve(namespace, &name, |n| self.contains(n)) {
+                found.interfaces.push(interface);
+            } else if let Some(parent) = resolve(namespace, &name, |n| classes.contains_key(n))
+                // Inheritance cycles are reported elsewhere
+                && !found.classes.contains(&parent)
+            {
+                self.collect_class(classes[parent.as_str()], classes, found);
+            }
+        }
+    }
+}
+
+fn collect_interfaces<'ast>(
+    items: &[Item<'ast>],
+    namespace: &str,
+    out: &mut Vec<(String, InterfaceDecl<'ast>)>,
+) {
+    for item in items {
+        match item {
+            Item::Interface(interface) => {
+                out.push((qualify(namespace, interface.name.name), *interface));
+            }
+            Item::Namespace(ns) => {
+                let mut nested = namespace.to_string();
+                for segment in ns.path {
+                    nested = qualify(&nested, segment.name);
+                }
+                collect_interfaces(ns.items, &nested, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Find the declaration `name` refers to from `namespace`, looking in the
+/// namespace first and then in each enclosing one.
+pub(crate) fn resolve(
+    namespace: &str,
+    name: &str,
+    exists: impl Fn(&str) -> bool,
+) -> Option<String> {
+    let mut scope = namespace;
+    loop {
+        let candidate = qualify(scope, name);
+        if exists(&candidate) {
+            return Some(candidate);
+        }
+        if scope.is_empty() {
+            return None;
+        }
+        scope = scope.rsplit_once("::").map_or("", |(outer, _)| outer);
+    }
+}
+
+pub(crate) fn signature(
+    return_type: &str,
+    name: &str,
+    params: &[FunctionParam<'_>],
+    is_const: bool,
+) -> String {
+    let params: Vec<String> = params.iter().map(|p| p.ty.to_string()).collect();
+    format!(
+        "{} {}({}){}",
+        return_type,
+        name,
+        params.join(", "),
+        if is_const { " const" } else { "" }
+    )
+}
+
+pub(crate) fn namespace_of(qualified_name: &str) -> &str {
+    qualified_name.rsplit_once("::").map_or("", |(ns, _)| ns)
+}
+
+pub(crate) fn base_name(base: &IdentExpr<'_>) -> String {
+    match base.scope {
+        Some(scope) if !scope.is_empty() => format!("{}::{}", scope, base.ident.name),
+        _ => base.ident.name.to_string(),
+    }
+}
+
+pub(crate) fn qualify(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}::{}", namespace, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::partial::merge_partial_classes;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    /// Missing methods of the classes in `source`, as `class: interface: signature`.
+    fn missing(source: &str) -> Vec<String> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let classes = merge_partial_classes(&[&script]).unwrap();
+        InterfaceSet::collect(&[&script])
+            .check_classes(&classes)
+            .into_iter()
+            .map(|error| match error {
+                CompilationError::MissingInterfaceMethod {
+                    class,
+                    interface,
+                    signature,
+                    ..
+                } => format!("{}: {}: {}", class, interface, signature),
+                other => panic!("unexpected error {other}"),
+            })
+            .collect()
+    }
+
+    const DIAMOND: &str = "
+        interface Named { string name() const; }
+        interface Actor : Named { void tick(float dt); }
+        interface Prop : Named { void place(); }
+        interface Thing : Actor, Prop { }
+    ";
+
+    #[test]
+    fn inherited_interfaces_are_listed_once() {
+        let arena = Bump::new();
+        let script = Parser::parse(DIAMOND, &arena).unwrap();
+        let set = InterfaceSet::collect(&[&script]);
+
+        assert_eq!(set.inherited("Thing"), ["Thing", "Actor", "Named", "Prop"]);
+        let required: Vec<String> = set
+            .required_methods(["Actor", "Prop"])
+            .into_iter()
+            .map(|r| format!("{}: {}", r.interface, r.signature))
+            .collect();
+        assert_eq!(
+            required,
+            [
+                "Actor: void tick(float)",
+                "Named: string name() const",
+                "Prop: void place()"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_each_missing_signature() {
+        let source = format!(
+            "{DIAMOND}
+            class Crate : Actor, Prop {{
+                string name() const {{ return \"crate\"; }}
+                void tick(float dt) {{ }}
+                void place() {{ }}
+            }}
+            class Ghost : Thing {{
+                string name() {{ return \"ghost\"; }}
+                void tick(int dt) {{ }}
+            }}"
+        );
+        assert_eq!(
+            missing(&source),
+            [
+                "Ghost: Actor: void tick(float)",
+                "Ghost: Named: string name() const",
+                "Ghost: Prop: void place()"
+            ]
+        );
+    }
+
+    #[test]
+    fn base_classes_and_namespaces() {
+        let source = "
+            namespace game {
+                interface Named { string name() const; }
+                interface Saved : Named { void save(); }
+                class Base : Named { string name() const { return \"base\"; } }
+            }
+            class Door : game::Base, game::Saved { }
+            namespace game { class Chest : Base, Saved { void save() { } } }
+        ";
+        assert_eq!(missing(source), ["Door: game::Saved: void save()"]);
+    }
+}
@@ -0,0 +1,369 @@
+//! Lowering of script declarations to the layouts the runtime uses.
+//!
+//! Each script class becomes a [`CompiledClass`] with its bases nearest
+//! first, every interface it implements, and its fields in slot order,
+//! inherited fields first:
+//!
+//! ```angelscript
+//! class Actor { string name; }
+//! class Player : Actor, Damageable {
+//!     [renamedFrom("hp")]
+//!     int health;
+//! }
+//! // Player: bases [Actor], interfaces [Damageable], fields [name, health]
+//! ```
+//!
+//! Each global variable becomes a [`CompiledGlobal`] with its qualified
+//! name and declared type.
+//!
+//! Function bodies are not compiled yet, so classes have no constructors
+//! or methods. Types the script and the registry do not declare are
+//! hashed by the name written in source.
+
+use angelscript_core::{DataType, TypeHash, primitives};
+use angelscript_parser::ast::{
+    ClassMember, Ident, IdentExpr, Item, PrimitiveType, Scope, Script, TypeBase, TypeExpr,
+    TypeSuffix,
+};
+use angelscript_registry::{SymbolRegistry, canonical_template_arg, template_instance_hash};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::access::candidate_names;
+use crate::interfaces::{InterfaceSet, base_name, namespace_of, resolve};
+use crate::modifiers::ancestors;
+use crate::partial::{MergedClass, merge_partial_classes};
+use crate::{CompiledClass, CompiledField, CompiledGlobal};
+
+/// Lower the classes declared in `script`, in declaration order.
+pub fn lower_classes(script: &Script<'_>, registry: &SymbolRegistry) -> Vec<CompiledClass> {
+    // Conflicting partial classes are reported before compilation
+    let classes = merge_partial_classes(&[script]).unwrap_or_default();
+    let interfaces = InterfaceSet::collect(&[script]);
+    let types = Types::collect(script, registry);
+    let by_name: FxHashMap<&str, &MergedClass<'_>> = classes
+        .iter()
+        .map(|class| (class.qualified_name.as_str(), class))
+        .collect();
+
+    classes
+        .iter()
+        .map(|class| {
+            let ancestors = ancestors(class, &by_name);
+            let mut implemented: Vec<TypeHash> = Vec::new();
+            for listing in std::iter::once(class).chain(ancestors.iter().copied()) {
+                for base in &listing.inheritance {
+                    for hash in types.interfaces(&listing.qualified_name, base, &interfaces) {
+                        if !implemented.contains(&hash) {
+                            implemented.push(hash);
+                        }
+                    }
+                }
+            }
+
+            let mut fields = Vec::new();
+            for listing in ancestors
+                .iter()
+                .rev()
+                .copied()
+                .chain(std::iter::once(class))
+            {
+                let namespace = split_namespace(namespace_of(&listing.qualified_name));
+                fields.extend(listing.members.iter().filter_map(|member| match member {
+                    ClassMember::Field(field) => Some(CompiledField {
+                        name: field.name.name.to_string(),
+                        data_type: types.data_type(&field.ty, &namespace),
+                        renamed_from: field.renamed_from.map(str::to_string),
+                    }),
+                    _ => None,
+                }));
+            }
+
+            CompiledClass {
+                name: class.qualified_name.clone(),
+                type_hash: TypeHash::from_name(&class.qualified_name),
+                bases: ancestors
+                    .iter()
+                    .map(|base| TypeHash::from_name(&base.qualified_name))
+                    .collect(),
+                interfaces: implemented,
+                fields,
+                constructors: Vec::new(),
+                methods: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// Lower the global variables declared in `script`, in declaration order.
+pub fn lower_globals(script: &Script<'_>, registry: &SymbolRegistry) -> Vec<CompiledGlobal> {
+    let types = Types::collect(script, registry);
+    let mut globals = Vec::new();
+    collect_globals(script.items(), &[], &types, &mut globals);
+    globals
+}
+
+fn collect_globals(
+    items: &[Item<'_>],
+    namespace: &[String],
+    types: &Types<'_>,
+    out: &mut Vec<CompiledGlobal>,
+) {
+    for item in items {
+        match item {
+            Item::GlobalVar(var) => {
+                let mut path = namespace.to_vec();
+                path.push(var.name.name.to_string());
+                out.push(CompiledGlobal {
+                    name: path.join("::"),
+                    data_type: types.data_type(&var.ty, namespace),
+                });
+            }
+            Item::Namespace(ns) => {
+                let mut nested = namespace.to_vec();
+                nested.extend(ns.path.iter().map(|s| s.name.to_string()));
+                collect_globals(ns.items, &nested, types, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Resolves type expressions against the script's declarations and the
+/// registry.
+pub(crate) struct Types<'r> {
+    registry: &'r SymbolRegistry,
+    /// Qualified names of the types declared by the script.
+    declared: FxHashSet<String>,
+}
+
+impl<'r> Types<'r> {
+    pub(crate) fn collect(script: &Script<'_>, registry: &'r SymbolRegistry) -> Self {
+        let mut declared = FxHashSet::default();
+        collect_types(script.items(), &[], &mut declared);
+        Self { registry, declared }
+    }
+
+    /// The type `ty` denotes when written in `namespace`.
+    pub(crate) fn data_type(&self, ty: &TypeExpr<'_>, namespace: &[String]) -> DataType {
+        let hash = match ty.base {
+            TypeBase::Primitive(primitive) => primitive_hash(primitive),
+            TypeBase::Named(ident) => self.named(ty.scope.as_ref(), ident, namespace),
+            TypeBase::TemplateParam(_) | TypeBase::Auto | TypeBase::Unknown => {
+                return DataType::void();
+            }
+        };
+        let hash = if ty.template_args.is_empty() {
+            hash
+        } else {
+            let args: Vec<DataType> = ty
+                .template_args
+                .iter()
+                .map(|arg| canonical_template_arg(&self.data_type(arg, namespace)))
+                .collect();
+            self.registry
+                .template_instance(hash, &args)
+                .unwrap_or_else(|| template_instance_hash(hash, &args))
+        };
+
+        match ty.suffixes.last() {
+            Some(TypeSuffix::Handle { is_const: true }) => {
+                DataType::const_handle(hash, ty.is_const)
+            }
+            Some(TypeSuffix::Handle { is_const: false }) => {
+                DataType::with_handle(hash, ty.is_const)
+            }
+            None if ty.is_const => DataType::with_const(hash),
+            None => DataType::simple(hash),
+        }
+    }
+
+    /// The type `ident`, without template arguments, denotes when written in
+    /// `namespace`.
+    pub(crate) fn named(
+        &self,
+        scope: Option<&Scope<'_>>,
+        ident: Ident<'_>,
+        namespace: &[String],
+    ) -> TypeHash {
+        let candidates = candidate_names(namespace, scope, ident.name);
+        for candidate in &candidates {
+            if self.declared.contains(candidate) {
+                return TypeHash::from_name(candidate);
+            }
+            if let Some(entry) = self.registry.get_by_name(candidate) {
+                return entry.type_hash();
+            }
+        }
+        // Unknown types are reported by the passes that use them
+        TypeHash::from_name(candidates.last().map_or(ident.name, String::as_str))
+    }
+
+    /// Qualified name of the script-declared type with `hash`.
+    pub(crate) fn script_name(&self, hash: TypeHash) -> Option<String> {
+        self.declared
+            .iter()
+            .find(|name| TypeHash::from_name(name) == hash)
+            .cloned()
+    }
+
+    /// The interfaces `base`, listed by the class `class`, stands for: a
+    /// script interface with those it extends, or a registered interface.
+    fn interfaces(
+        &self,
+        class: &str,
+        base: &IdentExpr<'_>,
+        interfaces: &InterfaceSet,
+    ) -> Vec<TypeHash> {
+        let namespace = namespace_of(class);
+        let name = base_name(base);
+        if let Some(found) = resolve(namespace, &name, |n| interfaces.contains(n)) {
+            return interfaces
+                .inherited(&found)
+                .iter()
+                .map(|interface| TypeHash::from_name(interface))
+                .collect();
+        }
+        resolve(namespace, &name, |n| {
+            self.registry
+                .get_by_name(n)
+                .is_some_and(|e| e.is_interface())
+        })
+        .and_then(|found| self.registry.get_by_name(&found))
+        .map(|entry| vec![entry.type_hash()])
+        .unwrap_or_default()
+    }
+}
+
+fn collect_types(items: &[Item<'_>], namespace: &[String], out: &mut FxHashSet<String>) {
+    for item in items {
+        let name = match item {
+            Item::Class(decl) => decl.name.name,
+            Item::Interface(decl) => decl.name.name,
+            Item::Enum(decl) => decl.name.name,
+            Item::Funcdef(decl) => decl.name.name,
+            Item::Namespace(ns) => {
+                let mut nested = namespace.to_vec();
+                nested.extend(ns.path.iter().map(|s| s.name.to_string()));
+                collect_types(ns.items, &nested, out);
+                continue;
+            }
+            _ => continue,
+        };
+        let mut path = namespace.to_vec();
+        path.push(name.to_string());
+        out.insert(path.join("::"));
+    }
+}
+
+pub(crate) fn split_namespace(namespace: &str) -> Vec<String> {
+    if namespace.is_empty() {
+        Vec::new()
+    } else {
+        namespace.split("::").map(str::to_string).collect()
+    }
+}
+
+fn primitive_hash(primitive: PrimitiveType) -> TypeHash {
+    match primitive {
+        PrimitiveType::Void => primitives::VOID,
+        PrimitiveType::Bool => primitives::BOOL,
+        PrimitiveType::Int => primitives::INT32,
+        PrimitiveType::Int8 => primitives::INT8,
+        PrimitiveType::Int16 => primitives::INT16,
+        PrimitiveType::Int64 => primitives::INT64,
+        PrimitiveType::UInt => primitives::UINT32,
+        PrimitiveType::UInt8 => primitives::UINT8,
+        PrimitiveType::UInt16 => primitives::UINT16,
+        PrimitiveType::UInt64 => primitives::UINT64,
+        PrimitiveType::Float => primitives::FLOAT,
+        PrimitiveType::Double => primitives::DOUBLE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn lower(source: &str) -> Vec<CompiledClass> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        lower_classes(&script, &SymbolRegistry::with_primitives())
+    }
+
+    #[test]
+    fn globals_with_qualified_names() {
+        let arena = Bump::new();
+        let script = Parser::parse(
+            "int score; namespace game { class Player {} const float gravity = 9.8; Player@ hero; }",
+            &arena,
+        )
+        .unwrap();
+        let globals = lower_globals(&script, &SymbolRegistry::with_primitives());
+
+        let lowered: Vec<_> = globals
+            .iter()
+            .map(|g| (g.name.as_str(), g.data_type))
+            .collect();
+        assert_eq!(
+            lowered,
+            [
+                ("score", DataType::simple(primitives::INT32)),
+                ("game::gravity", DataType::with_const(primitives::FLOAT)),
+                (
+                    "game::hero",
+                    DataType::with_handle(TypeHash::from_name("game::Player"), false)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn fields_in_slot_order_with_renames() {
+        let classes = lower(
+            "
+            interface Named {}
+            interface Damageable : Named {}
+            class Actor : Damageable { float x; }
+            namespace game {
+                class Player : Actor {
+                    [renamedFrom(\"hp\")]
+                    int health;
+                    const Player@ target;
+                }
+            }
+            ",
+        );
+        let player = classes.iter().find(|c| c.name == "game::Player").unwrap();
+        let actor = TypeHash::from_name("Actor");
+
+        assert_eq!(player.type_hash, TypeHash::from_name("game::Player"));
+        assert_eq!(player.bases, [actor]);
+        assert_eq!(
+            player.interfaces,
+            [
+                TypeHash::from_name("Damageable"),
+                TypeHash::from_name("Named")
+            ]
+        );
+
+        let fields: Vec<_> = player
+            .fields
+            .iter()
+            .map(|f| (f.name.as_str(), f.renamed_from.as_deref()))
+            .collect();
+        assert_eq!(
+            fields,
+            [("x", None), ("health", Some("hp")), ("target", None)]
+        );
+        assert_eq!(
+            player.fields[0].data_type,
+            DataType::simple(primitives::FLOAT)
+        );
+        assert_eq!(
+            player.fields[2].data_type,
+            DataType::with_handle(TypeHash::from_name("game::Player"), true)
+        );
+    }
+}
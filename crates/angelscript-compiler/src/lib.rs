@@ -1,14 +1,104 @@
 //! AngelScript Compiler
 //!
 //! This crate defines the compiler interface and bytecode types for AngelScript.
-//! The compilation logic is not yet implemented.
+//!
+//! [`Compiler::compile`] checks a script and lowers its declarations, but
+//! does not emit the bytecode of function bodies yet. It runs, in order:
+//!
+//! 1. access checks of registered entities ([`access`]), if masks are set;
+//! 2. constant evaluation ([`const_eval`]) and return checks
+//!    ([`ReturnChecker`]);
+//! 3. declaration checks ([`variable`]) and the typed expression checks
+//!    ([`typing`]), which drive [`auto`], [`operators`], [`cast`],
+//!    [`concat`](mod@concat), [`delegate`], [`foreach`], [`index`],
+//!    [`init_list`] and [`ternary`];
+//! 4. member visibility ([`visibility`]), extension gating
+//!    ([`extensions`]), nesting limits ([`nesting`]) and unsafe references
+//!    ([`references`]);
+//! 5. class and global layout ([`layout`]), virtual properties
+//!    ([`property::collect_properties`]) and closures ([`closure`]);
+//! 6. warnings ([`warnings`]) and the plugins' registration hooks;
+//! 7. reuse of unchanged functions from the build cache ([`incremental`]),
+//!    then constexpr evaluation, inlining, optimization and the plugins'
+//!    emit hooks.
+//!
+//! The unit building a module merges partial classes ([`partial`]), checks
+//! interfaces ([`interfaces`]) and modifiers ([`modifiers`]), and
+//! instantiates templates ([`templates`]) itself.
+//!
+//! Since no body is emitted, the only functions of a module are those
+//! reused from the cache, so the inliner and optimizer only ever see those,
+//! and skip them as already optimized. For the same reason the bytecode
+//! lowerings — the `emit` methods of [`PropertyAccess`], [`IndexAccess`],
+//! [`RefCast`] and the like — are not called by [`Compiler::compile`].
 
+pub mod access;
+pub mod auto;
 pub mod bytecode;
+pub mod cast;
+pub mod closure;
+pub mod concat;
+pub mod const_eval;
+pub mod constexpr;
+pub mod delegate;
+#[cfg(test)]
+mod differential;
+pub mod enum_intrinsics;
+pub mod extensions;
+pub mod foreach;
+pub mod incremental;
+pub mod index;
+pub mod init_list;
+pub mod inline;
+pub mod interfaces;
+pub mod layout;
+pub mod modifiers;
+pub mod nesting;
+pub mod operators;
+pub mod overflow;
+pub mod overload;
+pub mod partial;
+pub mod plugin;
+pub mod property;
+pub mod pure;
+pub mod reachability;
+pub mod references;
+pub mod returns;
+pub mod shared;
+pub mod templates;
+pub mod ternary;
+pub mod typing;
+pub mod usage;
+pub mod variable;
+pub mod visibility;
+pub mod warnings;
 
+pub use access::{AccessMask, AccessMasks};
 pub use angelscript_core::CompilationError;
+pub use cast::RefCast;
+pub use closure::{Capture, CaptureMode};
+pub use concat::{Concat, ConcatOperand, FormatArg};
+pub use const_eval::{CaseValues, ConstEvaluator, ConstValue, DefaultArg};
+pub use constexpr::ConstexprFunctions;
+pub use delegate::Delegate;
+pub use foreach::{ForeachLoop, ForeachProtocol};
+pub use incremental::{BuildCache, CacheKey, Fingerprints};
+pub use index::IndexAccess;
+pub use init_list::{InitList, InitShape};
+pub use inline::InlineFunctions;
+pub use interfaces::InterfaceSet;
+pub use overflow::IntegerOverflow;
+pub use overload::{Conversion, OverloadCache, OverloadCacheStats, OverloadMatch, UserConversion};
+pub use plugin::{CompilerPlugin, PluginContext, PluginFunction};
+pub use property::{AssignValue, PropertyAccess};
+pub use returns::ReturnChecker;
+pub use ternary::{ArmConversion, Ternary, TernaryArm};
+pub use usage::ApiUsage;
+pub use warnings::{Warning, WarningCode, WarningConfig, WarningLevel};
 
-use angelscript_core::{TypeHash, UnitId};
+use angelscript_core::{DataType, FuncdefEntry, Span, TypeHash, UnitId};
 use angelscript_parser::ast::Script;
+use angelscript_parser::directives::{SectionOptions, Suppressions};
 use angelscript_registry::SymbolRegistry;
 
 /// A compiled module containing bytecode and metadata.
@@ -20,23 +110,495 @@ pub struct CompiledModule {
     pub global_inits: Vec<CompiledFunction>,
     /// Module-level constant pool.
     pub constants: bytecode::ConstantPool,
+    /// Runtime layouts of script classes declared in the module.
+    pub classes: Vec<CompiledClass>,
+    /// Global variables declared in the module, in declaration order.
+    pub globals: Vec<CompiledGlobal>,
+    /// Constant globals declared in the module with their values, in
+    /// declaration order.
+    pub const_globals: Vec<(String, ConstValue)>,
+    /// Enums declared in the module, with their evaluated values.
+    pub enums: Vec<CompiledEnum>,
+    /// Default arguments of the module's functions and methods.
+    pub defaults: Vec<CompiledDefaults>,
+    /// Virtual properties of the module's classes.
+    pub properties: Vec<CompiledProperty>,
+    /// Captures of the module's lambdas, indexed by `NEW_CLOSURE`.
+    pub closures: Vec<CompiledClosure>,
+    /// What the module's integer arithmetic does on overflow.
+    pub integer_overflow: IntegerOverflow,
+}
+
+impl CompiledModule {
+    /// Find a function by name.
+    pub fn function(&self, name: &str) -> Option<&CompiledFunction> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+
+    /// Find a script class by qualified name.
+    pub fn class(&self, name: &str) -> Option<&CompiledClass> {
+        self.classes.iter().find(|c| c.name == name)
+    }
+
+    /// Find a global variable declaration by qualified name.
+    pub fn global(&self, name: &str) -> Option<&CompiledGlobal> {
+        self.globals.iter().find(|g| g.name == name)
+    }
+
+    /// Find the value of a constant global by qualified name.
+    pub fn const_global(&self, name: &str) -> Option<&ConstValue> {
+        self.const_globals
+            .iter()
+            .find(|(global, _)| global == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Find an enum by qualified name.
+    pub fn enum_type(&self, name: &str) -> Option<&CompiledEnum> {
+        self.enums.iter().find(|e| e.name == name)
+    }
+
+    /// Find the default arguments of every overload of a function or
+    /// method by qualified name (e.g. `game::Player::hit`).
+    pub fn defaults<'m>(&'m self, name: &'m str) -> impl Iterator<Item = &'m CompiledDefaults> {
+        self.defaults.iter().filter(move |d| d.function == name)
+    }
+
+    /// Find a virtual property of a script class by the class's qualified
+    /// name.
+    pub fn property(&self, class: &str, name: &str) -> Option<&CompiledProperty> {
+        self.properties
+            .iter()
+            .find(|p| p.class == class && p.name == name)
+    }
+
+    /// Find a script class by type hash.
+    pub fn class_by_hash(&self, type_hash: TypeHash) -> Option<&CompiledClass> {
+        self.classes.iter().find(|c| c.type_hash == type_hash)
+    }
 }
 
 /// A compiled function.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CompiledFunction {
     /// Function name.
     pub name: String,
+    /// Parameter and return types.
+    pub signature: FunctionSignature,
     /// Compiled bytecode.
     pub bytecode: bytecode::BytecodeChunk,
 }
 
+impl CompiledFunction {
+    /// Edit the function's bytecode.
+    ///
+    /// The bytecode is only replaced if `edit` and re-encoding succeed, so a
+    /// failed rewrite leaves the function unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// function.rewrite_bytecode(|rewriter| {
+    ///     let first = rewriter.ids().next().unwrap();
+    ///     rewriter.insert_before(first, [Instruction::simple(OpCode::PushZero, 0)]);
+    ///     Ok(())
+    /// })?;
+    /// ```
+    pub fn rewrite_bytecode<F>(&mut self, edit: F) -> Result<(), bytecode::RewriteError>
+    where
+        F: FnOnce(&mut bytecode::BytecodeRewriter) -> Result<(), bytecode::RewriteError>,
+    {
+        let mut rewriter = bytecode::BytecodeRewriter::new(&self.bytecode)?;
+        edit(&mut rewriter)?;
+        self.bytecode = rewriter.finish()?;
+        Ok(())
+    }
+}
+
+/// Parameter and return types of a function, available at runtime.
+///
+/// Used by the host to validate function handles against the funcdef they
+/// are expected to satisfy before storing or invoking them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSignature {
+    /// Parameter types, in declaration order.
+    pub params: Vec<DataType>,
+    /// Return type (`void` for functions without a return value).
+    pub return_type: DataType,
+}
+
+impl FunctionSignature {
+    /// Create a signature from parameter and return types.
+    pub fn new(params: Vec<DataType>, return_type: DataType) -> Self {
+        Self {
+            params,
+            return_type,
+        }
+    }
+
+    /// The signature declared by a funcdef.
+    pub fn of_funcdef(funcdef: &FuncdefEntry) -> Self {
+        Self::new(funcdef.params.clone(), funcdef.return_type)
+    }
+
+    /// Number of parameters.
+    pub fn param_count(&self) -> usize {
+        self.params.len()
+    }
+
+    /// Check if a function with this signature can be bound to `funcdef`.
+    pub fn matches_funcdef(&self, funcdef: &FuncdefEntry) -> bool {
+        self.params == funcdef.params && self.return_type == funcdef.return_type
+    }
+}
+
+impl Default for FunctionSignature {
+    fn default() -> Self {
+        Self::new(Vec::new(), DataType::void())
+    }
+}
+
+/// Runtime layout of a script class.
+#[derive(Debug, Clone)]
+pub struct CompiledClass {
+    /// Qualified class name (e.g. `game::Player`).
+    pub name: String,
+    /// Type hash of the class.
+    pub type_hash: TypeHash,
+    /// Base classes, nearest first.
+    pub bases: Vec<TypeHash>,
+    /// Interfaces implemented by the class, including inherited ones.
+    pub interfaces: Vec<TypeHash>,
+    /// Fields in slot order, including inherited fields first.
+    pub fields: Vec<CompiledField>,
+    /// Constructors.
+    pub constructors: Vec<CompiledMethod>,
+    /// Methods, including inherited ones not overridden.
+    pub methods: Vec<CompiledMethod>,
+}
+
+impl CompiledClass {
+    /// Check if a handle to the class can be used as a handle to `target`:
+    /// the class itself, one of its bases or one of its interfaces.
+    pub fn is_a(&self, target: TypeHash) -> bool {
+        self.type_hash == target || self.bases.contains(&target) || self.implements(target)
+    }
+
+    /// Check if the class implements an interface.
+    pub fn implements(&self, interface: TypeHash) -> bool {
+        self.interfaces.contains(&interface)
+    }
+
+    /// Find the slot index of a field by name.
+    pub fn field_index(&self, name: &str) -> Option<usize> {
+        self.fields.iter().position(|f| f.name == name)
+    }
+
+    /// Find a method by name and parameter count.
+    pub fn method(&self, name: &str, param_count: usize) -> Option<&CompiledMethod> {
+        self.methods
+            .iter()
+            .find(|m| m.name == name && m.param_count == param_count)
+    }
+
+    /// Find a constructor by parameter count.
+    pub fn constructor(&self, param_count: usize) -> Option<&CompiledMethod> {
+        self.constructors
+            .iter()
+            .find(|m| m.param_count == param_count)
+    }
+}
+
+/// A global variable declared by a module.
+#[derive(Debug, Clone)]
+pub struct CompiledGlobal {
+    /// Qualified name (e.g. `game::gravity`).
+    pub name: String,
+    /// Declared type.
+    pub data_type: DataType,
+}
+
+/// An enum declared by a module.
+#[derive(Debug, Clone)]
+pub struct CompiledEnum {
+    /// Qualified name (e.g. `game::Color`).
+    pub name: String,
+    /// Qualified enumerator names and values, in declaration order.
+    pub values: Vec<(String, i64)>,
+}
+
+impl CompiledEnum {
+    /// Find the value of an enumerator by its unqualified name.
+    pub fn value(&self, name: &str) -> Option<i64> {
+        self.values
+            .iter()
+            .find(|(qualified, _)| {
+                qualified
+                    .strip_prefix(self.name.as_str())
+                    .and_then(|rest| rest.strip_prefix("::"))
+                    == Some(name)
+            })
+            .map(|(_, value)| *value)
+    }
+
+    /// Unqualified name of the first enumerator with `value`.
+    pub fn name_of(&self, value: i64) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|(_, v)| *v == value)
+            .map(|(qualified, _)| {
+                qualified
+                    .rsplit_once("::")
+                    .map_or(qualified.as_str(), |(_, n)| n)
+            })
+    }
+
+    /// Text of a value as returned by the `toString` intrinsic: the name of
+    /// the first enumerator with the value, or the value in decimal if no
+    /// enumerator has it (e.g. combined flags).
+    pub fn display(&self, value: i64) -> String {
+        self.name_of(value)
+            .map_or_else(|| value.to_string(), str::to_string)
+    }
+
+    /// Value of an enumerator by its unqualified or qualified name, as read
+    /// by the `parse` intrinsic.
+    pub fn parse(&self, name: &str) -> Option<i64> {
+        self.values
+            .iter()
+            .find(|(qualified, _)| qualified == name)
+            .map(|(_, value)| *value)
+            .or_else(|| self.value(name))
+    }
+}
+
+/// Default arguments of one function declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledDefaults {
+    /// Qualified name of the function or method.
+    pub function: String,
+    /// Default argument of each parameter, `None` where there is none.
+    pub params: Vec<Option<DefaultArg>>,
+}
+
+impl CompiledDefaults {
+    /// Push the defaults of the parameters after the first `supplied`
+    /// arguments of a call.
+    ///
+    /// Returns `false`, without emitting anything, if one of them has no
+    /// default or a default that is not constant; the emitter then
+    /// compiles the default expressions in the scope of the function.
+    pub fn emit_missing(
+        &self,
+        chunk: &mut bytecode::BytecodeChunk,
+        constants: &mut bytecode::ConstantPool,
+        supplied: usize,
+        line: u32,
+    ) -> bool {
+        let missing = self.params.get(supplied..).unwrap_or_default();
+        let values: Option<Vec<&ConstValue>> = missing
+            .iter()
+            .map(|arg| match arg {
+                Some(DefaultArg::Constant(value)) => Some(value),
+                _ => None,
+            })
+            .collect();
+        let Some(values) = values else {
+            return false;
+        };
+        for value in values {
+            value.emit(chunk, constants, line);
+        }
+        true
+    }
+}
+
+/// A virtual property of a script class, backed by accessor methods.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledProperty {
+    /// Qualified name of the class.
+    pub class: String,
+    /// Property name.
+    pub name: String,
+    /// Declared type of the value, without `const`.
+    pub value_type: String,
+    /// Name of the getter method (`get_X`), if the property can be read.
+    pub getter: Option<String>,
+    /// Name of the setter method (`set_X`), if the property can be written.
+    pub setter: Option<String>,
+}
+
+/// The captures of a lambda or local function, making up the closure
+/// objects created from it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledClosure {
+    /// Qualified name of the function declaring the lambda; empty for
+    /// global variable initializers.
+    pub function: String,
+    /// For a local function, the name of the hidden module function it is
+    /// compiled to, `outer$name`, which scripts cannot refer to. `None` for
+    /// lambdas.
+    pub name: Option<String>,
+    /// Captured variables, in the order their values are pushed.
+    pub captures: Vec<Capture>,
+    /// Location of the lambda.
+    pub span: Span,
+}
+
+/// A field of a script class.
+#[derive(Debug, Clone)]
+pub struct CompiledField {
+    /// Field name.
+    pub name: String,
+    /// Declared type.
+    pub data_type: DataType,
+    /// Name of the field in an earlier version of the class, from
+    /// `[renamedFrom("old")]` metadata.
+    pub renamed_from: Option<String>,
+}
+
+/// A method or constructor of a script class.
+#[derive(Debug, Clone)]
+pub struct CompiledMethod {
+    /// Method name.
+    pub name: String,
+    /// Index of the compiled body in [`CompiledModule::functions`].
+    pub function: usize,
+    /// Number of parameters, excluding `this`.
+    pub param_count: usize,
+}
+
+/// Options controlling how a module is compiled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompilerOptions {
+    /// How much emitted bytecode is optimized.
+    pub optimization_level: bytecode::OptimizationLevel,
+    /// Thread conditions through `&&`, `||` and `!` and test loop conditions
+    /// at the bottom (see [`bytecode::optimize_branches`]). Turn off to keep
+    /// branches in source order while debugging; has no effect at
+    /// [`OptimizationLevel::None`](bytecode::OptimizationLevel::None).
+    pub optimize_branches: bool,
+    /// Instructions a call of a pure script function may run when it is
+    /// evaluated at compile time (see [`constexpr`]). Set to 0 to leave
+    /// script calls for the runtime.
+    pub constexpr_budget: usize,
+    /// Largest body, in instructions, of a script function whose calls are
+    /// inlined at [`OptimizationLevel::Aggressive`](bytecode::OptimizationLevel::Aggressive)
+    /// without an `[inline]` hint (see [`inline`]). Set to 0 to only inline
+    /// hinted functions.
+    pub inline_size: usize,
+    /// Whether integer overflow wraps or raises an exception (see
+    /// [`overflow`]).
+    pub integer_overflow: IntegerOverflow,
+    /// Levels of the warnings reported for the unit.
+    pub warning_config: WarningConfig,
+    /// Type of string literals, from the host's string factory. Without one,
+    /// string literals cannot be compiled.
+    pub string_type: Option<TypeHash>,
+    /// Deepest nesting of blocks allowed in a function body (see
+    /// [`nesting`]).
+    pub max_nesting_depth: usize,
+    /// Let script functions take primitives and value types by `&inout`
+    /// reference (see [`references`]).
+    pub allow_unsafe_references: bool,
+}
+
+impl Default for CompilerOptions {
+    fn default() -> Self {
+        Self {
+            optimization_level: bytecode::OptimizationLevel::default(),
+            optimize_branches: true,
+            constexpr_budget: constexpr::DEFAULT_BUDGET,
+            inline_size: inline::DEFAULT_INLINE_SIZE,
+            integer_overflow: IntegerOverflow::default(),
+            warning_config: WarningConfig::default(),
+            string_type: None,
+            max_nesting_depth: nesting::DEFAULT_MAX_DEPTH,
+            allow_unsafe_references: false,
+        }
+    }
+}
+
+impl CompilerOptions {
+    /// Create the default options.
+    ///
+    /// ```ignore
+    /// let options = CompilerOptions::new()
+    ///     .with_optimization_level(OptimizationLevel::Aggressive)
+    ///     .with_string_type(string_hash)
+    ///     .with_unsafe_references(true);
+    /// let compiler = Compiler::new(&registry, unit_id, options);
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how much emitted bytecode is optimized.
+    pub fn with_optimization_level(mut self, level: bytecode::OptimizationLevel) -> Self {
+        self.optimization_level = level;
+        self
+    }
+
+    /// Set whether branches are optimized.
+    pub fn with_branch_optimization(mut self, enabled: bool) -> Self {
+        self.optimize_branches = enabled;
+        self
+    }
+
+    /// Set the instructions a pure function may run at compile time.
+    pub fn with_constexpr_budget(mut self, budget: usize) -> Self {
+        self.constexpr_budget = budget;
+        self
+    }
+
+    /// Set the largest unhinted function inlined at the aggressive level.
+    pub fn with_inline_size(mut self, size: usize) -> Self {
+        self.inline_size = size;
+        self
+    }
+
+    /// Set what integer arithmetic does on overflow.
+    pub fn with_integer_overflow(mut self, overflow: IntegerOverflow) -> Self {
+        self.integer_overflow = overflow;
+        self
+    }
+
+    /// Set the levels of the warnings reported for the unit.
+    pub fn with_warning_config(mut self, config: WarningConfig) -> Self {
+        self.warning_config = config;
+        self
+    }
+
+    /// Set the type of string literals.
+    pub fn with_string_type(mut self, type_hash: TypeHash) -> Self {
+        self.string_type = Some(type_hash);
+        self
+    }
+
+    /// Set the deepest nesting of blocks allowed in a function body.
+    pub fn with_max_nesting_depth(mut self, depth: usize) -> Self {
+        self.max_nesting_depth = depth;
+        self
+    }
+
+    /// Set whether value types may be passed by `&inout` reference.
+    pub fn with_unsafe_references(mut self, allowed: bool) -> Self {
+        self.allow_unsafe_references = allowed;
+        self
+    }
+}
+
 /// Result of compilation.
 pub struct CompilationResult {
     /// The compiled module.
     pub module: CompiledModule,
     /// Any errors that occurred.
     pub errors: Vec<CompilationError>,
+    /// Warnings that were not suppressed, allowed or denied.
+    pub warnings: Vec<Warning>,
+    /// Names whose functions were taken from the build cache, sorted; the
+    /// empty name stands for the global initializers.
+    pub reused: Vec<String>,
 }
 
 impl CompilationResult {
@@ -49,34 +611,392 @@ impl CompilationResult {
 /// The main compiler entry point.
 pub struct Compiler<'a> {
     /// Global registry with FFI types and shared types.
-    _global_registry: &'a SymbolRegistry,
+    global_registry: &'a SymbolRegistry,
     /// Unit ID for this compilation.
-    _unit_id: UnitId,
-    /// String type hash from string factory (for string literal compilation).
-    _string_type_hash: Option<TypeHash>,
+    unit_id: UnitId,
+    /// Language options selected by the section's pragmas.
+    section_options: SectionOptions,
+    /// Custom passes run during compilation.
+    plugins: &'a [Box<dyn CompilerPlugin>],
+    /// Masks of registered entities and the access mask of the unit.
+    access: Option<(&'a AccessMasks, AccessMask)>,
+    /// Warnings suppressed by the section's pragmas.
+    suppressions: Suppressions,
+    /// Options set by the host.
+    options: CompilerOptions,
+    /// Functions of the previous build and the fingerprints of this script.
+    cache: Option<(&'a BuildCache, &'a Fingerprints)>,
+    /// Generation of the registry, access masks and plugins.
+    registry_generation: u64,
 }
 
 impl<'a> Compiler<'a> {
-    /// Create a new compiler with a global registry.
+    /// Create a new compiler with a global registry and the host's options.
     pub fn new(
         global_registry: &'a SymbolRegistry,
         unit_id: UnitId,
-        string_type_hash: Option<TypeHash>,
+        options: CompilerOptions,
     ) -> Self {
         Self {
-            _global_registry: global_registry,
-            _unit_id: unit_id,
-            _string_type_hash: string_type_hash,
+            global_registry,
+            unit_id,
+            section_options: SectionOptions::default(),
+            plugins: &[],
+            access: None,
+            suppressions: Suppressions::default(),
+            options,
+            cache: None,
+            registry_generation: 0,
         }
     }
 
+    /// The options set by the host.
+    pub fn options(&self) -> &CompilerOptions {
+        &self.options
+    }
+
+    /// Restrict the registered entities the script may use.
+    ///
+    /// Entities whose mask in `masks` shares no bit with `unit` are reported
+    /// as inaccessible.
+    pub fn with_access(mut self, masks: &'a AccessMasks, unit: AccessMask) -> Self {
+        self.access = Some((masks, unit));
+        self
+    }
+
+    /// Set the warnings suppressed by the section's pragmas.
+    pub fn with_suppressions(mut self, suppressions: Suppressions) -> Self {
+        self.suppressions = suppressions;
+        self
+    }
+
+    /// Reuse the functions of a previous build whose fingerprints match
+    /// `fingerprints`, those of the script about to be compiled.
+    pub fn with_cache(mut self, cache: &'a BuildCache, fingerprints: &'a Fingerprints) -> Self {
+        self.cache = Some((cache, fingerprints));
+        self
+    }
+
+    /// Set the generation of the registry, access masks and plugins.
+    ///
+    /// Hosts change it whenever any of them change, so that functions cached
+    /// from a build against the old ones are compiled again.
+    pub fn with_registry_generation(mut self, generation: u64) -> Self {
+        self.registry_generation = generation;
+        self
+    }
+
+    /// What a [`BuildCache`] of this compilation must match to be reused.
+    pub fn cache_key(&self) -> CacheKey {
+        CacheKey {
+            options: self.options.clone(),
+            section_options: self.section_options.clone(),
+            suppressions: self.suppressions.clone(),
+            access: self.access.map(|(_, unit)| unit),
+            registry_generation: self.registry_generation,
+        }
+    }
+
+    /// Set the plugins to run during compilation, in order.
+    pub fn with_plugins(mut self, plugins: &'a [Box<dyn CompilerPlugin>]) -> Self {
+        self.plugins = plugins;
+        self
+    }
+
+    /// Set the language options for the section being compiled.
+    ///
+    /// Strict sections report implicit narrowing
+    /// ([`WarningCode::ImplicitNarrowing`]) as an error whatever its level or
+    /// suppressions, and syntax extensions are only accepted when enabled
+    /// here.
+    pub fn with_section_options(mut self, options: SectionOptions) -> Self {
+        self.section_options = options;
+        self
+    }
+
+    /// The language options for the section being compiled.
+    pub fn section_options(&self) -> &SectionOptions {
+        &self.section_options
+    }
+
     /// Compile a script.
     ///
-    /// Currently a stub that returns an empty module with no errors.
-    pub fn compile(&self, _script: &Script<'_>) -> CompilationResult {
+    /// Currently a stub that emits a module without functions; only the
+    /// access check, constant evaluation, the warning checks and the plugin
+    /// hooks run, so plugins can already check the AST.
+    pub fn compile(&self, script: &Script<'_>) -> CompilationResult {
+        let mut module = CompiledModule::default();
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+
+        if let Some((masks, unit)) = self.access {
+            errors.extend(access::check_access(
+                script,
+                self.global_registry,
+                masks,
+                unit,
+            ));
+        }
+
+        module.integer_overflow = self.options.integer_overflow;
+        let (constants, const_errors) = const_eval::evaluate_constants(
+            script,
+            self.global_registry,
+            self.options.integer_overflow,
+        );
+        module.const_globals = constants.globals;
+        module.enums = constants.enums;
+        module.defaults = constants.defaults;
+        errors.extend(const_errors);
+
+        errors.extend(ReturnChecker::with_enums(self.global_registry, &module.enums).check(script));
+        errors.extend(variable::check_script_declarations(script));
+        errors.extend(typing::check_types(
+            script,
+            self.global_registry,
+            self.options.string_type,
+        ));
+        errors.extend(visibility::check_member_access(script));
+        errors.extend(extensions::check_extensions(script, &self.section_options));
+        errors.extend(nesting::check_nesting(
+            script,
+            self.options.max_nesting_depth,
+        ));
+        if !self.options.allow_unsafe_references {
+            errors.extend(references::check_unsafe_references(
+                script,
+                self.global_registry,
+            ));
+        }
+
+        module.classes = layout::lower_classes(script, self.global_registry);
+        module.globals = layout::lower_globals(script, self.global_registry);
+
+        let (properties, property_errors) = property::collect_properties(script);
+        module.properties = properties;
+        errors.extend(property_errors);
+
+        let (closures, closure_errors) = closure::collect_closures(script, self.global_registry);
+        module.closures = closures;
+        errors.extend(closure_errors);
+
+        for warning in warnings::check_warnings(script, self.global_registry) {
+            let level = match warning.code {
+                WarningCode::ImplicitNarrowing if self.section_options.strict => {
+                    errors.push(CompilationError::DeniedWarning {
+                        code: warning.code.code(),
+                        message: warning.message,
+                        span: warning.span,
+                    });
+                    continue;
+                }
+                code => self.options.warning_config.level(code),
+            };
+            if self
+                .suppressions
+                .is_suppressed(warning.code.code(), warning.span.line)
+            {
+                continue;
+            }
+            match level {
+                WarningLevel::Allow => {}
+                WarningLevel::Warn => warnings.push(warning),
+                WarningLevel::Deny => errors.push(CompilationError::DeniedWarning {
+                    code: warning.code.code(),
+                    message: warning.message,
+                    span: warning.span,
+                }),
+            }
+        }
+
+        for plugin in self.plugins {
+            let mut ctx = self.plugin_context(plugin.as_ref(), &mut errors);
+            plugin.after_registration(&mut ctx, script);
+        }
+
+        let mut bodies = Vec::new();
+        plugin::function_bodies(script.items(), "", &mut bodies);
+        for (name, decl) in &bodies {
+            let function = PluginFunction { name, decl };
+            for plugin in self.plugins {
+                let mut ctx = self.plugin_context(plugin.as_ref(), &mut errors);
+                plugin.before_emit(&mut ctx, &function);
+            }
+        }
+
+        // Reused functions come first and were already inlined, optimized
+        // and seen by the plugins
+        let mut reused = Vec::new();
+        if let Some((cache, fingerprints)) = self.cache {
+            reused = cache.reusable(fingerprints, &module.closures, &self.cache_key());
+            if !reused.is_empty() {
+                module.constants = cache.constants().clone();
+            }
+            for name in &reused {
+                let functions = cache.reuse(name, fingerprints);
+                if name.is_empty() {
+                    module.global_inits.extend(functions);
+                } else {
+                    module.functions.extend(functions);
+                }
+            }
+        }
+        let reused_functions = module.functions.len();
+        let reused_inits = module.global_inits.len();
+
+        let constexpr = ConstexprFunctions::collect(
+            &module.functions,
+            &module.constants,
+            self.global_registry,
+            self.options.constexpr_budget,
+        );
+
+        // Inline against the bodies as emitted, so one function's
+        // optimizations never change what is inlined into another
+        let inline = InlineFunctions::collect(
+            &module.functions,
+            &module.constants,
+            &inline::inline_hints(script),
+            self.options.optimization_level,
+            self.options.inline_size,
+        );
+        for function in module
+            .functions
+            .iter_mut()
+            .skip(reused_functions)
+            .chain(module.global_inits.iter_mut().skip(reused_inits))
+        {
+            let params = function.signature.params.len() as u16;
+            match inline::inline_calls(&function.bytecode, &module.constants, &inline, params) {
+                Ok(inlined) => function.bytecode = inlined,
+                Err(error) => errors.push(CompilationError::Internal {
+                    message: format!("failed to inline calls in '{}': {}", function.name, error),
+                }),
+            }
+        }
+
+        // Optimize before the plugins see the code, so instrumentation they
+        // add is kept as is
+        for function in module
+            .functions
+            .iter_mut()
+            .skip(reused_functions)
+            .chain(module.global_inits.iter_mut().skip(reused_inits))
+        {
+            errors.extend(optimize_function(
+                function,
+                &mut module.constants,
+                self.global_registry,
+                &constexpr,
+                &self.options,
+            ));
+        }
+
+        for function in module
+            .functions
+            .iter_mut()
+            .skip(reused_functions)
+            .chain(module.global_inits.iter_mut().skip(reused_inits))
+        {
+            for plugin in self.plugins {
+                let mut ctx = self.plugin_context(plugin.as_ref(), &mut errors);
+                plugin.after_emit(&mut ctx, function);
+            }
+        }
+
         CompilationResult {
-            module: CompiledModule::default(),
-            errors: Vec::new(),
+            module,
+            errors,
+            warnings,
+            reused,
+        }
+    }
+
+    fn plugin_context<'c>(
+        &'c self,
+        plugin: &'c dyn CompilerPlugin,
+        errors: &'c mut Vec<CompilationError>,
+    ) -> PluginContext<'c> {
+        PluginContext::new(self.global_registry, self.unit_id, plugin.name(), errors)
+    }
+}
+
+/// Run the optimization passes `options` enable on a compiled function.
+///
+/// Returns the errors of passes that failed; a failed pass leaves the
+/// bytecode as it was before it.
+pub(crate) fn optimize_function(
+    function: &mut CompiledFunction,
+    constants: &mut bytecode::ConstantPool,
+    registry: &SymbolRegistry,
+    constexpr: &ConstexprFunctions<'_>,
+    options: &CompilerOptions,
+) -> Vec<CompilationError> {
+    let mut errors = Vec::new();
+    let params: Vec<TypeHash> = function
+        .signature
+        .params
+        .iter()
+        .map(|param| param.type_hash)
+        .collect();
+    if options.optimization_level >= bytecode::OptimizationLevel::Basic {
+        let hash = TypeHash::from_function(&function.name, &params);
+        match bytecode::eliminate_tail_calls(
+            &function.bytecode,
+            constants,
+            hash,
+            params.len() as u16,
+        ) {
+            Ok(rewritten) => function.bytecode = rewritten,
+            Err(error) => errors.push(CompilationError::Internal {
+                message: format!("failed to optimize '{}': {}", function.name, error),
+            }),
+        }
+    }
+
+    if options.optimization_level >= bytecode::OptimizationLevel::Basic {
+        match constexpr::fold_constexpr_calls(&function.bytecode, constants, constexpr) {
+            Ok(folded) => function.bytecode = folded,
+            Err(error) => errors.push(CompilationError::Internal {
+                message: format!("failed to optimize '{}': {}", function.name, error),
+            }),
+        }
+    }
+
+    if options.optimize_branches && options.optimization_level > bytecode::OptimizationLevel::None {
+        match bytecode::optimize_branches(&function.bytecode) {
+            Ok(rewritten) => function.bytecode = rewritten,
+            Err(error) => errors.push(CompilationError::Internal {
+                message: format!("failed to optimize '{}': {}", function.name, error),
+            }),
+        }
+    }
+
+    match bytecode::optimize(&function.bytecode, constants, options.optimization_level) {
+        Ok(optimized) => function.bytecode = optimized,
+        Err(error) => errors.push(CompilationError::Internal {
+            message: format!("failed to optimize '{}': {}", function.name, error),
+        }),
+    }
+
+    if options.optimization_level >= bytecode::OptimizationLevel::Aggressive {
+        match bytecode::eliminate_common_subexpressions(
+            &function.bytecode,
+            constants,
+            params.len() as u16,
+            |callee| pure::is_pure(registry, callee),
+        ) {
+            Ok(rewritten) => function.bytecode = rewritten,
+            Err(error) => errors.push(CompilationError::Internal {
+                message: format!("failed to optimize '{}': {}", function.name, error),
+            }),
+        }
+        match bytecode::compact_locals(&function.bytecode, params.len() as u16) {
+            Ok((compacted, _)) => function.bytecode = compacted,
+            Err(error) => errors.push(CompilationError::Internal {
+                message: format!("failed to lay out '{}': {}", function.name, error),
+            }),
         }
     }
+    errors
 }
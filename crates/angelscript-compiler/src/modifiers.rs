@@ -0,0 +1,358 @@
+//! Verification of the `final`, `override` and `abstract` modifiers.
+//!
+//! ```angelscript
+//! abstract class Shape { float area() const { return 0; } }
+//! final class Circle : Shape {
+//!     float area() const override { return 3.14f; }
+//!     float radius() override { return 1; }   // error: overrides nothing
+//! }
+//! class Ring : Circle { }                     // error: Circle is final
+//!
+//! class Base { void tick() final { } }
+//! class Derived : Base { void tick() { } }    // error: tick is final
+//!
+//! void main() {
+//!     Shape s;                                // error: Shape is abstract
+//!     Shape@ h = Circle();                    // ok: only a handle
+//! }
+//! ```
+//!
+//! A method marked `override` must replace a method of a base class or of
+//! an interface the class implements. Signatures are compared as written
+//! in the source, like the interface checks do.
+//!
+//! Abstract classes may be used through handles and as base classes, but
+//! not declared by value nor constructed by calling the class.
+
+use angelscript_core::{CompilationError, Span};
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{
+    CallExpr, ClassMember, Expr, FieldDecl, FunctionDecl, GlobalVarDecl, NamespaceDecl, Script,
+    TypeBase, TypeExpr, TypeSuffix, VarDeclStmt,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::interfaces::{InterfaceSet, base_name, namespace_of, resolve, signature};
+use crate::partial::MergedClass;
+
+/// Report misuses of `final`, `override` and `abstract` in `sections`.
+///
+/// `classes` are the merged classes of the sections and `interfaces` the
+/// interfaces they declare.
+pub fn check_modifiers(
+    sections: &[&Script<'_>],
+    classes: &[MergedClass<'_>],
+    interfaces: &InterfaceSet,
+) -> Vec<CompilationError> {
+    let by_name: FxHashMap<&str, &MergedClass<'_>> = classes
+        .iter()
+        .map(|class| (class.qualified_name.as_str(), class))
+        .collect();
+
+    let mut errors = Vec::new();
+    for class in classes {
+        check_class(class, &by_name, interfaces, &mut errors);
+    }
+
+    let abstract_classes: FxHashSet<&str> = classes
+        .iter()
+        .filter(|class| class.modifiers.abstract_)
+        .map(|class| class.qualified_name.as_str())
+        .collect();
+    if !abstract_classes.is_empty() {
+        let mut pass = AbstractPass {
+            abstract_classes: &abstract_classes,
+            classes: &by_name,
+            namespace: Vec::new(),
+            errors: &mut errors,
+        };
+        for script in sections {
+            pass.visit_script(script);
+        }
+    }
+    errors
+}
+
+fn check_class(
+    class: &MergedClass<'_>,
+    classes: &FxHashMap<&str, &MergedClass<'_>>,
+    interfaces: &InterfaceSet,
+    errors: &mut Vec<CompilationError>,
+) {
+    let ancestors = ancestors(class, classes);
+    if let Some(base) = ancestors.first()
+        && base.modifiers.final_
+    {
+        errors.push(CompilationError::FinalClassDerived {
+            class: class.qualified_name.clone(),
+            base: base.qualified_name.clone(),
+            span: class.span(),
+        });
+    }
+
+    // Interfaces listed by the class or any of its bases
+    let mut implemented = Vec::new();
+    for listing in std::iter::once(class).chain(ancestors.iter().copied()) {
+        let namespace = namespace_of(&listing.qualified_name);
+        implemented.extend(
+            listing.inheritance.iter().filter_map(|base| {
+                resolve(namespace, &base_name(base), |n| interfaces.contains(n))
+            }),
+        );
+    }
+    let interface_methods: FxHashSet<String> = interfaces
+        .required_methods(implemented.iter().map(String::as_str))
+        .into_iter()
+        .map(|required| required.signature)
+        .collect();
+
+    for func in methods(class) {
+        let sig = method_signature(func);
+        let overridden = ancestors.iter().find_map(|base| {
+            methods(base)
+                .find(|m| method_signature(m) == sig)
+                .map(|m| (*base, m))
+        });
+
+        if let Some((base, method)) = overridden
+            && method.attrs.final_
+        {
+            errors.push(CompilationError::FinalMethodOverridden {
+                method: format!("{}::{}", class.qualified_name, func.name.name),
+                base: base.qualified_name.clone(),
+                span: func.span,
+            });
+        }
+        if func.attrs.override_ && overridden.is_none() && !interface_methods.contains(&sig) {
+            errors.push(CompilationError::OverrideWithoutBase {
+                method: format!("{}::{}", class.qualified_name, func.name.name),
+                span: func.span,
+            });
+        }
+    }
+}
+
+/// The base classes of a class, nearest first, stopping at cycles.
+pub(crate) fn ancestors<'c, 'ast>(
+    class: &MergedClass<'ast>,
+    classes: &FxHashMap<&str, &'c MergedClass<'ast>>,
+) -> Vec<&'c MergedClass<'ast>> {
+    let mut out: Vec<&MergedClass<'ast>> = Vec::new();
+    let mut current = class;
+    loop {
+        let namespace = namespace_of(&current.qualified_name);
+        let Some(parent) = current
+            .inheritance
+            .iter()
+            .find_map(|base| resolve(namespace, &base_name(base), |n| classes.contains_key(n)))
+        else {
+            break;
+        };
+        // Inheritance cycles are reported elsewhere
+        if parent == class.qualified_name || out.iter().any(|c| c.qualified_name == parent) {
+            break;
+        }
+        let parent = classes[parent.as_str()];
+        out.push(parent);
+        current = parent;
+    }
+    out
+}
+
+/// The methods of a class, without constructors and destructors.
+fn methods<'c, 'ast>(
+    class: &'c MergedClass<'ast>,
+) -> impl Iterator<Item = &'c FunctionDecl<'ast>> + 'c {
+    class.members.iter().filter_map(|member| match member {
+        ClassMember::Method(func) if func.return_type.is_some() && !func.is_destructor => {
+            Some(func)
+        }
+        _ => None,
+    })
+}
+
+fn method_signature(func: &FunctionDecl<'_>) -> String {
+    let return_type = func.return_type.as_ref().map(|r| r.to_string());
+    signature(
+        return_type.as_deref().unwrap_or_default(),
+        func.name.name,
+        func.params,
+        func.is_const,
+    )
+}
+
+struct AbstractPass<'c, 'ast> {
+    abstract_classes: &'c FxHashSet<&'c str>,
+    classes: &'c FxHashMap<&'c str, &'c MergedClass<'ast>>,
+    namespace: Vec<String>,
+    errors: &'c mut Vec<CompilationError>,
+}
+
+impl AbstractPass<'_, '_> {
+    /// Resolve a class name written in the current namespace to an abstract
+    /// class.
+    fn abstract_class(&self, scope: Option<&str>, name: &str) -> Option<String> {
+        let name = match scope {
+            Some(scope) if !scope.is_empty() => format!("{}::{}", scope, name),
+            _ => name.to_string(),
+        };
+        resolve(&self.namespace.join("::"), &name, |n| {
+            self.classes.contains_key(n)
+        })
+        .filter(|class| self.abstract_classes.contains(class.as_str()))
+    }
+
+    /// Report a value of an abstract class declared with `ty`.
+    fn check_type(&mut self, ty: &TypeExpr<'_>, span: Span) {
+        let TypeBase::Named(ident) = ty.base else {
+            return;
+        };
+        if ty
+            .suffixes
+            .iter()
+            .any(|suffix| matches!(suffix, TypeSuffix::Handle { .. }))
+        {
+            return;
+        }
+        let scope = ty.scope.map(|scope| scope.to_string());
+        if let Some(class) = self.abstract_class(scope.as_deref(), ident.name) {
+            self.errors
+                .push(CompilationError::AbstractInstantiation { class, span });
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for AbstractPass<'_, 'ast> {
+    fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+        let depth = self.namespace.len();
+        self.namespace
+            .extend(namespace.path.iter().map(|s| s.name.to_string()));
+        visitor::walk_namespace_decl(self, namespace);
+        self.namespace.truncate(depth);
+    }
+
+    fn visit_global_var_decl(&mut self, var: &GlobalVarDecl<'ast>) {
+        self.check_type(&var.ty, var.span);
+        visitor::walk_global_var_decl(self, var);
+    }
+
+    fn visit_field_decl(&mut self, field: &FieldDecl<'ast>) {
+        self.check_type(&field.ty, field.span);
+        visitor::walk_field_decl(self, field);
+    }
+
+    fn visit_var_decl_stmt(&mut self, stmt: &VarDeclStmt<'ast>) {
+        self.check_type(&stmt.ty, stmt.span);
+        visitor::walk_var_decl_stmt(self, stmt);
+    }
+
+    fn visit_call_expr(&mut self, expr: &CallExpr<'ast>) {
+        if let Expr::Ident(ident) = expr.callee {
+            let scope = ident.scope.map(|scope| scope.to_string());
+            if let Some(class) = self.abstract_class(scope.as_deref(), ident.ident.name) {
+                self.errors.push(CompilationError::AbstractInstantiation {
+                    class,
+                    span: expr.span,
+                });
+            }
+        }
+        visitor::walk_call_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::partial::merge_partial_classes;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    /// Errors reported for `source`, as `kind: name`.
+    fn errors(source: &str) -> Vec<String> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let classes = merge_partial_classes(&[&script]).unwrap();
+        let interfaces = InterfaceSet::collect(&[&script]);
+        check_modifiers(&[&script], &classes, &interfaces)
+            .into_iter()
+            .map(|error| match error {
+                CompilationError::FinalClassDerived { class, base, .. } => {
+                    format!("final class: {} : {}", class, base)
+                }
+                CompilationError::FinalMethodOverridden { method, base, .. } => {
+                    format!("final method: {} of {}", method, base)
+                }
+                CompilationError::OverrideWithoutBase { method, .. } => {
+                    format!("override: {}", method)
+                }
+                CompilationError::AbstractInstantiation { class, .. } => {
+                    format!("abstract: {}", class)
+                }
+                other => panic!("unexpected error {other}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn final_classes_and_methods() {
+        let source = "
+            class Base { void tick() final { } void draw() { } }
+            class Middle : Base { void draw() final { } }
+            final class Leaf : Middle { void draw() { } }
+            class Twig : Leaf { }
+        ";
+        assert_eq!(
+            errors(source),
+            [
+                "final method: Leaf::draw of Middle",
+                "final class: Twig : Leaf"
+            ]
+        );
+    }
+
+    #[test]
+    fn override_must_replace_a_method() {
+        let source = "
+            interface Named { string name() const; }
+            class Base : Named {
+                string name() const { return \"base\"; }
+                void tick(float dt) { }
+            }
+            class Derived : Base {
+                string name() const override { return \"derived\"; }
+                void tick(float dt) override { }
+                void tick(int dt) override { }
+                void draw() override { }
+            }
+            class Named2 : Named { string name() const override { return \"\"; } }
+        ";
+        assert_eq!(
+            errors(source),
+            ["override: Derived::tick", "override: Derived::draw"]
+        );
+    }
+
+    #[test]
+    fn abstract_classes_are_only_used_through_handles() {
+        let source = "
+            namespace game { abstract class Shape { } }
+            class Circle : game::Shape { }
+            game::Shape global;
+            class Holder { game::Shape@ shape; }
+            void main() {
+                game::Shape@ h = Circle();
+                Circle c;
+                game::Shape s;
+                @h = game::Shape();
+            }
+        ";
+        assert_eq!(
+            errors(source),
+            [
+                "abstract: game::Shape",
+                "abstract: game::Shape",
+                "abstract: game::Shape"
+            ]
+        );
+    }
+}
@@ -0,0 +1,108 @@
+//! Limit on how deeply blocks nest.
+//!
+//! The compiler's passes recurse over the statements of a body, so a script
+//! nesting blocks thousands deep could exhaust the stack. Hosts compiling
+//! untrusted scripts bound the depth with
+//! [`CompilerOptions::max_nesting_depth`]; a function body is depth 1, and
+//! each block inside it, including lambda bodies, one more:
+//!
+//! ```angelscript
+//! void main() {               // 1
+//!     if (ready) {            // 2
+//!         while (busy) {      // 3
+//!             wait();
+//!         }
+//!     }
+//! }
+//! ```
+//!
+//! Statements without braces, such as `if (a) if (b) f();`, do not add to the
+//! depth.
+//!
+//! [`CompilerOptions::max_nesting_depth`]: crate::CompilerOptions::max_nesting_depth
+
+use angelscript_core::CompilationError;
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{Block, Script};
+
+/// Depth allowed unless the host sets another.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Report every block nested deeper than `max_depth`.
+///
+/// Only the outermost block over the limit is reported; blocks inside it
+/// are not.
+pub fn check_nesting(script: &Script<'_>, max_depth: usize) -> Vec<CompilationError> {
+    let mut pass = NestingPass {
+        max_depth,
+        depth: 0,
+        errors: Vec::new(),
+    };
+    pass.visit_script(script);
+    pass.errors
+}
+
+struct NestingPass {
+    max_depth: usize,
+    depth: usize,
+    errors: Vec<CompilationError>,
+}
+
+impl<'ast> Visitor<'ast> for NestingPass {
+    fn visit_block(&mut self, block: &Block<'ast>) {
+        self.depth += 1;
+        if self.depth == self.max_depth + 1 {
+            self.errors.push(CompilationError::NestingTooDeep {
+                limit: self.max_depth,
+                span: block.span,
+            });
+        }
+        visitor::walk_block(self, block);
+        self.depth -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn check(source: &str, max_depth: usize) -> Vec<CompilationError> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        check_nesting(&script, max_depth)
+    }
+
+    #[test]
+    fn blocks_over_the_limit_are_reported_once() {
+        let source = "
+            void main() {
+                if (true) {
+                    while (false) { { int x = 1; } }
+                }
+                for (int i = 0; i < 2; i++) {}
+            }
+        ";
+        assert!(check(source, 4).is_empty());
+
+        let errors = check(source, 2);
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0],
+            CompilationError::NestingTooDeep { limit: 2, span } if span.line == 4
+        ));
+    }
+
+    #[test]
+    fn lambda_bodies_count() {
+        let source = "
+            funcdef void Cb();
+            void main() {
+                Cb@ cb = function() { if (true) {} };
+            }
+        ";
+        assert!(check(source, 3).is_empty());
+        assert_eq!(check(source, 2).len(), 1);
+    }
+}
@@ -0,0 +1,302 @@
+//! Binary operator overload lookup.
+//!
+//! For `a op b` the candidates are searched in this order:
+//!
+//! 1. `opX` methods on the type of `a`
+//! 2. `opX_r` methods on the type of `b`
+//! 3. Script extension operators: global script functions named `opX`
+//!    taking `(a, b)`, where at least one operand is a registered native type
+//!
+//! Extension operators are only considered when no native candidate exists,
+//! so a script can add operators to engine types (e.g.
+//! `string opMul(const string &in s, int n)`) but never replace one the
+//! application registered.
+//!
+//! Operands are matched by type identity; implicit conversions are applied
+//! by the caller before lookup.
+
+use angelscript_core::{FunctionDef, FunctionEntry, Operator, TypeHash};
+use angelscript_registry::SymbolRegistry;
+
+/// Where a resolved operator overload was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperatorSource {
+    /// `opX` method on the left operand's type.
+    Method,
+    /// `opX_r` method on the right operand's type.
+    ReverseMethod,
+    /// Global script function extending a native type.
+    Extension,
+}
+
+/// A resolved binary operator overload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperatorMatch {
+    /// The function implementing the operator.
+    pub func_hash: TypeHash,
+    /// Where the function was found, which determines the calling convention.
+    pub source: OperatorSource,
+}
+
+/// Find the overload implementing `left op right`.
+///
+/// `op` must be the forward form of a binary operator (`Add`, not `AddR`).
+pub fn resolve_binary(
+    registry: &SymbolRegistry,
+    op: Operator,
+    left: TypeHash,
+    right: TypeHash,
+) -> Option<OperatorMatch> {
+    if let Some(func_hash) = find_method(registry, left, op, right) {
+        return Some(OperatorMatch {
+            func_hash,
+            source: OperatorSource::Method,
+        });
+    }
+
+    if let Some(func_hash) = op
+        .reversed()
+        .and_then(|reverse| find_method(registry, right, reverse, left))
+    {
+        return Some(OperatorMatch {
+            func_hash,
+            source: OperatorSource::ReverseMethod,
+        });
+    }
+
+    registry
+        .get_function_overloads(op.method_name())?
+        .iter()
+        .filter_map(|&hash| registry.get_function(hash))
+        .find(|entry| {
+            entry.is_script()
+                && extension_operator(&entry.def, registry) == Some(op)
+                && has_param_types(entry, &[left, right])
+        })
+        .map(|entry| OperatorMatch {
+            func_hash: entry.def.func_hash,
+            source: OperatorSource::Extension,
+        })
+}
+
+/// Classify a global script function as an extension operator.
+///
+/// Returns the operator if the function is a global `opX` function taking
+/// two parameters, at least one of which is a registered native type.
+/// Primitive operands do not count: scripts cannot redefine `int * int`.
+pub fn extension_operator(def: &FunctionDef, registry: &SymbolRegistry) -> Option<Operator> {
+    if def.is_method() || !def.namespace.is_empty() || def.params.len() != 2 {
+        return None;
+    }
+
+    let op = Operator::from_method_name(&def.name)?;
+    if op.reversed().is_none() && !op.is_comparison() {
+        return None;
+    }
+
+    let extends_native = def.params.iter().any(|p| {
+        registry
+            .get(p.data_type.type_hash)
+            .and_then(|entry| entry.source())
+            .is_some_and(|source| source.is_ffi())
+    });
+    extends_native.then_some(op)
+}
+
+/// Find a single-argument operator method on `owner` taking `operand`.
+fn find_method(
+    registry: &SymbolRegistry,
+    owner: TypeHash,
+    op: Operator,
+    operand: TypeHash,
+) -> Option<TypeHash> {
+    let class = registry.get(owner)?.as_class()?;
+    class
+        .behaviors
+        .get_operator(op)?
+        .iter()
+        .copied()
+        .find(|&hash| {
+            registry
+                .get_function(hash)
+                .is_some_and(|entry| has_param_types(entry, &[operand]))
+        })
+}
+
+/// Check the base types of a function's parameters, without allocating.
+fn has_param_types(entry: &FunctionEntry, types: &[TypeHash]) -> bool {
+    entry.def.params.len() == types.len()
+        && entry
+            .def
+            .params
+            .iter()
+            .zip(types)
+            .all(|(param, &ty)| param.data_type.type_hash == ty)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{
+        ClassEntry, DataType, FunctionSource, FunctionTraits, Param, Span, TypeKind, UnitId,
+        Visibility, primitives,
+    };
+
+    fn string_hash() -> TypeHash {
+        TypeHash::from_name("string")
+    }
+
+    fn function(
+        name: &str,
+        object_type: Option<TypeHash>,
+        params: &[TypeHash],
+        return_type: TypeHash,
+    ) -> FunctionDef {
+        FunctionDef::new(
+            TypeHash::from_function(name, params),
+            name.to_string(),
+            Vec::new(),
+            params
+                .iter()
+                .enumerate()
+                .map(|(i, &t)| Param::new(format!("p{}", i), DataType::simple(t)))
+                .collect(),
+            DataType::simple(return_type),
+            object_type,
+            FunctionTraits::default(),
+            object_type.is_some(),
+            Visibility::Public,
+        )
+    }
+
+    fn registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+        registry
+            .register_type(ClassEntry::ffi("string", TypeKind::reference()).into())
+            .unwrap();
+        registry
+    }
+
+    fn register_script(registry: &mut SymbolRegistry, def: FunctionDef) -> TypeHash {
+        let hash = def.func_hash;
+        let source = FunctionSource::script(Span::new(1, 0, 10));
+        registry
+            .register_function(FunctionEntry::script(def, UnitId::new(0), source))
+            .unwrap();
+        hash
+    }
+
+    fn register_native_operator(
+        registry: &mut SymbolRegistry,
+        op: Operator,
+        operand: TypeHash,
+    ) -> TypeHash {
+        let def = function(
+            op.method_name(),
+            Some(string_hash()),
+            &[operand],
+            string_hash(),
+        );
+        let hash = def.func_hash;
+        registry.register_function(FunctionEntry::ffi(def)).unwrap();
+        registry
+            .get_class_mut(string_hash())
+            .unwrap()
+            .behaviors
+            .add_operator(op, hash);
+        hash
+    }
+
+    #[test]
+    fn extension_operator_on_native_type() {
+        let mut registry = registry();
+        let hash = register_script(
+            &mut registry,
+            function(
+                "opMul",
+                None,
+                &[string_hash(), primitives::INT32],
+                string_hash(),
+            ),
+        );
+
+        assert_eq!(
+            resolve_binary(&registry, Operator::Mul, string_hash(), primitives::INT32),
+            Some(OperatorMatch {
+                func_hash: hash,
+                source: OperatorSource::Extension,
+            })
+        );
+        // Operand order matters
+        assert_eq!(
+            resolve_binary(&registry, Operator::Mul, primitives::INT32, string_hash()),
+            None
+        );
+    }
+
+    #[test]
+    fn native_operators_take_precedence() {
+        let mut registry = registry();
+        register_script(
+            &mut registry,
+            function(
+                "opAdd",
+                None,
+                &[string_hash(), primitives::INT32],
+                string_hash(),
+            ),
+        );
+        let native = register_native_operator(&mut registry, Operator::Add, primitives::INT32);
+
+        assert_eq!(
+            resolve_binary(&registry, Operator::Add, string_hash(), primitives::INT32),
+            Some(OperatorMatch {
+                func_hash: native,
+                source: OperatorSource::Method,
+            })
+        );
+    }
+
+    #[test]
+    fn reverse_method_on_right_operand() {
+        let mut registry = registry();
+        let native = register_native_operator(&mut registry, Operator::AddR, primitives::INT32);
+
+        assert_eq!(
+            resolve_binary(&registry, Operator::Add, primitives::INT32, string_hash()),
+            Some(OperatorMatch {
+                func_hash: native,
+                source: OperatorSource::ReverseMethod,
+            })
+        );
+    }
+
+    #[test]
+    fn extension_requires_native_operand() {
+        let registry = registry();
+        let on_primitives = function("opMul", None, &[primitives::INT32; 2], primitives::INT32);
+        assert_eq!(extension_operator(&on_primitives, &registry), None);
+
+        let on_string = function("opEquals", None, &[string_hash(); 2], primitives::BOOL);
+        assert_eq!(
+            extension_operator(&on_string, &registry),
+            Some(Operator::Equals)
+        );
+
+        let unary = function(
+            "opNeg",
+            None,
+            &[string_hash(), string_hash()],
+            string_hash(),
+        );
+        assert_eq!(extension_operator(&unary, &registry), None);
+
+        let not_operator = function(
+            "repeat",
+            None,
+            &[string_hash(), primitives::INT32],
+            string_hash(),
+        );
+        assert_eq!(extension_operator(&not_operator, &registry), None);
+    }
+}
@@ -0,0 +1,61 @@
+//! Integer arithmetic semantics.
+//!
+//! Integers are two's complement of their declared width. Which results
+//! count as errors is chosen per module with [`IntegerOverflow`], stored in
+//! [`CompilerOptions::integer_overflow`] and in the compiled module for the
+//! VM. Constant expressions are folded with the same semantics, so an
+//! expression gives the same result folded or not:
+//!
+//! | Operation                     | [`Wrap`](IntegerOverflow::Wrap)   | [`Trap`](IntegerOverflow::Trap) |
+//! |-------------------------------|-----------------------------------|---------------------------------|
+//! | `+`, `-`, `*`, `**`, unary `-` | wraps around                     | overflow error                  |
+//! | `MIN / -1`, `MIN % -1`        | `MIN` and `0`                     | overflow error                  |
+//! | `/` and `%` by zero           | division error                    | division error                  |
+//! | shift by `n` outside `0..bits` | shifts by `n` modulo `bits`      | overflow error                  |
+//!
+//! Division truncates towards zero and `%` takes the sign of the dividend.
+//! `>>` shifts signed values arithmetically and `>>>` always shifts in
+//! zeros; bits shifted out by `<<` are lost in both modes. At runtime the
+//! errors are script exceptions; while folding constants they are
+//! compilation errors ([`ConstantOverflow`] and [`ConstantDivisionByZero`]).
+//!
+//! [`CompilerOptions::integer_overflow`]: crate::CompilerOptions::integer_overflow
+//! [`ConstantOverflow`]: angelscript_core::CompilationError::ConstantOverflow
+//! [`ConstantDivisionByZero`]: angelscript_core::CompilationError::ConstantDivisionByZero
+
+/// What happens when integer arithmetic overflows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum IntegerOverflow {
+    /// Results wrap around, as in C++. Fastest, since the VM checks nothing.
+    Wrap,
+    /// Overflows raise a script exception.
+    #[default]
+    Trap,
+}
+
+impl IntegerOverflow {
+    /// Apply a checked operation, wrapping with `wrapping` on overflow in
+    /// [`Wrap`](Self::Wrap) mode. Returns `None` if the operation traps.
+    pub fn apply<T>(self, checked: Option<T>, wrapping: impl FnOnce() -> T) -> Option<T> {
+        match (checked, self) {
+            (Some(value), _) => Some(value),
+            (None, Self::Wrap) => Some(wrapping()),
+            (None, Self::Trap) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_or_traps() {
+        let add = |mode: IntegerOverflow, a: i64, b: i64| {
+            mode.apply(a.checked_add(b), || a.wrapping_add(b))
+        };
+        assert_eq!(add(IntegerOverflow::Trap, 1, 2), Some(3));
+        assert_eq!(add(IntegerOverflow::Trap, i64::MAX, 1), None);
+        assert_eq!(add(IntegerOverflow::Wrap, i64::MAX, 1), Some(i64::MIN));
+    }
+}
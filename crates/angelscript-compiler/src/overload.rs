@@ -0,0 +1,790 @@
+//! Function overload resolution.
+//!
+//! Each argument is ranked against the parameter it would bind to by the
+//! [`Conversion`] it needs. A candidate is better than another if none of
+//! its conversions is worse and at least one is better; the call resolves
+//! to the single candidate that is better than all others.
+//!
+//! A variable-type parameter (`?&in`, `?&out`) accepts an argument of any
+//! type, but only when no typed overload fits as well, as it ranks below
+//! every other conversion. Output variable parameters still need a
+//! writable argument, so they reject constants and `null`. The last
+//! parameter of a variadic function, such as `print(const string &in, ?&in
+//! ...)`, takes any number of further arguments, each ranked against it.
+//!
+//! Values of a class type also convert through the class's conversion
+//! operators: `opImplConv` implicitly, and `opConv` too when converted
+//! explicitly as in `int(obj)` (see [`find_conversion`]).
+//!
+//! Ranking every overload of a heavily overloaded function (math functions,
+//! `print`) is repeated for each call, so results are memoized in an
+//! [`OverloadCache`]: first by call site, for sites compiled more than once,
+//! then by overload set and argument types, which most calls of the same
+//! function share.
+
+use angelscript_core::{
+    ClassEntry, CompilationError, DataType, Operator, RefModifier, Span, TypeHash, TypeRef,
+    TypeTable, primitives,
+};
+use angelscript_registry::SymbolRegistry;
+use rustc_hash::FxHashMap;
+
+/// Conversion applied to an argument to bind it to a parameter, from best
+/// to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Conversion {
+    /// The argument has the parameter's type.
+    Exact,
+    /// Only `const` or handle-to-const is added.
+    Qualification,
+    /// Widening within the same kind of number (e.g. `int8` to `int`,
+    /// `float` to `double`).
+    Promotion,
+    /// An enum value is passed as an integer.
+    EnumToInt,
+    /// Any other conversion between numbers, which may lose information.
+    Numeric,
+    /// A conversion operator of the argument's class.
+    UserDefined,
+    /// A conversion operator whose result then needs a numeric conversion.
+    UserDefinedNumeric,
+    /// The parameter accepts any type (`?`).
+    Variable,
+}
+
+/// A resolved overload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverloadMatch {
+    /// The selected function.
+    pub func_hash: TypeHash,
+    /// Conversion applied to each argument, in order.
+    pub conversions: Vec<Conversion>,
+}
+
+/// Rank the conversion binding an argument of type `arg` to `param`.
+///
+/// Returns `None` if the argument cannot be passed to the parameter.
+pub fn rank_conversion(param: &DataType, arg: &DataType) -> Option<Conversion> {
+    // Output references must bind to a variable of exactly the same type
+    let writes = matches!(param.ref_modifier, RefModifier::Out | RefModifier::InOut);
+
+    if param.type_hash == primitives::VARIABLE_PARAM {
+        let unwritable =
+            writes && ((arg.is_const && !arg.is_handle) || arg.type_hash == primitives::NULL);
+        if arg.type_hash == primitives::VOID || unwritable {
+            return None;
+        }
+        return Some(Conversion::Variable);
+    }
+
+    if param.type_hash == arg.type_hash {
+        let drops_const = (arg.is_const && !arg.is_handle && !param.is_const && writes)
+            || (arg.is_handle_to_const && param.is_handle && !param.is_handle_to_const);
+        if drops_const || (param.is_handle && !arg.is_handle && arg.type_hash != primitives::NULL) {
+            return None;
+        }
+        let adds_const = (param.is_const && !arg.is_const)
+            || (param.is_handle_to_const && !arg.is_handle_to_const);
+        return Some(if adds_const {
+            Conversion::Qualification
+        } else {
+            Conversion::Exact
+        });
+    }
+    if param.is_handle && arg.type_hash == primitives::NULL {
+        return Some(Conversion::Exact);
+    }
+    if writes || param.is_handle || arg.is_handle {
+        return None;
+    }
+
+    if arg.is_enum && param.type_hash == primitives::INT32 {
+        return Some(Conversion::EnumToInt);
+    }
+    let (param_kind, param_bits) = numeric(param.type_hash)?;
+    let (arg_kind, arg_bits) = numeric(arg.type_hash)?;
+    if param_kind == arg_kind && param_bits > arg_bits {
+        Some(Conversion::Promotion)
+    } else {
+        Some(Conversion::Numeric)
+    }
+}
+
+/// Rank the implicit conversion of a value of type `value` to `target`,
+/// as for an argument or the right-hand side of an assignment.
+///
+/// Unlike [`rank_conversion`], this also considers `opImplConv` operators
+/// of the value's class.
+///
+/// # Errors
+///
+/// Returns an error if several conversion operators fit equally well.
+pub fn rank_implicit_conversion(
+    registry: &SymbolRegistry,
+    target: &DataType,
+    value: &DataType,
+    span: Span,
+) -> Result<Option<Conversion>, CompilationError> {
+    if let Some(conversion) = rank_conversion(target, value) {
+        return Ok(Some(conversion));
+    }
+    let writes = matches!(target.ref_modifier, RefModifier::Out | RefModifier::InOut);
+    if writes || target.is_handle {
+        return Ok(None);
+    }
+    let found = find_conversion(registry, value, target, true, span)?;
+    Ok(found.map(|conversion| match conversion.then {
+        Conversion::Numeric => Conversion::UserDefinedNumeric,
+        _ => Conversion::UserDefined,
+    }))
+}
+
+/// A conversion operator selected to convert a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserConversion {
+    /// The `opConv` or `opImplConv` method to call on the value.
+    pub func_hash: TypeHash,
+    /// The type the operator returns.
+    pub output: DataType,
+    /// Conversion of the operator's result to the target type.
+    pub then: Conversion,
+}
+
+/// Find the conversion operator converting a value of type `from` to `to`.
+///
+/// Implicit conversions only use `opImplConv`; explicit ones prefer `opConv`
+/// and fall back to `opImplConv`. Operators registered on the class or
+/// declared as methods of it or its bases are considered, and one returning
+/// `to` itself is preferred over one whose result needs a further primitive
+/// conversion. Returns `None` if no operator converts the value.
+///
+/// # Errors
+///
+/// Returns an error if several operators fit equally well.
+pub fn find_conversion(
+    registry: &SymbolRegistry,
+    from: &DataType,
+    to: &DataType,
+    implicit: bool,
+    span: Span,
+) -> Result<Option<UserConversion>, CompilationError> {
+    let Some(class) = registry
+        .get(from.type_hash)
+        .and_then(|entry| entry.as_class())
+    else {
+        return Ok(None);
+    };
+    let operators: &[Operator] = if implicit {
+        &[Operator::ImplConv]
+    } else {
+        &[Operator::Conv, Operator::ImplConv]
+    };
+
+    for &op in operators {
+        let viable: Vec<UserConversion> = conversion_operators(registry, class, op)
+            .into_iter()
+            .filter_map(|(func_hash, output)| {
+                let then = rank_conversion(to, &output)?;
+                (then != Conversion::Variable).then_some(UserConversion {
+                    func_hash,
+                    output,
+                    then,
+                })
+            })
+            .collect();
+        let Some(best) = viable.iter().map(|conversion| conversion.then).min() else {
+            continue;
+        };
+        let best: Vec<&UserConversion> = viable.iter().filter(|c| c.then == best).collect();
+        if let [found] = best[..] {
+            return Ok(Some(*found));
+        }
+        return Err(CompilationError::AmbiguousConversion {
+            from: type_list(registry, std::iter::once(from)),
+            to: type_list(registry, std::iter::once(to)),
+            candidates: best
+                .iter()
+                .map(|c| {
+                    format!(
+                        "{} {}()",
+                        type_list(registry, std::iter::once(&c.output)),
+                        op
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            span,
+        });
+    }
+    Ok(None)
+}
+
+/// Implementations of a conversion operator on a class and its bases, with
+/// the types they return.
+fn conversion_operators(
+    registry: &SymbolRegistry,
+    class: &ClassEntry,
+    op: Operator,
+) -> Vec<(TypeHash, DataType)> {
+    let name = op.to_string();
+    let mut found: Vec<(TypeHash, DataType)> = Vec::new();
+    for class in std::iter::once(class).chain(registry.base_class_chain(class.type_hash)) {
+        let registered = class
+            .behaviors
+            .conversions()
+            .iter()
+            .filter(|entry| entry.op == op)
+            .map(|entry| (entry.func_hash, DataType::simple(entry.target_type)));
+        let declared = class.find_methods(&name).iter().filter_map(|&hash| {
+            let def = &registry.get_function(hash)?.def;
+            Some((hash, def.return_type))
+        });
+        for (hash, output) in registered.chain(declared) {
+            if !found.iter().any(|&(other, _)| other == hash) {
+                found.push((hash, output));
+            }
+        }
+    }
+    found
+}
+
+#[derive(PartialEq)]
+enum NumberKind {
+    Signed,
+    Unsigned,
+    Float,
+}
+
+fn numeric(type_hash: TypeHash) -> Option<(NumberKind, u8)> {
+    Some(match type_hash {
+        primitives::INT8 => (NumberKind::Signed, 8),
+        primitives::INT16 => (NumberKind::Signed, 16),
+        primitives::INT32 => (NumberKind::Signed, 32),
+        primitives::INT64 => (NumberKind::Signed, 64),
+        primitives::UINT8 => (NumberKind::Unsigned, 8),
+        primitives::UINT16 => (NumberKind::Unsigned, 16),
+        primitives::UINT32 => (NumberKind::Unsigned, 32),
+        primitives::UINT64 => (NumberKind::Unsigned, 64),
+        primitives::FLOAT => (NumberKind::Float, 32),
+        primitives::DOUBLE => (NumberKind::Float, 64),
+        _ => return None,
+    })
+}
+
+/// Check if conversions `a` are better than `b`: none worse, one better.
+pub(crate) fn is_better(a: &[Conversion], b: &[Conversion]) -> bool {
+    a.iter().zip(b).all(|(a, b)| a <= b) && a.iter().zip(b).any(|(a, b)| a < b)
+}
+
+/// Select the overload of `name` among `candidates` for arguments `args`.
+///
+/// Candidates that are not registered functions or take a different number
+/// of arguments are skipped. The last parameter of a variadic candidate
+/// takes the arguments past the others, however many there are.
+pub fn resolve_overload(
+    registry: &SymbolRegistry,
+    name: &str,
+    candidates: &[TypeHash],
+    args: &[DataType],
+    span: Span,
+) -> Result<OverloadMatch, CompilationError> {
+    // An ambiguous conversion rules out its candidate, and is reported if
+    // no candidate is left
+    let mut ambiguity = None;
+    let viable: Vec<OverloadMatch> = candidates
+        .iter()
+        .filter_map(|&hash| {
+            let def = &registry.get_function(hash)?.def;
+            let params: Vec<&DataType> = match def.params.split_last() {
+                Some((variadic, fixed)) if def.is_variadic && args.len() >= fixed.len() => fixed
+                    .iter()
+                    .map(|param| &param.data_type)
+                    .chain(std::iter::repeat(&variadic.data_type))
+                    .take(args.len())
+                    .collect(),
+                _ if def.params.len() == args.len() => {
+                    def.params.iter().map(|param| &param.data_type).collect()
+                }
+                _ => return None,
+            };
+            let conversions = params
+                .into_iter()
+                .zip(args)
+                .map(|(param, arg)| {
+                    rank_implicit_conversion(registry, param, arg, span).unwrap_or_else(|error| {
+                        ambiguity.get_or_insert(error);
+                        None
+                    })
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(OverloadMatch {
+                func_hash: hash,
+                conversions,
+            })
+        })
+        .collect();
+    if let (true, Some(error)) = (viable.is_empty(), ambiguity) {
+        return Err(error);
+    }
+
+    let best: Vec<&OverloadMatch> = viable
+        .iter()
+        .filter(|a| {
+            viable
+                .iter()
+                .all(|b| std::ptr::eq(*a, b) || !is_better(&b.conversions, &a.conversions))
+        })
+        .collect();
+
+    match best[..] {
+        [found] => Ok(found.clone()),
+        [] => Err(CompilationError::NoMatchingOverload {
+            name: name.to_string(),
+            args: type_list(registry, args.iter()),
+            span,
+        }),
+        _ => Err(CompilationError::AmbiguousOverload {
+            name: name.to_string(),
+            candidates: best
+                .iter()
+                .filter_map(|m| registry.get_function(m.func_hash))
+                .map(|f| {
+                    let params = type_list(registry, f.def.params.iter().map(|p| &p.data_type));
+                    format!("{}({})", f.def.name, params)
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            span,
+        }),
+    }
+}
+
+/// Format types with their registered names, e.g. `int, const string &in`.
+fn type_list<'t>(registry: &SymbolRegistry, types: impl Iterator<Item = &'t DataType>) -> String {
+    types
+        .map(|ty| {
+            let Some(entry) = registry.get(ty.type_hash) else {
+                return ty.to_string();
+            };
+            let mut name = String::new();
+            if ty.is_const && !ty.is_handle {
+                name.push_str("const ");
+            }
+            name.push_str(entry.qualified_name());
+            if ty.is_handle {
+                name.push('@');
+            }
+            if ty.ref_modifier != RefModifier::None {
+                name.push_str(&format!(" {}", ty.ref_modifier));
+            }
+            name
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Hit and miss counts of an [`OverloadCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OverloadCacheStats {
+    /// Lookups answered from the call site table.
+    pub site_hits: u64,
+    /// Lookups answered from the overload set table.
+    pub signature_hits: u64,
+    /// Lookups that ranked the candidates.
+    pub misses: u64,
+}
+
+impl OverloadCacheStats {
+    /// Total number of lookups.
+    pub fn lookups(&self) -> u64 {
+        self.site_hits + self.signature_hits + self.misses
+    }
+
+    /// Fraction of lookups answered from the cache, between 0 and 1.
+    pub fn hit_rate(&self) -> f64 {
+        match self.lookups() {
+            0 => 0.0,
+            lookups => (self.site_hits + self.signature_hits) as f64 / lookups as f64,
+        }
+    }
+}
+
+/// Memoized overload resolution for one compilation.
+///
+/// Results depend on the registry, so a cache must be [cleared] when
+/// functions are registered after it was filled.
+///
+/// [cleared]: OverloadCache::clear
+#[derive(Debug, Default)]
+pub struct OverloadCache {
+    types: TypeTable,
+    by_site: FxHashMap<(Span, Vec<TypeRef>), OverloadMatch>,
+    by_signature: FxHashMap<(String, Vec<TypeRef>), OverloadMatch>,
+    stats: OverloadCacheStats,
+}
+
+impl OverloadCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve a call to the overloads of `name` at `site`.
+    ///
+    /// Failed resolutions are not cached, so each failing call reports its
+    /// own error.
+    pub fn resolve(
+        &mut self,
+        registry: &SymbolRegistry,
+        name: &str,
+        candidates: &[TypeHash],
+        args: &[DataType],
+        site: Span,
+    ) -> Result<OverloadMatch, CompilationError> {
+        let key: Vec<TypeRef> = args.iter().map(|&arg| self.types.intern(arg)).collect();
+
+        let site_key = (site, key);
+        if let Some(found) = self.by_site.get(&site_key) {
+            self.stats.site_hits += 1;
+            return Ok(found.clone());
+        }
+        let signature_key = (name.to_string(), site_key.1.clone());
+        if let Some(found) = self.by_signature.get(&signature_key) {
+            self.stats.signature_hits += 1;
+            let found = found.clone();
+            self.by_site.insert(site_key, found.clone());
+            return Ok(found);
+        }
+
+        self.stats.misses += 1;
+        let found = resolve_overload(registry, name, candidates, args, site)?;
+        self.by_site.insert(site_key, found.clone());
+        self.by_signature.insert(signature_key, found.clone());
+        Ok(found)
+    }
+
+    /// Hit and miss counts since the cache was created or cleared.
+    pub fn stats(&self) -> OverloadCacheStats {
+        self.stats
+    }
+
+    /// Remove all cached resolutions and reset the counts.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{
+        ConversionEntry, FunctionDef, FunctionEntry, FunctionTraits, Param, TypeKind, Visibility,
+        primitives,
+    };
+
+    fn register(registry: &mut SymbolRegistry, name: &str, params: &[DataType]) -> TypeHash {
+        let hashes: Vec<TypeHash> = params.iter().map(|p| p.type_hash).collect();
+        let func_hash = TypeHash::from_function(name, &hashes);
+        let def = FunctionDef::new(
+            func_hash,
+            name.to_string(),
+            vec![],
+            params
+                .iter()
+                .enumerate()
+                .map(|(i, &t)| Param::new(format!("p{}", i), t))
+                .collect(),
+            DataType::void(),
+            None,
+            FunctionTraits::default(),
+            true,
+            Visibility::Public,
+        );
+        registry.register_function(FunctionEntry::ffi(def)).unwrap();
+        func_hash
+    }
+
+    fn math_registry() -> (SymbolRegistry, Vec<TypeHash>) {
+        let mut registry = SymbolRegistry::with_primitives();
+        let overloads = vec![
+            register(&mut registry, "abs", &[DataType::simple(primitives::INT32)]),
+            register(&mut registry, "abs", &[DataType::simple(primitives::INT64)]),
+            register(
+                &mut registry,
+                "abs",
+                &[DataType::simple(primitives::DOUBLE)],
+            ),
+        ];
+        (registry, overloads)
+    }
+
+    #[test]
+    fn ranks_conversions() {
+        let int = DataType::simple(primitives::INT32);
+        assert_eq!(rank_conversion(&int, &int), Some(Conversion::Exact));
+        assert_eq!(
+            rank_conversion(&DataType::with_const(primitives::INT32), &int),
+            Some(Conversion::Qualification)
+        );
+        assert_eq!(
+            rank_conversion(&int, &DataType::simple(primitives::INT8)),
+            Some(Conversion::Promotion)
+        );
+        assert_eq!(
+            rank_conversion(&int, &DataType::simple(primitives::FLOAT)),
+            Some(Conversion::Numeric)
+        );
+        assert_eq!(
+            rank_conversion(
+                &DataType::with_ref_out(primitives::INT32),
+                &DataType::simple(primitives::INT8)
+            ),
+            None
+        );
+        assert_eq!(
+            rank_conversion(&DataType::simple(primitives::VARIABLE_PARAM), &int),
+            Some(Conversion::Variable)
+        );
+    }
+
+    #[test]
+    fn selects_best_overload() {
+        let (registry, overloads) = math_registry();
+        let span = Span::new(1, 1, 1);
+
+        let found = resolve_overload(
+            &registry,
+            "abs",
+            &overloads,
+            &[DataType::simple(primitives::INT64)],
+            span,
+        )
+        .unwrap();
+        assert_eq!(found.func_hash, overloads[1]);
+
+        let found = resolve_overload(
+            &registry,
+            "abs",
+            &overloads,
+            &[DataType::simple(primitives::FLOAT)],
+            span,
+        )
+        .unwrap();
+        assert_eq!(found.func_hash, overloads[2]);
+        assert_eq!(found.conversions, [Conversion::Promotion]);
+
+        // int16 promotes equally well to int and int64
+        let error = resolve_overload(
+            &registry,
+            "abs",
+            &overloads,
+            &[DataType::simple(primitives::INT16)],
+            span,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "at 1:1: ambiguous call to 'abs': abs(int), abs(int64)"
+        );
+        assert!(matches!(
+            resolve_overload(&registry, "abs", &overloads, &[], span),
+            Err(CompilationError::NoMatchingOverload { .. })
+        ));
+    }
+
+    #[test]
+    fn variable_parameters() {
+        let var_in = DataType::with_ref_in(primitives::VARIABLE_PARAM);
+        let var_out = DataType::with_ref_out(primitives::VARIABLE_PARAM);
+        let int = DataType::simple(primitives::INT32);
+        let null = DataType::simple(primitives::NULL);
+        assert_eq!(rank_conversion(&var_in, &null), Some(Conversion::Variable));
+        assert_eq!(rank_conversion(&var_out, &null), None);
+        assert_eq!(rank_conversion(&var_out, &int.as_const()), None);
+        assert_eq!(rank_conversion(&var_in, &DataType::void()), None);
+
+        let mut registry = SymbolRegistry::with_primitives();
+        let typed = register(&mut registry, "show", &[int]);
+        let any = register(&mut registry, "show", &[var_in]);
+        let string = DataType::with_ref_in(primitives::STRING).as_const();
+        let func_hash = TypeHash::from_function("print", &[primitives::STRING]);
+        let mut def = FunctionDef::new(
+            func_hash,
+            "print".to_string(),
+            vec![],
+            vec![Param::new("fmt", string), Param::with_default("", var_in)],
+            DataType::void(),
+            None,
+            FunctionTraits::default(),
+            true,
+            Visibility::Public,
+        );
+        def.is_variadic = true;
+        registry.register_function(FunctionEntry::ffi(def)).unwrap();
+        let span = Span::new(1, 1, 1);
+
+        // A typed overload wins over `?` when it fits
+        let found = resolve_overload(&registry, "show", &[typed, any], &[int], span).unwrap();
+        assert_eq!(found.func_hash, typed);
+        let found = resolve_overload(&registry, "show", &[typed, any], &[string], span).unwrap();
+        assert_eq!(found.func_hash, any);
+
+        // Variadic arguments are each ranked against the last parameter
+        for count in 0..3 {
+            let mut args = vec![string];
+            args.extend(std::iter::repeat_n(int, count));
+            let found = resolve_overload(&registry, "print", &[func_hash], &args, span).unwrap();
+            assert_eq!(found.conversions.len(), count + 1);
+        }
+        assert!(resolve_overload(&registry, "print", &[func_hash], &[], span).is_err());
+    }
+
+    /// `Meters` converting explicitly to `int` and implicitly to `double`;
+    /// `Both` implicitly to `int` and `float`.
+    fn conversion_registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+        let mut meters = ClassEntry::ffi("Meters", TypeKind::value::<f64>());
+        for (op, target) in [
+            (Operator::Conv, primitives::INT32),
+            (Operator::ImplConv, primitives::DOUBLE),
+        ] {
+            meters.behaviors.add_conversion(ConversionEntry {
+                op,
+                target_type: target,
+                func_hash: TypeHash::from_name(&format!("Meters::{op}")),
+            });
+        }
+        registry.register_type(meters.into()).unwrap();
+
+        // Script-style operators, declared as methods
+        let mut both = ClassEntry::ffi("Both", TypeKind::reference());
+        for (name, target) in [("int", primitives::INT32), ("float", primitives::FLOAT)] {
+            let func_hash = TypeHash::from_name(&format!("Both::opImplConv_{name}"));
+            let def = FunctionDef::new(
+                func_hash,
+                "opImplConv".to_string(),
+                vec![],
+                vec![],
+                DataType::simple(target),
+                Some(both.type_hash),
+                FunctionTraits::default(),
+                true,
+                Visibility::Public,
+            );
+            registry.register_function(FunctionEntry::ffi(def)).unwrap();
+            both.add_method("opImplConv", func_hash);
+        }
+        registry.register_type(both.into()).unwrap();
+        registry
+    }
+
+    #[test]
+    fn finds_conversion_operators() {
+        let registry = conversion_registry();
+        let span = Span::new(1, 1, 1);
+        let meters = DataType::simple(TypeHash::from_name("Meters"));
+        let int = DataType::simple(primitives::INT32);
+        let double = DataType::simple(primitives::DOUBLE);
+
+        // int(m) uses opConv; implicitly only opImplConv applies
+        let explicit = find_conversion(&registry, &meters, &int, false, span)
+            .unwrap()
+            .unwrap();
+        assert_eq!(explicit.func_hash, TypeHash::from_name("Meters::opConv"));
+        assert_eq!(explicit.then, Conversion::Exact);
+        let implicit = find_conversion(&registry, &meters, &int, true, span)
+            .unwrap()
+            .unwrap();
+        assert_eq!(implicit.output, double);
+        assert_eq!(implicit.then, Conversion::Numeric);
+        assert_eq!(
+            rank_implicit_conversion(&registry, &double, &meters, span).unwrap(),
+            Some(Conversion::UserDefined)
+        );
+        assert_eq!(
+            rank_implicit_conversion(
+                &registry,
+                &DataType::with_handle(meters.type_hash, false),
+                &meters,
+                span
+            )
+            .unwrap(),
+            None
+        );
+
+        // Both operators need a numeric conversion to int8
+        let both = DataType::simple(TypeHash::from_name("Both"));
+        let found = find_conversion(&registry, &both, &int, true, span)
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.func_hash, TypeHash::from_name("Both::opImplConv_int"));
+        let error = find_conversion(
+            &registry,
+            &both,
+            &DataType::simple(primitives::INT8),
+            true,
+            span,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "at 1:1: ambiguous conversion from 'Both' to 'int8': int opImplConv(), float opImplConv()"
+        );
+    }
+
+    #[test]
+    fn overloads_accept_user_conversions() {
+        let mut registry = conversion_registry();
+        let span = Span::new(1, 1, 1);
+        let takes_double = register(
+            &mut registry,
+            "sqrt",
+            &[DataType::simple(primitives::DOUBLE)],
+        );
+        let takes_int = register(
+            &mut registry,
+            "sqrt",
+            &[DataType::simple(primitives::INT32)],
+        );
+        let meters = [DataType::simple(TypeHash::from_name("Meters"))];
+
+        // opImplConv returns double, which sqrt(int) would have to narrow
+        let found = resolve_overload(&registry, "sqrt", &[takes_double], &meters, span).unwrap();
+        assert_eq!(found.conversions, [Conversion::UserDefined]);
+        let found =
+            resolve_overload(&registry, "sqrt", &[takes_int, takes_double], &meters, span).unwrap();
+        assert_eq!(found.func_hash, takes_double);
+        let found = resolve_overload(&registry, "sqrt", &[takes_int], &meters, span).unwrap();
+        assert_eq!(found.conversions, [Conversion::UserDefinedNumeric]);
+
+        let takes_int8 = register(&mut registry, "abs", &[DataType::simple(primitives::INT8)]);
+        let both = [DataType::simple(TypeHash::from_name("Both"))];
+        assert!(matches!(
+            resolve_overload(&registry, "abs", &[takes_int8], &both, span),
+            Err(CompilationError::AmbiguousConversion { .. })
+        ));
+    }
+
+    #[test]
+    fn cache_counts_hits() {
+        let (registry, overloads) = math_registry();
+        let int = [DataType::simple(primitives::INT32)];
+        let mut cache = OverloadCache::new();
+
+        for line in [1, 2, 2, 3] {
+            let found = cache
+                .resolve(&registry, "abs", &overloads, &int, Span::new(line, 1, 3))
+                .unwrap();
+            assert_eq!(found.func_hash, overloads[0]);
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.site_hits, 1);
+        assert_eq!(stats.signature_hits, 2);
+        assert_eq!(stats.hit_rate(), 0.75);
+
+        cache.clear();
+        assert_eq!(cache.stats(), OverloadCacheStats::default());
+    }
+}
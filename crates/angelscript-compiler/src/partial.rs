@@ -0,0 +1,351 @@
+//! Merging of partial classes.
+//!
+//! A class declared `partial` may be split across several sections:
+//!
+//! ```angelscript
+//! // player_movement.as
+//! partial class Player { float speed; void move() { } }
+//!
+//! // player_combat.as
+//! partial class Player : IDamageable { int health; void damage(int) { } }
+//! ```
+//!
+//! The registration pass sees a single class with the union of the parts'
+//! modifiers, base types and members. Every part must be marked `partial`,
+//! and members may not be declared by more than one part.
+
+use angelscript_core::{CompilationError, Span};
+use angelscript_parser::ast::{
+    ClassDecl, ClassMember, DeclModifiers, FunctionDecl, Ident, IdentExpr, Item, Script,
+};
+use rustc_hash::FxHashMap;
+
+/// One declaration contributing to a class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClassPart {
+    /// Index of the section containing the declaration.
+    pub section: usize,
+    /// Location of the declaration.
+    pub span: Span,
+}
+
+/// A script class with all of its parts merged.
+#[derive(Debug, Clone)]
+pub struct MergedClass<'ast> {
+    /// Qualified name (`Namespace::Class`).
+    pub qualified_name: String,
+    /// Class name as written in the first part.
+    pub name: Ident<'ast>,
+    /// Union of the modifiers of every part.
+    pub modifiers: DeclModifiers,
+    /// Base class and interfaces, without duplicates.
+    pub inheritance: Vec<IdentExpr<'ast>>,
+    /// Members of every part, in section order.
+    pub members: Vec<ClassMember<'ast>>,
+    /// The declarations making up the class, in section order.
+    pub parts: Vec<ClassPart>,
+}
+
+impl<'ast> MergedClass<'ast> {
+    fn new(qualified_name: String, section: usize, class: &ClassDecl<'ast>) -> Self {
+        Self {
+            qualified_name,
+            name: class.name,
+            modifiers: class.modifiers,
+            inheritance: class.inheritance.to_vec(),
+            members: class.members.to_vec(),
+            parts: vec![ClassPart {
+                section,
+                span: class.span,
+            }],
+        }
+    }
+
+    /// Check if the class is declared in more than one part.
+    pub fn is_split(&self) -> bool {
+        self.parts.len() > 1
+    }
+
+    /// Location of the first declaration.
+    pub fn span(&self) -> Span {
+        self.parts[0].span
+    }
+
+    fn add_part(&mut self, section: usize, class: &ClassDecl<'ast>) {
+        let modifiers = &mut self.modifiers;
+        modifiers.shared |= class.modifiers.shared;
+        modifiers.external |= class.modifiers.external;
+        modifiers.abstract_ |= class.modifiers.abstract_;
+        modifiers.final_ |= class.modifiers.final_;
+
+        for base in class.inheritance {
+            let name = base_name(base);
+            if !self.inheritance.iter().any(|b| base_name(b) == name) {
+                self.inheritance.push(*base);
+            }
+        }
+
+        self.members.extend_from_slice(class.members);
+        self.parts.push(ClassPart {
+            section,
+            span: class.span,
+        });
+    }
+}
+
+/// Collect the classes declared in `sections`, merging partial classes.
+///
+/// Returns every class, including those declared in a single part, so the
+/// registration pass can use the result in place of the raw class items.
+pub fn merge_partial_classes<'ast>(
+    sections: &[&Script<'ast>],
+) -> Result<Vec<MergedClass<'ast>>, Vec<CompilationError>> {
+    let mut classes: Vec<MergedClass<'ast>> = Vec::new();
+    let mut by_name: FxHashMap<String, usize> = FxHashMap::default();
+    let mut errors = Vec::new();
+
+    let mut declared = Vec::new();
+    for (section, script) in sections.iter().enumerate() {
+        collect_classes(script.items(), "", section, &mut declared);
+    }
+
+    for (qualified_name, section, class) in declared {
+        let Some(&index) = by_name.get(&qualified_name) else {
+            by_name.insert(qualified_name.clone(), classes.len());
+            classes.push(MergedClass::new(qualified_name, section, &class));
+            continue;
+        };
+
+        let merged = &mut classes[index];
+        if !merged.modifiers.partial && !class.modifiers.partial {
+            errors.push(CompilationError::DuplicateDefinition {
+                name: qualified_name,
+                span: class.span,
+            });
+        } else if !merged.modifiers.partial || !class.modifiers.partial {
+            errors.push(CompilationError::Other {
+                message: format!(
+                    "class '{}' must be declared 'partial' in every part",
+                    qualified_name
+                ),
+                span: class.span,
+            });
+        } else {
+            merged.add_part(section, &class);
+        }
+    }
+
+    for class in classes.iter().filter(|c| c.is_split()) {
+        check_members(class, &mut errors);
+    }
+
+    if errors.is_empty() {
+        Ok(classes)
+    } else {
+        Err(errors)
+    }
+}
+
+fn collect_classes<'ast>(
+    items: &[Item<'ast>],
+    namespace: &str,
+    section: usize,
+    out: &mut Vec<(String, usize, ClassDecl<'ast>)>,
+) {
+    for item in items {
+        match item {
+            Item::Class(class) => {
+                out.push((qualify(namespace, class.name.name), section, *class));
+            }
+            Item::Namespace(ns) => {
+                let mut nested = namespace.to_string();
+                for segment in ns.path {
+                    nested = qualify(&nested, segment.name);
+                }
+                collect_classes(ns.items, &nested, section, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Report members declared by more than one part.
+///
+/// Fields and virtual properties share a namespace; methods are keyed by
+/// name, parameter types and constness so overloads may live in different
+/// parts.
+fn check_members(class: &MergedClass<'_>, errors: &mut Vec<CompilationError>) {
+    let mut seen: FxHashMap<String, Span> = FxHashMap::default();
+
+    for member in &class.members {
+        let (key, name, span) = match member {
+            ClassMember::Field(field) => (
+                format!("var {}", field.name.name),
+                field.name.name.to_string(),
+                field.span,
+            ),
+            ClassMember::VirtualProperty(prop) => (
+                format!("var {}", prop.name.name),
+                prop.name.name.to_string(),
+                prop.span,
+            ),
+            ClassMember::Method(func) => (method_key(func), method_name(func), func.span),
+            ClassMember::Funcdef(funcdef) => (
+                format!("funcdef {}", funcdef.name.name),
+                funcdef.name.name.to_string(),
+                funcdef.span,
+            ),
+        };
+
+        if seen.insert(key, span).is_some() {
+            errors.push(CompilationError::DuplicateDefinition {
+                name: format!("{}::{}", class.qualified_name, name),
+                span,
+            });
+        }
+    }
+}
+
+fn method_name(func: &FunctionDecl<'_>) -> String {
+    if func.is_destructor {
+        format!("~{}", func.name.name)
+    } else {
+        func.name.name.to_string()
+    }
+}
+
+fn method_key(func: &FunctionDecl<'_>) -> String {
+    let params: Vec<String> = func.params.iter().map(|p| p.ty.to_string()).collect();
+    format!(
+        "fn {}({}){}",
+        method_name(func),
+        params.join(", "),
+        if func.is_const { " const" } else { "" }
+    )
+}
+
+fn base_name(base: &IdentExpr<'_>) -> String {
+    match base.scope {
+        Some(scope) if !scope.is_empty() => format!("{}::{}", scope, base.ident.name),
+        _ => base.ident.name.to_string(),
+    }
+}
+
+fn qualify(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}::{}", namespace, name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn merge<'ast>(
+        arena: &'ast Bump,
+        sources: &[&str],
+    ) -> Result<Vec<MergedClass<'ast>>, Vec<CompilationError>> {
+        let scripts: Vec<Script<'ast>> = sources
+            .iter()
+            .map(|source| Parser::parse(source, arena).expect("parse failed"))
+            .collect();
+        let sections: Vec<&Script<'ast>> = scripts.iter().collect();
+        merge_partial_classes(&sections)
+    }
+
+    #[test]
+    fn merges_parts_across_sections() {
+        let arena = Bump::new();
+        let classes = merge(
+            &arena,
+            &[
+                "partial class Player : IMovable { float speed; void move() { } }",
+                "shared partial class Player : IMovable, IDamageable { int health; void damage(int amount) { } }",
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(classes.len(), 1);
+        let player = &classes[0];
+        assert_eq!(player.qualified_name, "Player");
+        assert!(player.is_split());
+        assert!(player.modifiers.shared);
+        assert_eq!(player.members.len(), 4);
+        assert_eq!(player.inheritance.len(), 2);
+        assert_eq!(
+            player.parts.iter().map(|p| p.section).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn overloads_may_be_split() {
+        let arena = Bump::new();
+        let classes = merge(
+            &arena,
+            &[
+                "partial class Player { void hit(int d) { } }",
+                "partial class Player { void hit(float d) { } void hit(int d) const { } }",
+            ],
+        )
+        .unwrap();
+        assert_eq!(classes[0].members.len(), 3);
+    }
+
+    #[test]
+    fn duplicate_members_are_reported() {
+        let arena = Bump::new();
+        let errors = merge(
+            &arena,
+            &[
+                "partial class Player { int health; void move() { } }",
+                "partial class Player { int health; void move() { } }",
+            ],
+        )
+        .unwrap_err();
+
+        let names: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                CompilationError::DuplicateDefinition { name, .. } => name.as_str(),
+                other => panic!("unexpected error: {:?}", other),
+            })
+            .collect();
+        assert_eq!(names, vec!["Player::health", "Player::move"]);
+    }
+
+    #[test]
+    fn every_part_must_be_partial() {
+        let arena = Bump::new();
+        let errors = merge(&arena, &["partial class Player { }", "class Player { }"]).unwrap_err();
+        assert!(matches!(errors[0], CompilationError::Other { .. }));
+
+        let errors = merge(&arena, &["class Player { }", "class Player { }"]).unwrap_err();
+        assert!(matches!(
+            &errors[0],
+            CompilationError::DuplicateDefinition { name, .. } if name == "Player"
+        ));
+    }
+
+    #[test]
+    fn namespaces_qualify_class_names() {
+        let arena = Bump::new();
+        let classes = merge(
+            &arena,
+            &[
+                "namespace game { partial class Player { int a; } }",
+                "namespace game { partial class Player { int b; } } class Player { }",
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(classes.len(), 2);
+        assert_eq!(classes[0].qualified_name, "game::Player");
+        assert_eq!(classes[0].members.len(), 2);
+        assert!(!classes[1].is_split());
+    }
+}
@@ -0,0 +1,245 @@
+//! Compiler plugins.
+//!
+//! A [`CompilerPlugin`] runs project-specific passes inside the compiler
+//! without forking it. Hooks run in this order for each compiled script:
+//!
+//! 1. [`after_registration`](CompilerPlugin::after_registration) once, after
+//!    the script's types and functions are registered
+//! 2. [`before_emit`](CompilerPlugin::before_emit) for every function body
+//!    about to be compiled
+//! 3. [`after_emit`](CompilerPlugin::after_emit) for every compiled function,
+//!    which may rewrite its bytecode
+//!
+//! Plugins report problems through [`PluginContext::error`]; any error fails
+//! the build like a normal compilation error.
+//!
+//! # Example
+//!
+//! ```ignore
+//! struct NoAllocInUpdate;
+//!
+//! impl CompilerPlugin for NoAllocInUpdate {
+//!     fn name(&self) -> &str {
+//!         "no-alloc-in-update"
+//!     }
+//!
+//!     fn before_emit(&self, ctx: &mut PluginContext<'_>, function: &PluginFunction<'_, '_>) {
+//!         if function.name == "onUpdate" && allocates(function.decl) {
+//!             ctx.error("onUpdate must not allocate", function.decl.span);
+//!         }
+//!     }
+//! }
+//! ```
+
+use angelscript_core::{CompilationError, Span, UnitId};
+use angelscript_parser::ast::{FunctionDecl, Item, Script};
+use angelscript_registry::SymbolRegistry;
+use std::sync::Arc;
+
+use crate::CompiledFunction;
+
+/// A custom pass run by the compiler.
+///
+/// All hooks default to doing nothing, so a plugin only implements the ones
+/// it needs. Plugins are shared by every unit built from a context, so hooks
+/// take `&self`; use interior mutability to collect state across units.
+pub trait CompilerPlugin: Send + Sync {
+    /// Name of the plugin, prefixed to the diagnostics it reports.
+    fn name(&self) -> &str;
+
+    /// Called once per script after registration, before any code is emitted.
+    fn after_registration(&self, _ctx: &mut PluginContext<'_>, _script: &Script<'_>) {}
+
+    /// Called before the body of `function` is compiled.
+    fn before_emit(&self, _ctx: &mut PluginContext<'_>, _function: &PluginFunction<'_, '_>) {}
+
+    /// Called after `function` is compiled. The bytecode may be rewritten.
+    fn after_emit(&self, _ctx: &mut PluginContext<'_>, _function: &mut CompiledFunction) {}
+}
+
+/// Lets the host keep a handle to a plugin to read what it collected.
+impl<P: CompilerPlugin + ?Sized> CompilerPlugin for Arc<P> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    fn after_registration(&self, ctx: &mut PluginContext<'_>, script: &Script<'_>) {
+        (**self).after_registration(ctx, script)
+    }
+
+    fn before_emit(&self, ctx: &mut PluginContext<'_>, function: &PluginFunction<'_, '_>) {
+        (**self).before_emit(ctx, function)
+    }
+
+    fn after_emit(&self, ctx: &mut PluginContext<'_>, function: &mut CompiledFunction) {
+        (**self).after_emit(ctx, function)
+    }
+}
+
+/// A function body about to be compiled.
+#[derive(Debug, Clone, Copy)]
+pub struct PluginFunction<'a, 'ast> {
+    /// Qualified name (`Namespace::function` or `Class::method`).
+    pub name: &'a str,
+    /// The declaration, including its body.
+    pub decl: &'a FunctionDecl<'ast>,
+}
+
+/// Compiler state available to plugin hooks.
+pub struct PluginContext<'a> {
+    registry: &'a SymbolRegistry,
+    unit_id: UnitId,
+    plugin: &'a str,
+    errors: &'a mut Vec<CompilationError>,
+}
+
+impl<'a> PluginContext<'a> {
+    pub(crate) fn new(
+        registry: &'a SymbolRegistry,
+        unit_id: UnitId,
+        plugin: &'a str,
+        errors: &'a mut Vec<CompilationError>,
+    ) -> Self {
+        Self {
+            registry,
+            unit_id,
+            plugin,
+            errors,
+        }
+    }
+
+    /// The registry of types and functions visible to the script.
+    pub fn registry(&self) -> &SymbolRegistry {
+        self.registry
+    }
+
+    /// The unit being compiled.
+    pub fn unit_id(&self) -> UnitId {
+        self.unit_id
+    }
+
+    /// Report an error, failing the build.
+    pub fn error(&mut self, message: impl AsRef<str>, span: Span) {
+        self.errors.push(CompilationError::Other {
+            message: format!("[{}] {}", self.plugin, message.as_ref()),
+            span,
+        });
+    }
+}
+
+/// Collect the function bodies declared in `items` with their qualified names.
+pub(crate) fn function_bodies<'s, 'ast>(
+    items: &'s [Item<'ast>],
+    scope: &str,
+    out: &mut Vec<(String, &'s FunctionDecl<'ast>)>,
+) {
+    use angelscript_parser::ast::ClassMember;
+
+    let qualify = |name: &str| {
+        if scope.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}::{}", scope, name)
+        }
+    };
+
+    for item in items {
+        match item {
+            Item::Function(func) if func.body.is_some() => {
+                out.push((qualify(func.name.name), func));
+            }
+            Item::Class(class) => {
+                let class_name = qualify(class.name.name);
+                for member in class.members {
+                    if let ClassMember::Method(method) = member
+                        && method.body.is_some()
+                    {
+                        out.push((format!("{}::{}", class_name, method.name.name), method));
+                    }
+                }
+            }
+            Item::Namespace(ns) => {
+                let mut nested = scope.to_string();
+                for segment in ns.path {
+                    nested = if nested.is_empty() {
+                        segment.name.to_string()
+                    } else {
+                        format!("{}::{}", nested, segment.name)
+                    };
+                }
+                function_bodies(ns.items, &nested, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Compiler, CompilerOptions};
+    use angelscript_parser::ast::Parser;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl CompilerPlugin for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn after_registration(&self, _ctx: &mut PluginContext<'_>, script: &Script<'_>) {
+            let mut events = self.events.lock().unwrap();
+            events.push(format!("registered {} items", script.items().len()));
+        }
+
+        fn before_emit(&self, ctx: &mut PluginContext<'_>, function: &PluginFunction<'_, '_>) {
+            self.events.lock().unwrap().push(function.name.to_string());
+            if function.decl.params.len() > 1 {
+                ctx.error("too many parameters", function.decl.span);
+            }
+        }
+    }
+
+    fn compile(plugins: &[Box<dyn CompilerPlugin>], source: &str) -> Vec<CompilationError> {
+        let arena = bumpalo::Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let registry = SymbolRegistry::with_primitives();
+        Compiler::new(&registry, UnitId::new(0), CompilerOptions::new())
+            .with_plugins(plugins)
+            .compile(&script)
+            .errors
+    }
+
+    #[test]
+    fn hooks_see_qualified_function_bodies() {
+        let recorder = Arc::new(Recorder::default());
+        let plugins: Vec<Box<dyn CompilerPlugin>> = vec![Box::new(Arc::clone(&recorder))];
+        let errors = compile(
+            &plugins,
+            "void main() { } \
+             namespace game { class Player { void update() { } } } \
+             import void spawn(int) from \"enemies\";",
+        );
+
+        assert!(errors.is_empty());
+        assert_eq!(
+            *recorder.events.lock().unwrap(),
+            vec!["registered 3 items", "main", "game::Player::update"]
+        );
+    }
+
+    #[test]
+    fn plugin_errors_are_prefixed() {
+        let plugins: Vec<Box<dyn CompilerPlugin>> = vec![Box::new(Recorder::default())];
+        let errors = compile(&plugins, "void mix(int a, int b) { }");
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            &errors[0],
+            CompilationError::Other { message, .. } if message == "[recorder] too many parameters"
+        ));
+    }
+}
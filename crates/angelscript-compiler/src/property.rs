@@ -0,0 +1,723 @@
+//! Virtual properties of script classes.
+//!
+//! A virtual property is accessed like a field but backed by accessor
+//! methods. It is declared either with accessor blocks, which declare the
+//! methods `get_hp` and `set_hp`:
+//!
+//! ```as
+//! class Player {
+//!     int hp { get { return m_hp; } set { m_hp = value; } }
+//! }
+//! ```
+//!
+//! or implicitly, by a `get_X` method taking no parameters and returning a
+//! value and/or a `set_X` method taking one parameter and returning `void`.
+//!
+//! [`collect_properties`] finds the properties of a script's classes, and
+//! [`PropertyAccess`] lowers reads, writes and compound assignments of a
+//! property to accessor calls:
+//!
+//! ```text
+//! obj.hp = 5    // obj; 5; CALL_METHOD set_hp 1
+//! obj.hp += 1   // obj; DUP; CALL_METHOD get_hp 0; PUSH_ONE; ADD; CALL_METHOD set_hp 1
+//! ```
+//!
+//! Setters return `void`, so an assignment used as an expression keeps its
+//! value in a temporary local across the setter call (see [`AssignValue`]).
+//!
+//! Compilation only collects the properties for now: function bodies are
+//! not emitted yet, so nothing calls the [`PropertyAccess`] lowering.
+
+use angelscript_core::{CompilationError, PropertyEntry, Span, TypeHash};
+use angelscript_parser::ast::{
+    AssignOp, ClassDecl, ClassMember, FunctionDecl, Item, PropertyAccessorKind, Script, TypeExpr,
+};
+use angelscript_registry::SymbolRegistry;
+
+use crate::CompiledProperty;
+use crate::bytecode::{BytecodeChunk, ConstantPool, OpCode};
+
+/// Find the virtual properties of every class in a script.
+///
+/// Reports properties declared twice, accessor blocks and methods declaring
+/// the same accessor, `property` methods of the wrong shape, and getters and
+/// setters of different types.
+pub fn collect_properties(script: &Script<'_>) -> (Vec<CompiledProperty>, Vec<CompilationError>) {
+    let mut pass = PropertyPass {
+        properties: Vec::new(),
+        namespace: Vec::new(),
+        errors: Vec::new(),
+    };
+    pass.items(script.items());
+    (pass.properties, pass.errors)
+}
+
+struct PropertyPass {
+    properties: Vec<CompiledProperty>,
+    namespace: Vec<String>,
+    errors: Vec<CompilationError>,
+}
+
+/// One accessor of a property, as found in a class.
+struct Accessor {
+    value_type: String,
+    explicit: bool,
+    span: Span,
+}
+
+impl PropertyPass {
+    fn items(&mut self, items: &[Item<'_>]) {
+        for item in items {
+            match item {
+                Item::Namespace(ns) => {
+                    let depth = self.namespace.len();
+                    self.namespace
+                        .extend(ns.path.iter().map(|s| s.name.to_string()));
+                    self.items(ns.items);
+                    self.namespace.truncate(depth);
+                }
+                Item::Class(class) => self.class(class),
+                _ => {}
+            }
+        }
+    }
+
+    fn class(&mut self, class: &ClassDecl<'_>) {
+        let mut path = self.namespace.clone();
+        path.push(class.name.name.to_string());
+        let class_name = path.join("::");
+
+        // Accessors by property name, in declaration order
+        let mut accessors: Vec<(String, Option<Accessor>, Option<Accessor>)> = Vec::new();
+
+        for member in class.members {
+            match member {
+                ClassMember::VirtualProperty(prop) => {
+                    let at = slot(&mut accessors, prop.name.name);
+                    if accessors[at].1.is_some() || accessors[at].2.is_some() {
+                        self.errors.push(CompilationError::DuplicateDefinition {
+                            name: format!("{}::{}", class_name, prop.name.name),
+                            span: prop.span,
+                        });
+                        continue;
+                    }
+                    for accessor in prop.accessors {
+                        let found = Accessor {
+                            value_type: value_type(&prop.ty.ty),
+                            explicit: true,
+                            span: accessor.span,
+                        };
+                        match accessor.kind {
+                            PropertyAccessorKind::Get => accessors[at].1 = Some(found),
+                            PropertyAccessorKind::Set => accessors[at].2 = Some(found),
+                        }
+                    }
+                }
+                ClassMember::Method(method) => {
+                    let Some((kind, name)) = accessor_name(method.name.name) else {
+                        continue;
+                    };
+                    let Some(value) = self.implicit_accessor(&class_name, method, kind) else {
+                        continue;
+                    };
+                    let at = slot(&mut accessors, name);
+                    let existing = match kind {
+                        PropertyAccessorKind::Get => &mut accessors[at].1,
+                        PropertyAccessorKind::Set => &mut accessors[at].2,
+                    };
+                    match existing {
+                        // Overloads of an implicit accessor are plain methods
+                        Some(accessor) if !accessor.explicit => {}
+                        Some(_) => self.errors.push(CompilationError::DuplicateDefinition {
+                            name: format!("{}::{}", class_name, method.name.name),
+                            span: method.span,
+                        }),
+                        None => *existing = Some(value),
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for (name, getter, setter) in accessors {
+            if let (Some(get), Some(set)) = (&getter, &setter)
+                && get.value_type != set.value_type
+            {
+                self.errors.push(CompilationError::TypeMismatch {
+                    message: format!(
+                        "getter of property '{}::{}' returns '{}' but its setter takes '{}'",
+                        class_name, name, get.value_type, set.value_type
+                    ),
+                    span: set.span,
+                });
+                continue;
+            }
+            let Some(value_type) = getter
+                .as_ref()
+                .or(setter.as_ref())
+                .map(|accessor| accessor.value_type.clone())
+            else {
+                continue;
+            };
+            self.properties.push(CompiledProperty {
+                getter: getter.map(|_| format!("get_{name}")),
+                setter: setter.map(|_| format!("set_{name}")),
+                class: class_name.clone(),
+                name,
+                value_type,
+            });
+        }
+    }
+
+    /// The accessor a `get_X`/`set_X` method declares, if it has the shape
+    /// of one. Methods marked `property` must have that shape.
+    fn implicit_accessor(
+        &mut self,
+        class_name: &str,
+        method: &FunctionDecl<'_>,
+        kind: PropertyAccessorKind,
+    ) -> Option<Accessor> {
+        let returns_value = method.return_type.is_some_and(|ret| !ret.ty.is_void());
+        let value_type = match kind {
+            PropertyAccessorKind::Get if method.params.is_empty() && returns_value => {
+                method.return_type.map(|ret| value_type(&ret.ty))
+            }
+            PropertyAccessorKind::Set if method.params.len() == 1 && !returns_value => {
+                Some(value_type(&method.params[0].ty.ty))
+            }
+            _ => None,
+        };
+        if value_type.is_none() && method.attrs.property {
+            let shape = match kind {
+                PropertyAccessorKind::Get => "take no parameters and return a value",
+                PropertyAccessorKind::Set => "take one parameter and return void",
+            };
+            self.errors.push(CompilationError::InvalidOperation {
+                message: format!(
+                    "property accessor '{}::{}' must {}",
+                    class_name, method.name.name, shape
+                ),
+                span: method.span,
+            });
+        }
+        Some(Accessor {
+            value_type: value_type?,
+            explicit: false,
+            span: method.span,
+        })
+    }
+}
+
+/// Position of a property's accessors, added if not seen yet.
+fn slot(accessors: &mut Vec<(String, Option<Accessor>, Option<Accessor>)>, name: &str) -> usize {
+    match accessors.iter().position(|(n, _, _)| n == name) {
+        Some(at) => at,
+        None => {
+            accessors.push((name.to_string(), None, None));
+            accessors.len() - 1
+        }
+    }
+}
+
+/// Split an accessor method name into its kind and property name.
+fn accessor_name(method: &str) -> Option<(PropertyAccessorKind, &str)> {
+    let (kind, name) = if let Some(name) = method.strip_prefix("get_") {
+        (PropertyAccessorKind::Get, name)
+    } else {
+        (PropertyAccessorKind::Set, method.strip_prefix("set_")?)
+    };
+    (!name.is_empty()).then_some((kind, name))
+}
+
+/// The type of a property's value; `const` only matters to the accessors.
+fn value_type(ty: &TypeExpr<'_>) -> String {
+    let text = ty.to_string();
+    match text.strip_prefix("const ") {
+        Some(rest) => rest.to_string(),
+        None => text,
+    }
+}
+
+/// Accessor calls for reading and writing a property of an object.
+///
+/// All emitters expect the object on top of the stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyAccess {
+    /// Property name, for diagnostics.
+    pub name: String,
+    /// Getter method hash.
+    pub getter: Option<TypeHash>,
+    /// Setter method hash.
+    pub setter: Option<TypeHash>,
+}
+
+impl PropertyAccess {
+    /// Access through the accessors of a registered property.
+    ///
+    /// Returns `None` for plain fields, which are accessed with
+    /// `GET_FIELD`/`SET_FIELD` instead.
+    pub fn of(entry: &PropertyEntry) -> Option<Self> {
+        (entry.getter.is_some() || entry.setter.is_some()).then(|| Self {
+            name: entry.name.clone(),
+            getter: entry.getter,
+            setter: entry.setter,
+        })
+    }
+
+    /// Find a property of a registered class or one of its bases.
+    pub fn find(registry: &SymbolRegistry, class: TypeHash, name: &str) -> Option<Self> {
+        registry
+            .all_properties(class)
+            .into_iter()
+            .find(|entry| entry.name == name)
+            .and_then(Self::of)
+    }
+
+    /// Access through the accessors of a script class's property, whose
+    /// value has type `value_type`.
+    pub fn of_script(property: &CompiledProperty, class: TypeHash, value_type: TypeHash) -> Self {
+        Self {
+            name: property.name.clone(),
+            getter: property
+                .getter
+                .as_ref()
+                .map(|getter| TypeHash::from_method(class, getter, &[])),
+            setter: property
+                .setter
+                .as_ref()
+                .map(|setter| TypeHash::from_method(class, setter, &[value_type])),
+        }
+    }
+
+    /// `[obj] -> [value]`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the property has no getter.
+    pub fn emit_get(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        span: Span,
+    ) -> Result<(), CompilationError> {
+        chunk.set_span(span);
+        let getter = self
+            .getter
+            .ok_or_else(|| self.missing("write-only", span))?;
+        emit_call(chunk, constants, getter, 0, span.line);
+        Ok(())
+    }
+
+    /// `[obj, value] -> []`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the property has no setter.
+    pub fn emit_set(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        span: Span,
+    ) -> Result<(), CompilationError> {
+        chunk.set_span(span);
+        let setter = self.setter.ok_or_else(|| self.missing("read-only", span))?;
+        emit_call(chunk, constants, setter, 1, span.line);
+        Ok(())
+    }
+
+    /// `[obj] -> []`, with `rhs` emitting the right-hand side
+    /// (`[] -> [value]`) between the getter call and the operator.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the property lacks either accessor, or `op` is a
+    /// plain assignment.
+    pub fn emit_compound<F>(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        op: AssignOp,
+        rhs: F,
+        span: Span,
+    ) -> Result<(), CompilationError>
+    where
+        F: FnOnce(&mut BytecodeChunk, &mut ConstantPool),
+    {
+        if compound_opcode(op).is_none() {
+            return Err(CompilationError::InvalidOperation {
+                message: format!("'{op}' is not a compound assignment"),
+                span,
+            });
+        }
+        self.emit_assign(chunk, constants, op, rhs, AssignValue::Discard, span)
+    }
+
+    /// `[obj] -> []` or `[obj] -> [value]`, for plain and compound
+    /// assignments alike. `rhs` emits the right-hand side (`[] -> [value]`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the property lacks an accessor the assignment
+    /// needs.
+    pub fn emit_assign<F>(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        op: AssignOp,
+        rhs: F,
+        value: AssignValue,
+        span: Span,
+    ) -> Result<(), CompilationError>
+    where
+        F: FnOnce(&mut BytecodeChunk, &mut ConstantPool),
+    {
+        chunk.set_span(span);
+        let opcode = compound_opcode(op);
+        let getter = match opcode {
+            Some(_) => Some(
+                self.getter
+                    .ok_or_else(|| self.missing("write-only", span))?,
+            ),
+            None => None,
+        };
+        let setter = self.setter.ok_or_else(|| self.missing("read-only", span))?;
+
+        if let (Some(opcode), Some(getter)) = (opcode, getter) {
+            chunk.write_op(OpCode::Dup, span.line);
+            emit_call(chunk, constants, getter, 0, span.line);
+            rhs(chunk, constants);
+            // The right-hand side may have set spans of its own
+            chunk.set_span(span);
+            chunk.write_op(opcode, span.line);
+        } else {
+            rhs(chunk, constants);
+            chunk.set_span(span);
+        }
+        value.emit_keep(chunk, span.line);
+        emit_call(chunk, constants, setter, 1, span.line);
+        value.emit_restore(chunk, span.line);
+        Ok(())
+    }
+
+    fn missing(&self, kind: &str, span: Span) -> CompilationError {
+        CompilationError::InvalidOperation {
+            message: format!("property '{}' is {}", self.name, kind),
+            span,
+        }
+    }
+}
+
+/// What an assignment through accessors leaves on the stack.
+///
+/// Statements discard the assigned value. Expressions keep it, but setters
+/// return `void`, so the value is parked in a temporary local across the
+/// setter call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignValue {
+    /// Leave nothing, for assignment statements.
+    Discard,
+    /// Leave the assigned value, for assignment expressions.
+    Keep {
+        /// Local slot holding the value during the setter call.
+        temp: u16,
+    },
+}
+
+impl AssignValue {
+    /// Save the value on top of the stack (`SET_LOCAL` peeks).
+    pub(crate) fn emit_keep(self, chunk: &mut BytecodeChunk, line: u32) {
+        if let AssignValue::Keep { temp } = self {
+            chunk.emit_set_local(temp, line);
+        }
+    }
+
+    /// Push the saved value back after the setter call.
+    pub(crate) fn emit_restore(self, chunk: &mut BytecodeChunk, line: u32) {
+        if let AssignValue::Keep { temp } = self {
+            chunk.emit_get_local(temp, line);
+        }
+    }
+}
+
+/// The arithmetic opcode of a compound assignment.
+pub fn compound_opcode(op: AssignOp) -> Option<OpCode> {
+    Some(match op {
+        AssignOp::Assign => return None,
+        AssignOp::AddAssign => OpCode::Add,
+        AssignOp::SubAssign => OpCode::Sub,
+        AssignOp::MulAssign => OpCode::Mul,
+        AssignOp::DivAssign => OpCode::Div,
+        AssignOp::ModAssign => OpCode::Mod,
+        AssignOp::PowAssign => OpCode::Pow,
+        AssignOp::AndAssign => OpCode::BitAnd,
+        AssignOp::OrAssign => OpCode::BitOr,
+        AssignOp::XorAssign => OpCode::BitXor,
+        AssignOp::ShlAssign => OpCode::Shl,
+        AssignOp::ShrAssign => OpCode::Shr,
+        AssignOp::UshrAssign => OpCode::Ushr,
+    })
+}
+
+pub(crate) fn emit_call(
+    chunk: &mut BytecodeChunk,
+    constants: &mut ConstantPool,
+    method: TypeHash,
+    args: u8,
+    line: u32,
+) {
+    let index = constants.add_type_hash(method);
+    chunk.write_op(OpCode::CallMethod, line);
+    chunk.write_u16(index as u16, line);
+    chunk.write_byte(args, line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::primitives;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn collect(source: &str) -> (Vec<CompiledProperty>, Vec<CompilationError>) {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).expect("parse");
+        collect_properties(&script)
+    }
+
+    #[test]
+    fn accessor_blocks_and_implicit_accessors() {
+        let (properties, errors) = collect(
+            r#"
+            namespace game {
+                class Player {
+                    private int m_hp;
+                    int hp { get { return m_hp; } set { m_hp = value; } }
+                    float get_speed() const { return 1.0f; }
+                    void set_speed(float v) {}
+                    string get_name() { return ""; }
+                    void get_nothing() {}
+                }
+            }
+            "#,
+        );
+        assert!(errors.is_empty(), "{errors:?}");
+        assert_eq!(properties.len(), 3);
+
+        let hp = &properties[0];
+        assert_eq!(hp.class, "game::Player");
+        assert_eq!(hp.name, "hp");
+        assert_eq!(hp.value_type, "int");
+        assert_eq!(hp.getter.as_deref(), Some("get_hp"));
+        assert_eq!(hp.setter.as_deref(), Some("set_hp"));
+
+        assert_eq!(properties[1].name, "speed");
+        assert_eq!(properties[1].value_type, "float");
+        assert_eq!(properties[2].name, "name");
+        assert!(properties[2].setter.is_none());
+    }
+
+    #[test]
+    fn accessor_conflicts_are_reported() {
+        let (_, errors) = collect(
+            r#"
+            class A {
+                int hp { get { return 0; } }
+                int get_hp() { return 1; }
+                int get_mana() { return 0; }
+                void set_mana(float v) {}
+                int get_bad(int x) property { return x; }
+            }
+            "#,
+        );
+        assert_eq!(errors.len(), 3, "{errors:?}");
+        assert!(
+            matches!(errors[0], CompilationError::DuplicateDefinition { ref name, .. } if name == "A::get_hp")
+        );
+        assert!(matches!(
+            errors[1],
+            CompilationError::InvalidOperation { .. }
+        ));
+        assert!(matches!(errors[2], CompilationError::TypeMismatch { .. }));
+    }
+
+    #[test]
+    fn assignment_lowers_to_setter_call() {
+        let class = TypeHash::from_name("Player");
+        let (properties, _) = collect("class Player { int hp { get { return 0; } set {} } }");
+        let access = PropertyAccess::of_script(&properties[0], class, primitives::INT32);
+
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(0, 1);
+        chunk.write_op(OpCode::PushOne, 1);
+        access
+            .emit_set(&mut chunk, &mut constants, Span::new(1, 1, 1))
+            .unwrap();
+        chunk.assert_opcodes(&[OpCode::GetLocal, OpCode::PushOne, OpCode::CallMethod]);
+
+        let setter = TypeHash::from_method(class, "set_hp", &[primitives::INT32]);
+        assert_eq!(
+            constants.get(0),
+            Some(&crate::bytecode::Constant::TypeHash(setter))
+        );
+    }
+
+    #[test]
+    fn compound_assignment_reads_then_writes() {
+        let access = PropertyAccess {
+            name: "hp".to_string(),
+            getter: Some(TypeHash::from_name("get_hp")),
+            setter: Some(TypeHash::from_name("set_hp")),
+        };
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        access
+            .emit_compound(
+                &mut chunk,
+                &mut constants,
+                AssignOp::AddAssign,
+                |chunk, _| chunk.write_op(OpCode::PushOne, 1),
+                Span::new(1, 1, 1),
+            )
+            .unwrap();
+        chunk.assert_opcodes(&[
+            OpCode::Dup,
+            OpCode::CallMethod,
+            OpCode::PushOne,
+            OpCode::Add,
+            OpCode::CallMethod,
+        ]);
+    }
+
+    #[test]
+    fn assignment_records_its_span() {
+        let access = PropertyAccess {
+            name: "hp".to_string(),
+            getter: Some(TypeHash::from_name("get_hp")),
+            setter: Some(TypeHash::from_name("set_hp")),
+        };
+        let (assign, rhs) = (Span::new(4, 3, 10), Span::new(4, 12, 1));
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        access
+            .emit_assign(
+                &mut chunk,
+                &mut constants,
+                AssignOp::AddAssign,
+                |chunk, _| {
+                    chunk.set_span(rhs);
+                    chunk.write_op(OpCode::PushOne, rhs.line);
+                },
+                AssignValue::Discard,
+                assign,
+            )
+            .unwrap();
+
+        // DUP, CALL_METHOD get, PUSH_ONE, ADD, CALL_METHOD set
+        assert_eq!(chunk.span_at(0), Some(assign));
+        assert_eq!(chunk.span_at(5), Some(rhs));
+        assert_eq!(chunk.span_at(6), Some(assign));
+        assert_eq!(chunk.span_at(chunk.len() - 1), Some(assign));
+    }
+
+    #[test]
+    fn assignment_expression_keeps_the_value() {
+        let access = PropertyAccess {
+            name: "hp".to_string(),
+            getter: Some(TypeHash::from_name("get_hp")),
+            setter: Some(TypeHash::from_name("set_hp")),
+        };
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        access
+            .emit_assign(
+                &mut chunk,
+                &mut constants,
+                AssignOp::MulAssign,
+                |chunk, _| chunk.write_op(OpCode::PushOne, 1),
+                AssignValue::Keep { temp: 3 },
+                Span::new(1, 1, 1),
+            )
+            .unwrap();
+        chunk.assert_opcodes(&[
+            OpCode::Dup,
+            OpCode::CallMethod,
+            OpCode::PushOne,
+            OpCode::Mul,
+            OpCode::SetLocal,
+            OpCode::CallMethod,
+            OpCode::GetLocal,
+        ]);
+
+        // A plain assignment never calls the getter
+        let write_only = PropertyAccess {
+            getter: None,
+            ..access
+        };
+        let mut chunk = BytecodeChunk::new();
+        write_only
+            .emit_assign(
+                &mut chunk,
+                &mut constants,
+                AssignOp::Assign,
+                |chunk, _| chunk.write_op(OpCode::PushOne, 1),
+                AssignValue::Discard,
+                Span::new(1, 1, 1),
+            )
+            .unwrap();
+        chunk.assert_opcodes(&[OpCode::PushOne, OpCode::CallMethod]);
+    }
+
+    #[test]
+    fn missing_accessors_are_errors() {
+        let read_only = PropertyAccess {
+            name: "id".to_string(),
+            getter: Some(TypeHash::from_name("get_id")),
+            setter: None,
+        };
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        let span = Span::new(3, 5, 2);
+
+        let error = read_only
+            .emit_set(&mut chunk, &mut constants, span)
+            .unwrap_err();
+        assert!(error.to_string().contains("property 'id' is read-only"));
+        assert!(
+            read_only
+                .emit_compound(
+                    &mut chunk,
+                    &mut constants,
+                    AssignOp::AddAssign,
+                    |_, _| {},
+                    span
+                )
+                .is_err()
+        );
+        assert!(chunk.is_empty());
+    }
+
+    #[test]
+    fn registered_properties_are_found_on_bases() {
+        use angelscript_core::{ClassEntry, DataType, TypeKind};
+
+        let mut registry = SymbolRegistry::with_primitives();
+        let base = ClassEntry::ffi("Entity", TypeKind::reference()).with_property(
+            PropertyEntry::read_only(
+                "id",
+                DataType::simple(primitives::INT32),
+                TypeHash::from_name("Entity::get_id"),
+            ),
+        );
+        let base_hash = base.type_hash;
+        registry.register_type(base.into()).unwrap();
+        let derived = ClassEntry::ffi("Player", TypeKind::reference()).with_base(base_hash);
+        let derived_hash = derived.type_hash;
+        registry.register_type(derived.into()).unwrap();
+
+        let access = PropertyAccess::find(&registry, derived_hash, "id").unwrap();
+        assert_eq!(access.getter, Some(TypeHash::from_name("Entity::get_id")));
+        assert!(PropertyAccess::find(&registry, derived_hash, "missing").is_none());
+    }
+}
@@ -0,0 +1,340 @@
+//! Optimization of calls to pure functions.
+//!
+//! Registrations mark a function pure when it has no side effects and its
+//! result depends only on its arguments (`.function(sqrt.pure())` or
+//! `#[angelscript::function(pure)]`). The compiler trusts the annotation:
+//!
+//! - [`fold_pure_calls`] runs native pure functions called with constant
+//!   arguments at compile time and replaces the call with its result, so
+//!   `sqrt(2.0)` costs nothing at runtime. Pure script functions are
+//!   evaluated the same way by [`fold_constexpr_calls`].
+//! - [`eliminate_common_subexpressions`] reuses the result of repeated calls
+//!   of pure functions and methods with the same arguments.
+//!
+//! Only primitive results are folded; a call returning an object, or one
+//! failing at compile time, is left for the runtime to make.
+//!
+//! [`eliminate_common_subexpressions`]: crate::bytecode::eliminate_common_subexpressions
+//! [`fold_constexpr_calls`]: crate::constexpr::fold_constexpr_calls
+
+use angelscript_core::{
+    CallContext, Dynamic, FunctionImpl, NativeFn, ObjectHeap, TypeHash, primitives,
+};
+use angelscript_registry::SymbolRegistry;
+
+use crate::bytecode::{
+    BytecodeChunk, BytecodeRewriter, Constant, ConstantPool, InstrId, Instruction, OpCode,
+    RewriteError,
+};
+
+/// Check if `function` is registered as pure.
+pub fn is_pure(registry: &SymbolRegistry, function: TypeHash) -> bool {
+    registry
+        .get_function(function)
+        .is_some_and(|entry| entry.def.is_pure())
+}
+
+/// Replace calls of native pure functions with constant arguments by their
+/// results. Results are added to `constants`.
+///
+/// # Errors
+///
+/// Returns an error if the chunk cannot be decoded or re-encoded.
+pub fn fold_pure_calls(
+    chunk: &BytecodeChunk,
+    constants: &mut ConstantPool,
+    registry: &SymbolRegistry,
+) -> Result<BytecodeChunk, RewriteError> {
+    fold_calls(chunk, constants, |function, args| {
+        call_native(registry, function, args)
+    })
+}
+
+/// Replace calls with constant arguments that `evaluate` can run by their
+/// results. `evaluate` gives the return type and value of a call, or `None`
+/// to leave it.
+pub(crate) fn fold_calls(
+    chunk: &BytecodeChunk,
+    constants: &mut ConstantPool,
+    evaluate: impl Fn(TypeHash, Vec<Dynamic>) -> Option<(TypeHash, Dynamic)>,
+) -> Result<BytecodeChunk, RewriteError> {
+    let mut rewriter = BytecodeRewriter::new(chunk)?;
+    let mut changed = false;
+
+    // A folded call may be the argument of another, so repeat until nothing
+    // folds
+    loop {
+        let ids: Vec<InstrId> = rewriter.ids().collect();
+        let mut folded = false;
+        for (position, &call) in ids.iter().enumerate() {
+            let Some((args, result)) = fold(&rewriter, &ids, position, constants, &evaluate) else {
+                continue;
+            };
+            let line = rewriter.get(call).map_or(0, Instruction::line);
+            let result = match result {
+                Folded::Bool(true) => Instruction::simple(OpCode::PushTrue, line),
+                Folded::Bool(false) => Instruction::simple(OpCode::PushFalse, line),
+                Folded::Constant(constant) => Instruction::load_constant(constants, constant, line),
+            };
+
+            // Jumps to the first argument land on the result
+            match args.split_first() {
+                Some((&first, rest)) => {
+                    rewriter.replace(first, result);
+                    for &id in rest.iter().chain([&call]) {
+                        rewriter.remove(id);
+                    }
+                }
+                None => rewriter.replace(call, result),
+            }
+            folded = true;
+            changed = true;
+        }
+        if !folded {
+            break;
+        }
+    }
+
+    if changed {
+        rewriter.finish()
+    } else {
+        Ok(chunk.clone())
+    }
+}
+
+/// The result of a folded call.
+enum Folded {
+    Bool(bool),
+    Constant(Constant),
+}
+
+/// The argument instructions and result of the call at `position`, if it
+/// has constant arguments and `evaluate` runs it.
+fn fold(
+    rewriter: &BytecodeRewriter,
+    ids: &[InstrId],
+    position: usize,
+    constants: &ConstantPool,
+    evaluate: &impl Fn(TypeHash, Vec<Dynamic>) -> Option<(TypeHash, Dynamic)>,
+) -> Option<(Vec<InstrId>, Folded)> {
+    let call = rewriter.get(ids[position])?;
+    let (OpCode::Call, &[hi, lo, args]) = (call.op(), call.operands()) else {
+        return None;
+    };
+    let Some(&Constant::TypeHash(function)) =
+        constants.get(u32::from(u16::from_be_bytes([hi, lo])))
+    else {
+        return None;
+    };
+
+    // The arguments are pushed directly before the call, on every path
+    let first = position.checked_sub(usize::from(args))?;
+    let arg_ids = ids[first..position].to_vec();
+    if ids[first + 1..=position]
+        .iter()
+        .any(|&id| rewriter.is_jump_target(id))
+    {
+        return None;
+    }
+    let values = arg_ids
+        .iter()
+        .map(|&id| constant_value(rewriter.get(id)?, constants))
+        .collect::<Option<Vec<_>>>()?;
+
+    let result = match evaluate(function, values)? {
+        (primitives::BOOL, Dynamic::Bool(value)) => Folded::Bool(value),
+        (
+            primitives::INT8 | primitives::INT16 | primitives::INT32 | primitives::INT64,
+            Dynamic::Int(value),
+        ) => Folded::Constant(Constant::Int(value)),
+        (
+            primitives::UINT8 | primitives::UINT16 | primitives::UINT32 | primitives::UINT64,
+            Dynamic::Int(value),
+        ) => Folded::Constant(Constant::Uint(value as u64)),
+        (primitives::FLOAT, Dynamic::Float(value)) => {
+            Folded::Constant(Constant::Float32(value as f32))
+        }
+        (primitives::DOUBLE, Dynamic::Float(value)) => Folded::Constant(Constant::Float64(value)),
+        _ => return None,
+    };
+    Some((arg_ids, result))
+}
+
+/// The value pushed by a constant instruction.
+fn constant_value(instruction: &Instruction, constants: &ConstantPool) -> Option<Dynamic> {
+    let index = match (instruction.op(), instruction.operands()) {
+        (OpCode::PushZero, _) => return Some(Dynamic::Int(0)),
+        (OpCode::PushOne, _) => return Some(Dynamic::Int(1)),
+        (OpCode::PushTrue, _) => return Some(Dynamic::Bool(true)),
+        (OpCode::PushFalse, _) => return Some(Dynamic::Bool(false)),
+        (OpCode::Constant, &[index]) => u32::from(index),
+        (OpCode::ConstantWide, &[hi, lo]) => u32::from(u16::from_be_bytes([hi, lo])),
+        _ => return None,
+    };
+    Some(match constants.get(index)? {
+        Constant::Int(value) => Dynamic::Int(*value),
+        Constant::Uint(value) => Dynamic::Int(*value as i64),
+        Constant::Float32(value) => Dynamic::Float(f64::from(*value)),
+        Constant::Float64(value) => Dynamic::Float(*value),
+        Constant::StringData(bytes) => Dynamic::String(String::from_utf8(bytes.clone()).ok()?),
+        Constant::TypeHash(_) => return None,
+    })
+}
+
+/// Call a native pure function that is not a method, giving its return
+/// type and result, or `None` if it is not one or fails.
+pub(crate) fn call_native(
+    registry: &SymbolRegistry,
+    function: TypeHash,
+    args: Vec<Dynamic>,
+) -> Option<(TypeHash, Dynamic)> {
+    let entry = registry.get_function(function)?;
+    let FunctionImpl::Native(Some(native)) = &entry.implementation else {
+        return None;
+    };
+    if !entry.def.is_pure() || entry.def.is_method() {
+        return None;
+    }
+    Some((entry.def.return_type.type_hash, evaluate(native, args)?))
+}
+
+/// Call a native function, returning `None` if it fails.
+fn evaluate(native: &NativeFn, mut args: Vec<Dynamic>) -> Option<Dynamic> {
+    let mut result = Dynamic::Void;
+    let mut heap = ObjectHeap::new();
+    let mut ctx = CallContext::new(&mut args, 0, &mut result, &mut heap);
+    native.call(&mut ctx).ok()?;
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{
+        DataType, FunctionDef, FunctionEntry, FunctionTraits, NativeError, Param, Visibility,
+    };
+
+    fn register(
+        registry: &mut SymbolRegistry,
+        name: &str,
+        param: TypeHash,
+        return_type: TypeHash,
+        traits: FunctionTraits,
+        f: fn(&mut CallContext) -> Result<(), NativeError>,
+    ) -> TypeHash {
+        let func_hash = TypeHash::from_function(name, &[param]);
+        let def = FunctionDef::new(
+            func_hash,
+            name.to_string(),
+            vec![],
+            vec![Param::new("x", DataType::simple(param))],
+            DataType::simple(return_type),
+            None,
+            traits,
+            true,
+            Visibility::Public,
+        );
+        let mut entry = FunctionEntry::ffi(def);
+        entry.implementation = FunctionImpl::Native(Some(NativeFn::new(func_hash, f)));
+        registry.register_function(entry).unwrap();
+        func_hash
+    }
+
+    fn registry() -> (SymbolRegistry, [TypeHash; 3]) {
+        let mut registry = SymbolRegistry::with_primitives();
+        let sqrt = register(
+            &mut registry,
+            "sqrt",
+            primitives::DOUBLE,
+            primitives::DOUBLE,
+            FunctionTraits::new().with_pure(),
+            |ctx| {
+                let x: f64 = ctx.arg(0)?;
+                if x < 0.0 {
+                    return Err(NativeError::other("negative"));
+                }
+                ctx.set_return(x.sqrt());
+                Ok(())
+            },
+        );
+        let is_even = register(
+            &mut registry,
+            "isEven",
+            primitives::INT32,
+            primitives::BOOL,
+            FunctionTraits::new().with_pure(),
+            |ctx| {
+                let x: i32 = ctx.arg(0)?;
+                ctx.set_return(x % 2 == 0);
+                Ok(())
+            },
+        );
+        let seed = register(
+            &mut registry,
+            "seed",
+            primitives::INT32,
+            primitives::INT32,
+            FunctionTraits::new(),
+            |ctx| {
+                ctx.set_return(4);
+                Ok(())
+            },
+        );
+        (registry, [sqrt, is_even, seed])
+    }
+
+    fn call(chunk: &mut BytecodeChunk, constants: &mut ConstantPool, function: TypeHash) {
+        let index = constants.add_type_hash(function);
+        chunk.write_op(OpCode::Call, 1);
+        chunk.write_u16(index as u16, 1);
+        chunk.write_byte(1, 1);
+    }
+
+    fn push(chunk: &mut BytecodeChunk, constants: &mut ConstantPool, constant: Constant) {
+        let index = constants.add(constant);
+        chunk.write_op(OpCode::Constant, 1);
+        chunk.write_byte(index as u8, 1);
+    }
+
+    #[test]
+    fn folds_nested_pure_calls() {
+        let (registry, [sqrt, is_even, _]) = registry();
+        let mut constants = ConstantPool::new();
+        let mut chunk = BytecodeChunk::new();
+        // sqrt(sqrt(16.0)); isEven(1)
+        push(&mut chunk, &mut constants, Constant::Float64(16.0));
+        call(&mut chunk, &mut constants, sqrt);
+        call(&mut chunk, &mut constants, sqrt);
+        chunk.write_op(OpCode::PushOne, 1);
+        call(&mut chunk, &mut constants, is_even);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let folded = fold_pure_calls(&chunk, &mut constants, &registry).unwrap();
+        folded.assert_opcodes(&[OpCode::Constant, OpCode::PushFalse, OpCode::ReturnVoid]);
+        let index = folded.read_byte(1).unwrap();
+        assert_eq!(
+            constants.get(u32::from(index)),
+            Some(&Constant::Float64(2.0))
+        );
+    }
+
+    #[test]
+    fn keeps_impure_failing_and_non_constant_calls() {
+        let (registry, [sqrt, _, seed]) = registry();
+        let mut constants = ConstantPool::new();
+        let mut chunk = BytecodeChunk::new();
+        chunk.write_op(OpCode::PushOne, 1);
+        call(&mut chunk, &mut constants, seed);
+        push(&mut chunk, &mut constants, Constant::Float64(-1.0));
+        call(&mut chunk, &mut constants, sqrt);
+        chunk.write_op(OpCode::GetLocal, 1);
+        chunk.write_byte(0, 1);
+        call(&mut chunk, &mut constants, sqrt);
+        chunk.write_op(OpCode::ReturnVoid, 1);
+
+        let folded = fold_pure_calls(&chunk, &mut constants, &registry).unwrap();
+        assert_eq!(folded.code(), chunk.code());
+        assert!(is_pure(&registry, sqrt));
+        assert!(!is_pure(&registry, seed));
+    }
+}
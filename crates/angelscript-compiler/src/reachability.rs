@@ -0,0 +1,187 @@
+//! Reachability of statements.
+//!
+//! A statement *completes* if control can run past its end. Statements
+//! following one that does not complete are unreachable, which the
+//! [`UnreachableCode`](crate::WarningCode::UnreachableCode) warning reports
+//! and the bytecode optimizer strips. A statement does not complete if it
+//!
+//! - is a `return`, `break` or `continue`, or a call of `throw(...)`;
+//! - is an `if` with an `else` where neither branch completes;
+//! - is a loop whose condition is missing or `true` and whose body has no
+//!   `break` leaving it;
+//! - is a `do`-`while` whose body neither completes nor continues;
+//! - is a `switch` with a `default` case, or whose labels are exhaustive
+//!   otherwise, no `break` leaving it, and a last case that does not
+//!   complete;
+//! - is a `try` whose try and catch blocks both do not complete;
+//! - is a block with a statement that does not complete.
+//!
+//! Conditions are only recognized as constant when written as the literal
+//! `true`, so the analysis never depends on constant folding. Likewise only
+//! switches with a `default` case are exhaustive, unless the caller knows
+//! better (see [`completes_with`]).
+
+use angelscript_parser::ast::{Expr, LiteralKind, Stmt, SwitchStmt};
+
+/// Check if control can run past the end of a statement.
+pub fn completes(stmt: &Stmt<'_>) -> bool {
+    completes_with(stmt, &|_| false)
+}
+
+/// Check if control can run past the end of a statement list.
+pub fn stmts_complete(stmts: &[Stmt<'_>]) -> bool {
+    stmts.iter().all(completes)
+}
+
+/// Check if control can run past the end of a statement, given which
+/// switches without a `default` case still cover every value of their
+/// expression.
+pub fn completes_with(stmt: &Stmt<'_>, exhaustive: &dyn Fn(&SwitchStmt<'_>) -> bool) -> bool {
+    let stmts_complete = |stmts: &[Stmt<'_>]| stmts.iter().all(|s| completes_with(s, exhaustive));
+    match stmt {
+        Stmt::Return(_) | Stmt::Break(_) | Stmt::Continue(_) => false,
+        Stmt::Expr(stmt) => !stmt.expr.is_some_and(is_throw),
+        Stmt::VarDecl(_) | Stmt::Foreach(_) | Stmt::Function(_) => true,
+        Stmt::Block(block) => stmts_complete(block.stmts),
+        Stmt::If(stmt) => {
+            completes_with(stmt.then_stmt, exhaustive)
+                || stmt
+                    .else_stmt
+                    .is_none_or(|stmt| completes_with(stmt, exhaustive))
+        }
+        Stmt::While(stmt) => !is_true(stmt.condition) || breaks(stmt.body),
+        Stmt::For(stmt) => !stmt.condition.is_none_or(is_true) || breaks(stmt.body),
+        Stmt::DoWhile(stmt) => {
+            breaks(stmt.body)
+                || ((completes_with(stmt.body, exhaustive) || continues(stmt.body))
+                    && !is_true(stmt.condition))
+        }
+        Stmt::Switch(stmt) => {
+            !(stmt.cases.iter().any(|case| case.is_default()) || exhaustive(stmt))
+                || stmt.cases.iter().any(|case| case.stmts.iter().any(breaks))
+                || stmt
+                    .cases
+                    .last()
+                    .is_none_or(|case| stmts_complete(case.stmts))
+        }
+        Stmt::TryCatch(stmt) => {
+            stmts_complete(stmt.try_block.stmts) || stmts_complete(stmt.catch_block.stmts)
+        }
+    }
+}
+
+/// Index of the first statement of a list that no path reaches, if any.
+pub fn first_unreachable(stmts: &[Stmt<'_>]) -> Option<usize> {
+    let jump = stmts.iter().position(|stmt| !completes(stmt))?;
+    (jump + 1 < stmts.len()).then_some(jump + 1)
+}
+
+/// Check if an expression is a call of the `throw` function.
+fn is_throw(expr: &Expr<'_>) -> bool {
+    matches!(expr, Expr::Call(call)
+        if matches!(call.callee, Expr::Ident(ident)
+            if ident.scope.is_none() && ident.ident.name == "throw"))
+}
+
+/// Check if a condition is the literal `true`.
+fn is_true(expr: &Expr<'_>) -> bool {
+    match expr {
+        Expr::Literal(literal) => literal.kind == LiteralKind::Bool(true),
+        Expr::Paren(paren) => is_true(paren.expr),
+        _ => false,
+    }
+}
+
+/// Check if a statement has a `break` leaving the enclosing loop or switch.
+fn breaks(stmt: &Stmt<'_>) -> bool {
+    match stmt {
+        Stmt::Break(_) => true,
+        Stmt::Block(block) => block.stmts.iter().any(breaks),
+        Stmt::If(stmt) => breaks(stmt.then_stmt) || stmt.else_stmt.is_some_and(breaks),
+        Stmt::TryCatch(stmt) => {
+            stmt.try_block.stmts.iter().any(breaks) || stmt.catch_block.stmts.iter().any(breaks)
+        }
+        // Breaks in nested loops and switches leave those
+        _ => false,
+    }
+}
+
+/// Check if a statement has a `continue` of the enclosing loop.
+fn continues(stmt: &Stmt<'_>) -> bool {
+    match stmt {
+        Stmt::Continue(_) => true,
+        Stmt::Block(block) => block.stmts.iter().any(continues),
+        Stmt::If(stmt) => continues(stmt.then_stmt) || stmt.else_stmt.is_some_and(continues),
+        Stmt::TryCatch(stmt) => {
+            stmt.try_block.stmts.iter().any(continues)
+                || stmt.catch_block.stmts.iter().any(continues)
+        }
+        // A switch passes `continue` on to the loop around it
+        Stmt::Switch(stmt) => stmt
+            .cases
+            .iter()
+            .any(|case| case.stmts.iter().any(continues)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::{Item, Parser};
+    use bumpalo::Bump;
+
+    /// Index of the first unreachable statement in the body of `f`.
+    fn unreachable(body: &str) -> Option<usize> {
+        let arena = Bump::new();
+        let source = format!("void f(int x) {{ {} }}", body);
+        let script = Parser::parse(&source, &arena).unwrap();
+        let Item::Function(function) = &script.items()[0] else {
+            panic!("expected a function");
+        };
+        first_unreachable(function.body.as_ref().unwrap().stmts)
+    }
+
+    #[test]
+    fn jumps_and_branches() {
+        assert_eq!(unreachable("x++; return; x++;"), Some(2));
+        assert_eq!(unreachable("throw(\"no\"); x++;"), Some(1));
+        assert_eq!(
+            unreachable("if (x > 0) return; else { throw(\"no\"); } x++;"),
+            Some(1)
+        );
+        assert_eq!(unreachable("if (x > 0) return; x++;"), None);
+        assert_eq!(unreachable("{ return; } x++;"), Some(1));
+        assert_eq!(unreachable("try { return; } catch { } x++;"), None);
+        assert_eq!(
+            unreachable("try { return; } catch { return; } x++;"),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn loops() {
+        assert_eq!(unreachable("while (true) { x++; } x++;"), Some(1));
+        assert_eq!(unreachable("for (;;) { if (x > 0) break; } x++;"), None);
+        assert_eq!(
+            unreachable("for (;;) { while (true) { break; } } x++;"),
+            Some(1)
+        );
+        assert_eq!(unreachable("while (x > 0) { return; } x++;"), None);
+        assert_eq!(unreachable("do { return; } while (x > 0); x++;"), Some(1));
+        assert_eq!(unreachable("do { continue; } while (x > 0); x++;"), None);
+    }
+
+    #[test]
+    fn switches() {
+        assert_eq!(
+            unreachable("switch (x) { case 1: return; default: return; } x++;"),
+            Some(1)
+        );
+        assert_eq!(
+            unreachable("switch (x) { case 1: break; default: return; } x++;"),
+            None
+        );
+        assert_eq!(unreachable("switch (x) { case 1: return; } x++;"), None);
+    }
+}
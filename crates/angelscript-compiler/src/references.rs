@@ -0,0 +1,98 @@
+//! `&inout` parameters of value types.
+//!
+//! A `&inout` reference, written `&inout` or just `&`, points straight at
+//! the caller's variable. For a reference type the object is kept alive by
+//! the reference itself, but nothing keeps a primitive or value type alive:
+//! if the callee does something that frees the variable, such as clearing
+//! the array holding it, the reference dangles. Script functions may
+//! therefore only take handle-capable types by `&inout`:
+//!
+//! ```angelscript
+//! class Player {}
+//!
+//! void heal(Player &inout p) {}     // ok: reference type
+//! void bump(int &inout count) {}    // error: use &in or &out
+//! void swap(int &a, int &b) {}      // error: `&` is `&inout`
+//! ```
+//!
+//! Hosts that trust their scripts can allow them with
+//! [`CompilerOptions::allow_unsafe_references`].
+//!
+//! [`CompilerOptions::allow_unsafe_references`]: crate::CompilerOptions::allow_unsafe_references
+
+use angelscript_core::CompilationError;
+use angelscript_parser::ast::{RefKind, Script};
+use angelscript_registry::SymbolRegistry;
+use rustc_hash::FxHashMap;
+
+use crate::closure::{self, Storage};
+use crate::interfaces::namespace_of;
+use crate::plugin;
+
+/// Report `&inout` parameters of primitives and value types declared by
+/// script functions and methods.
+pub fn check_unsafe_references(
+    script: &Script<'_>,
+    registry: &SymbolRegistry,
+) -> Vec<CompilationError> {
+    let mut script_types = FxHashMap::default();
+    closure::script_type_kinds(script.items(), "", &mut script_types);
+
+    let mut bodies = Vec::new();
+    plugin::function_bodies(script.items(), "", &mut bodies);
+    let mut errors = Vec::new();
+    for (name, decl) in &bodies {
+        for param in decl.params {
+            if !matches!(param.ty.ref_kind, RefKind::Ref | RefKind::RefInOut) {
+                continue;
+            }
+            let storage = closure::type_storage(
+                registry,
+                &script_types,
+                namespace_of(name),
+                &param.ty.ty,
+                RefKind::None,
+            );
+            if storage == Storage::Value {
+                errors.push(CompilationError::InvalidParameterType {
+                    type_name: param.ty.ty.to_string(),
+                    reason: "only reference types can be passed by '&inout'; use '&in' or '&out'"
+                        .to_string(),
+                    span: param.span,
+                });
+            }
+        }
+    }
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    #[test]
+    fn value_types_cannot_be_passed_by_inout() {
+        let arena = Bump::new();
+        let source = "
+            enum Team { Red, Blue }
+            class Player {}
+            void heal(Player &inout p, Player@ &in q, int &in hp, int &out left) {}
+            void bump(int &inout count) {}
+            void pick(Team &team) {}
+            class Box { void swap(double &a) {} }
+        ";
+        let script = Parser::parse(source, &arena).unwrap();
+        let registry = SymbolRegistry::with_primitives();
+        let errors = check_unsafe_references(&script, &registry);
+        let types: Vec<_> = errors
+            .iter()
+            .map(|e| match e {
+                CompilationError::InvalidParameterType { type_name, .. } => type_name.as_str(),
+                other => panic!("unexpected error {other}"),
+            })
+            .collect();
+        assert_eq!(types, ["int", "Team", "double"]);
+    }
+}
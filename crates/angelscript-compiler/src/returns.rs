@@ -0,0 +1,217 @@
+//! Missing return paths.
+//!
+//! Control must not reach the end of a function that returns a value.
+//! [`ReturnChecker`] reports such functions using the rules of
+//! [`reachability`], so a `throw`, a loop that never ends or a `try` whose
+//! blocks both return also end a path. A `switch` without a `default` case
+//! ends every path too when its labels name every enumerator of one enum:
+//!
+//! ```angelscript
+//! enum Side { Left, Right }
+//!
+//! int sign(Side side) {
+//!     switch (side) {
+//!         case Left: return -1;
+//!         case Side::Right: return 1;
+//!     }
+//! }
+//! ```
+
+use angelscript_core::CompilationError;
+use angelscript_parser::ast::{Expr, Script, SwitchStmt};
+use angelscript_registry::SymbolRegistry;
+
+use crate::{CompiledEnum, plugin, reachability};
+
+/// Reports functions returning a value where control can reach the end of
+/// the body.
+#[derive(Debug, Default)]
+pub struct ReturnChecker {
+    enums: Vec<EnumCases>,
+}
+
+/// Enumerators a switch must name to be exhaustive.
+#[derive(Debug)]
+struct EnumCases {
+    /// Qualified name.
+    name: String,
+    /// Unqualified enumerator names and values.
+    enumerators: Vec<(String, i64)>,
+}
+
+impl ReturnChecker {
+    /// Create a checker that knows no enums.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a checker that knows the enums registered in `registry` and
+    /// the script enums in `enums`.
+    pub fn with_enums(registry: &SymbolRegistry, enums: &[CompiledEnum]) -> Self {
+        let mut checker = Self::new();
+        for entry in registry.enums() {
+            checker.add_enum(
+                &entry.qualified_name,
+                entry.values.iter().map(|v| (v.name.clone(), v.value)),
+            );
+        }
+        for compiled in enums {
+            let prefix = format!("{}::", compiled.name);
+            checker.add_enum(
+                &compiled.name,
+                compiled.values.iter().map(|(qualified, value)| {
+                    let name = qualified.strip_prefix(&prefix).unwrap_or(qualified);
+                    (name.to_string(), *value)
+                }),
+            );
+        }
+        checker
+    }
+
+    /// Make switches naming every enumerator of an enum exhaustive.
+    pub fn add_enum(&mut self, name: &str, enumerators: impl IntoIterator<Item = (String, i64)>) {
+        self.enums.push(EnumCases {
+            name: name.to_string(),
+            enumerators: enumerators.into_iter().collect(),
+        });
+    }
+
+    /// Check every function and method body of a script.
+    pub fn check(&self, script: &Script<'_>) -> Vec<CompilationError> {
+        let mut bodies = Vec::new();
+        plugin::function_bodies(script.items(), "", &mut bodies);
+        bodies
+            .into_iter()
+            .filter(|(_, decl)| decl.return_type.is_some_and(|r| !r.ty.is_void()))
+            .filter_map(|(function, decl)| {
+                let body = decl.body.as_ref()?;
+                body.stmts
+                    .iter()
+                    .all(|stmt| reachability::completes_with(stmt, &|s| self.is_exhaustive(s)))
+                    .then_some(CompilationError::MissingReturn {
+                        function,
+                        span: decl.span,
+                    })
+            })
+            .collect()
+    }
+
+    /// Check if the labels of a switch name every enumerator of one enum.
+    pub fn is_exhaustive(&self, stmt: &SwitchStmt<'_>) -> bool {
+        let labels: Vec<(Option<String>, &str)> = stmt
+            .cases
+            .iter()
+            .flat_map(|case| case.values.iter())
+            .map(|&label| enumerator(label))
+            .collect::<Option<_>>()
+            .unwrap_or_default();
+        !labels.is_empty()
+            && self.enums.iter().any(|cases| {
+                let named: Option<Vec<i64>> = labels
+                    .iter()
+                    .map(|(scope, name)| cases.value(scope.as_deref(), name))
+                    .collect();
+                named.is_some_and(|named| {
+                    cases
+                        .enumerators
+                        .iter()
+                        .all(|(_, value)| named.contains(value))
+                })
+            })
+    }
+}
+
+impl EnumCases {
+    /// Value of the enumerator a label names, if it names one of this enum.
+    fn value(&self, scope: Option<&str>, name: &str) -> Option<i64> {
+        let in_scope = scope
+            .is_none_or(|scope| self.name == scope || self.name.ends_with(&format!("::{}", scope)));
+        if !in_scope {
+            return None;
+        }
+        self.enumerators
+            .iter()
+            .find(|(enumerator, _)| enumerator == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// Scope and name of a label that may name an enumerator.
+fn enumerator<'ast>(label: &Expr<'ast>) -> Option<(Option<String>, &'ast str)> {
+    let Expr::Ident(ident) = label else {
+        return None;
+    };
+    let scope = ident.scope.map(|scope| {
+        scope
+            .segments
+            .iter()
+            .map(|segment| segment.name)
+            .collect::<Vec<_>>()
+            .join("::")
+    });
+    Some((scope, ident.ident.name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn missing(source: &str) -> Vec<String> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let mut checker = ReturnChecker::new();
+        checker.add_enum(
+            "game::Side",
+            [("Left".to_string(), 0), ("Right".to_string(), 1)],
+        );
+        checker
+            .check(&script)
+            .into_iter()
+            .map(|error| match error {
+                CompilationError::MissingReturn { function, .. } => function,
+                other => panic!("unexpected error {other}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reports_paths_reaching_the_end() {
+        let source = "
+            int fine(int x) { if (x > 0) return 1; else return 2; }
+            int partial(int x) { if (x > 0) return 1; }
+            void nothing() {}
+            class Player {
+                Player() {}
+                int hp() { }
+            }
+            namespace game { int empty() { int x = 1; } }
+        ";
+        assert_eq!(missing(source), ["partial", "Player::hp", "game::empty"]);
+    }
+
+    #[test]
+    fn diverging_statements_end_paths() {
+        let source = r#"
+            int thrown() { throw("no"); }
+            int forever() { for (;;) { } }
+            int escapes() { for (;;) { break; } }
+            int caught() { try { return 1; } catch { return 2; } }
+            int half_caught() { try { return 1; } catch { } }
+            int defaulted(int x) { switch (x) { case 1: return 1; default: return 2; } }
+        "#;
+        assert_eq!(missing(source), ["escapes", "half_caught"]);
+    }
+
+    #[test]
+    fn switches_naming_every_enumerator_are_exhaustive() {
+        let source = "
+            int all(game::Side s) { switch (s) { case Left: return -1; case game::Side::Right: return 1; } }
+            int some(game::Side s) { switch (s) { case Left: return -1; } }
+            int numbers(int x) { switch (x) { case 0: return 0; case 1: return 1; } }
+            int other(game::Side s) { switch (s) { case Other::Left: return -1; case Right: return 1; } }
+        ";
+        assert_eq!(missing(source), ["some", "numbers", "other"]);
+    }
+}
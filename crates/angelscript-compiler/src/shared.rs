@@ -0,0 +1,462 @@
+//! Shared script entities.
+//!
+//! Classes, interfaces and enums declared `shared` are the same type in every
+//! unit of a context. Their type hash depends only on the qualified name, so
+//! a handle created by one unit is accepted by any other unit that declares
+//! the type.
+//!
+//! Every unit must declare a shared entity identically. [`SharedRegistry`]
+//! records the declaration of the first unit and rejects units whose
+//! declaration differs, when they are registered.
+
+use angelscript_core::{CompilationError, Span, TypeHash};
+use angelscript_parser::ast::{
+    ClassMember, EnumDecl, Expr, FunctionParam, InterfaceDecl, InterfaceMember, Item, LiteralKind,
+    Script,
+};
+use rustc_hash::FxHashMap;
+use std::fmt;
+
+use crate::partial::MergedClass;
+
+/// Kind of a shared entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharedKind {
+    /// `shared class`
+    Class,
+    /// `shared interface`
+    Interface,
+    /// `shared enum`
+    Enum,
+}
+
+impl fmt::Display for SharedKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Class => write!(f, "class"),
+            Self::Interface => write!(f, "interface"),
+            Self::Enum => write!(f, "enum"),
+        }
+    }
+}
+
+/// A shared entity declared by a unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedDecl {
+    /// Qualified name.
+    pub name: String,
+    /// Type hash, identical in every unit.
+    pub type_hash: TypeHash,
+    /// Kind of entity.
+    pub kind: SharedKind,
+    /// Canonical form of the declaration, one entry per base or member.
+    pub signature: Vec<String>,
+    /// Location of the declaration.
+    pub span: Span,
+}
+
+impl SharedDecl {
+    fn new(name: String, kind: SharedKind, signature: Vec<String>, span: Span) -> Self {
+        Self {
+            type_hash: TypeHash::from_name(&name),
+            name,
+            kind,
+            signature,
+            span,
+        }
+    }
+}
+
+/// Collect the shared entities declared in `sections`.
+///
+/// `classes` are the classes of the sections with partial classes merged,
+/// as returned by [`merge_partial_classes`](crate::partial::merge_partial_classes).
+pub fn collect_shared(sections: &[&Script<'_>], classes: &[MergedClass<'_>]) -> Vec<SharedDecl> {
+    let mut decls: Vec<SharedDecl> = classes
+        .iter()
+        .filter(|class| class.modifiers.shared)
+        .map(|class| {
+            SharedDecl::new(
+                class.qualified_name.clone(),
+                SharedKind::Class,
+                class_signature(class),
+                class.span(),
+            )
+        })
+        .collect();
+
+    for script in sections {
+        collect_items(script.items(), "", &mut decls);
+    }
+    decls
+}
+
+fn collect_items(items: &[Item<'_>], namespace: &str, out: &mut Vec<SharedDecl>) {
+    for item in items {
+        match item {
+            Item::Interface(iface) if iface.modifiers.shared => out.push(SharedDecl::new(
+                qualify(namespace, iface.name.name),
+                SharedKind::Interface,
+                interface_signature(iface),
+                iface.span,
+            )),
+            Item::Enum(decl) if decl.modifiers.shared => out.push(SharedDecl::new(
+                qualify(namespace, decl.name.name),
+                SharedKind::Enum,
+                enum_signature(decl),
+                decl.span,
+            )),
+            Item::Namespace(ns) => {
+                let mut nested = namespace.to_string();
+                for segment in ns.path {
+                    nested = qualify(&nested, segment.name);
+                }
+                collect_items(ns.items, &nested, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn class_signature(class: &MergedClass<'_>) -> Vec<String> {
+    let mut signature: Vec<String> = class
+        .inheritance
+        .iter()
+        .map(|base| match base.scope {
+            Some(scope) if !scope.is_empty() => format!("base {}::{}", scope, base.ident.name),
+            _ => format!("base {}", base.ident.name),
+        })
+        .collect();
+
+    for member in &class.members {
+        signature.push(match member {
+            ClassMember::Field(field) => {
+                format!(
+                    "field {} {} {}",
+                    field.visibility, field.ty, field.name.name
+                )
+            }
+            ClassMember::VirtualProperty(prop) => {
+                format!("property {} {}", prop.ty, prop.name.name)
+            }
+            ClassMember::Method(func) => {
+                let ret = func.return_type.map(|r| r.to_string()).unwrap_or_default();
+                let name = if func.is_destructor {
+                    format!("~{}", func.name.name)
+                } else {
+                    func.name.name.to_string()
+                };
+                format!(
+                    "method {} {} {}({}){}",
+                    func.visibility,
+                    ret,
+                    name,
+                    params(func.params),
+                    if func.is_const { " const" } else { "" }
+                )
+            }
+            ClassMember::Funcdef(funcdef) => format!("funcdef {}", funcdef.name.name),
+        });
+    }
+    signature
+}
+
+fn interface_signature(iface: &InterfaceDecl<'_>) -> Vec<String> {
+    let mut signature: Vec<String> = iface
+        .bases
+        .iter()
+        .map(|base| format!("base {}", base.name))
+        .collect();
+
+    for member in iface.members {
+        signature.push(match member {
+            InterfaceMember::Method(method) => format!(
+                "method {} {}({}){}",
+                method.return_type,
+                method.name.name,
+                params(method.params),
+                if method.is_const { " const" } else { "" }
+            ),
+            InterfaceMember::VirtualProperty(prop) => {
+                format!("property {} {}", prop.ty, prop.name.name)
+            }
+        });
+    }
+    signature
+}
+
+/// Enumerator values are compared when they are integer literals; other
+/// initializers only need to be present in both declarations.
+fn enum_signature(decl: &EnumDecl<'_>) -> Vec<String> {
+    decl.enumerators
+        .iter()
+        .map(|e| match e.value {
+            None => e.name.name.to_string(),
+            Some(Expr::Literal(lit)) => match lit.kind {
+                LiteralKind::Int(value) => format!("{} = {}", e.name.name, value),
+                _ => format!("{} = ?", e.name.name),
+            },
+            Some(_) => format!("{} = ?", e.name.name),
+        })
+        .collect()
+}
+
+fn params(params: &[FunctionParam<'_>]) -> String {
+    params
+        .iter()
+        .map(|p| p.ty.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn qualify(namespace: &str, name: &str) -> String {
+    if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}::{}", namespace, name)
+    }
+}
+
+#[derive(Debug)]
+struct SharedEntry {
+    decl: SharedDecl,
+    users: usize,
+}
+
+/// The shared entities of a context and the units using them.
+#[derive(Debug, Default)]
+pub struct SharedRegistry {
+    entries: FxHashMap<TypeHash, SharedEntry>,
+}
+
+impl SharedRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register the shared entities declared by a unit.
+    ///
+    /// Entities already registered by another unit must have the same kind
+    /// and signature. Nothing is registered if any declaration conflicts.
+    pub fn register(&mut self, decls: &[SharedDecl]) -> Result<(), Vec<CompilationError>> {
+        self.replace(&[], decls)
+    }
+
+    /// Replace the entities `released` of a rebuilt unit with `decls`.
+    ///
+    /// Entities only the unit used may change. Nothing is released or
+    /// registered if any declaration conflicts, so the unit keeps its
+    /// previous entities.
+    pub fn replace(
+        &mut self,
+        released: &[TypeHash],
+        decls: &[SharedDecl],
+    ) -> Result<(), Vec<CompilationError>> {
+        let errors: Vec<CompilationError> = decls
+            .iter()
+            .filter_map(|decl| {
+                let entry = self.entries.get(&decl.type_hash)?;
+                if entry.users == 1 && released.contains(&decl.type_hash) {
+                    return None;
+                }
+                let existing = &entry.decl;
+                let message = if existing.kind != decl.kind {
+                    format!(
+                        "shared {} '{}' is declared as a {} in another unit",
+                        decl.kind, decl.name, existing.kind
+                    )
+                } else if existing.signature != decl.signature {
+                    format!(
+                        "shared {} '{}' does not match its declaration in another unit",
+                        decl.kind, decl.name
+                    )
+                } else {
+                    return None;
+                };
+                Some(CompilationError::Other {
+                    message,
+                    span: decl.span,
+                })
+            })
+            .collect();
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        self.release(released);
+        for decl in decls {
+            self.entries
+                .entry(decl.type_hash)
+                .or_insert_with(|| SharedEntry {
+                    decl: decl.clone(),
+                    users: 0,
+                })
+                .users += 1;
+        }
+        Ok(())
+    }
+
+    /// Release entities registered by a unit that is rebuilt or dropped.
+    ///
+    /// An entity is forgotten once no unit uses it, so its declaration may
+    /// change on the next registration.
+    pub fn release(&mut self, type_hashes: &[TypeHash]) {
+        for hash in type_hashes {
+            if let Some(entry) = self.entries.get_mut(hash) {
+                entry.users -= 1;
+                if entry.users == 0 {
+                    self.entries.remove(hash);
+                }
+            }
+        }
+    }
+
+    /// Get the registered declaration of a shared entity.
+    pub fn get(&self, type_hash: TypeHash) -> Option<&SharedDecl> {
+        self.entries.get(&type_hash).map(|entry| &entry.decl)
+    }
+
+    /// Number of units using a shared entity.
+    pub fn users(&self, type_hash: TypeHash) -> usize {
+        self.entries.get(&type_hash).map_or(0, |entry| entry.users)
+    }
+
+    /// Number of registered shared entities.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if no shared entities are registered.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::partial::merge_partial_classes;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn shared_decls(source: &str) -> Vec<SharedDecl> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let classes = merge_partial_classes(&[&script]).unwrap();
+        collect_shared(&[&script], &classes)
+    }
+
+    #[test]
+    fn collects_shared_entities() {
+        let decls = shared_decls(
+            "shared class Item { int id; } \
+             class Local { } \
+             namespace game { shared interface IUsable { void use(Item@ item); } } \
+             shared enum Rarity { Common, Rare = 5 }",
+        );
+
+        let names: Vec<_> = decls.iter().map(|d| (d.name.as_str(), d.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("Item", SharedKind::Class),
+                ("game::IUsable", SharedKind::Interface),
+                ("Rarity", SharedKind::Enum),
+            ]
+        );
+        assert_eq!(decls[0].type_hash, TypeHash::from_name("Item"));
+        assert_eq!(decls[2].signature, vec!["Common", "Rare = 5"]);
+    }
+
+    #[test]
+    fn identical_declarations_share_entry() {
+        let mut registry = SharedRegistry::new();
+        let first = shared_decls("shared class Item { int id; void use() { } }");
+        let second = shared_decls("shared class Item { int id; void use() { } }");
+
+        registry.register(&first).unwrap();
+        registry.register(&second).unwrap();
+
+        let hash = TypeHash::from_name("Item");
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.users(hash), 2);
+
+        registry.release(&[hash]);
+        assert_eq!(registry.users(hash), 1);
+        registry.release(&[hash]);
+        assert!(registry.is_empty());
+    }
+
+    #[test]
+    fn mismatched_declarations_are_rejected() {
+        let mut registry = SharedRegistry::new();
+        registry
+            .register(&shared_decls("shared class Item { int id; }"))
+            .unwrap();
+
+        let errors = registry
+            .register(&shared_decls("shared class Item { float id; }"))
+            .unwrap_err();
+        assert!(matches!(
+            &errors[0],
+            CompilationError::Other { message, .. }
+                if message == "shared class 'Item' does not match its declaration in another unit"
+        ));
+
+        let errors = registry
+            .register(&shared_decls("shared enum Item { A }"))
+            .unwrap_err();
+        assert!(matches!(
+            &errors[0],
+            CompilationError::Other { message, .. }
+                if message == "shared enum 'Item' is declared as a class in another unit"
+        ));
+
+        // Failed registrations leave the registry untouched
+        assert_eq!(registry.users(TypeHash::from_name("Item")), 1);
+    }
+
+    #[test]
+    fn replacing_keeps_entities_on_conflict() {
+        let mut registry = SharedRegistry::new();
+        let rarity = shared_decls("shared enum Rarity { Common }");
+        let item = shared_decls("shared class Item { int id; }");
+        let hashes = [rarity[0].type_hash, item[0].type_hash];
+        registry
+            .register(&[rarity[0].clone(), item[0].clone()])
+            .unwrap();
+        registry.register(&item).unwrap();
+
+        // `Item` is used by another unit, so it cannot change
+        let changed = shared_decls("shared enum Rarity { Common, Rare } shared class Item { }");
+        assert!(registry.replace(&hashes, &changed).is_err());
+        assert_eq!(registry.get(hashes[0]).unwrap().signature, ["Common"]);
+        assert_eq!(registry.users(hashes[1]), 2);
+
+        // Entities only the unit used may change
+        let rarity: Vec<_> = changed
+            .into_iter()
+            .filter(|decl| decl.kind == SharedKind::Enum)
+            .collect();
+        registry.replace(&hashes, &rarity).unwrap();
+        assert_eq!(
+            registry.get(hashes[0]).unwrap().signature,
+            ["Common", "Rare"]
+        );
+        assert_eq!(registry.users(hashes[1]), 1);
+    }
+
+    #[test]
+    fn released_entities_may_change() {
+        let mut registry = SharedRegistry::new();
+        let first = shared_decls("shared enum Rarity { Common }");
+        registry.register(&first).unwrap();
+        registry.release(&[first[0].type_hash]);
+
+        registry
+            .register(&shared_decls("shared enum Rarity { Common, Rare }"))
+            .unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+//! Instantiation of the templates a script uses.
+//!
+//! Every template type written in the script is instantiated in the unit's
+//! own instance registry, innermost arguments first, so the shared registry
+//! of the context is never modified:
+//!
+//! ```angelscript
+//! class Enemy {}
+//! array<array<Enemy@>> waves;
+//! // instantiates array<Enemy@>, then array<array<Enemy@>>
+//! ```
+//!
+//! Instances of the same template and arguments have the same hash in every
+//! unit, so the registries of linked units can be merged with
+//! [`SymbolRegistry::link_template_instances`].
+
+use angelscript_core::{CompilationError, DataType};
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{NamespaceDecl, Script, TypeBase, TypeExpr};
+use angelscript_registry::SymbolRegistry;
+
+use crate::layout::Types;
+
+/// Instantiate the templates used by `script` in `instances`, reporting
+/// types used as templates that are not and wrong argument counts.
+///
+/// Templates are looked up in `registry`.
+pub fn instantiate_templates(
+    script: &Script<'_>,
+    registry: &SymbolRegistry,
+    instances: &mut SymbolRegistry,
+) -> Vec<CompilationError> {
+    let mut pass = TemplatePass {
+        types: Types::collect(script, registry),
+        registry,
+        instances,
+        namespace: Vec::new(),
+        errors: Vec::new(),
+    };
+    pass.visit_script(script);
+    pass.errors
+}
+
+struct TemplatePass<'r, 'i> {
+    types: Types<'r>,
+    registry: &'r SymbolRegistry,
+    instances: &'i mut SymbolRegistry,
+    namespace: Vec<String>,
+    errors: Vec<CompilationError>,
+}
+
+impl TemplatePass<'_, '_> {
+    fn instantiate(&mut self, ty: &TypeExpr<'_>) {
+        for arg in ty.template_args {
+            self.instantiate(arg);
+        }
+        let TypeBase::Named(ident) = ty.base else {
+            return;
+        };
+        if ty.template_args.is_empty() {
+            return;
+        }
+
+        let template = self.types.named(ty.scope.as_ref(), ident, &self.namespace);
+        let expected = match self.registry.get(template) {
+            Some(entry) => match entry.as_class() {
+                Some(class) if class.is_template() => class.template_params.len(),
+                _ => {
+                    self.errors.push(CompilationError::NotATemplate {
+                        name: entry.qualified_name().to_string(),
+                        span: ty.span,
+                    });
+                    return;
+                }
+            },
+            None => {
+                if let Some(name) = self.types.script_name(template) {
+                    self.errors.push(CompilationError::NotATemplate {
+                        name,
+                        span: ty.span,
+                    });
+                }
+                // Unknown types are reported by the passes that use them
+                return;
+            }
+        };
+        if expected != ty.template_args.len() {
+            self.errors
+                .push(CompilationError::TemplateArgCountMismatch {
+                    expected,
+                    got: ty.template_args.len(),
+                    span: ty.span,
+                });
+            return;
+        }
+
+        let args: Vec<DataType> = ty
+            .template_args
+            .iter()
+            .map(|arg| self.types.data_type(arg, &self.namespace))
+            .collect();
+        let types = &self.types;
+        // Unknown argument types are reported by the passes that use them
+        let _ = self
+            .instances
+            .instantiate_template_from(self.registry, template, &args, &|hash| {
+                types.script_name(hash)
+            });
+    }
+}
+
+impl<'ast> Visitor<'ast> for TemplatePass<'_, '_> {
+    fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+        let depth = self.namespace.len();
+        self.namespace
+            .extend(namespace.path.iter().map(|s| s.name.to_string()));
+        visitor::walk_namespace_decl(self, namespace);
+        self.namespace.truncate(depth);
+    }
+
+    fn visit_type_expr(&mut self, ty: &TypeExpr<'ast>) {
+        self.instantiate(ty);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::entries::ClassEntry;
+    use angelscript_core::{TypeHash, TypeKind, primitives};
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+        let mut array = ClassEntry::ffi("array", TypeKind::reference());
+        array.template_params = vec![TypeHash::from_name("array::T")];
+        registry.register_type(array.into()).unwrap();
+        registry
+    }
+
+    fn instantiate(source: &str) -> (SymbolRegistry, Vec<CompilationError>) {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let mut instances = SymbolRegistry::new();
+        let errors = instantiate_templates(&script, &registry(), &mut instances);
+        (instances, errors)
+    }
+
+    #[test]
+    fn nested_instances_in_the_unit_registry() {
+        let (instances, errors) = instantiate(
+            "
+            namespace game { class Enemy {} }
+            namespace game {
+                array<array<Enemy@>> waves;
+                void spawn(array<int>@ counts) { }
+            }
+            ",
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+
+        let array = TypeHash::from_name("array");
+        let enemies = DataType::with_handle(TypeHash::from_name("game::Enemy"), false);
+        let inner = instances.template_instance(array, &[enemies]).unwrap();
+        let names: Vec<_> = [
+            inner,
+            instances
+                .template_instance(array, &[DataType::simple(inner)])
+                .unwrap(),
+            instances
+                .template_instance(array, &[DataType::simple(primitives::INT32)])
+                .unwrap(),
+        ]
+        .iter()
+        .map(|&hash| instances.get(hash).unwrap().qualified_name().to_string())
+        .collect();
+        assert_eq!(
+            names,
+            [
+                "array<game::Enemy@>",
+                "array<array<game::Enemy@>>",
+                "array<int>"
+            ]
+        );
+    }
+
+    #[test]
+    fn template_arguments_are_checked() {
+        let (instances, errors) = instantiate("class Foo {} array<int, int> pairs; Foo<int> foo;");
+        assert_eq!(instances.classes().count(), 0);
+        assert!(matches!(
+            errors[0],
+            CompilationError::TemplateArgCountMismatch {
+                expected: 1,
+                got: 2,
+                ..
+            }
+        ));
+        assert!(matches!(&errors[1], CompilationError::NotATemplate { name, .. } if name == "Foo"));
+        assert_eq!(errors.len(), 2);
+    }
+}
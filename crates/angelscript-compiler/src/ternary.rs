@@ -0,0 +1,496 @@
+//! Conditional expressions.
+//!
+//! The branches of `cond ? a : b` are unified to a single result type:
+//!
+//! ```text
+//! int x = c ? 1 : 2.5;        // else converted to int, as upstream does
+//! Obj@ o = c ? obj : null;    // null takes the type of the other handle
+//! Base@ b = c ? derived : base;  // derived handle converted to Base@
+//! Base@ s = c ? circle : square; // both converted to their common base
+//! ```
+//!
+//! When both branches are lvalues of the same type, the conditional is an
+//! lvalue too and can be assigned through. Without references on the stack,
+//! the assignment is compiled as a store in each branch:
+//!
+//! ```text
+//! (c ? a : b) = v;  // v; c; JUMP_IF_FALSE else; SET_LOCAL a; JUMP end; else: SET_LOCAL b; end:
+//! ```
+
+use angelscript_core::{CompilationError, DataType, Span, TypeHash, primitives};
+use angelscript_registry::SymbolRegistry;
+
+use crate::bytecode::{BytecodeChunk, ConstantPool, OpCode};
+use crate::cast::RefCast;
+use crate::overload::{Conversion, rank_conversion};
+
+/// A branch of a conditional expression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TernaryArm {
+    /// The type of the branch expression.
+    pub ty: DataType,
+    /// Whether the branch can be assigned to.
+    pub is_lvalue: bool,
+}
+
+impl TernaryArm {
+    /// A branch producing a temporary value.
+    pub fn value(ty: DataType) -> Self {
+        Self {
+            ty,
+            is_lvalue: false,
+        }
+    }
+
+    /// A branch naming a variable, property or other assignable location.
+    pub fn lvalue(ty: DataType) -> Self {
+        Self {
+            ty,
+            is_lvalue: true,
+        }
+    }
+}
+
+/// Conversion of a branch's value to the result type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArmConversion {
+    /// The value already has the result type.
+    None,
+    /// A primitive conversion (`CAST`).
+    Numeric,
+    /// A handle conversion to a base class or interface.
+    Handle(RefCast),
+}
+
+/// A resolved conditional expression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ternary {
+    /// The type of the whole expression.
+    pub result: DataType,
+    /// Conversion of the `then` branch.
+    pub then_conversion: ArmConversion,
+    /// Conversion of the `else` branch.
+    pub else_conversion: ArmConversion,
+    /// Whether the expression can be assigned to.
+    pub is_lvalue: bool,
+}
+
+impl Ternary {
+    /// Unify the branches of a conditional expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if both branches are `null`, if `null` is paired
+    /// with a type that has no handles, or if neither branch converts to
+    /// the other's type and handles share no base.
+    pub fn resolve(
+        registry: &SymbolRegistry,
+        then_arm: TernaryArm,
+        else_arm: TernaryArm,
+        span: Span,
+    ) -> Result<Self, CompilationError> {
+        let (then_ty, else_ty) = (then_arm.ty, else_arm.ty);
+        let incompatible = |reason: &str| CompilationError::IncompatibleBranches {
+            then_type: type_name(registry, &then_ty),
+            else_type: type_name(registry, &else_ty),
+            reason: reason.to_string(),
+            span,
+        };
+        let same = |result: DataType| Self {
+            result,
+            then_conversion: ArmConversion::None,
+            else_conversion: ArmConversion::None,
+            is_lvalue: false,
+        };
+
+        match (then_ty.is_null(), else_ty.is_null()) {
+            (true, true) => return Err(incompatible("both branches are null")),
+            (true, false) | (false, true) => {
+                let other = if then_ty.is_null() { else_ty } else { then_ty };
+                if !has_handles(registry, &other) {
+                    return Err(incompatible("null is only a handle"));
+                }
+                return Ok(same(DataType::with_handle(
+                    other.type_hash,
+                    other.is_handle_to_const,
+                )));
+            }
+            (false, false) => {}
+        }
+
+        if then_ty.type_hash == else_ty.type_hash && then_ty.is_handle == else_ty.is_handle {
+            let mut result = then_ty.without_ref();
+            result.is_const |= else_ty.is_const;
+            result.is_handle_to_const |= else_ty.is_handle_to_const;
+            return Ok(Self {
+                is_lvalue: then_arm.is_lvalue
+                    && else_arm.is_lvalue
+                    && then_ty.is_const == else_ty.is_const
+                    && then_ty.is_handle_to_const == else_ty.is_handle_to_const,
+                ..same(result)
+            });
+        }
+
+        if then_ty.is_handle || else_ty.is_handle {
+            return unify_handles(registry, &then_ty, &else_ty, span)
+                .ok_or_else(|| incompatible("the handles share no base type"));
+        }
+
+        // Prefer a widening of either branch, then convert to the `then` type
+        let widens = |to: &DataType, from: &DataType| {
+            matches!(
+                rank_conversion(&to.without_const(), &from.without_const()),
+                Some(Conversion::Promotion | Conversion::EnumToInt)
+            )
+        };
+        let converts = |from: &DataType| {
+            if from.is_enum {
+                ArmConversion::None
+            } else {
+                ArmConversion::Numeric
+            }
+        };
+        if widens(&else_ty, &then_ty) {
+            Ok(Self {
+                then_conversion: converts(&then_ty),
+                ..same(else_ty.without_ref())
+            })
+        } else if rank_conversion(&then_ty.without_const(), &else_ty.without_const()).is_some() {
+            Ok(Self {
+                else_conversion: converts(&else_ty),
+                ..same(then_ty.without_ref())
+            })
+        } else {
+            Err(incompatible("neither branch converts to the other"))
+        }
+    }
+
+    /// Emit the expression as a value.
+    pub fn emit(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        line: u32,
+        emit_condition: impl FnOnce(&mut BytecodeChunk, &mut ConstantPool),
+        emit_then: impl FnOnce(&mut BytecodeChunk, &mut ConstantPool),
+        emit_else: impl FnOnce(&mut BytecodeChunk, &mut ConstantPool),
+    ) {
+        emit_condition(chunk, constants);
+        let to_else = chunk.emit_jump(OpCode::JumpIfFalse, line);
+        emit_then(chunk, constants);
+        self.emit_conversion(self.then_conversion, chunk, constants, line);
+        let to_end = chunk.emit_jump(OpCode::Jump, line);
+        chunk.patch_jump(to_else);
+        emit_else(chunk, constants);
+        self.emit_conversion(self.else_conversion, chunk, constants, line);
+        chunk.patch_jump(to_end);
+    }
+
+    /// Emit an assignment through the expression, with the assigned value on
+    /// top of the stack. Each store leaves the value on the stack, like an
+    /// assignment does.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the expression is not an lvalue.
+    pub fn emit_store(
+        &self,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        line: u32,
+        emit_condition: impl FnOnce(&mut BytecodeChunk, &mut ConstantPool),
+        store_then: impl FnOnce(&mut BytecodeChunk, &mut ConstantPool),
+        store_else: impl FnOnce(&mut BytecodeChunk, &mut ConstantPool),
+    ) {
+        assert!(self.is_lvalue, "assignment to a conditional rvalue");
+        emit_condition(chunk, constants);
+        let to_else = chunk.emit_jump(OpCode::JumpIfFalse, line);
+        store_then(chunk, constants);
+        let to_end = chunk.emit_jump(OpCode::Jump, line);
+        chunk.patch_jump(to_else);
+        store_else(chunk, constants);
+        chunk.patch_jump(to_end);
+    }
+
+    fn emit_conversion(
+        &self,
+        conversion: ArmConversion,
+        chunk: &mut BytecodeChunk,
+        constants: &mut ConstantPool,
+        line: u32,
+    ) {
+        match conversion {
+            ArmConversion::None => {}
+            ArmConversion::Numeric => {
+                let index = constants.add_type_hash(self.result.type_hash);
+                chunk.write_op(OpCode::Cast, line);
+                chunk.write_u16(index as u16, line);
+            }
+            ArmConversion::Handle(cast) => {
+                cast.emit(chunk, constants, self.result.type_hash, line);
+            }
+        }
+    }
+}
+
+/// Unify two handles: to one of the types if the other converts to it
+/// implicitly, or else to their nearest common base class.
+fn unify_handles(
+    registry: &SymbolRegistry,
+    then_ty: &DataType,
+    else_ty: &DataType,
+    span: Span,
+) -> Option<Ternary> {
+    if !has_handles(registry, then_ty) || !has_handles(registry, else_ty) {
+        return None;
+    }
+    let handle = |hash: TypeHash| {
+        DataType::with_handle(
+            hash,
+            then_ty.is_handle_to_const || else_ty.is_handle_to_const,
+        )
+    };
+    let implicit = |from: &DataType, to: TypeHash| {
+        RefCast::resolve(registry, from, to, true, span)
+            .ok()
+            .map(|cast| match cast {
+                RefCast::Identity => ArmConversion::None,
+                cast => ArmConversion::Handle(cast),
+            })
+    };
+
+    if let Some(conversion) = implicit(else_ty, then_ty.type_hash) {
+        return Some(Ternary {
+            result: handle(then_ty.type_hash),
+            then_conversion: ArmConversion::None,
+            else_conversion: conversion,
+            is_lvalue: false,
+        });
+    }
+    if let Some(conversion) = implicit(then_ty, else_ty.type_hash) {
+        return Some(Ternary {
+            result: handle(else_ty.type_hash),
+            then_conversion: conversion,
+            else_conversion: ArmConversion::None,
+            is_lvalue: false,
+        });
+    }
+
+    let else_bases = registry.base_class_chain(else_ty.type_hash);
+    let common = registry
+        .base_class_chain(then_ty.type_hash)
+        .into_iter()
+        .find(|base| else_bases.iter().any(|b| b.type_hash == base.type_hash))?;
+    Some(Ternary {
+        result: handle(common.type_hash),
+        then_conversion: ArmConversion::Handle(RefCast::ToBase),
+        else_conversion: ArmConversion::Handle(RefCast::ToBase),
+        is_lvalue: false,
+    })
+}
+
+/// Whether values of the type can be held by handle.
+fn has_handles(registry: &SymbolRegistry, ty: &DataType) -> bool {
+    ty.is_handle
+        || registry.get(ty.type_hash).is_some_and(|entry| {
+            entry.is_interface()
+                || entry.is_funcdef()
+                || entry.as_class().is_some_and(|class| !class.is_value_type())
+        })
+}
+
+fn type_name(registry: &SymbolRegistry, ty: &DataType) -> String {
+    if ty.type_hash == primitives::NULL {
+        return "null".to_string();
+    }
+    let name = registry.get(ty.type_hash).map_or_else(
+        || ty.type_hash.to_string(),
+        |entry| entry.qualified_name().to_string(),
+    );
+    if ty.is_handle {
+        format!("{}@", name)
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{ClassEntry, TypeKind};
+
+    fn hash(name: &str) -> TypeHash {
+        TypeHash::from_name(name)
+    }
+
+    fn handle(name: &str) -> TernaryArm {
+        TernaryArm::value(DataType::with_handle(hash(name), false))
+    }
+
+    fn null() -> TernaryArm {
+        TernaryArm::value(DataType::null_literal())
+    }
+
+    fn simple(ty: TypeHash) -> TernaryArm {
+        TernaryArm::value(DataType::simple(ty))
+    }
+
+    /// `Circle : Shape`, `Square : Shape`, and the value type `Vec2`.
+    fn registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+        let shape = ClassEntry::ffi("Shape", TypeKind::reference());
+        let circle = ClassEntry::ffi("Circle", TypeKind::reference()).with_base(shape.type_hash);
+        let square = ClassEntry::ffi("Square", TypeKind::reference()).with_base(shape.type_hash);
+        let vec2 = ClassEntry::ffi("Vec2", TypeKind::value::<[f32; 2]>());
+        for class in [shape, circle, square, vec2] {
+            registry.register_type(class.into()).unwrap();
+        }
+        registry
+    }
+
+    #[test]
+    fn null_takes_the_handle_type() {
+        let registry = registry();
+        let span = Span::new(1, 1, 1);
+        let shape = DataType::with_handle(hash("Shape"), false);
+
+        for (then_arm, else_arm) in [(handle("Shape"), null()), (null(), handle("Shape"))] {
+            let ternary = Ternary::resolve(&registry, then_arm, else_arm, span).unwrap();
+            assert_eq!(ternary.result, shape);
+            assert!(!ternary.is_lvalue);
+        }
+        // A reference type written without `@` still unifies with null
+        let object = simple(hash("Shape"));
+        assert_eq!(
+            Ternary::resolve(&registry, object, null(), span).map(|t| t.result),
+            Ok(shape)
+        );
+
+        let err = Ternary::resolve(&registry, null(), null(), span).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "at 1:1: incompatible branches 'null' and 'null' in a conditional expression: both branches are null"
+        );
+        assert!(Ternary::resolve(&registry, simple(primitives::INT32), null(), span).is_err());
+        assert!(Ternary::resolve(&registry, simple(hash("Vec2")), null(), span).is_err());
+    }
+
+    #[test]
+    fn handles_unify_to_a_common_base() {
+        let registry = registry();
+        let span = Span::new(1, 1, 1);
+
+        let derived = Ternary::resolve(&registry, handle("Shape"), handle("Circle"), span).unwrap();
+        assert_eq!(derived.result, DataType::with_handle(hash("Shape"), false));
+        assert_eq!(derived.then_conversion, ArmConversion::None);
+        assert_eq!(
+            derived.else_conversion,
+            ArmConversion::Handle(RefCast::ToBase)
+        );
+
+        let siblings =
+            Ternary::resolve(&registry, handle("Circle"), handle("Square"), span).unwrap();
+        assert_eq!(siblings.result, DataType::with_handle(hash("Shape"), false));
+        assert_eq!(
+            siblings.then_conversion,
+            ArmConversion::Handle(RefCast::ToBase)
+        );
+
+        let err = Ternary::resolve(&registry, handle("Circle"), simple(primitives::INT32), span)
+            .unwrap_err();
+        assert!(matches!(err, CompilationError::IncompatibleBranches { .. }));
+    }
+
+    #[test]
+    fn values_convert_and_lvalues_assign() {
+        let registry = registry();
+        let span = Span::new(1, 1, 1);
+        let int = DataType::simple(primitives::INT32);
+
+        let widened = Ternary::resolve(
+            &registry,
+            simple(primitives::INT32),
+            simple(primitives::INT64),
+            span,
+        )
+        .unwrap();
+        assert_eq!(widened.result, DataType::simple(primitives::INT64));
+        assert_eq!(widened.then_conversion, ArmConversion::Numeric);
+
+        let narrowed = Ternary::resolve(
+            &registry,
+            simple(primitives::INT32),
+            simple(primitives::DOUBLE),
+            span,
+        )
+        .unwrap();
+        assert_eq!(narrowed.result, int);
+        assert_eq!(narrowed.else_conversion, ArmConversion::Numeric);
+
+        let lvalue = Ternary::resolve(
+            &registry,
+            TernaryArm::lvalue(int),
+            TernaryArm::lvalue(int),
+            span,
+        )
+        .unwrap();
+        assert!(lvalue.is_lvalue);
+        let mixed = Ternary::resolve(
+            &registry,
+            TernaryArm::lvalue(int),
+            simple(primitives::INT32),
+            span,
+        )
+        .unwrap();
+        assert!(!mixed.is_lvalue);
+
+        // (c ? a : b) = 5;
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        chunk.write_op(OpCode::PushOne, 1);
+        lvalue.emit_store(
+            &mut chunk,
+            &mut constants,
+            1,
+            |chunk, _| chunk.emit_get_local(0, 1),
+            |chunk, _| chunk.emit_set_local(1, 1),
+            |chunk, _| chunk.emit_set_local(2, 1),
+        );
+        chunk.assert_opcodes(&[
+            OpCode::PushOne,
+            OpCode::GetLocal,
+            OpCode::JumpIfFalse,
+            OpCode::SetLocal,
+            OpCode::Jump,
+            OpCode::SetLocal,
+        ]);
+    }
+
+    #[test]
+    fn emits_branch_conversions() {
+        let registry = registry();
+        let span = Span::new(1, 1, 1);
+        let ternary =
+            Ternary::resolve(&registry, handle("Circle"), handle("Square"), span).unwrap();
+
+        let mut chunk = BytecodeChunk::new();
+        let mut constants = ConstantPool::new();
+        ternary.emit(
+            &mut chunk,
+            &mut constants,
+            1,
+            |chunk, _| chunk.write_op(OpCode::PushTrue, 1),
+            |chunk, _| chunk.emit_get_local(0, 1),
+            |chunk, _| chunk.emit_get_local(1, 1),
+        );
+        chunk.assert_opcodes(&[
+            OpCode::PushTrue,
+            OpCode::JumpIfFalse,
+            OpCode::GetLocal,
+            OpCode::DerivedToBase,
+            OpCode::Jump,
+            OpCode::GetLocal,
+            OpCode::DerivedToBase,
+        ]);
+    }
+}
@@ -0,0 +1,1055 @@
+//! Checks of expressions whose types the declarations in scope tell.
+//!
+//! Function bodies are not compiled yet, so this pass types the expressions
+//! it can from the script's declarations and the registry — literals,
+//! locals, parameters, fields, globals, enum values, calls of functions
+//! and constructors, string concatenations, operators overloaded by the
+//! registry or by the script's extension operators, reference casts,
+//! delegates of registered funcdefs, elements of registered containers, and
+//! conditionals — and runs the checks that need those
+//! types:
+//!
+//! ```angelscript
+//! void spawn() { }
+//! string opMul(const string &in s, int n) { ... }
+//!
+//! void main() {
+//!     auto a = null;      // error: cannot deduce the type of 'a'
+//!     auto b = spawn();   // error: cannot deduce the type of 'b'
+//!     auto c = 1.5f;      // float
+//!     auto d = "ab" * 3;  // string
+//!     auto e = d + 1;     // string
+//!     cast<int>(d);       // error: not a handle cast
+//! }
+//! ```
+//!
+//! Expressions of unknown type are skipped rather than reported; the passes
+//! that resolve names report what is undeclared.
+
+use angelscript_core::{
+    CompilationError, DataType, FunctionDef, FunctionTraits, Operator, Param, RefModifier,
+    TypeHash, Visibility, primitives,
+};
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{
+    BinaryExpr, BinaryOp, Block, CallExpr, CastExpr, ClassDecl, ClassMember, Expr, ForStmt,
+    ForeachStmt, FunctionDecl, GlobalVarDecl, IdentExpr, IndexExpr, Item, LambdaExpr, LiteralKind,
+    MemberAccess, NamespaceDecl, Script, TernaryExpr, VarDeclStmt,
+};
+use angelscript_registry::SymbolRegistry;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::access::candidate_names;
+use crate::auto;
+use crate::cast::RefCast;
+use crate::concat::{Concat, ConcatOperand, concat_operands};
+use crate::delegate::Delegate;
+use crate::foreach::ForeachProtocol;
+use crate::index::IndexAccess;
+use crate::init_list::{InitList, InitShape};
+use crate::layout::{Types, lower_globals};
+use crate::operators;
+use crate::ternary::{Ternary, TernaryArm};
+
+/// Check the typed expressions of `script`.
+///
+/// String literals have type `string_type`, if given.
+pub fn check_types(
+    script: &Script<'_>,
+    registry: &SymbolRegistry,
+    string_type: Option<TypeHash>,
+) -> Vec<CompilationError> {
+    let types = Types::collect(script, registry);
+    let mut declarations = Declarations::default();
+    declarations.collect(script.items(), &[], &types, registry);
+
+    let mut pass = TypePass {
+        registry,
+        string_type,
+        globals: lower_globals(script, registry)
+            .into_iter()
+            .filter(|global| !global.data_type.is_void())
+            .map(|global| (global.name, global.data_type))
+            .collect(),
+        functions: declarations.functions,
+        enums: declarations.enums,
+        extensions: declarations.extensions,
+        types,
+        namespace: Vec::new(),
+        class: None,
+        scopes: Vec::new(),
+        errors: Vec::new(),
+    };
+    pass.visit_script(script);
+    pass.errors
+}
+
+/// Declarations of a script the pass looks up by name.
+#[derive(Default)]
+struct Declarations {
+    /// Return types of the overloads of the global functions, by qualified
+    /// name.
+    functions: FxHashMap<String, Vec<DataType>>,
+    /// Qualified names of the enums.
+    enums: FxHashSet<String>,
+    /// Extension operators the script adds to registered types.
+    extensions: Vec<(Operator, FunctionDef)>,
+}
+
+impl Declarations {
+    fn collect(
+        &mut self,
+        items: &[Item<'_>],
+        namespace: &[String],
+        types: &Types<'_>,
+        registry: &SymbolRegistry,
+    ) {
+        for item in items {
+            match item {
+                Item::Function(func) => {
+                    let returns = func
+                        .return_type
+                        .map_or_else(DataType::void, |ret| types.data_type(&ret.ty, namespace));
+                    if namespace.is_empty() && Operator::from_method_name(func.name.name).is_some()
+                    {
+                        let def = operator_def(func, returns, types);
+                        if let Some(op) = operators::extension_operator(&def, registry) {
+                            self.extensions.push((op, def));
+                        }
+                    }
+                    let mut path = namespace.to_vec();
+                    path.push(func.name.name.to_string());
+                    self.functions
+                        .entry(path.join("::"))
+                        .or_default()
+                        .push(returns);
+                }
+                Item::Enum(decl) => {
+                    let mut path = namespace.to_vec();
+                    path.push(decl.name.name.to_string());
+                    self.enums.insert(path.join("::"));
+                }
+                Item::Namespace(ns) => {
+                    let mut nested = namespace.to_vec();
+                    nested.extend(ns.path.iter().map(|s| s.name.to_string()));
+                    self.collect(ns.items, &nested, types, registry);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The definition of the global function `func`, to classify it as an
+/// operator.
+fn operator_def(func: &FunctionDecl<'_>, returns: DataType, types: &Types<'_>) -> FunctionDef {
+    let params: Vec<Param> = func
+        .params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            let name = param
+                .name
+                .map_or_else(|| format!("p{}", i), |n| n.name.to_string());
+            Param::new(name, types.data_type(&param.ty.ty, &[]))
+        })
+        .collect();
+    let hashes: Vec<TypeHash> = params.iter().map(|p| p.data_type.type_hash).collect();
+    FunctionDef::new(
+        TypeHash::from_function(func.name.name, &hashes),
+        func.name.name.to_string(),
+        Vec::new(),
+        params,
+        returns,
+        None,
+        FunctionTraits::default(),
+        false,
+        Visibility::Public,
+    )
+}
+
+/// A variable in scope, with its type if known.
+type Local = (String, Option<DataType>);
+
+struct TypePass<'r> {
+    registry: &'r SymbolRegistry,
+    string_type: Option<TypeHash>,
+    /// Types of the global variables, by qualified name.
+    globals: FxHashMap<String, DataType>,
+    /// Return types of the overloads of the global functions.
+    functions: FxHashMap<String, Vec<DataType>>,
+    /// Qualified names of the enums declared by the script.
+    enums: FxHashSet<String>,
+    /// Extension operators declared by the script.
+    extensions: Vec<(Operator, FunctionDef)>,
+    types: Types<'r>,
+    /// Namespace the visited code is declared in.
+    namespace: Vec<String>,
+    /// Members of the enclosing class, with `this`; methods are untyped.
+    class: Option<Vec<Local>>,
+    /// Local scopes of the function being checked (innermost last).
+    scopes: Vec<Vec<Local>>,
+    errors: Vec<CompilationError>,
+}
+
+impl TypePass<'_> {
+    /// The type of `expr`, if the declarations in scope tell it.
+    fn type_of(&self, expr: &Expr<'_>) -> Option<DataType> {
+        match expr {
+            Expr::Literal(literal) => Some(match literal.kind {
+                LiteralKind::Int(_) => DataType::simple(primitives::INT32),
+                LiteralKind::Float(_) => DataType::simple(primitives::FLOAT),
+                LiteralKind::Double(_) => DataType::simple(primitives::DOUBLE),
+                LiteralKind::Bool(_) => DataType::simple(primitives::BOOL),
+                LiteralKind::String(_) => DataType::simple(self.string_type?),
+                LiteralKind::Null => DataType::null_literal(),
+            }),
+            Expr::Ident(ident) => self.variable(ident),
+            Expr::Paren(paren) => self.type_of(paren.expr),
+            Expr::Binary(binary) => self.binary(binary),
+            Expr::Index(index) => {
+                let access = self.index_access(index)?.ok()?;
+                let element = access.reference.or(access.getter)?;
+                let element = self.registry.get_function(element)?.def.return_type;
+                Some(DataType {
+                    ref_modifier: RefModifier::None,
+                    ..element
+                })
+            }
+            Expr::Ternary(ternary) => Some(self.ternary(ternary)?.ok()?.result),
+            Expr::Cast(cast) => {
+                let to = self.types.data_type(&cast.target_type, &self.namespace);
+                self.is_type(to.type_hash)
+                    .then(|| DataType::with_handle(to.type_hash, false))
+            }
+            Expr::Call(call) => match call.callee {
+                Expr::Ident(callee) => self.call(callee),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The type of the variable or enum value `ident` names.
+    fn variable(&self, ident: &IdentExpr<'_>) -> Option<DataType> {
+        let name = ident.ident.name;
+        if ident.scope.is_none()
+            && let Some(ty) = self.local(name)
+        {
+            return ty;
+        }
+
+        if let Some(scope) = ident.scope.as_ref().filter(|s| !s.segments.is_empty()) {
+            let path: Vec<&str> = scope.segments.iter().map(|s| s.name).collect();
+            let path = path.join("::");
+            let is_enum = candidate_names(&self.namespace, None, &path)
+                .into_iter()
+                .find(|name| {
+                    self.enums.contains(name)
+                        || self.registry.get_by_name(name).is_some_and(|e| e.is_enum())
+                });
+            if let Some(name) = is_enum {
+                let hash = self
+                    .registry
+                    .get_by_name(&name)
+                    .map_or_else(|| TypeHash::from_name(&name), |e| e.type_hash());
+                return Some(DataType {
+                    is_enum: true,
+                    ..DataType::simple(hash)
+                });
+            }
+        }
+
+        candidate_names(&self.namespace, ident.scope.as_ref(), name)
+            .iter()
+            .find_map(|name| self.globals.get(name).copied())
+    }
+
+    /// The type of the binary expression `expr`.
+    ///
+    /// Comparisons and logical operators give `bool`; other operators are
+    /// typed when they concatenate strings or an overload of the operand
+    /// types implements them.
+    fn binary(&self, expr: &BinaryExpr<'_>) -> Option<DataType> {
+        if expr.op == BinaryOp::Add
+            && let Some(string_type) = self.string_type
+            && let Some(concat) = self.concat(&Expr::Binary(expr), string_type)
+        {
+            return Some(concat);
+        }
+
+        let op = match expr.op {
+            BinaryOp::NullCoalesce => return None,
+            BinaryOp::LogicalOr
+            | BinaryOp::LogicalAnd
+            | BinaryOp::LogicalXor
+            | BinaryOp::Equal
+            | BinaryOp::NotEqual
+            | BinaryOp::Is
+            | BinaryOp::NotIs
+            | BinaryOp::Less
+            | BinaryOp::LessEqual
+            | BinaryOp::Greater
+            | BinaryOp::GreaterEqual => return Some(DataType::simple(primitives::BOOL)),
+            BinaryOp::BitwiseOr => Operator::Or,
+            BinaryOp::BitwiseXor => Operator::Xor,
+            BinaryOp::BitwiseAnd => Operator::And,
+            BinaryOp::ShiftLeft => Operator::Shl,
+            BinaryOp::ShiftRight => Operator::Shr,
+            BinaryOp::ShiftRightUnsigned => Operator::Ushr,
+            BinaryOp::Add => Operator::Add,
+            BinaryOp::Sub => Operator::Sub,
+            BinaryOp::Mul => Operator::Mul,
+            BinaryOp::Div => Operator::Div,
+            BinaryOp::Mod => Operator::Mod,
+            BinaryOp::Pow => Operator::Pow,
+        };
+        let left = self.type_of(expr.left)?.type_hash;
+        let right = self.type_of(expr.right)?.type_hash;
+
+        // Registered overloads come first; the script's extensions only
+        // apply where the registry has none
+        if let Some(found) = operators::resolve_binary(self.registry, op, left, right) {
+            return self
+                .registry
+                .get_function(found.func_hash)
+                .map(|function| function.def.return_type);
+        }
+        self.extensions
+            .iter()
+            .find(|(ext, def)| {
+                *ext == op
+                    && def.params[0].data_type.type_hash == left
+                    && def.params[1].data_type.type_hash == right
+            })
+            .map(|(_, def)| def.return_type)
+    }
+
+    /// The type of the chain of `+` in `expr`, if it concatenates strings
+    /// and primitives.
+    fn concat(&self, expr: &Expr<'_>, string_type: TypeHash) -> Option<DataType> {
+        let operands = concat_operands(expr)
+            .into_iter()
+            .map(|operand| Some(ConcatOperand::of(operand, self.type_of(operand)?)))
+            .collect::<Option<Vec<_>>>()?;
+        Concat::resolve(&operands, string_type).map(|_| DataType::simple(string_type))
+    }
+
+    /// The type of the local, parameter or member `name`, if it names one.
+    fn local(&self, name: &str) -> Option<Option<DataType>> {
+        self.scopes
+            .iter()
+            .rev()
+            .chain(self.class.iter())
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(local, _)| local == name)
+            .map(|(_, ty)| *ty)
+    }
+
+    /// The type of a call of the function or constructor `callee`.
+    fn call(&self, callee: &IdentExpr<'_>) -> Option<DataType> {
+        if callee.scope.is_none() && self.local(callee.ident.name).is_some() {
+            // Methods and funcdef handles are not typed yet
+            return None;
+        }
+        for name in candidate_names(&self.namespace, callee.scope.as_ref(), callee.ident.name) {
+            if let Some(returns) = self.functions.get(&name) {
+                return same(returns.iter().copied());
+            }
+            if let Some(overloads) = self.registry.get_function_overloads(&name) {
+                return same(
+                    overloads
+                        .iter()
+                        .filter_map(|&hash| self.registry.get_function(hash))
+                        .map(|function| function.def.return_type),
+                );
+            }
+        }
+
+        // A constructor call creates a value of the type, and a funcdef
+        // call a delegate
+        let hash = self
+            .types
+            .named(callee.scope.as_ref(), callee.ident, &self.namespace);
+        if self.registry.get(hash).is_some_and(|e| e.is_funcdef()) {
+            return Some(DataType::with_handle(hash, false));
+        }
+        self.is_type(hash).then(|| DataType::simple(hash))
+    }
+
+    /// The delegate `call` creates, if it binds a method of a registered
+    /// class for a registered funcdef.
+    fn delegate(&self, call: &CallExpr<'_>) -> Option<Result<Delegate, CompilationError>> {
+        let (Expr::Ident(callee), [arg]) = (call.callee, call.args) else {
+            return None;
+        };
+        let Expr::Member(member) = arg.value else {
+            return None;
+        };
+        let MemberAccess::Field(method) = member.member else {
+            return None;
+        };
+        if callee.scope.is_none() && self.local(callee.ident.name).is_some() {
+            return None;
+        }
+        let funcdef = self
+            .types
+            .named(callee.scope.as_ref(), callee.ident, &self.namespace);
+        if !self.registry.get(funcdef).is_some_and(|e| e.is_funcdef()) {
+            return None;
+        }
+
+        // A property holding a handle is copied rather than bound
+        let object = self.type_of(member.object)?;
+        let class = self.registry.get(object.type_hash)?.as_class()?;
+        let accessor = format!("get_{}", method.name);
+        let is_property = std::iter::once(class)
+            .chain(self.registry.base_class_chain(class.type_hash))
+            .any(|class| {
+                class.find_property(method.name).is_some()
+                    || !class.find_methods(&accessor).is_empty()
+            });
+        (!is_property)
+            .then(|| Delegate::resolve(self.registry, funcdef, &object, method.name, call.span))
+    }
+
+    /// The index operators of the object `expr` indexes, if it is of a
+    /// registered type.
+    fn index_access(&self, expr: &IndexExpr<'_>) -> Option<Result<IndexAccess, CompilationError>> {
+        let object = self.type_of(expr.object)?;
+        self.registry.get(object.type_hash)?;
+        Some(IndexAccess::resolve(
+            self.registry,
+            object.type_hash,
+            expr.span,
+        ))
+    }
+
+    /// The unified branches of `expr`, if both are of registered types or
+    /// null.
+    fn ternary(&self, expr: &TernaryExpr<'_>) -> Option<Result<Ternary, CompilationError>> {
+        let arm = |branch: &Expr<'_>| {
+            let ty = self.type_of(branch)?;
+            if !ty.is_null() && self.registry.get(ty.type_hash).is_none() {
+                return None;
+            }
+            Some(match branch {
+                Expr::Ident(_) => TernaryArm::lvalue(ty),
+                _ => TernaryArm::value(ty),
+            })
+        };
+        let (then_arm, else_arm) = (arm(expr.then_expr)?, arm(expr.else_expr)?);
+        Some(Ternary::resolve(
+            self.registry,
+            then_arm,
+            else_arm,
+            expr.span,
+        ))
+    }
+
+    /// Whether `hash` is a class or interface of the script or registry.
+    fn is_type(&self, hash: TypeHash) -> bool {
+        self.types.script_name(hash).is_some()
+            || self
+                .registry
+                .get(hash)
+                .is_some_and(|e| e.is_class() || e.is_interface())
+    }
+
+    /// Check `init` against the registered type `target` if it is an init
+    /// list whose elements are all typed.
+    fn check_init_list(&mut self, target: &DataType, init: &Expr<'_>) {
+        let Expr::InitList(list) = init else {
+            return;
+        };
+        if self.registry.get(target.type_hash).is_none() {
+            return;
+        }
+        let mut typed = true;
+        let shape = InitShape::of(list, &mut |expr| {
+            self.type_of(expr).unwrap_or_else(|| {
+                typed = false;
+                DataType::void()
+            })
+        });
+        if typed && let Err(error) = InitList::resolve(self.registry, target, &shape, list.span) {
+            self.errors.push(error);
+        }
+    }
+
+    fn declare(&mut self, name: &str, ty: Option<DataType>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push((name.to_string(), ty));
+        }
+    }
+
+    /// Check a function body in a fresh set of scopes holding `params`.
+    fn visit_body<'ast>(&mut self, params: Vec<Local>, body: &Block<'ast>) {
+        let outer = std::mem::replace(&mut self.scopes, vec![params]);
+        self.visit_block(body);
+        self.scopes = outer;
+    }
+}
+
+/// The type all of `types` have, if there are any and they agree.
+fn same(mut types: impl Iterator<Item = DataType>) -> Option<DataType> {
+    let first = types.next()?;
+    types.all(|ty| ty == first).then_some(first)
+}
+
+impl<'ast> Visitor<'ast> for TypePass<'_> {
+    fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+        let depth = self.namespace.len();
+        self.namespace
+            .extend(namespace.path.iter().map(|s| s.name.to_string()));
+        visitor::walk_namespace_decl(self, namespace);
+        self.namespace.truncate(depth);
+    }
+
+    fn visit_class_decl(&mut self, class: &ClassDecl<'ast>) {
+        let mut path = self.namespace.clone();
+        path.push(class.name.name.to_string());
+        let this = DataType::with_handle(TypeHash::from_name(&path.join("::")), false);
+        let mut members = vec![("this".to_string(), Some(this))];
+        for member in class.members {
+            match member {
+                ClassMember::Field(field) => {
+                    let ty = self.types.data_type(&field.ty, &self.namespace);
+                    members.push((field.name.name.to_string(), Some(ty)));
+                }
+                ClassMember::Method(method) => {
+                    members.push((method.name.name.to_string(), None));
+                }
+                _ => {}
+            }
+        }
+
+        let outer = self.class.replace(members);
+        visitor::walk_class_decl(self, class);
+        self.class = outer;
+    }
+
+    fn visit_function_decl(&mut self, func: &FunctionDecl<'ast>) {
+        for param in func.params {
+            if let Some(default) = param.default {
+                self.visit_expr(default);
+            }
+        }
+        if let Some(body) = &func.body {
+            let params = func
+                .params
+                .iter()
+                .filter_map(|p| {
+                    let ty = self.types.data_type(&p.ty.ty, &self.namespace);
+                    p.name.map(|n| (n.name.to_string(), Some(ty)))
+                })
+                .collect();
+            self.visit_body(params, body);
+        }
+    }
+
+    fn visit_lambda_expr(&mut self, expr: &LambdaExpr<'ast>) {
+        let params = expr
+            .params
+            .iter()
+            .filter_map(|p| {
+                let ty = p.ty.map(|ty| self.types.data_type(&ty.ty, &self.namespace));
+                p.name.map(|n| (n.name.to_string(), ty))
+            })
+            .collect();
+        self.visit_body(params, expr.body);
+    }
+
+    fn visit_block(&mut self, block: &Block<'ast>) {
+        self.scopes.push(Vec::new());
+        visitor::walk_block(self, block);
+        self.scopes.pop();
+    }
+
+    fn visit_call_expr(&mut self, expr: &CallExpr<'ast>) {
+        visitor::walk_call_expr(self, expr);
+        if let Some(Err(error)) = self.delegate(expr) {
+            self.errors.push(error);
+        }
+    }
+
+    fn visit_index_expr(&mut self, expr: &IndexExpr<'ast>) {
+        visitor::walk_index_expr(self, expr);
+        if let Some(Err(error)) = self.index_access(expr) {
+            self.errors.push(error);
+        }
+    }
+
+    fn visit_ternary_expr(&mut self, expr: &TernaryExpr<'ast>) {
+        visitor::walk_ternary_expr(self, expr);
+        if let Some(Err(error)) = self.ternary(expr) {
+            self.errors.push(error);
+        }
+    }
+
+    fn visit_cast_expr(&mut self, expr: &CastExpr<'ast>) {
+        visitor::walk_cast_expr(self, expr);
+
+        // Script types are not registered until the module is built
+        let to = self.types.data_type(&expr.target_type, &self.namespace);
+        let Some(from) = self.type_of(expr.expr) else {
+            return;
+        };
+        let registered = |hash| hash == primitives::NULL || self.registry.get(hash).is_some();
+        if registered(to.type_hash)
+            && registered(from.type_hash)
+            && let Err(error) =
+                RefCast::resolve(self.registry, &from, to.type_hash, false, expr.span)
+        {
+            self.errors.push(error);
+        }
+    }
+
+    fn visit_global_var_decl(&mut self, var: &GlobalVarDecl<'ast>) {
+        visitor::walk_global_var_decl(self, var);
+        if let Some(init) = var.init {
+            let ty = self.types.data_type(&var.ty, &self.namespace);
+            self.check_init_list(&ty, init);
+        }
+    }
+
+    fn visit_var_decl_stmt(&mut self, stmt: &VarDeclStmt<'ast>) {
+        for var in stmt.vars {
+            if let Some(init) = var.init {
+                self.visit_expr(init);
+            }
+
+            let ty = if auto::is_auto(&stmt.ty) {
+                let init = match var.init {
+                    Some(init) => match self.type_of(init) {
+                        Some(ty) => Some(ty),
+                        None => {
+                            self.declare(var.name.name, None);
+                            continue;
+                        }
+                    },
+                    None => None,
+                };
+                match auto::deduce_auto(
+                    self.registry,
+                    &stmt.ty,
+                    init.as_ref(),
+                    var.name.name,
+                    var.span,
+                ) {
+                    Ok(ty) => Some(ty),
+                    Err(error) => {
+                        self.errors.push(error);
+                        None
+                    }
+                }
+            } else {
+                let ty = self.types.data_type(&stmt.ty, &self.namespace);
+                if let Some(init) = var.init {
+                    self.check_init_list(&ty, init);
+                }
+                Some(ty)
+            };
+            self.declare(var.name.name, ty);
+        }
+    }
+
+    fn visit_for_stmt(&mut self, stmt: &ForStmt<'ast>) {
+        self.scopes.push(Vec::new());
+        visitor::walk_for_stmt(self, stmt);
+        self.scopes.pop();
+    }
+
+    fn visit_foreach_stmt(&mut self, stmt: &ForeachStmt<'ast>) {
+        self.visit_expr(stmt.expr);
+
+        // Script containers are not registered until the module is built
+        let values = match self.type_of(stmt.expr) {
+            Some(container) if self.registry.get(container.type_hash).is_some() => {
+                match ForeachProtocol::resolve(
+                    self.registry,
+                    container.type_hash,
+                    stmt.vars.len(),
+                    stmt.span,
+                ) {
+                    Ok(protocol) => Some(protocol.value_types(self.registry)),
+                    Err(error) => {
+                        self.errors.push(error);
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        self.scopes.push(Vec::new());
+        for (index, var) in stmt.vars.iter().enumerate() {
+            let ty = if auto::is_auto(&var.ty) {
+                let value = values.as_ref().map(|values| &values[index]);
+                match value.map(|value| auto::deduce_foreach_var(self.registry, var, value)) {
+                    Some(Ok(ty)) => Some(ty),
+                    Some(Err(error)) => {
+                        self.errors.push(error);
+                        None
+                    }
+                    None => None,
+                }
+            } else {
+                Some(self.types.data_type(&var.ty, &self.namespace))
+            };
+            self.declare(var.name.name, ty);
+        }
+        self.visit_stmt(stmt.body);
+        self.scopes.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{
+        ClassEntry, FuncdefEntry, FunctionEntry, ListBehavior, ListPattern, TypeKind, TypeSource,
+    };
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn check(source: &str) -> Vec<String> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        check_types(&script, &SymbolRegistry::with_primitives(), None)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn auto_needs_a_typed_initializer() {
+        let errors = check(
+            "
+            void spawn() { }
+            class Player { }
+            void main() {
+                auto a = null;
+                auto b = spawn();
+                auto c = 1.5f, d = (c);
+                const auto@ p = Player();
+                auto@ n = 1;
+            }
+            ",
+        );
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].contains("'a'") && errors[0].contains("null"));
+        assert!(errors[1].contains("'b'") && errors[1].contains("no value"));
+        assert!(errors[2].contains("auto@"));
+    }
+
+    #[test]
+    fn extension_operators_type_their_expressions() {
+        let arena = Bump::new();
+        let script = Parser::parse(
+            "
+            void opMul(const string &in s, int n) { }
+            void main() {
+                auto a = \"ab\" * 3;
+                auto b = 3 * \"ab\";
+                auto c = \"ab\" == \"cd\";
+            }
+            ",
+            &arena,
+        )
+        .unwrap();
+        let mut registry = SymbolRegistry::with_primitives();
+        registry
+            .register_type(ClassEntry::ffi("string", TypeKind::reference()).into())
+            .unwrap();
+
+        let errors = check_types(&script, &registry, Some(TypeHash::from_name("string")));
+        // Only the extension types `a`, as void
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].to_string().contains("'a'"));
+    }
+
+    #[test]
+    fn concatenations_are_strings() {
+        let arena = Bump::new();
+        let script = Parser::parse(
+            "
+            void main() {
+                auto@ a = \"hp: \" + 3 + \"/\" + 4.5f;
+                auto@ b = 1 + 2 + \"x\";
+            }
+            ",
+            &arena,
+        )
+        .unwrap();
+        let mut registry = SymbolRegistry::with_primitives();
+        registry
+            .register_type(ClassEntry::ffi("string", TypeKind::value::<String>()).into())
+            .unwrap();
+
+        let errors = check_types(&script, &registry, Some(TypeHash::from_name("string")));
+        // `1 + 2` adds the numbers, which are not typed yet
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(errors[0].to_string().contains("string"));
+    }
+
+    #[test]
+    fn delegates_of_registered_methods() {
+        let arena = Bump::new();
+        let script = Parser::parse(
+            "
+            void f(Player@ p) {
+                auto@ a = Callback(p.takeDamage);
+                Callback(p.heal);
+                Callback(p.missing);
+            }
+            ",
+            &arena,
+        )
+        .unwrap();
+        let mut registry = SymbolRegistry::with_primitives();
+        let callback = FuncdefEntry::new(
+            "Callback".to_string(),
+            vec![],
+            "Callback".to_string(),
+            TypeHash::from_name("Callback"),
+            TypeSource::ffi_untyped(),
+            vec![DataType::simple(primitives::INT32)],
+            DataType::void(),
+        );
+        registry.register_type(callback.into()).unwrap();
+        let player = TypeHash::from_name("Player");
+        let mut class = ClassEntry::ffi("Player", TypeKind::reference());
+        for (name, param) in [
+            ("takeDamage", primitives::INT32),
+            ("heal", primitives::FLOAT),
+        ] {
+            let def = FunctionDef::new(
+                TypeHash::from_method(player, name, &[param]),
+                name.to_string(),
+                vec![],
+                vec![Param::new("x", DataType::simple(param))],
+                DataType::void(),
+                Some(player),
+                FunctionTraits::default(),
+                true,
+                Visibility::Public,
+            );
+            class = class.with_method(name, def.func_hash);
+            registry.register_function(FunctionEntry::ffi(def)).unwrap();
+        }
+        registry.register_type(class.into()).unwrap();
+
+        let errors: Vec<_> = check_types(&script, &registry, None)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].contains("heal"));
+        assert!(errors[1].contains("missing"));
+    }
+
+    #[test]
+    fn foreach_over_registered_containers() {
+        let arena = Bump::new();
+        let script = Parser::parse(
+            "
+            void f(dictionary@ d, int n) {
+                foreach (auto k, auto v : d) { }
+                foreach (auto k, auto@ v : d) { }
+                foreach (auto x : n) { }
+                foreach (auto a, auto b, auto c : d) { }
+            }
+            ",
+            &arena,
+        )
+        .unwrap();
+        let mut registry = SymbolRegistry::with_primitives();
+        let hash = TypeHash::from_name;
+        let dict = ClassEntry::ffi("dictionary", TypeKind::reference()).with_iterator(
+            hash("dict::begin"),
+            hash("dict::end"),
+            hash("dict::next"),
+            &[hash("dict::key"), hash("dict::value")],
+        );
+        registry.register_type(dict.into()).unwrap();
+        for (name, returns) in [("key", primitives::INT32), ("value", primitives::FLOAT)] {
+            let def = FunctionDef::new(
+                hash(&format!("dict::{}", name)),
+                name.to_string(),
+                vec![],
+                vec![Param::new("it", DataType::simple(primitives::INT32))],
+                DataType::simple(returns),
+                Some(hash("dictionary")),
+                FunctionTraits::default(),
+                true,
+                Visibility::Public,
+            );
+            registry.register_function(FunctionEntry::ffi(def)).unwrap();
+        }
+
+        let errors: Vec<_> = check_types(&script, &registry, None)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].contains("auto@"));
+        assert!(errors[1].contains("container"));
+        assert!(errors[2].contains("opForValue2"));
+    }
+
+    #[test]
+    fn elements_of_registered_containers() {
+        let arena = Bump::new();
+        let script = Parser::parse(
+            "
+            void f(table@ t, Node@ node, int n) {
+                auto@ a = t[0];
+                node[0];
+                n[0];
+            }
+            ",
+            &arena,
+        )
+        .unwrap();
+        let mut registry = SymbolRegistry::with_primitives();
+        let table = TypeHash::from_name("table");
+        let getter = FunctionDef::new(
+            TypeHash::from_method(table, "get_opIndex", &[primitives::INT32]),
+            "get_opIndex".to_string(),
+            vec![],
+            vec![Param::new("i", DataType::simple(primitives::INT32))],
+            DataType::simple(primitives::FLOAT),
+            Some(table),
+            FunctionTraits::default(),
+            true,
+            Visibility::Public,
+        );
+        let mut class = ClassEntry::ffi("table", TypeKind::reference());
+        class
+            .behaviors
+            .add_operator(Operator::IndexGet, getter.func_hash);
+        registry.register_type(class.into()).unwrap();
+        registry
+            .register_function(FunctionEntry::ffi(getter))
+            .unwrap();
+        registry
+            .register_type(ClassEntry::ffi("Node", TypeKind::reference()).into())
+            .unwrap();
+
+        let errors: Vec<_> = check_types(&script, &registry, None)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].contains("auto@"));
+        assert!(errors[1].contains("cannot be indexed"));
+        assert!(errors[2].contains("only objects"));
+    }
+
+    #[test]
+    fn init_lists_of_registered_types() {
+        let arena = Bump::new();
+        let script = Parser::parse(
+            "
+            Rgb red = {255, 0, 0};
+            Rgb bad = {1, 2};
+            void f(int n) {
+                Rgb c = {n, n, n};
+                Rgb d = {n, undeclared, n, n};
+                Rgb e = {n};
+            }
+            ",
+            &arena,
+        )
+        .unwrap();
+        let mut registry = SymbolRegistry::with_primitives();
+        let mut rgb = ClassEntry::ffi("Rgb", TypeKind::value::<[u8; 3]>());
+        rgb.behaviors.add_list_construct(ListBehavior::new(
+            TypeHash::from_name("Rgb::$list"),
+            ListPattern::Fixed(vec![primitives::INT32; 3]),
+        ));
+        registry.register_type(rgb.into()).unwrap();
+
+        let errors = check_types(&script, &registry, None);
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors.iter().all(|error| error.to_string().contains("Rgb")));
+    }
+
+    #[test]
+    fn conditionals_unify_their_branches() {
+        let arena = Bump::new();
+        let script = Parser::parse(
+            "
+            void f(int n, float x, Node@ node) {
+                auto@ a = n > 0 ? x : n;
+                auto b = n > 0 ? null : null;
+                auto@ c = n > 0 ? node : null;
+                n > 0 ? n : node;
+            }
+            ",
+            &arena,
+        )
+        .unwrap();
+        let mut registry = SymbolRegistry::with_primitives();
+        registry
+            .register_type(ClassEntry::ffi("Node", TypeKind::reference()).into())
+            .unwrap();
+
+        let errors: Vec<_> = check_types(&script, &registry, None)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors[0].contains("auto@"));
+        assert!(errors[1].contains("both branches are null"));
+        assert!(errors[2].contains("no base"));
+    }
+
+    #[test]
+    fn casts_between_registered_types() {
+        let arena = Bump::new();
+        let script = Parser::parse(
+            "
+            class Player { }
+            void f(Node@ node, int n) {
+                auto@ a = cast<Node>(null);
+                auto@ b = cast<Node>(node);
+                cast<int>(node);
+                cast<Node>(n);
+                cast<Player>(node);
+            }
+            ",
+            &arena,
+        )
+        .unwrap();
+        let mut registry = SymbolRegistry::with_primitives();
+        registry
+            .register_type(ClassEntry::ffi("Node", TypeKind::reference()).into())
+            .unwrap();
+
+        let errors: Vec<_> = check_types(&script, &registry, None)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(errors[0].contains("int"));
+        assert!(errors[1].contains("int"));
+    }
+
+    #[test]
+    fn unknown_initializers_are_skipped() {
+        assert!(
+            check(
+                "
+                void main() {
+                    auto a = undeclared();
+                    auto b = a;
+                    auto c = b.field;
+                }
+                "
+            )
+            .is_empty()
+        );
+    }
+}
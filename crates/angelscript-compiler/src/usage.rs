@@ -0,0 +1,178 @@
+//! References to the registered API.
+//!
+//! [`collect_usage`] counts how often a script names each registered type
+//! and global function, so hosts can tell which parts of their API scripts
+//! rely on. Names are resolved like the compiler resolves them, from the
+//! namespace the reference is in; calls of overloaded functions count
+//! towards the function name rather than a particular overload. Methods are
+//! not counted, since resolving them needs the type of the object.
+
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{
+    CallExpr, ClassDecl, ClassMember, Expr, NamespaceDecl, Script, TypeBase, TypeExpr,
+};
+use angelscript_registry::SymbolRegistry;
+use rustc_hash::FxHashMap;
+
+use crate::access::candidate_names;
+
+/// Number of references to registered types and functions, by qualified
+/// name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ApiUsage {
+    /// References to registered types.
+    pub types: FxHashMap<String, u64>,
+    /// Calls of registered global functions.
+    pub functions: FxHashMap<String, u64>,
+}
+
+/// Count the references in `script` to the types and global functions
+/// registered by the application in `registry`.
+pub fn collect_usage(script: &Script<'_>, registry: &SymbolRegistry) -> ApiUsage {
+    let mut collector = UsageCollector {
+        registry,
+        namespace: Vec::new(),
+        methods: Vec::new(),
+        usage: ApiUsage::default(),
+    };
+    collector.visit_script(script);
+    collector.usage
+}
+
+struct UsageCollector<'a> {
+    registry: &'a SymbolRegistry,
+    /// Namespace the visited code is declared in.
+    namespace: Vec<String>,
+    /// Method names of the enclosing classes, which shadow global functions.
+    methods: Vec<Vec<String>>,
+    usage: ApiUsage,
+}
+
+impl<'ast> Visitor<'ast> for UsageCollector<'_> {
+    fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+        let depth = self.namespace.len();
+        self.namespace
+            .extend(namespace.path.iter().map(|s| s.name.to_string()));
+        visitor::walk_namespace_decl(self, namespace);
+        self.namespace.truncate(depth);
+    }
+
+    fn visit_class_decl(&mut self, class: &ClassDecl<'ast>) {
+        let methods = class
+            .members
+            .iter()
+            .filter_map(|member| match member {
+                ClassMember::Method(method) => Some(method.name.name.to_string()),
+                _ => None,
+            })
+            .collect();
+        self.methods.push(methods);
+        visitor::walk_class_decl(self, class);
+        self.methods.pop();
+    }
+
+    fn visit_type_expr(&mut self, ty: &TypeExpr<'ast>) {
+        if let TypeBase::Named(ident) = ty.base {
+            let found = candidate_names(&self.namespace, ty.scope.as_ref(), ident.name)
+                .iter()
+                .find_map(|name| self.registry.get_by_name(name));
+            if let Some(entry) = found
+                && entry.source().is_some_and(|source| source.is_ffi())
+            {
+                *self
+                    .usage
+                    .types
+                    .entry(entry.qualified_name().to_string())
+                    .or_default() += 1;
+            }
+        }
+        visitor::walk_type_expr(self, ty);
+    }
+
+    fn visit_call_expr(&mut self, expr: &CallExpr<'ast>) {
+        if let Expr::Ident(callee) = expr.callee {
+            let is_method = callee.scope.is_none()
+                && self
+                    .methods
+                    .iter()
+                    .any(|methods| methods.iter().any(|m| m == callee.ident.name));
+            let found = if is_method {
+                None
+            } else {
+                candidate_names(&self.namespace, callee.scope.as_ref(), callee.ident.name)
+                    .into_iter()
+                    .find(|name| self.registry.get_function_overloads(name).is_some())
+            };
+            if let Some(name) = found {
+                let registered = self
+                    .registry
+                    .get_function_overloads(&name)
+                    .unwrap_or_default()
+                    .iter()
+                    .any(|&hash| {
+                        self.registry
+                            .get_function(hash)
+                            .is_some_and(|f| !f.is_script())
+                    });
+                if registered {
+                    *self.usage.functions.entry(name).or_default() += 1;
+                }
+            }
+        }
+        visitor::walk_call_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::{
+        ClassEntry, DataType, FunctionDef, FunctionEntry, FunctionTraits, TypeHash, TypeKind,
+        Visibility,
+    };
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+        registry
+            .register_type(ClassEntry::ffi("Sprite", TypeKind::reference()).into())
+            .unwrap();
+        for (namespace, name) in [("gfx", "draw"), ("", "log")] {
+            let namespace: Vec<String> = if namespace.is_empty() {
+                Vec::new()
+            } else {
+                vec![namespace.into()]
+            };
+            let def = FunctionDef::new(
+                TypeHash::from_function(name, &[]),
+                name.to_string(),
+                namespace,
+                Vec::new(),
+                DataType::void(),
+                None,
+                FunctionTraits::default(),
+                true,
+                Visibility::Public,
+            );
+            registry.register_function(FunctionEntry::ffi(def)).unwrap();
+        }
+        registry
+    }
+
+    #[test]
+    fn counts_registered_references() {
+        let source = "namespace gfx { void frame(Sprite@ s) { draw(); draw(); } }\n\
+                      class Logger { void log() { } void run() { log(); } }\n\
+                      void main() { Sprite@ s; gfx::draw(); log(); int x = 0; }";
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let usage = collect_usage(&script, &registry());
+
+        assert_eq!(usage.types.get("Sprite"), Some(&2));
+        assert_eq!(usage.types.len(), 1);
+        assert_eq!(usage.functions.get("gfx::draw"), Some(&3));
+        // The method call in Logger::run is not the registered `log`
+        assert_eq!(usage.functions.get("log"), Some(&1));
+    }
+}
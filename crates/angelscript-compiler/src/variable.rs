@@ -0,0 +1,164 @@
+//! Variable-type parameters.
+//!
+//! A registered function may declare `?&in`, `?&out` or `?&inout`
+//! parameters, which accept a value of any type:
+//!
+//! ```angelscript
+//! dictionary d;
+//! d.set("hp", 100);
+//! d.set("name", "Zed");
+//! ```
+//!
+//! The callee cannot know the type from the value alone, so the caller
+//! pushes the type hash of every argument bound to a `?` parameter after
+//! all of the arguments, in argument order, and counts them in the argument
+//! count of the call. A `null` argument passes [`primitives::NULL`]. The
+//! native side reads them with `CallContext::arg_type_id`.
+//!
+//! Script functions cannot declare `?` parameters since script code has no
+//! way to use a value whose type it does not know.
+//!
+//! [`primitives::NULL`]: angelscript_core::primitives::NULL
+
+use angelscript_core::{CompilationError, DataType, FunctionDef, primitives};
+use angelscript_parser::ast::{Script, TypeBase};
+
+use crate::bytecode::{BytecodeChunk, ConstantPool, OpCode};
+use crate::plugin;
+
+/// Indices of the arguments bound to `?` parameters when calling `def`
+/// with `args` arguments.
+///
+/// The last parameter of a variadic function binds every extra argument.
+pub fn variable_params(def: &FunctionDef, args: usize) -> Vec<usize> {
+    (0..args)
+        .filter(|&index| {
+            let param = match def.params.get(index) {
+                Some(param) => param,
+                None if def.is_variadic => match def.params.last() {
+                    Some(param) => param,
+                    None => return false,
+                },
+                None => return false,
+            };
+            param.data_type.type_hash == primitives::VARIABLE_PARAM
+        })
+        .collect()
+}
+
+/// Emit the type hashes of the `indices` arguments of a call, after the
+/// arguments themselves.
+///
+/// Returns how many values were pushed, to add to the argument count.
+pub fn emit_type_ids(
+    chunk: &mut BytecodeChunk,
+    pool: &mut ConstantPool,
+    args: &[DataType],
+    indices: &[usize],
+    line: u32,
+) -> u8 {
+    for &index in indices {
+        let constant = pool.add_type_hash(args[index].type_hash);
+        match u8::try_from(constant) {
+            Ok(byte) => {
+                chunk.write_op(OpCode::Constant, line);
+                chunk.write_byte(byte, line);
+            }
+            Err(_) => {
+                chunk.write_op(OpCode::ConstantWide, line);
+                chunk.write_u16(constant as u16, line);
+            }
+        }
+    }
+    indices.len() as u8
+}
+
+/// Report `?` parameters declared by script functions and methods.
+pub fn check_script_declarations(script: &Script<'_>) -> Vec<CompilationError> {
+    let mut bodies = Vec::new();
+    plugin::function_bodies(script.items(), "", &mut bodies);
+    bodies
+        .into_iter()
+        .flat_map(|(_, decl)| decl.params.iter())
+        .filter(|param| matches!(param.ty.ty.base, TypeBase::Unknown))
+        .map(|param| CompilationError::InvalidParameterType {
+            type_name: "?".to_string(),
+            reason: "variable types are only allowed in registered functions".to_string(),
+            span: param.span,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::Constant;
+    use angelscript_core::{FunctionTraits, Param, TypeHash, Visibility};
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn def(params: &[TypeHash], is_variadic: bool) -> FunctionDef {
+        let mut def = FunctionDef::new(
+            TypeHash::from_name("f"),
+            "f".to_string(),
+            vec![],
+            params
+                .iter()
+                .enumerate()
+                .map(|(i, &hash)| Param::new(format!("p{}", i), DataType::simple(hash)))
+                .collect(),
+            DataType::void(),
+            None,
+            FunctionTraits::default(),
+            true,
+            Visibility::Public,
+        );
+        def.is_variadic = is_variadic;
+        def
+    }
+
+    #[test]
+    fn arguments_bound_to_variable_params() {
+        let set = def(&[primitives::STRING, primitives::VARIABLE_PARAM], false);
+        assert_eq!(variable_params(&set, 2), [1]);
+
+        let print = def(&[primitives::INT32, primitives::VARIABLE_PARAM], true);
+        assert_eq!(variable_params(&print, 4), [1, 2, 3]);
+        assert_eq!(variable_params(&print, 1), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn type_ids_follow_the_arguments() {
+        let mut chunk = BytecodeChunk::new();
+        let mut pool = ConstantPool::new();
+        let args = [
+            DataType::simple(primitives::STRING),
+            DataType::simple(primitives::NULL),
+        ];
+        assert_eq!(emit_type_ids(&mut chunk, &mut pool, &args, &[0, 1], 1), 2);
+        assert_eq!(
+            chunk.code(),
+            [OpCode::Constant as u8, 0, OpCode::Constant as u8, 1]
+        );
+        assert_eq!(pool.get(0), Some(&Constant::TypeHash(primitives::STRING)));
+        assert_eq!(pool.get(1), Some(&Constant::TypeHash(primitives::NULL)));
+    }
+
+    #[test]
+    fn script_functions_cannot_declare_variable_params() {
+        let arena = Bump::new();
+        let source = "
+            void fine(int x) {}
+            void any(?&in value) {}
+            class Box { void put(const ?&in value) {} }
+        ";
+        let script = Parser::parse(source, &arena).unwrap();
+        let errors = check_script_declarations(&script);
+        assert_eq!(errors.len(), 2);
+        assert!(
+            errors
+                .iter()
+                .all(|e| matches!(e, CompilationError::InvalidParameterType { type_name, .. } if type_name == "?"))
+        );
+    }
+}
@@ -0,0 +1,524 @@
+//! Enforcement of `private` and `protected` class members.
+//!
+//! A `private` member may only be used by its own class, and a `protected`
+//! member by its class and the classes deriving from it. Code outside
+//! classes may use neither:
+//!
+//! ```angelscript
+//! class Actor {
+//!     private int id;
+//!     protected int hp;
+//!     protected int get_armor() const { return 2; }
+//! }
+//!
+//! class Player : Actor {
+//!     void heal() { hp += 10; }   // ok: protected, inherited
+//!     int key() { return id; }    // error: private to Actor
+//! }
+//!
+//! void main() {
+//!     Player p;
+//!     p.hp = 0;                   // error: protected
+//!     int a = p.armor;            // error: the getter is protected
+//! }
+//! ```
+//!
+//! Virtual properties are checked against the accessor a use calls: a read
+//! calls the getter, an assignment the setter, and a compound assignment or
+//! increment both.
+//!
+//! Member uses are checked where the type of the object is known from the
+//! declarations in scope: `this`, parameters, locals and fields whose type
+//! is a script class, possibly through casts and parentheses. Uses of a
+//! member by name inside a method are checked against the class and its
+//! bases.
+
+use angelscript_core::{CompilationError, Span};
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{
+    AssignExpr, AssignOp, CallExpr, ClassDecl, ClassMember, Expr, FunctionDecl, IdentExpr, Item,
+    MemberAccess, MemberExpr, NamespaceDecl, PostfixExpr, Script, TypeBase, TypeExpr, UnaryExpr,
+    UnaryOp, VarDeclStmt, Visibility,
+};
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::interfaces::{base_name, namespace_of, qualify, resolve};
+
+/// Report uses of `private` and `protected` members outside the classes
+/// allowed to use them.
+pub fn check_member_access(script: &Script<'_>) -> Vec<CompilationError> {
+    let mut classes = FxHashMap::default();
+    collect_classes(script.items(), "", &mut classes);
+
+    let mut pass = AccessPass {
+        classes: &classes,
+        namespace: Vec::new(),
+        class: None,
+        locals: FxHashMap::default(),
+        usage: Usage::Read,
+        errors: Vec::new(),
+    };
+    pass.visit_script(script);
+    pass.errors
+}
+
+/// The members of a script class.
+#[derive(Debug)]
+struct ClassInfo {
+    /// Qualified name of the base class, if it is a script class.
+    base: Option<String>,
+    members: Vec<Member>,
+}
+
+#[derive(Debug)]
+struct Member {
+    name: String,
+    kind: MemberKind,
+    visibility: Visibility,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum MemberKind {
+    /// A field, with its type name as written.
+    Field(Option<String>),
+    Method,
+    Property,
+}
+
+/// How an expression uses the member it names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Usage {
+    Read,
+    Write,
+    ReadWrite,
+}
+
+fn collect_classes(items: &[Item<'_>], namespace: &str, out: &mut FxHashMap<String, ClassInfo>) {
+    let mut declared = Vec::new();
+    declared_classes(items, namespace, &mut declared);
+    let names: FxHashSet<String> = declared.iter().map(|(name, _)| name.clone()).collect();
+
+    for (name, class) in declared {
+        let scope = namespace_of(&name);
+        let base = class
+            .inheritance
+            .iter()
+            .find_map(|base| resolve(scope, &base_name(base), |n| names.contains(n)));
+        let members = class
+            .members
+            .iter()
+            .filter_map(|member| match member {
+                ClassMember::Field(field) => Some(Member {
+                    name: field.name.name.to_string(),
+                    kind: MemberKind::Field(type_name(&field.ty)),
+                    visibility: field.visibility,
+                }),
+                ClassMember::Method(method) if method.return_type.is_some() => Some(Member {
+                    name: method.name.name.to_string(),
+                    kind: MemberKind::Method,
+                    visibility: method.visibility,
+                }),
+                ClassMember::VirtualProperty(prop) => Some(Member {
+                    name: prop.name.name.to_string(),
+                    kind: MemberKind::Property,
+                    visibility: prop.visibility,
+                }),
+                _ => None,
+            })
+            .collect();
+        out.insert(name, ClassInfo { base, members });
+    }
+}
+
+fn declared_classes<'a, 'ast>(
+    items: &'a [Item<'ast>],
+    namespace: &str,
+    out: &mut Vec<(String, &'a ClassDecl<'ast>)>,
+) {
+    for item in items {
+        match item {
+            Item::Class(class) => out.push((qualify(namespace, class.name.name), class)),
+            Item::Namespace(ns) => {
+                let mut nested = namespace.to_string();
+                for segment in ns.path {
+                    nested = qualify(&nested, segment.name);
+                }
+                declared_classes(ns.items, &nested, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Name of a named type as written, e.g. `game::Player` for `game::Player@`.
+fn type_name(ty: &TypeExpr<'_>) -> Option<String> {
+    let TypeBase::Named(ident) = ty.base else {
+        return None;
+    };
+    Some(match ty.scope {
+        Some(scope) if !scope.is_empty() => format!("{}::{}", scope, ident.name),
+        _ => ident.name.to_string(),
+    })
+}
+
+struct AccessPass<'c> {
+    classes: &'c FxHashMap<String, ClassInfo>,
+    namespace: Vec<String>,
+    /// Qualified name of the class whose methods are being checked.
+    class: Option<String>,
+    /// Locals and parameters in scope, with their script class type.
+    locals: FxHashMap<String, Option<String>>,
+    /// How the expression being visited is used.
+    usage: Usage,
+    errors: Vec<CompilationError>,
+}
+
+impl<'c> AccessPass<'c> {
+    /// Resolve a type name written in `scope` to a script class.
+    fn class_named(&self, scope: &str, name: &str) -> Option<String> {
+        resolve(scope, name, |n| self.classes.contains_key(n))
+    }
+
+    /// Find a member in a class or its bases, with the class declaring it.
+    fn find(
+        &self,
+        class: &str,
+        matches: impl Fn(&Member) -> bool,
+    ) -> Option<(&'c str, &'c Member)> {
+        let classes = self.classes;
+        let mut seen = FxHashSet::default();
+        let mut current = classes.get_key_value(class);
+        while let Some((declaring, info)) = current {
+            if !seen.insert(declaring) {
+                break;
+            }
+            if let Some(member) = info.members.iter().find(|m| matches(m)) {
+                return Some((declaring.as_str(), member));
+            }
+            current = info
+                .base
+                .as_ref()
+                .and_then(|base| classes.get_key_value(base));
+        }
+        None
+    }
+
+    /// Check if `class` is `ancestor` or derives from it.
+    fn derives(&self, class: &str, ancestor: &str) -> bool {
+        let mut seen = FxHashSet::default();
+        let mut current = Some(class);
+        while let Some(name) = current {
+            if name == ancestor {
+                return true;
+            }
+            if !seen.insert(name) {
+                return false;
+            }
+            current = self.classes.get(name).and_then(|c| c.base.as_deref());
+        }
+        false
+    }
+
+    fn check(&mut self, declaring: &str, member: &Member, span: Span) {
+        let allowed = match member.visibility {
+            Visibility::Public => true,
+            Visibility::Private => self.class.as_deref() == Some(declaring),
+            Visibility::Protected => self
+                .class
+                .as_deref()
+                .is_some_and(|class| self.derives(class, declaring)),
+        };
+        if !allowed {
+            self.errors.push(CompilationError::InaccessibleMember {
+                member: format!("{}::{}", declaring, member.name),
+                visibility: member.visibility.to_string(),
+                span,
+            });
+        }
+    }
+
+    /// Check a use of the field or property `name` of `class`.
+    fn check_value(&mut self, class: &str, name: &str, usage: Usage, span: Span) {
+        if let Some((declaring, member)) =
+            self.find(class, |m| m.name == name && m.kind != MemberKind::Method)
+        {
+            self.check(declaring, member, span);
+            return;
+        }
+
+        // Otherwise the name may be a property with accessor methods
+        let accessors: &[&str] = match usage {
+            Usage::Read => &["get_"],
+            Usage::Write => &["set_"],
+            Usage::ReadWrite => &["get_", "set_"],
+        };
+        for prefix in accessors {
+            self.check_method(class, &format!("{}{}", prefix, name), span);
+        }
+    }
+
+    /// Check a call of the method `name` of `class`.
+    fn check_method(&mut self, class: &str, name: &str, span: Span) {
+        if let Some((declaring, member)) =
+            self.find(class, |m| m.name == name && m.kind == MemberKind::Method)
+        {
+            self.check(declaring, member, span);
+        }
+    }
+
+    /// The script class of an expression's value, where declarations in
+    /// scope tell it.
+    fn type_of(&self, expr: &Expr<'_>) -> Option<String> {
+        match expr {
+            Expr::Ident(ident) if ident.scope.is_none() => {
+                let name = ident.ident.name;
+                if name == "this" {
+                    return self.class.clone();
+                }
+                if let Some(local) = self.locals.get(name) {
+                    return local.clone();
+                }
+                let class = self.class.as_deref()?;
+                self.field_type(class, name)
+            }
+            Expr::Paren(paren) => self.type_of(paren.expr),
+            Expr::Cast(cast) => {
+                let name = type_name(&cast.target_type)?;
+                self.class_named(&self.namespace.join("::"), &name)
+            }
+            Expr::Member(member) => match member.member {
+                MemberAccess::Field(field) => {
+                    let class = self.type_of(member.object)?;
+                    self.field_type(&class, field.name)
+                }
+                MemberAccess::Method { .. } => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn field_type(&self, class: &str, name: &str) -> Option<String> {
+        let (declaring, member) = self.find(class, |m| m.name == name)?;
+        match &member.kind {
+            MemberKind::Field(Some(ty)) => self.class_named(namespace_of(declaring), ty),
+            _ => None,
+        }
+    }
+
+    fn with_usage(&mut self, usage: Usage, expr: &Expr<'_>) {
+        let outer = std::mem::replace(&mut self.usage, usage);
+        self.visit_expr(expr);
+        self.usage = outer;
+    }
+
+    fn declare(&mut self, name: &str, ty: &TypeExpr<'_>) {
+        let namespace = self.namespace.join("::");
+        let class = type_name(ty).and_then(|ty| self.class_named(&namespace, &ty));
+        self.locals.insert(name.to_string(), class);
+    }
+}
+
+impl<'ast> Visitor<'ast> for AccessPass<'_> {
+    fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+        let depth = self.namespace.len();
+        self.namespace
+            .extend(namespace.path.iter().map(|s| s.name.to_string()));
+        visitor::walk_namespace_decl(self, namespace);
+        self.namespace.truncate(depth);
+    }
+
+    fn visit_class_decl(&mut self, class: &ClassDecl<'ast>) {
+        let name = qualify(&self.namespace.join("::"), class.name.name);
+        let outer = self.class.replace(name);
+        visitor::walk_class_decl(self, class);
+        self.class = outer;
+    }
+
+    fn visit_function_decl(&mut self, func: &FunctionDecl<'ast>) {
+        let outer = std::mem::take(&mut self.locals);
+        for param in func.params {
+            if let Some(name) = param.name {
+                self.declare(name.name, &param.ty.ty);
+            }
+        }
+        visitor::walk_function_decl(self, func);
+        self.locals = outer;
+    }
+
+    fn visit_var_decl_stmt(&mut self, stmt: &VarDeclStmt<'ast>) {
+        visitor::walk_var_decl_stmt(self, stmt);
+        for var in stmt.vars {
+            self.declare(var.name.name, &stmt.ty);
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expr<'ast>) {
+        match expr {
+            // These name the member being used, or wrap an expression that does
+            Expr::Ident(_) | Expr::Member(_) | Expr::Paren(_) => visitor::walk_expr(self, expr),
+            _ => {
+                let outer = std::mem::replace(&mut self.usage, Usage::Read);
+                visitor::walk_expr(self, expr);
+                self.usage = outer;
+            }
+        }
+    }
+
+    fn visit_assign_expr(&mut self, expr: &AssignExpr<'ast>) {
+        let usage = if expr.op == AssignOp::Assign {
+            Usage::Write
+        } else {
+            Usage::ReadWrite
+        };
+        self.with_usage(usage, expr.target);
+        self.with_usage(Usage::Read, expr.value);
+    }
+
+    fn visit_unary_expr(&mut self, expr: &UnaryExpr<'ast>) {
+        let usage = match expr.op {
+            UnaryOp::PreInc | UnaryOp::PreDec => Usage::ReadWrite,
+            _ => Usage::Read,
+        };
+        self.with_usage(usage, expr.operand);
+    }
+
+    fn visit_postfix_expr(&mut self, expr: &PostfixExpr<'ast>) {
+        self.with_usage(Usage::ReadWrite, expr.operand);
+    }
+
+    fn visit_member_expr(&mut self, expr: &MemberExpr<'ast>) {
+        let usage = std::mem::replace(&mut self.usage, Usage::Read);
+        if let Some(class) = self.type_of(expr.object) {
+            match expr.member {
+                MemberAccess::Field(field) => {
+                    self.check_value(&class, field.name, usage, field.span)
+                }
+                MemberAccess::Method { name, .. } => {
+                    self.check_method(&class, name.name, name.span)
+                }
+            }
+        }
+        visitor::walk_member_expr(self, expr);
+        self.usage = usage;
+    }
+
+    fn visit_call_expr(&mut self, expr: &CallExpr<'ast>) {
+        // A method of the class called by name
+        if let Expr::Ident(ident) = expr.callee
+            && ident.scope.is_none()
+            && !self.locals.contains_key(ident.ident.name)
+        {
+            if let Some(class) = self.class.clone() {
+                self.check_method(&class, ident.ident.name, ident.span);
+            }
+            for arg in expr.args {
+                self.with_usage(Usage::Read, arg.value);
+            }
+            return;
+        }
+        visitor::walk_call_expr(self, expr);
+    }
+
+    fn visit_ident_expr(&mut self, expr: &IdentExpr<'ast>) {
+        let name = expr.ident.name;
+        if expr.scope.is_some() || name == "this" || self.locals.contains_key(name) {
+            return;
+        }
+        if let Some(class) = self.class.clone() {
+            self.check_value(&class, name, self.usage, expr.span);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    /// Members used where they are not accessible.
+    fn denied(source: &str) -> Vec<String> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        check_member_access(&script)
+            .into_iter()
+            .map(|error| match error {
+                CompilationError::InaccessibleMember { member, .. } => member,
+                other => panic!("unexpected error {other}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn private_and_protected_members() {
+        let source = "
+            class Actor {
+                private int id;
+                protected int hp;
+                int level;
+                private void reset() { id = 0; hp = 0; }
+                bool same(Actor@ other) { return other.id == id; }
+            }
+            class Player : Actor {
+                void heal() { hp += 10; this.hp++; }
+                int key() { reset(); return id; }
+            }
+            void main() {
+                Player p;
+                p.level = 2;
+                p.hp = 0;
+                p.reset();
+            }
+        ";
+        assert_eq!(
+            denied(source),
+            ["Actor::reset", "Actor::id", "Actor::hp", "Actor::reset"]
+        );
+    }
+
+    #[test]
+    fn property_accessors() {
+        let source = "
+            class Door {
+                int get_width() const { return 1; }
+                protected void set_width(int value) { }
+                private bool open { get { return true; } set { } }
+            }
+            class Gate : Door {
+                void widen() { width += 1; }
+            }
+            void main() {
+                Door d;
+                int w = d.width;
+                d.width = 2;
+                (d).open = false;
+            }
+        ";
+        assert_eq!(denied(source), ["Door::set_width", "Door::open"]);
+    }
+
+    #[test]
+    fn field_chains_and_namespaces() {
+        let source = "
+            namespace game {
+                class Stats { private int secret; int visible; }
+                class Unit {
+                    protected Stats stats;
+                    int peek() { return stats.secret; }
+                }
+            }
+            class Hero : game::Unit {
+                int look() { return stats.visible + cast<game::Stats>(stats).secret; }
+            }
+            void main(game::Unit u) { u.stats.visible = 1; }
+        ";
+        assert_eq!(
+            denied(source),
+            [
+                "game::Stats::secret",
+                "game::Stats::secret",
+                "game::Unit::stats"
+            ]
+        );
+    }
+}
@@ -0,0 +1,816 @@
+//! Compiler warnings.
+//!
+//! Warnings report suspicious code that still compiles. Each kind has a
+//! stable code (e.g. `W0001`) that scripts use to suppress it with
+//! `#pragma warning(disable: W0001)` or `// as-ignore[W0001]`, and that hosts
+//! use to change its [`WarningLevel`] per unit through a [`WarningConfig`]:
+//!
+//! | Code    | Warning                                         | Default |
+//! |---------|-------------------------------------------------|---------|
+//! | `W0001` | [`UnusedVariable`](WarningCode::UnusedVariable)           | warn    |
+//! | `W0002` | [`UnreachableCode`](WarningCode::UnreachableCode)         | warn    |
+//! | `W0003` | [`ImplicitNarrowing`](WarningCode::ImplicitNarrowing)     | warn    |
+//! | `W0004` | [`ShadowedDeclaration`](WarningCode::ShadowedDeclaration) | warn    |
+//! | `W0005` | [`UnusedReturnValue`](WarningCode::UnusedReturnValue)     | allow   |
+//! | `W0006` | [`NonExhaustiveSwitch`](WarningCode::NonExhaustiveSwitch) | warn    |
+//! | `W0007` | [`ImplicitFallthrough`](WarningCode::ImplicitFallthrough) | allow   |
+//!
+//! Denied warnings are reported as [`CompilationError::DeniedWarning`] and
+//! fail the build.
+//!
+//! [`CompilationError::DeniedWarning`]: angelscript_core::CompilationError::DeniedWarning
+
+use angelscript_core::{Span, primitives};
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{
+    Block, ClassDecl, ClassMember, Expr, ExprStmt, ForStmt, ForeachStmt, FunctionDecl, IdentExpr,
+    Item, LambdaExpr, LiteralKind, NamespaceDecl, PrimitiveType, Script, Stmt, SwitchStmt,
+    TypeBase, TypeExpr, UnaryOp, VarDeclStmt,
+};
+use angelscript_registry::SymbolRegistry;
+use rustc_hash::FxHashMap;
+use std::fmt;
+
+use crate::access::candidate_names;
+use crate::reachability;
+
+/// Kind of a compiler warning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningCode {
+    /// A local variable is never used.
+    UnusedVariable,
+    /// A statement follows a `return`, `break` or `continue`.
+    UnreachableCode,
+    /// A constant is implicitly converted to a type that cannot hold it.
+    ImplicitNarrowing,
+    /// A local variable hides a variable or parameter of an enclosing scope.
+    ShadowedDeclaration,
+    /// The value returned by a function call is discarded.
+    UnusedReturnValue,
+    /// A switch over an enum without `default` misses some enumerators.
+    NonExhaustiveSwitch,
+    /// A non-empty case runs on into the next one without a `break`.
+    ImplicitFallthrough,
+}
+
+impl WarningCode {
+    /// All warning kinds.
+    pub const ALL: [Self; 7] = [
+        Self::UnusedVariable,
+        Self::UnreachableCode,
+        Self::ImplicitNarrowing,
+        Self::ShadowedDeclaration,
+        Self::UnusedReturnValue,
+        Self::NonExhaustiveSwitch,
+        Self::ImplicitFallthrough,
+    ];
+
+    /// The stable code, as used in suppression pragmas.
+    pub const fn code(self) -> &'static str {
+        match self {
+            Self::UnusedVariable => "W0001",
+            Self::UnreachableCode => "W0002",
+            Self::ImplicitNarrowing => "W0003",
+            Self::ShadowedDeclaration => "W0004",
+            Self::UnusedReturnValue => "W0005",
+            Self::NonExhaustiveSwitch => "W0006",
+            Self::ImplicitFallthrough => "W0007",
+        }
+    }
+
+    /// Look up a warning kind by its code.
+    pub fn from_code(code: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.code() == code)
+    }
+
+    /// The level used when a unit does not configure the warning.
+    pub const fn default_level(self) -> WarningLevel {
+        match self {
+            // Calls are often made for their side effects only
+            Self::UnusedReturnValue => WarningLevel::Allow,
+            // Falling through is often intended
+            Self::ImplicitFallthrough => WarningLevel::Allow,
+            _ => WarningLevel::Warn,
+        }
+    }
+}
+
+impl fmt::Display for WarningCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// How a warning is reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningLevel {
+    /// The warning is not reported.
+    Allow,
+    /// The warning is reported without failing the build.
+    Warn,
+    /// The warning is reported as an error.
+    Deny,
+}
+
+/// Per-unit warning levels.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WarningConfig {
+    levels: FxHashMap<WarningCode, WarningLevel>,
+}
+
+impl WarningConfig {
+    /// Create a configuration using the default level of every warning.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the level of a warning.
+    pub fn set(&mut self, code: WarningCode, level: WarningLevel) {
+        self.levels.insert(code, level);
+    }
+
+    /// Get the level of a warning.
+    pub fn level(&self, code: WarningCode) -> WarningLevel {
+        self.levels
+            .get(&code)
+            .copied()
+            .unwrap_or(code.default_level())
+    }
+}
+
+/// A warning found in a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    /// Kind of warning.
+    pub code: WarningCode,
+    /// Description of the problem.
+    pub message: String,
+    /// Where the problem is.
+    pub span: Span,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at {}: {} [{}]", self.span, self.message, self.code)
+    }
+}
+
+/// Find all warnings in `script`, ordered by location.
+///
+/// Levels and suppressions are not applied.
+pub fn check_warnings(script: &Script<'_>, registry: &SymbolRegistry) -> Vec<Warning> {
+    let mut script_functions = FxHashMap::default();
+    collect_functions(script.items(), "", &mut script_functions);
+    let mut script_enums = FxHashMap::default();
+    collect_enums(script.items(), "", &mut script_enums);
+
+    let mut checker = WarningChecker {
+        registry,
+        script_functions,
+        script_enums,
+        namespace: Vec::new(),
+        methods: Vec::new(),
+        scopes: Vec::new(),
+        warnings: Vec::new(),
+    };
+    checker.visit_script(script);
+
+    let mut warnings = checker.warnings;
+    warnings.sort_by_key(|w| (w.span.line, w.span.col));
+    warnings
+}
+
+/// Record for each global script function whether every overload returns a
+/// value.
+fn collect_functions(items: &[Item<'_>], prefix: &str, out: &mut FxHashMap<String, bool>) {
+    for item in items {
+        match item {
+            Item::Function(func) => {
+                let returns = func.return_type.is_some_and(|r| !r.ty.is_void());
+                let name = format!("{}{}", prefix, func.name.name);
+                *out.entry(name).or_insert(true) &= returns;
+            }
+            Item::Namespace(ns) => {
+                let mut prefix = prefix.to_string();
+                for segment in ns.path {
+                    prefix.push_str(segment.name);
+                    prefix.push_str("::");
+                }
+                collect_functions(ns.items, &prefix, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Record the enumerator names of the enums declared by a script.
+fn collect_enums(items: &[Item<'_>], prefix: &str, out: &mut FxHashMap<String, Vec<String>>) {
+    for item in items {
+        match item {
+            Item::Enum(decl) => {
+                let names = decl
+                    .enumerators
+                    .iter()
+                    .map(|e| e.name.name.to_string())
+                    .collect();
+                out.insert(format!("{}{}", prefix, decl.name.name), names);
+            }
+            Item::Namespace(ns) => {
+                let mut prefix = prefix.to_string();
+                for segment in ns.path {
+                    prefix.push_str(segment.name);
+                    prefix.push_str("::");
+                }
+                collect_enums(ns.items, &prefix, out);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A numeric constant, as far as it can be determined from the syntax.
+enum Constant {
+    Int(i128),
+    Float,
+}
+
+fn constant(expr: &Expr<'_>) -> Option<Constant> {
+    match expr {
+        Expr::Literal(literal) => match literal.kind {
+            LiteralKind::Int(value) => Some(Constant::Int(value.into())),
+            LiteralKind::Float(_) | LiteralKind::Double(_) => Some(Constant::Float),
+            _ => None,
+        },
+        Expr::Paren(paren) => constant(paren.expr),
+        Expr::Unary(unary) if unary.op == UnaryOp::Neg => match constant(unary.operand)? {
+            Constant::Int(value) => Some(Constant::Int(-value)),
+            Constant::Float => Some(Constant::Float),
+        },
+        _ => None,
+    }
+}
+
+/// Check if an integer type can hold `value`.
+fn fits(ty: PrimitiveType, value: i128) -> bool {
+    let (min, max): (i128, i128) = match ty {
+        PrimitiveType::Int8 => (i8::MIN.into(), i8::MAX.into()),
+        PrimitiveType::Int16 => (i16::MIN.into(), i16::MAX.into()),
+        PrimitiveType::Int => (i32::MIN.into(), i32::MAX.into()),
+        PrimitiveType::Int64 => (i64::MIN.into(), i64::MAX.into()),
+        PrimitiveType::UInt8 => (0, u8::MAX.into()),
+        PrimitiveType::UInt16 => (0, u16::MAX.into()),
+        PrimitiveType::UInt => (0, u32::MAX.into()),
+        PrimitiveType::UInt64 => (0, u64::MAX.into()),
+        _ => return true,
+    };
+    (min..=max).contains(&value)
+}
+
+struct Local {
+    name: String,
+    span: Span,
+    used: bool,
+    is_param: bool,
+}
+
+struct WarningChecker<'a> {
+    registry: &'a SymbolRegistry,
+    /// Global script functions and whether they return a value.
+    script_functions: FxHashMap<String, bool>,
+    /// Enumerator names of the enums declared by the script.
+    script_enums: FxHashMap<String, Vec<String>>,
+    /// Namespace the visited code is declared in.
+    namespace: Vec<String>,
+    /// Method names of the enclosing classes, which shadow global functions.
+    methods: Vec<Vec<String>>,
+    /// Local scopes of the function being checked (innermost last).
+    scopes: Vec<Vec<Local>>,
+    warnings: Vec<Warning>,
+}
+
+impl WarningChecker<'_> {
+    fn warn(&mut self, code: WarningCode, span: Span, message: String) {
+        self.warnings.push(Warning {
+            code,
+            message,
+            span,
+        });
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(Vec::new());
+    }
+
+    fn pop_scope(&mut self) {
+        let Some(scope) = self.scopes.pop() else {
+            return;
+        };
+        for local in scope {
+            if !local.used && !local.is_param && !local.name.starts_with('_') {
+                self.warn(
+                    WarningCode::UnusedVariable,
+                    local.span,
+                    format!("unused variable '{}'", local.name),
+                );
+            }
+        }
+    }
+
+    fn find_local(&mut self, name: &str) -> Option<&mut Local> {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|local| local.name == name)
+    }
+
+    fn declare(&mut self, name: &str, span: Span, is_param: bool) {
+        if let Some(outer) = self.find_local(name) {
+            let kind = if outer.is_param {
+                "parameter"
+            } else {
+                "variable"
+            };
+            let message = format!("'{}' shadows a {} declared at {}", name, kind, outer.span);
+            self.warn(WarningCode::ShadowedDeclaration, span, message);
+        }
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(Local {
+                name: name.to_string(),
+                span,
+                used: false,
+                is_param,
+            });
+        }
+    }
+
+    /// Visit a statement list, reporting the first statement that follows a
+    /// statement control cannot run past.
+    fn visit_stmts<'ast>(&mut self, stmts: &[Stmt<'ast>]) {
+        let mut jumped = false;
+        for stmt in stmts {
+            if jumped {
+                self.warn(
+                    WarningCode::UnreachableCode,
+                    stmt.span(),
+                    "unreachable code".to_string(),
+                );
+                jumped = false;
+            }
+            self.visit_stmt(stmt);
+            if !reachability::completes(stmt) {
+                jumped = true;
+            }
+        }
+    }
+
+    /// Check a function body in a fresh set of scopes holding `params`.
+    fn visit_body<'ast>(&mut self, params: &[(&str, Span)], body: &Block<'ast>) {
+        let outer = std::mem::take(&mut self.scopes);
+        self.push_scope();
+        for &(name, span) in params {
+            self.declare(name, span, true);
+        }
+        self.visit_block(body);
+        self.scopes = outer;
+    }
+
+    fn check_narrowing(&mut self, ty: &TypeExpr<'_>, init: &Expr<'_>) {
+        let TypeBase::Primitive(target) = ty.base else {
+            return;
+        };
+        if !ty.suffixes.is_empty() || !target.is_integer() {
+            return;
+        }
+        let message = match constant(init) {
+            Some(Constant::Float) => {
+                format!("floating-point value is truncated to '{}'", target)
+            }
+            Some(Constant::Int(value)) if !fits(target, value) => {
+                format!("value {} does not fit in '{}'", value, target)
+            }
+            _ => return,
+        };
+        self.warn(WarningCode::ImplicitNarrowing, init.span(), message);
+    }
+
+    /// Find the enumerators of the enum named by `path`, as written in the
+    /// visited namespace.
+    fn enumerators(&self, path: &str) -> Option<(String, Vec<String>)> {
+        candidate_names(&self.namespace, None, path)
+            .into_iter()
+            .find_map(|name| {
+                if let Some(names) = self.script_enums.get(&name) {
+                    return Some((name, names.clone()));
+                }
+                let entry = self.registry.get_by_name(&name)?.as_enum()?;
+                let names = entry.values.iter().map(|v| v.name.clone()).collect();
+                Some((name, names))
+            })
+    }
+
+    /// Find the one enum visible from the visited namespace that has an
+    /// enumerator for every unqualified label.
+    fn enum_of_labels(&self, labels: &[&IdentExpr<'_>]) -> Option<(String, Vec<String>)> {
+        let shadowed = |name: &str| {
+            self.scopes
+                .iter()
+                .flat_map(|scope| scope.iter())
+                .any(|local| local.name == name)
+        };
+        if labels.is_empty()
+            || labels
+                .iter()
+                .any(|label| label.scope.is_some() || shadowed(label.ident.name))
+        {
+            return None;
+        }
+
+        let namespace = self.namespace.join("::");
+        let visible = |name: &str| match name.rsplit_once("::") {
+            Some((ns, _)) => namespace == ns || namespace.starts_with(&format!("{ns}::")),
+            None => true,
+        };
+        let has_labels = |names: &[String]| {
+            labels
+                .iter()
+                .all(|l| names.iter().any(|n| n == l.ident.name))
+        };
+        let script = self
+            .script_enums
+            .iter()
+            .map(|(name, names)| (name.as_str(), names.clone()));
+        let registered = self.registry.enums().map(|entry| {
+            let names = entry.values.iter().map(|v| v.name.clone()).collect();
+            (entry.qualified_name.as_str(), names)
+        });
+        let mut found = script
+            .chain(registered)
+            .filter(|(name, names)| visible(name) && has_labels(names));
+        let (name, names) = found.next()?;
+        found.next().is_none().then(|| (name.to_string(), names))
+    }
+
+    /// Warn about enumerators a switch without `default` does not handle,
+    /// if its labels show it switches over an enum: a label qualified with
+    /// the enum's name, or unqualified labels only one enum has.
+    fn check_exhaustive(&mut self, stmt: &SwitchStmt<'_>) {
+        if stmt.cases.iter().any(|case| case.is_default()) {
+            return;
+        }
+        let labels: Vec<&IdentExpr<'_>> = stmt
+            .cases
+            .iter()
+            .flat_map(|case| case.values.iter())
+            .filter_map(|value| match value {
+                Expr::Ident(ident) => Some(ident),
+                _ => None,
+            })
+            .collect();
+        let qualified = labels.iter().find_map(|label| {
+            let scope = label.scope.as_ref().filter(|s| !s.segments.is_empty())?;
+            let path: Vec<&str> = scope.segments.iter().map(|s| s.name).collect();
+            self.enumerators(&path.join("::"))
+        });
+        let Some((name, enumerators)) = qualified.or_else(|| self.enum_of_labels(&labels)) else {
+            return;
+        };
+
+        let missing: Vec<&str> = enumerators
+            .iter()
+            .filter(|e| !labels.iter().any(|label| label.ident.name == e.as_str()))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            let message = format!(
+                "switch over '{}' does not handle {}",
+                name,
+                missing.join(", ")
+            );
+            self.warn(WarningCode::NonExhaustiveSwitch, stmt.span, message);
+        }
+    }
+
+    /// Check if a call to `callee` returns a value.
+    fn returns_value(&mut self, callee: &IdentExpr<'_>) -> bool {
+        if callee.scope.is_none() {
+            let name = callee.ident.name;
+            let is_method = self.methods.iter().any(|m| m.iter().any(|m| m == name));
+            if is_method || self.find_local(name).is_some() {
+                return false;
+            }
+        }
+
+        for name in candidate_names(&self.namespace, callee.scope.as_ref(), callee.ident.name) {
+            if let Some(&returns) = self.script_functions.get(&name) {
+                return returns;
+            }
+            if let Some(overloads) = self.registry.get_function_overloads(&name) {
+                return !overloads.is_empty()
+                    && overloads.iter().all(|&hash| {
+                        self.registry
+                            .get_function(hash)
+                            .is_some_and(|f| f.def.return_type.type_hash != primitives::VOID)
+                    });
+            }
+        }
+        false
+    }
+}
+
+impl<'ast> Visitor<'ast> for WarningChecker<'_> {
+    fn visit_namespace_decl(&mut self, namespace: &NamespaceDecl<'ast>) {
+        let depth = self.namespace.len();
+        self.namespace
+            .extend(namespace.path.iter().map(|s| s.name.to_string()));
+        visitor::walk_namespace_decl(self, namespace);
+        self.namespace.truncate(depth);
+    }
+
+    fn visit_class_decl(&mut self, class: &ClassDecl<'ast>) {
+        let methods = class
+            .members
+            .iter()
+            .filter_map(|member| match member {
+                ClassMember::Method(method) => Some(method.name.name.to_string()),
+                _ => None,
+            })
+            .collect();
+        self.methods.push(methods);
+        visitor::walk_class_decl(self, class);
+        self.methods.pop();
+    }
+
+    fn visit_function_decl(&mut self, func: &FunctionDecl<'ast>) {
+        for param in func.params {
+            if let Some(default) = param.default {
+                self.visit_expr(default);
+            }
+        }
+        if let Some(body) = &func.body {
+            let params: Vec<_> = func
+                .params
+                .iter()
+                .filter_map(|p| p.name.map(|n| (n.name, n.span)))
+                .collect();
+            self.visit_body(&params, body);
+        }
+    }
+
+    fn visit_lambda_expr(&mut self, expr: &LambdaExpr<'ast>) {
+        let params: Vec<_> = expr
+            .params
+            .iter()
+            .filter_map(|p| p.name.map(|n| (n.name, n.span)))
+            .collect();
+        self.visit_body(&params, expr.body);
+    }
+
+    fn visit_block(&mut self, block: &Block<'ast>) {
+        self.push_scope();
+        self.visit_stmts(block.stmts);
+        self.pop_scope();
+    }
+
+    fn visit_var_decl_stmt(&mut self, stmt: &VarDeclStmt<'ast>) {
+        self.visit_type_expr(&stmt.ty);
+        for var in stmt.vars {
+            if let Some(init) = var.init {
+                self.visit_expr(init);
+                self.check_narrowing(&stmt.ty, init);
+            }
+            self.declare(var.name.name, var.name.span, false);
+        }
+    }
+
+    fn visit_for_stmt(&mut self, stmt: &ForStmt<'ast>) {
+        self.push_scope();
+        visitor::walk_for_stmt(self, stmt);
+        self.pop_scope();
+    }
+
+    fn visit_foreach_stmt(&mut self, stmt: &ForeachStmt<'ast>) {
+        self.visit_expr(stmt.expr);
+        self.push_scope();
+        for var in stmt.vars {
+            self.visit_type_expr(&var.ty);
+            self.declare(var.name.name, var.name.span, false);
+        }
+        self.visit_stmt(stmt.body);
+        self.pop_scope();
+    }
+
+    fn visit_switch_stmt(&mut self, stmt: &SwitchStmt<'ast>) {
+        self.visit_expr(stmt.expr);
+        self.check_exhaustive(stmt);
+        self.push_scope();
+        for (i, case) in stmt.cases.iter().enumerate() {
+            for value in case.values {
+                self.visit_expr(value);
+            }
+            self.visit_stmts(case.stmts);
+            if let Some(next) = stmt.cases.get(i + 1)
+                && !case.stmts.is_empty()
+                && reachability::stmts_complete(case.stmts)
+            {
+                self.warn(
+                    WarningCode::ImplicitFallthrough,
+                    next.span,
+                    "previous case falls through into this one".to_string(),
+                );
+            }
+        }
+        self.pop_scope();
+    }
+
+    fn visit_expr_stmt(&mut self, stmt: &ExprStmt<'ast>) {
+        if let Some(Expr::Call(call)) = stmt.expr
+            && let Expr::Ident(callee) = call.callee
+            && self.returns_value(callee)
+        {
+            let message = format!("return value of '{}' is ignored", callee.ident.name);
+            self.warn(WarningCode::UnusedReturnValue, call.span, message);
+        }
+        visitor::walk_expr_stmt(self, stmt);
+    }
+
+    fn visit_ident_expr(&mut self, expr: &IdentExpr<'ast>) {
+        if expr.scope.is_none()
+            && let Some(local) = self.find_local(expr.ident.name)
+        {
+            local.used = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::Parser;
+    use bumpalo::Bump;
+
+    fn check(source: &str) -> Vec<(WarningCode, u32)> {
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let registry = SymbolRegistry::with_primitives();
+        check_warnings(&script, &registry)
+            .into_iter()
+            .map(|w| (w.code, w.span.line))
+            .collect()
+    }
+
+    #[test]
+    fn unused_and_shadowed_locals() {
+        let source = "void f(int a) {\n\
+                      int unused = 1;\n\
+                      int _ignored = 2;\n\
+                      int b = a;\n\
+                      if (b > 0) { int a = b; a++; }\n\
+                      }";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningCode::UnusedVariable, 2),
+                (WarningCode::ShadowedDeclaration, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn unreachable_after_jump() {
+        let source = "int f(int x) {\n\
+                      while (x > 0) { break; x--; }\n\
+                      if (x > 1) { return x; } else { throw(\"x\"); }\n\
+                      x = 1;\n\
+                      while (true) { }\n\
+                      return x;\n\
+                      }";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningCode::UnreachableCode, 2),
+                (WarningCode::UnreachableCode, 4),
+                (WarningCode::UnreachableCode, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn narrowing_constants() {
+        let source = "void f() {\n\
+                      int a = 1.5;\n\
+                      int8 b = -200;\n\
+                      uint8 c = 255;\n\
+                      float d = 1.5;\n\
+                      a = b + c + int(d);\n\
+                      }";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningCode::ImplicitNarrowing, 2),
+                (WarningCode::ImplicitNarrowing, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn ignored_return_values() {
+        let source = "int get() { return 1; }\n\
+                      void set() { }\n\
+                      class C { void get() { } void run() { get(); } }\n\
+                      void f() { get(); set(); int x = get(); x++; }";
+        assert_eq!(check(source), vec![(WarningCode::UnusedReturnValue, 4)]);
+    }
+
+    #[test]
+    fn switches_over_enums() {
+        let source = "enum Color { Red, Green, Blue }\n\
+                      void f(Color c) {\n\
+                      switch (c) { case Color::Red: break; }\n\
+                      switch (c) { case Color::Red: case Green: break; case Blue: return; }\n\
+                      switch (c) { case Color::Red: break; default: break; }\n\
+                      switch (1) { case 1: break; }\n\
+                      }";
+        assert_eq!(check(source), vec![(WarningCode::NonExhaustiveSwitch, 3)]);
+
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let registry = SymbolRegistry::with_primitives();
+        let warnings = check_warnings(&script, &registry);
+        assert_eq!(
+            warnings[0].message,
+            "switch over 'Color' does not handle Green, Blue"
+        );
+    }
+
+    #[test]
+    fn switches_over_enums_with_unqualified_labels() {
+        let source = "enum Color { Red, Green, Blue }\n\
+                      enum Light { Red, Amber, Green }\n\
+                      namespace ui { enum Align { Left, Center, Right } }\n\
+                      void f(Color c, ui::Align a) {\n\
+                      switch (c) { case Blue: break; }\n\
+                      switch (c) { case Red: case Green: break; }\n\
+                      switch (a) { case Left: break; }\n\
+                      }\n\
+                      void g(int Blue) { switch (Blue) { case Blue: break; } }\n\
+                      namespace ui { void h(Align a) { switch (a) { case Left: break; } } }";
+        assert_eq!(
+            check(source),
+            vec![
+                (WarningCode::NonExhaustiveSwitch, 5),
+                (WarningCode::NonExhaustiveSwitch, 10)
+            ]
+        );
+
+        let arena = Bump::new();
+        let script = Parser::parse(source, &arena).unwrap();
+        let registry = SymbolRegistry::with_primitives();
+        let warnings = check_warnings(&script, &registry);
+        assert_eq!(
+            warnings[0].message,
+            "switch over 'Color' does not handle Red, Green"
+        );
+        assert_eq!(
+            warnings[1].message,
+            "switch over 'ui::Align' does not handle Center, Right"
+        );
+    }
+
+    #[test]
+    fn fallthrough_between_cases() {
+        let source = "void f(int x) {\n\
+                      switch (x) {\n\
+                      case 0:\n\
+                      case 1: x++;\n\
+                      case 2: if (x > 1) { break; } else { return; }\n\
+                      case 3: { x--; break; }\n\
+                      case 4: x++;\n\
+                      }\n\
+                      }";
+        assert_eq!(check(source), vec![(WarningCode::ImplicitFallthrough, 5)]);
+    }
+
+    #[test]
+    fn config_levels() {
+        let mut config = WarningConfig::new();
+        assert_eq!(
+            config.level(WarningCode::UnusedVariable),
+            WarningLevel::Warn
+        );
+        assert_eq!(
+            config.level(WarningCode::UnusedReturnValue),
+            WarningLevel::Allow
+        );
+
+        config.set(WarningCode::UnusedVariable, WarningLevel::Deny);
+        assert_eq!(
+            config.level(WarningCode::UnusedVariable),
+            WarningLevel::Deny
+        );
+        assert_eq!(
+            WarningCode::from_code("W0003"),
+            Some(WarningCode::ImplicitNarrowing)
+        );
+    }
+}
@@ -332,6 +332,38 @@ impl TypeBehaviors {
         self.operators.iter()
     }
 
+    /// Register the foreach iterator protocol.
+    ///
+    /// `values` are the `opForValue` methods, one per iteration variable:
+    /// a single value registers `opForValue`, several register
+    /// `opForValue0`, `opForValue1`, ... (as in `foreach (k, v : dict)`).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `values` is empty or has more than 256 entries.
+    pub fn add_iterator(
+        &mut self,
+        begin: TypeHash,
+        end: TypeHash,
+        next: TypeHash,
+        values: &[TypeHash],
+    ) {
+        assert!(
+            (1..=256).contains(&values.len()),
+            "foreach iterates 1 to 256 values"
+        );
+        self.add_operator(Operator::ForBegin, begin);
+        self.add_operator(Operator::ForEnd, end);
+        self.add_operator(Operator::ForNext, next);
+        if let [value] = values {
+            self.add_operator(Operator::ForValue, *value);
+        } else {
+            for (index, value) in values.iter().enumerate() {
+                self.add_operator(Operator::ForValueN(index as u8), *value);
+            }
+        }
+    }
+
     /// Add a conversion operator overload for this type.
     pub fn add_conversion(&mut self, entry: ConversionEntry) {
         self.conversions.push(entry);
@@ -673,6 +705,35 @@ mod tests {
         assert_eq!(overloads[1], op_add_float);
     }
 
+    #[test]
+    fn iterator_protocol() {
+        let hash = |name: &str| TypeHash::from_name(name);
+        let mut list = TypeBehaviors::new();
+        list.add_iterator(hash("begin"), hash("end"), hash("next"), &[hash("value")]);
+        assert_eq!(
+            list.get_operator(Operator::ForBegin),
+            Some(&[hash("begin")][..])
+        );
+        assert_eq!(
+            list.get_operator(Operator::ForValue),
+            Some(&[hash("value")][..])
+        );
+        assert!(!list.has_operator(Operator::ForValueN(0)));
+
+        let mut dict = TypeBehaviors::new();
+        dict.add_iterator(
+            hash("begin"),
+            hash("end"),
+            hash("next"),
+            &[hash("key"), hash("value")],
+        );
+        assert!(!dict.has_operator(Operator::ForValue));
+        assert_eq!(
+            dict.get_operator(Operator::ForValueN(1)),
+            Some(&[hash("value")][..])
+        );
+    }
+
     #[test]
     fn get_nonexistent_operator() {
         let behaviors = TypeBehaviors::new();
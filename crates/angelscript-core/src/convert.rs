@@ -3,6 +3,7 @@
 //! This module provides traits for converting between Rust types and [`Dynamic`] values:
 //! - [`FromDynamic`]: Extract a Rust value from a [`Dynamic`]
 //! - [`IntoDynamic`]: Convert a Rust value into a [`Dynamic`]
+//! - [`IntoArgs`]: Convert a tuple of Rust values into script call arguments
 //!
 //! ## Supported Primitive Types
 //!
@@ -270,10 +271,77 @@ impl IntoDynamic for &str {
     }
 }
 
+// ============================================================================
+// Argument lists
+// ============================================================================
+
+/// Convert a set of Rust values into arguments for a script call.
+///
+/// Implemented for tuples of up to eight [`IntoDynamic`] values, so calls
+/// can be written as `unit.instantiate("Player", (100, "Bob"))`. Use `()` for
+/// no arguments and a one-element tuple `(x,)` for a single argument.
+pub trait IntoArgs {
+    /// Convert into a list of argument slots, in declaration order.
+    fn into_args(self) -> Vec<Dynamic>;
+}
+
+impl IntoArgs for Vec<Dynamic> {
+    fn into_args(self) -> Vec<Dynamic> {
+        self
+    }
+}
+
+impl IntoArgs for () {
+    fn into_args(self) -> Vec<Dynamic> {
+        Vec::new()
+    }
+}
+
+macro_rules! impl_into_args {
+    ($($name:ident),+) => {
+        impl<$($name: IntoDynamic),+> IntoArgs for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn into_args(self) -> Vec<Dynamic> {
+                let ($($name,)+) = self;
+                vec![$($name.into_dynamic()),+]
+            }
+        }
+    };
+}
+
+impl_into_args!(A);
+impl_into_args!(A, B);
+impl_into_args!(A, B, C);
+impl_into_args!(A, B, C, D);
+impl_into_args!(A, B, C, D, E);
+impl_into_args!(A, B, C, D, E, F);
+impl_into_args!(A, B, C, D, E, F, G);
+impl_into_args!(A, B, C, D, E, F, G, H);
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // ========================================================================
+    // IntoArgs tests
+    // ========================================================================
+
+    #[test]
+    fn into_args_tuples() {
+        assert!(().into_args().is_empty());
+
+        let args = (100,).into_args();
+        assert!(matches!(args[..], [Dynamic::Int(100)]));
+
+        let args = (1, 2.5f32, true, "name").into_args();
+        assert_eq!(args.len(), 4);
+        assert!(matches!(args[1], Dynamic::Float(v) if v == 2.5));
+        assert!(matches!(&args[3], Dynamic::String(s) if s == "name"));
+
+        let args = vec![Dynamic::Bool(false)].into_args();
+        assert!(matches!(args[..], [Dynamic::Bool(false)]));
+    }
+
     // ========================================================================
     // FromDynamic tests
     // ========================================================================
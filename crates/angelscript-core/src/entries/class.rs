@@ -269,6 +269,19 @@ impl ClassEntry {
         self
     }
 
+    /// Register the foreach iterator protocol (see
+    /// [`TypeBehaviors::add_iterator`](crate::TypeBehaviors::add_iterator)).
+    pub fn with_iterator(
+        mut self,
+        begin: TypeHash,
+        end: TypeHash,
+        next: TypeHash,
+        values: &[TypeHash],
+    ) -> Self {
+        self.behaviors.add_iterator(begin, end, next, values);
+        self
+    }
+
     /// Set template parameters (makes this a template definition).
     pub fn with_template_params(mut self, params: Vec<TypeHash>) -> Self {
         self.template_params = params;
@@ -59,6 +59,10 @@ pub enum LexError {
     /// A numeric literal could not be parsed.
     #[error("invalid number at {span}: {detail}")]
     InvalidNumber { span: Span, detail: String },
+
+    /// A `#pragma` directive did not start its line.
+    #[error("'#pragma' must be at the start of a line at {span}")]
+    MisplacedDirective { span: Span },
 }
 
 impl LexError {
@@ -70,6 +74,7 @@ impl LexError {
             LexError::UnterminatedHeredoc { span } => *span,
             LexError::UnterminatedComment { span } => *span,
             LexError::InvalidNumber { span, .. } => *span,
+            LexError::MisplacedDirective { span } => *span,
         }
     }
 }
@@ -793,6 +798,71 @@ pub enum CompilationError {
         span: Span,
     },
 
+    /// More than one conversion operator could convert a value.
+    #[error("at {span}: ambiguous conversion from '{from}' to '{to}': {candidates}")]
+    AmbiguousConversion {
+        /// The source type.
+        from: String,
+        /// The target type.
+        to: String,
+        /// The candidate conversion operators.
+        candidates: String,
+        /// Where the conversion occurred.
+        span: Span,
+    },
+
+    /// No method can be bound to a funcdef in a delegate.
+    #[error("at {span}: cannot bind '{type_name}::{method}' to funcdef '{funcdef}': {reason}")]
+    InvalidDelegate {
+        /// The method name.
+        method: String,
+        /// The type of the bound object.
+        type_name: String,
+        /// The funcdef of the delegate.
+        funcdef: String,
+        /// Why no method can be bound.
+        reason: String,
+        /// Where the delegate is created.
+        span: Span,
+    },
+
+    /// The type of an `auto` variable cannot be deduced.
+    #[error("at {span}: cannot deduce the type of '{name}': {reason}")]
+    CannotDeduceAuto {
+        /// The variable name.
+        name: String,
+        /// Why the type cannot be deduced.
+        reason: String,
+        /// Where the variable is declared.
+        span: Span,
+    },
+
+    /// An init list does not match any way to build its type.
+    #[error("at {span}: cannot initialize '{type_name}' from an init list: {reason}")]
+    InvalidInitList {
+        /// The type being initialized.
+        type_name: String,
+        /// Why the list does not match.
+        reason: String,
+        /// Where the list is.
+        span: Span,
+    },
+
+    /// The branches of a conditional expression have no common type.
+    #[error(
+        "at {span}: incompatible branches '{then_type}' and '{else_type}' in a conditional expression: {reason}"
+    )]
+    IncompatibleBranches {
+        /// The type of the `then` branch.
+        then_type: String,
+        /// The type of the `else` branch.
+        else_type: String,
+        /// Why no common type exists.
+        reason: String,
+        /// Where the conditional expression is.
+        span: Span,
+    },
+
     /// Invalid handle type - type does not support handles.
     #[error("at {span}: cannot create handle to '{type_name}': {reason}")]
     InvalidHandleType {
@@ -823,6 +893,155 @@ pub enum CompilationError {
         /// Where the switch expression occurred.
         span: Span,
     },
+    /// A registered entity is hidden from the unit by its access mask.
+    #[error("at {span}: {kind} '{name}' is not accessible from this unit")]
+    Inaccessible {
+        /// What kind of entity (e.g., "type", "function").
+        kind: String,
+        /// The entity name as written.
+        name: String,
+        /// Where the entity was referenced.
+        span: Span,
+    },
+
+    /// A warning the unit is configured to treat as an error.
+    #[error("at {span}: {message} [{code}]")]
+    DeniedWarning {
+        /// The warning code (e.g., "W0001").
+        code: &'static str,
+        /// Description of the problem.
+        message: String,
+        /// Where the problem is.
+        span: Span,
+    },
+
+    /// An expression that must be constant could not be evaluated.
+    #[error("at {span}: expression is not constant")]
+    NotConstant {
+        /// Where the expression is.
+        span: Span,
+    },
+
+    /// A constant expression divides by zero.
+    #[error("at {span}: division by zero in constant expression")]
+    ConstantDivisionByZero {
+        /// Where the division is.
+        span: Span,
+    },
+
+    /// A constant expression overflows its type.
+    #[error("at {span}: constant expression overflows '{type_name}'")]
+    ConstantOverflow {
+        /// The type that cannot hold the value.
+        type_name: String,
+        /// Where the expression is.
+        span: Span,
+    },
+
+    /// Two case labels of a switch have the same value.
+    #[error("at {span}: duplicate case value {value}")]
+    DuplicateCase {
+        /// The repeated value, as written in a diagnostic.
+        value: String,
+        /// Where the second label is.
+        span: Span,
+    },
+
+    /// Control can reach the end of a function that returns a value.
+    #[error("at {span}: not all paths of '{function}' return a value")]
+    MissingReturn {
+        /// Qualified name of the function.
+        function: String,
+        /// Where the function is declared.
+        span: Span,
+    },
+
+    /// A private or protected member is used where it is not accessible.
+    #[error("at {span}: {visibility} member '{member}' is not accessible here")]
+    InaccessibleMember {
+        /// Qualified name of the member, e.g. `Player::hp`.
+        member: String,
+        /// The member's visibility.
+        visibility: String,
+        /// Where the member is used.
+        span: Span,
+    },
+
+    /// A class does not implement a method required by one of its interfaces.
+    #[error(
+        "at {span}: class '{class}' does not implement '{signature}' of interface '{interface}'"
+    )]
+    MissingInterfaceMethod {
+        /// Qualified name of the class.
+        class: String,
+        /// Qualified name of the interface declaring the method.
+        interface: String,
+        /// Signature of the missing method.
+        signature: String,
+        /// Where the class is declared.
+        span: Span,
+    },
+
+    /// A class derives from a class declared `final`.
+    #[error("at {span}: class '{class}' cannot derive from final class '{base}'")]
+    FinalClassDerived {
+        /// Qualified name of the derived class.
+        class: String,
+        /// Qualified name of the final class.
+        base: String,
+        /// Where the derived class is declared.
+        span: Span,
+    },
+
+    /// A method replaces a base class method declared `final`.
+    #[error("at {span}: method '{method}' overrides a final method of '{base}'")]
+    FinalMethodOverridden {
+        /// Qualified name of the method, e.g. `Player::tick`.
+        method: String,
+        /// Qualified name of the class declaring the final method.
+        base: String,
+        /// Where the method is declared.
+        span: Span,
+    },
+
+    /// A method marked `override` does not replace any inherited method.
+    #[error("at {span}: method '{method}' is marked override but overrides nothing")]
+    OverrideWithoutBase {
+        /// Qualified name of the method, e.g. `Player::tick`.
+        method: String,
+        /// Where the method is declared.
+        span: Span,
+    },
+
+    /// A lambda captures a variable it cannot keep, or one missing from
+    /// its capture list.
+    #[error("at {span}: lambda cannot capture '{name}': {reason}")]
+    InvalidCapture {
+        /// The captured variable.
+        name: String,
+        /// Why it cannot be captured.
+        reason: String,
+        /// Where the variable is captured.
+        span: Span,
+    },
+
+    /// Blocks are nested deeper than the compiler options allow.
+    #[error("at {span}: blocks nested deeper than the limit of {limit}")]
+    NestingTooDeep {
+        /// The deepest nesting allowed.
+        limit: usize,
+        /// Where the first block over the limit starts.
+        span: Span,
+    },
+
+    /// A value of an abstract class is declared or constructed.
+    #[error("at {span}: cannot instantiate abstract class '{class}'")]
+    AbstractInstantiation {
+        /// Qualified name of the abstract class.
+        class: String,
+        /// Where the class is instantiated.
+        span: Span,
+    },
 }
 
 impl CompilationError {
@@ -856,11 +1075,151 @@ impl CompilationError {
             CompilationError::UnknownMethod { span, .. } => *span,
             CompilationError::ArgumentCountMismatch { span, .. } => *span,
             CompilationError::InvalidCast { span, .. } => *span,
+            CompilationError::AmbiguousConversion { span, .. } => *span,
+            CompilationError::InvalidDelegate { span, .. } => *span,
+            CompilationError::CannotDeduceAuto { span, .. } => *span,
+            CompilationError::InvalidInitList { span, .. } => *span,
+            CompilationError::IncompatibleBranches { span, .. } => *span,
             CompilationError::NoDefaultConstructor { span, .. } => *span,
             CompilationError::NoBaseDefaultConstructor { span, .. } => *span,
             CompilationError::InvalidHandleType { span, .. } => *span,
             CompilationError::InvalidParameterType { span, .. } => *span,
             CompilationError::InvalidSwitchType { span, .. } => *span,
+            CompilationError::Inaccessible { span, .. } => *span,
+            CompilationError::DeniedWarning { span, .. } => *span,
+            CompilationError::NotConstant { span } => *span,
+            CompilationError::ConstantDivisionByZero { span } => *span,
+            CompilationError::ConstantOverflow { span, .. } => *span,
+            CompilationError::DuplicateCase { span, .. } => *span,
+            CompilationError::MissingReturn { span, .. } => *span,
+            CompilationError::MissingInterfaceMethod { span, .. } => *span,
+            CompilationError::InaccessibleMember { span, .. } => *span,
+            CompilationError::FinalClassDerived { span, .. } => *span,
+            CompilationError::FinalMethodOverridden { span, .. } => *span,
+            CompilationError::OverrideWithoutBase { span, .. } => *span,
+            CompilationError::AbstractInstantiation { span, .. } => *span,
+            CompilationError::InvalidCapture { span, .. } => *span,
+            CompilationError::NestingTooDeep { span, .. } => *span,
+        }
+    }
+
+    /// Get a stable code identifying the kind of error, e.g. `"UnknownType"`.
+    ///
+    /// Denied warnings keep their warning code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            CompilationError::UnknownType { .. } => "UnknownType",
+            CompilationError::UnknownFunction { .. } => "UnknownFunction",
+            CompilationError::UnknownVariable { .. } => "UnknownVariable",
+            CompilationError::AmbiguousSymbol { .. } => "AmbiguousSymbol",
+            CompilationError::TypeMismatch { .. } => "TypeMismatch",
+            CompilationError::InvalidOperation { .. } => "InvalidOperation",
+            CompilationError::CircularInheritance { .. } => "CircularInheritance",
+            CompilationError::DuplicateDefinition { .. } => "DuplicateDefinition",
+            CompilationError::VariableRedeclaration { .. } => "VariableRedeclaration",
+            CompilationError::Other { .. } => "Other",
+            CompilationError::NoStringFactory { .. } => "NoStringFactory",
+            CompilationError::TemplateArgCountMismatch { .. } => "TemplateArgCountMismatch",
+            CompilationError::NotATemplate { .. } => "NotATemplate",
+            CompilationError::TemplateValidationFailed { .. } => "TemplateValidationFailed",
+            CompilationError::FunctionNotFound { .. } => "FunctionNotFound",
+            CompilationError::Internal { .. } => "Internal",
+            CompilationError::NoMatchingOverload { .. } => "NoMatchingOverload",
+            CompilationError::AmbiguousOverload { .. } => "AmbiguousOverload",
+            CompilationError::NoOperator { .. } => "NoOperator",
+            CompilationError::NotAnLvalue { .. } => "NotAnLvalue",
+            CompilationError::CannotModifyConst { .. } => "CannotModifyConst",
+            CompilationError::ThisOutsideClass { .. } => "ThisOutsideClass",
+            CompilationError::UndefinedVariable { .. } => "UndefinedVariable",
+            CompilationError::UnknownField { .. } => "UnknownField",
+            CompilationError::UnknownMethod { .. } => "UnknownMethod",
+            CompilationError::ArgumentCountMismatch { .. } => "ArgumentCountMismatch",
+            CompilationError::InvalidCast { .. } => "InvalidCast",
+            CompilationError::AmbiguousConversion { .. } => "AmbiguousConversion",
+            CompilationError::InvalidDelegate { .. } => "InvalidDelegate",
+            CompilationError::CannotDeduceAuto { .. } => "CannotDeduceAuto",
+            CompilationError::InvalidInitList { .. } => "InvalidInitList",
+            CompilationError::IncompatibleBranches { .. } => "IncompatibleBranches",
+            CompilationError::NoDefaultConstructor { .. } => "NoDefaultConstructor",
+            CompilationError::NoBaseDefaultConstructor { .. } => "NoBaseDefaultConstructor",
+            CompilationError::InvalidHandleType { .. } => "InvalidHandleType",
+            CompilationError::InvalidParameterType { .. } => "InvalidParameterType",
+            CompilationError::InvalidSwitchType { .. } => "InvalidSwitchType",
+            CompilationError::Inaccessible { .. } => "Inaccessible",
+            CompilationError::DeniedWarning { code, .. } => code,
+            CompilationError::NotConstant { .. } => "NotConstant",
+            CompilationError::ConstantDivisionByZero { .. } => "ConstantDivisionByZero",
+            CompilationError::ConstantOverflow { .. } => "ConstantOverflow",
+            CompilationError::DuplicateCase { .. } => "DuplicateCase",
+            CompilationError::MissingReturn { .. } => "MissingReturn",
+            CompilationError::MissingInterfaceMethod { .. } => "MissingInterfaceMethod",
+            CompilationError::InaccessibleMember { .. } => "InaccessibleMember",
+            CompilationError::FinalClassDerived { .. } => "FinalClassDerived",
+            CompilationError::FinalMethodOverridden { .. } => "FinalMethodOverridden",
+            CompilationError::OverrideWithoutBase { .. } => "OverrideWithoutBase",
+            CompilationError::AbstractInstantiation { .. } => "AbstractInstantiation",
+            CompilationError::InvalidCapture { .. } => "InvalidCapture",
+            CompilationError::NestingTooDeep { .. } => "NestingTooDeep",
+        }
+    }
+
+    /// Get a mutable reference to the span where this error occurred.
+    ///
+    /// Returns `None` for errors without a source location.
+    pub fn span_mut(&mut self) -> Option<&mut Span> {
+        match self {
+            CompilationError::UnknownType { span, .. } => Some(span),
+            CompilationError::UnknownFunction { span, .. } => Some(span),
+            CompilationError::UnknownVariable { span, .. } => Some(span),
+            CompilationError::AmbiguousSymbol { span, .. } => Some(span),
+            CompilationError::TypeMismatch { span, .. } => Some(span),
+            CompilationError::InvalidOperation { span, .. } => Some(span),
+            CompilationError::CircularInheritance { span, .. } => Some(span),
+            CompilationError::DuplicateDefinition { span, .. } => Some(span),
+            CompilationError::VariableRedeclaration { new_span, .. } => Some(new_span),
+            CompilationError::Other { span, .. } => Some(span),
+            CompilationError::NoStringFactory { span } => Some(span),
+            CompilationError::TemplateArgCountMismatch { span, .. } => Some(span),
+            CompilationError::NotATemplate { span, .. } => Some(span),
+            CompilationError::TemplateValidationFailed { span, .. } => Some(span),
+            CompilationError::FunctionNotFound { span, .. } => Some(span),
+            CompilationError::Internal { .. } => None,
+            CompilationError::NoMatchingOverload { span, .. } => Some(span),
+            CompilationError::AmbiguousOverload { span, .. } => Some(span),
+            CompilationError::NoOperator { span, .. } => Some(span),
+            CompilationError::NotAnLvalue { span } => Some(span),
+            CompilationError::CannotModifyConst { span, .. } => Some(span),
+            CompilationError::ThisOutsideClass { span } => Some(span),
+            CompilationError::UndefinedVariable { span, .. } => Some(span),
+            CompilationError::UnknownField { span, .. } => Some(span),
+            CompilationError::UnknownMethod { span, .. } => Some(span),
+            CompilationError::ArgumentCountMismatch { span, .. } => Some(span),
+            CompilationError::InvalidCast { span, .. } => Some(span),
+            CompilationError::AmbiguousConversion { span, .. } => Some(span),
+            CompilationError::InvalidDelegate { span, .. } => Some(span),
+            CompilationError::CannotDeduceAuto { span, .. } => Some(span),
+            CompilationError::InvalidInitList { span, .. } => Some(span),
+            CompilationError::IncompatibleBranches { span, .. } => Some(span),
+            CompilationError::NoDefaultConstructor { span, .. } => Some(span),
+            CompilationError::NoBaseDefaultConstructor { span, .. } => Some(span),
+            CompilationError::InvalidHandleType { span, .. } => Some(span),
+            CompilationError::InvalidParameterType { span, .. } => Some(span),
+            CompilationError::InvalidSwitchType { span, .. } => Some(span),
+            CompilationError::Inaccessible { span, .. } => Some(span),
+            CompilationError::DeniedWarning { span, .. } => Some(span),
+            CompilationError::NotConstant { span } => Some(span),
+            CompilationError::ConstantDivisionByZero { span } => Some(span),
+            CompilationError::ConstantOverflow { span, .. } => Some(span),
+            CompilationError::DuplicateCase { span, .. } => Some(span),
+            CompilationError::MissingReturn { span, .. } => Some(span),
+            CompilationError::MissingInterfaceMethod { span, .. } => Some(span),
+            CompilationError::InaccessibleMember { span, .. } => Some(span),
+            CompilationError::FinalClassDerived { span, .. } => Some(span),
+            CompilationError::FinalMethodOverridden { span, .. } => Some(span),
+            CompilationError::OverrideWithoutBase { span, .. } => Some(span),
+            CompilationError::AbstractInstantiation { span, .. } => Some(span),
+            CompilationError::InvalidCapture { span, .. } => Some(span),
+            CompilationError::NestingTooDeep { span, .. } => Some(span),
         }
     }
 }
@@ -930,6 +1289,28 @@ pub enum RuntimeError {
         message: String,
     },
 
+    /// A handle was passed as a type its object is not.
+    #[error("handle of type '{actual}' cannot be passed as '{expected}'")]
+    HandleTypeMismatch {
+        /// The parameter type.
+        expected: String,
+        /// The class of the object.
+        actual: String,
+    },
+
+    /// A handle was passed to another unit as a type that is not shared.
+    #[error(
+        "handle of type '{type_name}' cannot be passed from unit {from} to unit {to}: the type is not shared"
+    )]
+    UnsharedHandle {
+        /// The parameter type.
+        type_name: String,
+        /// The unit that created the object.
+        from: crate::UnitId,
+        /// The unit the handle is passed to.
+        to: crate::UnitId,
+    },
+
     /// A global property access error.
     #[error("property error: {0}")]
     Property(#[from] crate::PropertyError),
@@ -1134,6 +1515,20 @@ mod tests {
         assert_eq!(err.span(), span);
     }
 
+    #[test]
+    fn compilation_error_span_mut() {
+        let mut err = CompilationError::NotAnLvalue {
+            span: Span::new(5, 10, 8),
+        };
+        *err.span_mut().unwrap() = Span::new(2, 1, 0);
+        assert_eq!(err.span(), Span::new(2, 1, 0));
+
+        let mut internal = CompilationError::Internal {
+            message: "bug".to_string(),
+        };
+        assert!(internal.span_mut().is_none());
+    }
+
     #[test]
     fn runtime_error_display() {
         let err = RuntimeError::TypeMismatch {
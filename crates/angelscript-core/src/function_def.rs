@@ -28,8 +28,8 @@
 //! assert_eq!(func.params.len(), 2);
 //! ```
 
-use std::cell::OnceCell;
 use std::fmt;
+use std::sync::OnceLock;
 
 use crate::types::Visibility;
 use crate::{DataType, TypeHash};
@@ -125,6 +125,10 @@ pub struct FunctionTraits {
     pub is_const: bool,
     /// This constructor is explicit (cannot be used for implicit conversions).
     pub is_explicit: bool,
+    /// This function has no side effects and its result depends only on its
+    /// arguments (and object, for methods), so the compiler may evaluate
+    /// calls with constant arguments and reuse repeated calls.
+    pub is_pure: bool,
     /// If this is an auto-generated method, specifies which type.
     pub auto_generated: Option<AutoGeneratedMethod>,
 }
@@ -140,6 +144,7 @@ impl FunctionTraits {
             is_abstract: false,
             is_const: false,
             is_explicit: false,
+            is_pure: false,
             auto_generated: None,
         }
     }
@@ -154,6 +159,7 @@ impl FunctionTraits {
             is_abstract: false,
             is_const: false,
             is_explicit: false,
+            is_pure: false,
             auto_generated: None,
         }
     }
@@ -168,6 +174,7 @@ impl FunctionTraits {
             is_abstract: false,
             is_const: false,
             is_explicit: false,
+            is_pure: false,
             auto_generated: None,
         }
     }
@@ -182,6 +189,7 @@ impl FunctionTraits {
             is_abstract: false,
             is_const: true,
             is_explicit: false,
+            is_pure: false,
             auto_generated: None,
         }
     }
@@ -196,10 +204,17 @@ impl FunctionTraits {
             is_abstract: false,
             is_const: false,
             is_explicit: false,
+            is_pure: false,
             auto_generated: None,
         }
     }
 
+    /// Mark the function pure.
+    pub const fn with_pure(mut self) -> Self {
+        self.is_pure = true;
+        self
+    }
+
     /// Set the auto-generated type.
     pub const fn with_auto_generated(mut self, auto: AutoGeneratedMethod) -> Self {
         self.auto_generated = Some(auto);
@@ -237,7 +252,7 @@ pub struct FunctionDef {
     /// True if this function accepts variadic arguments.
     pub is_variadic: bool,
     /// Cached qualified name (computed on first access).
-    cached_qualified_name: OnceCell<String>,
+    cached_qualified_name: OnceLock<String>,
 }
 
 impl PartialEq for FunctionDef {
@@ -283,7 +298,7 @@ impl FunctionDef {
             visibility,
             template_params: Vec::new(),
             is_variadic: false,
-            cached_qualified_name: OnceCell::new(),
+            cached_qualified_name: OnceLock::new(),
         }
     }
 
@@ -313,7 +328,7 @@ impl FunctionDef {
             visibility,
             template_params,
             is_variadic: false,
-            cached_qualified_name: OnceCell::new(),
+            cached_qualified_name: OnceLock::new(),
         }
     }
 
@@ -369,6 +384,11 @@ impl FunctionDef {
         self.traits.is_const
     }
 
+    /// Check if this function is pure.
+    pub fn is_pure(&self) -> bool {
+        self.traits.is_pure
+    }
+
     /// Check if this function is virtual.
     pub fn is_virtual(&self) -> bool {
         self.traits.is_virtual
@@ -82,6 +82,7 @@ mod error;
 mod function_def;
 mod span;
 pub mod type_hash;
+mod type_table;
 pub mod types;
 
 // New types for unified type registry
@@ -112,14 +113,15 @@ pub use type_hash::{TypeHash, hash_constants, primitives};
 
 // --- Type System ---
 pub use data_type::{DataType, RefModifier};
+pub use type_table::{TypeModifiers, TypeRef, TypeTable};
 pub use types::{MethodSignature, PrimitiveKind, ReferenceKind, TypeKind, Visibility};
 
 // --- Registry Entries ---
 pub use entries::{
     AutoGenKind, ClassEntry, ConstantValue, EnumEntry, EnumValue, FuncdefEntry, FunctionEntry,
     FunctionImpl, FunctionSource, GlobalPropertyAccessor, GlobalPropertyEntry, GlobalPropertyImpl,
-    InterfaceEntry, IntoGlobalProperty, PrimitiveEntry, PropertyEntry, PropertyError,
-    TemplateParamEntry, TypeEntry, TypeSource,
+    ITableMap, InterfaceEntry, IntoGlobalProperty, PrimitiveEntry, PropertyEntry, PropertyError,
+    TemplateParamEntry, TypeEntry, TypeSource, VTable,
 };
 
 // --- Functions & Operators ---
@@ -130,11 +132,12 @@ pub use operator::{ConversionEntry, Operator};
 pub use behaviors::{BehaviorValidationResult, ForbiddenBehavior, ListBehavior, TypeBehaviors};
 
 // --- Runtime / FFI ---
-pub use convert::{FromDynamic, IntoDynamic};
+pub use convert::{FromDynamic, IntoArgs, IntoDynamic};
 pub use list_buffer::{ListBuffer, ListPattern, TupleListBuffer};
-pub use native_error::{ConversionError, NativeError};
+pub use native_error::{ConversionError, NativeError, catch_native_panic};
 pub use runtime::{
-    CallContext, Dynamic, FuncdefHandle, NativeCallable, NativeFn, ObjectHandle, ObjectHeap,
+    CallArgs, CallContext, Dynamic, FuncdefHandle, FunctionObject, MemoryBudget, NativeCallable,
+    NativeFn, ObjectHandle, ObjectHeap, OutOfMemory, ScriptCallable, ScriptDispatch, ScriptProxy,
 };
 pub use template::{TemplateInstanceInfo, TemplateValidation};
 
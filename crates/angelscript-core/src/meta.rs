@@ -186,6 +186,9 @@ pub struct FunctionMeta {
     pub is_const: bool,
     /// True if this is a property accessor.
     pub is_property: bool,
+    /// True if the function has no side effects (see
+    /// [`FunctionTraits::is_pure`](crate::FunctionTraits::is_pure)).
+    pub is_pure: bool,
     /// Property name for accessors (inferred from get_/set_ prefix or explicit).
     pub property_name: Option<&'static str>,
     /// True if using generic calling convention.
@@ -525,6 +528,7 @@ mod tests {
             behavior: None,
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -551,6 +555,7 @@ mod tests {
             behavior: Some(Behavior::Constructor),
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -574,6 +579,7 @@ mod tests {
             behavior: Some(Behavior::Operator(Operator::Add)),
             is_const: true,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -608,6 +614,7 @@ mod tests {
             behavior: None,
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: true,
             list_pattern: None,
@@ -738,6 +745,7 @@ mod tests {
             behavior: None,
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: true,
             list_pattern: None,
@@ -1,5 +1,8 @@
 //! Error types for native function execution.
 
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
 use thiserror::Error;
 
 use crate::TypeHash;
@@ -74,6 +77,16 @@ pub enum NativeError {
     /// Generic native error
     #[error("native error: {message}")]
     Other { message: String },
+
+    /// A script exception unwinding through the native function
+    ///
+    /// Raised with [`CallContext::set_exception`], or returned by a call
+    /// back into script that ended in an uncaught exception. The calling
+    /// script frame unwinds it to its nearest `try` block.
+    ///
+    /// [`CallContext::set_exception`]: crate::CallContext::set_exception
+    #[error("script exception: {message}")]
+    Exception { message: String },
 }
 
 impl NativeError {
@@ -90,6 +103,41 @@ impl NativeError {
             message: message.into(),
         }
     }
+
+    /// Create a script exception.
+    pub fn exception(message: impl Into<String>) -> Self {
+        NativeError::Exception {
+            message: message.into(),
+        }
+    }
+
+    /// Check if this is a script exception, as opposed to a failure of the
+    /// native function itself.
+    pub fn is_exception(&self) -> bool {
+        matches!(self, NativeError::Exception { .. })
+    }
+
+    /// Create a panic error from the payload of a caught panic.
+    pub fn from_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => match payload.downcast_ref::<&'static str>() {
+                Some(message) => message.to_string(),
+                None => "unknown panic payload".to_string(),
+            },
+        };
+        NativeError::Panic { message }
+    }
+}
+
+/// Run a native callback, converting a panic into [`NativeError::Panic`].
+///
+/// This is the boundary between script execution and host code: a panic in a
+/// buggy binding becomes a script exception instead of unwinding through the
+/// VM. The panic hook still runs, so the panic is reported as usual.
+pub fn catch_native_panic<R>(f: impl FnOnce() -> Result<R, NativeError>) -> Result<R, NativeError> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(NativeError::from_panic(payload)))
 }
 
 #[cfg(test)]
@@ -194,6 +242,29 @@ mod tests {
         assert!(err.to_string().contains("panicked"));
     }
 
+    #[test]
+    fn native_panics_are_caught() {
+        let result: Result<(), _> = catch_native_panic(|| panic!("bad binding {}", 7));
+        assert!(matches!(
+            result,
+            Err(NativeError::Panic { message }) if message == "bad binding 7"
+        ));
+
+        let result: Result<(), _> = catch_native_panic(|| panic!("static message"));
+        assert!(matches!(
+            result,
+            Err(NativeError::Panic { message }) if message == "static message"
+        ));
+
+        let result: Result<(), _> = catch_native_panic(|| std::panic::panic_any(42));
+        assert!(matches!(
+            result,
+            Err(NativeError::Panic { message }) if message == "unknown panic payload"
+        ));
+
+        assert!(matches!(catch_native_panic(|| Ok(1)), Ok(1)));
+    }
+
     #[test]
     fn native_error_other() {
         let err = NativeError::other("generic error");
@@ -335,6 +335,29 @@ impl Operator {
         }
     }
 
+    /// Get the reverse form of a binary operator (`opAdd` -> `opAdd_r`).
+    ///
+    /// The reverse form is looked up on the right operand when the left
+    /// operand has no matching overload. Returns `None` for operators
+    /// without a reverse form.
+    pub const fn reversed(&self) -> Option<Operator> {
+        Some(match self {
+            Operator::Add => Operator::AddR,
+            Operator::Sub => Operator::SubR,
+            Operator::Mul => Operator::MulR,
+            Operator::Div => Operator::DivR,
+            Operator::Mod => Operator::ModR,
+            Operator::Pow => Operator::PowR,
+            Operator::And => Operator::AndR,
+            Operator::Or => Operator::OrR,
+            Operator::Xor => Operator::XorR,
+            Operator::Shl => Operator::ShlR,
+            Operator::Shr => Operator::ShrR,
+            Operator::Ushr => Operator::UshrR,
+            _ => return None,
+        })
+    }
+
     /// Check if this is an assignment operator.
     pub const fn is_assignment(&self) -> bool {
         matches!(
@@ -485,6 +508,14 @@ mod tests {
         assert_eq!(Operator::ForValue.method_name(), "opForValue");
     }
 
+    #[test]
+    fn reversed() {
+        assert_eq!(Operator::Add.reversed(), Some(Operator::AddR));
+        assert_eq!(Operator::Ushr.reversed(), Some(Operator::UshrR));
+        assert_eq!(Operator::AddR.reversed(), None);
+        assert_eq!(Operator::Equals.reversed(), None);
+    }
+
     #[test]
     fn is_assignment() {
         assert!(Operator::Assign.is_assignment());
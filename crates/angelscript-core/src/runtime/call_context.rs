@@ -3,10 +3,11 @@
 use std::any::Any;
 use std::fmt;
 
+use crate::TypeHash;
 use crate::convert::{FromDynamic, IntoDynamic};
-use crate::native_error::NativeError;
+use crate::native_error::{ConversionError, NativeError};
 
-use super::{Dynamic, ObjectHeap};
+use super::{Dynamic, ObjectHeap, ScriptCallable};
 
 /// Context for native function calls.
 ///
@@ -29,6 +30,44 @@ use super::{Dynamic, ObjectHeap};
 /// ```ignore
 /// ctx.set_return(x + y);
 /// ```
+///
+/// ## Variable-Type Arguments
+///
+/// Arguments of `?&in` and `?&out` parameters can have any type, so the VM
+/// passes the type of each one along (see [`with_arg_types`]). Use
+/// `arg_type_id()` to find what was passed before converting it:
+///
+/// ```ignore
+/// if ctx.arg_type_id(1)? == primitives::INT32 {
+///     let value: i32 = ctx.arg(1)?;
+/// }
+/// ```
+///
+/// [`with_arg_types`]: CallContext::with_arg_types
+///
+/// ## Exceptions
+///
+/// Use `set_exception()` to raise a script exception from a native function.
+/// It is raised when the function returns, so the function can still clean
+/// up first; the return value is discarded.
+///
+/// A native function that calls back into script (through a
+/// [`ScriptCallable`] or a [`ScriptDispatch`](super::ScriptDispatch)) gets
+/// [`NativeError::Exception`] when the script raises an exception it does
+/// not catch. The script frames up to the native one have already unwound.
+/// The native function observes the failure there, releases what it holds
+/// on the [`ObjectHeap`], and returns the error so the exception continues
+/// to the script frame that called it:
+///
+/// ```ignore
+/// let cb = ctx.arg_callable(0)?;
+/// let result = vm.call(&cb, args);
+/// cb.release(ctx.heap_mut());
+/// result?;
+/// ```
+///
+/// Returning `Ok` instead swallows the exception. Any other error, or a
+/// panic, also becomes a script exception in the calling frame.
 pub struct CallContext<'vm> {
     /// VM stack/argument slots
     slots: &'vm mut [Dynamic],
@@ -38,6 +77,10 @@ pub struct CallContext<'vm> {
     return_slot: &'vm mut Dynamic,
     /// Object heap for reference type access
     heap: &'vm mut ObjectHeap,
+    /// Types of the arguments, by argument index, if the VM passed them
+    arg_types: &'vm [TypeHash],
+    /// Exception raised by the native function
+    exception: Option<String>,
 }
 
 impl<'vm> CallContext<'vm> {
@@ -60,9 +103,38 @@ impl<'vm> CallContext<'vm> {
             arg_offset,
             return_slot,
             heap,
+            arg_types: &[],
+            exception: None,
         }
     }
 
+    /// Set the types of the arguments, by argument index.
+    ///
+    /// The VM passes them for functions with variable-type (`?`)
+    /// parameters. A null handle argument has type [`primitives::NULL`].
+    ///
+    /// [`primitives::NULL`]: crate::primitives::NULL
+    pub fn with_arg_types(mut self, arg_types: &'vm [TypeHash]) -> Self {
+        self.arg_types = arg_types;
+        self
+    }
+
+    /// Get the type of an argument passed to a variable-type (`?`)
+    /// parameter.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index is out of bounds or the VM passed no
+    /// type for the argument.
+    pub fn arg_type_id(&self, index: usize) -> Result<TypeHash, NativeError> {
+        self.arg_slot(index)?;
+        self.arg_types
+            .get(index)
+            .copied()
+            .filter(|ty| !ty.is_empty())
+            .ok_or_else(|| NativeError::other(format!("no type passed for argument {}", index)))
+    }
+
     /// Get the number of arguments (excluding `this` for methods).
     pub fn arg_count(&self) -> usize {
         self.slots.len().saturating_sub(self.arg_offset)
@@ -106,6 +178,58 @@ impl<'vm> CallContext<'vm> {
         T::from_dynamic(slot).map_err(NativeError::Conversion)
     }
 
+    /// Get a funcdef argument as a [`ScriptCallable`] that can be stored.
+    ///
+    /// The returned callable holds its own reference to the function handle
+    /// or delegate, so it stays valid after this call returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument is null, not a function handle, or
+    /// refers to a freed object.
+    pub fn arg_callable(&mut self, index: usize) -> Result<ScriptCallable, NativeError> {
+        let handle = match self.arg_slot(index)? {
+            Dynamic::Object(handle) => *handle,
+            Dynamic::NullHandle => {
+                return Err(ConversionError::NullHandle {
+                    target_type: "ScriptCallable",
+                }
+                .into());
+            }
+            other => {
+                return Err(ConversionError::TypeMismatch {
+                    expected: "function handle",
+                    actual: other.type_name(),
+                }
+                .into());
+            }
+        };
+
+        ScriptCallable::retain(self.heap, handle).ok_or(NativeError::StaleHandle {
+            index: handle.index,
+        })
+    }
+
+    /// Get a funcdef argument that may be null, like
+    /// [`arg_callable`](Self::arg_callable).
+    ///
+    /// Returns `None` for a null handle, so natives can accept optional
+    /// callbacks (`Callback@ cb = null`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument is not a function handle or refers
+    /// to a freed object.
+    pub fn arg_callable_or_null(
+        &mut self,
+        index: usize,
+    ) -> Result<Option<ScriptCallable>, NativeError> {
+        if self.arg_slot(index)?.is_null() {
+            return Ok(None);
+        }
+        self.arg_callable(index).map(Some)
+    }
+
     /// Set the return value from a raw slot.
     pub fn set_return_slot(&mut self, slot: Dynamic) {
         *self.return_slot = slot;
@@ -127,6 +251,30 @@ impl<'vm> CallContext<'vm> {
         *self.return_slot = value.into_dynamic();
     }
 
+    /// Raise a script exception once the native function returns.
+    ///
+    /// A later call replaces the message.
+    pub fn set_exception(&mut self, message: impl Into<String>) {
+        self.exception = Some(message.into());
+    }
+
+    /// Get the message of the exception raised with `set_exception()`.
+    pub fn exception(&self) -> Option<&str> {
+        self.exception.as_deref()
+    }
+
+    /// Combine the result of a native function with the exception it raised.
+    ///
+    /// Called by the VM when the function returns. An error returned by the
+    /// function takes precedence over an exception it raised.
+    pub fn finish(self, result: Result<(), NativeError>) -> Result<(), NativeError> {
+        result?;
+        match self.exception {
+            Some(message) => Err(NativeError::Exception { message }),
+            None => Ok(()),
+        }
+    }
+
     /// Get an immutable reference to `this` for method calls.
     ///
     /// This extracts a reference to the receiver object from slot 0.
@@ -173,38 +321,54 @@ impl<'vm> CallContext<'vm> {
     /// - Slot 0 is not a Native value
     /// - The native value's type doesn't match `T`
     pub fn this_mut<T: Any>(&mut self) -> Result<&mut T, NativeError> {
-        if self.slots.is_empty() {
-            return Err(NativeError::invalid_this("no slots available"));
+        match self.slots.first_mut() {
+            Some(slot) => receiver_mut(slot, self.heap),
+            None => Err(NativeError::invalid_this("no slots available")),
         }
+    }
 
-        // We need to handle Object handles specially since they reference the heap
-        match &self.slots[0] {
-            Dynamic::Object(handle) => {
-                let handle = *handle;
-                self.heap.get_mut::<T>(handle).ok_or_else(|| {
-                    NativeError::invalid_this(format!(
-                        "object type mismatch or stale handle for {}",
-                        std::any::type_name::<T>()
-                    ))
-                })
-            }
-            Dynamic::Native(_) => {
-                // For Native, we can access it directly
-                match &mut self.slots[0] {
-                    Dynamic::Native(boxed) => boxed.downcast_mut::<T>().ok_or_else(|| {
-                        NativeError::invalid_this(format!(
-                            "type mismatch: expected {}, got different type",
-                            std::any::type_name::<T>()
-                        ))
-                    }),
-                    _ => unreachable!(),
-                }
-            }
-            other => Err(NativeError::invalid_this(format!(
-                "expected native or object, got {}",
-                other.type_name()
-            ))),
-        }
+    /// Get a mutable reference to `this` together with the arguments.
+    ///
+    /// Unlike [`this_mut`](Self::this_mut), the arguments stay accessible
+    /// while `this` is borrowed, so a method taking `&mut self` can also
+    /// borrow its arguments (e.g. `fn add_from(&mut self, other: &Self)`).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let (this, args) = ctx.split_this_mut::<Counter>()?;
+    /// this.value += args.native::<Counter>(0)?.value;
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`this_mut`](Self::this_mut).
+    pub fn split_this_mut<T: Any>(&mut self) -> Result<(&mut T, CallArgs<'_>), NativeError> {
+        let offset = self.arg_offset.min(self.slots.len());
+        let (receiver, args) = self.slots.split_at_mut(offset);
+        let this = match receiver.first_mut() {
+            Some(slot) => receiver_mut(slot, self.heap)?,
+            None => return Err(NativeError::invalid_this("no slots available")),
+        };
+        Ok((this, CallArgs { slots: args }))
+    }
+
+    /// Get raw pointers to argument slots, for the generic calling
+    /// convention's `*mut ()` parameters.
+    ///
+    /// Each pointer points to the argument's [`Dynamic`] slot. All pointers
+    /// are derived from the same borrow of the slots, so they may be used
+    /// together until the context is accessed again.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an index is out of bounds.
+    pub fn arg_ptrs<const N: usize>(
+        &mut self,
+        indices: [usize; N],
+    ) -> Result<[*mut (); N], NativeError> {
+        let offset = self.arg_offset.min(self.slots.len());
+        slot_ptrs(&mut self.slots[offset..], indices)
     }
 
     /// Get access to the object heap.
@@ -218,11 +382,158 @@ impl<'vm> CallContext<'vm> {
     }
 }
 
+/// Resolve the receiver in `slot`, which is either boxed in place or an
+/// object on the heap.
+fn receiver_mut<'a, T: Any>(
+    slot: &'a mut Dynamic,
+    heap: &'a mut ObjectHeap,
+) -> Result<&'a mut T, NativeError> {
+    match slot {
+        Dynamic::Object(handle) => heap.get_mut::<T>(*handle).ok_or_else(|| {
+            NativeError::invalid_this(format!(
+                "object type mismatch or stale handle for {}",
+                std::any::type_name::<T>()
+            ))
+        }),
+        Dynamic::Native(boxed) => boxed.downcast_mut::<T>().ok_or_else(|| {
+            NativeError::invalid_this(format!(
+                "type mismatch: expected {}, got different type",
+                std::any::type_name::<T>()
+            ))
+        }),
+        other => Err(NativeError::invalid_this(format!(
+            "expected native or object, got {}",
+            other.type_name()
+        ))),
+    }
+}
+
+/// Get pointers to several slots.
+fn slot_ptrs<const N: usize>(
+    slots: &mut [Dynamic],
+    indices: [usize; N],
+) -> Result<[*mut (); N], NativeError> {
+    let count = slots.len();
+    if let Some(&index) = indices.iter().find(|&&index| index >= count) {
+        return Err(NativeError::ArgumentIndexOutOfBounds { index, count });
+    }
+    // Offsetting a single base pointer keeps the provenance of the whole
+    // slice; borrowing each slot separately would invalidate the earlier
+    // pointers.
+    let base = slots.as_mut_ptr();
+    Ok(indices.map(|index| base.wrapping_add(index).cast::<()>()))
+}
+
+/// Argument slots of a method call, borrowed separately from `this`.
+///
+/// Obtained from [`CallContext::split_this_mut`]. Indices are argument
+/// indices, as for [`CallContext::arg`].
+pub struct CallArgs<'a> {
+    slots: &'a mut [Dynamic],
+}
+
+impl CallArgs<'_> {
+    /// Get the number of arguments.
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Check if there are no arguments.
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Get a raw reference to an argument slot.
+    pub fn slot(&self, index: usize) -> Result<&Dynamic, NativeError> {
+        let count = self.slots.len();
+        self.slots
+            .get(index)
+            .ok_or(NativeError::ArgumentIndexOutOfBounds { index, count })
+    }
+
+    /// Get a mutable reference to an argument slot.
+    pub fn slot_mut(&mut self, index: usize) -> Result<&mut Dynamic, NativeError> {
+        let count = self.slots.len();
+        self.slots
+            .get_mut(index)
+            .ok_or(NativeError::ArgumentIndexOutOfBounds { index, count })
+    }
+
+    /// Get a typed argument value.
+    pub fn arg<T: FromDynamic>(&self, index: usize) -> Result<T, NativeError> {
+        T::from_dynamic(self.slot(index)?).map_err(NativeError::Conversion)
+    }
+
+    /// Borrow a native value argument.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument is not a native value of type `T`.
+    pub fn native<T: Any>(&self, index: usize) -> Result<&T, NativeError> {
+        match self.slot(index)? {
+            Dynamic::Native(boxed) => boxed
+                .downcast_ref::<T>()
+                .ok_or_else(|| native_mismatch::<T>(index)),
+            other => Err(ConversionError::TypeMismatch {
+                expected: "native",
+                actual: other.type_name(),
+            }
+            .into()),
+        }
+    }
+
+    /// Mutably borrow a native value argument.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the argument is not a native value of type `T`.
+    pub fn native_mut<T: Any>(&mut self, index: usize) -> Result<&mut T, NativeError> {
+        match self.slot_mut(index)? {
+            Dynamic::Native(boxed) => boxed
+                .downcast_mut::<T>()
+                .ok_or_else(|| native_mismatch::<T>(index)),
+            other => Err(ConversionError::TypeMismatch {
+                expected: "native",
+                actual: other.type_name(),
+            }
+            .into()),
+        }
+    }
+
+    /// Get raw pointers to argument slots.
+    ///
+    /// See [`CallContext::arg_ptrs`].
+    pub fn ptrs<const N: usize>(
+        &mut self,
+        indices: [usize; N],
+    ) -> Result<[*mut (); N], NativeError> {
+        slot_ptrs(self.slots, indices)
+    }
+}
+
+fn native_mismatch<T>(index: usize) -> NativeError {
+    NativeError::other(format!(
+        "failed to downcast argument {} to {}",
+        index,
+        std::any::type_name::<T>()
+    ))
+}
+
+impl fmt::Debug for CallArgs<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CallArgs")
+            .field("len", &self.slots.len())
+            .finish()
+    }
+}
+
 impl fmt::Debug for CallContext<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("CallContext")
             .field("arg_count", &self.arg_count())
             .field("arg_offset", &self.arg_offset)
+            .field("arg_types", &self.arg_types)
+            .field("exception", &self.exception)
             .finish()
     }
 }
@@ -0,0 +1,28 @@
+//! Dispatch of Rust trait calls to script implementations.
+//!
+//! `#[angelscript::interface(proxy)]` generates a proxy type implementing the
+//! Rust trait by forwarding each method to a [`ScriptDispatch`]. The host
+//! obtains a dispatcher for a script object that implements the matching
+//! script interface, and the proxy turns it back into a normal trait object.
+
+use crate::TypeHash;
+use crate::native_error::NativeError;
+
+use super::Dynamic;
+
+/// Calls methods on a script object by name.
+pub trait ScriptDispatch {
+    /// Call `method` with `args`, returning the script's return value.
+    fn dispatch(&mut self, method: &str, args: Vec<Dynamic>) -> Result<Dynamic, NativeError>;
+}
+
+/// A Rust trait implementation backed by a script object.
+///
+/// Implemented by the proxy types generated for `#[interface(proxy)]` traits.
+pub trait ScriptProxy<'a>: Sized {
+    /// Type hash of the script interface the proxy implements.
+    fn interface_hash() -> TypeHash;
+
+    /// Wrap a dispatcher for an object implementing the interface.
+    fn from_dispatch(dispatch: Box<dyn ScriptDispatch + 'a>) -> Self;
+}
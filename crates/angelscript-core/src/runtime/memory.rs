@@ -0,0 +1,163 @@
+//! Memory budget for script-triggered allocations.
+//!
+//! Allocations a script can grow without bound (object construction,
+//! string concatenation, array resizing) go through a [`MemoryBudget`]
+//! instead of the infallible standard library growth methods. When the
+//! allocator fails, or the allocation would exceed the configured limit,
+//! the [`OutOfMemory`] policy decides what happens:
+//!
+//! - [`OutOfMemory::Exception`] raises a script exception, which scripts
+//!   can catch and hosts receive as an error from the call.
+//! - [`OutOfMemory::Abort`] aborts the process, as the standard library does.
+//!
+//! The budget counts bytes requested through it, not the true heap usage of
+//! the process.
+
+use std::alloc::Layout;
+
+use crate::NativeError;
+
+/// What happens when a script-triggered allocation fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum OutOfMemory {
+    /// Raise a script exception that unwinds to the nearest handler.
+    #[default]
+    Exception,
+    /// Abort the process.
+    Abort,
+}
+
+/// Tracks the bytes allocated on behalf of scripts against an optional limit.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBudget {
+    policy: OutOfMemory,
+    limit: Option<usize>,
+    used: usize,
+}
+
+impl MemoryBudget {
+    /// Create a budget with `policy`, allowing at most `limit` bytes if set.
+    pub fn new(policy: OutOfMemory, limit: Option<usize>) -> Self {
+        Self {
+            policy,
+            limit,
+            used: 0,
+        }
+    }
+
+    /// Get what happens when an allocation fails.
+    pub fn policy(&self) -> OutOfMemory {
+        self.policy
+    }
+
+    /// Get the maximum number of bytes, if limited.
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// Get the number of bytes currently charged to the budget.
+    pub fn used(&self) -> usize {
+        self.used
+    }
+
+    /// Charge `bytes` to the budget.
+    ///
+    /// # Errors
+    ///
+    /// Fails following the policy if the limit would be exceeded.
+    pub fn try_reserve(&mut self, bytes: usize) -> Result<(), NativeError> {
+        match self.used.checked_add(bytes) {
+            Some(total) if self.limit.is_none_or(|limit| total <= limit) => {
+                self.used = total;
+                Ok(())
+            }
+            _ => Err(self.out_of_memory(bytes)),
+        }
+    }
+
+    /// Return `bytes` to the budget.
+    pub fn release(&mut self, bytes: usize) {
+        self.used = self.used.saturating_sub(bytes);
+    }
+
+    /// Grow `string` to hold `additional` more bytes.
+    ///
+    /// # Errors
+    ///
+    /// Fails following the policy if the budget is exhausted or the
+    /// allocator fails.
+    pub fn reserve_string(
+        &mut self,
+        string: &mut String,
+        additional: usize,
+    ) -> Result<(), NativeError> {
+        let (len, capacity) = (string.len(), string.capacity());
+        self.grow(len, capacity, additional, 1, || {
+            string
+                .try_reserve(additional)
+                .ok()
+                .map(|_| string.capacity())
+        })
+    }
+
+    /// Grow `vec` to hold `additional` more elements.
+    ///
+    /// # Errors
+    ///
+    /// Fails following the policy if the budget is exhausted or the
+    /// allocator fails.
+    pub fn reserve_vec<T>(
+        &mut self,
+        vec: &mut Vec<T>,
+        additional: usize,
+    ) -> Result<(), NativeError> {
+        let (len, capacity) = (vec.len(), vec.capacity());
+        self.grow(len, capacity, additional, size_of::<T>(), || {
+            vec.try_reserve(additional).ok().map(|_| vec.capacity())
+        })
+    }
+
+    /// Charge growing a buffer of `len` out of `capacity` elements of `size`
+    /// bytes by `additional` elements, then allocate with `reserve`, which
+    /// returns the new capacity.
+    ///
+    /// The allocator may round the capacity up, so the budget is settled
+    /// against the capacity actually allocated.
+    fn grow(
+        &mut self,
+        len: usize,
+        capacity: usize,
+        additional: usize,
+        size: usize,
+        reserve: impl FnOnce() -> Option<usize>,
+    ) -> Result<(), NativeError> {
+        let needed = len
+            .checked_add(additional)
+            .and_then(|count| count.saturating_sub(capacity).checked_mul(size))
+            .ok_or_else(|| self.out_of_memory(usize::MAX))?;
+        self.try_reserve(needed)?;
+        self.release(needed);
+        match reserve() {
+            Some(grown) => {
+                let charged = grown.saturating_sub(capacity).saturating_mul(size);
+                self.used = self.used.saturating_add(charged);
+                Ok(())
+            }
+            None => Err(self.out_of_memory(needed)),
+        }
+    }
+
+    /// The error for a failed allocation of `bytes`, or an abort under
+    /// [`OutOfMemory::Abort`].
+    pub fn out_of_memory(&self, bytes: usize) -> NativeError {
+        match self.policy {
+            OutOfMemory::Exception => {
+                NativeError::exception(format!("out of memory: cannot allocate {} bytes", bytes))
+            }
+            OutOfMemory::Abort => match Layout::from_size_align(bytes.max(1), 1) {
+                Ok(layout) => std::alloc::handle_alloc_error(layout),
+                Err(_) => std::process::abort(),
+            },
+        }
+    }
+}
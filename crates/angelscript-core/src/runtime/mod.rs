@@ -9,24 +9,34 @@
 //! - [`NativeFn`]: Type-erased callable wrapper for FFI functions
 //! - [`CallContext`]: Bridge between VM and Rust for function calls
 //! - [`ObjectHeap`]: Generational arena for reference-counted objects
+//! - [`MemoryBudget`]: Limit and failure policy of script-triggered allocations
+//! - [`ScriptCallable`]: Native-held reference to a script function handle or delegate
+//! - [`ScriptDispatch`], [`ScriptProxy`]: Rust trait objects backed by script objects
 
 mod call_context;
+mod dispatch;
 mod dynamic;
+mod memory;
 mod native_fn;
 mod object_heap;
+mod script_callable;
 
-pub use call_context::CallContext;
+pub use call_context::{CallArgs, CallContext};
+pub use dispatch::{ScriptDispatch, ScriptProxy};
 pub use dynamic::Dynamic;
+pub use memory::{MemoryBudget, OutOfMemory};
 pub use native_fn::{FuncdefHandle, NativeCallable, NativeFn};
 pub use object_heap::{ObjectHandle, ObjectHeap};
+pub use script_callable::{FunctionObject, ScriptCallable};
 
 #[cfg(test)]
 mod tests {
     use std::any::TypeId;
+    use std::cell::Cell;
 
     use super::*;
-    use crate::TypeHash;
-    use crate::native_error::NativeError;
+    use crate::native_error::{ConversionError, NativeError};
+    use crate::{TypeHash, primitives};
 
     #[test]
     fn dynamic_type_names() {
@@ -140,6 +150,20 @@ mod tests {
         assert_eq!(ctx.arg_count(), 1);
     }
 
+    #[test]
+    fn call_context_arg_types() {
+        let mut slots = vec![Dynamic::Int(1), Dynamic::Int(2), Dynamic::NullHandle];
+        let mut ret = Dynamic::Void;
+        let mut heap = ObjectHeap::new();
+        let types = [TypeHash::EMPTY, primitives::INT32, primitives::NULL];
+
+        let ctx = CallContext::new(&mut slots, 0, &mut ret, &mut heap).with_arg_types(&types);
+        assert!(ctx.arg_type_id(0).is_err());
+        assert_eq!(ctx.arg_type_id(1).unwrap(), primitives::INT32);
+        assert_eq!(ctx.arg_type_id(2).unwrap(), primitives::NULL);
+        assert!(ctx.arg_type_id(3).is_err());
+    }
+
     #[test]
     fn native_fn_call() {
         let native = NativeFn::new(TypeHash::from_name("test_add"), |ctx: &mut CallContext| {
@@ -159,6 +183,59 @@ mod tests {
         assert!(matches!(ret, Dynamic::Int(30)));
     }
 
+    #[test]
+    fn native_fn_call_catches_panic() {
+        let native = NativeFn::new(TypeHash::from_name("test_panic"), |_: &mut CallContext| {
+            panic!("host bug")
+        });
+
+        let mut slots = vec![];
+        let mut ret = Dynamic::Void;
+        let mut heap = ObjectHeap::new();
+
+        let mut ctx = CallContext::new(&mut slots, 0, &mut ret, &mut heap);
+        let err = native.call(&mut ctx).unwrap_err();
+        assert!(matches!(err, NativeError::Panic { ref message } if message == "host bug"));
+    }
+
+    #[test]
+    fn call_context_split_this_mut() {
+        let mut slots = vec![
+            Dynamic::Native(Box::new(40i32)),
+            Dynamic::Native(Box::new(2i32)),
+            Dynamic::Int(7),
+        ];
+        let mut ret = Dynamic::Void;
+        let mut heap = ObjectHeap::new();
+
+        let mut ctx = CallContext::new(&mut slots, 1, &mut ret, &mut heap);
+        let (this, mut args) = ctx.split_this_mut::<i32>().unwrap();
+        *this += *args.native::<i32>(0).unwrap();
+        *args.native_mut::<i32>(0).unwrap() = 0;
+        assert_eq!(args.len(), 2);
+        assert_eq!(args.arg::<i32>(1).unwrap(), 7);
+        assert!(args.native::<i32>(1).is_err());
+        assert!(args.native::<String>(0).is_err());
+
+        assert_eq!(*ctx.this::<i32>().unwrap(), 42);
+        assert!(matches!(ctx.arg_slot(0), Ok(Dynamic::Native(_))));
+    }
+
+    #[test]
+    fn call_context_arg_ptrs_point_to_slots() {
+        let mut slots = vec![Dynamic::Int(1), Dynamic::Int(2)];
+        let mut ret = Dynamic::Void;
+        let mut heap = ObjectHeap::new();
+
+        let mut ctx = CallContext::new(&mut slots, 0, &mut ret, &mut heap);
+        let [first, second] = ctx.arg_ptrs([0, 1]).unwrap();
+        assert_eq!(second as usize - first as usize, size_of::<Dynamic>());
+        assert!(matches!(
+            ctx.arg_ptrs([2]),
+            Err(NativeError::ArgumentIndexOutOfBounds { index: 2, count: 2 })
+        ));
+    }
+
     #[test]
     fn call_context_typed_arg() {
         let mut slots = vec![Dynamic::Int(42), Dynamic::Float(3.14), Dynamic::Bool(true)];
@@ -423,6 +500,46 @@ mod tests {
         assert!(heap.get_mut::<i32>(handle).is_none());
     }
 
+    #[test]
+    fn object_heap_charges_the_memory_budget() {
+        let mut heap = ObjectHeap::with_memory(MemoryBudget::new(OutOfMemory::Exception, Some(12)));
+        let first = heap.try_allocate(1u64).unwrap();
+        assert_eq!(heap.memory().used(), 8);
+
+        let err = heap.try_allocate(2u64).unwrap_err();
+        assert!(err.is_exception());
+        assert_eq!(
+            err.to_string(),
+            "script exception: out of memory: cannot allocate 8 bytes"
+        );
+        assert_eq!(heap.memory().used(), 8);
+
+        heap.release(first);
+        assert_eq!(heap.memory().used(), 0);
+        let second = heap.try_allocate(2u64).unwrap();
+        heap.free(second);
+        assert_eq!(heap.memory().used(), 0);
+    }
+
+    #[test]
+    fn memory_budget_grows_buffers() {
+        let mut budget = MemoryBudget::new(OutOfMemory::Exception, Some(64));
+        let mut text = String::new();
+        budget.reserve_string(&mut text, 16).unwrap();
+        assert!(text.capacity() >= 16);
+        assert_eq!(budget.used(), text.capacity());
+
+        let mut values: Vec<u32> = Vec::new();
+        assert!(budget.reserve_vec(&mut values, 64).is_err());
+        assert_eq!(values.capacity(), 0);
+        assert_eq!(budget.used(), text.capacity());
+
+        // Growing past the allocator's limit fails without a budget
+        let mut unlimited = MemoryBudget::default();
+        assert!(unlimited.reserve_vec(&mut values, usize::MAX / 2).is_err());
+        assert_eq!(unlimited.used(), 0);
+    }
+
     #[test]
     fn call_context_debug() {
         let mut slots = vec![Dynamic::Int(1), Dynamic::Int(2)];
@@ -526,6 +643,164 @@ mod tests {
         assert_eq!(ctx.heap().get::<i32>(handle), Some(&42));
     }
 
+    #[test]
+    fn call_context_arg_callable() {
+        let mut heap = ObjectHeap::new();
+        let handle = heap.allocate(FunctionObject {
+            function: 2,
+            this: None,
+        });
+
+        let mut slots = vec![Dynamic::Object(handle)];
+        let mut ret = Dynamic::Void;
+
+        let mut ctx = CallContext::new(&mut slots, 0, &mut ret, &mut heap);
+        let callable = ctx.arg_callable(0).unwrap();
+        assert_eq!(callable.function(), 2);
+
+        // The stored callable keeps the function object alive
+        heap.release(handle);
+        assert!(callable.is_valid(&heap));
+    }
+
+    #[test]
+    fn call_context_arg_callable_rejects_non_functions() {
+        let mut heap = ObjectHeap::new();
+        let handle = heap.allocate(42i32);
+
+        let mut slots = vec![
+            Dynamic::NullHandle,
+            Dynamic::Int(1),
+            Dynamic::Object(handle),
+        ];
+        let mut ret = Dynamic::Void;
+
+        let mut ctx = CallContext::new(&mut slots, 0, &mut ret, &mut heap);
+        assert!(matches!(
+            ctx.arg_callable(0),
+            Err(NativeError::Conversion(ConversionError::NullHandle { .. }))
+        ));
+        assert!(matches!(
+            ctx.arg_callable(1),
+            Err(NativeError::Conversion(
+                ConversionError::TypeMismatch { .. }
+            ))
+        ));
+        assert!(matches!(
+            ctx.arg_callable(2),
+            Err(NativeError::StaleHandle { .. })
+        ));
+    }
+
+    #[test]
+    fn call_context_arg_callable_or_null() {
+        let mut heap = ObjectHeap::new();
+        let handle = heap.allocate(FunctionObject {
+            function: 2,
+            this: None,
+        });
+
+        let mut slots = vec![
+            Dynamic::NullHandle,
+            Dynamic::Object(handle),
+            Dynamic::Int(1),
+        ];
+        let mut ret = Dynamic::Void;
+
+        let mut ctx = CallContext::new(&mut slots, 0, &mut ret, &mut heap);
+        assert!(ctx.arg_callable_or_null(0).unwrap().is_none());
+        let callable = ctx.arg_callable_or_null(1).unwrap().unwrap();
+        assert_eq!(callable.function(), 2);
+        assert!(ctx.arg_callable_or_null(2).is_err());
+    }
+
+    /// Run a native function as the VM does, raising the exception it set.
+    fn call_native(
+        native: impl Fn(&mut CallContext) -> Result<(), NativeError>,
+        slots: &mut [Dynamic],
+        heap: &mut ObjectHeap,
+    ) -> Result<Dynamic, NativeError> {
+        let mut ret = Dynamic::Void;
+        let mut ctx = CallContext::new(slots, 0, &mut ret, heap);
+        let result = native(&mut ctx);
+        ctx.finish(result)?;
+        Ok(ret)
+    }
+
+    #[test]
+    fn call_context_set_exception() {
+        let mut heap = ObjectHeap::new();
+        let raise = |ctx: &mut CallContext| {
+            ctx.set_exception("bad index");
+            assert_eq!(ctx.exception(), Some("bad index"));
+            ctx.set_return(1i32);
+            Ok(())
+        };
+        let err = call_native(raise, &mut [], &mut heap).unwrap_err();
+        assert!(matches!(err, NativeError::Exception { ref message } if message == "bad index"));
+
+        let fail = |ctx: &mut CallContext| {
+            ctx.set_exception("ignored");
+            Err(NativeError::other("failed"))
+        };
+        let err = call_native(fail, &mut [], &mut heap).unwrap_err();
+        assert!(!err.is_exception());
+
+        let ok = call_native(|_: &mut CallContext| Ok(()), &mut [], &mut heap);
+        assert!(ok.is_ok());
+    }
+
+    #[test]
+    fn exceptions_unwind_through_native_frames() {
+        // script -> each(callback) -> callback (script) -> check() raising
+        let check = |ctx: &mut CallContext| {
+            ctx.set_exception("out of range");
+            Ok(())
+        };
+        // The callback has no try block, so the exception leaves it
+        let callback = |heap: &mut ObjectHeap| call_native(check, &mut [], heap);
+
+        let observed = Cell::new(false);
+        let each = |ctx: &mut CallContext| {
+            let cb = ctx.arg_callable(0)?;
+            let result = callback(ctx.heap_mut());
+            observed.set(result.as_ref().is_err_and(NativeError::is_exception));
+            cb.release(ctx.heap_mut());
+            result.map(|_| ())
+        };
+
+        let mut heap = ObjectHeap::new();
+        let handle = heap.allocate(FunctionObject {
+            function: 0,
+            this: None,
+        });
+        let err = call_native(each, &mut [Dynamic::Object(handle)], &mut heap).unwrap_err();
+
+        assert!(observed.get());
+        assert!(matches!(err, NativeError::Exception { ref message } if message == "out of range"));
+        assert_eq!(heap.ref_count(handle), Some(1));
+    }
+
+    #[test]
+    fn native_frames_can_swallow_exceptions() {
+        let callback = |heap: &mut ObjectHeap| {
+            call_native(
+                |_: &mut CallContext| Err(NativeError::exception("no")),
+                &mut [],
+                heap,
+            )
+        };
+        let guarded = |ctx: &mut CallContext| {
+            let handled = callback(ctx.heap_mut()).is_err();
+            ctx.set_return(handled);
+            Ok(())
+        };
+
+        let mut heap = ObjectHeap::new();
+        let ret = call_native(guarded, &mut [], &mut heap).unwrap();
+        assert!(matches!(ret, Dynamic::Bool(true)));
+    }
+
     #[test]
     fn native_fn_debug() {
         let native = NativeFn::new(TypeHash::from_name("test_debug"), |_: &mut CallContext| {
@@ -3,7 +3,7 @@
 use std::fmt;
 
 use crate::TypeHash;
-use crate::native_error::NativeError;
+use crate::native_error::{NativeError, catch_native_panic};
 
 use super::CallContext;
 
@@ -35,8 +35,11 @@ impl NativeFn {
     }
 
     /// Call this native function with the given context.
+    ///
+    /// A panic in the function is caught and returned as
+    /// [`NativeError::Panic`].
     pub fn call(&self, ctx: &mut CallContext) -> Result<(), NativeError> {
-        self.inner.call(ctx)
+        catch_native_panic(|| self.inner.call(ctx))
     }
 
     /// Clone this NativeFn, sharing the same underlying callable.
@@ -3,6 +3,9 @@
 use std::any::{Any, TypeId};
 use std::fmt;
 
+use super::memory::MemoryBudget;
+use crate::NativeError;
+
 /// Handle to a heap-allocated object.
 ///
 /// This is a safe, copyable reference to an object in the `ObjectHeap`.
@@ -33,28 +36,75 @@ impl ObjectHandle {
 /// Objects are stored in a Vec with generation tracking. When an object
 /// is freed, its slot is reused but the generation is incremented. This
 /// allows detecting stale handles at runtime.
+///
+/// Objects allocated with [`try_allocate`](Self::try_allocate) are charged
+/// to the heap's [`MemoryBudget`] until they are freed.
 pub struct ObjectHeap {
     slots: Vec<HeapSlot>,
     free_list: Vec<u32>,
+    memory: MemoryBudget,
 }
 
 struct HeapSlot {
     generation: u32,
     value: Option<Box<dyn Any + Send + Sync>>,
     ref_count: u32,
+    /// Bytes charged to the memory budget for the object.
+    charged: usize,
 }
 
 impl ObjectHeap {
     /// Create a new empty object heap.
     pub fn new() -> Self {
+        Self::with_memory(MemoryBudget::default())
+    }
+
+    /// Create a new empty object heap charging script allocations to
+    /// `memory`.
+    pub fn with_memory(memory: MemoryBudget) -> Self {
         Self {
             slots: Vec::new(),
             free_list: Vec::new(),
+            memory,
         }
     }
 
+    /// Get the memory budget of script allocations.
+    pub fn memory(&self) -> &MemoryBudget {
+        &self.memory
+    }
+
+    /// Get the memory budget of script allocations mutably.
+    pub fn memory_mut(&mut self) -> &mut MemoryBudget {
+        &mut self.memory
+    }
+
     /// Allocate a new object on the heap.
     pub fn allocate<T: Any + Send + Sync>(&mut self, value: T) -> ObjectHandle {
+        self.insert(value, 0)
+    }
+
+    /// Allocate a new object on the heap, charging its size to the memory
+    /// budget.
+    ///
+    /// # Errors
+    ///
+    /// Fails following the budget's policy if the budget is exhausted or the
+    /// slot table cannot grow.
+    pub fn try_allocate<T: Any + Send + Sync>(
+        &mut self,
+        value: T,
+    ) -> Result<ObjectHandle, NativeError> {
+        let size = size_of::<T>();
+        self.memory.try_reserve(size)?;
+        if self.free_list.is_empty() && self.slots.try_reserve(1).is_err() {
+            self.memory.release(size);
+            return Err(self.memory.out_of_memory(size_of::<HeapSlot>()));
+        }
+        Ok(self.insert(value, size))
+    }
+
+    fn insert<T: Any + Send + Sync>(&mut self, value: T, charged: usize) -> ObjectHandle {
         let type_id = TypeId::of::<T>();
         let boxed: Box<dyn Any + Send + Sync> = Box::new(value);
 
@@ -63,6 +113,7 @@ impl ObjectHeap {
             let generation = slot.generation;
             slot.value = Some(boxed);
             slot.ref_count = 1;
+            slot.charged = charged;
             ObjectHandle::new(index, generation, type_id)
         } else {
             let index = self.slots.len() as u32;
@@ -70,6 +121,7 @@ impl ObjectHeap {
                 generation: 0,
                 value: Some(boxed),
                 ref_count: 1,
+                charged,
             });
             ObjectHandle::new(index, 0, type_id)
         }
@@ -121,6 +173,7 @@ impl ObjectHeap {
             if slot.ref_count == 0 {
                 slot.value = None;
                 slot.generation = slot.generation.wrapping_add(1);
+                self.memory.release(std::mem::take(&mut slot.charged));
                 self.free_list.push(handle.index);
                 return true;
             }
@@ -135,10 +188,38 @@ impl ObjectHeap {
         {
             slot.value = None;
             slot.generation = slot.generation.wrapping_add(1);
+            self.memory.release(std::mem::take(&mut slot.charged));
             self.free_list.push(handle.index);
         }
     }
 
+    /// Free every object on the heap.
+    ///
+    /// Slots keep their generations, so handles to the freed objects stay
+    /// stale instead of aliasing objects allocated afterwards.
+    pub fn clear(&mut self) {
+        self.free_list.clear();
+        for (index, slot) in self.slots.iter_mut().enumerate().rev() {
+            if slot.value.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+                self.memory.release(std::mem::take(&mut slot.charged));
+            }
+            slot.ref_count = 0;
+            self.free_list.push(index as u32);
+        }
+    }
+
+    /// Get handles to every live object of type `T`.
+    pub fn handles_of<T: Any>(&self) -> Vec<ObjectHandle> {
+        let type_id = TypeId::of::<T>();
+        self.slots
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.value.as_ref().is_some_and(|v| v.is::<T>()))
+            .map(|(index, slot)| ObjectHandle::new(index as u32, slot.generation, type_id))
+            .collect()
+    }
+
     /// Get the reference count for an object.
     pub fn ref_count(&self, handle: ObjectHandle) -> Option<u32> {
         let slot = self.slots.get(handle.index as usize)?;
@@ -0,0 +1,196 @@
+//! Script function handles and delegates held by native code.
+
+use super::{Dynamic, ObjectHandle, ObjectHeap};
+
+/// Heap representation of a funcdef value.
+///
+/// The VM allocates one of these for every function handle (`@func`) and
+/// delegate (`Callback(obj.method)`) it creates. Delegates hold a reference
+/// on their bound object for as long as the function object is alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FunctionObject {
+    /// Index of the target function in the compiled module.
+    pub function: usize,
+    /// Bound object for delegates, `None` for plain function handles.
+    pub this: Option<ObjectHandle>,
+}
+
+/// A native-held reference to a script function handle or delegate.
+///
+/// Native functions receive funcdef arguments as `ScriptCallable` via
+/// [`CallContext::arg_callable`], and may store them to invoke later
+/// (e.g. event callbacks registered by scripts) through the owning unit.
+///
+/// Each `ScriptCallable` accounts for one reference on the function object.
+/// It is deliberately not `Clone`, and it is only valid while the heap it
+/// was taken from is alive: once the unit is cleared or dropped, invoking
+/// it reports an invalid handle instead of calling into freed state.
+///
+/// [`CallContext::arg_callable`]: super::CallContext::arg_callable
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScriptCallable {
+    handle: ObjectHandle,
+    target: FunctionObject,
+}
+
+impl ScriptCallable {
+    /// Take a new reference to the function object behind `handle`.
+    ///
+    /// Returns `None` if the handle is stale or does not refer to a
+    /// [`FunctionObject`].
+    pub fn retain(heap: &mut ObjectHeap, handle: ObjectHandle) -> Option<Self> {
+        let target = *heap.get::<FunctionObject>(handle)?;
+        heap.add_ref(handle);
+        Some(Self { handle, target })
+    }
+
+    /// Take another reference to the same function object, e.g. to store a
+    /// callback in more than one container.
+    ///
+    /// Returns `None` if the function object was freed.
+    pub fn clone_ref(&self, heap: &mut ObjectHeap) -> Option<Self> {
+        Self::retain(heap, self.handle)
+    }
+
+    /// Move this reference into a slot, e.g. to return the callable through
+    /// a `?` or funcdef return value.
+    pub fn into_dynamic(self) -> Dynamic {
+        Dynamic::Object(self.handle)
+    }
+
+    /// Give back this reference, releasing the delegate's bound object if
+    /// the function object is destroyed.
+    pub fn release(self, heap: &mut ObjectHeap) {
+        if heap.release(self.handle)
+            && let Some(this) = self.target.this
+        {
+            heap.release(this);
+        }
+    }
+
+    /// Check that the function object is still alive in `heap`.
+    pub fn is_valid(&self, heap: &ObjectHeap) -> bool {
+        heap.get::<FunctionObject>(self.handle).is_some()
+    }
+
+    /// The heap handle of the function object.
+    pub fn handle(&self) -> ObjectHandle {
+        self.handle
+    }
+
+    /// Index of the target function in the compiled module.
+    pub fn function(&self) -> usize {
+        self.target.function
+    }
+
+    /// The bound object, if this is a delegate.
+    pub fn this(&self) -> Option<ObjectHandle> {
+        self.target.this
+    }
+
+    /// Script `is` comparison of two funcdef handles.
+    ///
+    /// Handles to the same function are the same, however they were
+    /// created. Delegates are only the same as themselves: two delegates
+    /// binding the same method of the same object are different handles.
+    pub fn is_same(&self, other: &Self) -> bool {
+        self.handle == other.handle
+            || (!self.is_delegate() && !other.is_delegate() && self.function() == other.function())
+    }
+
+    /// Check if this is a delegate bound to an object.
+    pub fn is_delegate(&self) -> bool {
+        self.target.this.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retain_and_release() {
+        let mut heap = ObjectHeap::new();
+        let handle = heap.allocate(FunctionObject {
+            function: 3,
+            this: None,
+        });
+
+        let callable = ScriptCallable::retain(&mut heap, handle).unwrap();
+        assert_eq!(callable.function(), 3);
+        assert!(!callable.is_delegate());
+        assert_eq!(heap.ref_count(handle), Some(2));
+
+        callable.release(&mut heap);
+        assert_eq!(heap.ref_count(handle), Some(1));
+    }
+
+    #[test]
+    fn retain_rejects_other_objects() {
+        let mut heap = ObjectHeap::new();
+        let handle = heap.allocate(42i32);
+        assert!(ScriptCallable::retain(&mut heap, handle).is_none());
+        assert_eq!(heap.ref_count(handle), Some(1));
+    }
+
+    #[test]
+    fn identity_of_handles_and_delegates() {
+        let mut heap = ObjectHeap::new();
+        let object = heap.allocate(42i32);
+        let callable = |heap: &mut ObjectHeap, function, this| {
+            let handle = heap.allocate(FunctionObject { function, this });
+            let callable = ScriptCallable::retain(heap, handle).unwrap();
+            heap.release(handle);
+            callable
+        };
+        let first = callable(&mut heap, 1, None);
+        let second = callable(&mut heap, 1, None);
+        let other = callable(&mut heap, 2, None);
+        let delegate = callable(&mut heap, 1, Some(object));
+        let rebound = callable(&mut heap, 1, Some(object));
+
+        assert!(first.is_same(&second));
+        assert!(!first.is_same(&other));
+        assert!(!first.is_same(&delegate));
+        assert!(!delegate.is_same(&rebound));
+
+        let copy = delegate.clone_ref(&mut heap).unwrap();
+        assert!(copy.is_same(&delegate));
+        assert_eq!(heap.ref_count(delegate.handle()), Some(2));
+        delegate.release(&mut heap);
+        let handle = copy.handle();
+        assert_eq!(copy.into_dynamic(), Dynamic::Object(handle));
+    }
+
+    #[test]
+    fn delegate_releases_bound_object() {
+        let mut heap = ObjectHeap::new();
+        let object = heap.allocate(42i32);
+        let handle = heap.allocate(FunctionObject {
+            function: 0,
+            this: Some(object),
+        });
+
+        let callable = ScriptCallable::retain(&mut heap, handle).unwrap();
+        assert!(callable.is_delegate());
+        heap.release(handle);
+        assert!(callable.is_valid(&heap));
+
+        callable.release(&mut heap);
+        assert_eq!(heap.ref_count(handle), None);
+        assert_eq!(heap.ref_count(object), None);
+    }
+
+    #[test]
+    fn stale_after_heap_reset() {
+        let mut heap = ObjectHeap::new();
+        let handle = heap.allocate(FunctionObject {
+            function: 0,
+            this: None,
+        });
+        let callable = ScriptCallable::retain(&mut heap, handle).unwrap();
+
+        let heap = ObjectHeap::new();
+        assert!(!callable.is_valid(&heap));
+    }
+}
@@ -0,0 +1,321 @@
+//! Interned type representation.
+//!
+//! [`DataType`] carries a 64-bit [`TypeHash`] and seven flags, so comparing
+//! two of them checks every field. Code that compares and copies types in
+//! tight loops (conversion and overload resolution) can intern them instead:
+//! a [`TypeTable`] maps each base type to a dense index, and a [`TypeRef`]
+//! packs that index with the modifier bits into 8 bytes, half the size of a
+//! `DataType`, compared and hashed as two integers.
+//!
+//! # Example
+//!
+//! ```
+//! use angelscript_core::{DataType, TypeTable, primitives};
+//!
+//! let mut table = TypeTable::new();
+//! let a = table.intern(DataType::with_const(primitives::INT32));
+//! let b = table.intern(DataType::simple(primitives::INT32));
+//!
+//! assert_ne!(a, b);
+//! assert!(a.same_base(b));
+//! assert_eq!(table.resolve(a), DataType::with_const(primitives::INT32));
+//! ```
+
+use rustc_hash::FxHashMap;
+use std::fmt;
+
+use crate::{DataType, RefModifier, TypeHash};
+
+/// Modifier bits of a type, as packed into a [`TypeRef`].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TypeModifiers(u16);
+
+impl TypeModifiers {
+    /// No modifiers.
+    pub const NONE: Self = Self(0);
+    /// `const` value.
+    pub const CONST: Self = Self(1 << 0);
+    /// Handle (`@`).
+    pub const HANDLE: Self = Self(1 << 1);
+    /// Handle to a const object (`const T@`).
+    pub const HANDLE_TO_CONST: Self = Self(1 << 2);
+    /// `&in` reference.
+    pub const REF_IN: Self = Self(1 << 3);
+    /// `&out` reference.
+    pub const REF_OUT: Self = Self(1 << 4);
+    /// `&inout` reference.
+    pub const REF_INOUT: Self = Self(1 << 5);
+    /// The base type is a mixin class.
+    pub const MIXIN: Self = Self(1 << 6);
+    /// The base type is an interface.
+    pub const INTERFACE: Self = Self(1 << 7);
+    /// The base type is an enum.
+    pub const ENUM: Self = Self(1 << 8);
+
+    const REF_MASK: u16 = Self::REF_IN.0 | Self::REF_OUT.0 | Self::REF_INOUT.0;
+
+    /// The raw bits.
+    pub const fn bits(self) -> u16 {
+        self.0
+    }
+
+    /// Check if all modifiers in `other` are set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Set or clear the modifiers in `other`.
+    pub const fn set(self, other: Self, value: bool) -> Self {
+        if value {
+            Self(self.0 | other.0)
+        } else {
+            Self(self.0 & !other.0)
+        }
+    }
+
+    /// The reference modifier.
+    pub const fn ref_modifier(self) -> RefModifier {
+        match self.0 & Self::REF_MASK {
+            bits if bits == Self::REF_IN.0 => RefModifier::In,
+            bits if bits == Self::REF_OUT.0 => RefModifier::Out,
+            bits if bits == Self::REF_INOUT.0 => RefModifier::InOut,
+            _ => RefModifier::None,
+        }
+    }
+
+    /// Replace the reference modifier.
+    pub const fn with_ref_modifier(self, modifier: RefModifier) -> Self {
+        let bits = self.0 & !Self::REF_MASK;
+        Self(match modifier {
+            RefModifier::None => bits,
+            RefModifier::In => bits | Self::REF_IN.0,
+            RefModifier::Out => bits | Self::REF_OUT.0,
+            RefModifier::InOut => bits | Self::REF_INOUT.0,
+        })
+    }
+
+    /// The modifiers of a type.
+    pub const fn of(data_type: &DataType) -> Self {
+        Self::NONE
+            .set(Self::CONST, data_type.is_const)
+            .set(Self::HANDLE, data_type.is_handle)
+            .set(Self::HANDLE_TO_CONST, data_type.is_handle_to_const)
+            .set(Self::MIXIN, data_type.is_mixin)
+            .set(Self::INTERFACE, data_type.is_interface)
+            .set(Self::ENUM, data_type.is_enum)
+            .with_ref_modifier(data_type.ref_modifier)
+    }
+
+    /// Apply the modifiers to a base type.
+    pub const fn apply(self, type_hash: TypeHash) -> DataType {
+        DataType {
+            type_hash,
+            is_const: self.contains(Self::CONST),
+            is_handle: self.contains(Self::HANDLE),
+            is_handle_to_const: self.contains(Self::HANDLE_TO_CONST),
+            ref_modifier: self.ref_modifier(),
+            is_mixin: self.contains(Self::MIXIN),
+            is_interface: self.contains(Self::INTERFACE),
+            is_enum: self.contains(Self::ENUM),
+        }
+    }
+}
+
+impl fmt::Debug for TypeModifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const NAMES: [(TypeModifiers, &str); 9] = [
+            (TypeModifiers::CONST, "CONST"),
+            (TypeModifiers::HANDLE, "HANDLE"),
+            (TypeModifiers::HANDLE_TO_CONST, "HANDLE_TO_CONST"),
+            (TypeModifiers::REF_IN, "REF_IN"),
+            (TypeModifiers::REF_OUT, "REF_OUT"),
+            (TypeModifiers::REF_INOUT, "REF_INOUT"),
+            (TypeModifiers::MIXIN, "MIXIN"),
+            (TypeModifiers::INTERFACE, "INTERFACE"),
+            (TypeModifiers::ENUM, "ENUM"),
+        ];
+        let set: Vec<&str> = NAMES
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| *name)
+            .collect();
+        if set.is_empty() {
+            f.write_str("NONE")
+        } else {
+            f.write_str(&set.join(" | "))
+        }
+    }
+}
+
+/// An interned [`DataType`]: index of the base type in a [`TypeTable`] plus
+/// its modifiers.
+///
+/// `TypeRef`s are only meaningful for the table that created them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeRef {
+    index: u32,
+    modifiers: TypeModifiers,
+}
+
+impl TypeRef {
+    /// Index of the base type in its table.
+    pub const fn index(self) -> u32 {
+        self.index
+    }
+
+    /// The modifiers.
+    pub const fn modifiers(self) -> TypeModifiers {
+        self.modifiers
+    }
+
+    /// Check if both refer to the same base type, ignoring modifiers.
+    pub const fn same_base(self, other: Self) -> bool {
+        self.index == other.index
+    }
+
+    /// The base type without modifiers.
+    pub const fn unqualified(self) -> Self {
+        self.with_modifiers(TypeModifiers::NONE)
+    }
+
+    /// The same base type with other modifiers.
+    pub const fn with_modifiers(self, modifiers: TypeModifiers) -> Self {
+        Self {
+            index: self.index,
+            modifiers,
+        }
+    }
+
+    /// Check if the value is const.
+    pub const fn is_const(self) -> bool {
+        self.modifiers.contains(TypeModifiers::CONST)
+    }
+
+    /// Check if this is a handle.
+    pub const fn is_handle(self) -> bool {
+        self.modifiers.contains(TypeModifiers::HANDLE)
+    }
+
+    /// The reference modifier.
+    pub const fn ref_modifier(self) -> RefModifier {
+        self.modifiers.ref_modifier()
+    }
+}
+
+/// Interns base types to dense indices.
+#[derive(Debug, Clone, Default)]
+pub struct TypeTable {
+    hashes: Vec<TypeHash>,
+    indices: FxHashMap<TypeHash, u32>,
+}
+
+impl TypeTable {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of interned base types.
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    /// Check if no type has been interned.
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    /// Intern a type, adding its base type to the table if needed.
+    pub fn intern(&mut self, data_type: DataType) -> TypeRef {
+        let index = *self.indices.entry(data_type.type_hash).or_insert_with(|| {
+            self.hashes.push(data_type.type_hash);
+            (self.hashes.len() - 1) as u32
+        });
+        TypeRef {
+            index,
+            modifiers: TypeModifiers::of(&data_type),
+        }
+    }
+
+    /// Look up an interned type without adding it.
+    pub fn get(&self, data_type: &DataType) -> Option<TypeRef> {
+        self.indices
+            .get(&data_type.type_hash)
+            .map(|&index| TypeRef {
+                index,
+                modifiers: TypeModifiers::of(data_type),
+            })
+    }
+
+    /// The base type of an interned type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `type_ref` was created by another table.
+    pub fn type_hash(&self, type_ref: TypeRef) -> TypeHash {
+        self.hashes[type_ref.index as usize]
+    }
+
+    /// The full type of an interned type.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `type_ref` was created by another table.
+    pub fn resolve(&self, type_ref: TypeRef) -> DataType {
+        type_ref.modifiers.apply(self.type_hash(type_ref))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::primitives;
+
+    #[test]
+    fn round_trips_all_modifiers() {
+        let types = [
+            DataType::simple(primitives::INT32),
+            DataType::with_const(primitives::FLOAT),
+            DataType::with_handle(primitives::INT32, true),
+            DataType::const_handle(primitives::INT32, false),
+            DataType::with_ref_in(primitives::DOUBLE),
+            DataType::with_ref_out(primitives::INT32),
+            DataType::with_ref_inout(primitives::INT32),
+            DataType {
+                is_enum: true,
+                is_interface: true,
+                is_mixin: true,
+                ..DataType::simple(primitives::INT64)
+            },
+        ];
+        let mut table = TypeTable::new();
+        for data_type in types {
+            let type_ref = table.intern(data_type);
+            assert_eq!(table.resolve(type_ref), data_type);
+            assert_eq!(table.get(&data_type), Some(type_ref));
+        }
+        assert_eq!(table.len(), 4);
+    }
+
+    #[test]
+    fn equality_follows_data_type() {
+        let mut table = TypeTable::new();
+        let a = table.intern(DataType::with_ref_in(primitives::INT32));
+        let b = table.intern(DataType::with_ref_in(primitives::INT32));
+        let c = table.intern(DataType::with_ref_out(primitives::INT32));
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert!(a.same_base(c));
+        assert_eq!(a.unqualified(), c.unqualified());
+        assert_eq!(a.ref_modifier(), RefModifier::In);
+        assert!(TypeRef::is_const(a.with_modifiers(TypeModifiers::CONST)));
+        assert_eq!(
+            format!(
+                "{:?}",
+                TypeModifiers::CONST.set(TypeModifiers::HANDLE, true)
+            ),
+            "CONST | HANDLE"
+        );
+    }
+}
@@ -57,6 +57,8 @@ pub struct FunctionAttrs {
     pub is_const: bool,
     /// Is property accessor
     pub is_property: bool,
+    /// Is pure (no side effects)
+    pub is_pure: bool,
     /// Property name override (overrides inference from get_/set_ prefix)
     pub property_name: Option<String>,
     /// Is generic calling convention
@@ -241,6 +243,7 @@ impl FunctionAttrs {
                         "get_weakref_flag" => result.kind = FunctionKind::GetWeakRefFlag,
                         "const" => result.is_const = true,
                         "property" => result.is_property = true,
+                        "pure" => result.is_pure = true,
                         "generic" => result.is_generic = true,
                         "template" => result.is_template = true,
                         "copy" => result.is_copy = true,
@@ -253,7 +256,7 @@ impl FunctionAttrs {
                                      instance, constructor, factory, destructor, addref, release, \
                                      list_construct, list_factory, template_callback, gc_getrefcount, \
                                      gc_setflag, gc_getflag, gc_enumrefs, gc_releaserefs, get_weakref_flag, \
-                                     const, property, generic, template, copy, keep",
+                                     const, property, pure, generic, template, copy, keep",
                                     name
                                 ),
                             ));
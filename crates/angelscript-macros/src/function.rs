@@ -198,6 +198,7 @@ fn function_inner(attrs: &FunctionAttrs, input: &ItemFn) -> syn::Result<TokenStr
     // Generate function traits (early, needed for return meta)
     let is_const = attrs.is_const;
     let is_property = attrs.is_property;
+    let is_pure = attrs.is_pure;
     let is_generic = attrs.is_generic;
 
     // Check if this is a "true" generic calling convention function (takes &mut CallContext)
@@ -297,6 +298,7 @@ fn function_inner(attrs: &FunctionAttrs, input: &ItemFn) -> syn::Result<TokenStr
                 behavior: #behavior,
                 is_const: #is_const,
                 is_property: #is_property,
+                is_pure: #is_pure,
                 property_name: #property_name_token,
                 is_generic: #is_generic,
                 list_pattern: #list_pattern_token,
@@ -970,14 +972,25 @@ fn generate_native_fn(
         }
     });
 
-    // For &mut self methods with &T params, we need special handling to avoid borrow conflicts
-    // We'll use unsafe pointer access to work around the borrow checker
-    let needs_unsafe_self_access = receiver_is_mut && has_ref_param;
+    // Raw pointer params (generic calling convention `*mut ()`) point to their argument slots
+    let ptr_params: Vec<(usize, syn::Ident, &syn::TypePtr)> = params
+        .iter()
+        .enumerate()
+        .filter_map(|(i, (name, ty))| match ty {
+            Type::Ptr(ptr) => Some((i, name.clone(), ptr)),
+            _ => None,
+        })
+        .collect();
+
+    // For &mut self methods with &T or pointer params, `this` and the arguments must be
+    // borrowed separately, since both live in the CallContext slots
+    let needs_split_self_access = receiver_is_mut && (has_ref_param || !ptr_params.is_empty());
 
     // Generate extraction code for each parameter
     let extractions: Vec<_> = params
         .iter()
         .enumerate()
+        .filter(|(_, (_, ty))| !matches!(ty, Type::Ptr(_)))
         .map(|(i, (name, ty))| generate_param_extraction(name, ty, i))
         .collect();
 
@@ -1060,79 +1073,58 @@ fn generate_native_fn(
         }
     };
 
-    // For &mut self methods with &T params, we need a different code structure
-    // to avoid borrow conflicts. We use unsafe pointer access.
-    if needs_unsafe_self_access {
-        // Find which params are non-primitive &T and need special handling
-        let ref_params: Vec<_> = params
+    // For &mut self methods with &T or pointer params, split `this` from the arguments
+    // so both can be borrowed at once.
+    if needs_split_self_access {
+        // Generate borrows of &T params from the split-off arguments
+        let ref_borrows: Vec<_> = params
             .iter()
             .enumerate()
             .filter(|(_, (_, ty))| is_non_primitive_ref(ty))
             .map(|(i, (name, ty))| {
-                let base_ty = if let Type::Reference(type_ref) = ty {
-                    type_ref.elem.as_ref().clone()
-                } else {
-                    ty.clone()
+                let Type::Reference(type_ref) = ty else {
+                    unreachable!()
                 };
-                (i, name.clone(), base_ty)
+                let base_ty = type_ref.elem.as_ref();
+                if type_ref.mutability.is_some() {
+                    quote! { let #name: &mut #base_ty = __args.native_mut::<#base_ty>(#i)?; }
+                } else {
+                    quote! { let #name: &#base_ty = __args.native::<#base_ty>(#i)?; }
+                }
             })
             .collect();
 
-        // Generate extractions for primitive params only (they don't hold borrows)
+        // Generate extractions for the remaining params (they don't hold borrows)
         let non_ref_extractions: Vec<_> = params
             .iter()
             .enumerate()
-            .filter(|(_, (_, ty))| !is_non_primitive_ref(ty))
+            .filter(|(_, (_, ty))| !is_non_primitive_ref(ty) && !matches!(ty, Type::Ptr(_)))
             .map(|(i, (name, ty))| generate_param_extraction(name, ty, i))
             .collect();
 
-        // Generate pointer extraction for &T params
-        let ref_extractions: Vec<_> = ref_params
-            .iter()
-            .map(|(i, name, base_ty)| {
-                quote! {
-                    let #name: *const #base_ty = {
-                        let __slot = __ctx.arg_slot(#i)?;
-                        match __slot {
-                            ::angelscript_core::Dynamic::Native(boxed) => {
-                                let __ref = boxed.downcast_ref::<#base_ty>().ok_or_else(|| {
-                                    ::angelscript_core::NativeError::other(
-                                        concat!("failed to downcast argument ", stringify!(#name))
-                                    )
-                                })?;
-                                __ref as *const #base_ty
-                            }
-                            _ => return Err(::angelscript_core::NativeError::Conversion(
-                                ::angelscript_core::ConversionError::TypeMismatch {
-                                    expected: "native",
-                                    actual: __slot.type_name(),
-                                }
-                            )),
-                        }
-                    };
-                }
-            })
-            .collect();
-
-        // Generate the unsafe dereference for &T params in the call
-        let unsafe_derefs: Vec<_> = ref_params
-            .iter()
-            .map(|(_, name, base_ty)| {
-                quote! { let #name: &#base_ty = unsafe { &*#name }; }
-            })
-            .collect();
+        let ptr_extraction = generate_ptr_extraction(&ptr_params, quote! { __args.ptrs });
+        let args_mut = if ptr_params.is_empty()
+            && !params.iter().any(|(_, ty)| {
+                matches!(ty, Type::Reference(r) if r.mutability.is_some())
+                    && is_non_primitive_ref(ty)
+            }) {
+            quote! {}
+        } else {
+            quote! { mut }
+        };
 
         quote! {
             Some(::angelscript_core::NativeFn::new(
                 ::angelscript_core::TypeHash::from_name(#fn_name),
                 |__ctx: &mut ::angelscript_core::CallContext| {
                     #(#non_ref_extractions)*
-                    #(#ref_extractions)*
                     let __result = {
-                        let __this: &mut Self = __ctx.this_mut::<Self>()?;
-                        #(#unsafe_derefs)*
+                        let (__this, #args_mut __args) = __ctx.split_this_mut::<Self>()?;
+                        #ptr_extraction
+                        #(#ref_borrows)*
                         __this.#mangled_fn_name(#(#arg_names),*)
                     };
+                    #(#writebacks)*
                     #return_handling
                     Ok(())
                 }
@@ -1140,6 +1132,7 @@ fn generate_native_fn(
         }
     } else {
         // Normal case - no borrow conflicts
+        let ptr_extraction = generate_ptr_extraction(&ptr_params, quote! { __ctx.arg_ptrs });
         let call_expr = if has_receiver {
             // Method call: need to extract `this` first
             if receiver_is_mut {
@@ -1173,6 +1166,7 @@ fn generate_native_fn(
                 ::angelscript_core::TypeHash::from_name(#fn_name),
                 |__ctx: &mut ::angelscript_core::CallContext| {
                     #(#extractions)*
+                    #ptr_extraction
                     let __result = #call_expr;
                     #(#writebacks)*
                     #return_handling
@@ -1183,6 +1177,33 @@ fn generate_native_fn(
     }
 }
 
+/// Generate code to get raw pointers to the argument slots of pointer params.
+///
+/// All pointers come from a single call to `getter` (`arg_ptrs` or `ptrs`) so that
+/// they share the provenance of the argument slots.
+fn generate_ptr_extraction(
+    ptr_params: &[(usize, syn::Ident, &syn::TypePtr)],
+    getter: TokenStream2,
+) -> Option<TokenStream2> {
+    if ptr_params.is_empty() {
+        return None;
+    }
+    let indices = ptr_params.iter().map(|(i, _, _)| i);
+    let names: Vec<_> = ptr_params.iter().map(|(_, name, _)| name).collect();
+    let casts = ptr_params.iter().map(|(_, name, ptr)| {
+        let elem = ptr.elem.as_ref();
+        if ptr.mutability.is_some() {
+            quote! { let #name: *mut #elem = #name.cast::<#elem>(); }
+        } else {
+            quote! { let #name: *const #elem = #name.cast::<#elem>().cast_const(); }
+        }
+    });
+    Some(quote! {
+        let [#(#names),*] = #getter([#(#indices),*])?;
+        #(#casts)*
+    })
+}
+
 /// Generate code to extract a parameter from CallContext.
 fn generate_param_extraction(name: &syn::Ident, ty: &Type, index: usize) -> TokenStream2 {
     // Get the base type (strip references)
@@ -5,6 +5,12 @@
 //! Methods can be annotated with `#[function(...)]` to customize their registration:
 //! - `name = "..."` - Override the AngelScript method name
 //! - `const` - Explicitly mark as const (normally inferred from &self)
+//!
+//! With `#[interface(proxy)]`, a `<Trait>Proxy` type is also generated that
+//! implements the trait by dispatching each method to a script object. Each
+//! method also gets a `try_<method>` variant on the proxy that returns the
+//! script's failure instead of panicking, and trait methods returning
+//! `Result<T, E>` with `E: From<NativeError>` return it as their error.
 
 use proc_macro::TokenStream;
 use proc_macro2::TokenStream as TokenStream2;
@@ -18,6 +24,8 @@ use crate::attrs::FunctionAttrs;
 pub struct InterfaceAttrs {
     /// Override the AngelScript interface name.
     pub name: Option<String>,
+    /// Generate a proxy type implementing the trait via script dispatch.
+    pub proxy: bool,
 }
 
 impl InterfaceAttrs {
@@ -26,17 +34,24 @@ impl InterfaceAttrs {
 
         let mut result = Self::default();
 
-        if input.is_empty() {
-            return Ok(result);
-        }
-
-        // Parse name = "..."
-        if input.peek(syn::Ident) {
+        while !input.is_empty() {
             let ident: syn::Ident = input.parse()?;
             if ident == "name" {
+                // name = "..."
                 let _: Token![=] = input.parse()?;
                 let value: LitStr = input.parse()?;
                 result.name = Some(value.value());
+            } else if ident == "proxy" {
+                result.proxy = true;
+            } else {
+                return Err(syn::Error::new(
+                    ident.span(),
+                    format!("unknown interface attribute: {}", ident),
+                ));
+            }
+
+            if !input.is_empty() {
+                let _: Token![,] = input.parse()?;
             }
         }
 
@@ -104,12 +119,21 @@ fn interface_inner(attrs: &InterfaceAttrs, input: &ItemTrait) -> syn::Result<Tok
         trait_name.span(),
     );
 
+    let proxy = if attrs.proxy {
+        generate_proxy(input, &as_name, &methods)?
+    } else {
+        quote! {}
+    };
+
     Ok(quote! {
         #trait_vis trait #trait_name {
             #(#filtered_items)*
         }
 
+        #proxy
+
         /// Get interface metadata for registration.
+        #[allow(non_snake_case)]
         #trait_vis fn #meta_fn_name() -> ::angelscript_core::InterfaceMeta {
             ::angelscript_core::InterfaceMeta {
                 name: #as_name,
@@ -120,6 +144,126 @@ fn interface_inner(attrs: &InterfaceAttrs, input: &ItemTrait) -> syn::Result<Tok
     })
 }
 
+/// Generate `<Trait>Proxy`, implementing the trait by script dispatch.
+///
+/// Parameters must implement `IntoDynamic` and return types `FromDynamic`.
+/// Every method gets a `try_<method>` variant returning script failures as
+/// a `NativeError`. Trait methods returning `Result<T, E>` convert the
+/// failure with `E::from`; the others cannot report it, so they panic.
+fn generate_proxy(
+    input: &ItemTrait,
+    as_name: &str,
+    methods: &[MethodInfo],
+) -> syn::Result<TokenStream2> {
+    let trait_name = &input.ident;
+    let trait_vis = &input.vis;
+    let proxy_name = syn::Ident::new(&format!("{}Proxy", trait_name), trait_name.span());
+    let doc = format!(
+        "Proxy implementing [`{}`] by dispatching to a script object.",
+        trait_name
+    );
+
+    let fns = input.items.iter().filter_map(|item| match item {
+        TraitItem::Fn(method) => Some(method),
+        _ => None,
+    });
+
+    let mut impls = Vec::new();
+    let mut try_fns = Vec::new();
+    for (method, info) in fns.zip(methods) {
+        let mut sig = method.sig.clone();
+        match sig.inputs.first() {
+            Some(FnArg::Receiver(recv)) if recv.reference.is_some() => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &method.sig,
+                    "proxy interface methods must take &self or &mut self",
+                ));
+            }
+        }
+
+        // Give every parameter a name so the body can forward it
+        let mut args = Vec::new();
+        let mut params = Vec::new();
+        for (i, arg) in sig.inputs.iter_mut().skip(1).enumerate() {
+            if let FnArg::Typed(pat_type) = arg {
+                let ident = syn::Ident::new(&format!("__arg{}", i), proc_macro2::Span::call_site());
+                *pat_type.pat = syn::parse_quote!(#ident);
+                let ty = &pat_type.ty;
+                params.push(quote! { #ident: #ty });
+                args.push(ident);
+            }
+        }
+
+        let method_name = &info.as_name;
+        let return_type = &info.return_type;
+        let try_name = syn::Ident::new(&format!("try_{}", sig.ident), sig.ident.span());
+        let try_doc = format!(
+            "Call the script's `{}`, returning its failure instead of panicking.",
+            method_name
+        );
+        try_fns.push(quote! {
+            #[doc = #try_doc]
+            pub fn #try_name(&self, #(#params),*) -> ::std::result::Result<#return_type, ::angelscript_core::NativeError> {
+                let __ret = self
+                    .dispatch
+                    .borrow_mut()
+                    .dispatch(
+                        #method_name,
+                        vec![#(::angelscript_core::IntoDynamic::into_dynamic(#args)),*],
+                    )?;
+                Ok(<#return_type as ::angelscript_core::FromDynamic>::from_dynamic(&__ret)?)
+            }
+        });
+
+        let body = match &info.error_type {
+            Some(error_type) => quote! {
+                self.#try_name(#(#args),*).map_err(<#error_type as ::std::convert::From<::angelscript_core::NativeError>>::from)
+            },
+            None => quote! {
+                self.#try_name(#(#args),*)
+                    .unwrap_or_else(|e| panic!("script method '{}' failed: {}", #method_name, e))
+            },
+        };
+        impls.push(quote! {
+            #sig {
+                #body
+            }
+        });
+    }
+
+    Ok(quote! {
+        #[doc = #doc]
+        #trait_vis struct #proxy_name<'a> {
+            dispatch: ::std::cell::RefCell<
+                ::std::boxed::Box<dyn ::angelscript_core::ScriptDispatch + 'a>,
+            >,
+        }
+
+        impl<'a> ::angelscript_core::ScriptProxy<'a> for #proxy_name<'a> {
+            fn interface_hash() -> ::angelscript_core::TypeHash {
+                ::angelscript_core::TypeHash::from_name(#as_name)
+            }
+
+            fn from_dispatch(
+                dispatch: ::std::boxed::Box<dyn ::angelscript_core::ScriptDispatch + 'a>,
+            ) -> Self {
+                Self {
+                    dispatch: ::std::cell::RefCell::new(dispatch),
+                }
+            }
+        }
+
+        impl<'a> #proxy_name<'a> {
+            #(#try_fns)*
+        }
+
+        impl<'a> #trait_name for #proxy_name<'a> {
+            #(#impls)*
+        }
+    })
+}
+
 /// Filter out #[function] attributes from a trait item.
 fn filter_trait_item_attrs(item: &TraitItem) -> TokenStream2 {
     match item {
@@ -152,7 +296,11 @@ struct MethodInfo {
     as_name: String,
     is_const: bool,
     param_types: Vec<syn::Type>,
+    /// Type the script method returns; `T` for trait methods returning
+    /// `Result<T, E>`.
     return_type: syn::Type,
+    /// `E` for trait methods returning `Result<T, E>`.
+    error_type: Option<syn::Type>,
 }
 
 fn collect_method_signatures(items: &[TraitItem]) -> syn::Result<Vec<MethodInfo>> {
@@ -193,10 +341,13 @@ fn collect_method_signatures(items: &[TraitItem]) -> syn::Result<Vec<MethodInfo>
                 })
                 .collect();
 
-            // Get return type
-            let return_type = match &sig.output {
-                ReturnType::Default => syn::parse_quote!(()),
-                ReturnType::Type(_, ty) => (**ty).clone(),
+            // Get return type, looking through `Result`
+            let (return_type, error_type) = match &sig.output {
+                ReturnType::Default => (syn::parse_quote!(()), None),
+                ReturnType::Type(_, ty) => match result_types(ty) {
+                    Some((ok, err)) => (ok, Some(err)),
+                    None => ((**ty).clone(), None),
+                },
             };
 
             methods.push(MethodInfo {
@@ -204,6 +355,7 @@ fn collect_method_signatures(items: &[TraitItem]) -> syn::Result<Vec<MethodInfo>
                 is_const,
                 param_types,
                 return_type,
+                error_type,
             });
         }
     }
@@ -211,6 +363,28 @@ fn collect_method_signatures(items: &[TraitItem]) -> syn::Result<Vec<MethodInfo>
     Ok(methods)
 }
 
+/// The `T` and `E` of a `Result<T, E>` type.
+fn result_types(ty: &syn::Type) -> Option<(syn::Type, syn::Type)> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    let mut types = args.args.iter().filter_map(|arg| match arg {
+        syn::GenericArgument::Type(ty) => Some(ty.clone()),
+        _ => None,
+    });
+    match (types.next(), types.next(), types.next()) {
+        (Some(ok), Some(err), None) => Some((ok, err)),
+        _ => None,
+    }
+}
+
 /// Parse #[function(...)] attributes from a method's attribute list.
 fn parse_method_function_attrs(attrs: &[Attribute]) -> syn::Result<FunctionAttrs> {
     for attr in attrs {
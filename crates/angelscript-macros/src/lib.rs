@@ -91,6 +91,7 @@ pub fn derive_any(input: TokenStream) -> TokenStream {
 /// ## Modifiers
 /// - `const` - Method is const (doesn't modify object)
 /// - `property` - Virtual property accessor
+/// - `pure` - No side effects; calls may be folded and reused by the compiler
 /// - `operator = Operator::Add` - Operator overload
 /// - `generic` - Uses generic calling convention
 ///
@@ -151,6 +152,8 @@ pub fn function(attr: TokenStream, item: TokenStream) -> TokenStream {
 /// # Attributes
 ///
 /// - `name = "..."` - Override the AngelScript interface name
+/// - `proxy` - Also generate `<Trait>Proxy`, which implements the trait by
+///   calling a script object (see `Unit::proxy`)
 ///
 /// # Example
 ///
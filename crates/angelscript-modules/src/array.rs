@@ -16,7 +16,12 @@ pub type Less = fn(Dynamic, Dynamic) -> bool;
 /// Placeholder for AngelScript `array<T>` template.
 ///
 /// This is an empty struct used purely for FFI registration.
-/// The actual implementation will be provided by the VM.
+/// The actual implementation will be provided by the VM, which grows the
+/// storage (`resize`, `reserve`, `insertLast`, ...) with
+/// [`MemoryBudget::reserve_vec`] so that exhausting memory raises a script
+/// exception instead of aborting.
+///
+/// [`MemoryBudget::reserve_vec`]: angelscript_core::MemoryBudget::reserve_vec
 #[derive(Any)]
 #[angelscript(name = "array", reference, template = "<T>")]
 pub struct ScriptArray;
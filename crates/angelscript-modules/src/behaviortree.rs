@@ -0,0 +1,668 @@
+//! Behavior trees for AI scripts.
+//!
+//! A tree is built from composite nodes (`sequence`, `selector`,
+//! `parallel`), decorators (`inverter`, `succeeder`, `repeat`) and leaves
+//! running script code. Leaves are functions or objects implementing
+//! `BTLeaf`, and return `BT_SUCCESS`, `BT_FAILURE` or `BT_RUNNING`:
+//!
+//! ```angelscript
+//! bool isHungry() { return hunger > 50; }
+//! int eat() { return food.consume() ? BT_SUCCESS : BT_RUNNING; }
+//! int wander() { moveRandomly(); return BT_RUNNING; }
+//!
+//! class Flee : BTLeaf {
+//!     int tick() { return escaped() ? BT_SUCCESS : BT_RUNNING; }
+//! }
+//!
+//! BTNode@ tree = selector()
+//!     .add(sequence().add(condition(isHungry)).add(action(eat)))
+//!     .add(leaf(Flee()))
+//!     .add(action(wander));
+//! ```
+//!
+//! A composite resumes at the child that was running on the previous tick,
+//! so `eat` keeps running without `isHungry` being checked again until the
+//! sequence finishes.
+//!
+//! The host ticks the tree, usually once per frame. The VM keeps a
+//! [`Node`] for every `BTNode`, and [`BehaviorTree::tick`] runs the leaves
+//! through the function passed to it:
+//!
+//! ```ignore
+//! let status = tree.tick(|leaf, kind| match kind {
+//!     Leaf::Condition => unit.call_callable::<_, bool>(leaf, ()).map(Status::from),
+//!     Leaf::Action => unit.call_callable::<_, i32>(leaf, ()).map(Status::from_i32_lossy),
+//! })?;
+//! ```
+//!
+//! The module is not part of the default modules; hosts install it with
+//! [`module`].
+
+use angelscript_core::{CallContext, native_error::NativeError};
+use angelscript_macros::{Any, funcdef, interface};
+use angelscript_registry::Module;
+
+/// Action leaf, returning `BT_SUCCESS`, `BT_FAILURE` or `BT_RUNNING`.
+///
+/// AngelScript: `funcdef int BTAction();`
+#[funcdef]
+pub type BTAction = fn() -> i32;
+
+/// Condition leaf, succeeding if it returns true.
+///
+/// AngelScript: `funcdef bool BTCondition();`
+#[funcdef]
+pub type BTCondition = fn() -> bool;
+
+/// Leaf implemented by a script class.
+///
+/// AngelScript: `interface BTLeaf { int tick(); }`
+#[interface(name = "BTLeaf")]
+pub trait BehaviorLeaf {
+    /// Run the leaf, returning `BT_SUCCESS`, `BT_FAILURE` or `BT_RUNNING`.
+    fn tick(&mut self) -> i32;
+}
+
+/// Placeholder for the AngelScript `BTNode` type.
+///
+/// This is an empty struct used for FFI registration. The VM stores a
+/// [`Node`] for each instance.
+#[derive(Any)]
+#[angelscript(name = "BTNode", reference)]
+pub struct ScriptBehaviorNode;
+
+impl ScriptBehaviorNode {
+    /// Increment reference count.
+    #[angelscript_macros::function(addref)]
+    pub fn add_ref(&self) {
+        todo!()
+    }
+
+    /// Decrement reference count.
+    #[angelscript_macros::function(release)]
+    pub fn release(&self) -> bool {
+        todo!()
+    }
+
+    /// Append a child to a composite node and return the node, so trees can
+    /// be built in one expression.
+    ///
+    /// Raises an exception if the node is not a composite.
+    #[angelscript_macros::function(instance, generic)]
+    #[param(type = ScriptBehaviorNode)]
+    #[returns(handle, type = ScriptBehaviorNode)]
+    pub fn add(_ctx: &mut CallContext) -> Result<(), NativeError> {
+        todo!()
+    }
+
+    /// Tick the tree below this node, returning its status.
+    ///
+    /// Note: The VM runs the leaves of the tree.
+    #[angelscript_macros::function(instance)]
+    pub fn tick(&mut self) -> i32 {
+        todo!()
+    }
+
+    /// Forget the running children, so the next tick starts over.
+    #[angelscript_macros::function(instance)]
+    pub fn reset(&mut self) {
+        todo!()
+    }
+}
+
+/// Create a sequence, which runs its children in order until one fails.
+///
+/// Usage: `BTNode@ n = sequence().add(a).add(b);`
+#[angelscript_macros::function(generic, name = "sequence")]
+#[returns(handle, type = ScriptBehaviorNode)]
+pub fn as_sequence(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Create a selector, which runs its children in order until one succeeds.
+///
+/// Usage: `BTNode@ n = selector().add(a).add(b);`
+#[angelscript_macros::function(generic, name = "selector")]
+#[returns(handle, type = ScriptBehaviorNode)]
+pub fn as_selector(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Create a parallel node, which ticks all its children and succeeds once
+/// `successes` of them succeeded.
+///
+/// Usage: `BTNode@ n = parallel(1).add(a).add(b);`
+#[angelscript_macros::function(generic, name = "parallel")]
+#[param(type = u32)]
+#[returns(handle, type = ScriptBehaviorNode)]
+pub fn as_parallel(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Create a decorator swapping success and failure of its child.
+///
+/// Usage: `BTNode@ n = inverter(condition(isSafe));`
+#[angelscript_macros::function(generic, name = "inverter")]
+#[param(type = ScriptBehaviorNode)]
+#[returns(handle, type = ScriptBehaviorNode)]
+pub fn as_inverter(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Create a decorator succeeding when its child fails.
+///
+/// Usage: `BTNode@ n = succeeder(action(tryOpenDoor));`
+#[angelscript_macros::function(generic, name = "succeeder")]
+#[param(type = ScriptBehaviorNode)]
+#[returns(handle, type = ScriptBehaviorNode)]
+pub fn as_succeeder(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Create a decorator running its child `count` times, or until it fails
+/// if `count` is 0.
+///
+/// Usage: `BTNode@ n = repeat(action(shoot), 3);`
+#[angelscript_macros::function(generic, name = "repeat")]
+#[param(type = ScriptBehaviorNode)]
+#[param(type = u32, default = "0")]
+#[returns(handle, type = ScriptBehaviorNode)]
+pub fn as_repeat(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Create a leaf running a function.
+///
+/// Usage: `BTNode@ n = action(eat);`
+#[angelscript_macros::function(generic, name = "action")]
+#[param(type = BTAction)]
+#[returns(handle, type = ScriptBehaviorNode)]
+pub fn as_action(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Create a leaf succeeding when a function returns true.
+///
+/// Usage: `BTNode@ n = condition(isHungry);`
+#[angelscript_macros::function(generic, name = "condition")]
+#[param(type = BTCondition)]
+#[returns(handle, type = ScriptBehaviorNode)]
+pub fn as_condition(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Create a leaf calling `tick` on a script object implementing `BTLeaf`.
+///
+/// Raises an exception if the object does not implement `BTLeaf`.
+///
+/// Usage: `BTNode@ n = leaf(Flee());`
+#[angelscript_macros::function(generic, name = "leaf")]
+#[param(variable)]
+#[returns(handle, type = ScriptBehaviorNode)]
+pub fn as_leaf(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Result of ticking a node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(i32)]
+pub enum Status {
+    /// The node finished and succeeded. `BT_SUCCESS` in scripts.
+    Success = 0,
+    /// The node finished and failed. `BT_FAILURE` in scripts.
+    Failure = 1,
+    /// The node needs more ticks. `BT_RUNNING` in scripts.
+    Running = 2,
+}
+
+impl Status {
+    /// Convert the status returned by a script leaf.
+    pub fn from_i32(status: i32) -> Option<Self> {
+        match status {
+            0 => Some(Self::Success),
+            1 => Some(Self::Failure),
+            2 => Some(Self::Running),
+            _ => None,
+        }
+    }
+
+    /// Convert the status returned by a script leaf, treating unknown
+    /// values as failure.
+    pub fn from_i32_lossy(status: i32) -> Self {
+        Self::from_i32(status).unwrap_or(Self::Failure)
+    }
+}
+
+impl From<bool> for Status {
+    fn from(success: bool) -> Self {
+        if success {
+            Self::Success
+        } else {
+            Self::Failure
+        }
+    }
+}
+
+/// Kind of a leaf being run by [`BehaviorTree::tick`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Leaf {
+    /// An action, returning any status.
+    Action,
+    /// A condition, returning success or failure.
+    Condition,
+}
+
+#[derive(Debug, Clone)]
+enum Kind<C> {
+    Sequence,
+    Selector,
+    Parallel { successes: usize },
+    Inverter,
+    Succeeder,
+    Repeat { count: Option<u32> },
+    Leaf(Leaf, C),
+}
+
+/// A node of a behavior tree holding leaves of type `C`, with its children.
+#[derive(Debug, Clone)]
+pub struct Node<C> {
+    kind: Kind<C>,
+    children: Vec<Node<C>>,
+    /// Running child of a sequence or selector, or successes of a repeat.
+    cursor: usize,
+    /// Finished children of a parallel node.
+    finished: Vec<Option<Status>>,
+}
+
+impl<C> Node<C> {
+    fn new(kind: Kind<C>, children: Vec<Node<C>>) -> Self {
+        Self {
+            kind,
+            children,
+            cursor: 0,
+            finished: Vec::new(),
+        }
+    }
+
+    /// A sequence, running its children in order until one fails.
+    pub fn sequence(children: impl IntoIterator<Item = Node<C>>) -> Self {
+        Self::new(Kind::Sequence, children.into_iter().collect())
+    }
+
+    /// A selector, running its children in order until one succeeds.
+    pub fn selector(children: impl IntoIterator<Item = Node<C>>) -> Self {
+        Self::new(Kind::Selector, children.into_iter().collect())
+    }
+
+    /// A parallel node, ticking all its children until `successes` of them
+    /// succeeded, or too many failed for that. `successes` is clamped to
+    /// the number of children.
+    pub fn parallel(successes: usize, children: impl IntoIterator<Item = Node<C>>) -> Self {
+        Self::new(Kind::Parallel { successes }, children.into_iter().collect())
+    }
+
+    /// Swap success and failure of `child`.
+    pub fn inverter(child: Node<C>) -> Self {
+        Self::new(Kind::Inverter, vec![child])
+    }
+
+    /// Succeed when `child` fails.
+    pub fn succeeder(child: Node<C>) -> Self {
+        Self::new(Kind::Succeeder, vec![child])
+    }
+
+    /// Run `child` until it succeeded `count` times, or forever if `count`
+    /// is `None`. Fails when the child fails.
+    ///
+    /// One run of the child is started per tick.
+    pub fn repeat(child: Node<C>, count: Option<u32>) -> Self {
+        Self::new(Kind::Repeat { count }, vec![child])
+    }
+
+    /// A leaf running an action.
+    pub fn action(action: C) -> Self {
+        Self::new(Kind::Leaf(Leaf::Action, action), Vec::new())
+    }
+
+    /// A leaf checking a condition.
+    pub fn condition(condition: C) -> Self {
+        Self::new(Kind::Leaf(Leaf::Condition, condition), Vec::new())
+    }
+
+    /// Append a child to a sequence, selector or parallel node.
+    ///
+    /// Returns the child back if this node is not a composite.
+    pub fn add(&mut self, child: Node<C>) -> Result<(), Node<C>> {
+        match self.kind {
+            Kind::Sequence | Kind::Selector | Kind::Parallel { .. } => {
+                self.children.push(child);
+                Ok(())
+            }
+            _ => Err(child),
+        }
+    }
+
+    /// Append a child, for building trees in one expression.
+    ///
+    /// # Panics
+    ///
+    /// Panics if this node is not a composite.
+    pub fn with(mut self, child: Node<C>) -> Self {
+        if self.add(child).is_err() {
+            panic!("only sequence, selector and parallel nodes have children");
+        }
+        self
+    }
+
+    /// The children of the node.
+    pub fn children(&self) -> &[Node<C>] {
+        &self.children
+    }
+
+    /// The leaf of a leaf node.
+    pub fn leaf(&self) -> Option<(Leaf, &C)> {
+        match &self.kind {
+            Kind::Leaf(kind, leaf) => Some((*kind, leaf)),
+            _ => None,
+        }
+    }
+
+    /// Forget the running children of this node and its descendants.
+    pub fn reset(&mut self) {
+        self.cursor = 0;
+        self.finished.clear();
+        for child in &mut self.children {
+            child.reset();
+        }
+    }
+
+    fn tick<E>(
+        &mut self,
+        run: &mut impl FnMut(&C, Leaf) -> Result<Status, E>,
+    ) -> Result<Status, E> {
+        let (stop, done) = match &self.kind {
+            Kind::Leaf(kind, leaf) => return run(leaf, *kind),
+            Kind::Sequence => (Status::Failure, Status::Success),
+            Kind::Selector => (Status::Success, Status::Failure),
+            Kind::Parallel { successes } => {
+                let successes = (*successes).min(self.children.len());
+                return self.tick_parallel(successes, run);
+            }
+            Kind::Inverter => {
+                return Ok(match self.children[0].tick(run)? {
+                    Status::Success => Status::Failure,
+                    Status::Failure => Status::Success,
+                    Status::Running => Status::Running,
+                });
+            }
+            Kind::Succeeder => {
+                return Ok(match self.children[0].tick(run)? {
+                    Status::Running => Status::Running,
+                    _ => Status::Success,
+                });
+            }
+            Kind::Repeat { count } => {
+                let count = *count;
+                return Ok(match self.children[0].tick(run)? {
+                    Status::Running => Status::Running,
+                    Status::Failure => {
+                        self.cursor = 0;
+                        Status::Failure
+                    }
+                    Status::Success => {
+                        self.cursor += 1;
+                        if count.is_some_and(|count| self.cursor >= count as usize) {
+                            self.cursor = 0;
+                            Status::Success
+                        } else {
+                            Status::Running
+                        }
+                    }
+                });
+            }
+        };
+
+        while self.cursor < self.children.len() {
+            match self.children[self.cursor].tick(run)? {
+                Status::Running => return Ok(Status::Running),
+                status if status == stop => {
+                    self.cursor = 0;
+                    return Ok(status);
+                }
+                _ => self.cursor += 1,
+            }
+        }
+        self.cursor = 0;
+        Ok(done)
+    }
+
+    fn tick_parallel<E>(
+        &mut self,
+        successes: usize,
+        run: &mut impl FnMut(&C, Leaf) -> Result<Status, E>,
+    ) -> Result<Status, E> {
+        self.finished.resize(self.children.len(), None);
+        for (child, finished) in self.children.iter_mut().zip(&mut self.finished) {
+            if finished.is_none() {
+                *finished = match child.tick(run)? {
+                    Status::Running => None,
+                    status => Some(status),
+                };
+            }
+        }
+
+        let count = |status| self.finished.iter().filter(|f| **f == Some(status)).count();
+        let succeeded = count(Status::Success);
+        let failed = count(Status::Failure);
+        let status = if succeeded >= successes {
+            Status::Success
+        } else if failed > self.children.len() - successes {
+            Status::Failure
+        } else {
+            return Ok(Status::Running);
+        };
+        // Stop the children still running
+        self.reset();
+        Ok(status)
+    }
+}
+
+/// A behavior tree, ticked by the host.
+#[derive(Debug, Clone)]
+pub struct BehaviorTree<C> {
+    root: Node<C>,
+    status: Option<Status>,
+}
+
+impl<C> BehaviorTree<C> {
+    /// Create a tree from its root node.
+    pub fn new(root: Node<C>) -> Self {
+        Self { root, status: None }
+    }
+
+    /// The root node.
+    pub fn root(&self) -> &Node<C> {
+        &self.root
+    }
+
+    /// Status returned by the last tick, or `None` before the first.
+    pub fn status(&self) -> Option<Status> {
+        self.status
+    }
+
+    /// Tick the tree, running leaves through `run`.
+    ///
+    /// If a leaf fails with an error, the tree is reset so the next tick
+    /// starts over.
+    pub fn tick<E>(
+        &mut self,
+        mut run: impl FnMut(&C, Leaf) -> Result<Status, E>,
+    ) -> Result<Status, E> {
+        match self.root.tick(&mut run) {
+            Ok(status) => {
+                self.status = Some(status);
+                Ok(status)
+            }
+            Err(error) => {
+                self.reset();
+                Err(error)
+            }
+        }
+    }
+
+    /// Forget the running nodes, so the next tick starts over.
+    pub fn reset(&mut self) {
+        self.root.reset();
+        self.status = None;
+    }
+}
+
+/// Creates the behavior tree module with the `BTNode` type, the leaf
+/// funcdefs and interface, the node constructors and the status constants.
+pub fn module() -> Module {
+    Module::new()
+        .ty::<ScriptBehaviorNode>()
+        .function(ScriptBehaviorNode::add_ref__meta)
+        .function(ScriptBehaviorNode::release__meta)
+        .function(ScriptBehaviorNode::add__meta)
+        .function(ScriptBehaviorNode::tick__meta)
+        .function(ScriptBehaviorNode::reset__meta)
+        .funcdef(__as_BTAction_funcdef_meta())
+        .funcdef(__as_BTCondition_funcdef_meta())
+        .interface(__as_BehaviorLeaf_interface_meta())
+        .function(as_sequence)
+        .function(as_selector)
+        .function(as_parallel)
+        .function(as_inverter)
+        .function(as_succeeder)
+        .function(as_repeat)
+        .function(as_action)
+        .function(as_condition)
+        .function(as_leaf)
+        .global("BT_SUCCESS", Status::Success as i32)
+        .global("BT_FAILURE", Status::Failure as i32)
+        .global("BT_RUNNING", Status::Running as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Runs leaves by name, returning scripted statuses in turn and
+    /// recording the calls.
+    struct Script {
+        results: HashMap<&'static str, Vec<Status>>,
+        calls: Vec<&'static str>,
+    }
+
+    impl Script {
+        fn new(results: &[(&'static str, &[Status])]) -> Self {
+            Self {
+                results: results.iter().map(|(n, r)| (*n, r.to_vec())).collect(),
+                calls: Vec::new(),
+            }
+        }
+
+        fn tick(&mut self, tree: &mut BehaviorTree<&'static str>) -> Status {
+            tree.tick(|leaf, _| {
+                self.calls.push(*leaf);
+                let results = self.results.get_mut(leaf).ok_or(())?;
+                Ok::<_, ()>(if results.len() > 1 {
+                    results.remove(0)
+                } else {
+                    results[0]
+                })
+            })
+            .unwrap()
+        }
+    }
+
+    use Status::*;
+
+    #[test]
+    fn test_module_creates() {
+        let m = module();
+        assert_eq!(m.classes.len(), 1);
+        assert_eq!(m.funcdefs.len(), 2);
+        assert_eq!(m.interfaces.len(), 1);
+        assert_eq!(m.functions.len(), 14);
+        assert_eq!(m.globals.len(), 3);
+    }
+
+    #[test]
+    fn composites_resume_running_children() {
+        let mut tree = BehaviorTree::new(
+            Node::selector([])
+                .with(Node::sequence([
+                    Node::condition("hungry"),
+                    Node::action("eat"),
+                ]))
+                .with(Node::action("wander")),
+        );
+        let mut script = Script::new(&[
+            ("hungry", &[Success, Failure]),
+            ("eat", &[Running, Success]),
+            ("wander", &[Running]),
+        ]);
+
+        assert_eq!(tree.status(), None);
+        assert_eq!(script.tick(&mut tree), Running);
+        assert_eq!(script.tick(&mut tree), Success);
+        assert_eq!(script.tick(&mut tree), Running);
+        assert_eq!(tree.status(), Some(Running));
+        assert_eq!(script.calls, ["hungry", "eat", "eat", "hungry", "wander"]);
+    }
+
+    #[test]
+    fn decorators_and_parallel() {
+        let mut tree = BehaviorTree::new(Node::parallel(
+            2,
+            [
+                Node::inverter(Node::condition("enemy")),
+                Node::succeeder(Node::action("open")),
+                Node::repeat(Node::action("shoot"), Some(2)),
+            ],
+        ));
+        let mut script = Script::new(&[
+            ("enemy", &[Failure]),
+            ("open", &[Failure]),
+            ("shoot", &[Success]),
+        ]);
+
+        // The first two succeed at once; the repeat is stopped
+        assert_eq!(script.tick(&mut tree), Success);
+        assert_eq!(script.calls, ["enemy", "open", "shoot"]);
+
+        let mut forever = BehaviorTree::new(Node::repeat(Node::action("patrol"), None));
+        let mut script = Script::new(&[("patrol", &[Success, Success, Failure])]);
+        assert_eq!(script.tick(&mut forever), Running);
+        assert_eq!(script.tick(&mut forever), Running);
+        assert_eq!(script.tick(&mut forever), Failure);
+
+        let mut all = BehaviorTree::new(Node::parallel(5, [Node::action("a"), Node::action("b")]));
+        let mut script = Script::new(&[("a", &[Success]), ("b", &[Running, Failure])]);
+        assert_eq!(script.tick(&mut all), Running);
+        assert_eq!(script.tick(&mut all), Failure);
+        assert_eq!(script.calls, ["a", "b", "b"]);
+    }
+
+    #[test]
+    fn errors_reset_the_tree() {
+        let mut tree = BehaviorTree::new(Node::sequence([
+            Node::action("walk"),
+            Node::action("missing"),
+        ]));
+        let mut script = Script::new(&[("walk", &[Success])]);
+        let result = tree.tick(|leaf, _| {
+            script.calls.push(*leaf);
+            script.results.get(leaf).map(|r| r[0]).ok_or("no leaf")
+        });
+        assert_eq!(result, Err("no leaf"));
+        assert_eq!(tree.status(), None);
+        assert!(Node::action("leaf").add(Node::action("child")).is_err());
+        assert_eq!(Status::from_i32(2), Some(Running));
+        assert_eq!(Status::from_i32_lossy(7), Failure);
+        assert_eq!(Status::from(true), Success);
+    }
+}
@@ -0,0 +1,416 @@
+//! ScriptBlob - AngelScript `blob` type.
+//!
+//! A `blob` is a byte buffer for binary protocols and save formats. Numbers
+//! are read and written little-endian at byte offsets:
+//!
+//! ```angelscript
+//! blob header;                       // growable, empty
+//! header.writeUInt32(0, 0x53415645);
+//! header.writeFloat(4, player.x);
+//! header.writeString(8, player.name);
+//!
+//! blob packet(16, true);             // fixed at 16 zero bytes
+//! if (!packet.writeInt64(12, id)) { /* does not fit */ }
+//! float x = header.readFloat(4);
+//! ```
+//!
+//! Writes past the end grow a growable blob, filling any gap with zeros,
+//! and fail on a fixed one, which never changes size. Reads past the end
+//! return 0, or the bytes that exist for `readString`.
+//!
+//! Rust code reads and fills a blob in place with [`ScriptBlob::as_slice`]
+//! and [`ScriptBlob::as_mut_slice`].
+
+use std::fmt;
+
+use angelscript_macros::Any;
+use angelscript_registry::Module;
+
+use crate::ScriptString;
+
+/// AngelScript `blob`, a fixed or growable byte buffer.
+#[derive(Any, Clone, Default, PartialEq, Eq)]
+#[angelscript(name = "blob", value)]
+pub struct ScriptBlob {
+    bytes: Vec<u8>,
+    fixed: bool,
+}
+
+impl ScriptBlob {
+    /// Create an empty growable blob.
+    #[angelscript_macros::function(keep, constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a blob of `size` zero bytes, fixed at that size if `fixed`.
+    #[angelscript_macros::function(keep, constructor)]
+    pub fn with_size(size: u32, #[param(default = "false")] fixed: bool) -> Self {
+        Self {
+            bytes: vec![0; size as usize],
+            fixed,
+        }
+    }
+
+    /// Create a blob fixed at the size of `bytes`.
+    pub fn fixed(bytes: Vec<u8>) -> Self {
+        Self { bytes, fixed: true }
+    }
+
+    /// Get the bytes.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Get the bytes for writing in place.
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+
+    /// Take the bytes.
+    pub fn into_vec(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Returns the size in bytes.
+    #[angelscript_macros::function(instance, const)]
+    pub fn size(&self) -> u32 {
+        self.bytes.len() as u32
+    }
+
+    /// Returns true if the blob has no bytes.
+    #[angelscript_macros::function(instance, const, name = "isEmpty")]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Returns true if the blob cannot change size.
+    #[angelscript_macros::function(instance, const, name = "isFixed")]
+    pub fn is_fixed(&self) -> bool {
+        self.fixed
+    }
+
+    /// Resize, zero-filling new bytes. Returns false for a fixed blob.
+    #[angelscript_macros::function(instance)]
+    pub fn resize(&mut self, size: u32) -> bool {
+        if self.fixed {
+            return false;
+        }
+        self.bytes.resize(size as usize, 0);
+        true
+    }
+
+    /// Append another blob's bytes. Returns false for a fixed blob.
+    #[angelscript_macros::function(instance)]
+    pub fn append(&mut self, #[param(const, in)] other: &Self) -> bool {
+        self.write(self.bytes.len() as u32, &other.bytes)
+    }
+
+    /// A growable copy of `length` bytes from `start`, clamped to the end.
+    #[angelscript_macros::function(instance, const)]
+    pub fn slice(&self, start: u32, length: u32) -> Self {
+        let start = (start as usize).min(self.bytes.len());
+        let end = start.saturating_add(length as usize).min(self.bytes.len());
+        Self::from(&self.bytes[start..end])
+    }
+
+    /// The `N` bytes at `offset`, if they are all in the blob.
+    fn read<const N: usize>(&self, offset: u32) -> [u8; N] {
+        let offset = offset as usize;
+        offset
+            .checked_add(N)
+            .and_then(|end| self.bytes.get(offset..end))
+            .and_then(|bytes| bytes.try_into().ok())
+            .unwrap_or([0; N])
+    }
+
+    /// Copy `data` to `offset`, growing a growable blob to fit.
+    pub(crate) fn write(&mut self, offset: u32, data: &[u8]) -> bool {
+        let offset = offset as usize;
+        let Some(end) = offset.checked_add(data.len()) else {
+            return false;
+        };
+        if end > self.bytes.len() {
+            if self.fixed {
+                return false;
+            }
+            self.bytes.resize(end, 0);
+        }
+        self.bytes[offset..end].copy_from_slice(data);
+        true
+    }
+
+    /// Read an int8 at `offset`.
+    #[angelscript_macros::function(instance, const, name = "readInt8")]
+    pub fn read_i8(&self, offset: u32) -> i8 {
+        i8::from_le_bytes(self.read(offset))
+    }
+
+    /// Read an int16 at `offset`.
+    #[angelscript_macros::function(instance, const, name = "readInt16")]
+    pub fn read_i16(&self, offset: u32) -> i16 {
+        i16::from_le_bytes(self.read(offset))
+    }
+
+    /// Read an int32 at `offset`.
+    #[angelscript_macros::function(instance, const, name = "readInt32")]
+    pub fn read_i32(&self, offset: u32) -> i32 {
+        i32::from_le_bytes(self.read(offset))
+    }
+
+    /// Read an int64 at `offset`.
+    #[angelscript_macros::function(instance, const, name = "readInt64")]
+    pub fn read_i64(&self, offset: u32) -> i64 {
+        i64::from_le_bytes(self.read(offset))
+    }
+
+    /// Read a uint8 at `offset`.
+    #[angelscript_macros::function(instance, const, name = "readUInt8")]
+    pub fn read_u8(&self, offset: u32) -> u8 {
+        u8::from_le_bytes(self.read(offset))
+    }
+
+    /// Read a uint16 at `offset`.
+    #[angelscript_macros::function(instance, const, name = "readUInt16")]
+    pub fn read_u16(&self, offset: u32) -> u16 {
+        u16::from_le_bytes(self.read(offset))
+    }
+
+    /// Read a uint32 at `offset`.
+    #[angelscript_macros::function(instance, const, name = "readUInt32")]
+    pub fn read_u32(&self, offset: u32) -> u32 {
+        u32::from_le_bytes(self.read(offset))
+    }
+
+    /// Read a uint64 at `offset`.
+    #[angelscript_macros::function(instance, const, name = "readUInt64")]
+    pub fn read_u64(&self, offset: u32) -> u64 {
+        u64::from_le_bytes(self.read(offset))
+    }
+
+    /// Read a float at `offset`.
+    #[angelscript_macros::function(instance, const, name = "readFloat")]
+    pub fn read_f32(&self, offset: u32) -> f32 {
+        f32::from_le_bytes(self.read(offset))
+    }
+
+    /// Read a double at `offset`.
+    #[angelscript_macros::function(instance, const, name = "readDouble")]
+    pub fn read_f64(&self, offset: u32) -> f64 {
+        f64::from_le_bytes(self.read(offset))
+    }
+
+    /// Read up to `length` bytes at `offset` as text. Bytes that do not
+    /// form UTF-8 are replaced with U+FFFD.
+    #[angelscript_macros::function(instance, const, name = "readString")]
+    pub fn read_string(&self, offset: u32, length: u32) -> ScriptString {
+        let text = self.slice(offset, length);
+        ScriptString::from(String::from_utf8_lossy(&text.bytes).into_owned())
+    }
+
+    /// Write an int8 at `offset`.
+    #[angelscript_macros::function(instance, name = "writeInt8")]
+    pub fn write_i8(&mut self, offset: u32, value: i8) -> bool {
+        self.write(offset, &value.to_le_bytes())
+    }
+
+    /// Write an int16 at `offset`.
+    #[angelscript_macros::function(instance, name = "writeInt16")]
+    pub fn write_i16(&mut self, offset: u32, value: i16) -> bool {
+        self.write(offset, &value.to_le_bytes())
+    }
+
+    /// Write an int32 at `offset`.
+    #[angelscript_macros::function(instance, name = "writeInt32")]
+    pub fn write_i32(&mut self, offset: u32, value: i32) -> bool {
+        self.write(offset, &value.to_le_bytes())
+    }
+
+    /// Write an int64 at `offset`.
+    #[angelscript_macros::function(instance, name = "writeInt64")]
+    pub fn write_i64(&mut self, offset: u32, value: i64) -> bool {
+        self.write(offset, &value.to_le_bytes())
+    }
+
+    /// Write a uint8 at `offset`.
+    #[angelscript_macros::function(instance, name = "writeUInt8")]
+    pub fn write_u8(&mut self, offset: u32, value: u8) -> bool {
+        self.write(offset, &value.to_le_bytes())
+    }
+
+    /// Write a uint16 at `offset`.
+    #[angelscript_macros::function(instance, name = "writeUInt16")]
+    pub fn write_u16(&mut self, offset: u32, value: u16) -> bool {
+        self.write(offset, &value.to_le_bytes())
+    }
+
+    /// Write a uint32 at `offset`.
+    #[angelscript_macros::function(instance, name = "writeUInt32")]
+    pub fn write_u32(&mut self, offset: u32, value: u32) -> bool {
+        self.write(offset, &value.to_le_bytes())
+    }
+
+    /// Write a uint64 at `offset`.
+    #[angelscript_macros::function(instance, name = "writeUInt64")]
+    pub fn write_u64(&mut self, offset: u32, value: u64) -> bool {
+        self.write(offset, &value.to_le_bytes())
+    }
+
+    /// Write a float at `offset`.
+    #[angelscript_macros::function(instance, name = "writeFloat")]
+    pub fn write_f32(&mut self, offset: u32, value: f32) -> bool {
+        self.write(offset, &value.to_le_bytes())
+    }
+
+    /// Write a double at `offset`.
+    #[angelscript_macros::function(instance, name = "writeDouble")]
+    pub fn write_f64(&mut self, offset: u32, value: f64) -> bool {
+        self.write(offset, &value.to_le_bytes())
+    }
+
+    /// Write the bytes of `text` at `offset`, without a length or
+    /// terminator.
+    #[angelscript_macros::function(instance, name = "writeString")]
+    pub fn write_string(&mut self, offset: u32, #[param(const, in)] text: &ScriptString) -> bool {
+        self.write(offset, text.as_str().as_bytes())
+    }
+
+    /// Index access - get byte at position, or 0 past the end.
+    #[angelscript_macros::function(operator = Operator::Index, const)]
+    pub fn byte_at(&self, index: u32) -> u8 {
+        self.read_u8(index)
+    }
+
+    /// Equality comparison of the bytes.
+    #[angelscript_macros::function(operator = Operator::Equals, const)]
+    pub fn eq_op(&self, #[param(const, in)] other: &Self) -> bool {
+        self.bytes == other.bytes
+    }
+}
+
+impl From<Vec<u8>> for ScriptBlob {
+    fn from(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            fixed: false,
+        }
+    }
+}
+
+impl From<&[u8]> for ScriptBlob {
+    fn from(bytes: &[u8]) -> Self {
+        Self::from(bytes.to_vec())
+    }
+}
+
+impl fmt::Debug for ScriptBlob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScriptBlob")
+            .field("len", &self.bytes.len())
+            .field("fixed", &self.fixed)
+            .finish()
+    }
+}
+
+/// Creates the blob module.
+pub fn module() -> Module {
+    Module::new()
+        .ty::<ScriptBlob>()
+        .function(ScriptBlob::new__meta)
+        .function(ScriptBlob::with_size__meta)
+        .function(ScriptBlob::size__meta)
+        .function(ScriptBlob::is_empty__meta)
+        .function(ScriptBlob::is_fixed__meta)
+        .function(ScriptBlob::resize__meta)
+        .function(ScriptBlob::append__meta)
+        .function(ScriptBlob::slice__meta)
+        .function(ScriptBlob::read_i8__meta)
+        .function(ScriptBlob::read_i16__meta)
+        .function(ScriptBlob::read_i32__meta)
+        .function(ScriptBlob::read_i64__meta)
+        .function(ScriptBlob::read_u8__meta)
+        .function(ScriptBlob::read_u16__meta)
+        .function(ScriptBlob::read_u32__meta)
+        .function(ScriptBlob::read_u64__meta)
+        .function(ScriptBlob::read_f32__meta)
+        .function(ScriptBlob::read_f64__meta)
+        .function(ScriptBlob::read_string__meta)
+        .function(ScriptBlob::write_i8__meta)
+        .function(ScriptBlob::write_i16__meta)
+        .function(ScriptBlob::write_i32__meta)
+        .function(ScriptBlob::write_i64__meta)
+        .function(ScriptBlob::write_u8__meta)
+        .function(ScriptBlob::write_u16__meta)
+        .function(ScriptBlob::write_u32__meta)
+        .function(ScriptBlob::write_u64__meta)
+        .function(ScriptBlob::write_f32__meta)
+        .function(ScriptBlob::write_f64__meta)
+        .function(ScriptBlob::write_string__meta)
+        .function(ScriptBlob::byte_at__meta)
+        .function(ScriptBlob::eq_op__meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbers_round_trip_little_endian() {
+        let mut blob = ScriptBlob::new();
+        assert!(blob.write_u32(0, 0x5341_5645));
+        assert!(blob.write_f32(4, 1.5));
+        assert!(blob.write_i16(10, -2));
+        assert_eq!(blob.size(), 12);
+        assert_eq!(&blob.as_slice()[..4], &[0x45, 0x56, 0x41, 0x53]);
+        // The gap is zero-filled
+        assert_eq!(&blob.as_slice()[8..10], &[0, 0]);
+
+        assert_eq!(blob.read_u32(0), 0x5341_5645);
+        assert_eq!(blob.read_f32(4), 1.5);
+        assert_eq!(blob.read_i16(10), -2);
+        assert_eq!(blob.read_u16(10), 0xFFFE);
+        assert_eq!(blob.byte_at(0), 0x45);
+        // Reads past the end are 0
+        assert_eq!(blob.read_i32(10), 0);
+        assert_eq!(blob.read_f64(u32::MAX), 0.0);
+        assert_eq!(blob.byte_at(12), 0);
+    }
+
+    #[test]
+    fn fixed_blobs_keep_their_size() {
+        let mut blob = ScriptBlob::with_size(8, true);
+        assert!(blob.write_i64(0, -1));
+        assert!(!blob.write_u8(8, 1));
+        assert!(!blob.write_u32(6, 1));
+        assert!(!blob.resize(16));
+        assert!(!blob.append(&ScriptBlob::from(vec![1])));
+        assert_eq!(blob.size(), 8);
+        assert_eq!(blob.read_i64(0), -1);
+
+        // Zero-copy access from Rust
+        blob.as_mut_slice()[0] = 7;
+        assert_eq!(blob.read_u8(0), 7);
+        assert!(ScriptBlob::fixed(vec![1, 2]).is_fixed());
+    }
+
+    #[test]
+    fn strings_and_slices() {
+        let mut blob = ScriptBlob::new();
+        assert!(blob.write_string(2, &ScriptString::from("héllo")));
+        assert_eq!(blob.read_string(2, 6).as_str(), "héllo");
+        assert_eq!(blob.read_string(2, 100).as_str(), "héllo");
+        // Half a character
+        assert_eq!(blob.read_string(2, 2).as_str(), "h\u{FFFD}");
+
+        let tail = blob.slice(4, 100);
+        assert!(!tail.is_fixed());
+        assert_eq!(tail.as_slice(), &blob.as_slice()[4..]);
+        assert!(blob.slice(100, 1).is_empty());
+
+        let mut joined = ScriptBlob::from(&b"ab"[..]);
+        assert!(joined.append(&ScriptBlob::from(&b"cd"[..])));
+        assert!(joined.eq_op(&ScriptBlob::from(b"abcd".to_vec())));
+    }
+}
@@ -3,10 +3,19 @@
 //! This crate provides the built-in types and functions for AngelScript:
 //!
 //! - **string** - `string` value type for text
+//! - **stringbuilder** - `stringbuilder` for building long text in loops
 //! - **array** - `array<T>` template type for dynamic arrays
+//! - **blob** - `blob` byte buffer for binary data
+//! - **behaviortree** - `BTNode` behavior trees with script-defined leaves
 //! - **dictionary** - `dictionary<K,V>` template type for key-value maps
-//! - **math** - Mathematical functions (sin, cos, sqrt, etc.)
+//! - **math** - Mathematical functions (sin, cos, sqrt, etc.), `math::Color`, geometry types, noise and `ease` easing functions
+//! - **path** - Path manipulation on strings (join, normalize, extension, etc.)
+//! - **reflect** - Lookup of the calling module's functions by declaration
+//! - **resource** - `ResId` handles to host resources, loaded by path
+//! - **statemachine** - `StateMachine` with named states, callbacks and guarded transitions
 //! - **std** - Standard functions (print, println, etc.)
+//! - **stream** - `stream` reading and writing blobs, files and host connections
+//! - **url** - Percent-encoding and decoding of text
 //!
 //! # Usage
 //!
@@ -28,12 +37,29 @@
 //! ```
 
 pub mod array;
+pub mod behaviortree;
+pub mod blob;
 pub mod dictionary;
 pub mod math;
+pub mod path;
+pub mod reflect;
+pub mod resource;
+pub mod statemachine;
 pub mod std;
+pub mod stream;
 pub mod string;
+pub mod stringbuilder;
+pub mod timers;
+pub mod url;
 
 // Re-export the types for convenience
 pub use array::ScriptArray;
+pub use behaviortree::{BehaviorTree, ScriptBehaviorNode};
+pub use blob::ScriptBlob;
 pub use dictionary::ScriptDict;
-pub use string::ScriptString;
+pub use resource::{ResId, ResourceResolver};
+pub use statemachine::{ScriptStateMachine, StateMachine};
+pub use stream::{ScriptStream, Stream, StreamBackend};
+pub use string::{NumberLocale, ScriptString};
+pub use stringbuilder::ScriptStringBuilder;
+pub use timers::{TimerHandle, Timers};
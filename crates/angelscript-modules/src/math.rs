@@ -1,9 +1,21 @@
 //! Math module providing constants and functions.
 //!
 //! All items are in the `math` namespace, e.g., `math::PI`, `math::sin(x)`.
+//! The [`color`] submodule adds `math::Color`, [`geometry`] the `math::Rect`,
+//! `math::AABB`, `math::Ray` and `math::Plane` types, [`noise`] Perlin and
+//! simplex noise, and [`ease`] the easing functions of the `ease`
+//! namespace, which is a module of its own.
 
 use angelscript_registry::Module;
 
+pub mod color;
+pub mod ease;
+pub mod geometry;
+pub mod noise;
+
+pub use color::Color;
+pub use geometry::{Aabb, Plane, Ray, Rect};
+
 // =============================================================================
 // TRIGONOMETRIC FUNCTIONS
 // =============================================================================
@@ -615,19 +627,54 @@ pub fn inv_lerp_f32(a: f32, b: f32, x: f32) -> f32 {
 }
 
 /// Smooth step (f64).
-#[angelscript_macros::function(name = "smoothstep")]
+#[angelscript_macros::function(pure, name = "smoothstep")]
 pub fn smooth_step(edge0: f64, edge1: f64, x: f64) -> f64 {
     let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
     t * t * (3.0 - 2.0 * t)
 }
 
 /// Smooth step (f32).
-#[angelscript_macros::function(name = "smoothstep")]
+#[angelscript_macros::function(pure, name = "smoothstep")]
 pub fn smooth_step_f32(edge0: f32, edge1: f32, x: f32) -> f32 {
     let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
     t * t * (3.0 - 2.0 * t)
 }
 
+/// Map `x` from one range to another, without clamping (f64).
+#[angelscript_macros::function(pure)]
+pub fn remap(x: f64, in_min: f64, in_max: f64, out_min: f64, out_max: f64) -> f64 {
+    __as_fn__lerp(out_min, out_max, __as_fn__inv_lerp(in_min, in_max, x))
+}
+
+/// Map `x` from one range to another, without clamping (f32).
+#[angelscript_macros::function(pure, name = "remap")]
+pub fn remap_f32(x: f32, in_min: f32, in_max: f32, out_min: f32, out_max: f32) -> f32 {
+    __as_fn__lerp_f32(out_min, out_max, __as_fn__inv_lerp_f32(in_min, in_max, x))
+}
+
+/// Catmull-Rom spline through `p1` (at `t` 0) and `p2` (at `t` 1), shaped
+/// by their neighbours `p0` and `p3` (f64).
+#[angelscript_macros::function(pure, name = "catmullRom")]
+pub fn catmull_rom(p0: f64, p1: f64, p2: f64, p3: f64, t: f64) -> f64 {
+    let (t2, t3) = (t * t, t * t * t);
+    0.5 * (2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+}
+
+/// Catmull-Rom spline (f32).
+#[angelscript_macros::function(pure, name = "catmullRom")]
+pub fn catmull_rom_f32(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    __as_fn__catmull_rom(
+        f64::from(p0),
+        f64::from(p1),
+        f64::from(p2),
+        f64::from(p3),
+        f64::from(t),
+    ) as f32
+}
+
 // =============================================================================
 // SPECIAL VALUE CHECKS
 // =============================================================================
@@ -1016,6 +1063,21 @@ pub fn module() -> Module {
         .function(inv_lerp_f32)
         .function(smooth_step)
         .function(smooth_step_f32)
+        .function(remap)
+        .function(remap_f32)
+        .function(catmull_rom)
+        .function(catmull_rom_f32)
+        // Noise
+        .function(noise::perlin_1)
+        .function(noise::perlin_1_f32)
+        .function(noise::perlin_2)
+        .function(noise::perlin_2_f32)
+        .function(noise::perlin_3)
+        .function(noise::perlin_3_f32)
+        .function(noise::simplex_2)
+        .function(noise::simplex_2_f32)
+        .function(noise::simplex_3)
+        .function(noise::simplex_3_f32)
         // Special values
         .function(is_nan)
         .function(is_nan_f32)
@@ -1058,6 +1120,41 @@ pub fn module() -> Module {
         .function(to_bits_f32)
         .function(from_bits_f64)
         .function(from_bits_f32)
+        // Color
+        .ty::<Color>()
+        .function(Color::new__meta)
+        .function(Color::to_hsv__meta)
+        .function(Color::lerp__meta)
+        .function(Color::to_hex__meta)
+        .function(Color::eq_op__meta)
+        .function(color::hsv)
+        .function(color::color_from_hex)
+        .function(color::lerp_color)
+        // Geometry
+        .ty::<Rect>()
+        .function(Rect::new__meta)
+        .function(Rect::is_empty__meta)
+        .function(Rect::area__meta)
+        .function(Rect::contains_point__meta)
+        .function(Rect::contains_rect__meta)
+        .function(Rect::intersects__meta)
+        .function(Rect::intersection__meta)
+        .function(Rect::eq_op__meta)
+        .ty::<Aabb>()
+        .function(Aabb::new__meta)
+        .function(Aabb::contains_point__meta)
+        .function(Aabb::contains_box__meta)
+        .function(Aabb::intersects__meta)
+        .function(Aabb::eq_op__meta)
+        .ty::<Ray>()
+        .function(Ray::new__meta)
+        .function(Ray::point_at__meta)
+        .function(Ray::intersects_box__meta)
+        .function(Ray::intersects_plane__meta)
+        .ty::<Plane>()
+        .function(Plane::new__meta)
+        .function(Plane::distance__meta)
+        .function(Plane::intersects__meta)
 }
 
 // =============================================================================
@@ -1125,6 +1222,20 @@ mod tests {
         assert!((__as_fn__lerp(0.0, 10.0, 1.0) - 10.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn test_remap_and_splines() {
+        assert!((__as_fn__remap(5.0, 0.0, 10.0, 100.0, 200.0) - 150.0).abs() < f64::EPSILON);
+        assert!((__as_fn__remap(20.0, 0.0, 10.0, 1.0, 0.0) + 1.0).abs() < f64::EPSILON);
+        assert!((__as_fn__smooth_step(0.0, 1.0, 0.5) - 0.5).abs() < f64::EPSILON);
+
+        // The spline passes through the inner points
+        let (p0, p1, p2, p3) = (0.0, 2.0, 3.0, 7.0);
+        assert!((__as_fn__catmull_rom(p0, p1, p2, p3, 0.0) - p1).abs() < f64::EPSILON);
+        assert!((__as_fn__catmull_rom(p0, p1, p2, p3, 1.0) - p2).abs() < f64::EPSILON);
+        // Evenly spaced points give a straight line
+        assert!((__as_fn__catmull_rom(0.0, 1.0, 2.0, 3.0, 0.25) - 1.25).abs() < f64::EPSILON);
+    }
+
     #[test]
     fn test_special_values() {
         assert!(__as_fn__is_nan(f64::NAN));
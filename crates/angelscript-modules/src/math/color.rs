@@ -0,0 +1,222 @@
+//! `math::Color`, an RGBA color with float channels.
+//!
+//! Channels are in `0..1`. Colors convert to and from HSV and hex text:
+//!
+//! ```angelscript
+//! math::Color red(1, 0, 0);
+//! math::Color sky = math::colorFromHex("#87CEEB");
+//! math::Color mid = math::lerp(red, sky, 0.5f);
+//! string hex = mid.toHex();                 // "#C36776"
+//! math::Color pastel = math::hsv(200, 0.3f, 1);
+//! ```
+//!
+//! The free functions are pure, so calls with constant arguments are folded
+//! while compiling.
+
+use angelscript_macros::Any;
+
+use crate::ScriptString;
+
+/// RGBA color with `f32` channels in `0..1`.
+#[derive(Any, Clone, Copy, Debug, Default, PartialEq)]
+#[angelscript(name = "Color", pod)]
+pub struct Color {
+    /// Red channel.
+    #[angelscript(get, set)]
+    pub r: f32,
+    /// Green channel.
+    #[angelscript(get, set)]
+    pub g: f32,
+    /// Blue channel.
+    #[angelscript(get, set)]
+    pub b: f32,
+    /// Alpha channel, 1 for opaque.
+    #[angelscript(get, set)]
+    pub a: f32,
+}
+
+impl Color {
+    /// Create a color from its channels.
+    #[angelscript_macros::function(keep, constructor)]
+    pub fn new(r: f32, g: f32, b: f32, #[param(default = "1.0f")] a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Create a color from hue in degrees, saturation and value in `0..1`.
+    pub fn from_hsv(h: f32, s: f32, v: f32, a: f32) -> Self {
+        let h = h.rem_euclid(360.0) / 60.0;
+        let (s, v) = (s.clamp(0.0, 1.0), v.clamp(0.0, 1.0));
+        let c = v * s;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+        let m = v - c;
+        Self::new(r + m, g + m, b + m, a)
+    }
+
+    /// Parse `#RGB`, `#RGBA`, `#RRGGBB` or `#RRGGBBAA`; the `#` is optional.
+    pub fn from_hex(text: &str) -> Option<Self> {
+        let digits = text.trim().strip_prefix('#').unwrap_or(text.trim());
+        if !digits.is_ascii() {
+            return None;
+        }
+        let channel = |at: usize, width: usize| {
+            let value = u8::from_str_radix(&digits[at * width..(at + 1) * width], 16).ok()?;
+            // A single digit stands for itself repeated: `F` is `FF`
+            Some(match width {
+                1 => f32::from(value * 17) / 255.0,
+                _ => f32::from(value) / 255.0,
+            })
+        };
+        let (width, alpha) = match digits.len() {
+            3 => (1, false),
+            4 => (1, true),
+            6 => (2, false),
+            8 => (2, true),
+            _ => return None,
+        };
+        Some(Self::new(
+            channel(0, width)?,
+            channel(1, width)?,
+            channel(2, width)?,
+            if alpha { channel(3, width)? } else { 1.0 },
+        ))
+    }
+
+    /// Hue in degrees, saturation and value.
+    #[angelscript_macros::function(instance, const, name = "toHsv")]
+    pub fn to_hsv(
+        &self,
+        #[param(out)] h: &mut f32,
+        #[param(out)] s: &mut f32,
+        #[param(out)] v: &mut f32,
+    ) {
+        let max = self.r.max(self.g).max(self.b);
+        let min = self.r.min(self.g).min(self.b);
+        let delta = max - min;
+        *h = if delta == 0.0 {
+            0.0
+        } else if max == self.r {
+            60.0 * ((self.g - self.b) / delta).rem_euclid(6.0)
+        } else if max == self.g {
+            60.0 * ((self.b - self.r) / delta + 2.0)
+        } else {
+            60.0 * ((self.r - self.g) / delta + 4.0)
+        };
+        *s = if max == 0.0 { 0.0 } else { delta / max };
+        *v = max;
+    }
+
+    /// Interpolate each channel towards `other`.
+    #[angelscript_macros::function(instance, const, pure)]
+    pub fn lerp(&self, #[param(const, in)] other: &Self, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + t * (b - a);
+        Self::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+            mix(self.a, other.a),
+        )
+    }
+
+    /// `#RRGGBB`, or `#RRGGBBAA` if the color is not opaque. Channels are
+    /// clamped to `0..1`.
+    #[angelscript_macros::function(instance, const, pure, name = "toHex")]
+    pub fn to_hex(&self) -> ScriptString {
+        let byte = |channel: f32| (channel.clamp(0.0, 1.0) * 255.0).round() as u8;
+        let mut hex = format!(
+            "#{:02X}{:02X}{:02X}",
+            byte(self.r),
+            byte(self.g),
+            byte(self.b)
+        );
+        if byte(self.a) != 255 {
+            hex.push_str(&format!("{:02X}", byte(self.a)));
+        }
+        ScriptString::from(hex)
+    }
+
+    /// Equality comparison.
+    #[angelscript_macros::function(operator = Operator::Equals, const)]
+    pub fn eq_op(&self, #[param(const, in)] other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// Color from hue in degrees, saturation and value in `0..1`.
+#[angelscript_macros::function(pure)]
+pub fn hsv(h: f32, s: f32, v: f32, #[param(default = "1.0f")] a: f32) -> Color {
+    Color::from_hsv(h, s, v, a)
+}
+
+/// Color from hex text, or transparent black if the text is not a color.
+#[angelscript_macros::function(pure, name = "colorFromHex")]
+pub fn color_from_hex(#[param(const, in)] text: &ScriptString) -> Color {
+    Color::from_hex(text.as_str()).unwrap_or(Color::new(0.0, 0.0, 0.0, 0.0))
+}
+
+/// Linear interpolation of colors.
+#[angelscript_macros::function(pure, name = "lerp")]
+pub fn lerp_color(#[param(const, in)] a: &Color, #[param(const, in)] b: &Color, t: f32) -> Color {
+    a.lerp(b, t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        let sky = Color::from_hex("#87CEEB").unwrap();
+        assert_eq!(sky.to_hex().as_str(), "#87CEEB");
+        assert_eq!(
+            Color::from_hex("f0a8").unwrap().to_hex().as_str(),
+            "#FF00AA88"
+        );
+        assert!(Color::from_hex("#12345").is_none());
+        assert!(Color::from_hex("#GGGGGG").is_none());
+        assert_eq!(
+            __as_fn__color_from_hex(&ScriptString::from("nope")),
+            Color::new(0.0, 0.0, 0.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn hsv_round_trips() {
+        let color = Color::from_hsv(200.0, 0.5, 0.8, 1.0);
+        let (mut h, mut s, mut v) = (0.0, 0.0, 0.0);
+        color.to_hsv(&mut h, &mut s, &mut v);
+        assert!(
+            close(h, 200.0) && close(s, 0.5) && close(v, 0.8),
+            "{h} {s} {v}"
+        );
+
+        assert_eq!(
+            Color::from_hsv(0.0, 1.0, 1.0, 1.0),
+            Color::new(1.0, 0.0, 0.0, 1.0)
+        );
+        assert_eq!(
+            Color::from_hsv(480.0, 1.0, 1.0, 1.0),
+            Color::new(0.0, 1.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn lerp_mixes_every_channel() {
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let clear = Color::new(0.0, 0.0, 1.0, 0.0);
+        let mid = __as_fn__lerp_color(&red, &clear, 0.5);
+        assert_eq!(mid, Color::new(0.5, 0.0, 0.5, 0.5));
+        assert_eq!(mid.to_hex().as_str(), "#80008080");
+    }
+}
@@ -0,0 +1,230 @@
+//! Easing functions for animation.
+//!
+//! All items are in the `ease` namespace. Each maps progress `t` in `0..1`
+//! to eased progress, starting at 0 and ending at 1:
+//!
+//! ```angelscript
+//! float x = math::lerp(startX, endX, ease::cubicInOut(t));
+//! ```
+//!
+//! `In` functions start slowly, `Out` functions end slowly and `InOut`
+//! functions do both. The elastic functions overshoot, leaving `0..1`
+//! briefly. Every function is pure, so calls with constant arguments are
+//! folded while compiling.
+
+use std::f64::consts::PI;
+
+use angelscript_registry::Module;
+
+// =============================================================================
+// QUADRATIC
+// =============================================================================
+
+/// Quadratic ease in (f64).
+#[angelscript_macros::function(pure, name = "quadIn")]
+pub fn quad_in(t: f64) -> f64 {
+    t * t
+}
+
+/// Quadratic ease in (f32).
+#[angelscript_macros::function(pure, name = "quadIn")]
+pub fn quad_in_f32(t: f32) -> f32 {
+    t * t
+}
+
+/// Quadratic ease out (f64).
+#[angelscript_macros::function(pure, name = "quadOut")]
+pub fn quad_out(t: f64) -> f64 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+/// Quadratic ease out (f32).
+#[angelscript_macros::function(pure, name = "quadOut")]
+pub fn quad_out_f32(t: f32) -> f32 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+/// Quadratic ease in and out (f64).
+#[angelscript_macros::function(pure, name = "quadInOut")]
+pub fn quad_in_out(t: f64) -> f64 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+    }
+}
+
+/// Quadratic ease in and out (f32).
+#[angelscript_macros::function(pure, name = "quadInOut")]
+pub fn quad_in_out_f32(t: f32) -> f32 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+    }
+}
+
+// =============================================================================
+// CUBIC
+// =============================================================================
+
+/// Cubic ease in (f64).
+#[angelscript_macros::function(pure, name = "cubicIn")]
+pub fn cubic_in(t: f64) -> f64 {
+    t * t * t
+}
+
+/// Cubic ease in (f32).
+#[angelscript_macros::function(pure, name = "cubicIn")]
+pub fn cubic_in_f32(t: f32) -> f32 {
+    t * t * t
+}
+
+/// Cubic ease out (f64).
+#[angelscript_macros::function(pure, name = "cubicOut")]
+pub fn cubic_out(t: f64) -> f64 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+/// Cubic ease out (f32).
+#[angelscript_macros::function(pure, name = "cubicOut")]
+pub fn cubic_out_f32(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+/// Cubic ease in and out (f64).
+#[angelscript_macros::function(pure, name = "cubicInOut")]
+pub fn cubic_in_out(t: f64) -> f64 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+/// Cubic ease in and out (f32).
+#[angelscript_macros::function(pure, name = "cubicInOut")]
+pub fn cubic_in_out_f32(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+// =============================================================================
+// ELASTIC
+// =============================================================================
+
+/// Elastic ease in (f64).
+#[angelscript_macros::function(pure, name = "elasticIn")]
+pub fn elastic_in(t: f64) -> f64 {
+    if t <= 0.0 || t >= 1.0 {
+        return t.clamp(0.0, 1.0);
+    }
+    -(2f64.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+}
+
+/// Elastic ease in (f32).
+#[angelscript_macros::function(pure, name = "elasticIn")]
+pub fn elastic_in_f32(t: f32) -> f32 {
+    __as_fn__elastic_in(f64::from(t)) as f32
+}
+
+/// Elastic ease out (f64).
+#[angelscript_macros::function(pure, name = "elasticOut")]
+pub fn elastic_out(t: f64) -> f64 {
+    if t <= 0.0 || t >= 1.0 {
+        return t.clamp(0.0, 1.0);
+    }
+    2f64.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+}
+
+/// Elastic ease out (f32).
+#[angelscript_macros::function(pure, name = "elasticOut")]
+pub fn elastic_out_f32(t: f32) -> f32 {
+    __as_fn__elastic_out(f64::from(t)) as f32
+}
+
+/// Elastic ease in and out (f64).
+#[angelscript_macros::function(pure, name = "elasticInOut")]
+pub fn elastic_in_out(t: f64) -> f64 {
+    if t <= 0.0 || t >= 1.0 {
+        return t.clamp(0.0, 1.0);
+    }
+    let wave = ((20.0 * t - 11.125) * (2.0 * PI / 4.5)).sin();
+    if t < 0.5 {
+        -(2f64.powf(20.0 * t - 10.0) * wave) / 2.0
+    } else {
+        2f64.powf(-20.0 * t + 10.0) * wave / 2.0 + 1.0
+    }
+}
+
+/// Elastic ease in and out (f32).
+#[angelscript_macros::function(pure, name = "elasticInOut")]
+pub fn elastic_in_out_f32(t: f32) -> f32 {
+    __as_fn__elastic_in_out(f64::from(t)) as f32
+}
+
+// =============================================================================
+// MODULE CREATION
+// =============================================================================
+
+/// Creates the easing module.
+///
+/// Everything is in the `ease` namespace, accessible as `ease::quadIn(t)`.
+pub fn module() -> Module {
+    Module::in_namespace(&["ease"])
+        .function(quad_in)
+        .function(quad_in_f32)
+        .function(quad_out)
+        .function(quad_out_f32)
+        .function(quad_in_out)
+        .function(quad_in_out_f32)
+        .function(cubic_in)
+        .function(cubic_in_f32)
+        .function(cubic_out)
+        .function(cubic_out_f32)
+        .function(cubic_in_out)
+        .function(cubic_in_out_f32)
+        .function(elastic_in)
+        .function(elastic_in_f32)
+        .function(elastic_out)
+        .function(elastic_out_f32)
+        .function(elastic_in_out)
+        .function(elastic_in_out_f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn endpoints_are_fixed() {
+        let functions: [fn(f64) -> f64; 9] = [
+            __as_fn__quad_in,
+            __as_fn__quad_out,
+            __as_fn__quad_in_out,
+            __as_fn__cubic_in,
+            __as_fn__cubic_out,
+            __as_fn__cubic_in_out,
+            __as_fn__elastic_in,
+            __as_fn__elastic_out,
+            __as_fn__elastic_in_out,
+        ];
+        for ease in functions {
+            assert!(ease(0.0).abs() < 1e-9);
+            assert!((ease(1.0) - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn in_out_curves_are_symmetric() {
+        assert!((__as_fn__quad_in_out(0.5) - 0.5).abs() < 1e-9);
+        assert!((__as_fn__cubic_in_out(0.25) + __as_fn__cubic_in_out(0.75) - 1.0).abs() < 1e-9);
+        assert!(__as_fn__quad_in(0.25) < 0.25 && __as_fn__quad_out(0.25) > 0.25);
+        assert!((__as_fn__cubic_in_f32(0.5) - 0.125).abs() < 1e-6);
+        // Elastic curves overshoot
+        assert!((0..10).any(|i| __as_fn__elastic_out(f64::from(i) / 10.0) > 1.0));
+    }
+}
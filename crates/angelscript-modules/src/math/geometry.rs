@@ -0,0 +1,442 @@
+//! Geometry value types with containment and intersection tests.
+//!
+//! `math::Rect` is a 2D rectangle, `math::AABB` an axis-aligned 3D box,
+//! `math::Ray` a half-line and `math::Plane` the points `p` with
+//! `dot(normal, p) + d == 0`:
+//!
+//! ```angelscript
+//! math::Rect button(10, 10, 80, 20);
+//! if (button.contains(mouseX, mouseY)) { ... }
+//!
+//! math::AABB crate(0, 0, 0, 1, 1, 1);
+//! math::Ray aim(eyeX, eyeY, eyeZ, dirX, dirY, dirZ);
+//! float t;
+//! if (aim.intersects(crate, t)) { ... }   // hit `t` units along the ray
+//! ```
+//!
+//! Ray directions need not be normalized; `t` is in multiples of the
+//! direction. Touching edges count as intersecting.
+
+use angelscript_macros::Any;
+
+/// Axis-aligned rectangle, from its top-left corner.
+#[derive(Any, Clone, Copy, Debug, Default, PartialEq)]
+#[angelscript(name = "Rect", pod)]
+pub struct Rect {
+    /// Left edge.
+    #[angelscript(get, set)]
+    pub x: f32,
+    /// Top edge.
+    #[angelscript(get, set)]
+    pub y: f32,
+    /// Width; a rectangle with no width or height is empty.
+    #[angelscript(get, set)]
+    pub width: f32,
+    /// Height.
+    #[angelscript(get, set)]
+    pub height: f32,
+}
+
+impl Rect {
+    /// Create a rectangle from its top-left corner and size.
+    #[angelscript_macros::function(keep, constructor)]
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+
+    /// Returns true if the rectangle has no area.
+    #[angelscript_macros::function(instance, const, pure, name = "isEmpty")]
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0.0 || self.height <= 0.0
+    }
+
+    /// Area of the rectangle.
+    #[angelscript_macros::function(instance, const, pure)]
+    pub fn area(&self) -> f32 {
+        if self.is_empty() {
+            0.0
+        } else {
+            self.width * self.height
+        }
+    }
+
+    /// Returns true if the point is inside or on the edge.
+    #[angelscript_macros::function(instance, const, pure, name = "contains")]
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        x >= self.x && x <= self.right() && y >= self.y && y <= self.bottom()
+    }
+
+    /// Returns true if `other` lies entirely inside.
+    #[angelscript_macros::function(instance, const, pure, name = "contains")]
+    pub fn contains_rect(&self, #[param(const, in)] other: &Self) -> bool {
+        other.x >= self.x
+            && other.right() <= self.right()
+            && other.y >= self.y
+            && other.bottom() <= self.bottom()
+    }
+
+    /// Returns true if the rectangles overlap or touch.
+    #[angelscript_macros::function(instance, const, pure)]
+    pub fn intersects(&self, #[param(const, in)] other: &Self) -> bool {
+        self.x <= other.right()
+            && other.x <= self.right()
+            && self.y <= other.bottom()
+            && other.y <= self.bottom()
+    }
+
+    /// The overlap of two rectangles, empty if they do not overlap.
+    #[angelscript_macros::function(instance, const, pure)]
+    pub fn intersection(&self, #[param(const, in)] other: &Self) -> Self {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        Self::new(x, y, (right - x).max(0.0), (bottom - y).max(0.0))
+    }
+
+    /// Equality comparison.
+    #[angelscript_macros::function(operator = Operator::Equals, const)]
+    pub fn eq_op(&self, #[param(const, in)] other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// Axis-aligned bounding box.
+#[derive(Any, Clone, Copy, Debug, Default, PartialEq)]
+#[angelscript(name = "AABB", pod)]
+pub struct Aabb {
+    /// Smallest x.
+    #[angelscript(get, set, name = "minX")]
+    pub min_x: f32,
+    /// Smallest y.
+    #[angelscript(get, set, name = "minY")]
+    pub min_y: f32,
+    /// Smallest z.
+    #[angelscript(get, set, name = "minZ")]
+    pub min_z: f32,
+    /// Largest x.
+    #[angelscript(get, set, name = "maxX")]
+    pub max_x: f32,
+    /// Largest y.
+    #[angelscript(get, set, name = "maxY")]
+    pub max_y: f32,
+    /// Largest z.
+    #[angelscript(get, set, name = "maxZ")]
+    pub max_z: f32,
+}
+
+impl Aabb {
+    /// Create a box from two opposite corners, in any order.
+    #[angelscript_macros::function(keep, constructor)]
+    pub fn new(x1: f32, y1: f32, z1: f32, x2: f32, y2: f32, z2: f32) -> Self {
+        Self {
+            min_x: x1.min(x2),
+            min_y: y1.min(y2),
+            min_z: z1.min(z2),
+            max_x: x1.max(x2),
+            max_y: y1.max(y2),
+            max_z: z1.max(z2),
+        }
+    }
+
+    fn min(&self) -> [f32; 3] {
+        [self.min_x, self.min_y, self.min_z]
+    }
+
+    fn max(&self) -> [f32; 3] {
+        [self.max_x, self.max_y, self.max_z]
+    }
+
+    /// Returns true if the point is inside or on a face.
+    #[angelscript_macros::function(instance, const, pure, name = "contains")]
+    pub fn contains_point(&self, x: f32, y: f32, z: f32) -> bool {
+        let (min, max) = (self.min(), self.max());
+        [x, y, z]
+            .iter()
+            .enumerate()
+            .all(|(axis, &v)| v >= min[axis] && v <= max[axis])
+    }
+
+    /// Returns true if `other` lies entirely inside.
+    #[angelscript_macros::function(instance, const, pure, name = "contains")]
+    pub fn contains_box(&self, #[param(const, in)] other: &Self) -> bool {
+        (0..3).all(|axis| {
+            other.min()[axis] >= self.min()[axis] && other.max()[axis] <= self.max()[axis]
+        })
+    }
+
+    /// Returns true if the boxes overlap or touch.
+    #[angelscript_macros::function(instance, const, pure)]
+    pub fn intersects(&self, #[param(const, in)] other: &Self) -> bool {
+        (0..3).all(|axis| {
+            self.min()[axis] <= other.max()[axis] && other.min()[axis] <= self.max()[axis]
+        })
+    }
+
+    /// Equality comparison.
+    #[angelscript_macros::function(operator = Operator::Equals, const)]
+    pub fn eq_op(&self, #[param(const, in)] other: &Self) -> bool {
+        self == other
+    }
+}
+
+/// Half-line from an origin along a direction.
+#[derive(Any, Clone, Copy, Debug, Default, PartialEq)]
+#[angelscript(name = "Ray", pod)]
+pub struct Ray {
+    /// Origin x.
+    #[angelscript(get, set, name = "originX")]
+    pub origin_x: f32,
+    /// Origin y.
+    #[angelscript(get, set, name = "originY")]
+    pub origin_y: f32,
+    /// Origin z.
+    #[angelscript(get, set, name = "originZ")]
+    pub origin_z: f32,
+    /// Direction x.
+    #[angelscript(get, set, name = "dirX")]
+    pub dir_x: f32,
+    /// Direction y.
+    #[angelscript(get, set, name = "dirY")]
+    pub dir_y: f32,
+    /// Direction z.
+    #[angelscript(get, set, name = "dirZ")]
+    pub dir_z: f32,
+}
+
+impl Ray {
+    /// Create a ray from its origin and direction.
+    #[angelscript_macros::function(keep, constructor)]
+    pub fn new(
+        origin_x: f32,
+        origin_y: f32,
+        origin_z: f32,
+        dir_x: f32,
+        dir_y: f32,
+        dir_z: f32,
+    ) -> Self {
+        Self {
+            origin_x,
+            origin_y,
+            origin_z,
+            dir_x,
+            dir_y,
+            dir_z,
+        }
+    }
+
+    fn origin(&self) -> [f32; 3] {
+        [self.origin_x, self.origin_y, self.origin_z]
+    }
+
+    fn dir(&self) -> [f32; 3] {
+        [self.dir_x, self.dir_y, self.dir_z]
+    }
+
+    /// The point `t` directions along the ray.
+    #[angelscript_macros::function(instance, const, name = "pointAt")]
+    pub fn point_at(
+        &self,
+        t: f32,
+        #[param(out)] x: &mut f32,
+        #[param(out)] y: &mut f32,
+        #[param(out)] z: &mut f32,
+    ) {
+        *x = self.origin_x + t * self.dir_x;
+        *y = self.origin_y + t * self.dir_y;
+        *z = self.origin_z + t * self.dir_z;
+    }
+
+    /// Where the ray enters a box, or 0 if it starts inside it.
+    pub fn hit_box(&self, aabb: &Aabb) -> Option<f32> {
+        let (origin, dir) = (self.origin(), self.dir());
+        let (min, max) = (aabb.min(), aabb.max());
+        let (mut near, mut far) = (0.0f32, f32::INFINITY);
+        for axis in 0..3 {
+            if dir[axis] == 0.0 {
+                // Parallel to the slab: inside it or never
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let a = (min[axis] - origin[axis]) / dir[axis];
+            let b = (max[axis] - origin[axis]) / dir[axis];
+            near = near.max(a.min(b));
+            far = far.min(a.max(b));
+            if near > far {
+                return None;
+            }
+        }
+        Some(near)
+    }
+
+    /// Where the ray crosses a plane. A ray lying in the plane hits at 0.
+    pub fn hit_plane(&self, plane: &Plane) -> Option<f32> {
+        let distance = plane.distance(self.origin_x, self.origin_y, self.origin_z);
+        let speed =
+            plane.normal_x * self.dir_x + plane.normal_y * self.dir_y + plane.normal_z * self.dir_z;
+        if speed == 0.0 {
+            return (distance == 0.0).then_some(0.0);
+        }
+        let t = -distance / speed;
+        (t >= 0.0).then_some(t)
+    }
+
+    /// Returns true if the ray hits the box, with `t` where it enters.
+    #[angelscript_macros::function(instance, const, name = "intersects")]
+    pub fn intersects_box(
+        &self,
+        #[param(const, in)] aabb: &Aabb,
+        #[param(out)] t: &mut f32,
+    ) -> bool {
+        self.hit_box(aabb).map(|hit| *t = hit).is_some()
+    }
+
+    /// Returns true if the ray hits the plane, with `t` where it crosses.
+    #[angelscript_macros::function(instance, const, name = "intersects")]
+    pub fn intersects_plane(
+        &self,
+        #[param(const, in)] plane: &Plane,
+        #[param(out)] t: &mut f32,
+    ) -> bool {
+        self.hit_plane(plane).map(|hit| *t = hit).is_some()
+    }
+}
+
+/// Plane of the points `p` with `dot(normal, p) + d == 0`.
+#[derive(Any, Clone, Copy, Debug, Default, PartialEq)]
+#[angelscript(name = "Plane", pod)]
+pub struct Plane {
+    /// Normal x.
+    #[angelscript(get, set, name = "normalX")]
+    pub normal_x: f32,
+    /// Normal y.
+    #[angelscript(get, set, name = "normalY")]
+    pub normal_y: f32,
+    /// Normal z.
+    #[angelscript(get, set, name = "normalZ")]
+    pub normal_z: f32,
+    /// Offset along the normal.
+    #[angelscript(get, set)]
+    pub d: f32,
+}
+
+impl Plane {
+    /// Create a plane from its normal, which is normalized, and offset.
+    #[angelscript_macros::function(keep, constructor)]
+    pub fn new(normal_x: f32, normal_y: f32, normal_z: f32, d: f32) -> Self {
+        let length = (normal_x * normal_x + normal_y * normal_y + normal_z * normal_z).sqrt();
+        let scale = if length > 0.0 { 1.0 / length } else { 1.0 };
+        Self {
+            normal_x: normal_x * scale,
+            normal_y: normal_y * scale,
+            normal_z: normal_z * scale,
+            d: d * scale,
+        }
+    }
+
+    /// Signed distance of a point, positive on the side the normal faces.
+    #[angelscript_macros::function(instance, const, pure)]
+    pub fn distance(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.normal_x * x + self.normal_y * y + self.normal_z * z + self.d
+    }
+
+    /// Returns true if the box is on both sides of the plane.
+    #[angelscript_macros::function(instance, const, pure)]
+    pub fn intersects(&self, #[param(const, in)] aabb: &Aabb) -> bool {
+        // The corners nearest and furthest along the normal
+        let pick = |normal: f32, min: f32, max: f32| {
+            if normal >= 0.0 {
+                (min, max)
+            } else {
+                (max, min)
+            }
+        };
+        let (near_x, far_x) = pick(self.normal_x, aabb.min_x, aabb.max_x);
+        let (near_y, far_y) = pick(self.normal_y, aabb.min_y, aabb.max_y);
+        let (near_z, far_z) = pick(self.normal_z, aabb.min_z, aabb.max_z);
+        self.distance(near_x, near_y, near_z) <= 0.0 && self.distance(far_x, far_y, far_z) >= 0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rect_containment_and_overlap() {
+        let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+        let b = Rect::new(5.0, 5.0, 10.0, 10.0);
+        assert!(a.contains_point(10.0, 0.0));
+        assert!(!a.contains_point(10.5, 5.0));
+        assert!(a.contains_rect(&Rect::new(2.0, 2.0, 3.0, 3.0)));
+        assert!(!a.contains_rect(&b));
+        assert!(a.intersects(&b));
+        assert_eq!(a.intersection(&b), Rect::new(5.0, 5.0, 5.0, 5.0));
+
+        let far = Rect::new(20.0, 20.0, 1.0, 1.0);
+        assert!(!a.intersects(&far));
+        assert!(a.intersection(&far).is_empty());
+        assert_eq!(a.intersection(&far).area(), 0.0);
+    }
+
+    #[test]
+    fn aabb_tests() {
+        let unit = Aabb::new(1.0, 1.0, 1.0, 0.0, 0.0, 0.0);
+        assert_eq!(unit.min_x, 0.0);
+        assert!(unit.contains_point(0.5, 1.0, 0.0));
+        assert!(!unit.contains_point(0.5, 1.5, 0.0));
+        assert!(unit.intersects(&Aabb::new(1.0, 1.0, 1.0, 2.0, 2.0, 2.0)));
+        assert!(!unit.intersects(&Aabb::new(1.5, 0.0, 0.0, 2.0, 1.0, 1.0)));
+        assert!(unit.contains_box(&Aabb::new(0.2, 0.2, 0.2, 0.8, 0.8, 0.8)));
+    }
+
+    #[test]
+    fn ray_hits_boxes_and_planes() {
+        let unit = Aabb::new(0.0, 0.0, 0.0, 1.0, 1.0, 1.0);
+        let mut t = -1.0;
+        assert!(Ray::new(-2.0, 0.5, 0.5, 1.0, 0.0, 0.0).intersects_box(&unit, &mut t));
+        assert_eq!(t, 2.0);
+        assert!(!Ray::new(-2.0, 0.5, 0.5, -1.0, 0.0, 0.0).intersects_box(&unit, &mut t));
+        assert!(!Ray::new(-2.0, 2.0, 0.5, 1.0, 0.0, 0.0).intersects_box(&unit, &mut t));
+        assert_eq!(
+            Ray::new(0.5, 0.5, 0.5, 0.0, 1.0, 0.0).hit_box(&unit),
+            Some(0.0)
+        );
+
+        // The ground, y = 0, seen from above
+        let ground = Plane::new(0.0, 2.0, 0.0, 0.0);
+        let down = Ray::new(0.0, 4.0, 0.0, 0.0, -2.0, 0.0);
+        assert!(down.intersects_plane(&ground, &mut t));
+        assert_eq!(t, 2.0);
+        let (mut x, mut y, mut z) = (1.0, 1.0, 1.0);
+        down.point_at(t, &mut x, &mut y, &mut z);
+        assert_eq!((x, y, z), (0.0, 0.0, 0.0));
+        assert_eq!(
+            Ray::new(0.0, 4.0, 0.0, 1.0, 0.0, 0.0).hit_plane(&ground),
+            None
+        );
+    }
+
+    #[test]
+    fn plane_sides() {
+        let plane = Plane::new(0.0, 0.0, 3.0, -1.5);
+        assert_eq!(plane.distance(0.0, 0.0, 2.0), 1.5);
+        assert!(plane.intersects(&Aabb::new(0.0, 0.0, 0.0, 1.0, 1.0, 1.0)));
+        assert!(!plane.intersects(&Aabb::new(0.0, 0.0, 1.0, 1.0, 1.0, 2.0)));
+    }
+}
@@ -0,0 +1,315 @@
+//! Gradient noise for procedural generation.
+//!
+//! `math::perlin` is Ken Perlin's improved noise in one to three dimensions,
+//! and `math::simplex` simplex noise in two or three. Both return values
+//! roughly in `-1..1` and are seamless and deterministic, with no seed: offset
+//! the coordinates for independent fields.
+//!
+//! ```angelscript
+//! float height = 0;
+//! for (int octave = 0; octave < 4; octave++) {
+//!     float scale = math::pow(2.0f, float(octave));
+//!     height += math::simplex(x * scale, y * scale) / scale;
+//! }
+//! ```
+//!
+//! Perlin noise is zero at every integer coordinate. Every function is pure,
+//! so calls with constant arguments are folded while compiling.
+
+/// The reference permutation of `0..256`.
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225, 140, 36, 103, 30, 69,
+    142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148, 247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219,
+    203, 117, 35, 11, 32, 57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122, 60, 211, 133, 230,
+    220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54, 65, 25, 63, 161, 1, 216, 80, 73, 209, 76,
+    132, 187, 208, 89, 18, 169, 200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173,
+    186, 3, 64, 52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212, 207, 206,
+    59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213, 119, 248, 152, 2, 44, 154, 163,
+    70, 221, 153, 101, 155, 167, 43, 172, 9, 129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232,
+    178, 185, 112, 104, 218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162,
+    241, 81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157, 184, 84, 204,
+    176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93, 222, 114, 67, 29, 24, 72, 243, 141,
+    128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+/// Gradients of simplex noise: the midpoints of a cube's edges.
+const GRADIENTS: [[f64; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// Permutation lookup, wrapping every 256 lattice cells.
+fn hash(i: usize) -> usize {
+    usize::from(PERMUTATION[i & 255])
+}
+
+/// Lattice cell of a coordinate, wrapped to `0..256`, and the offset in it.
+fn cell(x: f64) -> (usize, f64) {
+    let floor = x.floor();
+    ((floor as i64 & 255) as usize, x - floor)
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn mix(a: f64, b: f64, t: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// Dot product of the offset with one of Perlin's 12 gradients, picked by
+/// the low bits of `hash`.
+fn perlin_gradient(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        0..=3 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Improved Perlin noise at a point.
+pub fn perlin_3d(x: f64, y: f64, z: f64) -> f64 {
+    let ((xi, x), (yi, y), (zi, z)) = (cell(x), cell(y), cell(z));
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = hash(xi) + yi;
+    let (aa, ab) = (hash(a) + zi, hash(a + 1) + zi);
+    let b = hash(xi + 1) + yi;
+    let (ba, bb) = (hash(b) + zi, hash(b + 1) + zi);
+
+    let corner =
+        |h: usize, dx: f64, dy: f64, dz: f64| perlin_gradient(hash(h), x - dx, y - dy, z - dz);
+    mix(
+        mix(
+            mix(corner(aa, 0.0, 0.0, 0.0), corner(ba, 1.0, 0.0, 0.0), u),
+            mix(corner(ab, 0.0, 1.0, 0.0), corner(bb, 1.0, 1.0, 0.0), u),
+            v,
+        ),
+        mix(
+            mix(
+                corner(aa + 1, 0.0, 0.0, 1.0),
+                corner(ba + 1, 1.0, 0.0, 1.0),
+                u,
+            ),
+            mix(
+                corner(ab + 1, 0.0, 1.0, 1.0),
+                corner(bb + 1, 1.0, 1.0, 1.0),
+                u,
+            ),
+            v,
+        ),
+        w,
+    )
+}
+
+/// Contribution of one simplex corner at `offset` with gradient `gradient`.
+fn simplex_corner(radius: f64, gradient: usize, offset: [f64; 3]) -> f64 {
+    let t = radius - offset.iter().map(|d| d * d).sum::<f64>();
+    if t < 0.0 {
+        return 0.0;
+    }
+    let g = GRADIENTS[gradient % 12];
+    t.powi(4) * (g[0] * offset[0] + g[1] * offset[1] + g[2] * offset[2])
+}
+
+/// Simplex noise in the plane.
+pub fn simplex_2d(x: f64, y: f64) -> f64 {
+    let skew = 0.5 * (3f64.sqrt() - 1.0);
+    let unskew = (3.0 - 3f64.sqrt()) / 6.0;
+
+    // Skew to find the cell, then which of its two triangles holds the point
+    let s = (x + y) * skew;
+    let (i, j) = ((x + s).floor(), (y + s).floor());
+    let t = (i + j) * unskew;
+    let (x0, y0) = (x - (i - t), y - (j - t));
+    let (i1, j1) = if x0 > y0 { (1.0, 0.0) } else { (0.0, 1.0) };
+
+    let (ii, jj) = ((i as i64 & 255) as usize, (j as i64 & 255) as usize);
+    let corners = [
+        (0.0, 0.0, [x0, y0, 0.0]),
+        (i1, j1, [x0 - i1 + unskew, y0 - j1 + unskew, 0.0]),
+        (
+            1.0,
+            1.0,
+            [x0 - 1.0 + 2.0 * unskew, y0 - 1.0 + 2.0 * unskew, 0.0],
+        ),
+    ];
+    let total: f64 = corners
+        .iter()
+        .map(|&(di, dj, offset)| {
+            let gradient = hash(ii + di as usize + hash(jj + dj as usize));
+            simplex_corner(0.5, gradient, offset)
+        })
+        .sum();
+    70.0 * total
+}
+
+/// Simplex noise in space.
+pub fn simplex_3d(x: f64, y: f64, z: f64) -> f64 {
+    let (skew, unskew) = (1.0 / 3.0, 1.0 / 6.0);
+
+    // Skew to find the cell, then which of its six tetrahedra holds the point
+    let s = (x + y + z) * skew;
+    let (i, j, k) = ((x + s).floor(), (y + s).floor(), (z + s).floor());
+    let t = (i + j + k) * unskew;
+    let origin = [x - (i - t), y - (j - t), z - (k - t)];
+    let [x0, y0, z0] = origin;
+    let (first, second) = if x0 >= y0 {
+        if y0 >= z0 {
+            ([1, 0, 0], [1, 1, 0])
+        } else if x0 >= z0 {
+            ([1, 0, 0], [1, 0, 1])
+        } else {
+            ([0, 0, 1], [1, 0, 1])
+        }
+    } else if y0 < z0 {
+        ([0, 0, 1], [0, 1, 1])
+    } else if x0 < z0 {
+        ([0, 1, 0], [0, 1, 1])
+    } else {
+        ([0, 1, 0], [1, 1, 0])
+    };
+
+    let lattice = [i, j, k].map(|v| (v as i64 & 255) as usize);
+    let total: f64 = [[0, 0, 0], first, second, [1, 1, 1]]
+        .iter()
+        .enumerate()
+        .map(|(corner, step)| {
+            let offset =
+                [0, 1, 2].map(|axis| origin[axis] - step[axis] as f64 + corner as f64 * unskew);
+            let gradient = hash(
+                lattice[0] + step[0] + hash(lattice[1] + step[1] + hash(lattice[2] + step[2])),
+            );
+            simplex_corner(0.6, gradient, offset)
+        })
+        .sum();
+    32.0 * total
+}
+
+/// Perlin noise along a line (f64).
+#[angelscript_macros::function(pure, name = "perlin")]
+pub fn perlin_1(x: f64) -> f64 {
+    perlin_3d(x, 0.0, 0.0)
+}
+
+/// Perlin noise along a line (f32).
+#[angelscript_macros::function(pure, name = "perlin")]
+pub fn perlin_1_f32(x: f32) -> f32 {
+    perlin_3d(f64::from(x), 0.0, 0.0) as f32
+}
+
+/// Perlin noise in the plane (f64).
+#[angelscript_macros::function(pure, name = "perlin")]
+pub fn perlin_2(x: f64, y: f64) -> f64 {
+    perlin_3d(x, y, 0.0)
+}
+
+/// Perlin noise in the plane (f32).
+#[angelscript_macros::function(pure, name = "perlin")]
+pub fn perlin_2_f32(x: f32, y: f32) -> f32 {
+    perlin_3d(f64::from(x), f64::from(y), 0.0) as f32
+}
+
+/// Perlin noise in space (f64).
+#[angelscript_macros::function(pure, name = "perlin")]
+pub fn perlin_3(x: f64, y: f64, z: f64) -> f64 {
+    perlin_3d(x, y, z)
+}
+
+/// Perlin noise in space (f32).
+#[angelscript_macros::function(pure, name = "perlin")]
+pub fn perlin_3_f32(x: f32, y: f32, z: f32) -> f32 {
+    perlin_3d(f64::from(x), f64::from(y), f64::from(z)) as f32
+}
+
+/// Simplex noise in the plane (f64).
+#[angelscript_macros::function(pure, name = "simplex")]
+pub fn simplex_2(x: f64, y: f64) -> f64 {
+    simplex_2d(x, y)
+}
+
+/// Simplex noise in the plane (f32).
+#[angelscript_macros::function(pure, name = "simplex")]
+pub fn simplex_2_f32(x: f32, y: f32) -> f32 {
+    simplex_2d(f64::from(x), f64::from(y)) as f32
+}
+
+/// Simplex noise in space (f64).
+#[angelscript_macros::function(pure, name = "simplex")]
+pub fn simplex_3(x: f64, y: f64, z: f64) -> f64 {
+    simplex_3d(x, y, z)
+}
+
+/// Simplex noise in space (f32).
+#[angelscript_macros::function(pure, name = "simplex")]
+pub fn simplex_3_f32(x: f32, y: f32, z: f32) -> f32 {
+    simplex_3d(f64::from(x), f64::from(y), f64::from(z)) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sample points spread over several cells, including negative ones.
+    fn samples() -> impl Iterator<Item = (f64, f64, f64)> {
+        (0..2000).map(|i| {
+            let i = f64::from(i);
+            (i * 0.137 - 50.0, i * 0.071 - 30.0, i * 0.053 - 20.0)
+        })
+    }
+
+    #[test]
+    fn perlin_is_zero_on_the_lattice() {
+        for (x, y, z) in [(0.0, 0.0, 0.0), (3.0, -7.0, 12.0), (-256.0, 1.0, 255.0)] {
+            assert_eq!(perlin_3d(x, y, z), 0.0);
+        }
+        assert_eq!(__as_fn__perlin_1(5.0), 0.0);
+        assert_ne!(__as_fn__perlin_2(5.5, 0.25), 0.0);
+    }
+
+    #[test]
+    fn noise_stays_in_range_and_varies() {
+        let mut perlin_spread = (0.0f64, 0.0f64);
+        let mut simplex_spread = (0.0f64, 0.0f64);
+        for (x, y, z) in samples() {
+            for value in [perlin_3d(x, y, z), simplex_3d(x, y, z), simplex_2d(x, y)] {
+                assert!((-1.0..=1.0).contains(&value), "{value} at {x} {y} {z}");
+            }
+            let p = perlin_3d(x, y, z);
+            perlin_spread = (perlin_spread.0.min(p), perlin_spread.1.max(p));
+            let s = simplex_2d(x, y);
+            simplex_spread = (simplex_spread.0.min(s), simplex_spread.1.max(s));
+        }
+        assert!(perlin_spread.0 < -0.3 && perlin_spread.1 > 0.3);
+        assert!(simplex_spread.0 < -0.5 && simplex_spread.1 > 0.5);
+    }
+
+    #[test]
+    fn noise_is_continuous() {
+        let step = 1e-4;
+        for (x, y, z) in samples() {
+            let near = |f: &dyn Fn(f64, f64, f64) -> f64| {
+                (f(x, y, z) - f(x + step, y + step, z + step)).abs()
+            };
+            assert!(near(&perlin_3d) < 0.01, "perlin jumps at {x} {y} {z}");
+            assert!(near(&simplex_3d) < 0.01, "simplex jumps at {x} {y} {z}");
+            assert!(near(&|x, y, _| simplex_2d(x, y)) < 0.01);
+        }
+        // Wraps every 256 cells
+        assert_eq!(perlin_3d(1.5, 2.25, 3.75), perlin_3d(257.5, 2.25, 3.75));
+    }
+}
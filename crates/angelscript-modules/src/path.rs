@@ -0,0 +1,193 @@
+//! Path module for manipulating paths as strings.
+//!
+//! All items are in the `path` namespace, e.g., `path::join(dir, file)`.
+//! Nothing here touches the file system: paths are plain text, separated by
+//! `/`. `normalize` also accepts `\`, so paths typed on Windows work too:
+//!
+//! ```angelscript
+//! path::join("mods/hero", "textures/skin.png")   // "mods/hero/textures/skin.png"
+//! path::normalize("mods\\hero/../base/./a.txt")   // "mods/base/a.txt"
+//! path::extension("skin.png")                     // "png"
+//! path::basename("mods/hero/skin.png")            // "skin.png"
+//! path::dirname("mods/hero/skin.png")             // "mods/hero"
+//! ```
+
+use angelscript_registry::Module;
+
+use crate::ScriptString;
+
+/// Join two paths with a `/`. A rooted `b` replaces `a`.
+#[angelscript_macros::function]
+pub fn join(
+    #[param(const, in)] a: &ScriptString,
+    #[param(const, in)] b: &ScriptString,
+) -> ScriptString {
+    let (a, b) = (a.as_str(), b.as_str());
+    if a.is_empty() || b.starts_with('/') {
+        return ScriptString::from(b);
+    }
+    match a.ends_with('/') || b.is_empty() {
+        true => ScriptString::from(format!("{a}{b}")),
+        false => ScriptString::from(format!("{a}/{b}")),
+    }
+}
+
+/// Normalize a path: use `/` separators, drop empty and `.` components and
+/// resolve `..` against the component before it.
+///
+/// A relative path keeps the `..` it cannot resolve; a rooted path drops
+/// them. An empty result is `.`.
+#[angelscript_macros::function]
+pub fn normalize(#[param(const, in)] p: &ScriptString) -> ScriptString {
+    let text = p.as_str().replace('\\', "/");
+    let rooted = text.starts_with('/');
+
+    let mut parts: Vec<&str> = Vec::new();
+    for part in text.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => match parts.last() {
+                Some(&last) if last != ".." => {
+                    parts.pop();
+                }
+                _ if rooted => {}
+                _ => parts.push(".."),
+            },
+            _ => parts.push(part),
+        }
+    }
+
+    let joined = parts.join("/");
+    ScriptString::from(match (rooted, joined.is_empty()) {
+        (true, _) => format!("/{joined}"),
+        (false, true) => ".".to_string(),
+        (false, false) => joined,
+    })
+}
+
+/// Last component of a path, ignoring trailing separators.
+#[angelscript_macros::function]
+pub fn basename(#[param(const, in)] p: &ScriptString) -> ScriptString {
+    ScriptString::from(split(p.as_str()).1)
+}
+
+/// Everything before the last component, without the separator. Empty if
+/// the path has a single component; `/` for a component at the root.
+#[angelscript_macros::function]
+pub fn dirname(#[param(const, in)] p: &ScriptString) -> ScriptString {
+    ScriptString::from(split(p.as_str()).0)
+}
+
+/// Extension of the last component, without the dot. Empty if there is
+/// none; a leading dot (`.gitignore`) does not start an extension.
+#[angelscript_macros::function]
+pub fn extension(#[param(const, in)] p: &ScriptString) -> ScriptString {
+    ScriptString::from(split_extension(split(p.as_str()).1).1)
+}
+
+/// Last component of a path, without its extension.
+#[angelscript_macros::function]
+pub fn stem(#[param(const, in)] p: &ScriptString) -> ScriptString {
+    ScriptString::from(split_extension(split(p.as_str()).1).0)
+}
+
+/// Whether a path starts at the root (`/`, `\` or a drive like `C:`).
+#[angelscript_macros::function(name = "isAbsolute")]
+pub fn is_absolute(#[param(const, in)] p: &ScriptString) -> bool {
+    let p = p.as_str();
+    let bytes = p.as_bytes();
+    p.starts_with(['/', '\\'])
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// Split a path into its directory and last component.
+fn split(p: &str) -> (&str, &str) {
+    let trimmed = p.trim_end_matches('/');
+    if trimmed.is_empty() {
+        // "" or only separators
+        return (if p.is_empty() { "" } else { "/" }, "");
+    }
+    match trimmed.rfind('/') {
+        Some(at) => {
+            // A run of leading separators is the root
+            let dir = trimmed[..at].trim_end_matches('/');
+            (if dir.is_empty() { "/" } else { dir }, &trimmed[at + 1..])
+        }
+        None => ("", trimmed),
+    }
+}
+
+/// Split a file name into its stem and extension.
+fn split_extension(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(at) if at > 0 => (&name[..at], &name[at + 1..]),
+        _ => (name, ""),
+    }
+}
+
+/// Creates the path module with all functions.
+pub fn module() -> Module {
+    Module::in_namespace(&["path"])
+        .function(join)
+        .function(normalize)
+        .function(basename)
+        .function(dirname)
+        .function(extension)
+        .function(stem)
+        .function(is_absolute)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s(text: &str) -> ScriptString {
+        ScriptString::from(text)
+    }
+
+    #[test]
+    fn join_adds_one_separator() {
+        assert_eq!(__as_fn__join(&s("a/b"), &s("c.txt")).as_str(), "a/b/c.txt");
+        assert_eq!(__as_fn__join(&s("a/b/"), &s("c.txt")).as_str(), "a/b/c.txt");
+        assert_eq!(__as_fn__join(&s(""), &s("c.txt")).as_str(), "c.txt");
+        assert_eq!(__as_fn__join(&s("a"), &s("/etc")).as_str(), "/etc");
+        assert_eq!(__as_fn__join(&s("a"), &s("")).as_str(), "a");
+    }
+
+    #[test]
+    fn normalize_resolves_dots() {
+        assert_eq!(
+            __as_fn__normalize(&s("a\\b/../c/./d.txt")).as_str(),
+            "a/c/d.txt"
+        );
+        assert_eq!(__as_fn__normalize(&s("a//b/")).as_str(), "a/b");
+        assert_eq!(__as_fn__normalize(&s("../a/../../b")).as_str(), "../../b");
+        assert_eq!(__as_fn__normalize(&s("/../a")).as_str(), "/a");
+        assert_eq!(__as_fn__normalize(&s("a/..")).as_str(), ".");
+        assert_eq!(__as_fn__normalize(&s("/")).as_str(), "/");
+    }
+
+    #[test]
+    fn components() {
+        assert_eq!(
+            __as_fn__basename(&s("mods/hero/skin.png")).as_str(),
+            "skin.png"
+        );
+        assert_eq!(
+            __as_fn__dirname(&s("mods/hero/skin.png")).as_str(),
+            "mods/hero"
+        );
+        assert_eq!(__as_fn__basename(&s("mods/hero/")).as_str(), "hero");
+        assert_eq!(__as_fn__dirname(&s("skin.png")).as_str(), "");
+        assert_eq!(__as_fn__dirname(&s("/skin.png")).as_str(), "/");
+        assert_eq!(__as_fn__dirname(&s("//skin.png")).as_str(), "/");
+        assert_eq!(__as_fn__basename(&s("//skin.png")).as_str(), "skin.png");
+        assert_eq!(__as_fn__extension(&s("a/skin.tar.gz")).as_str(), "gz");
+        assert_eq!(__as_fn__stem(&s("a/skin.tar.gz")).as_str(), "skin.tar");
+        assert_eq!(__as_fn__extension(&s(".gitignore")).as_str(), "");
+        assert_eq!(__as_fn__extension(&s("a.d/readme")).as_str(), "");
+        assert!(__as_fn__is_absolute(&s("/a")));
+        assert!(__as_fn__is_absolute(&s("C:\\a")));
+        assert!(!__as_fn__is_absolute(&s("a/b")));
+    }
+}
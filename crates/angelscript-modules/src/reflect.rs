@@ -0,0 +1,110 @@
+//! Reflection of the calling script's own module.
+//!
+//! Lets scripts find their own functions at runtime, for plugin-style
+//! architectures where scripts register their handlers by convention
+//! instead of through a hand-written table:
+//!
+//! ```angelscript
+//! funcdef void Handler(const string &in);
+//!
+//! void onSave(const string &in path) { }
+//! void onLoad(const string &in path) { }
+//!
+//! void registerHandlers() {
+//!     for (uint i = 0; i < getFunctionCount(); i++) {
+//!         string decl = getFunctionDecl(i);
+//!         Handler@ handler;
+//!         if (decl.findFirst("void on") == 0 && getFunctionByDecl(decl, @handler))
+//!             addHandler(handler);
+//!     }
+//! }
+//! ```
+//!
+//! Functions are the global functions of the unit running the script, in
+//! declaration order. A declaration names types by their qualified name and
+//! leaves out parameter names, like `int calc(int, float)`; the host sees
+//! the same list through `Unit::function_declarations` and looks functions
+//! up the same way with `Unit::get_function_by_decl`.
+//!
+//! The module is not part of the default modules, so hosts opt in to
+//! exposing it.
+
+use angelscript_core::{CallContext, native_error::NativeError};
+use angelscript_registry::Module;
+
+use crate::ScriptString;
+
+/// Get the number of global functions in the calling module.
+///
+/// Usage: `uint count = getFunctionCount();`
+///
+/// Note: The VM answers from the unit running the script.
+#[angelscript_macros::function(generic, name = "getFunctionCount")]
+#[returns(type = u32)]
+pub fn as_get_function_count(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Get the declaration of a global function of the calling module by index.
+///
+/// Raises an exception if the index is out of range.
+///
+/// Usage: `string decl = getFunctionDecl(0);`
+///
+/// Note: The VM answers from the unit running the script.
+#[angelscript_macros::function(generic, name = "getFunctionDecl")]
+#[param(type = u32, in)]
+#[returns(type = ScriptString)]
+pub fn as_get_function_decl(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Check if the calling module has a global function with a declaration.
+///
+/// Usage: `if (functionExists("void onSave(const string &in)")) { ... }`
+///
+/// Note: The VM answers from the unit running the script.
+#[angelscript_macros::function(generic, name = "functionExists")]
+#[param(type = ScriptString, const, in)]
+#[returns(type = bool)]
+pub fn as_function_exists(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Get a handle to a global function of the calling module by declaration.
+///
+/// The second argument must be a funcdef handle whose signature matches
+/// the function. Returns false, leaving it unchanged, if no function has the
+/// declaration or the signatures differ.
+///
+/// Usage: `Handler@ h; getFunctionByDecl("void onSave(const string &in)", @h);`
+///
+/// Note: The VM creates the handle in the unit running the script.
+#[angelscript_macros::function(generic, name = "getFunctionByDecl")]
+#[param(type = ScriptString, const, in)]
+#[param(variable, out)]
+#[returns(type = bool)]
+pub fn as_get_function_by_decl(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Creates the reflection module.
+pub fn module() -> Module {
+    Module::new()
+        .function(as_get_function_count)
+        .function(as_get_function_decl)
+        .function(as_function_exists)
+        .function(as_get_function_by_decl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_creates() {
+        let m = module();
+        assert!(m.namespace.is_empty());
+        assert_eq!(m.functions.len(), 4);
+    }
+}
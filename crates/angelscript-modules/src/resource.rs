@@ -0,0 +1,125 @@
+//! Resource handles.
+//!
+//! `ResId` identifies an asset of the host engine, like a texture, sound or
+//! level, by an opaque id. Scripts get one by path:
+//!
+//! ```angelscript
+//! ResId icon = load("ui/icon.png");
+//! if (icon.isValid()) { ... }
+//! ```
+//!
+//! The host maps paths to ids with a [`ResourceResolver`]. Calls with a
+//! literal path are resolved while the script compiles, so a missing asset
+//! is a compilation error instead of an invalid id at runtime. Hosts wrap
+//! `ResId` in their own asset types (e.g. a `Texture` constructed from a
+//! `ResId`) to keep scripts engine-agnostic.
+
+use angelscript_core::{CallContext, native_error::NativeError};
+use angelscript_macros::Any;
+use angelscript_registry::Module;
+
+use crate::ScriptString;
+
+/// Opaque id of a host resource. The id `0` is no resource.
+#[derive(Any, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[angelscript(name = "ResId", value)]
+pub struct ResId(u64);
+
+impl ResId {
+    /// No resource.
+    pub const NONE: Self = Self(0);
+
+    /// Create a resource id.
+    #[inline]
+    pub const fn new(id: u64) -> Self {
+        Self(id)
+    }
+
+    /// Get the raw id.
+    #[inline]
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the raw id.
+    #[angelscript_macros::function(instance, const, name = "id")]
+    pub fn script_id(&self) -> u64 {
+        self.0
+    }
+
+    /// Returns true if the id refers to a resource.
+    #[angelscript_macros::function(instance, const, name = "isValid")]
+    pub fn is_valid(&self) -> bool {
+        self.0 != 0
+    }
+
+    /// Equality comparison.
+    #[angelscript_macros::function(operator = Operator::Equals, const)]
+    pub fn eq_op(&self, #[param(const, in)] other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+/// Maps resource paths to ids, implemented by the host.
+///
+/// Closures `Fn(&str) -> Result<ResId, String>` are resolvers.
+pub trait ResourceResolver: Send + Sync {
+    /// Resolve a path, or explain why it names no resource.
+    fn resolve(&self, path: &str) -> Result<ResId, String>;
+}
+
+impl<F> ResourceResolver for F
+where
+    F: Fn(&str) -> Result<ResId, String> + Send + Sync,
+{
+    fn resolve(&self, path: &str) -> Result<ResId, String> {
+        self(path)
+    }
+}
+
+/// Load a resource by path.
+///
+/// Usage: `ResId icon = load("ui/icon.png");`
+///
+/// Note: Literal paths are resolved at compile time; the VM resolves the
+/// others through the host's resolver.
+#[angelscript_macros::function(generic, name = "load")]
+#[param(type = ScriptString, const, in)]
+#[returns(type = ResId)]
+pub fn as_load(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Creates the resource module with the `ResId` type and `load`.
+pub fn module() -> Module {
+    Module::new()
+        .ty::<ResId>()
+        .function(ResId::script_id__meta)
+        .function(ResId::is_valid__meta)
+        .function(ResId::eq_op__meta)
+        .function(as_load)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_creates() {
+        let m = module();
+        assert!(m.namespace.is_empty());
+        assert_eq!(m.classes.len(), 1);
+        assert_eq!(m.functions.len(), 4);
+    }
+
+    #[test]
+    fn closures_resolve() {
+        let resolver = |path: &str| match path {
+            "ui/icon.png" => Ok(ResId::new(7)),
+            _ => Err("no such file".to_string()),
+        };
+        assert_eq!(resolver.resolve("ui/icon.png"), Ok(ResId::new(7)));
+        assert!(resolver.resolve("ui/missing.png").is_err());
+        assert!(!ResId::NONE.is_valid());
+    }
+}
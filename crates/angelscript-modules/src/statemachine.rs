@@ -0,0 +1,633 @@
+//! State machines for AI and gameplay scripts.
+//!
+//! A `StateMachine` has named states, each with optional callbacks run on
+//! entering, every update, and on leaving it. Changes between states only
+//! follow the declared transitions, which can be guarded by a function that
+//! must return true:
+//!
+//! ```angelscript
+//! void patrolUpdate(float dt) { ... }
+//! void chaseEnter() { playSound("alert"); }
+//! bool targetVisible() { return target !is null; }
+//!
+//! StateMachine ai;
+//!
+//! void init() {
+//!     ai.addState("Patrol", null, patrolUpdate, null);
+//!     ai.addState("Chase", chaseEnter, chaseUpdate, null);
+//!     ai.addTransition("Patrol", "Chase", targetVisible);
+//!     ai.addTransition("Chase", "Patrol");
+//!     ai.start("Patrol");
+//! }
+//!
+//! void tick(float dt) {
+//!     ai.changeTo("Chase");   // only if targetVisible()
+//!     ai.update(dt);
+//!     if (ai.current == "Chase" && ai.timeInState > 10.0f)
+//!         ai.changeTo("Patrol");
+//! }
+//! ```
+//!
+//! [`ScriptStateMachine`] registers the script type; the VM keeps a
+//! [`StateMachine`] for every instance, which runs the callbacks through the
+//! function passed to it:
+//!
+//! ```ignore
+//! machine.change_to("Chase", |callback, phase| match phase {
+//!     Phase::Update { dt } => unit.call_callable::<_, ()>(callback, (dt,)).map(|_| true),
+//!     Phase::Guard => unit.call_callable::<_, bool>(callback, ()),
+//!     _ => unit.call_callable::<_, ()>(callback, ()).map(|_| true),
+//! })?;
+//! ```
+//!
+//! The module is not part of the default modules; hosts install it with
+//! [`module`].
+
+use std::fmt;
+
+use angelscript_core::{CallContext, native_error::NativeError};
+use angelscript_macros::{Any, funcdef};
+use angelscript_registry::Module;
+
+use crate::ScriptString;
+
+/// Callback run when a state is entered or left.
+///
+/// AngelScript: `funcdef void StateCallback();`
+#[funcdef]
+pub type StateCallback = fn();
+
+/// Callback run on every update while a state is current.
+///
+/// AngelScript: `funcdef void StateUpdateCallback(float dt);`
+#[funcdef]
+pub type StateUpdateCallback = fn(f32);
+
+/// Guard of a transition, which is only taken if it returns true.
+///
+/// AngelScript: `funcdef bool TransitionGuard();`
+#[funcdef]
+pub type TransitionGuard = fn() -> bool;
+
+/// Placeholder for the AngelScript `StateMachine` type.
+///
+/// This is an empty struct used for FFI registration. The VM stores a
+/// [`StateMachine`] for each instance.
+#[derive(Any)]
+#[angelscript(name = "StateMachine", reference)]
+pub struct ScriptStateMachine;
+
+impl ScriptStateMachine {
+    /// Increment reference count.
+    #[angelscript_macros::function(addref)]
+    pub fn add_ref(&self) {
+        todo!()
+    }
+
+    /// Decrement reference count.
+    #[angelscript_macros::function(release)]
+    pub fn release(&self) -> bool {
+        todo!()
+    }
+
+    /// Add a state. Any callback can be null.
+    ///
+    /// Raises an exception if a state of that name already exists.
+    #[angelscript_macros::function(instance, name = "addState")]
+    pub fn add_state(
+        &mut self,
+        #[param(const, in)] name: &ScriptString,
+        #[param(in)] enter: &StateCallback,
+        #[param(in)] update: &StateUpdateCallback,
+        #[param(in)] exit: &StateCallback,
+    ) {
+        let _ = (name, enter, update, exit);
+        todo!()
+    }
+
+    /// Allow changing from one state to another.
+    ///
+    /// Raises an exception if either state does not exist.
+    #[angelscript_macros::function(instance, name = "addTransition")]
+    pub fn add_transition(
+        &mut self,
+        #[param(const, in)] from: &ScriptString,
+        #[param(const, in)] to: &ScriptString,
+    ) {
+        let _ = (from, to);
+        todo!()
+    }
+
+    /// Allow changing from one state to another while `guard` returns true.
+    ///
+    /// Raises an exception if either state does not exist.
+    #[angelscript_macros::function(instance, name = "addTransition")]
+    pub fn add_guarded_transition(
+        &mut self,
+        #[param(const, in)] from: &ScriptString,
+        #[param(const, in)] to: &ScriptString,
+        #[param(in)] guard: &TransitionGuard,
+    ) {
+        let _ = (from, to, guard);
+        todo!()
+    }
+
+    /// Enter the initial state, running its enter callback.
+    ///
+    /// Raises an exception if the state does not exist or the machine was
+    /// already started.
+    #[angelscript_macros::function(instance)]
+    pub fn start(&mut self, #[param(const, in)] state: &ScriptString) {
+        let _ = state;
+        todo!()
+    }
+
+    /// Change to another state if a transition allows it. Returns false if
+    /// the guard refused it.
+    ///
+    /// Raises an exception if there is no transition to the state.
+    #[angelscript_macros::function(instance, name = "changeTo")]
+    pub fn change_to(&mut self, #[param(const, in)] state: &ScriptString) -> bool {
+        let _ = state;
+        todo!()
+    }
+
+    /// Run the update callback of the current state.
+    #[angelscript_macros::function(instance)]
+    pub fn update(&mut self, dt: f32) {
+        let _ = dt;
+        todo!()
+    }
+
+    /// Returns the current state, or an empty string before `start`.
+    #[angelscript_macros::function(instance, const, name = "get_current", property)]
+    pub fn current(&self) -> ScriptString {
+        todo!()
+    }
+
+    /// Returns the state before the current one, or an empty string.
+    #[angelscript_macros::function(instance, const, name = "get_previous", property)]
+    pub fn previous(&self) -> ScriptString {
+        todo!()
+    }
+
+    /// Returns the seconds of updates since the current state was entered.
+    #[angelscript_macros::function(instance, const, name = "get_timeInState", property)]
+    pub fn time_in_state(&self) -> f32 {
+        todo!()
+    }
+
+    /// Returns true if the machine has a state of that name.
+    #[angelscript_macros::function(instance, const, name = "hasState")]
+    pub fn has_state(&self, #[param(const, in)] name: &ScriptString) -> bool {
+        let _ = name;
+        todo!()
+    }
+
+    /// Returns true if a transition leads from the current state to
+    /// `state`. The guard is not run.
+    #[angelscript_macros::function(instance, const, name = "canChangeTo")]
+    pub fn can_change_to(&self, #[param(const, in)] state: &ScriptString) -> bool {
+        let _ = state;
+        todo!()
+    }
+
+    /// Default factory for creating empty state machines.
+    ///
+    /// Called when: `StateMachine sm;` or `StateMachine()`
+    #[angelscript_macros::function(factory, generic)]
+    pub fn default_factory(_ctx: &mut CallContext) -> Result<(), NativeError> {
+        todo!()
+    }
+}
+
+/// Which callback a [`StateMachine`] is running.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// The guard of a transition; its result decides the transition.
+    Guard,
+    /// The enter callback of the new state.
+    Enter,
+    /// The update callback of the current state, called with `dt`.
+    Update {
+        /// Seconds since the last update.
+        dt: f32,
+    },
+    /// The exit callback of the state being left.
+    Exit,
+}
+
+/// Error of a [`StateMachine`] operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateMachineError<E> {
+    /// A state of that name already exists.
+    DuplicateState(String),
+    /// No state has that name.
+    UnknownState(String),
+    /// No transition leads from the current state to the named one.
+    NoTransition {
+        /// Current state, empty before the machine started.
+        from: String,
+        /// Requested state.
+        to: String,
+    },
+    /// The machine was already started.
+    AlreadyStarted,
+    /// A callback failed.
+    Callback(E),
+}
+
+impl<E: fmt::Display> fmt::Display for StateMachineError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateState(name) => write!(f, "state '{}' already exists", name),
+            Self::UnknownState(name) => write!(f, "no state named '{}'", name),
+            Self::NoTransition { from, to } => {
+                write!(f, "no transition from '{}' to '{}'", from, to)
+            }
+            Self::AlreadyStarted => write!(f, "state machine already started"),
+            Self::Callback(error) => write!(f, "state callback failed: {}", error),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for StateMachineError<E> {}
+
+/// Callbacks of a state, each optional.
+#[derive(Debug, Clone)]
+pub struct StateCallbacks<C> {
+    /// Run when the state is entered.
+    pub enter: Option<C>,
+    /// Run on every update while the state is current.
+    pub update: Option<C>,
+    /// Run when the state is left.
+    pub exit: Option<C>,
+}
+
+impl<C> Default for StateCallbacks<C> {
+    fn default() -> Self {
+        Self {
+            enter: None,
+            update: None,
+            exit: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State<C> {
+    name: String,
+    callbacks: StateCallbacks<C>,
+}
+
+#[derive(Debug)]
+struct Transition<C> {
+    from: usize,
+    to: usize,
+    guard: Option<C>,
+}
+
+/// A state machine holding callbacks of type `C`.
+///
+/// The callbacks are run by the function passed to
+/// [`start`](Self::start), [`change_to`](Self::change_to) and
+/// [`update`](Self::update), so hosts decide how to call them.
+#[derive(Debug)]
+pub struct StateMachine<C> {
+    states: Vec<State<C>>,
+    transitions: Vec<Transition<C>>,
+    current: Option<usize>,
+    previous: Option<usize>,
+    time_in_state: f32,
+}
+
+impl<C> Default for StateMachine<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> StateMachine<C> {
+    /// Create a state machine without states.
+    pub fn new() -> Self {
+        Self {
+            states: Vec::new(),
+            transitions: Vec::new(),
+            current: None,
+            previous: None,
+            time_in_state: 0.0,
+        }
+    }
+
+    /// Add a state.
+    pub fn add_state<E>(
+        &mut self,
+        name: &str,
+        callbacks: StateCallbacks<C>,
+    ) -> Result<(), StateMachineError<E>> {
+        if self.has_state(name) {
+            return Err(StateMachineError::DuplicateState(name.to_string()));
+        }
+        self.states.push(State {
+            name: name.to_string(),
+            callbacks,
+        });
+        Ok(())
+    }
+
+    /// Allow changing from `from` to `to`, if `guard` is `None` or returns
+    /// true.
+    ///
+    /// A later transition between the same states replaces the earlier one.
+    pub fn add_transition<E>(
+        &mut self,
+        from: &str,
+        to: &str,
+        guard: Option<C>,
+    ) -> Result<(), StateMachineError<E>> {
+        let from = self.index(from)?;
+        let to = self.index(to)?;
+        self.transitions.retain(|t| !(t.from == from && t.to == to));
+        self.transitions.push(Transition { from, to, guard });
+        Ok(())
+    }
+
+    /// Names of the states, in the order they were added.
+    pub fn states(&self) -> impl Iterator<Item = &str> {
+        self.states.iter().map(|s| s.name.as_str())
+    }
+
+    /// Returns true if a state has that name.
+    pub fn has_state(&self, name: &str) -> bool {
+        self.states.iter().any(|s| s.name == name)
+    }
+
+    /// Name of the current state, or `None` before the machine started.
+    pub fn current(&self) -> Option<&str> {
+        self.current.map(|i| self.states[i].name.as_str())
+    }
+
+    /// Name of the state before the current one.
+    pub fn previous(&self) -> Option<&str> {
+        self.previous.map(|i| self.states[i].name.as_str())
+    }
+
+    /// Seconds passed to [`update`](Self::update) since the current state
+    /// was entered.
+    pub fn time_in_state(&self) -> f32 {
+        self.time_in_state
+    }
+
+    /// Returns true if a transition leads from the current state to `to`.
+    /// The guard is not run.
+    pub fn can_change_to(&self, to: &str) -> bool {
+        self.transition(to).is_some()
+    }
+
+    /// Enter the initial state, running its enter callback.
+    pub fn start<E>(
+        &mut self,
+        state: &str,
+        mut run: impl FnMut(&C, Phase) -> Result<bool, E>,
+    ) -> Result<(), StateMachineError<E>> {
+        if self.current.is_some() {
+            return Err(StateMachineError::AlreadyStarted);
+        }
+        let index = self.index(state)?;
+        self.enter(index, &mut run)
+    }
+
+    /// Change to `to` along a transition from the current state.
+    ///
+    /// Runs the guard first and returns `Ok(false)` if it refused. Otherwise
+    /// runs the exit callback of the current state, then the enter callback
+    /// of the new one. Changing to the current state exits and re-enters it.
+    pub fn change_to<E>(
+        &mut self,
+        to: &str,
+        mut run: impl FnMut(&C, Phase) -> Result<bool, E>,
+    ) -> Result<bool, StateMachineError<E>> {
+        let Some(transition) = self.transition(to) else {
+            return Err(match self.index::<E>(to) {
+                Ok(_) => StateMachineError::NoTransition {
+                    from: self.current().unwrap_or_default().to_string(),
+                    to: to.to_string(),
+                },
+                Err(error) => error,
+            });
+        };
+        let to = transition.to;
+        if let Some(guard) = &transition.guard
+            && !run(guard, Phase::Guard).map_err(StateMachineError::Callback)?
+        {
+            return Ok(false);
+        }
+        if let Some(current) = self.current
+            && let Some(exit) = &self.states[current].callbacks.exit
+        {
+            run(exit, Phase::Exit).map_err(StateMachineError::Callback)?;
+        }
+        self.enter(to, &mut run)?;
+        Ok(true)
+    }
+
+    /// Advance the time in the current state by `dt` seconds and run its
+    /// update callback. Does nothing before the machine started.
+    pub fn update<E>(
+        &mut self,
+        dt: f32,
+        mut run: impl FnMut(&C, Phase) -> Result<bool, E>,
+    ) -> Result<(), StateMachineError<E>> {
+        let Some(current) = self.current else {
+            return Ok(());
+        };
+        if dt.is_finite() && dt > 0.0 {
+            self.time_in_state += dt;
+        }
+        if let Some(update) = &self.states[current].callbacks.update {
+            run(update, Phase::Update { dt }).map_err(StateMachineError::Callback)?;
+        }
+        Ok(())
+    }
+
+    fn index<E>(&self, name: &str) -> Result<usize, StateMachineError<E>> {
+        self.states
+            .iter()
+            .position(|s| s.name == name)
+            .ok_or_else(|| StateMachineError::UnknownState(name.to_string()))
+    }
+
+    fn transition(&self, to: &str) -> Option<&Transition<C>> {
+        let from = self.current?;
+        self.transitions
+            .iter()
+            .find(|t| t.from == from && self.states[t.to].name == to)
+    }
+
+    fn enter<E>(
+        &mut self,
+        index: usize,
+        run: &mut impl FnMut(&C, Phase) -> Result<bool, E>,
+    ) -> Result<(), StateMachineError<E>> {
+        self.previous = self.current;
+        self.current = Some(index);
+        self.time_in_state = 0.0;
+        if let Some(enter) = &self.states[index].callbacks.enter {
+            run(enter, Phase::Enter).map_err(StateMachineError::Callback)?;
+        }
+        Ok(())
+    }
+}
+
+/// Creates the state machine module with the `StateMachine` type and its
+/// callback funcdefs.
+pub fn module() -> Module {
+    Module::new()
+        .ty::<ScriptStateMachine>()
+        .funcdef(__as_StateCallback_funcdef_meta())
+        .funcdef(__as_StateUpdateCallback_funcdef_meta())
+        .funcdef(__as_TransitionGuard_funcdef_meta())
+        .function(ScriptStateMachine::add_ref__meta)
+        .function(ScriptStateMachine::release__meta)
+        .function(ScriptStateMachine::add_state__meta)
+        .function(ScriptStateMachine::add_transition__meta)
+        .function(ScriptStateMachine::add_guarded_transition__meta)
+        .function(ScriptStateMachine::start__meta)
+        .function(ScriptStateMachine::change_to__meta)
+        .function(ScriptStateMachine::update__meta)
+        .function(ScriptStateMachine::current__meta)
+        .function(ScriptStateMachine::previous__meta)
+        .function(ScriptStateMachine::time_in_state__meta)
+        .function(ScriptStateMachine::has_state__meta)
+        .function(ScriptStateMachine::can_change_to__meta)
+        .function(ScriptStateMachine::default_factory__meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Log = Vec<(&'static str, Phase)>;
+
+    fn callbacks(
+        enter: &'static str,
+        update: &'static str,
+        exit: &'static str,
+    ) -> StateCallbacks<&'static str> {
+        StateCallbacks {
+            enter: Some(enter),
+            update: Some(update),
+            exit: Some(exit),
+        }
+    }
+
+    fn machine() -> StateMachine<&'static str> {
+        let mut sm = StateMachine::new();
+        sm.add_state::<()>("Patrol", callbacks("patrol+", "patrol", "patrol-"))
+            .unwrap();
+        sm.add_state::<()>("Chase", callbacks("chase+", "chase", "chase-"))
+            .unwrap();
+        sm.add_state::<()>("Idle", StateCallbacks::default())
+            .unwrap();
+        sm.add_transition::<()>("Patrol", "Chase", Some("visible"))
+            .unwrap();
+        sm.add_transition::<()>("Chase", "Patrol", None).unwrap();
+        sm
+    }
+
+    #[test]
+    fn test_module_creates() {
+        let m = module();
+        assert!(m.namespace.is_empty());
+        assert_eq!(m.classes.len(), 1);
+        assert_eq!(m.funcdefs.len(), 3);
+        assert_eq!(m.functions.len(), 14);
+    }
+
+    #[test]
+    fn transitions_run_guard_exit_and_enter() {
+        let mut sm = machine();
+        let mut log: Log = Vec::new();
+        let mut visible = false;
+        let mut run = |cb: &&'static str, phase| -> Result<bool, ()> {
+            log.push((*cb, phase));
+            Ok(*cb != "visible" || visible)
+        };
+
+        assert_eq!(sm.current(), None);
+        sm.start("Patrol", &mut run).unwrap();
+        sm.update(0.5, &mut run).unwrap();
+        sm.update(0.25, &mut run).unwrap();
+        assert_eq!(sm.time_in_state(), 0.75);
+        assert!(sm.can_change_to("Chase"));
+        assert!(!sm.change_to("Chase", &mut run).unwrap());
+        assert_eq!(sm.current(), Some("Patrol"));
+
+        visible = true;
+        let mut run = |cb: &&'static str, phase| -> Result<bool, ()> {
+            log.push((*cb, phase));
+            Ok(*cb != "visible" || visible)
+        };
+        assert!(sm.change_to("Chase", &mut run).unwrap());
+        assert_eq!(sm.current(), Some("Chase"));
+        assert_eq!(sm.previous(), Some("Patrol"));
+        assert_eq!(sm.time_in_state(), 0.0);
+
+        assert_eq!(
+            log,
+            [
+                ("patrol+", Phase::Enter),
+                ("patrol", Phase::Update { dt: 0.5 }),
+                ("patrol", Phase::Update { dt: 0.25 }),
+                ("visible", Phase::Guard),
+                ("visible", Phase::Guard),
+                ("patrol-", Phase::Exit),
+                ("chase+", Phase::Enter),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_operations_are_errors() {
+        let mut sm = machine();
+        let ok = |_: &&'static str, _| Ok::<_, &str>(true);
+
+        assert_eq!(
+            sm.add_state::<&str>("Idle", StateCallbacks::default()),
+            Err(StateMachineError::DuplicateState("Idle".into()))
+        );
+        assert_eq!(
+            sm.add_transition::<&str>("Idle", "Sleep", None),
+            Err(StateMachineError::UnknownState("Sleep".into()))
+        );
+        assert_eq!(
+            sm.change_to("Chase", ok),
+            Err(StateMachineError::NoTransition {
+                from: String::new(),
+                to: "Chase".into()
+            })
+        );
+        sm.start("Chase", ok).unwrap();
+        assert_eq!(
+            sm.start("Patrol", ok),
+            Err(StateMachineError::AlreadyStarted)
+        );
+        assert_eq!(
+            sm.change_to("Idle", ok),
+            Err(StateMachineError::NoTransition {
+                from: "Chase".into(),
+                to: "Idle".into()
+            })
+        );
+        assert_eq!(
+            sm.change_to("Ghost", ok),
+            Err(StateMachineError::UnknownState("Ghost".into()))
+        );
+        assert_eq!(
+            sm.change_to("Patrol", |_, _| Err("boom")),
+            Err(StateMachineError::Callback("boom"))
+        );
+        assert_eq!(sm.current(), Some("Chase"));
+        assert_eq!(sm.states().collect::<Vec<_>>(), ["Patrol", "Chase", "Idle"]);
+    }
+}
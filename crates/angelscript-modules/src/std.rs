@@ -13,21 +13,16 @@ use angelscript_registry::Module;
 /// Throw an exception with the given message.
 ///
 /// This raises an exception that will be caught by the nearest try-catch block,
-/// or will terminate script execution if uncaught.
+/// or will terminate script execution if uncaught. Native functions between
+/// the throw and the handler see it as [`NativeError::Exception`].
 ///
 /// Usage: `throw("Something went wrong")`
-///
-/// Note: The actual exception raising is handled by the VM through CallContext.
-/// This function signature is for registration purposes.
 #[angelscript_macros::function(generic, name = "throw")]
 #[param(type = ScriptString, in)]
-pub fn as_throw(_ctx: &mut CallContext) -> Result<(), NativeError> {
-    // VM implementation will:
-    // 1. Get the message from the stack via ctx
-    // 2. Store the exception message in VM state
-    // 3. Set exception state flag
-    // 4. Return control to VM which will unwind to nearest TryBegin handler
-    todo!()
+pub fn as_throw(ctx: &mut CallContext) -> Result<(), NativeError> {
+    let message: String = ctx.arg(0)?;
+    ctx.set_exception(message);
+    Ok(())
 }
 
 /// Get information about the current exception.
@@ -50,6 +45,9 @@ pub fn as_get_exception_info(_ctx: &mut CallContext) -> Result<(), NativeError>
 // =============================================================================
 // OUTPUT FUNCTIONS
 // =============================================================================
+//
+// `{}` placeholders format numbers in the invariant format of the string
+// module: `.` decimal separator and no digit grouping, whatever the locale.
 
 /// Print formatted string to stdout without newline.
 /// Usage: `print("Hello {}", name)`
@@ -112,4 +110,20 @@ mod tests {
         assert!(m.namespace.is_empty());
         assert_eq!(m.functions.len(), 6); // throw, getExceptionInfo, print, println, eprint, eprintln
     }
+
+    #[test]
+    fn throw_raises_an_exception() {
+        use angelscript_core::{Dynamic, ObjectHeap};
+
+        let mut slots = vec![Dynamic::String("Something went wrong".into())];
+        let mut ret = Dynamic::Void;
+        let mut heap = ObjectHeap::new();
+        let mut ctx = CallContext::new(&mut slots, 0, &mut ret, &mut heap);
+
+        let result = __as_fn__as_throw(&mut ctx);
+        assert!(matches!(
+            ctx.finish(result),
+            Err(NativeError::Exception { message }) if message == "Something went wrong"
+        ));
+    }
 }
@@ -0,0 +1,760 @@
+//! Byte streams over blobs, files and host connections.
+//!
+//! A `stream` reads and writes numbers and text in order, so serialization
+//! code is written once whatever the bytes end up in:
+//!
+//! ```angelscript
+//! void save(stream@ out, const Player &in player) {
+//!     out.writeUInt32(0x53415645);
+//!     out.writeFloat(player.x);
+//!     out.writeUInt32(player.name.length());
+//!     out.writeString(player.name);
+//! }
+//!
+//! stream@ memory = openBlob(blob());
+//! save(memory, player);
+//! blob saved = memory.toBlob();
+//!
+//! stream@ file = openFile("save.dat", "w");
+//! if (file !is null) save(file, player);
+//! ```
+//!
+//! Numbers use the same little-endian layout as `blob`. A read past the end
+//! returns 0, or the bytes that exist for `readString`, and sets `eof`. A
+//! failed write returns false, as does `seek` on a stream that cannot seek,
+//! like a network connection.
+//!
+//! The bytes come from a [`StreamBackend`]. [`BlobStream`] and [`FileStream`]
+//! back `openBlob` and `openFile`; hosts wrap their own connections with
+//! [`IoStream`] or implement the trait, and hand them to scripts from their
+//! own functions returning `stream@`. The VM keeps a [`Stream`] for every
+//! instance.
+//!
+//! The module opens files, so it is not part of the default modules; hosts
+//! install it with [`module`], after `blob`.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use angelscript_core::{CallContext, native_error::NativeError};
+use angelscript_macros::Any;
+use angelscript_registry::Module;
+
+use crate::{ScriptBlob, ScriptString};
+
+/// Source and destination of the bytes of a [`Stream`].
+pub trait StreamBackend: Send {
+    /// Read up to `buf.len()` bytes, returning how many were read; 0 at the
+    /// end.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+
+    /// Write all of `data`.
+    fn write(&mut self, data: &[u8]) -> io::Result<()>;
+
+    /// Offset of the next byte read or written.
+    fn position(&self) -> u64;
+
+    /// Move to `position`. Streams that cannot seek keep the default, which
+    /// fails.
+    fn seek(&mut self, position: u64) -> io::Result<()> {
+        let _ = position;
+        Err(ErrorKind::Unsupported.into())
+    }
+
+    /// Total size in bytes, if known.
+    fn size(&self) -> Option<u64> {
+        None
+    }
+
+    /// Send buffered writes on.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The blob holding the bytes, for streams backed by one.
+    fn as_blob(&self) -> Option<&ScriptBlob> {
+        None
+    }
+}
+
+/// Stream over a blob, which grows as it is written unless it is fixed.
+#[derive(Debug, Default)]
+pub struct BlobStream {
+    blob: ScriptBlob,
+    position: u64,
+}
+
+impl BlobStream {
+    /// Create a stream at the start of `blob`.
+    pub fn new(blob: ScriptBlob) -> Self {
+        Self { blob, position: 0 }
+    }
+
+    /// Take the blob.
+    pub fn into_blob(self) -> ScriptBlob {
+        self.blob
+    }
+}
+
+impl StreamBackend for BlobStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes = self.blob.as_slice();
+        let start = usize::try_from(self.position)
+            .unwrap_or(usize::MAX)
+            .min(bytes.len());
+        let count = buf.len().min(bytes.len() - start);
+        buf[..count].copy_from_slice(&bytes[start..start + count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        let offset = u32::try_from(self.position).map_err(|_| ErrorKind::FileTooLarge)?;
+        if !self.blob.write(offset, data) {
+            return Err(ErrorKind::WriteZero.into());
+        }
+        self.position += data.len() as u64;
+        Ok(())
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn seek(&mut self, position: u64) -> io::Result<()> {
+        self.position = position;
+        Ok(())
+    }
+
+    fn size(&self) -> Option<u64> {
+        Some(self.blob.as_slice().len() as u64)
+    }
+
+    fn as_blob(&self) -> Option<&ScriptBlob> {
+        Some(&self.blob)
+    }
+}
+
+/// Stream over a file.
+#[derive(Debug)]
+pub struct FileStream {
+    file: File,
+    position: u64,
+}
+
+impl FileStream {
+    /// Open `path` as `openFile` does: `"r"` reads, `"w"` creates or
+    /// truncates, `"a"` creates or appends and `"r+"` reads and writes an
+    /// existing file.
+    pub fn open(path: impl AsRef<Path>, mode: &str) -> io::Result<Self> {
+        let mut options = OpenOptions::new();
+        match mode {
+            "r" => options.read(true),
+            "w" => options.write(true).create(true).truncate(true),
+            "a" => options.append(true).create(true),
+            "r+" => options.read(true).write(true),
+            _ => return Err(ErrorKind::InvalidInput.into()),
+        };
+        let mut file = options.open(path)?;
+        let position = match mode {
+            "a" => file.seek(SeekFrom::End(0))?,
+            _ => 0,
+        };
+        Ok(Self { file, position })
+    }
+}
+
+impl StreamBackend for FileStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.file.read(buf)?;
+        self.position += count as u64;
+        Ok(count)
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.file.write_all(data)?;
+        self.position = self.file.stream_position()?;
+        Ok(())
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn seek(&mut self, position: u64) -> io::Result<()> {
+        self.position = self.file.seek(SeekFrom::Start(position))?;
+        Ok(())
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.file.metadata().ok().map(|metadata| metadata.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Stream over a host connection, or anything else that reads and writes
+/// but cannot seek.
+///
+/// ```ignore
+/// let stream = Stream::new(IoStream::new(TcpStream::connect(addr)?));
+/// ```
+#[derive(Debug)]
+pub struct IoStream<T> {
+    inner: T,
+    position: u64,
+}
+
+impl<T: Read + Write + Send> IoStream<T> {
+    /// Wrap a reader and writer.
+    pub fn new(inner: T) -> Self {
+        Self { inner, position: 0 }
+    }
+
+    /// Take the wrapped value.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read + Write + Send> StreamBackend for IoStream<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        self.position += count as u64;
+        Ok(count)
+    }
+
+    fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        self.inner.write_all(data)?;
+        self.position += data.len() as u64;
+        Ok(())
+    }
+
+    fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// A number read and written as little-endian bytes.
+pub trait StreamNumber: Copy + Default {
+    /// Size in bytes.
+    const SIZE: usize;
+
+    /// Decode from exactly [`SIZE`](Self::SIZE) bytes.
+    fn from_le_slice(bytes: &[u8]) -> Self;
+
+    /// Call `f` with the encoded bytes.
+    fn with_le_bytes<R>(self, f: impl FnOnce(&[u8]) -> R) -> R;
+}
+
+macro_rules! stream_number {
+    ($($ty:ty),*) => {$(
+        impl StreamNumber for $ty {
+            const SIZE: usize = std::mem::size_of::<$ty>();
+
+            fn from_le_slice(bytes: &[u8]) -> Self {
+                <$ty>::from_le_bytes(bytes.try_into().unwrap())
+            }
+
+            fn with_le_bytes<R>(self, f: impl FnOnce(&[u8]) -> R) -> R {
+                f(&self.to_le_bytes())
+            }
+        }
+    )*};
+}
+
+stream_number!(i8, i16, i32, i64, u8, u16, u32, u64, f32, f64);
+
+/// The stream behind a script `stream`.
+pub struct Stream {
+    backend: Box<dyn StreamBackend>,
+    /// A read came up short.
+    eof: bool,
+}
+
+impl Stream {
+    /// Create a stream over `backend`.
+    pub fn new(backend: impl StreamBackend + 'static) -> Self {
+        Self {
+            backend: Box::new(backend),
+            eof: false,
+        }
+    }
+
+    /// Create a stream at the start of `blob`.
+    pub fn from_blob(blob: ScriptBlob) -> Self {
+        Self::new(BlobStream::new(blob))
+    }
+
+    /// Open a file; see [`FileStream::open`] for the modes.
+    pub fn open_file(path: impl AsRef<Path>, mode: &str) -> io::Result<Self> {
+        FileStream::open(path, mode).map(Self::new)
+    }
+
+    /// Read up to `length` bytes, fewer at the end.
+    pub fn read_bytes(&mut self, length: usize) -> Vec<u8> {
+        let mut bytes = vec![0; length];
+        let mut filled = 0;
+        while filled < length {
+            match self.backend.read(&mut bytes[filled..]) {
+                Ok(0) => break,
+                Ok(count) => filled += count,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+        if filled < length {
+            self.eof = true;
+            bytes.truncate(filled);
+        }
+        bytes
+    }
+
+    /// Write all of `data`, returning false if the backend failed.
+    pub fn write_bytes(&mut self, data: &[u8]) -> bool {
+        self.backend.write(data).is_ok()
+    }
+
+    /// Read a number, or 0 if the stream ends first.
+    pub fn read<T: StreamNumber>(&mut self) -> T {
+        let bytes = self.read_bytes(T::SIZE);
+        if bytes.len() == T::SIZE {
+            T::from_le_slice(&bytes)
+        } else {
+            T::default()
+        }
+    }
+
+    /// Write a number.
+    pub fn write<T: StreamNumber>(&mut self, value: T) -> bool {
+        value.with_le_bytes(|bytes| self.write_bytes(bytes))
+    }
+
+    /// Read up to `length` bytes as text. Bytes that do not form UTF-8 are
+    /// replaced with U+FFFD.
+    pub fn read_string(&mut self, length: usize) -> ScriptString {
+        let bytes = self.read_bytes(length);
+        ScriptString::from(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// Write the bytes of `text`, without a length or terminator.
+    pub fn write_string(&mut self, text: &str) -> bool {
+        self.write_bytes(text.as_bytes())
+    }
+
+    /// Move to `position`, returning false if the stream cannot seek.
+    pub fn seek(&mut self, position: u64) -> bool {
+        let moved = self.backend.seek(position).is_ok();
+        if moved {
+            self.eof = false;
+        }
+        moved
+    }
+
+    /// Offset of the next byte read or written.
+    pub fn position(&self) -> u64 {
+        self.backend.position()
+    }
+
+    /// Total size in bytes, if known.
+    pub fn size(&self) -> Option<u64> {
+        self.backend.size()
+    }
+
+    /// Returns true once a read came up short, or when at the end of a
+    /// stream of known size.
+    pub fn is_eof(&self) -> bool {
+        self.eof || self.size().is_some_and(|size| self.position() >= size)
+    }
+
+    /// Send buffered writes on, returning false if the backend failed.
+    pub fn flush(&mut self) -> bool {
+        self.backend.flush().is_ok()
+    }
+
+    /// A copy of the bytes of a blob-backed stream; empty for others.
+    pub fn to_blob(&self) -> ScriptBlob {
+        self.backend.as_blob().cloned().unwrap_or_default()
+    }
+
+    /// Get the backend.
+    pub fn backend(&self) -> &dyn StreamBackend {
+        self.backend.as_ref()
+    }
+}
+
+impl fmt::Debug for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stream")
+            .field("position", &self.position())
+            .field("size", &self.size())
+            .field("eof", &self.eof)
+            .finish()
+    }
+}
+
+/// Placeholder for the AngelScript `stream` type.
+///
+/// This is an empty struct used for FFI registration. The VM stores a
+/// [`Stream`] for each instance.
+#[derive(Any)]
+#[angelscript(name = "stream", reference)]
+pub struct ScriptStream;
+
+impl ScriptStream {
+    /// Increment reference count.
+    #[angelscript_macros::function(addref)]
+    pub fn add_ref(&self) {
+        todo!()
+    }
+
+    /// Decrement reference count.
+    #[angelscript_macros::function(release)]
+    pub fn release(&self) -> bool {
+        todo!()
+    }
+
+    /// Read an int8.
+    #[angelscript_macros::function(instance, name = "readInt8")]
+    pub fn read_i8(&mut self) -> i8 {
+        todo!()
+    }
+
+    /// Read an int16.
+    #[angelscript_macros::function(instance, name = "readInt16")]
+    pub fn read_i16(&mut self) -> i16 {
+        todo!()
+    }
+
+    /// Read an int32.
+    #[angelscript_macros::function(instance, name = "readInt32")]
+    pub fn read_i32(&mut self) -> i32 {
+        todo!()
+    }
+
+    /// Read an int64.
+    #[angelscript_macros::function(instance, name = "readInt64")]
+    pub fn read_i64(&mut self) -> i64 {
+        todo!()
+    }
+
+    /// Read a uint8.
+    #[angelscript_macros::function(instance, name = "readUInt8")]
+    pub fn read_u8(&mut self) -> u8 {
+        todo!()
+    }
+
+    /// Read a uint16.
+    #[angelscript_macros::function(instance, name = "readUInt16")]
+    pub fn read_u16(&mut self) -> u16 {
+        todo!()
+    }
+
+    /// Read a uint32.
+    #[angelscript_macros::function(instance, name = "readUInt32")]
+    pub fn read_u32(&mut self) -> u32 {
+        todo!()
+    }
+
+    /// Read a uint64.
+    #[angelscript_macros::function(instance, name = "readUInt64")]
+    pub fn read_u64(&mut self) -> u64 {
+        todo!()
+    }
+
+    /// Read a float.
+    #[angelscript_macros::function(instance, name = "readFloat")]
+    pub fn read_f32(&mut self) -> f32 {
+        todo!()
+    }
+
+    /// Read a double.
+    #[angelscript_macros::function(instance, name = "readDouble")]
+    pub fn read_f64(&mut self) -> f64 {
+        todo!()
+    }
+
+    /// Read up to `length` bytes as text.
+    #[angelscript_macros::function(instance, name = "readString")]
+    pub fn read_string(&mut self, length: u32) -> ScriptString {
+        let _ = length;
+        todo!()
+    }
+
+    /// Read up to `length` bytes into a growable blob.
+    #[angelscript_macros::function(instance, name = "readBlob")]
+    pub fn read_blob(&mut self, length: u32) -> ScriptBlob {
+        let _ = length;
+        todo!()
+    }
+
+    /// Write an int8.
+    #[angelscript_macros::function(instance, name = "writeInt8")]
+    pub fn write_i8(&mut self, value: i8) -> bool {
+        let _ = value;
+        todo!()
+    }
+
+    /// Write an int16.
+    #[angelscript_macros::function(instance, name = "writeInt16")]
+    pub fn write_i16(&mut self, value: i16) -> bool {
+        let _ = value;
+        todo!()
+    }
+
+    /// Write an int32.
+    #[angelscript_macros::function(instance, name = "writeInt32")]
+    pub fn write_i32(&mut self, value: i32) -> bool {
+        let _ = value;
+        todo!()
+    }
+
+    /// Write an int64.
+    #[angelscript_macros::function(instance, name = "writeInt64")]
+    pub fn write_i64(&mut self, value: i64) -> bool {
+        let _ = value;
+        todo!()
+    }
+
+    /// Write a uint8.
+    #[angelscript_macros::function(instance, name = "writeUInt8")]
+    pub fn write_u8(&mut self, value: u8) -> bool {
+        let _ = value;
+        todo!()
+    }
+
+    /// Write a uint16.
+    #[angelscript_macros::function(instance, name = "writeUInt16")]
+    pub fn write_u16(&mut self, value: u16) -> bool {
+        let _ = value;
+        todo!()
+    }
+
+    /// Write a uint32.
+    #[angelscript_macros::function(instance, name = "writeUInt32")]
+    pub fn write_u32(&mut self, value: u32) -> bool {
+        let _ = value;
+        todo!()
+    }
+
+    /// Write a uint64.
+    #[angelscript_macros::function(instance, name = "writeUInt64")]
+    pub fn write_u64(&mut self, value: u64) -> bool {
+        let _ = value;
+        todo!()
+    }
+
+    /// Write a float.
+    #[angelscript_macros::function(instance, name = "writeFloat")]
+    pub fn write_f32(&mut self, value: f32) -> bool {
+        let _ = value;
+        todo!()
+    }
+
+    /// Write a double.
+    #[angelscript_macros::function(instance, name = "writeDouble")]
+    pub fn write_f64(&mut self, value: f64) -> bool {
+        let _ = value;
+        todo!()
+    }
+
+    /// Write the bytes of `text`, without a length or terminator.
+    #[angelscript_macros::function(instance, name = "writeString")]
+    pub fn write_string(&mut self, #[param(const, in)] text: &ScriptString) -> bool {
+        let _ = text;
+        todo!()
+    }
+
+    /// Write the bytes of a blob.
+    #[angelscript_macros::function(instance, name = "writeBlob")]
+    pub fn write_blob(&mut self, #[param(const, in)] data: &ScriptBlob) -> bool {
+        let _ = data;
+        todo!()
+    }
+
+    /// Move to byte `position`. Returns false if the stream cannot seek.
+    #[angelscript_macros::function(instance)]
+    pub fn seek(&mut self, position: u64) -> bool {
+        let _ = position;
+        todo!()
+    }
+
+    /// Returns the offset of the next byte read or written.
+    #[angelscript_macros::function(instance, const)]
+    pub fn position(&self) -> u64 {
+        todo!()
+    }
+
+    /// Returns the total size in bytes, or -1 if it is not known.
+    #[angelscript_macros::function(instance, const)]
+    pub fn size(&self) -> i64 {
+        todo!()
+    }
+
+    /// Returns true once a read came up short, or at the end of a stream of
+    /// known size.
+    #[angelscript_macros::function(instance, const)]
+    pub fn eof(&self) -> bool {
+        todo!()
+    }
+
+    /// Send buffered writes on. Returns false if that failed.
+    #[angelscript_macros::function(instance)]
+    pub fn flush(&mut self) -> bool {
+        todo!()
+    }
+
+    /// Returns a copy of the bytes of a stream opened with `openBlob`, or an
+    /// empty blob for other streams.
+    #[angelscript_macros::function(instance, const, name = "toBlob")]
+    pub fn to_blob(&self) -> ScriptBlob {
+        todo!()
+    }
+}
+
+/// Open a stream at the start of a copy of a blob.
+///
+/// Usage: `stream@ s = openBlob(blob());`
+#[angelscript_macros::function(generic, name = "openBlob")]
+#[param(type = ScriptBlob, const, in)]
+#[returns(handle, type = ScriptStream)]
+pub fn as_open_blob(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Open a file with mode `"r"`, `"w"`, `"a"` or `"r+"`, or get null if it
+/// cannot be opened.
+///
+/// Usage: `stream@ s = openFile("save.dat", "w");`
+#[angelscript_macros::function(generic, name = "openFile")]
+#[param(type = ScriptString, const, in)]
+#[param(type = ScriptString, const, in, default = "\"r\"")]
+#[returns(handle, type = ScriptStream)]
+pub fn as_open_file(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Creates the stream module.
+pub fn module() -> Module {
+    Module::new()
+        .ty::<ScriptStream>()
+        .function(ScriptStream::add_ref__meta)
+        .function(ScriptStream::release__meta)
+        .function(ScriptStream::read_i8__meta)
+        .function(ScriptStream::read_i16__meta)
+        .function(ScriptStream::read_i32__meta)
+        .function(ScriptStream::read_i64__meta)
+        .function(ScriptStream::read_u8__meta)
+        .function(ScriptStream::read_u16__meta)
+        .function(ScriptStream::read_u32__meta)
+        .function(ScriptStream::read_u64__meta)
+        .function(ScriptStream::read_f32__meta)
+        .function(ScriptStream::read_f64__meta)
+        .function(ScriptStream::read_string__meta)
+        .function(ScriptStream::read_blob__meta)
+        .function(ScriptStream::write_i8__meta)
+        .function(ScriptStream::write_i16__meta)
+        .function(ScriptStream::write_i32__meta)
+        .function(ScriptStream::write_i64__meta)
+        .function(ScriptStream::write_u8__meta)
+        .function(ScriptStream::write_u16__meta)
+        .function(ScriptStream::write_u32__meta)
+        .function(ScriptStream::write_u64__meta)
+        .function(ScriptStream::write_f32__meta)
+        .function(ScriptStream::write_f64__meta)
+        .function(ScriptStream::write_string__meta)
+        .function(ScriptStream::write_blob__meta)
+        .function(ScriptStream::seek__meta)
+        .function(ScriptStream::position__meta)
+        .function(ScriptStream::size__meta)
+        .function(ScriptStream::eof__meta)
+        .function(ScriptStream::flush__meta)
+        .function(ScriptStream::to_blob__meta)
+        .function(as_open_blob)
+        .function(as_open_file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn save(out: &mut Stream) {
+        assert!(out.write(0x5341_5645u32));
+        assert!(out.write(1.5f32));
+        assert!(out.write(-2i16));
+        assert!(out.write_string("héllo"));
+    }
+
+    fn load(input: &mut Stream) {
+        assert_eq!(input.read::<u32>(), 0x5341_5645);
+        assert_eq!(input.read::<f32>(), 1.5);
+        assert_eq!(input.read::<i16>(), -2);
+        assert!(!input.is_eof());
+        assert_eq!(input.read_string(100).as_str(), "héllo");
+        assert!(input.is_eof());
+        assert_eq!(input.read::<u64>(), 0);
+    }
+
+    #[test]
+    fn blob_streams_share_the_blob_layout() {
+        let mut stream = Stream::from_blob(ScriptBlob::new());
+        save(&mut stream);
+        let blob = stream.to_blob();
+        assert_eq!(blob.read_u32(0), 0x5341_5645);
+        assert_eq!(blob.read_f32(4), 1.5);
+        assert_eq!(stream.size(), Some(16));
+
+        assert!(stream.seek(0));
+        load(&mut stream);
+        assert!(stream.seek(4));
+        assert!(!stream.is_eof());
+
+        let mut fixed = Stream::from_blob(ScriptBlob::with_size(4, true));
+        assert!(fixed.write(7u32));
+        assert!(!fixed.write(1u8));
+        assert_eq!(fixed.position(), 4);
+    }
+
+    #[test]
+    fn file_streams_round_trip() {
+        let path = std::env::temp_dir().join(format!("as-stream-{}.dat", std::process::id()));
+        let mut out = Stream::open_file(&path, "w").unwrap();
+        save(&mut out);
+        assert!(out.flush());
+        drop(out);
+
+        let append = Stream::open_file(&path, "a").unwrap();
+        assert_eq!(append.position(), 16);
+        drop(append);
+
+        let mut input = Stream::open_file(&path, "r").unwrap();
+        assert_eq!(input.size(), Some(16));
+        load(&mut input);
+        assert!(!input.write(1u8));
+        assert!(input.to_blob().is_empty());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(Stream::open_file(&path, "r").is_err());
+        assert!(Stream::open_file(&path, "x").is_err());
+    }
+
+    #[test]
+    fn host_streams_cannot_seek() {
+        let mut stream = Stream::new(IoStream::new(io::Cursor::new(Vec::new())));
+        save(&mut stream);
+        assert_eq!(stream.position(), 16);
+        assert_eq!(stream.size(), None);
+        assert!(!stream.seek(0));
+        assert!(!stream.is_eof());
+        assert_eq!(stream.read::<u8>(), 0);
+        assert!(stream.is_eof());
+    }
+}
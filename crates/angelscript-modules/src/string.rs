@@ -2,11 +2,22 @@
 //!
 //! This is a VALUE type - copied on assignment. It provides all methods
 //! needed for the AngelScript string type.
+//!
+//! Numbers are converted in one invariant format whatever the locale of the
+//! machine: `parseFloat`, `formatFloat` and `string + double` always use `.`
+//! as the decimal separator and never group digits, so text one machine
+//! writes parses on every other. Text shown to players can be formatted for
+//! a locale with `formatNumber`, which takes the locale explicitly:
+//!
+//! ```angelscript
+//! formatNumber(1234567.891, 2, "de-DE")   // "1.234.567,89"
+//! formatNumber(1234567.891, 2, "")        // "1234567.89"
+//! ```
 
 use std::fmt;
 use std::ops::{Deref, DerefMut};
 
-use angelscript_core::Dynamic;
+use angelscript_core::{Dynamic, MemoryBudget, NativeError};
 use angelscript_macros::Any;
 use angelscript_registry::Module;
 
@@ -271,6 +282,124 @@ pub fn format_float_opts_width_prec(
     ScriptString(format_float_impl(val, &options.0, width, precision))
 }
 
+// =============================================================================
+// LOCALE-AWARE FORMATTING
+// =============================================================================
+
+/// Separators used to format numbers for a locale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberLocale {
+    /// Separator between the integer and fractional digits.
+    pub decimal: char,
+    /// Separator between groups of three integer digits, if any.
+    pub grouping: Option<char>,
+}
+
+impl NumberLocale {
+    /// The format of the parsing and formatting functions: `.` decimal
+    /// separator and no grouping.
+    pub const INVARIANT: Self = Self {
+        decimal: '.',
+        grouping: None,
+    };
+
+    /// Look up a locale by tag, such as `de-DE`, `fr` or `pt_BR.UTF-8`.
+    /// Unknown languages, the empty tag and `C`/`POSIX` get
+    /// [`INVARIANT`](Self::INVARIANT).
+    pub fn from_tag(tag: &str) -> Self {
+        let tag = tag.split(['.', '@']).next().unwrap_or_default();
+        let mut parts = tag.split(['-', '_']);
+        let language = parts.next().unwrap_or_default().to_ascii_lowercase();
+        let region = parts.next().unwrap_or_default().to_ascii_uppercase();
+
+        let (decimal, grouping) = match (language.as_str(), region.as_str()) {
+            ("de", "CH" | "LI") => ('.', '\u{2019}'),
+            ("es", "MX" | "US") => ('.', ','),
+            ("en" | "ja" | "zh" | "ko" | "he" | "th" | "hi", _) => ('.', ','),
+            (
+                "de" | "nl" | "it" | "es" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "sl" | "hr"
+                | "sr" | "vi",
+                _,
+            ) => (',', '.'),
+            ("fr", _) => (',', '\u{202F}'),
+            (
+                "ru" | "uk" | "pl" | "cs" | "sk" | "fi" | "sv" | "nb" | "no" | "hu" | "bg" | "lt"
+                | "lv" | "et",
+                _,
+            ) => (',', '\u{A0}'),
+            _ => return Self::INVARIANT,
+        };
+        Self {
+            decimal,
+            grouping: Some(grouping),
+        }
+    }
+
+    /// Format an integer with the locale's digit grouping.
+    pub fn format_int(&self, val: i64) -> String {
+        let digits = val.unsigned_abs().to_string();
+        let mut s = String::with_capacity(digits.len() * 2);
+        if val < 0 {
+            s.push('-');
+        }
+        self.push_grouped(&mut s, &digits);
+        s
+    }
+
+    /// Format a float with `precision` fractional digits and the locale's
+    /// separators. NaN and infinities are formatted as in Rust.
+    pub fn format_float(&self, val: f64, precision: u32) -> String {
+        let invariant = format!("{:.precision$}", val, precision = precision as usize);
+        if !val.is_finite() {
+            return invariant;
+        }
+        let (sign, unsigned) = match invariant.strip_prefix('-') {
+            Some(unsigned) => ("-", unsigned),
+            None => ("", invariant.as_str()),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+
+        let mut s = String::with_capacity(invariant.len() * 2);
+        s.push_str(sign);
+        self.push_grouped(&mut s, integer);
+        if !fraction.is_empty() {
+            s.push(self.decimal);
+            s.push_str(fraction);
+        }
+        s
+    }
+
+    fn push_grouped(&self, s: &mut String, digits: &str) {
+        let Some(grouping) = self.grouping else {
+            s.push_str(digits);
+            return;
+        };
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                s.push(grouping);
+            }
+            s.push(digit);
+        }
+    }
+}
+
+/// Format i64 with the digit grouping of `locale` (e.g. "de-DE").
+#[angelscript_macros::function(name = "formatNumber")]
+pub fn format_number_int(val: i64, #[param(const, in)] locale: &ScriptString) -> ScriptString {
+    ScriptString(NumberLocale::from_tag(&locale.0).format_int(val))
+}
+
+/// Format f64 with `precision` fractional digits and the separators of
+/// `locale` (e.g. "de-DE").
+#[angelscript_macros::function(name = "formatNumber")]
+pub fn format_number_float(
+    val: f64,
+    precision: u32,
+    #[param(const, in)] locale: &ScriptString,
+) -> ScriptString {
+    ScriptString(NumberLocale::from_tag(&locale.0).format_float(val, precision))
+}
+
 // =============================================================================
 // JOIN FUNCTION
 // =============================================================================
@@ -827,6 +956,36 @@ impl ScriptString {
         self.0.push_str(&other.0);
     }
 
+    /// Concatenate, charging the new string to `memory`.
+    ///
+    /// # Errors
+    ///
+    /// Fails following the budget's policy if the budget is exhausted or
+    /// the allocator fails.
+    pub fn try_concat(&self, other: &Self, memory: &mut MemoryBudget) -> Result<Self, NativeError> {
+        let mut out = String::new();
+        memory.reserve_string(&mut out, self.0.len() + other.0.len())?;
+        out.push_str(&self.0);
+        out.push_str(&other.0);
+        Ok(Self(out))
+    }
+
+    /// Append, charging any growth to `memory`.
+    ///
+    /// # Errors
+    ///
+    /// Fails following the budget's policy if the budget is exhausted or
+    /// the allocator fails. The string is left unchanged.
+    pub fn try_append(
+        &mut self,
+        other: &Self,
+        memory: &mut MemoryBudget,
+    ) -> Result<(), NativeError> {
+        memory.reserve_string(&mut self.0, other.0.len())?;
+        self.0.push_str(&other.0);
+        Ok(())
+    }
+
     // =========================================================================
     // STRING + PRIMITIVE OPERATORS
     // =========================================================================
@@ -1158,6 +1317,8 @@ pub fn module() -> Module {
         .function(format_float_opts)
         .function(format_float_opts_width)
         .function(format_float_opts_width_prec)
+        .function(format_number_int)
+        .function(format_number_float)
         // Join function
         .function(join)
 }
@@ -1289,6 +1450,22 @@ mod tests {
         assert_eq!(s.as_str(), "hello world");
     }
 
+    #[test]
+    fn test_concat_within_budget() {
+        use angelscript_core::OutOfMemory;
+
+        let mut memory = MemoryBudget::new(OutOfMemory::Exception, Some(16));
+        let hello = ScriptString::from("hello");
+        let joined = hello.try_concat(&" world".into(), &mut memory).unwrap();
+        assert_eq!(joined.as_str(), "hello world");
+        assert_eq!(memory.used(), joined.0.capacity());
+
+        let mut s = joined;
+        let err = s.try_append(&" again".into(), &mut memory).unwrap_err();
+        assert!(err.is_exception());
+        assert_eq!(s.as_str(), "hello world");
+    }
+
     #[test]
     fn test_cmp() {
         let s1 = ScriptString::from("abc");
@@ -1320,6 +1497,58 @@ mod tests {
         assert_eq!(s.count_occurrences(&"x".into()), 0);
     }
 
+    #[test]
+    fn test_numbers_use_invariant_format() {
+        assert_eq!(__as_fn__parse_float(&"1.5".into()), 1.5);
+        assert_eq!(__as_fn__parse_float(&"1,5".into()), 0.0);
+        let mut count = 0;
+        assert_eq!(
+            __as_fn__parse_float_with_count(&"1,5".into(), &mut count),
+            1.0
+        );
+        assert_eq!(count, 1);
+        assert_eq!(
+            __as_fn__format_float_opts_width_prec(0.5, &"".into(), 0, 2).as_str(),
+            "0.50"
+        );
+        assert_eq!(
+            ScriptString::from("x=").concat_double(2.5).as_str(),
+            "x=2.5"
+        );
+        assert_eq!(__as_fn__format_int(1234567).as_str(), "1234567");
+    }
+
+    #[test]
+    fn test_format_number_with_locale() {
+        let de = ScriptString::from("de-DE");
+        assert_eq!(
+            __as_fn__format_number_int(-1234567, &de).as_str(),
+            "-1.234.567"
+        );
+        assert_eq!(
+            __as_fn__format_number_float(1234567.891, 2, &de).as_str(),
+            "1.234.567,89"
+        );
+        assert_eq!(
+            __as_fn__format_number_float(-999.5, 1, &"en_US.UTF-8".into()).as_str(),
+            "-999.5"
+        );
+        assert_eq!(
+            __as_fn__format_number_float(1000.0, 0, &"fr".into()).as_str(),
+            "1\u{202F}000"
+        );
+        assert_eq!(__as_fn__format_number_int(123, &de).as_str(), "123");
+        assert_eq!(NumberLocale::from_tag("C"), NumberLocale::INVARIANT);
+        assert_eq!(
+            __as_fn__format_number_float(1234.5, 1, &"".into()).as_str(),
+            "1234.5"
+        );
+        assert_eq!(
+            __as_fn__format_number_float(f64::NAN, 2, &de).as_str(),
+            "NaN"
+        );
+    }
+
     #[test]
     fn test_module_creates() {
         use angelscript_registry::HasClassMeta;
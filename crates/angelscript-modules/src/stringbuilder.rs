@@ -0,0 +1,185 @@
+//! ScriptStringBuilder - AngelScript `stringbuilder` type.
+//!
+//! `s = s + part` copies `s` on every iteration, which is quadratic when a
+//! script builds long text in a loop. A `stringbuilder` appends into one
+//! growing buffer and produces the string once at the end:
+//!
+//! ```angelscript
+//! stringbuilder sb;
+//! for (uint i = 0; i < lines.length(); i++) {
+//!     sb += lines[i];
+//!     sb += "\n";
+//! }
+//! string text = sb.toString();
+//! ```
+//!
+//! Like `string`, it is a value type indexed by bytes.
+
+use std::fmt::{self, Write};
+
+use angelscript_macros::Any;
+use angelscript_registry::Module;
+
+use crate::ScriptString;
+
+/// AngelScript `stringbuilder`, a growable text buffer.
+#[derive(Any, Clone, Default, PartialEq, Eq)]
+#[angelscript(name = "stringbuilder", value)]
+pub struct ScriptStringBuilder(String);
+
+impl ScriptStringBuilder {
+    /// Create an empty builder.
+    #[inline]
+    pub fn new() -> Self {
+        Self(String::new())
+    }
+
+    /// Get the text built so far.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the byte length of the text.
+    #[angelscript_macros::function(instance, const, name = "length")]
+    pub fn len(&self) -> u32 {
+        self.0.len() as u32
+    }
+
+    /// Returns true if nothing was appended.
+    #[angelscript_macros::function(instance, const, name = "isEmpty")]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Reserve capacity for at least `additional` more bytes.
+    #[angelscript_macros::function(instance)]
+    pub fn reserve(&mut self, additional: u32) {
+        self.0.reserve(additional as usize);
+    }
+
+    /// Remove all text, keeping the capacity.
+    #[angelscript_macros::function(instance)]
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Append a string.
+    #[angelscript_macros::function(instance)]
+    pub fn append(&mut self, #[param(const, in)] text: &ScriptString) {
+        self.0.push_str(text.as_str());
+    }
+
+    /// Append an int64 in decimal.
+    #[angelscript_macros::function(instance, name = "append")]
+    pub fn append_int(&mut self, val: i64) {
+        write!(self.0, "{}", val).unwrap();
+    }
+
+    /// Append a uint64 in decimal.
+    #[angelscript_macros::function(instance, name = "append")]
+    pub fn append_uint(&mut self, val: u64) {
+        write!(self.0, "{}", val).unwrap();
+    }
+
+    /// Append a double.
+    #[angelscript_macros::function(instance, name = "append")]
+    pub fn append_double(&mut self, val: f64) {
+        write!(self.0, "{}", val).unwrap();
+    }
+
+    /// Append `true` or `false`.
+    #[angelscript_macros::function(instance, name = "append")]
+    pub fn append_bool(&mut self, val: bool) {
+        self.0.push_str(if val { "true" } else { "false" });
+    }
+
+    /// Append operator - sb += text.
+    #[angelscript_macros::function(instance, operator = Operator::AddAssign)]
+    pub fn append_op(&mut self, #[param(const, in)] text: &ScriptString) {
+        self.append(text);
+    }
+
+    /// Insert a string at byte position `pos`, clamped to the length.
+    /// Does nothing if `pos` is not on a character boundary.
+    #[angelscript_macros::function(instance)]
+    pub fn insert(&mut self, pos: u32, #[param(const, in)] text: &ScriptString) {
+        let pos = (pos as usize).min(self.0.len());
+        if self.0.is_char_boundary(pos) {
+            self.0.insert_str(pos, text.as_str());
+        }
+    }
+
+    /// Returns the text built so far.
+    #[angelscript_macros::function(instance, const, name = "toString")]
+    pub fn to_script_string(&self) -> ScriptString {
+        ScriptString::from(self.0.as_str())
+    }
+}
+
+impl fmt::Debug for ScriptStringBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ScriptStringBuilder").field(&self.0).finish()
+    }
+}
+
+impl fmt::Display for ScriptStringBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Creates the stringbuilder module.
+pub fn module() -> Module {
+    Module::new()
+        .ty::<ScriptStringBuilder>()
+        .function(ScriptStringBuilder::len__meta)
+        .function(ScriptStringBuilder::is_empty__meta)
+        .function(ScriptStringBuilder::reserve__meta)
+        .function(ScriptStringBuilder::clear__meta)
+        .function(ScriptStringBuilder::append__meta)
+        .function(ScriptStringBuilder::append_int__meta)
+        .function(ScriptStringBuilder::append_uint__meta)
+        .function(ScriptStringBuilder::append_double__meta)
+        .function(ScriptStringBuilder::append_bool__meta)
+        .function(ScriptStringBuilder::append_op__meta)
+        .function(ScriptStringBuilder::insert__meta)
+        .function(ScriptStringBuilder::to_script_string__meta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_text() {
+        let mut sb = ScriptStringBuilder::new();
+        sb.append(&ScriptString::from("hp "));
+        sb.append_int(-5);
+        sb.append_op(&ScriptString::from("/"));
+        sb.append_uint(10);
+        sb.append_bool(true);
+        sb.insert(0, &ScriptString::from("> "));
+        sb.insert(100, &ScriptString::from("!"));
+        assert_eq!(sb.to_script_string().as_str(), "> hp -5/10true!");
+        assert_eq!(sb.len(), 15);
+
+        sb.clear();
+        assert!(sb.is_empty());
+    }
+
+    #[test]
+    fn insert_keeps_characters_whole() {
+        let mut sb = ScriptStringBuilder::new();
+        sb.append(&ScriptString::from("é"));
+        sb.insert(1, &ScriptString::from("x"));
+        assert_eq!(sb.as_str(), "é");
+    }
+
+    #[test]
+    fn test_module_creates() {
+        let m = module();
+        assert_eq!(m.classes.len(), 1);
+        assert_eq!(m.functions.len(), 12);
+    }
+}
@@ -0,0 +1,297 @@
+//! Timers driven by the host.
+//!
+//! Scripts schedule callbacks after a delay or at an interval, without each
+//! game writing its own timer manager:
+//!
+//! ```angelscript
+//! void spawnWave() { ... }
+//! void blink() { ... }
+//!
+//! void main() {
+//!     schedule(2.0f, spawnWave);
+//!     TimerHandle cursor = scheduleRepeating(0.5f, blink);
+//!     ...
+//!     cancel(cursor);
+//! }
+//! ```
+//!
+//! Time only advances when the host calls [`Timers::update`], usually once
+//! per frame with the frame time. It returns the callbacks that came due, in
+//! order, and the host calls them:
+//!
+//! ```ignore
+//! for callback in timers.update(dt) {
+//!     unit.call_callable::<_, ()>(&callback, ())?;
+//! }
+//! ```
+//!
+//! A repeating timer fires once for every interval that elapsed, so a long
+//! frame catches up instead of drifting. The module is not part of the
+//! default modules, since it needs the host to drive it.
+
+use angelscript_core::{CallContext, native_error::NativeError};
+use angelscript_macros::{Any, funcdef};
+use angelscript_registry::Module;
+
+/// Callback of a timer.
+///
+/// AngelScript: `funcdef void TimerCallback();`
+#[funcdef]
+pub type TimerCallback = fn();
+
+/// Handle of a scheduled timer. The id `0` is no timer.
+#[derive(Any, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[angelscript(name = "TimerHandle", value)]
+pub struct TimerHandle(u64);
+
+impl TimerHandle {
+    /// No timer.
+    pub const NONE: Self = Self(0);
+
+    /// Get the raw id.
+    #[inline]
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+
+    /// Returns true if the handle was returned by a `schedule` call.
+    ///
+    /// The timer may have fired or been cancelled since.
+    #[angelscript_macros::function(instance, const, name = "isValid")]
+    pub fn is_valid(&self) -> bool {
+        self.0 != 0
+    }
+
+    /// Equality comparison.
+    #[angelscript_macros::function(operator = Operator::Equals, const)]
+    pub fn eq_op(&self, #[param(const, in)] other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+#[derive(Debug)]
+struct Timer<C> {
+    handle: TimerHandle,
+    due: f64,
+    /// Time between firings of a repeating timer.
+    interval: Option<f64>,
+    callback: C,
+}
+
+/// The timers of a unit, holding callbacks of type `C`.
+///
+/// Hosts keep one per unit, with `C` the unit's callable type.
+#[derive(Debug)]
+pub struct Timers<C> {
+    now: f64,
+    last_id: u64,
+    timers: Vec<Timer<C>>,
+}
+
+impl<C> Default for Timers<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C> Timers<C> {
+    /// Create an empty set of timers, at time zero.
+    pub fn new() -> Self {
+        Self {
+            now: 0.0,
+            last_id: 0,
+            timers: Vec::new(),
+        }
+    }
+
+    /// Time advanced by [`update`](Self::update) so far, in seconds.
+    pub fn now(&self) -> f64 {
+        self.now
+    }
+
+    /// Number of pending timers.
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    /// Returns true if no timer is pending.
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// Fire `callback` once, `delay` seconds from now.
+    ///
+    /// A negative delay is treated as zero, firing on the next update.
+    pub fn schedule(&mut self, delay: f32, callback: C) -> TimerHandle {
+        self.add(delay, None, callback)
+    }
+
+    /// Fire `callback` every `interval` seconds, starting `interval` seconds
+    /// from now, until cancelled.
+    ///
+    /// A timer with an interval of zero or less fires once per update.
+    pub fn schedule_repeating(&mut self, interval: f32, callback: C) -> TimerHandle {
+        self.add(interval, Some(f64::from(interval.max(0.0))), callback)
+    }
+
+    /// Cancel a pending timer, returning its callback.
+    ///
+    /// Returns `None` if the timer already fired or was cancelled.
+    pub fn cancel(&mut self, handle: TimerHandle) -> Option<C> {
+        let index = self.timers.iter().position(|t| t.handle == handle)?;
+        Some(self.timers.remove(index).callback)
+    }
+
+    /// Cancel all pending timers.
+    pub fn clear(&mut self) {
+        self.timers.clear();
+    }
+
+    fn add(&mut self, delay: f32, interval: Option<f64>, callback: C) -> TimerHandle {
+        self.last_id += 1;
+        let handle = TimerHandle(self.last_id);
+        self.timers.push(Timer {
+            handle,
+            due: self.now + f64::from(delay.max(0.0)),
+            interval,
+            callback,
+        });
+        handle
+    }
+}
+
+impl<C: Clone> Timers<C> {
+    /// Advance time by `dt` seconds and return the callbacks that came due,
+    /// earliest first; timers due at the same time fire in the order they
+    /// were scheduled.
+    ///
+    /// Timers scheduled or cancelled while the returned callbacks run take
+    /// effect from the next update.
+    pub fn update(&mut self, dt: f32) -> Vec<C> {
+        if dt.is_finite() && dt > 0.0 {
+            self.now += f64::from(dt);
+        }
+        let now = self.now;
+
+        let mut fired: Vec<(f64, TimerHandle, C)> = Vec::new();
+        self.timers.retain_mut(|timer| {
+            while timer.due <= now {
+                fired.push((timer.due, timer.handle, timer.callback.clone()));
+                match timer.interval {
+                    None => return false,
+                    Some(interval) if interval > 0.0 => timer.due += interval,
+                    // Fire again on the next update
+                    Some(_) => {
+                        timer.due = now;
+                        break;
+                    }
+                }
+            }
+            true
+        });
+        fired.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.0.cmp(&b.1.0)));
+        fired.into_iter().map(|(_, _, callback)| callback).collect()
+    }
+}
+
+/// Call a function once after a delay in seconds.
+///
+/// Usage: `TimerHandle h = schedule(2.0f, spawnWave);`
+///
+/// Note: The VM adds the callback to the host's timers for the unit.
+#[angelscript_macros::function(generic, name = "schedule")]
+#[param(type = f32)]
+#[param(type = TimerCallback)]
+#[returns(type = TimerHandle)]
+pub fn as_schedule(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Call a function every interval in seconds until cancelled.
+///
+/// Usage: `TimerHandle h = scheduleRepeating(0.5f, blink);`
+///
+/// Note: The VM adds the callback to the host's timers for the unit.
+#[angelscript_macros::function(generic, name = "scheduleRepeating")]
+#[param(type = f32)]
+#[param(type = TimerCallback)]
+#[returns(type = TimerHandle)]
+pub fn as_schedule_repeating(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Cancel a timer. Returns false if it already fired or was cancelled.
+///
+/// Usage: `cancel(h);`
+///
+/// Note: The VM removes the callback from the host's timers for the unit.
+#[angelscript_macros::function(generic, name = "cancel")]
+#[param(type = TimerHandle)]
+#[returns(type = bool)]
+pub fn as_cancel(_ctx: &mut CallContext) -> Result<(), NativeError> {
+    todo!()
+}
+
+/// Creates the timers module with the `TimerHandle` type, the
+/// `TimerCallback` funcdef and the scheduling functions.
+pub fn module() -> Module {
+    Module::new()
+        .ty::<TimerHandle>()
+        .function(TimerHandle::is_valid__meta)
+        .function(TimerHandle::eq_op__meta)
+        .funcdef(__as_TimerCallback_funcdef_meta())
+        .function(as_schedule)
+        .function(as_schedule_repeating)
+        .function(as_cancel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_creates() {
+        let m = module();
+        assert!(m.namespace.is_empty());
+        assert_eq!(m.classes.len(), 1);
+        assert_eq!(m.funcdefs.len(), 1);
+        assert_eq!(m.functions.len(), 5);
+    }
+
+    #[test]
+    fn one_shot_timers_fire_once_in_order() {
+        let mut timers = Timers::new();
+        timers.schedule(1.0, "late");
+        timers.schedule(0.5, "early");
+        let tie = timers.schedule(0.5, "tie");
+        timers.schedule(-1.0, "now");
+        assert!(tie.is_valid());
+
+        assert_eq!(timers.update(0.0), ["now"]);
+        assert_eq!(timers.update(0.5), ["early", "tie"]);
+        assert!(timers.update(0.25).is_empty());
+        assert_eq!(timers.update(0.25), ["late"]);
+        assert!(timers.is_empty());
+        assert_eq!(timers.cancel(tie), None);
+    }
+
+    #[test]
+    fn repeating_timers_catch_up_until_cancelled() {
+        let mut timers = Timers::new();
+        let blink = timers.schedule_repeating(0.5, "blink");
+        timers.schedule(0.75, "wave");
+        let every_update = timers.schedule_repeating(0.0, "tick");
+
+        assert_eq!(timers.update(0.25), ["tick"]);
+        assert_eq!(
+            timers.update(1.5),
+            ["tick", "blink", "wave", "blink", "blink"]
+        );
+        assert_eq!(timers.now(), 1.75);
+        assert_eq!(timers.cancel(blink), Some("blink"));
+        assert_eq!(timers.cancel(blink), None);
+        assert_eq!(timers.update(f32::NAN), ["tick"]);
+        assert_eq!(timers.cancel(every_update), Some("tick"));
+        assert!(timers.update(10.0).is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+//! URL module for percent-encoding text.
+//!
+//! All items are in the `url` namespace:
+//!
+//! ```angelscript
+//! url::encode("name=Sir Robin&x")   // "name%3DSir%20Robin%26x"
+//! url::decode("Sir%20Robin+II")     // "Sir Robin II"
+//! ```
+
+use angelscript_registry::Module;
+
+use crate::ScriptString;
+
+/// Percent-encode every byte except the unreserved characters
+/// `A-Z a-z 0-9 - _ . ~`.
+#[angelscript_macros::function]
+pub fn encode(#[param(const, in)] s: &ScriptString) -> ScriptString {
+    let mut out = String::with_capacity(s.as_str().len());
+    for byte in s.as_str().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(char::from(byte))
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    ScriptString::from(out)
+}
+
+/// Decode `%XX` escapes, and `+` as a space as in query strings.
+///
+/// Malformed escapes are kept as they are, and bytes that do not form
+/// UTF-8 are replaced with U+FFFD.
+#[angelscript_macros::function]
+pub fn decode(#[param(const, in)] s: &ScriptString) -> ScriptString {
+    let bytes = s.as_str().as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut at = 0;
+    while at < bytes.len() {
+        match bytes[at] {
+            b'%' => {
+                // from_str_radix accepts a sign, so check the digits first
+                let escape = bytes
+                    .get(at + 1..at + 3)
+                    .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match escape {
+                    Some(byte) => {
+                        out.push(byte);
+                        at += 3;
+                        continue;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b'+' => out.push(b' '),
+            byte => out.push(byte),
+        }
+        at += 1;
+    }
+    ScriptString::from(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// Creates the url module with all functions.
+pub fn module() -> Module {
+    Module::in_namespace(&["url"])
+        .function(encode)
+        .function(decode)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_escapes_reserved_bytes() {
+        let encoded = __as_fn__encode(&ScriptString::from("name=Sir Robin&x~é"));
+        assert_eq!(encoded.as_str(), "name%3DSir%20Robin%26x~%C3%A9");
+        assert_eq!(__as_fn__decode(&encoded).as_str(), "name=Sir Robin&x~é");
+    }
+
+    #[test]
+    fn decode_keeps_malformed_escapes() {
+        assert_eq!(
+            __as_fn__decode(&ScriptString::from("a+b%2")).as_str(),
+            "a b%2"
+        );
+        assert_eq!(
+            __as_fn__decode(&ScriptString::from("%zz%41")).as_str(),
+            "%zzA"
+        );
+        assert_eq!(__as_fn__decode(&ScriptString::from("%+5")).as_str(), "% 5");
+    }
+}
@@ -0,0 +1,181 @@
+//! Operator precedence conformance with upstream AngelScript.
+//!
+//! [`UPSTREAM`] is the binary operator table of the reference compiler
+//! (`asCCompiler::GetPrecedence`), and the tests parse every pair of
+//! operators in both orders, checking the parser groups them as upstream
+//! does. Prefix, postfix, ternary and assignment operators are checked
+//! against hand-written cases.
+
+use super::expr::{Expr, MemberAccess};
+use super::{BinaryOp, Parser};
+
+/// Upstream precedence of each binary operator spelling; higher binds
+/// tighter. Only `**` is right-associative.
+const UPSTREAM: &[(&str, BinaryOp, i8)] = &[
+    ("**", BinaryOp::Pow, 1),
+    ("*", BinaryOp::Mul, 0),
+    ("/", BinaryOp::Div, 0),
+    ("%", BinaryOp::Mod, 0),
+    ("+", BinaryOp::Add, -1),
+    ("-", BinaryOp::Sub, -1),
+    ("<<", BinaryOp::ShiftLeft, -2),
+    (">>", BinaryOp::ShiftRight, -2),
+    (">>>", BinaryOp::ShiftRightUnsigned, -2),
+    ("&", BinaryOp::BitwiseAnd, -3),
+    ("^", BinaryOp::BitwiseXor, -4),
+    ("|", BinaryOp::BitwiseOr, -5),
+    ("<=", BinaryOp::LessEqual, -6),
+    ("<", BinaryOp::Less, -6),
+    (">=", BinaryOp::GreaterEqual, -6),
+    (">", BinaryOp::Greater, -6),
+    ("==", BinaryOp::Equal, -7),
+    ("!=", BinaryOp::NotEqual, -7),
+    ("xor", BinaryOp::LogicalXor, -7),
+    ("^^", BinaryOp::LogicalXor, -7),
+    ("is", BinaryOp::Is, -7),
+    ("!is", BinaryOp::NotIs, -7),
+    ("&&", BinaryOp::LogicalAnd, -8),
+    ("and", BinaryOp::LogicalAnd, -8),
+    ("||", BinaryOp::LogicalOr, -9),
+    ("or", BinaryOp::LogicalOr, -9),
+];
+
+/// Parse an expression and render it fully parenthesized.
+fn grouping(source: &str) -> String {
+    let arena = bumpalo::Bump::new();
+    let mut parser = Parser::new(source, &arena);
+    let expr = parser
+        .parse_expr(0)
+        .unwrap_or_else(|error| panic!("failed to parse '{source}': {error:?}"));
+    assert!(
+        parser.is_eof(),
+        "'{source}' was not parsed as one expression"
+    );
+    render(expr)
+}
+
+fn render(expr: &Expr<'_>) -> String {
+    match expr {
+        Expr::Ident(ident) => ident.ident.name.to_string(),
+        Expr::Binary(bin) => format!("({} {} {})", render(bin.left), bin.op, render(bin.right)),
+        Expr::Unary(unary) => format!("({}{})", unary.op, render(unary.operand)),
+        Expr::Postfix(postfix) => format!("({}{})", render(postfix.operand), postfix.op),
+        Expr::Assign(assign) => format!(
+            "({} {} {})",
+            render(assign.target),
+            assign.op,
+            render(assign.value)
+        ),
+        Expr::Ternary(ternary) => format!(
+            "({} ? {} : {})",
+            render(ternary.condition),
+            render(ternary.then_expr),
+            render(ternary.else_expr)
+        ),
+        Expr::Member(member) => match member.member {
+            MemberAccess::Field(field) => format!("({}.{})", render(member.object), field.name),
+            MemberAccess::Method { name, .. } => {
+                format!("({}.{}())", render(member.object), name.name)
+            }
+        },
+        Expr::Paren(paren) => render(paren.expr),
+        other => panic!("unexpected expression in conformance case: {other:?}"),
+    }
+}
+
+#[test]
+fn table_covers_every_binary_operator() {
+    let (left, right) = BinaryOp::Pow.binding_power();
+    assert!(right < left, "** is right-associative");
+    for &(spelling, op, _) in UPSTREAM {
+        let source = format!("a {spelling} b");
+        let expected = format!("(a {op} b)");
+        assert_eq!(grouping(&source), expected, "{source}");
+    }
+}
+
+#[test]
+fn every_binary_pair_groups_as_upstream() {
+    let mut mismatches = Vec::new();
+    for &(first, first_op, first_level) in UPSTREAM {
+        for &(second, second_op, second_level) in UPSTREAM {
+            let source = format!("a {first} b {second} c");
+            // Equal levels group to the left, except for `**`
+            let left = first_level > second_level
+                || (first_level == second_level && first_op != BinaryOp::Pow);
+            let expected = match left {
+                true => format!("((a {first_op} b) {second_op} c)"),
+                false => format!("(a {first_op} (b {second_op} c))"),
+            };
+            let actual = grouping(&source);
+            if actual != expected {
+                mismatches.push(format!("{source}: expected {expected}, got {actual}"));
+            }
+        }
+    }
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+#[test]
+fn prefix_and_postfix_operators() {
+    let cases = [
+        // Prefix operators bind tighter than every binary operator
+        ("-a ** b", "((-a) ** b)"),
+        ("!a == b", "((!a) == b)"),
+        ("~a & b", "((~a) & b)"),
+        ("@a is b", "((@a) is b)"),
+        // Postfix operators and member access bind tighter than prefix ones
+        ("-a.b", "(-(a.b))"),
+        ("-a++", "(-(a++))"),
+        ("++a.b", "(++(a.b))"),
+        ("a.b() + c", "((a.b()) + c)"),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(grouping(source), expected, "{source}");
+    }
+}
+
+#[test]
+fn ternary_and_assignment() {
+    let cases = [
+        ("a || b ? c : d", "((a || b) ? c : d)"),
+        ("a ? b : c ? d : e", "(a ? b : (c ? d : e))"),
+        ("a ? b = c : d", "(a ? (b = c) : d)"),
+        // The else branch is an assignment expression, as upstream's grammar
+        ("x = a ? b : c = d", "(x = (a ? b : (c = d)))"),
+        ("a = b += c", "(a = (b += c))"),
+        ("a |= b & c", "(a |= (b & c))"),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(grouping(source), expected, "{source}");
+    }
+}
+
+#[test]
+fn null_coalesce_extension() {
+    // Not upstream syntax; `??` binds looser than `||` and tighter than `?:`
+    let cases = [
+        ("a ?? b ?? c", "(a ?? (b ?? c))"),
+        ("a ?? b || c", "(a ?? (b || c))"),
+        ("a || b ?? c", "((a || b) ?? c)"),
+        ("a ?? b ? c : d", "((a ?? b) ? c : d)"),
+        ("x = a ?? b", "(x = (a ?? b))"),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(grouping(source), expected, "{source}");
+    }
+}
+
+#[test]
+fn chained_comparisons_group_left() {
+    let cases = [
+        ("a < b < c", "((a < b) < c)"),
+        ("a == b != c", "((a == b) != c)"),
+        ("a is b == c", "((a is b) == c)"),
+        ("a < b == c > d", "((a < b) == (c > d))"),
+        ("a & MASK == z", "((a & MASK) == z)"),
+    ];
+    for (source, expected) in cases {
+        assert_eq!(grouping(source), expected, "{source}");
+    }
+}
@@ -170,6 +170,9 @@ pub struct FieldDecl<'ast> {
     pub name: Ident<'ast>,
     /// Optional initializer
     pub init: Option<&'ast Expr<'ast>>,
+    /// `[renamedFrom("old")]` metadata - the field's name in an earlier
+    /// version of the class, so hot reload keeps its value
+    pub renamed_from: Option<&'ast str>,
     /// Source location
     pub span: Span,
 }
@@ -746,6 +749,7 @@ mod tests {
             ty: TypeExpr::primitive(PrimitiveType::Int, Span::new(1, 9, 3)),
             name: Ident::new("value", Span::new(1, 13, 5)),
             init: None,
+            renamed_from: None,
             span: Span::new(1, 1, 18),
         });
         assert!(matches!(field, ClassMember::Field(_)));
@@ -805,6 +809,7 @@ mod tests {
                 kind: LiteralKind::Int(0),
                 span: Span::new(1, 21, 1),
             }))),
+            renamed_from: None,
             span: Span::new(1, 1, 22),
         };
         // Verify field init is literal 0
@@ -69,6 +69,14 @@ impl<'ast> Parser<'ast> {
 
         let token = *self.peek();
 
+        if modifiers.partial && token.kind != TokenKind::Class {
+            self.error(
+                ParseErrorKind::InvalidModifier,
+                token.span,
+                "'partial' is only allowed on classes",
+            );
+        }
+
         match token.kind {
             TokenKind::Class => self.parse_class(modifiers, visibility),
             TokenKind::Interface => self.parse_interface(modifiers),
@@ -88,9 +96,47 @@ impl<'ast> Parser<'ast> {
         }
     }
 
-    /// Parse declaration modifiers (shared, external, abstract, final).
+    /// Parse declaration metadata and modifiers (shared, external, abstract,
+    /// final).
+    ///
+    /// Metadata is a bracketed list of names before the declaration, like
+    /// `[inline]`, each optionally taking a string argument, like
+    /// `[renamedFrom("hp")]`. Names the compiler does not know are ignored,
+    /// so scripts may carry metadata for other tools.
     fn parse_modifiers(&mut self) -> Result<DeclModifiers, ParseError> {
+        self.parse_metadata_and_modifiers()
+            .map(|(modifiers, _)| modifiers)
+    }
+
+    /// Parse declaration metadata and modifiers, also returning the argument
+    /// of `[renamedFrom("old")]` metadata.
+    fn parse_metadata_and_modifiers(
+        &mut self,
+    ) -> Result<(DeclModifiers, Option<&'ast str>), ParseError> {
         let mut modifiers = DeclModifiers::new();
+        let mut renamed_from = None;
+
+        while self.eat(TokenKind::LeftBracket).is_some() {
+            loop {
+                let name = self.expect(TokenKind::Identifier)?.lexeme;
+                let argument = if self.eat(TokenKind::LeftParen).is_some() {
+                    let argument = self.expect(TokenKind::StringLiteral)?.lexeme;
+                    self.expect(TokenKind::RightParen)?;
+                    Some(argument.trim_matches('"'))
+                } else {
+                    None
+                };
+                match (name, argument) {
+                    ("inline", None) => modifiers.inline = true,
+                    ("renamedFrom", Some(old)) => renamed_from = Some(old),
+                    _ => {}
+                }
+                if self.eat(TokenKind::Comma).is_none() {
+                    break;
+                }
+            }
+            self.expect(TokenKind::RightBracket)?;
+        }
 
         loop {
             if self.check_contextual("shared") {
@@ -137,12 +183,40 @@ impl<'ast> Parser<'ast> {
                 }
                 self.advance();
                 modifiers.final_ = true;
+            } else if self.check_partial_modifier() {
+                if modifiers.partial {
+                    let span = self.peek().span;
+                    self.error(
+                        ParseErrorKind::ConflictingModifiers,
+                        span,
+                        "duplicate 'partial' modifier",
+                    );
+                }
+                self.advance();
+                modifiers.partial = true;
             } else {
                 break;
             }
         }
 
-        Ok(modifiers)
+        Ok((modifiers, renamed_from))
+    }
+
+    /// Check for the contextual `partial` keyword.
+    ///
+    /// `partial` is only a modifier when followed by `class` or another
+    /// modifier, so it remains usable as an identifier.
+    fn check_partial_modifier(&mut self) -> bool {
+        if !self.check_contextual("partial") {
+            return false;
+        }
+        let next = *self.peek_nth(1);
+        next.kind == TokenKind::Class
+            || (next.kind == TokenKind::Identifier
+                && matches!(
+                    next.lexeme,
+                    "shared" | "external" | "abstract" | "final" | "partial"
+                ))
     }
 
     /// Parse visibility modifier (private, protected, or default to public).
@@ -335,7 +409,7 @@ impl<'ast> Parser<'ast> {
     /// Parse a function or global variable declaration.
     ///
     /// This disambiguates between functions and global variables.
-    fn parse_function_or_global_var(
+    pub(super) fn parse_function_or_global_var(
         &mut self,
         modifiers: DeclModifiers,
         visibility: Visibility,
@@ -688,6 +762,87 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_partial_modifier() {
+        let arena = bumpalo::Bump::new();
+        let mut parser = Parser::new("shared partial class Player { }", &arena);
+        let item = parser.parse_item().unwrap();
+        match item {
+            Item::Class(class) => {
+                assert!(class.modifiers.partial);
+                assert!(class.modifiers.shared);
+            }
+            _ => panic!("Expected class"),
+        }
+        assert!(parser.errors.is_empty());
+    }
+
+    #[test]
+    fn parse_partial_as_identifier() {
+        let arena = bumpalo::Bump::new();
+        let mut parser = Parser::new("int partial = 0;", &arena);
+        assert!(matches!(parser.parse_item().unwrap(), Item::GlobalVar(_)));
+
+        let mut parser = Parser::new("partial p;", &arena);
+        assert!(matches!(parser.parse_item().unwrap(), Item::GlobalVar(_)));
+        assert!(parser.errors.is_empty());
+    }
+
+    #[test]
+    fn parse_metadata() {
+        let arena = bumpalo::Bump::new();
+        let mut parser = Parser::new(
+            "[inline, editor] [hot] int twice(int x) { return x * 2; }",
+            &arena,
+        );
+        match parser.parse_item().unwrap() {
+            Item::Function(function) => assert!(function.modifiers.inline),
+            _ => panic!("Expected function"),
+        }
+
+        let mut parser = Parser::new("class C { [inline] int get() { return 1; } }", &arena);
+        match parser.parse_item().unwrap() {
+            Item::Class(class) => match class.members[0] {
+                ClassMember::Method(method) => assert!(method.modifiers.inline),
+                _ => panic!("Expected method"),
+            },
+            _ => panic!("Expected class"),
+        }
+        assert!(parser.errors.is_empty());
+    }
+
+    #[test]
+    fn parse_renamed_from_metadata() {
+        let arena = bumpalo::Bump::new();
+        let mut parser = Parser::new(
+            "class Player { [renamedFrom(\"hp\"), editor(\"Health\")] int health; int armor; }",
+            &arena,
+        );
+        match parser.parse_item().unwrap() {
+            Item::Class(class) => {
+                let renamed: Vec<_> = class
+                    .members
+                    .iter()
+                    .map(|member| match member {
+                        ClassMember::Field(field) => field.renamed_from,
+                        _ => panic!("Expected field"),
+                    })
+                    .collect();
+                assert_eq!(renamed, [Some("hp"), None]);
+            }
+            _ => panic!("Expected class"),
+        }
+        assert!(parser.errors.is_empty());
+    }
+
+    #[test]
+    fn parse_partial_on_non_class() {
+        let arena = bumpalo::Bump::new();
+        let mut parser = Parser::new("partial shared interface IFoo { }", &arena);
+        let _ = parser.parse_item();
+        assert!(!parser.errors.is_empty());
+    }
+
     #[test]
     fn parse_private_visibility() {
         let arena = bumpalo::Bump::new();
@@ -2217,7 +2372,7 @@ impl<'ast> Parser<'ast> {
     fn parse_class_member(&mut self) -> Result<ClassMember<'ast>, ParseError> {
         // Parse visibility and modifiers
         let visibility = self.parse_visibility()?;
-        let modifiers = self.parse_modifiers()?;
+        let (modifiers, renamed_from) = self.parse_metadata_and_modifiers()?;
 
         // Check for funcdef
         if self.check(TokenKind::FuncDef) {
@@ -2361,6 +2516,7 @@ impl<'ast> Parser<'ast> {
                 ty,
                 name,
                 init,
+                renamed_from,
                 span: ty_start.merge(end_span),
             }))
         }
@@ -266,6 +266,8 @@ pub struct CastExpr<'ast> {
 /// A lambda (anonymous function).
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct LambdaExpr<'ast> {
+    /// Explicit capture list (`function[a, @b](...)`), if given
+    pub captures: Option<&'ast [LambdaCapture<'ast>]>,
     /// Parameters
     pub params: &'ast [LambdaParam<'ast>],
     /// Return type (if specified)
@@ -276,6 +278,17 @@ pub struct LambdaExpr<'ast> {
     pub span: Span,
 }
 
+/// A variable named in a lambda's capture list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LambdaCapture<'ast> {
+    /// Captured variable
+    pub name: Ident<'ast>,
+    /// Whether the variable is captured by handle (`@name`)
+    pub by_handle: bool,
+    /// Source location
+    pub span: Span,
+}
+
 /// A lambda parameter.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct LambdaParam<'ast> {
@@ -522,6 +535,7 @@ mod tests {
             span: Span::new(1, 10, 2),
         });
         let lambda = Expr::Lambda(arena.alloc(LambdaExpr {
+            captures: None,
             params: &[],
             return_type: None,
             body,
@@ -472,7 +472,7 @@ impl<'ast> Parser<'ast> {
         }))))
     }
 
-    /// Parse lambda expression: function(params) { body }
+    /// Parse lambda expression: function[captures](params) { body }
     fn parse_lambda(&mut self) -> Result<&'ast Expr<'ast>, ParseError> {
         let start_span = self
             .eat_contextual("function")
@@ -486,6 +486,12 @@ impl<'ast> Parser<'ast> {
             })?
             .span;
 
+        let captures = if self.check(TokenKind::LeftBracket) {
+            Some(self.parse_lambda_captures()?)
+        } else {
+            None
+        };
+
         self.expect(TokenKind::LeftParen)?;
 
         let mut params = bumpalo::collections::Vec::new_in(self.arena);
@@ -507,6 +513,7 @@ impl<'ast> Parser<'ast> {
         let end_span = body.span;
 
         Ok(self.arena.alloc(Expr::Lambda(self.arena.alloc(LambdaExpr {
+            captures,
             params: params.into_bump_slice(),
             return_type: None,
             body: self.arena.alloc(body),
@@ -514,6 +521,29 @@ impl<'ast> Parser<'ast> {
         }))))
     }
 
+    /// Parse a lambda capture list: `[a, @b]`. The list may be empty.
+    fn parse_lambda_captures(&mut self) -> Result<&'ast [LambdaCapture<'ast>], ParseError> {
+        self.expect(TokenKind::LeftBracket)?;
+        let mut captures = bumpalo::collections::Vec::new_in(self.arena);
+        if !self.check(TokenKind::RightBracket) {
+            loop {
+                let start_span = self.peek().span;
+                let by_handle = self.eat(TokenKind::At).is_some();
+                let token = self.expect(TokenKind::Identifier)?;
+                captures.push(LambdaCapture {
+                    name: Ident::new(token.lexeme, token.span),
+                    by_handle,
+                    span: start_span.merge(token.span),
+                });
+                if self.eat(TokenKind::Comma).is_none() {
+                    break;
+                }
+            }
+        }
+        self.expect(TokenKind::RightBracket)?;
+        Ok(captures.into_bump_slice())
+    }
+
     /// Parse a lambda parameter.
     fn parse_lambda_param(&mut self) -> Result<LambdaParam<'ast>, ParseError> {
         let start_span = self.peek().span;
@@ -1585,6 +1615,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_lambda_with_captures() {
+        let arena = bumpalo::Bump::new();
+        let mut parser = Parser::new("function[count, @target](int x) { }", &arena);
+        let expr = parser.parse_expr(0).unwrap();
+        let Expr::Lambda(lambda) = expr else {
+            panic!("Expected lambda expression");
+        };
+        let captures = lambda.captures.unwrap();
+        assert_eq!(captures.len(), 2);
+        assert_eq!(captures[0].name.name, "count");
+        assert!(!captures[0].by_handle);
+        assert_eq!(captures[1].name.name, "target");
+        assert!(captures[1].by_handle);
+        assert_eq!(lambda.params.len(), 1);
+
+        let mut parser = Parser::new("function[]() { }", &arena);
+        let Expr::Lambda(lambda) = parser.parse_expr(0).unwrap() else {
+            panic!("Expected lambda expression");
+        };
+        assert_eq!(lambda.captures, Some(&[][..]));
+
+        let mut parser = Parser::new("function[@](){ }", &arena);
+        assert!(parser.parse_expr(0).is_err());
+    }
+
     #[test]
     fn parse_lambda_with_return_type() {
         let arena = bumpalo::Bump::new();
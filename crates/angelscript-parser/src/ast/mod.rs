@@ -48,6 +48,9 @@ mod decl_parser;
 
 pub mod visitor;
 
+#[cfg(test)]
+mod conformance;
+
 // Re-export error types from core
 pub use angelscript_core::{ParseError, ParseErrorKind, ParseErrors};
 
@@ -967,6 +970,21 @@ mod tests {
         assert_eq!(sig.params.len(), 1);
     }
 
+    #[test]
+    fn parse_function_decl_variable_param() {
+        let arena = bumpalo::Bump::new();
+        let sig =
+            Parser::function_decl("void set(const string &in key, ?&in value)", &arena).unwrap();
+        assert_eq!(sig.params.len(), 2);
+        assert!(matches!(sig.params[1].ty.ty.base, TypeBase::Unknown));
+        assert_eq!(sig.params[1].ty.ref_kind, RefKind::RefIn);
+
+        let sig =
+            Parser::function_decl("bool get(const string &in key, ?&out value)", &arena).unwrap();
+        assert!(matches!(sig.params[1].ty.ty.base, TypeBase::Unknown));
+        assert_eq!(sig.params[1].ty.ref_kind, RefKind::RefOut);
+    }
+
     #[test]
     fn parse_function_decl_multiple_params() {
         let arena = bumpalo::Bump::new();
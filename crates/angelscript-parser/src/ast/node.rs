@@ -124,6 +124,10 @@ pub struct DeclModifiers {
     pub abstract_: bool,
     /// `final` - final class (cannot be inherited from)
     pub final_: bool,
+    /// `partial` - class declared in several parts, merged at registration
+    pub partial: bool,
+    /// `[inline]` metadata - hint to inline calls of the function
+    pub inline: bool,
 }
 
 impl DeclModifiers {
@@ -134,13 +138,21 @@ impl DeclModifiers {
 
     /// Check if any modifiers are set.
     pub fn is_empty(&self) -> bool {
-        !self.shared && !self.external && !self.abstract_ && !self.final_
+        !self.shared
+            && !self.external
+            && !self.abstract_
+            && !self.final_
+            && !self.partial
+            && !self.inline
     }
 }
 
 impl fmt::Display for DeclModifiers {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut parts = Vec::new();
+        if self.inline {
+            parts.push("[inline]");
+        }
         if self.shared {
             parts.push("shared");
         }
@@ -153,6 +165,9 @@ impl fmt::Display for DeclModifiers {
         if self.final_ {
             parts.push("final");
         }
+        if self.partial {
+            parts.push("partial");
+        }
         write!(f, "{}", parts.join(" "))
     }
 }
@@ -308,6 +323,13 @@ mod tests {
             ..Default::default()
         };
         assert!(!mods.is_empty());
+
+        let mods = DeclModifiers {
+            partial: true,
+            ..Default::default()
+        };
+        assert!(!mods.is_empty());
+        assert_eq!(mods.to_string(), "partial");
     }
 
     #[test]
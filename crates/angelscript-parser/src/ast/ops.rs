@@ -8,9 +8,15 @@ use std::fmt;
 
 /// Binary operators in AngelScript.
 ///
-/// Organized by precedence from lowest to highest.
+/// Organized by precedence from lowest to highest. As in upstream
+/// AngelScript, and unlike C, the bitwise operators bind tighter than the
+/// comparisons, so `flags & MASK == 0` is `(flags & MASK) == 0`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BinaryOp {
+    // Null coalescing (precedence 2)
+    /// `??`, only accepted in sections enabling the `null_coalesce` extension
+    NullCoalesce,
+
     // Logical OR (precedence 3)
     /// `||` or `or`
     LogicalOr,
@@ -19,19 +25,7 @@ pub enum BinaryOp {
     /// `&&` or `and`
     LogicalAnd,
 
-    // Bitwise OR (precedence 5)
-    /// `|`
-    BitwiseOr,
-
-    // Bitwise XOR (precedence 6)
-    /// `^`
-    BitwiseXor,
-
-    // Bitwise AND (precedence 7)
-    /// `&`
-    BitwiseAnd,
-
-    // Equality / logical XOR (precedence 8)
+    // Equality / logical XOR (precedence 5)
     /// `^^` or `xor`
     LogicalXor,
     /// `==`
@@ -43,7 +37,7 @@ pub enum BinaryOp {
     /// `!is`
     NotIs,
 
-    // Relational (precedence 9)
+    // Relational (precedence 6)
     /// `<`
     Less,
     /// `<=`
@@ -53,6 +47,18 @@ pub enum BinaryOp {
     /// `>=`
     GreaterEqual,
 
+    // Bitwise OR (precedence 7)
+    /// `|`
+    BitwiseOr,
+
+    // Bitwise XOR (precedence 8)
+    /// `^`
+    BitwiseXor,
+
+    // Bitwise AND (precedence 9)
+    /// `&`
+    BitwiseAnd,
+
     // Bitwise shift (precedence 10)
     /// `<<`
     ShiftLeft,
@@ -85,30 +91,35 @@ impl BinaryOp {
     ///
     /// Higher values bind more tightly. Returns (left_bp, right_bp).
     /// For left-associative operators: right_bp = left_bp + 1
-    /// For right-associative operators: right_bp = left_bp
+    /// For right-associative operators: right_bp = left_bp - 1
     pub fn binding_power(&self) -> (u8, u8) {
         use BinaryOp::*;
         match self {
+            // Precedence 2 - Null coalescing (right-associative). Both sides
+            // share a power so that the ternary, at 2, cannot appear in the
+            // right operand without parentheses.
+            NullCoalesce => (3, 3),
+
             // Precedence 3 - Logical OR (left-associative)
             LogicalOr => (3, 4),
 
             // Precedence 4 - Logical AND (left-associative)
             LogicalAnd => (5, 6),
 
-            // Precedence 5 - Bitwise OR (left-associative)
-            BitwiseOr => (7, 8),
+            // Precedence 5 - Equality / logical XOR (left-associative)
+            Equal | NotEqual | Is | NotIs | LogicalXor => (7, 8),
 
-            // Precedence 6 - Bitwise XOR (left-associative)
-            BitwiseXor => (9, 10),
+            // Precedence 6 - Relational (left-associative)
+            Less | LessEqual | Greater | GreaterEqual => (9, 10),
 
-            // Precedence 7 - Bitwise AND (left-associative)
-            BitwiseAnd => (11, 12),
+            // Precedence 7 - Bitwise OR (left-associative)
+            BitwiseOr => (11, 12),
 
-            // Precedence 8 - Equality / logical XOR (left-associative)
-            Equal | NotEqual | Is | NotIs | LogicalXor => (13, 14),
+            // Precedence 8 - Bitwise XOR (left-associative)
+            BitwiseXor => (13, 14),
 
-            // Precedence 9 - Relational (left-associative)
-            Less | LessEqual | Greater | GreaterEqual => (15, 16),
+            // Precedence 9 - Bitwise AND (left-associative)
+            BitwiseAnd => (15, 16),
 
             // Precedence 10 - Bitwise shift (left-associative)
             ShiftLeft | ShiftRight | ShiftRightUnsigned => (17, 18),
@@ -129,6 +140,7 @@ impl BinaryOp {
         use TokenKind::*;
 
         Some(match token {
+            QuestionQuestion => BinaryOp::NullCoalesce,
             PipePipe | Or => BinaryOp::LogicalOr,
             CaretCaret | Xor => BinaryOp::LogicalXor,
             AmpAmp | And => BinaryOp::LogicalAnd,
@@ -170,6 +182,7 @@ impl fmt::Display for BinaryOp {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         use BinaryOp::*;
         let s = match self {
+            NullCoalesce => "??",
             LogicalOr => "||",
             LogicalXor => "^^",
             LogicalAnd => "&&",
@@ -458,6 +471,10 @@ mod tests {
     #[test]
     fn all_binary_ops_from_token() {
         // Test all BinaryOp variants can be created from tokens
+        assert_eq!(
+            BinaryOp::from_token(TokenKind::QuestionQuestion),
+            Some(BinaryOp::NullCoalesce)
+        );
         assert_eq!(
             BinaryOp::from_token(TokenKind::PipePipe),
             Some(BinaryOp::LogicalOr)
@@ -551,6 +568,7 @@ mod tests {
 
     #[test]
     fn all_binary_ops_display() {
+        assert_eq!(format!("{}", BinaryOp::NullCoalesce), "??");
         assert_eq!(format!("{}", BinaryOp::LogicalOr), "||");
         assert_eq!(format!("{}", BinaryOp::LogicalXor), "^^");
         assert_eq!(format!("{}", BinaryOp::LogicalAnd), "&&");
@@ -579,6 +597,7 @@ mod tests {
     fn all_binary_ops_binding_power() {
         // Test that all operators have valid binding powers
         let ops = vec![
+            BinaryOp::NullCoalesce,
             BinaryOp::LogicalOr,
             BinaryOp::LogicalXor,
             BinaryOp::LogicalAnd,
@@ -340,7 +340,7 @@ impl<'ast> Parser<'ast> {
     /// Try to skip past a type expression and check if it's followed by an identifier.
     ///
     /// This is a lookahead helper for variable declaration detection.
-    fn try_skip_type(&mut self) -> bool {
+    pub(super) fn try_skip_type(&mut self) -> bool {
         // Skip optional const
         self.eat(TokenKind::Const);
 
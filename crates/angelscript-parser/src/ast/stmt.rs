@@ -7,9 +7,11 @@
 //! - Loops (while, do-while, for, foreach)
 //! - Jump statements (return, break, continue)
 //! - Exception handling (try-catch)
+//! - Local function declarations
 //! - Blocks
 
 use crate::ast::Ident;
+use crate::ast::decl::FunctionDecl;
 use crate::ast::expr::Expr;
 use crate::ast::types::TypeExpr;
 use angelscript_core::Span;
@@ -43,6 +45,9 @@ pub enum Stmt<'ast> {
     Switch(&'ast SwitchStmt<'ast>),
     /// Try-catch statement
     TryCatch(&'ast TryCatchStmt<'ast>),
+    /// Function declared inside a function body, visible to the rest of the
+    /// enclosing block
+    Function(&'ast FunctionDecl<'ast>),
 }
 
 impl<'ast> Stmt<'ast> {
@@ -62,6 +67,7 @@ impl<'ast> Stmt<'ast> {
             Self::Foreach(s) => s.span,
             Self::Switch(s) => s.span,
             Self::TryCatch(s) => s.span,
+            Self::Function(s) => s.span,
         }
     }
 }
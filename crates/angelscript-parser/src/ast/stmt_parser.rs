@@ -6,7 +6,7 @@
 use super::parser::Parser;
 use crate::ast::expr::Expr;
 use crate::ast::stmt::*;
-use crate::ast::{Ident, ParseError, ParseErrorKind};
+use crate::ast::{DeclModifiers, Ident, Item, ParseError, ParseErrorKind, Visibility};
 use crate::lexer::TokenKind;
 use bumpalo::collections::Vec as BVec;
 
@@ -41,6 +41,9 @@ impl<'ast> Parser<'ast> {
             // Check for foreach (contextual keyword)
             _ if self.check_contextual("foreach") => self.parse_foreach(),
 
+            // Local function declaration
+            _ if self.is_local_function() => self.parse_local_function(),
+
             // Variable declaration or expression statement
             _ => {
                 // Try to determine if this is a variable declaration or expression
@@ -102,6 +105,57 @@ impl<'ast> Parser<'ast> {
         }))
     }
 
+    /// Check if the next tokens declare a local function.
+    ///
+    /// A declaration with arguments like `Foo f(1, 2);` is a variable; a
+    /// function has a body after its parameter list.
+    pub fn is_local_function(&mut self) -> bool {
+        if !self.is_type_start() {
+            return false;
+        }
+        let saved_pos = self.position;
+        let is_function = self.try_skip_type() && {
+            self.advance();
+            self.try_skip_parens() && {
+                self.eat(TokenKind::Const);
+                self.check(TokenKind::LeftBrace)
+            }
+        };
+        self.position = saved_pos;
+        is_function
+    }
+
+    /// Skip a parenthesized list, returning false if it is not closed.
+    fn try_skip_parens(&mut self) -> bool {
+        if self.eat(TokenKind::LeftParen).is_none() {
+            return false;
+        }
+        let mut depth = 1;
+        while depth > 0 {
+            match self.advance().kind {
+                TokenKind::LeftParen => depth += 1,
+                TokenKind::RightParen => depth -= 1,
+                TokenKind::Eof => return false,
+                _ => {}
+            }
+        }
+        true
+    }
+
+    /// Parse a function declared inside a function body.
+    ///
+    /// Grammar: `TYPE IDENTIFIER PARAMLIST 'const'? FUNCATTR BLOCK`
+    pub fn parse_local_function(&mut self) -> Result<Stmt<'ast>, ParseError> {
+        match self.parse_function_or_global_var(DeclModifiers::new(), Visibility::Public)? {
+            Item::Function(func) => Ok(Stmt::Function(self.arena.alloc(func))),
+            other => Err(ParseError::new(
+                ParseErrorKind::InternalError,
+                other.span(),
+                "expected a local function declaration",
+            )),
+        }
+    }
+
     /// Parse a variable declarator (name with optional initializer).
     fn parse_var_declarator(
         &mut self,
@@ -833,6 +887,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_local_function() {
+        let arena = bumpalo::Bump::new();
+        let mut parser = Parser::new(
+            "{ int twice(int v) { return v * 2; } Point p(twice(1), (2)); }",
+            &arena,
+        );
+        let block = parser.parse_block().unwrap();
+        match block.stmts {
+            [Stmt::Function(func), Stmt::VarDecl(_)] => {
+                assert_eq!(func.name.name, "twice");
+                assert_eq!(func.params.len(), 1);
+                assert!(func.body.is_some());
+            }
+            other => panic!("Expected local function and variable, got {other:?}"),
+        }
+    }
+
     #[test]
     fn parse_var_decl_multiple_mixed() {
         let arena = bumpalo::Bump::new();
@@ -542,6 +542,7 @@ pub fn walk_stmt<'src, 'ast, V: Visitor<'ast>>(visitor: &mut V, stmt: &Stmt<'ast
         Stmt::Foreach(s) => visitor.visit_foreach_stmt(s),
         Stmt::Switch(s) => visitor.visit_switch_stmt(s),
         Stmt::TryCatch(s) => visitor.visit_try_catch_stmt(s),
+        Stmt::Function(s) => visitor.visit_function_decl(s),
     }
 }
 
@@ -0,0 +1,594 @@
+//! Source-level directives.
+//!
+//! Directives are instructions embedded in source text that are not part of
+//! the AngelScript grammar. The lexer skips them; this module extracts them
+//! with a separate scan over the source so that later stages can honor them.
+//!
+//! Supported forms:
+//!
+//! - `#pragma <name>(<args>)` - recorded as a [`Pragma`]
+//! - `#pragma warning(disable: W0123, W0456)` - suppress warnings from this
+//!   line onwards
+//! - `#pragma warning(enable: W0123)` - end a previous `disable`
+//! - `// as-ignore[W0123]` - suppress warnings on a single line. A trailing
+//!   comment applies to its own line; a comment on a line by itself applies
+//!   to the next line.
+//! - `#pragma strict` - opt the section into stricter conversion rules
+//! - `#pragma extension(null_coalesce)` - enable experimental syntax
+//!
+//! `strict` and `extension` affect the whole section and must appear before
+//! any code. A `#pragma` must start its line; the lexer reports one that
+//! follows code on the same line as an error.
+//!
+//! # Example
+//!
+//! ```
+//! use angelscript_parser::directives::Directives;
+//!
+//! let source = "int x = 1; // as-ignore[W0001]\nint y = 2;";
+//! let directives = Directives::scan(source);
+//!
+//! assert!(directives.suppressions().is_suppressed("W0001", 1));
+//! assert!(!directives.suppressions().is_suppressed("W0001", 2));
+//! ```
+
+use angelscript_core::{ParseError, ParseErrorKind, Span};
+
+/// Marker introducing a line-scoped suppression comment.
+const IGNORE_MARKER: &str = "as-ignore[";
+
+/// A `#pragma` directive found in source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pragma {
+    /// The pragma name (e.g. `warning`).
+    pub name: String,
+    /// The raw argument text, without surrounding parentheses.
+    pub args: String,
+    /// Location of the directive.
+    pub span: Span,
+}
+
+/// A region of lines in which a warning is suppressed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SuppressedRegion {
+    code: String,
+    /// First suppressed line (inclusive).
+    start: u32,
+    /// Last suppressed line (inclusive), or `u32::MAX` if never re-enabled.
+    end: u32,
+}
+
+/// Warning suppressions declared in a source file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Suppressions {
+    /// Regions from `#pragma warning(disable: ...)`.
+    regions: Vec<SuppressedRegion>,
+    /// Single lines from `// as-ignore[...]`, as `(line, code)`.
+    lines: Vec<(u32, String)>,
+}
+
+impl Suppressions {
+    /// Check whether a warning code is suppressed on the given line.
+    pub fn is_suppressed(&self, code: &str, line: u32) -> bool {
+        self.lines.iter().any(|(l, c)| *l == line && c == code)
+            || self
+                .regions
+                .iter()
+                .any(|r| r.code == code && (r.start..=r.end).contains(&line))
+    }
+
+    /// Check if no suppressions were declared.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty() && self.lines.is_empty()
+    }
+
+    fn disable(&mut self, code: &str, line: u32) {
+        let open = self
+            .regions
+            .iter()
+            .any(|r| r.code == code && r.end == u32::MAX);
+        if !open {
+            self.regions.push(SuppressedRegion {
+                code: code.to_string(),
+                start: line,
+                end: u32::MAX,
+            });
+        }
+    }
+
+    fn enable(&mut self, code: &str, line: u32) {
+        for region in &mut self.regions {
+            if region.code == code && region.end == u32::MAX {
+                region.end = line;
+            }
+        }
+    }
+}
+
+/// An experimental language extension enabled with `#pragma extension(...)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Extension {
+    /// The `??` null-coalescing operator.
+    NullCoalesce,
+}
+
+impl Extension {
+    /// All known extensions.
+    pub const ALL: [Extension; 1] = [Extension::NullCoalesce];
+
+    /// The name used in `#pragma extension(...)`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Extension::NullCoalesce => "null_coalesce",
+        }
+    }
+
+    /// Look up an extension by its pragma name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|e| e.name() == name)
+    }
+}
+
+/// Per-section language options selected by pragmas.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SectionOptions {
+    /// Whether `#pragma strict` is in effect.
+    ///
+    /// Strict sections report implicit narrowing of constants as an error
+    /// rather than a warning.
+    pub strict: bool,
+    /// Extensions enabled with `#pragma extension(...)`, in declaration order.
+    pub extensions: Vec<Extension>,
+}
+
+impl SectionOptions {
+    /// Check if an extension is enabled.
+    pub fn has_extension(&self, extension: Extension) -> bool {
+        self.extensions.contains(&extension)
+    }
+}
+
+/// All directives found in a source file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Directives {
+    pragmas: Vec<Pragma>,
+    suppressions: Suppressions,
+    options: SectionOptions,
+    errors: Vec<ParseError>,
+}
+
+impl Directives {
+    /// Scan source text for directives.
+    pub fn scan(source: &str) -> Self {
+        let mut directives = Self::default();
+        // String or comment still open from a previous line
+        let mut open = Continuation::None;
+        // Line-scoped suppressions waiting for the next line of code
+        let mut pending: Vec<String> = Vec::new();
+        // Whether any code has been seen (section-wide pragmas must precede it)
+        let mut seen_code = false;
+
+        for (index, text) in source.lines().enumerate() {
+            let line = index as u32 + 1;
+            let trimmed = text.trim_start();
+
+            if open == Continuation::None && trimmed.starts_with("#pragma") {
+                let col = (text.len() - trimmed.len()) as u32 + 1;
+                let span = Span::new(line, col, trimmed.trim_end().len() as u32);
+                directives.add_pragma(&trimmed["#pragma".len()..], span, seen_code);
+                continue;
+            }
+
+            let (code_before, comment) = split_line_comment(text, &mut open);
+            seen_code |= !code_before.trim().is_empty();
+
+            if !pending.is_empty() && !code_before.trim().is_empty() {
+                for code in pending.drain(..) {
+                    directives.suppressions.lines.push((line, code));
+                }
+            }
+
+            let Some(comment) = comment else { continue };
+            let Some(start) = comment.find(IGNORE_MARKER) else {
+                continue;
+            };
+            let rest = &comment[start + IGNORE_MARKER.len()..];
+            let Some(end) = rest.find(']') else {
+                let col = (text.len() - comment.len() + start) as u32 + 1;
+                directives.error(
+                    Span::new(line, col, comment.len() as u32),
+                    "unterminated as-ignore comment, expected ']'",
+                );
+                continue;
+            };
+
+            let col = (text.len() - comment.len() + start) as u32 + 1;
+            let Some(codes) = directives.parse_codes(&rest[..end], Span::point(line, col)) else {
+                continue;
+            };
+
+            if code_before.trim().is_empty() {
+                pending.extend(codes);
+            } else {
+                for code in codes {
+                    directives.suppressions.lines.push((line, code));
+                }
+            }
+        }
+
+        directives
+    }
+
+    /// All `#pragma` directives, in source order.
+    pub fn pragmas(&self) -> &[Pragma] {
+        &self.pragmas
+    }
+
+    /// Warning suppressions declared in the source.
+    pub fn suppressions(&self) -> &Suppressions {
+        &self.suppressions
+    }
+
+    /// Language options selected by `#pragma strict` and `#pragma extension`.
+    pub fn options(&self) -> &SectionOptions {
+        &self.options
+    }
+
+    /// Errors for malformed directives.
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// Take the errors for malformed directives, leaving an empty vec.
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    fn add_pragma(&mut self, body: &str, span: Span, seen_code: bool) {
+        let body = body.trim();
+        let name_len = body
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(body.len());
+        let (name, rest) = body.split_at(name_len);
+
+        if name.is_empty() {
+            self.error(span, "expected pragma name after '#pragma'");
+            return;
+        }
+
+        let rest = rest.trim();
+        let args = match rest.strip_prefix('(') {
+            Some(inner) => match inner.strip_suffix(')') {
+                Some(args) => args.trim(),
+                None => {
+                    self.error(span, format!("expected ')' to close '#pragma {}'", name));
+                    return;
+                }
+            },
+            None => rest,
+        };
+
+        match name {
+            "warning" => self.apply_warning_pragma(args, span),
+            "strict" | "extension" if seen_code => self.error(
+                span,
+                format!(
+                    "'#pragma {}' must appear before any code in the section",
+                    name
+                ),
+            ),
+            "strict" => self.options.strict = true,
+            "extension" => self.apply_extension_pragma(args, span),
+            _ => {}
+        }
+
+        self.pragmas.push(Pragma {
+            name: name.to_string(),
+            args: args.to_string(),
+            span,
+        });
+    }
+
+    fn apply_warning_pragma(&mut self, args: &str, span: Span) {
+        let Some((action, codes)) = args.split_once(':') else {
+            self.error(
+                span,
+                "expected '#pragma warning(disable: <codes>)' or '#pragma warning(enable: <codes>)'",
+            );
+            return;
+        };
+
+        let Some(codes) = self.parse_codes(codes, span) else {
+            return;
+        };
+
+        match action.trim() {
+            "disable" => codes
+                .iter()
+                .for_each(|c| self.suppressions.disable(c, span.line)),
+            "enable" => codes
+                .iter()
+                .for_each(|c| self.suppressions.enable(c, span.line)),
+            other => self.error(
+                span,
+                format!(
+                    "unknown warning action '{}', expected 'disable' or 'enable'",
+                    other
+                ),
+            ),
+        }
+    }
+
+    fn apply_extension_pragma(&mut self, args: &str, span: Span) {
+        for name in args.split(',').map(str::trim) {
+            match Extension::from_name(name) {
+                Some(ext) if !self.options.has_extension(ext) => self.options.extensions.push(ext),
+                Some(_) => {}
+                None => self.error(span, format!("unknown language extension '{}'", name)),
+            }
+        }
+    }
+
+    /// Parse a comma-separated list of warning codes like `W0123, W0456`.
+    fn parse_codes(&mut self, list: &str, span: Span) -> Option<Vec<String>> {
+        let mut codes = Vec::new();
+        for code in list.split(',').map(str::trim) {
+            if !is_warning_code(code) {
+                self.error(
+                    span,
+                    format!("invalid warning code '{}', expected e.g. 'W0123'", code),
+                );
+                return None;
+            }
+            codes.push(code.to_string());
+        }
+        Some(codes)
+    }
+
+    fn error(&mut self, span: Span, message: impl Into<String>) {
+        self.errors.push(ParseError::new(
+            ParseErrorKind::InvalidSyntax,
+            span,
+            message,
+        ));
+    }
+}
+
+/// Check if text is a warning code: `W` followed by one or more digits.
+fn is_warning_code(code: &str) -> bool {
+    code.strip_prefix('W')
+        .is_some_and(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// A construct left open at the end of a line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Continuation {
+    #[default]
+    None,
+    BlockComment,
+    /// A string literal opened with the given quote byte.
+    String(u8),
+    Heredoc,
+}
+
+/// Split a line into the code before a `//` comment and the comment body.
+///
+/// Skips string literals, heredocs and block comments, tracking whether one
+/// of them continues onto the next line.
+fn split_line_comment<'a>(text: &'a str, open: &mut Continuation) -> (String, Option<&'a str>) {
+    let bytes = text.as_bytes();
+    let mut code = String::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        let next = bytes.get(i + 1).copied();
+
+        match *open {
+            Continuation::BlockComment => {
+                if b == b'*' && next == Some(b'/') {
+                    *open = Continuation::None;
+                    i += 2;
+                } else {
+                    i += 1;
+                }
+                continue;
+            }
+            Continuation::Heredoc => {
+                if text[i..].starts_with("\"\"\"") {
+                    *open = Continuation::None;
+                    i += 3;
+                } else {
+                    i += 1;
+                }
+                continue;
+            }
+            Continuation::String(q) => {
+                if b == b'\\' {
+                    i += 2;
+                    continue;
+                }
+                if b == q {
+                    *open = Continuation::None;
+                }
+                i += 1;
+                continue;
+            }
+            Continuation::None => {}
+        }
+
+        match (b, next) {
+            (b'/', Some(b'/')) => return (code, Some(&text[i + 2..])),
+            (b'/', Some(b'*')) => {
+                *open = Continuation::BlockComment;
+                i += 2;
+            }
+            (b'"', _) if text[i..].starts_with("\"\"\"") => {
+                *open = Continuation::Heredoc;
+                code.push('"');
+                i += 3;
+            }
+            (b'"' | b'\'', _) => {
+                *open = Continuation::String(b);
+                code.push(b as char);
+                i += 1;
+            }
+            _ => {
+                // Only the emptiness of the code matters, so non-ASCII
+                // bytes can be recorded as placeholders.
+                code.push(if b.is_ascii() { b as char } else { '?' });
+                i += 1;
+            }
+        }
+    }
+
+    // Only double-quoted strings and heredocs may span lines
+    if *open == Continuation::String(b'\'') {
+        *open = Continuation::None;
+    }
+    (code, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pragma_disable_and_enable() {
+        let source = "\
+int a;
+#pragma warning(disable: W0123, W0456)
+int b;
+#pragma warning(enable: W0123)
+int c;";
+        let directives = Directives::scan(source);
+        let s = directives.suppressions();
+
+        assert!(directives.errors().is_empty());
+        assert!(!s.is_suppressed("W0123", 1));
+        assert!(s.is_suppressed("W0123", 3));
+        assert!(!s.is_suppressed("W0123", 5));
+        assert!(s.is_suppressed("W0456", 5));
+        assert!(!s.is_suppressed("W0789", 3));
+    }
+
+    #[test]
+    fn pragma_inside_multiline_string_is_ignored() {
+        let source =
+            "string a = \"\"\"\n#pragma strict\n\"\"\";\nstring b = \"x\n#pragma strict\n\";";
+        let directives = Directives::scan(source);
+        assert!(directives.pragmas().is_empty());
+        assert!(!directives.options().strict);
+    }
+
+    #[test]
+    fn trailing_ignore_comment() {
+        let directives = Directives::scan("int x; // as-ignore[W0001]\nint y;");
+        let s = directives.suppressions();
+        assert!(s.is_suppressed("W0001", 1));
+        assert!(!s.is_suppressed("W0001", 2));
+    }
+
+    #[test]
+    fn own_line_ignore_comment_applies_to_next_code_line() {
+        let directives = Directives::scan("// as-ignore[W0001, W0002]\n\n// note\nint y;\nint z;");
+        let s = directives.suppressions();
+        assert!(s.is_suppressed("W0001", 4));
+        assert!(s.is_suppressed("W0002", 4));
+        assert!(!s.is_suppressed("W0001", 5));
+    }
+
+    #[test]
+    fn ignore_marker_inside_string_is_not_a_comment() {
+        let directives = Directives::scan("string s = \"// as-ignore[W0001]\";");
+        assert!(directives.suppressions().is_empty());
+    }
+
+    #[test]
+    fn ignore_marker_inside_block_comment() {
+        let directives = Directives::scan("/* start\n// as-ignore[W0001]\n*/ int x;");
+        assert!(directives.suppressions().is_empty());
+    }
+
+    #[test]
+    fn records_other_pragmas() {
+        let directives = Directives::scan("  #pragma once\n#pragma foo(bar, baz)");
+        let pragmas = directives.pragmas();
+
+        assert_eq!(pragmas.len(), 2);
+        assert_eq!(pragmas[0].name, "once");
+        assert_eq!(pragmas[0].args, "");
+        assert_eq!(pragmas[0].span, Span::new(1, 3, 12));
+        assert_eq!(pragmas[1].name, "foo");
+        assert_eq!(pragmas[1].args, "bar, baz");
+    }
+
+    #[test]
+    fn malformed_directives_report_errors() {
+        let directives = Directives::scan(
+            "#pragma warning(silence: W0001)\n\
+             #pragma warning(disable: nope)\n\
+             #pragma warning disable\n\
+             #pragma warning(disable: W0001\n\
+             #pragma\n\
+             int x; // as-ignore[W0001",
+        );
+        let lines: Vec<u32> = directives.errors().iter().map(|e| e.span.line).collect();
+        assert_eq!(lines, vec![1, 2, 3, 4, 5, 6]);
+        assert!(directives.suppressions().is_empty());
+    }
+
+    #[test]
+    fn section_options() {
+        let directives = Directives::scan(
+            "// header comment\n#pragma strict\n#pragma extension(null_coalesce)\nint x;",
+        );
+        let options = directives.options();
+
+        assert!(directives.errors().is_empty());
+        assert!(options.strict);
+        assert!(options.has_extension(Extension::NullCoalesce));
+    }
+
+    #[test]
+    fn section_options_default() {
+        let options = Directives::scan("int x;").options().clone();
+        assert_eq!(options, SectionOptions::default());
+        assert!(!options.strict);
+    }
+
+    #[test]
+    fn section_pragmas_must_precede_code() {
+        let directives =
+            Directives::scan("int x;\n#pragma strict\n#pragma extension(null_coalesce)");
+        assert_eq!(directives.errors().len(), 2);
+        assert!(!directives.options().strict);
+        assert!(directives.options().extensions.is_empty());
+    }
+
+    #[test]
+    fn unknown_extension() {
+        let directives = Directives::scan("#pragma extension(null_coalesce, teleport)");
+        assert_eq!(directives.errors().len(), 1);
+        assert_eq!(
+            directives.options().extensions,
+            vec![Extension::NullCoalesce]
+        );
+    }
+
+    #[test]
+    fn extension_names_round_trip() {
+        for ext in Extension::ALL {
+            assert_eq!(Extension::from_name(ext.name()), Some(ext));
+        }
+    }
+
+    #[test]
+    fn warning_codes() {
+        assert!(is_warning_code("W0123"));
+        assert!(is_warning_code("W1"));
+        assert!(!is_warning_code("W"));
+        assert!(!is_warning_code("E0123"));
+        assert!(!is_warning_code("W01a"));
+    }
+}
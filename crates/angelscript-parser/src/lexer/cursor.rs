@@ -111,11 +111,8 @@ impl<'src> Cursor<'src> {
         // Fast path: ASCII character (most common case)
         if first_byte < 128 {
             let ch = first_byte as char;
-            self.rest = unsafe {
-                // SAFETY: We know first_byte < 128, so it's valid UTF-8
-                // and we're advancing by exactly 1 byte
-                std::str::from_utf8_unchecked(&bytes[1..])
-            };
+            // An ASCII byte is a full character, so index 1 is a char boundary
+            self.rest = &self.rest[1..];
             self.offset += 1;
 
             if ch == '\n' {
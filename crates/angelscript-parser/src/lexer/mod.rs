@@ -91,6 +91,11 @@ impl<'src, 'ast> Lexer<'src, 'ast> {
             // Comments or slash operator
             '/' => self.scan_slash(start_line, start_col, start_offset),
 
+            // Pragma directives are handled by `directives::Directives`
+            '#' if self.cursor.check_str("#pragma") => {
+                self.skip_directive(start_line, start_col, start_offset)
+            }
+
             // String literals
             '"' => self.scan_string('"', start_line, start_col, start_offset),
             '\'' => self.scan_string('\'', start_line, start_col, start_offset),
@@ -203,6 +208,42 @@ impl<'src, 'ast> Lexer<'src, 'ast> {
         }
     }
 
+    /// Skip a `#pragma` line, then scan the next token.
+    ///
+    /// `directives::Directives` only honours pragmas that start a line, so
+    /// one preceded by code on the same line is reported as an error rather
+    /// than silently ignored.
+    fn skip_directive(
+        &mut self,
+        start_line: u32,
+        start_col: u32,
+        start_offset: u32,
+    ) -> Token<'ast> {
+        let source = self.cursor.source();
+        let line_start = source[..start_offset as usize]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let at_line_start = source[line_start..start_offset as usize]
+            .chars()
+            .all(|c| c.is_ascii_whitespace() || c == '\u{FEFF}');
+
+        while let Some(c) = self.cursor.peek() {
+            if c == '\n' {
+                break;
+            }
+            self.cursor.advance();
+        }
+
+        if at_line_start {
+            return self.scan_token();
+        }
+        let len = self.cursor.offset() - start_offset;
+        let error = LexError::MisplacedDirective {
+            span: Span::new(start_line, start_col, len),
+        };
+        self.make_error(error)
+    }
+
     /// Scan a block comment `/* ... */`.
     fn scan_block_comment(
         &mut self,
@@ -491,6 +532,10 @@ impl<'src, 'ast> Lexer<'src, 'ast> {
             (';', _) => TokenKind::Semicolon,
             (',', _) => TokenKind::Comma,
             ('~', _) => TokenKind::Tilde,
+            ('?', Some('?')) => {
+                self.cursor.advance();
+                TokenKind::QuestionQuestion
+            }
             ('?', _) => TokenKind::Question,
             ('@', _) => TokenKind::At,
             ('.', _) => TokenKind::Dot,
@@ -938,6 +983,52 @@ string
         assert!(lexer.has_errors());
     }
 
+    #[test]
+    fn pragma_line_skipped() {
+        assert_eq!(
+            tokenize("a\n#pragma warning(disable: W0001)\nb"),
+            vec![
+                (TokenKind::Identifier, "a".to_string()),
+                (TokenKind::Identifier, "b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn indented_pragma_skipped() {
+        assert_eq!(
+            tokenize("a\n    #pragma strict\nb"),
+            vec![
+                (TokenKind::Identifier, "a".to_string()),
+                (TokenKind::Identifier, "b".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn mid_line_pragma_is_error() {
+        let arena = Bump::new();
+        let mut lexer = Lexer::new("int x; #pragma strict\nb", &arena);
+        let _ = lexer.next_token(); // 'int'
+        let _ = lexer.next_token(); // 'x'
+        let _ = lexer.next_token(); // ';'
+        let token = lexer.next_token();
+        assert_eq!(token.kind, TokenKind::Error);
+        assert!(matches!(
+            lexer.take_errors().as_slice(),
+            [LexError::MisplacedDirective { span }] if span.col == 8
+        ));
+        // The rest of the directive line is skipped
+        assert_eq!(lexer.next_token().lexeme, "b");
+    }
+
+    #[test]
+    fn other_hash_is_error() {
+        let arena = Bump::new();
+        let mut lexer = Lexer::new("#include \"a.as\"", &arena);
+        assert_eq!(lexer.next_token().kind, TokenKind::Error);
+    }
+
     // =========================================
     // Operators
     // =========================================
@@ -1064,12 +1155,13 @@ string
     #[test]
     fn other_operators() {
         assert_eq!(
-            token_kinds(". : :: ? @ ~"),
+            token_kinds(". : :: ? ?? @ ~"),
             vec![
                 TokenKind::Dot,
                 TokenKind::Colon,
                 TokenKind::ColonColon,
                 TokenKind::Question,
+                TokenKind::QuestionQuestion,
                 TokenKind::At,
                 TokenKind::Tilde,
             ]
@@ -298,6 +298,8 @@ pub enum TokenKind {
     // =========================================
     /// `?`
     Question,
+    /// `??`
+    QuestionQuestion,
     /// `:`
     Colon,
     /// `::`
@@ -455,6 +457,7 @@ impl TokenKind {
                 | PlusPlus
                 | MinusMinus
                 | Question
+                | QuestionQuestion
                 | Colon
                 | ColonColon
                 | Dot
@@ -587,6 +590,7 @@ impl TokenKind {
             PlusPlus => "'++'",
             MinusMinus => "'--'",
             Question => "'?'",
+            QuestionQuestion => "'??'",
             Colon => "':'",
             ColonColon => "'::'",
             Dot => "'.'",
@@ -10,6 +10,7 @@
 //! - Parser for transforming tokens into AST
 //! - Error types and reporting
 //! - Visitor pattern for AST traversal
+//! - Source directives such as `#pragma` and warning suppressions
 //!
 //! # Example
 //!
@@ -37,6 +38,9 @@
 // Lexer module
 pub mod lexer;
 
+// Source directives (pragmas, suppression comments)
+pub mod directives;
+
 // AST module
 pub mod ast;
 
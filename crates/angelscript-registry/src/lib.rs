@@ -44,7 +44,7 @@ mod module;
 mod registry;
 
 pub use module::{HasClassMeta, HasFunctionMeta, IntoFunctionMeta, Module};
-pub use registry::SymbolRegistry;
+pub use registry::{SymbolRegistry, canonical_template_arg, template_instance_hash};
 
 // Re-export from core for backwards compatibility during transition
 pub use angelscript_core::{
@@ -244,6 +244,25 @@ pub trait HasFunctionMeta {
 pub trait IntoFunctionMeta {
     /// Convert to function metadata.
     fn into_fn_meta(self) -> FunctionMeta;
+
+    /// Mark the function pure: free of side effects, with a result that
+    /// depends only on its arguments (and object, for methods).
+    ///
+    /// The compiler then evaluates calls with constant arguments at compile
+    /// time and reuses the result of repeated calls, so marking a function
+    /// that is not pure changes program behavior.
+    ///
+    /// ```ignore
+    /// let module = Module::new().function(sqrt.pure());
+    /// ```
+    fn pure(self) -> FunctionMeta
+    where
+        Self: Sized,
+    {
+        let mut meta = self.into_fn_meta();
+        meta.is_pure = true;
+        meta
+    }
 }
 
 impl<T: HasFunctionMeta> IntoFunctionMeta for T {
@@ -318,6 +337,7 @@ mod tests {
             behavior: None,
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -325,10 +345,14 @@ mod tests {
         };
 
         let mut module = Module::new();
-        module.functions.push(meta);
+        module.functions.push(meta.clone());
         assert_eq!(module.len(), 1);
         assert_eq!(module.functions.len(), 1);
         assert_eq!(module.functions[0].name, "greet");
+        assert!(!module.functions[0].is_pure);
+
+        let module = Module::new().function(meta.pure());
+        assert!(module.functions[0].is_pure);
     }
 
     #[test]
@@ -399,6 +423,7 @@ mod tests {
             behavior: None,
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -438,6 +463,7 @@ mod tests {
             behavior: None,
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -47,9 +47,9 @@
 use rustc_hash::{FxHashMap, FxHashSet};
 
 use angelscript_core::{
-    ClassEntry, EnumEntry, FuncdefEntry, FunctionEntry, GlobalPropertyEntry, InterfaceEntry,
-    PrimitiveEntry, PrimitiveKind, PropertyEntry, RegistrationError, TemplateParamEntry, TypeEntry,
-    TypeHash,
+    ClassEntry, DataType, EnumEntry, FuncdefEntry, FunctionDef, FunctionEntry, GlobalPropertyEntry,
+    ITableMap, InterfaceEntry, MethodSignature, PrimitiveEntry, PrimitiveKind, PropertyEntry,
+    RegistrationError, TemplateParamEntry, TypeEntry, TypeHash, VTable,
 };
 
 /// Unified type and function registry.
@@ -247,16 +247,7 @@ impl SymbolRegistry {
 
         // If this is a method, add to the owning class's vtable
         if let Some(owner_hash) = entry.def.object_type {
-            // Compute signature hash from params (including modifiers)
-            let param_sig_hashes: Vec<u64> = entry
-                .def
-                .params
-                .iter()
-                .map(|p| p.data_type.signature_hash())
-                .collect();
-            let sig_hash =
-                TypeHash::from_signature(&entry.def.name, &param_sig_hashes, entry.def.is_const())
-                    .0;
+            let sig_hash = method_signature_hash(&entry.def);
 
             // Add to class vtable if the class exists
             if let Some(class) = self.get_class_mut(owner_hash) {
@@ -434,6 +425,177 @@ impl SymbolRegistry {
         properties
     }
 
+    /// Build the flattened vtable of a class.
+    ///
+    /// The base class's vtable comes first, so a method keeps its slot in
+    /// every derived class. Methods of the class itself then either override
+    /// the inherited slot with the same signature or are appended.
+    ///
+    /// Returns `None` if the type doesn't exist or is not a class.
+    pub fn build_vtable(&self, class_hash: TypeHash) -> Option<VTable> {
+        let class = self.get(class_hash)?.as_class()?;
+
+        let mut vtable = class
+            .base_class
+            .filter(|&base| base != class_hash)
+            .and_then(|base| self.build_vtable(base))
+            .unwrap_or_default();
+
+        // Only the class's own methods: once completed, its vtable also
+        // holds inherited ones, which the base vtable already placed.
+        for &method_hash in &class.vtable.slots {
+            if let Some(func) = self.functions.get(&method_hash)
+                && func.def.object_type == Some(class_hash)
+            {
+                vtable.add_method(
+                    &func.def.name,
+                    method_signature_hash(&func.def),
+                    method_hash,
+                );
+            }
+        }
+
+        Some(vtable)
+    }
+
+    /// Flatten inherited methods into the vtable of every class.
+    ///
+    /// Call once all types and methods are registered. Afterwards a method
+    /// call can be bound to a slot index at compile time instead of being
+    /// looked up by name on every call. Completing again is harmless.
+    pub fn complete_vtables(&mut self) {
+        let vtables: Vec<(TypeHash, VTable)> = self
+            .types
+            .iter()
+            .filter(|(_, entry)| entry.is_class())
+            .filter_map(|(&hash, _)| self.build_vtable(hash).map(|vtable| (hash, vtable)))
+            .collect();
+
+        for (hash, vtable) in vtables {
+            if let Some(class) = self.get_class_mut(hash) {
+                class.vtable = vtable;
+            }
+        }
+    }
+
+    /// Get the methods of an interface in itable slot order.
+    ///
+    /// Methods of base interfaces come first, so an interface handle
+    /// upcast to a base interface uses the same slots. A signature declared
+    /// by several bases gets a single slot.
+    pub fn interface_methods(&self, interface: TypeHash) -> Vec<&MethodSignature> {
+        let mut methods = Vec::new();
+        let mut visited = FxHashSet::default();
+        let mut signatures = FxHashSet::default();
+        self.collect_interface_methods(interface, &mut visited, &mut signatures, &mut methods);
+        methods
+    }
+
+    fn collect_interface_methods<'a>(
+        &'a self,
+        interface: TypeHash,
+        visited: &mut FxHashSet<TypeHash>,
+        signatures: &mut FxHashSet<u64>,
+        methods: &mut Vec<&'a MethodSignature>,
+    ) {
+        if !visited.insert(interface) {
+            return;
+        }
+        let Some(entry) = self.get(interface).and_then(|e| e.as_interface()) else {
+            return;
+        };
+        for &base in &entry.base_interfaces {
+            self.collect_interface_methods(base, visited, signatures, methods);
+        }
+        for method in &entry.methods {
+            if signatures.insert(method.signature_hash()) {
+                methods.push(method);
+            }
+        }
+    }
+
+    /// Get the itable slot of an interface method by its signature hash.
+    pub fn interface_slot(&self, interface: TypeHash, sig_hash: u64) -> Option<u16> {
+        self.interface_methods(interface)
+            .iter()
+            .position(|method| method.signature_hash() == sig_hash)
+            .map(|slot| slot as u16)
+    }
+
+    /// Get every interface a class implements: its own, those of its base
+    /// classes, and their base interfaces.
+    pub fn all_interfaces(&self, class_hash: TypeHash) -> Vec<TypeHash> {
+        let mut pending: Vec<TypeHash> = Vec::new();
+        if let Some(class) = self.get(class_hash).and_then(|e| e.as_class()) {
+            pending.extend(class.interfaces.iter().rev());
+        }
+        for base in self.base_class_chain(class_hash) {
+            pending.extend(base.interfaces.iter().rev());
+        }
+
+        let mut interfaces = Vec::new();
+        while let Some(interface) = pending.pop() {
+            if interfaces.contains(&interface) {
+                continue;
+            }
+            interfaces.push(interface);
+            if let Some(entry) = self.get(interface).and_then(|e| e.as_interface()) {
+                pending.extend(entry.base_interfaces.iter().rev());
+            }
+        }
+        interfaces
+    }
+
+    /// Build the itable of a class for one interface: the class's
+    /// implementation of each interface method, in slot order.
+    ///
+    /// Uses the class's vtable, so call after [`complete_vtables`].
+    /// Returns `None` if the class does not implement every method.
+    ///
+    /// [`complete_vtables`]: SymbolRegistry::complete_vtables
+    pub fn build_itable(&self, class_hash: TypeHash, interface: TypeHash) -> Option<Vec<TypeHash>> {
+        let class = self.get(class_hash)?.as_class()?;
+        self.interface_methods(interface)
+            .iter()
+            .map(|method| {
+                class
+                    .vtable_slot(method.signature_hash())
+                    .and_then(|slot| class.vtable_method(slot))
+            })
+            .collect()
+    }
+
+    /// Build the itables of every class for all interfaces it implements.
+    ///
+    /// Call after [`complete_vtables`]. Afterwards a call through an
+    /// interface handle is dispatched with [`ClassEntry::itable_method`]
+    /// using a slot from [`interface_slot`] instead of searching by name.
+    /// Interfaces a class leaves partly unimplemented get no itable.
+    ///
+    /// [`complete_vtables`]: SymbolRegistry::complete_vtables
+    /// [`interface_slot`]: SymbolRegistry::interface_slot
+    pub fn complete_itables(&mut self) {
+        let itables: Vec<(TypeHash, ITableMap)> = self
+            .types
+            .iter()
+            .filter(|(_, entry)| entry.is_class())
+            .map(|(&hash, _)| {
+                let itables = self
+                    .all_interfaces(hash)
+                    .into_iter()
+                    .filter_map(|iface| self.build_itable(hash, iface).map(|t| (iface, t)))
+                    .collect();
+                (hash, itables)
+            })
+            .collect();
+
+        for (hash, itables) in itables {
+            if let Some(class) = self.get_class_mut(hash) {
+                class.itables = itables;
+            }
+        }
+    }
+
     // ==========================================================================
     // Namespace Helpers
     // ==========================================================================
@@ -589,6 +751,141 @@ impl SymbolRegistry {
         errors
     }
 
+    // ==========================================================================
+    // Template Instances
+    // ==========================================================================
+
+    /// Instantiate `template` with `args`, returning the instance's hash.
+    ///
+    /// The hash depends only on the template and the canonical form of the
+    /// arguments, never on which unit instantiates first or in what order,
+    /// so `array<Foo@>` has the same hash in every unit and in serialized
+    /// data. An existing instance is returned as is, so each instance has a
+    /// single entry.
+    ///
+    /// Arguments are canonicalized by [`canonical_template_arg`]: only the
+    /// type, constness and handle modifiers take part.
+    pub fn instantiate_template(
+        &mut self,
+        template: TypeHash,
+        args: &[DataType],
+    ) -> Result<TypeHash, RegistrationError> {
+        self.instantiate(None, template, args, &|_| None)
+    }
+
+    /// Instantiate a template of `definitions` with `args` in this
+    /// registry, returning the instance's hash.
+    ///
+    /// Used to keep the instances a unit creates apart from the shared
+    /// registry. Types are looked up in this registry, then in
+    /// `definitions`; arguments neither knows, such as script classes, are
+    /// named by `script_name`. The hash is the one
+    /// [`instantiate_template`](Self::instantiate_template) gives.
+    pub fn instantiate_template_from(
+        &mut self,
+        definitions: &SymbolRegistry,
+        template: TypeHash,
+        args: &[DataType],
+        script_name: &dyn Fn(TypeHash) -> Option<String>,
+    ) -> Result<TypeHash, RegistrationError> {
+        self.instantiate(Some(definitions), template, args, script_name)
+    }
+
+    fn instantiate(
+        &mut self,
+        definitions: Option<&SymbolRegistry>,
+        template: TypeHash,
+        args: &[DataType],
+        script_name: &dyn Fn(TypeHash) -> Option<String>,
+    ) -> Result<TypeHash, RegistrationError> {
+        let args: Vec<DataType> = args.iter().map(canonical_template_arg).collect();
+        let hash = template_instance_hash(template, &args);
+        if self.types.contains_key(&hash) {
+            return Ok(hash);
+        }
+
+        let lookup = |hash: TypeHash| {
+            self.get(hash)
+                .or_else(|| definitions.and_then(|d| d.get(hash)))
+        };
+        let definition = lookup(template)
+            .and_then(TypeEntry::as_class)
+            .filter(|class| class.is_template())
+            .ok_or_else(|| RegistrationError::TypeNotFound(format!("template {:?}", template)))?;
+        if definition.template_params.len() != args.len() {
+            return Err(RegistrationError::InvalidType(format!(
+                "{} expects {} type arguments, got {}",
+                definition.qualified_name,
+                definition.template_params.len(),
+                args.len()
+            )));
+        }
+
+        let names = args
+            .iter()
+            .map(|arg| {
+                let name = lookup(arg.type_hash)
+                    .map(|entry| entry.qualified_name().to_string())
+                    .or_else(|| script_name(arg.type_hash))
+                    .ok_or_else(|| {
+                        RegistrationError::TypeNotFound(format!("{:?}", arg.type_hash))
+                    })?;
+                Ok(template_arg_name(name, arg))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let name = format!("{}<{}>", definition.name, names.join(", "));
+        let qualified_name = if definition.namespace.is_empty() {
+            name.clone()
+        } else {
+            format!("{}::{}", definition.namespace.join("::"), name)
+        };
+
+        let instance = ClassEntry::new(
+            name,
+            definition.namespace.clone(),
+            qualified_name,
+            hash,
+            definition.type_kind.clone(),
+            definition.source.clone(),
+        )
+        .with_template_instance(template, args);
+        self.register_type(instance.into())?;
+        Ok(hash)
+    }
+
+    /// Get the instance of `template` with `args`, if instantiated.
+    pub fn template_instance(&self, template: TypeHash, args: &[DataType]) -> Option<TypeHash> {
+        let args: Vec<DataType> = args.iter().map(canonical_template_arg).collect();
+        let hash = template_instance_hash(template, &args);
+        self.types.contains_key(&hash).then_some(hash)
+    }
+
+    /// Add the template instances of `other` missing from this registry.
+    ///
+    /// Used when linking units: instances created by several units share
+    /// their hash, so each ends up with a single entry. Returns how many
+    /// instances were added.
+    pub fn link_template_instances(
+        &mut self,
+        other: &SymbolRegistry,
+    ) -> Result<usize, RegistrationError> {
+        let mut instances: Vec<&ClassEntry> = other
+            .classes()
+            .filter(|class| class.is_template_instance())
+            .collect();
+        // Deterministic order, whatever order `other` created them in
+        instances.sort_by(|a, b| a.qualified_name.cmp(&b.qualified_name));
+
+        let mut added = 0;
+        for instance in instances {
+            if !self.types.contains_key(&instance.type_hash) {
+                self.register_type(instance.clone().into())?;
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
     // ==========================================================================
     // Type Aliases (typedef)
     // ==========================================================================
@@ -664,6 +961,47 @@ impl SymbolRegistry {
     }
 }
 
+/// The form of a template argument that identifies an instance.
+///
+/// Reference modifiers and the flags set during type resolution do not
+/// change which instance is meant, so they are cleared.
+pub fn canonical_template_arg(arg: &DataType) -> DataType {
+    DataType {
+        type_hash: arg.type_hash,
+        is_const: arg.is_const,
+        is_handle: arg.is_handle,
+        is_handle_to_const: arg.is_handle && arg.is_handle_to_const,
+        ..DataType::simple(arg.type_hash)
+    }
+}
+
+/// Name of a template argument as written in source, e.g. `const Foo@`,
+/// given the qualified name of its type.
+fn template_arg_name(mut type_name: String, arg: &DataType) -> String {
+    if arg.is_handle_to_const || (arg.is_const && !arg.is_handle) {
+        type_name.insert_str(0, "const ");
+    }
+    if arg.is_handle {
+        type_name.push('@');
+        if arg.is_const {
+            type_name.push_str(" const");
+        }
+    }
+    type_name
+}
+
+/// Hash of the instance of `template` with canonical `args`.
+///
+/// Plain arguments hash as their type, so `array<int>` matches
+/// [`TypeHash::from_template_instance`] with `int`.
+pub fn template_instance_hash(template: TypeHash, args: &[DataType]) -> TypeHash {
+    let args: Vec<TypeHash> = args
+        .iter()
+        .map(|arg| TypeHash(arg.signature_hash()))
+        .collect();
+    TypeHash::from_template_instance(template, &args)
+}
+
 impl std::fmt::Debug for SymbolRegistry {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("SymbolRegistry")
@@ -676,11 +1014,25 @@ impl std::fmt::Debug for SymbolRegistry {
     }
 }
 
+/// Signature hash a method is matched by in vtables.
+///
+/// Covers name, parameter types (with modifiers) and constness but not the
+/// owner, so an override hashes the same as the method it overrides.
+fn method_signature_hash(def: &FunctionDef) -> u64 {
+    let param_sig_hashes: Vec<u64> = def
+        .params
+        .iter()
+        .map(|p| p.data_type.signature_hash())
+        .collect();
+    TypeHash::from_signature(&def.name, &param_sig_hashes, def.is_const()).0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use angelscript_core::{
-        DataType, FunctionDef, FunctionTraits, Param, TypeKind, Visibility, primitives,
+        FunctionDef, FunctionTraits, MethodSignature, Param, Span, TypeKind, TypeSource, UnitId,
+        Visibility, primitives,
     };
 
     #[test]
@@ -1315,6 +1667,162 @@ mod tests {
         )));
     }
 
+    /// A registry with the `array<T>` and `dictionary<K, V>` templates and a
+    /// script class `game::Foo`, as each unit of a context sees them.
+    fn template_registry() -> SymbolRegistry {
+        let mut registry = SymbolRegistry::with_primitives();
+        let mut array = ClassEntry::ffi("array", TypeKind::reference());
+        array.template_params = vec![TypeHash::from_name("array::T")];
+        registry.register_type(array.into()).unwrap();
+        let mut dict = ClassEntry::ffi("dictionary", TypeKind::reference());
+        dict.template_params = vec![
+            TypeHash::from_name("dictionary::K"),
+            TypeHash::from_name("dictionary::V"),
+        ];
+        registry.register_type(dict.into()).unwrap();
+        let foo = ClassEntry::script(
+            "Foo",
+            vec!["game".into()],
+            "game::Foo",
+            TypeSource::script(UnitId::new(1), Span::default()),
+        );
+        registry.register_type(foo.into()).unwrap();
+        registry
+    }
+
+    #[test]
+    fn template_instances_are_deterministic_across_units() {
+        let array = TypeHash::from_name("array");
+        let dict = TypeHash::from_name("dictionary");
+        let foo = TypeHash::from_name("game::Foo");
+        let foo_handle = DataType::with_handle(foo, false);
+        let pair = [DataType::simple(primitives::INT32), foo_handle];
+
+        // Two units instantiating in different orders
+        let mut first = template_registry();
+        let a = first.instantiate_template(array, &[foo_handle]).unwrap();
+        let b = first.instantiate_template(dict, &pair).unwrap();
+        let mut second = template_registry();
+        assert_eq!(second.instantiate_template(dict, &pair).unwrap(), b);
+        assert_eq!(
+            second.instantiate_template(array, &[foo_handle]).unwrap(),
+            a
+        );
+
+        // Plain arguments keep the documented hash
+        let ints = first
+            .instantiate_template(array, &[DataType::simple(primitives::INT32)])
+            .unwrap();
+        assert_eq!(
+            ints,
+            TypeHash::from_template_instance(array, &[primitives::INT32])
+        );
+
+        // Handles and values are different instances; reference modifiers
+        // are not part of the argument
+        let values = first
+            .instantiate_template(array, &[DataType::simple(foo)])
+            .unwrap();
+        assert_ne!(values, a);
+        assert_eq!(
+            first.template_instance(array, &[DataType::with_ref_in(foo)]),
+            Some(values)
+        );
+
+        let entry = first.get(a).unwrap().as_class().unwrap();
+        assert_eq!(entry.qualified_name, "array<game::Foo@>");
+        assert_eq!(entry.template, Some(array));
+        assert_eq!(entry.type_args, [foo_handle]);
+    }
+
+    #[test]
+    fn linking_keeps_a_single_instance_entry() {
+        let array = TypeHash::from_name("array");
+        let foo = DataType::with_handle(TypeHash::from_name("game::Foo"), true);
+
+        let mut first = template_registry();
+        let shared = first.instantiate_template(array, &[foo]).unwrap();
+        let mut second = template_registry();
+        second.instantiate_template(array, &[foo]).unwrap();
+        let ints = second
+            .instantiate_template(array, &[DataType::simple(primitives::INT32)])
+            .unwrap();
+
+        let before = first.type_count();
+        assert_eq!(first.link_template_instances(&second).unwrap(), 1);
+        assert_eq!(first.type_count(), before + 1);
+        assert_eq!(
+            first.get(shared).unwrap().qualified_name(),
+            "array<const game::Foo@>"
+        );
+        assert!(first.get(ints).is_some());
+        assert_eq!(first.instantiate_template(array, &[foo]).unwrap(), shared);
+        assert_eq!(first.type_count(), before + 1);
+    }
+
+    #[test]
+    fn instances_kept_apart_from_definitions() {
+        let mut definitions = SymbolRegistry::with_primitives();
+        let mut array = ClassEntry::ffi("array", TypeKind::reference());
+        array.template_params = vec![TypeHash::from_name("array::T")];
+        definitions.register_type(array.into()).unwrap();
+        let array = TypeHash::from_name("array");
+        let foo = DataType::with_handle(TypeHash::from_name("game::Foo"), false);
+        let script_name = |hash: TypeHash| {
+            (hash == TypeHash::from_name("game::Foo")).then(|| "game::Foo".to_string())
+        };
+
+        let mut instances = SymbolRegistry::new();
+        let hash = instances
+            .instantiate_template_from(&definitions, array, &[foo], &script_name)
+            .unwrap();
+        let nested = instances
+            .instantiate_template_from(&definitions, array, &[DataType::simple(hash)], &script_name)
+            .unwrap();
+
+        assert_eq!(
+            hash,
+            template_registry()
+                .instantiate_template(array, &[foo])
+                .unwrap()
+        );
+        assert!(definitions.template_instance(array, &[foo]).is_none());
+        assert_eq!(
+            instances.get(nested).unwrap().qualified_name(),
+            "array<array<game::Foo@>>"
+        );
+        assert!(matches!(
+            instances.instantiate_template_from(
+                &definitions,
+                array,
+                &[DataType::simple(TypeHash::from_name("Missing"))],
+                &script_name,
+            ),
+            Err(RegistrationError::TypeNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn instantiating_checks_the_template() {
+        let mut registry = template_registry();
+        let int = DataType::simple(primitives::INT32);
+        assert!(matches!(
+            registry.instantiate_template(TypeHash::from_name("game::Foo"), &[int]),
+            Err(RegistrationError::TypeNotFound(_))
+        ));
+        assert!(matches!(
+            registry.instantiate_template(TypeHash::from_name("dictionary"), &[int]),
+            Err(RegistrationError::InvalidType(_))
+        ));
+        assert!(matches!(
+            registry.instantiate_template(
+                TypeHash::from_name("array"),
+                &[DataType::simple(TypeHash::from_name("Missing"))]
+            ),
+            Err(RegistrationError::TypeNotFound(_))
+        ));
+    }
+
     #[test]
     fn validate_skips_templates() {
         use angelscript_core::TypeHash;
@@ -1518,6 +2026,155 @@ mod tests {
         assert_eq!(class.vtable.slots_for_name("foo").len(), 2);
     }
 
+    fn register_method(registry: &mut SymbolRegistry, owner: TypeHash, name: &str) -> TypeHash {
+        let hash = TypeHash::from_method(owner, name, &[]);
+        let def = FunctionDef::new(
+            hash,
+            name.to_string(),
+            vec![],
+            vec![],
+            DataType::void(),
+            Some(owner),
+            FunctionTraits::default(),
+            true,
+            Visibility::Public,
+        );
+        registry.register_function(FunctionEntry::ffi(def)).unwrap();
+        hash
+    }
+
+    #[test]
+    fn complete_vtables_flattens_inherited_methods() {
+        let mut registry = SymbolRegistry::with_primitives();
+
+        let base = ClassEntry::ffi("Base", TypeKind::reference());
+        let base_hash = base.type_hash;
+        let derived = ClassEntry::ffi("Derived", TypeKind::reference()).with_base(base_hash);
+        let derived_hash = derived.type_hash;
+        registry.register_type(base.into()).unwrap();
+        registry.register_type(derived.into()).unwrap();
+
+        let base_update = register_method(&mut registry, base_hash, "update");
+        let base_render = register_method(&mut registry, base_hash, "render");
+        let derived_render = register_method(&mut registry, derived_hash, "render");
+        let derived_jump = register_method(&mut registry, derived_hash, "jump");
+
+        registry.complete_vtables();
+        // Completing twice must not duplicate slots
+        registry.complete_vtables();
+
+        let base = registry.get(base_hash).unwrap().as_class().unwrap();
+        assert_eq!(base.vtable.slots, vec![base_update, base_render]);
+
+        let derived = registry.get(derived_hash).unwrap().as_class().unwrap();
+        // Inherited slots keep their index; the override replaces its slot
+        assert_eq!(
+            derived.vtable.slots,
+            vec![base_update, derived_render, derived_jump]
+        );
+        assert_eq!(derived.vtable.slots_for_name("render"), &[1]);
+        assert_eq!(derived.vtable.slots_for_name("jump"), &[2]);
+    }
+
+    #[test]
+    fn build_vtable_follows_whole_chain() {
+        let mut registry = SymbolRegistry::with_primitives();
+
+        let a = ClassEntry::ffi("A", TypeKind::reference());
+        let a_hash = a.type_hash;
+        let b = ClassEntry::ffi("B", TypeKind::reference()).with_base(a_hash);
+        let b_hash = b.type_hash;
+        let c = ClassEntry::ffi("C", TypeKind::reference()).with_base(b_hash);
+        let c_hash = c.type_hash;
+        registry.register_type(a.into()).unwrap();
+        registry.register_type(b.into()).unwrap();
+        registry.register_type(c.into()).unwrap();
+
+        let a_tick = register_method(&mut registry, a_hash, "tick");
+        let c_tick = register_method(&mut registry, c_hash, "tick");
+
+        assert_eq!(registry.build_vtable(b_hash).unwrap().slots, vec![a_tick]);
+        assert_eq!(registry.build_vtable(c_hash).unwrap().slots, vec![c_tick]);
+        assert!(registry.build_vtable(primitives::INT32).is_none());
+    }
+
+    #[test]
+    fn complete_itables_maps_interface_slots() {
+        let mut registry = SymbolRegistry::with_primitives();
+
+        let named = InterfaceEntry::ffi("INamed").with_method(MethodSignature::new(
+            "name",
+            vec![],
+            DataType::void(),
+        ));
+        let named_hash = named.type_hash;
+        let widget = InterfaceEntry::ffi("IWidget")
+            .with_base(named_hash)
+            .with_method(MethodSignature::new("draw", vec![], DataType::void()))
+            .with_method(MethodSignature::new("name", vec![], DataType::void()));
+        let widget_hash = widget.type_hash;
+        registry.register_type(named.into()).unwrap();
+        registry.register_type(widget.into()).unwrap();
+
+        let base = ClassEntry::ffi("Control", TypeKind::reference());
+        let base_hash = base.type_hash;
+        let button = ClassEntry::ffi("Button", TypeKind::reference())
+            .with_base(base_hash)
+            .with_interface(widget_hash);
+        let button_hash = button.type_hash;
+        registry.register_type(base.into()).unwrap();
+        registry.register_type(button.into()).unwrap();
+
+        let control_name = register_method(&mut registry, base_hash, "name");
+        let button_draw = register_method(&mut registry, button_hash, "draw");
+
+        registry.complete_vtables();
+        registry.complete_itables();
+
+        // Base interface methods first, shared signatures once
+        let names: Vec<&str> = registry
+            .interface_methods(widget_hash)
+            .iter()
+            .map(|m| m.name.as_str())
+            .collect();
+        assert_eq!(names, ["name", "draw"]);
+        assert_eq!(
+            registry.all_interfaces(button_hash),
+            vec![widget_hash, named_hash]
+        );
+
+        let draw_sig = MethodSignature::new("draw", vec![], DataType::void()).signature_hash();
+        let slot = registry.interface_slot(widget_hash, draw_sig).unwrap();
+        let button = registry.get(button_hash).unwrap().as_class().unwrap();
+        assert_eq!(button.itable_method(widget_hash, slot), Some(button_draw));
+        // Inherited implementation, dispatched through the base interface
+        assert_eq!(button.itable(named_hash), Some(&vec![control_name]));
+        assert_eq!(button.itable_method(widget_hash, 0), Some(control_name));
+    }
+
+    #[test]
+    fn build_itable_requires_every_method() {
+        let mut registry = SymbolRegistry::with_primitives();
+
+        let iface = InterfaceEntry::ffi("IRunnable")
+            .with_method(MethodSignature::new("run", vec![], DataType::void()))
+            .with_method(MethodSignature::new("stop", vec![], DataType::void()));
+        let iface_hash = iface.type_hash;
+        registry.register_type(iface.into()).unwrap();
+
+        let class = ClassEntry::ffi("Job", TypeKind::reference()).with_interface(iface_hash);
+        let class_hash = class.type_hash;
+        registry.register_type(class.into()).unwrap();
+        register_method(&mut registry, class_hash, "run");
+
+        registry.complete_vtables();
+        registry.complete_itables();
+
+        assert!(registry.build_itable(class_hash, iface_hash).is_none());
+        let class = registry.get(class_hash).unwrap().as_class().unwrap();
+        assert!(class.itable(iface_hash).is_none());
+    }
+
     #[test]
     fn register_function_global_function_no_vtable() {
         let mut registry = SymbolRegistry::with_primitives();
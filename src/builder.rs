@@ -0,0 +1,287 @@
+//! Builder for [`Context`]s.
+//!
+//! Setting up a context by hand takes a sequence of calls whose order
+//! matters: the standard library before modules using its types, and a
+//! string factory whose type is registered. [`ContextBuilder`] collects the
+//! setup and applies it in the right order:
+//!
+//! ```ignore
+//! let ctx = Context::builder()
+//!     .with_default_modules()
+//!     .install(game_module())
+//!     .strictness(Strictness::Strict)
+//!     .build()?;
+//! ```
+
+use std::sync::Arc;
+
+use angelscript_compiler::{CompilerPlugin, ConstValue, IntegerOverflow};
+use angelscript_core::{OutOfMemory, StringFactory};
+use angelscript_modules::resource::ResourceResolver;
+use angelscript_registry::Module;
+
+use crate::context::{Context, ContextError, Strictness};
+use crate::diagnostic::Diagnostic;
+use crate::resource::ResourcePaths;
+use crate::validate::CallValidators;
+
+/// Collects the setup of a [`Context`], created with [`Context::builder`].
+#[must_use = "a builder does nothing until built"]
+pub struct ContextBuilder {
+    context: Context,
+    default_modules: bool,
+    modules: Vec<Module>,
+    string_factory: Option<Box<dyn StringFactory>>,
+    validators: CallValidators,
+}
+
+impl ContextBuilder {
+    pub(crate) fn new() -> Self {
+        Self {
+            context: Context::new(),
+            default_modules: false,
+            modules: Vec::new(),
+            string_factory: None,
+            validators: CallValidators::new(),
+        }
+    }
+
+    /// Install the standard library: `string`, `array`, `dictionary`, `blob`,
+    /// the math, easing, path and url functions and the std module, with
+    /// string literals created by the `string` type.
+    ///
+    /// The standard library is installed before any other module, so they
+    /// can use its types.
+    pub fn with_default_modules(mut self) -> Self {
+        self.default_modules = true;
+        self
+    }
+
+    /// Install a module, after the standard library and the modules
+    /// installed before it.
+    pub fn install(mut self, module: Module) -> Self {
+        self.modules.push(module);
+        self
+    }
+
+    /// Create string literals with `factory`, in place of the standard
+    /// library's factory. Its string type must be registered by one of the
+    /// installed modules.
+    pub fn with_string_factory(mut self, factory: Box<dyn StringFactory>) -> Self {
+        self.string_factory = Some(factory);
+        self
+    }
+
+    /// Install the resource module, with the literal paths passed to `load`
+    /// resolved by `resolver` while compiling.
+    ///
+    /// Use [`plugin`](Self::plugin) with an `Arc<ResourcePaths>` instead to
+    /// read the resolved ids.
+    pub fn with_resource_resolver(self, resolver: impl ResourceResolver + 'static) -> Self {
+        self.install(angelscript_modules::resource::module())
+            .plugin(Box::new(ResourcePaths::new(resolver)))
+    }
+
+    /// Check the arguments of calls to `function` while compiling; an error
+    /// returned by `validator` fails the build. See [`CallValidators`].
+    pub fn validate_call<F>(mut self, function: impl Into<String>, validator: F) -> Self
+    where
+        F: Fn(&[Option<ConstValue>]) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.validators.add(function, validator);
+        self
+    }
+
+    /// Set how strictly the units of the context are compiled.
+    pub fn strictness(mut self, strictness: Strictness) -> Self {
+        self.context.set_strictness(strictness);
+        self
+    }
+
+    /// Set whether integer overflow wraps or raises an exception.
+    pub fn integer_overflow(mut self, overflow: IntegerOverflow) -> Self {
+        self.context.set_integer_overflow(overflow);
+        self
+    }
+
+    /// Set whether failed script allocations raise an exception or abort.
+    pub fn out_of_memory(mut self, policy: OutOfMemory) -> Self {
+        self.context.set_out_of_memory(policy);
+        self
+    }
+
+    /// Limit the bytes each unit may allocate on behalf of scripts.
+    pub fn memory_limit(mut self, bytes: usize) -> Self {
+        self.context.set_memory_limit(Some(bytes));
+        self
+    }
+
+    /// Add a compiler plugin run for every unit.
+    pub fn plugin(mut self, plugin: Box<dyn CompilerPlugin>) -> Self {
+        self.context.add_plugin(plugin);
+        self
+    }
+
+    /// Set the handler receiving the diagnostics of every unit.
+    pub fn diagnostic_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&Diagnostic) + Send + Sync + 'static,
+    {
+        self.context.set_diagnostic_handler(handler);
+        self
+    }
+
+    /// Install the modules and create the context.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a module fails to install, or the string
+    /// factory's type is not registered.
+    pub fn build(self) -> Result<Context, ContextError> {
+        let mut context = self.context;
+        let mut string_factory = self.string_factory;
+        if self.default_modules {
+            context.install(angelscript_modules::string::module())?;
+            context.install(angelscript_modules::stringbuilder::module())?;
+            context.install(angelscript_modules::array::module())?;
+            context.install(angelscript_modules::dictionary::module())?;
+            context.install(angelscript_modules::blob::module())?;
+            context.install(angelscript_modules::math::module())?;
+            context.install(angelscript_modules::math::ease::module())?;
+            context.install(angelscript_modules::path::module())?;
+            context.install(angelscript_modules::url::module())?;
+            context.install(angelscript_modules::std::module())?;
+            string_factory
+                .get_or_insert_with(|| Box::new(angelscript_modules::string::ScriptStringFactory));
+        }
+        for module in self.modules {
+            context.install(module)?;
+        }
+        if !self.validators.is_empty() {
+            context.add_plugin(Box::new(self.validators));
+        }
+
+        if let Some(factory) = string_factory {
+            let type_hash = factory.type_hash();
+            if context.registry().get(type_hash).is_none() {
+                return Err(ContextError::UnregisteredStringType(type_hash));
+            }
+            context.set_string_factory(factory);
+        }
+        Ok(context)
+    }
+
+    /// Build the context, ready to create units from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if [`build`](Self::build) does.
+    pub fn build_shared(self) -> Result<Arc<Context>, ContextError> {
+        self.build().map(Arc::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BuildError;
+    use angelscript_core::{CompilationError, TypeHash};
+
+    struct Utf16Factory;
+
+    impl StringFactory for Utf16Factory {
+        fn create(&self, data: &[u8]) -> Box<dyn std::any::Any + Send + Sync> {
+            Box::new(
+                String::from_utf8_lossy(data)
+                    .encode_utf16()
+                    .collect::<Vec<u16>>(),
+            )
+        }
+
+        fn type_hash(&self) -> TypeHash {
+            TypeHash::from_name("wstring")
+        }
+    }
+
+    #[test]
+    fn default_modules_wire_the_string_factory() {
+        let ctx = Context::builder().with_default_modules().build().unwrap();
+        let string = ctx.string_factory().unwrap().type_hash();
+        assert!(ctx.registry().get(string).is_some());
+        assert!(ctx.registry().get_by_name("dictionary").is_some());
+        assert_eq!(ctx.strictness(), Strictness::Standard);
+    }
+
+    #[test]
+    fn rejects_unregistered_string_types() {
+        let result = Context::builder()
+            .with_default_modules()
+            .with_string_factory(Box::new(Utf16Factory))
+            .build();
+        assert!(matches!(
+            result,
+            Err(ContextError::UnregisteredStringType(hash)) if hash == TypeHash::from_name("wstring")
+        ));
+    }
+
+    #[test]
+    fn strict_contexts_deny_warnings() {
+        let source = "void f() { int unused = 1; }";
+        let build = |strictness| {
+            let ctx = Context::builder()
+                .strictness(strictness)
+                .build_shared()
+                .unwrap();
+            let mut unit = ctx.create_unit().unwrap();
+            unit.add_source("test.as", source).unwrap();
+            (unit.build(), unit.warnings().len())
+        };
+        assert!(matches!(build(Strictness::Standard), (Ok(()), 1)));
+        let (result, warnings) = build(Strictness::Strict);
+        assert_eq!(warnings, 0);
+        assert!(matches!(
+            result,
+            Err(BuildError::CompilationErrors(errors))
+                if matches!(&errors[..], [CompilationError::DeniedWarning { code: "W0001", .. }])
+        ));
+    }
+
+    #[test]
+    fn resource_resolver_checks_literal_paths() {
+        let ctx = Context::builder()
+            .with_default_modules()
+            .with_resource_resolver(|path: &str| match path {
+                "ui/icon.png" => Ok(crate::ResId::new(3)),
+                _ => Err("no such asset".to_string()),
+            })
+            .build_shared()
+            .unwrap();
+        let build = |source| {
+            let mut unit = ctx.create_unit().unwrap();
+            unit.add_source("test.as", source).unwrap();
+            unit.build()
+        };
+        assert!(build(r#"ResId icon = load("ui/icon.png");"#).is_ok());
+        assert!(build(r#"ResId icon = load("ui/missing.png");"#).is_err());
+    }
+
+    #[test]
+    fn validate_call_checks_constant_arguments() {
+        let ctx = Context::builder()
+            .validate_call("format", |args| match args.first() {
+                Some(Some(ConstValue::String(pattern))) if pattern.contains(&b'%') => {
+                    Err("use {} placeholders".to_string())
+                }
+                _ => Ok(()),
+            })
+            .build_shared()
+            .unwrap();
+        let build = |source| {
+            let mut unit = ctx.create_unit().unwrap();
+            unit.add_source("test.as", source).unwrap();
+            unit.build()
+        };
+        assert!(build(r#"void f(string s) { format("{}", 1); format(s, 2); }"#).is_ok());
+        assert!(build(r#"void f() { format("%d", 1); }"#).is_err());
+    }
+}
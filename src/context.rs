@@ -3,16 +3,29 @@
 //! The Context owns a [`SymbolRegistry`] that stores all registered types and functions.
 //! Users install modules into the context, then create compilation units from it.
 
-use std::sync::Arc;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use thiserror::Error;
 
+use angelscript_compiler::shared::{SharedDecl, SharedRegistry};
+use angelscript_compiler::{
+    AccessMask, AccessMasks, CompilerPlugin, IntegerOverflow, WarningCode, WarningConfig,
+    WarningLevel,
+};
 use angelscript_core::{
-    ClassEntry, ClassMeta, DataType, FuncdefEntry, FuncdefMeta, FunctionDef, FunctionEntry,
-    FunctionMeta, FunctionTraits, InterfaceEntry, InterfaceMeta, MethodSignature, Param,
-    PropertyEntry, StringFactory, TemplateParamEntry, TypeHash, TypeSource, Visibility,
+    ClassEntry, ClassMeta, CompilationError, DataType, FuncdefEntry, FuncdefMeta, FunctionDef,
+    FunctionEntry, FunctionMeta, FunctionTraits, InterfaceEntry, InterfaceMeta, MemoryBudget,
+    MethodSignature, OutOfMemory, Param, PropertyEntry, StringFactory, TemplateParamEntry,
+    TypeHash, TypeSource, UnitId, Visibility,
 };
 use angelscript_registry::{Module, SymbolRegistry};
+use rustc_hash::FxHashMap;
 
+use crate::builder::ContextBuilder;
+use crate::diagnostic::{Diagnostic, DiagnosticHandler};
+use crate::extension::ExtensionOp;
+use crate::imports::{ImportError, UnresolvedImport, UnresolvedReason};
 use crate::unit::Unit;
 
 /// Execution context that owns the type registry.
@@ -38,6 +51,64 @@ pub struct Context {
     /// The string factory for creating string literal values.
     /// If None, string literals will produce a compile error.
     string_factory: Option<Box<dyn StringFactory>>,
+    /// Custom compiler passes run for every unit built from this context.
+    plugins: Vec<Box<dyn CompilerPlugin>>,
+    /// Shared script entities declared by the units of this context.
+    shared: Mutex<SharedRegistry>,
+    /// Id of the next unit created from this context.
+    next_unit_id: AtomicU32,
+    /// Units each unit imports functions from (importer → providers).
+    import_links: Mutex<FxHashMap<UnitId, Vec<UnitId>>>,
+    /// Host handlers for `EXTENSION` instructions, by extension op.
+    extension_ops: FxHashMap<u8, ExtensionOp>,
+    /// Access masks of types and functions installed with a mask.
+    access: AccessMasks,
+    /// Receives diagnostics of the units built from this context.
+    diagnostic_handler: Option<DiagnosticHandler>,
+    /// How strictly the units of this context are compiled.
+    strictness: Strictness,
+    /// What integer overflow does in the units of this context.
+    integer_overflow: IntegerOverflow,
+    /// What failed script allocations do in the units of this context.
+    out_of_memory: OutOfMemory,
+    /// Bytes each unit may allocate on behalf of scripts, if limited.
+    memory_limit: Option<usize>,
+    /// Changes whenever the registry, access masks or plugins change.
+    generation: u64,
+}
+
+/// Next registry generation, shared by every context so that no two
+/// contexts, or states of one context, have the same generation. Units
+/// created without a context compile at generation 0.
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// How strictly the units of a context are compiled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strictness {
+    /// Sections opt into strict conversions with `#pragma strict`, and
+    /// warnings are reported at their default levels.
+    #[default]
+    Standard,
+    /// Every section is compiled as if it began with `#pragma strict`, and
+    /// warnings reported by default fail the build. Units can still lower
+    /// the level of a warning with
+    /// [`Unit::set_warning_level`](crate::Unit::set_warning_level).
+    Strict,
+}
+
+impl Strictness {
+    /// The warning levels of a new unit.
+    pub(crate) fn warning_config(self) -> WarningConfig {
+        let mut config = WarningConfig::new();
+        if self == Self::Strict {
+            for code in WarningCode::ALL {
+                if code.default_level() == WarningLevel::Warn {
+                    config.set(code, WarningLevel::Deny);
+                }
+            }
+        }
+        config
+    }
 }
 
 impl Context {
@@ -46,28 +117,44 @@ impl Context {
         Self {
             registry: SymbolRegistry::with_primitives(),
             string_factory: None,
+            plugins: Vec::new(),
+            shared: Mutex::new(SharedRegistry::new()),
+            // Id 0 is used by units created without a context
+            next_unit_id: AtomicU32::new(1),
+            import_links: Mutex::new(FxHashMap::default()),
+            extension_ops: FxHashMap::default(),
+            access: AccessMasks::new(),
+            diagnostic_handler: None,
+            strictness: Strictness::Standard,
+            integer_overflow: IntegerOverflow::default(),
+            out_of_memory: OutOfMemory::default(),
+            memory_limit: None,
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
         }
     }
 
+    /// Start building a context.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let ctx = Context::builder()
+    ///     .with_default_modules()
+    ///     .install(game_module())
+    ///     .strictness(Strictness::Strict)
+    ///     .build_shared()?;
+    /// ```
+    pub fn builder() -> ContextBuilder {
+        ContextBuilder::new()
+    }
+
     /// Create a context with default modules pre-installed.
     ///
     /// This registers the standard library types (string, array, dictionary, etc.)
     /// in addition to primitives. Also sets the default string factory for
     /// string literals.
     pub fn with_default_modules() -> Result<Self, ContextError> {
-        let mut ctx = Self::new();
-
-        // Install stdlib modules
-        ctx.install(angelscript_modules::string::module())?;
-        ctx.install(angelscript_modules::array::module())?;
-        ctx.install(angelscript_modules::dictionary::module())?;
-        ctx.install(angelscript_modules::math::module())?;
-        ctx.install(angelscript_modules::std::module())?;
-
-        // Set default string factory
-        ctx.set_string_factory(Box::new(angelscript_modules::string::ScriptStringFactory));
-
-        Ok(ctx)
+        Self::builder().with_default_modules().build()
     }
 
     /// Install a module into the context.
@@ -79,6 +166,42 @@ impl Context {
     ///
     /// Returns an error if registration fails (e.g., duplicate type names).
     pub fn install(&mut self, module: Module) -> Result<(), ContextError> {
+        self.install_module(module, AccessMask::ALL)
+    }
+
+    /// Install a module whose types and global functions are only visible to
+    /// units sharing a bit with `mask`.
+    ///
+    /// Scripts of other units referencing them fail to build with
+    /// [`CompilationError::Inaccessible`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if registration fails (e.g., duplicate type names).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// const GAMEPLAY: AccessMask = AccessMask::new(1 << 0);
+    /// const FILESYSTEM: AccessMask = AccessMask::new(1 << 1);
+    ///
+    /// ctx.install_with_mask(engine_module(), GAMEPLAY | FILESYSTEM)?;
+    /// ctx.install_with_mask(fs_module(), FILESYSTEM)?;
+    ///
+    /// let mut mod_unit = ctx.create_unit()?;
+    /// mod_unit.set_access_mask(GAMEPLAY);
+    /// ```
+    pub fn install_with_mask(
+        &mut self,
+        module: Module,
+        mask: AccessMask,
+    ) -> Result<(), ContextError> {
+        self.install_module(module, mask)
+    }
+
+    fn install_module(&mut self, module: Module, mask: AccessMask) -> Result<(), ContextError> {
+        self.bump_generation();
+
         // Compute qualified namespace string once (only for registry operations that need it)
         let qualified_ns = if module.namespace.is_empty() {
             String::new()
@@ -93,32 +216,156 @@ impl Context {
 
         // Install classes
         for class_meta in module.classes {
+            let type_hash = class_meta.type_hash;
             self.install_class(&module.namespace, &qualified_ns, class_meta)?;
+            self.access.set(type_hash, mask);
         }
 
         // Install functions - pass associated_type for methods, None for globals
         for func_meta in module.functions {
-            self.install_function(&module.namespace, func_meta.associated_type, func_meta)?;
+            let associated_type = func_meta.associated_type;
+            let func_hash = self.install_function(&module.namespace, associated_type, func_meta)?;
+            // Methods are reachable only through their type
+            if associated_type.is_none() {
+                self.access.set(func_hash, mask);
+            }
         }
 
         // Install interfaces
         for interface_meta in module.interfaces {
+            let type_hash = interface_meta.type_hash;
             self.install_interface(&module.namespace, &qualified_ns, interface_meta)?;
+            self.access.set(type_hash, mask);
         }
 
         // Install funcdefs
         for funcdef_meta in module.funcdefs {
+            let type_hash = funcdef_meta.type_hash;
             self.install_funcdef(&module.namespace, &qualified_ns, funcdef_meta)?;
+            self.access.set(type_hash, mask);
         }
 
+        // Install global properties
+        for global in module.globals {
+            let type_hash = global.type_hash;
+            self.registry
+                .register_global(global)
+                .map_err(|e| ContextError::RegistrationFailed(e.to_string()))?;
+            self.access.set(type_hash, mask);
+        }
+
+        // Type completion: methods bind to vtable and itable slots from here on
+        self.registry.complete_vtables();
+        self.registry.complete_itables();
+
         Ok(())
     }
 
+    /// Install a module under a namespace.
+    ///
+    /// Everything the module registers is placed under `namespace`, in front
+    /// of any namespace the module declares itself. This lets hosts group
+    /// third-party modules without their authors hard-coding namespaces.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if registration fails (e.g., duplicate type names).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // `Player` and `spawn` become `Engine::Player` and `Engine::spawn`
+    /// ctx.install_in("Engine", Module::new().class::<Player>().function(spawn))?;
+    /// ```
+    pub fn install_in(&mut self, namespace: &str, mut module: Module) -> Result<(), ContextError> {
+        let mut path: Vec<String> = namespace
+            .split("::")
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect();
+        module.globals = std::mem::take(&mut module.globals)
+            .into_iter()
+            .map(|global| {
+                let namespace = path.iter().chain(&global.namespace).cloned().collect();
+                global.with_namespace(namespace)
+            })
+            .collect();
+        path.append(&mut module.namespace);
+        module.namespace = path;
+        self.install(module)
+    }
+
+    /// Set how strictly units created from now on are compiled.
+    pub fn set_strictness(&mut self, strictness: Strictness) {
+        self.strictness = strictness;
+    }
+
+    /// Get how strictly the units of this context are compiled.
+    pub fn strictness(&self) -> Strictness {
+        self.strictness
+    }
+
+    /// Set whether integer overflow wraps or raises an exception in units
+    /// created from now on. Traps by default.
+    pub fn set_integer_overflow(&mut self, overflow: IntegerOverflow) {
+        self.integer_overflow = overflow;
+    }
+
+    /// Get what integer overflow does in the units of this context.
+    pub fn integer_overflow(&self) -> IntegerOverflow {
+        self.integer_overflow
+    }
+
+    /// Set whether failed script allocations raise an exception or abort in
+    /// units created from now on. Raises an exception by default.
+    pub fn set_out_of_memory(&mut self, policy: OutOfMemory) {
+        self.out_of_memory = policy;
+    }
+
+    /// Get what failed script allocations do in the units of this context.
+    pub fn out_of_memory(&self) -> OutOfMemory {
+        self.out_of_memory
+    }
+
+    /// Limit the bytes each unit created from now on may allocate on behalf
+    /// of scripts. Exhausting the limit fails like the allocator failing.
+    /// Unlimited by default.
+    pub fn set_memory_limit(&mut self, limit: Option<usize>) {
+        self.memory_limit = limit;
+    }
+
+    /// Get the bytes each unit may allocate on behalf of scripts, if limited.
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+
+    /// The memory budget of a new unit.
+    pub(crate) fn memory_budget(&self) -> MemoryBudget {
+        MemoryBudget::new(self.out_of_memory, self.memory_limit)
+    }
+
+    /// Get the access masks of installed types and functions.
+    pub fn access_masks(&self) -> &AccessMasks {
+        &self.access
+    }
+
     /// Get a reference to the type registry.
     pub fn registry(&self) -> &SymbolRegistry {
         &self.registry
     }
 
+    /// Get the generation of the registry, access masks and plugins.
+    ///
+    /// Installing a module or adding a plugin changes it, so units do not
+    /// reuse functions compiled against what the context held before.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn bump_generation(&mut self) {
+        self.generation = NEXT_GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+
     /// Set a custom string factory.
     ///
     /// The string factory creates string values from raw byte data when
@@ -145,11 +392,308 @@ impl Context {
         self.string_factory.as_deref()
     }
 
+    /// Add a compiler plugin.
+    ///
+    /// Plugins run in the order they were added, for every unit built from
+    /// this context. Errors they report fail the build.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// ctx.add_plugin(Box::new(NoAllocInUpdate));
+    /// ```
+    pub fn add_plugin(&mut self, plugin: Box<dyn CompilerPlugin>) {
+        self.plugins.push(plugin);
+        self.bump_generation();
+    }
+
+    /// Get the compiler plugins, in the order they run.
+    pub fn plugins(&self) -> &[Box<dyn CompilerPlugin>] {
+        &self.plugins
+    }
+
+    /// Set the handler receiving the diagnostics of units built from this
+    /// context.
+    ///
+    /// Diagnostics are reported as each build stage produces them, in
+    /// addition to being returned in the [`BuildError`](crate::BuildError).
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// ctx.set_diagnostic_handler(|diag| eprintln!("{}", diag));
+    /// ```
+    pub fn set_diagnostic_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(&Diagnostic) + Send + Sync + 'static,
+    {
+        self.diagnostic_handler = Some(Arc::new(handler));
+    }
+
+    /// Remove the diagnostic handler.
+    pub fn clear_diagnostic_handler(&mut self) {
+        self.diagnostic_handler = None;
+    }
+
+    /// Pass a diagnostic to the handler, if one is set.
+    pub(crate) fn report(&self, diagnostic: &Diagnostic) {
+        if let Some(handler) = &self.diagnostic_handler {
+            handler(diagnostic);
+        }
+    }
+
+    /// Register the handler of an extension op.
+    ///
+    /// Compiler plugins emit `EXTENSION` instructions for the op, which the
+    /// VM dispatches to `handler` instead of a native call.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `op` is already registered.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// const ECS_FETCH: u8 = 0;
+    /// ctx.register_extension_op(ECS_FETCH, ExtensionOp::new("ecs_fetch", 1, true, fetch))?;
+    /// ctx.add_plugin(Box::new(EcsFetchPass { op: ECS_FETCH }));
+    /// ```
+    pub fn register_extension_op(
+        &mut self,
+        op: u8,
+        handler: ExtensionOp,
+    ) -> Result<(), ContextError> {
+        if let Some(existing) = self.extension_ops.get(&op) {
+            return Err(ContextError::ExtensionOpTaken {
+                op,
+                name: existing.name().to_string(),
+            });
+        }
+        self.extension_ops.insert(op, handler);
+        Ok(())
+    }
+
+    /// Get the handler of an extension op (for VM use).
+    pub fn extension_op(&self, op: u8) -> Option<&ExtensionOp> {
+        self.extension_ops.get(&op)
+    }
+
+    /// Check if a script type is a shared entity declared by a built unit.
+    ///
+    /// Shared classes, interfaces and enums have the same type hash in every
+    /// unit of the context, so handles to them can be passed between units.
+    pub fn is_shared_type(&self, type_hash: TypeHash) -> bool {
+        self.shared_registry().get(type_hash).is_some()
+    }
+
+    /// Register the shared entities declared by a unit being built in place
+    /// of `released`, its previous ones, which are kept on conflict.
+    pub(crate) fn replace_shared(
+        &self,
+        released: &[TypeHash],
+        decls: &[SharedDecl],
+    ) -> Result<(), Vec<CompilationError>> {
+        self.shared_registry().replace(released, decls)
+    }
+
+    /// Release the shared entities of a unit that is rebuilt or dropped.
+    pub(crate) fn release_shared(&self, type_hashes: &[TypeHash]) {
+        self.shared_registry().release(type_hashes);
+    }
+
+    fn shared_registry(&self) -> MutexGuard<'_, SharedRegistry> {
+        // The registry holds no invariants a panicking unit could break
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
     /// Create a new compilation unit from this context.
     pub fn create_unit(self: &Arc<Self>) -> Result<Unit, ContextError> {
         Ok(Unit::with_context(Arc::clone(self)))
     }
 
+    /// Allocate the id of a new unit.
+    pub(crate) fn next_unit_id(&self) -> UnitId {
+        UnitId::new(self.next_unit_id.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Discard a unit, freeing its compiled code and the shared entities
+    /// only it declared.
+    ///
+    /// Dropping a unit frees it unconditionally; this checks first that
+    /// nothing still depends on it, which is what a host unloading a level or
+    /// mod wants.
+    ///
+    /// # Errors
+    ///
+    /// Returns the unit unchanged in the error if it was created from another
+    /// context, other live units import functions from it, or the host still
+    /// holds handles to its script objects or functions.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// match ctx.discard_unit(level) {
+    ///     Ok(()) => {}
+    ///     Err(err) => {
+    ///         log::warn!("cannot unload level: {}", err);
+    ///         level = err.into_unit();
+    ///     }
+    /// }
+    /// ```
+    pub fn discard_unit(&self, unit: Unit) -> Result<(), DiscardError> {
+        let reason = if !unit.belongs_to(self) {
+            Some(DiscardReason::ContextMismatch)
+        } else {
+            let importers: Vec<UnitId> = self
+                .links()
+                .iter()
+                .filter(|(_, providers)| providers.contains(&unit.id()))
+                .map(|(&importer, _)| importer)
+                .collect();
+            let handles = unit.host_handle_count();
+
+            if !importers.is_empty() {
+                Some(DiscardReason::Imported(importers))
+            } else if handles > 0 {
+                Some(DiscardReason::HandlesAlive(handles))
+            } else {
+                None
+            }
+        };
+
+        match reason {
+            Some(reason) => Err(DiscardError {
+                unit: Box::new(unit),
+                reason,
+            }),
+            // Dropping the unit releases its shared entities and links
+            None => Ok(()),
+        }
+    }
+
+    /// Record the units `importer` has bound imports to.
+    fn link_imports(&self, importer: UnitId, providers: Vec<UnitId>) {
+        if providers.is_empty() {
+            self.links().remove(&importer);
+        } else {
+            self.links().insert(importer, providers);
+        }
+    }
+
+    /// Forget the import links of a unit that is cleared or dropped.
+    pub(crate) fn unlink_imports(&self, importer: UnitId) {
+        self.links().remove(&importer);
+    }
+
+    fn links(&self) -> MutexGuard<'_, FxHashMap<UnitId, Vec<UnitId>>> {
+        self.import_links
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Link the imports of `unit` against the functions of other units.
+    ///
+    /// Each `import ... from "module"` in `unit` is bound to the function in
+    /// the provider whose [name](Unit::set_name) is `module`, matching the
+    /// function name and parameter count. All units must be built and
+    /// created from this context. Rebinding replaces earlier bindings.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ImportError::Unresolved`] listing every import that could
+    /// not be bound; the imports that could be resolved are still bound.
+    /// Returns [`ImportError::Template`] if the template instances of a
+    /// provider conflict with those of `unit`.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// let mut enemies = ctx.create_unit()?;
+    /// enemies.set_name("enemies");
+    /// enemies.add_source("enemies.as", "void spawn(int count) { }")?;
+    /// enemies.build()?;
+    ///
+    /// let mut game = ctx.create_unit()?;
+    /// game.add_source("game.as", r#"import void spawn(int) from "enemies";"#)?;
+    /// game.build()?;
+    ///
+    /// ctx.bind_imports(&mut game, &[&enemies])?;
+    /// ```
+    pub fn bind_imports(&self, unit: &mut Unit, providers: &[&Unit]) -> Result<(), ImportError> {
+        if !unit.belongs_to(self) || providers.iter().any(|p| !p.belongs_to(self)) {
+            return Err(ImportError::ContextMismatch);
+        }
+        if !unit.is_built() || providers.iter().any(|p| !p.is_built()) {
+            return Err(ImportError::NotBuilt);
+        }
+
+        let mut unresolved = Vec::new();
+        let mut linked = Vec::new();
+        for import in unit.imports_mut() {
+            import.unbind();
+
+            let Some((provider, module)) = providers
+                .iter()
+                .find(|p| p.name() == Some(import.module()))
+                .and_then(|p| Some((p, p.compiled()?)))
+            else {
+                unresolved.push(UnresolvedImport::new(
+                    import,
+                    UnresolvedReason::ModuleNotFound,
+                ));
+                continue;
+            };
+
+            let candidates: Vec<usize> = module
+                .functions
+                .iter()
+                .enumerate()
+                .filter(|(_, f)| {
+                    f.name == import.name() && f.signature.param_count() == import.param_count()
+                })
+                .map(|(index, _)| index)
+                .collect();
+
+            match candidates.as_slice() {
+                [function] => {
+                    import.bind(*function);
+                    if !linked.contains(&provider.id()) {
+                        linked.push(provider.id());
+                    }
+                }
+                [] => unresolved.push(UnresolvedImport::new(
+                    import,
+                    UnresolvedReason::FunctionNotFound,
+                )),
+                _ => unresolved.push(UnresolvedImport::new(
+                    import,
+                    UnresolvedReason::Ambiguous(candidates.len()),
+                )),
+            }
+        }
+
+        let linked_providers: Vec<&Unit> = providers
+            .iter()
+            .copied()
+            .filter(|p| linked.contains(&p.id()))
+            .collect();
+        self.link_imports(unit.id(), linked);
+
+        // Handles to template instances pass between linked units, so each
+        // needs the instances of its providers
+        for provider in linked_providers {
+            unit.template_instances_mut()
+                .link_template_instances(provider.template_instances())
+                .map_err(ImportError::Template)?;
+        }
+
+        if unresolved.is_empty() {
+            Ok(())
+        } else {
+            Err(ImportError::Unresolved(unresolved))
+        }
+    }
+
     // =========================================================================
     // Private installation helpers
     // =========================================================================
@@ -251,7 +795,7 @@ impl Context {
         namespace: &[String],
         object_type: Option<TypeHash>,
         meta: FunctionMeta,
-    ) -> Result<(), ContextError> {
+    ) -> Result<TypeHash, ContextError> {
         let name = meta.as_name.unwrap_or(meta.name);
 
         // Get owner class qualified name for resolving template param names
@@ -383,6 +927,7 @@ impl Context {
         };
         let traits = FunctionTraits {
             is_const: meta.is_const,
+            is_pure: meta.is_pure,
             is_constructor,
             is_destructor,
             ..Default::default()
@@ -475,7 +1020,7 @@ impl Context {
             )?;
         }
 
-        Ok(())
+        Ok(func_hash)
     }
 
     /// Wire a function's behavior to the type's TypeBehaviors.
@@ -694,17 +1239,73 @@ impl Default for Context {
     }
 }
 
-unsafe impl Send for Context {}
-unsafe impl Sync for Context {}
-
 /// Errors that can occur during context operations.
 #[derive(Debug, Error)]
 pub enum ContextError {
     /// Registration failed
     #[error("registration failed: {0}")]
     RegistrationFailed(String),
+
+    /// Extension op already has a handler
+    #[error("extension op {op} is already registered as '{name}'")]
+    ExtensionOpTaken { op: u8, name: String },
+
+    /// The string factory creates a type no module registers
+    #[error("string factory creates unregistered type {0}")]
+    UnregisteredStringType(TypeHash),
 }
 
+/// Why a unit could not be discarded.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum DiscardReason {
+    /// The unit was created from a different context.
+    #[error("Unit was not created from this context")]
+    ContextMismatch,
+
+    /// Other live units import functions from the unit.
+    #[error("Unit is imported by {} live unit(s)", .0.len())]
+    Imported(Vec<UnitId>),
+
+    /// The host still holds handles to script objects or functions of the unit.
+    #[error("Host still holds {0} handle(s) into the unit")]
+    HandlesAlive(usize),
+}
+
+/// A unit that could not be discarded, returned to the caller.
+pub struct DiscardError {
+    unit: Box<Unit>,
+    reason: DiscardReason,
+}
+
+impl DiscardError {
+    /// Why the unit could not be discarded.
+    pub fn reason(&self) -> &DiscardReason {
+        &self.reason
+    }
+
+    /// Take back the unit, which is still usable.
+    pub fn into_unit(self) -> Unit {
+        *self.unit
+    }
+}
+
+impl fmt::Debug for DiscardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DiscardError")
+            .field("unit", &self.unit.id())
+            .field("reason", &self.reason)
+            .finish()
+    }
+}
+
+impl fmt::Display for DiscardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.reason.fmt(f)
+    }
+}
+
+impl std::error::Error for DiscardError {}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -717,6 +1318,22 @@ mod tests {
         assert!(ctx.registry().get(primitives::INT32).is_some());
     }
 
+    #[test]
+    fn generation_changes_with_registry() {
+        let mut ctx = Context::new();
+        let before = ctx.generation();
+        assert_ne!(Context::new().generation(), before);
+
+        ctx.install(Module::new()).unwrap();
+        assert_ne!(ctx.generation(), before);
+    }
+
+    #[test]
+    fn context_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Context>();
+    }
+
     #[test]
     fn context_default() {
         let ctx = Context::default();
@@ -727,6 +1344,7 @@ mod tests {
     fn context_with_default_modules() {
         let ctx = Context::with_default_modules().unwrap();
         assert!(ctx.registry().get(primitives::BOOL).is_some());
+        assert!(ctx.registry().get_by_name("stringbuilder").is_some());
     }
 
     #[test]
@@ -735,6 +1353,111 @@ mod tests {
         let _unit = ctx.create_unit().unwrap();
     }
 
+    #[test]
+    fn context_plugins_report_build_errors() {
+        use angelscript_compiler::{PluginContext, PluginFunction};
+
+        struct NoUpdate;
+
+        impl CompilerPlugin for NoUpdate {
+            fn name(&self) -> &str {
+                "no-update"
+            }
+
+            fn before_emit(&self, ctx: &mut PluginContext<'_>, function: &PluginFunction<'_, '_>) {
+                if function.name == "onUpdate" {
+                    ctx.error("onUpdate is not allowed", function.decl.span);
+                }
+            }
+        }
+
+        let mut ctx = Context::new();
+        ctx.add_plugin(Box::new(NoUpdate));
+        assert_eq!(ctx.plugins().len(), 1);
+        let ctx = Arc::new(ctx);
+
+        let mut unit = ctx.create_unit().unwrap();
+        unit.add_source("ok.as", "void onStart() { }").unwrap();
+        assert!(unit.build().is_ok());
+
+        let mut unit = ctx.create_unit().unwrap();
+        unit.add_source("bad.as", "void onUpdate() { }").unwrap();
+        let errors = unit.build().unwrap_err().into_errors();
+        assert_eq!(errors.len(), 1);
+        assert!(
+            errors[0]
+                .to_string()
+                .contains("[no-update] onUpdate is not allowed")
+        );
+    }
+
+    #[test]
+    fn context_register_extension_op() {
+        use angelscript_core::{Dynamic, ObjectHeap};
+
+        let mut ctx = Context::new();
+        let negate = ExtensionOp::new("negate", 1, true, |ctx, _| {
+            let value: i32 = ctx.arg(0)?;
+            ctx.set_return(-value);
+            Ok(())
+        });
+        ctx.register_extension_op(7, negate.clone()).unwrap();
+
+        let err = ctx.register_extension_op(7, negate).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "extension op 7 is already registered as 'negate'"
+        );
+        assert!(ctx.extension_op(8).is_none());
+
+        let op = ctx.extension_op(7).unwrap();
+        let result = op
+            .invoke(&mut [Dynamic::Int(5)], 0, &mut ObjectHeap::new())
+            .unwrap();
+        assert!(matches!(result, Some(Dynamic::Int(-5))));
+    }
+
+    #[test]
+    fn context_shared_types_across_units() {
+        let ctx = Arc::new(Context::new());
+        let item = TypeHash::from_name("Item");
+        let source = "shared class Item { int id; } class Local { }";
+
+        let mut first = ctx.create_unit().unwrap();
+        first.add_source("first.as", source).unwrap();
+        first.build().unwrap();
+
+        let mut second = ctx.create_unit().unwrap();
+        second.add_source("second.as", source).unwrap();
+        second.build().unwrap();
+
+        assert_eq!(first.shared_types(), &[item]);
+        assert!(ctx.is_shared_type(item));
+        assert!(!ctx.is_shared_type(TypeHash::from_name("Local")));
+
+        let mut mismatched = ctx.create_unit().unwrap();
+        mismatched
+            .add_source("third.as", "shared class Item { float id; }")
+            .unwrap();
+        assert!(matches!(
+            mismatched.build(),
+            Err(crate::BuildError::CompilationErrors(_))
+        ));
+
+        // A failed rebuild keeps the unit's shared types registered
+        first
+            .update_source("first.as", "shared class Item { float id; }")
+            .unwrap();
+        assert!(first.rebuild().is_err());
+        assert_eq!(first.shared_types(), &[item]);
+        second.clear();
+        assert!(ctx.is_shared_type(item));
+        assert!(mismatched.build().is_err());
+
+        drop(first);
+        assert!(!ctx.is_shared_type(item));
+    }
+
     #[test]
     fn context_install_empty_module() {
         let mut ctx = Context::new();
@@ -1020,6 +1743,7 @@ mod tests {
             behavior: None,
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: true,
             list_pattern: None,
@@ -1036,6 +1760,83 @@ mod tests {
         assert!(t_param.unwrap().as_template_param().is_some());
     }
 
+    #[test]
+    fn context_install_in_namespace() {
+        let mut ctx = Context::new();
+        let class = |name| ClassMeta {
+            name,
+            type_hash: TypeHash::from_name(name),
+            type_kind: TypeKind::reference(),
+            rust_type_id: None,
+            properties: vec![],
+            template_params: vec![],
+            specialization_of: None,
+            specialization_args: vec![],
+        };
+
+        let mut module = Module::new();
+        module.classes.push(class("Player"));
+        ctx.install_in("Engine", module).unwrap();
+
+        let mut module = Module::in_namespace(&["physics"]).global("GRAVITY", 9.81f64);
+        module.classes.push(class("Body"));
+        ctx.install_in("Engine", module).unwrap();
+
+        let registry = ctx.registry();
+        assert!(registry.has_namespace("Engine"));
+        assert!(registry.has_namespace("Engine::physics"));
+        assert!(registry.get_by_name("Engine::Player").is_some());
+        assert!(registry.get_by_name("Engine::physics::Body").is_some());
+        assert!(registry.get_by_name("Player").is_none());
+        assert!(
+            registry
+                .get_global_by_name("Engine::physics::GRAVITY")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn context_install_with_mask_hides_types() {
+        const GAMEPLAY: AccessMask = AccessMask::new(0b01);
+        const FILESYSTEM: AccessMask = AccessMask::new(0b10);
+
+        let mut ctx = Context::new();
+        let mut module = Module::in_namespace(&["io"]);
+        module.classes.push(ClassMeta {
+            name: "File",
+            type_hash: TypeHash::from_name("io::File"),
+            type_kind: TypeKind::reference(),
+            rust_type_id: None,
+            properties: vec![],
+            template_params: vec![],
+            specialization_of: None,
+            specialization_args: vec![],
+        });
+        ctx.install_with_mask(module, FILESYSTEM).unwrap();
+        assert_eq!(
+            ctx.access_masks().get(TypeHash::from_name("io::File")),
+            FILESYSTEM
+        );
+
+        let ctx = Arc::new(ctx);
+        let source = "void main() { io::File@ f = null; }";
+
+        let mut tools = ctx.create_unit().unwrap();
+        tools.add_source("tools.as", source).unwrap();
+        tools.build().unwrap();
+
+        let mut mod_unit = ctx.create_unit().unwrap();
+        mod_unit.set_access_mask(GAMEPLAY);
+        mod_unit.add_source("mod.as", source).unwrap();
+        let Err(crate::BuildError::CompilationErrors(errors)) = mod_unit.build() else {
+            panic!("expected an access error");
+        };
+        assert_eq!(
+            errors[0].to_string(),
+            "at 1:15: type 'io::File' is not accessible from this unit"
+        );
+    }
+
     #[test]
     fn context_install_namespaced_template_class() {
         let mut ctx = Context::new();
@@ -1117,6 +1918,7 @@ mod tests {
             behavior: None,
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: true,
             list_pattern: None,
@@ -1175,6 +1977,7 @@ mod tests {
             behavior: None,
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: true,
             list_pattern: None,
@@ -1234,6 +2037,7 @@ mod tests {
             behavior: Some(Behavior::Operator(Operator::Conv)),
             is_const: true,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -1282,6 +2086,7 @@ mod tests {
             behavior: Some(Behavior::Operator(Operator::ImplCast)),
             is_const: true,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -1330,6 +2135,7 @@ mod tests {
             behavior: Some(Behavior::Operator(Operator::Add)),
             is_const: true,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -1381,6 +2187,7 @@ mod tests {
             behavior: Some(Behavior::Operator(Operator::Conv)),
             is_const: true,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -1402,6 +2209,7 @@ mod tests {
             behavior: Some(Behavior::Operator(Operator::ImplConv)),
             is_const: true,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: false,
             list_pattern: None,
@@ -1464,6 +2272,7 @@ mod tests {
             behavior: None,
             is_const: false,
             is_property: false,
+            is_pure: false,
             property_name: None,
             is_generic: true,
             list_pattern: None,
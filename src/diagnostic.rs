@@ -0,0 +1,180 @@
+//! Structured build diagnostics.
+//!
+//! [`Unit::build`](crate::Unit::build) returns all errors at once in a
+//! [`BuildError`](crate::BuildError). Hosts that want feedback as each stage
+//! finishes (e.g. an editor underlining errors while the user types) can set
+//! a handler with [`Context::set_diagnostic_handler`]. Every unit built from
+//! the context reports its lexer, parser and compiler diagnostics to it as
+//! they are produced.
+//!
+//! # Example
+//!
+//! ```ignore
+//! ctx.set_diagnostic_handler(|diag| {
+//!     editor.underline(diag.section.as_deref(), diag.span, diag.severity, &diag.message);
+//! });
+//! ```
+//!
+//! [`Context::set_diagnostic_handler`]: crate::Context::set_diagnostic_handler
+
+use angelscript_compiler::Warning;
+use angelscript_core::{CompilationError, Span, UnitId};
+use angelscript_parser::ast::ParseError;
+use std::fmt;
+use std::sync::Arc;
+
+/// Callback receiving diagnostics as they are produced.
+pub type DiagnosticHandler = Arc<dyn Fn(&Diagnostic) + Send + Sync>;
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Informational note.
+    Info,
+    /// Suspicious code that does not fail the build.
+    Warning,
+    /// The build fails.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// A message produced while building a unit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// How serious the diagnostic is.
+    pub severity: Severity,
+    /// Unit being built.
+    pub unit: UnitId,
+    /// Script section the diagnostic refers to, if it is tied to one.
+    pub section: Option<String>,
+    /// Location in the section.
+    pub span: Span,
+    /// Stable code identifying the kind of diagnostic, e.g. `"UnknownType"`.
+    pub code: String,
+    /// Human-readable message, without the location.
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Create an error diagnostic.
+    pub fn error(
+        unit: UnitId,
+        section: Option<String>,
+        span: Span,
+        code: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            severity: Severity::Error,
+            unit,
+            section,
+            span,
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Create a diagnostic from a lexer or parser error.
+    pub(crate) fn from_parse_error(unit: UnitId, section: &str, error: &ParseError) -> Self {
+        Self::error(
+            unit,
+            Some(section.to_string()),
+            error.span,
+            format!("{:?}", error.kind),
+            error.message.clone(),
+        )
+    }
+
+    /// Create a diagnostic from a compiler warning.
+    pub(crate) fn from_warning(unit: UnitId, section: &str, warning: &Warning) -> Self {
+        Self {
+            severity: Severity::Warning,
+            unit,
+            section: Some(section.to_string()),
+            span: warning.span,
+            code: warning.code.code().to_string(),
+            message: warning.message.clone(),
+        }
+    }
+
+    /// Create a diagnostic from a compilation error.
+    pub(crate) fn from_compilation_error(
+        unit: UnitId,
+        section: Option<&str>,
+        error: &CompilationError,
+    ) -> Self {
+        let span = error.span();
+        let text = error.to_string();
+        // Messages lead with the location, which is carried separately
+        let message = text
+            .strip_prefix(&format!("at {}: ", span))
+            .unwrap_or(&text)
+            .to_string();
+        Self::error(
+            unit,
+            section.map(str::to_string),
+            span,
+            error.code(),
+            message,
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(section) = &self.section {
+            write!(f, "{}:", section)?;
+        }
+        write!(
+            f,
+            "{}: {}[{}]: {}",
+            self.span, self.severity, self.code, self.message
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_parser::ast::ParseErrorKind;
+
+    #[test]
+    fn compilation_errors_drop_location_prefix() {
+        let error = CompilationError::UnknownType {
+            name: "Foo".into(),
+            span: Span::new(3, 7, 3),
+        };
+        let diag = Diagnostic::from_compilation_error(UnitId::new(1), Some("main.as"), &error);
+
+        assert_eq!(diag.severity, Severity::Error);
+        assert_eq!(diag.code, "UnknownType");
+        assert_eq!(diag.message, "unknown type 'Foo'");
+        assert_eq!(
+            diag.to_string(),
+            "main.as:3:7: error[UnknownType]: unknown type 'Foo'"
+        );
+    }
+
+    #[test]
+    fn parse_errors_use_kind_as_code() {
+        let error = ParseError::new(
+            ParseErrorKind::ExpectedToken,
+            Span::new(1, 2, 1),
+            "expected ';'",
+        );
+        let diag = Diagnostic::from_parse_error(UnitId::new(0), "a.as", &error);
+
+        assert_eq!(diag.code, "ExpectedToken");
+        assert_eq!(diag.section.as_deref(), Some("a.as"));
+        assert!(Severity::Error > Severity::Warning);
+    }
+}
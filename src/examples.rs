@@ -0,0 +1,351 @@
+//! Embedding examples.
+//!
+//! A curated set of scripts, each with the host registrations it needs,
+//! covering the common ways of embedding the engine: registering native
+//! types and functions, script classes and interfaces, templates and
+//! cooperative coroutines. They double as smoke tests for the whole
+//! pipeline and as starting points to copy into a new embedding:
+//!
+//! ```ignore
+//! for (name, result) in angelscript::examples::run_all() {
+//!     println!("{name}: {}", if result.is_ok() { "ok" } else { "failed" });
+//! }
+//! ```
+//!
+//! Examples build in [`Strictness::Strict`] mode, so warnings fail them too.
+//! Only available with the `examples` feature.
+
+use std::sync::Arc;
+
+use angelscript_macros::Any;
+use angelscript_registry::Module;
+use thiserror::Error;
+
+use crate::{BuildError, Context, ContextError, Strictness, UnitError};
+
+/// A script and the host registrations it needs.
+#[derive(Debug, Clone, Copy)]
+pub struct Example {
+    /// Short identifier, used as the file name of the script.
+    pub name: &'static str,
+    /// What the example shows.
+    pub description: &'static str,
+    /// AngelScript source.
+    pub source: &'static str,
+    /// Host modules installed next to the default modules.
+    modules: fn() -> Vec<Module>,
+}
+
+impl Example {
+    /// The host modules the script needs, besides the default modules.
+    pub fn modules(&self) -> Vec<Module> {
+        (self.modules)()
+    }
+
+    /// Build the example in a fresh context.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of setting up the context or building the
+    /// script.
+    pub fn run(&self) -> Result<(), ExampleError> {
+        let mut ctx = Context::with_default_modules()?;
+        ctx.set_strictness(Strictness::Strict);
+        for module in self.modules() {
+            ctx.install(module)?;
+        }
+        let ctx = Arc::new(ctx);
+        let mut unit = ctx.create_unit()?;
+        unit.add_source(format!("{}.as", self.name), self.source)?;
+        unit.build()?;
+        Ok(())
+    }
+}
+
+/// Failure of an example.
+#[derive(Debug, Error)]
+pub enum ExampleError {
+    /// The context could not be set up.
+    #[error(transparent)]
+    Context(#[from] ContextError),
+    /// The source could not be added.
+    #[error(transparent)]
+    Unit(#[from] UnitError),
+    /// The script did not build.
+    #[error(transparent)]
+    Build(#[from] BuildError),
+}
+
+/// All examples, simplest first.
+pub fn all() -> &'static [Example] {
+    EXAMPLES
+}
+
+/// Look up an example by name.
+pub fn find(name: &str) -> Option<&'static Example> {
+    EXAMPLES.iter().find(|example| example.name == name)
+}
+
+/// Build every example, returning the result of each by name.
+pub fn run_all() -> Vec<(&'static str, Result<(), ExampleError>)> {
+    EXAMPLES
+        .iter()
+        .map(|example| (example.name, example.run()))
+        .collect()
+}
+
+static EXAMPLES: &[Example] = &[
+    Example {
+        name: "hello",
+        description: "Global functions, variables, string formatting and building",
+        source: HELLO,
+        modules: Vec::new,
+    },
+    Example {
+        name: "host_types",
+        description: "A native value type with properties and methods, and a host constant",
+        source: HOST_TYPES,
+        modules: host_types,
+    },
+    Example {
+        name: "classes",
+        description: "Script classes, interfaces, inheritance and handles",
+        source: CLASSES,
+        modules: Vec::new,
+    },
+    Example {
+        name: "templates",
+        description: "The array<T> and dictionary templates",
+        source: TEMPLATES,
+        modules: Vec::new,
+    },
+    Example {
+        name: "coroutines",
+        description: "Cooperative scheduling through a host-registered yield()",
+        source: COROUTINES,
+        modules: coroutines,
+    },
+];
+
+const HELLO: &str = r#"
+const string GREETING = "Hello";
+
+int greetings = 0;
+
+void greet(const string &in name) {
+    greetings++;
+    println("{}, {}! ({})", GREETING, name, greetings);
+}
+
+void main() {
+    greet("world");
+    greet("AngelScript");
+
+    stringbuilder log;
+    for (int i = 0; i < 3; i++) {
+        log += "line ";
+        log.append(i);
+        log += "\n";
+    }
+    print(log.toString());
+}
+"#;
+
+const HOST_TYPES: &str = r#"
+class Body {
+    Vec2 position;
+    Vec2 velocity;
+
+    void step(float dt) {
+        velocity.y += GRAVITY * dt;
+        position = position + velocity * dt;
+    }
+}
+
+void main() {
+    Body body;
+    body.velocity = Vec2(3, 0);
+    for (int i = 0; i < 10; i++) {
+        body.step(0.1f);
+    }
+    println("fell {} units", body.position.length());
+}
+"#;
+
+const CLASSES: &str = r#"
+interface Shape {
+    float area() const;
+    string name() const;
+}
+
+class Rectangle : Shape {
+    float width;
+    float height;
+
+    Rectangle(float w, float h) { width = w; height = h; }
+
+    float area() const { return width * height; }
+    string name() const { return "rectangle"; }
+}
+
+class Square : Rectangle {
+    Square(float side) { super(side, side); }
+
+    string name() const override { return "square"; }
+}
+
+float total(const array<Shape@> &in shapes) {
+    float sum = 0;
+    for (uint i = 0; i < shapes.length(); i++) {
+        println("{} of area {}", shapes[i].name(), shapes[i].area());
+        sum += shapes[i].area();
+    }
+    return sum;
+}
+
+void main() {
+    array<Shape@> shapes;
+    shapes.insertLast(Rectangle(2, 3));
+    shapes.insertLast(Square(4));
+    Shape@ largest = shapes[1];
+    if (largest !is null) {
+        println("largest is a {}", largest.name());
+    }
+    println("total area {}", total(shapes));
+}
+"#;
+
+const TEMPLATES: &str = r#"
+array<int> squares(int count) {
+    array<int> result;
+    for (int i = 0; i < count; i++) {
+        result.insertLast(i * i);
+    }
+    return result;
+}
+
+void main() {
+    array<int> numbers = squares(5);
+    numbers.reverse();
+
+    dictionary ages;
+    ages.set("alice", 31);
+    ages.set("bob", 27);
+
+    int age = 0;
+    if (ages.get("alice", age)) {
+        println("alice is {}, largest square {}", age, numbers[0]);
+    }
+}
+"#;
+
+const COROUTINES: &str = r#"
+funcdef void Task();
+
+array<Task@> tasks;
+
+void spawn(Task@ task) {
+    tasks.insertLast(task);
+}
+
+void countdown() {
+    for (int i = 3; i > 0; i--) {
+        println("countdown {}", i);
+        yield();
+    }
+}
+
+void blink() {
+    for (int i = 0; i < 3; i++) {
+        println(i % 2 == 0 ? "on" : "off");
+        yield();
+    }
+}
+
+void main() {
+    spawn(countdown);
+    spawn(blink);
+    for (uint i = 0; i < tasks.length(); i++) {
+        tasks[i]();
+    }
+}
+"#;
+
+/// A 2D vector registered as a value type.
+#[derive(Any, Clone, Copy, Debug, Default, PartialEq)]
+#[angelscript(name = "Vec2", value)]
+pub struct Vec2 {
+    /// Horizontal component.
+    #[angelscript(get, set)]
+    pub x: f32,
+    /// Vertical component.
+    #[angelscript(get, set)]
+    pub y: f32,
+}
+
+impl Vec2 {
+    /// Create a vector.
+    #[angelscript_macros::function(constructor)]
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    /// Length of the vector.
+    #[angelscript_macros::function(instance, const)]
+    pub fn length(&self) -> f32 {
+        self.x.hypot(self.y)
+    }
+
+    /// Component-wise sum.
+    #[angelscript_macros::function(operator = Operator::Add, const)]
+    pub fn add(&self, #[param(const, in)] other: &Self) -> Self {
+        Self::new(self.x + other.x, self.y + other.y)
+    }
+
+    /// Scale by a factor.
+    #[angelscript_macros::function(operator = Operator::Mul, const)]
+    pub fn scale(&self, factor: f32) -> Self {
+        Self::new(self.x * factor, self.y * factor)
+    }
+}
+
+fn host_types() -> Vec<Module> {
+    vec![
+        Module::new()
+            .ty::<Vec2>()
+            .function(Vec2::new__meta)
+            .function(Vec2::length__meta)
+            .function(Vec2::add__meta)
+            .function(Vec2::scale__meta)
+            .global("GRAVITY", -9.81f32),
+    ]
+}
+
+/// Suspend the calling coroutine until the host resumes it.
+#[angelscript_macros::function(name = "yield")]
+pub fn yield_now() {}
+
+fn coroutines() -> Vec<Module> {
+    vec![Module::new().function(yield_now)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_examples_build() {
+        for (name, result) in run_all() {
+            if let Err(error) = result {
+                panic!("example '{name}' failed: {error:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn examples_are_found_by_name() {
+        assert_eq!(find("classes").unwrap().name, "classes");
+        assert!(find("missing").is_none());
+        assert_eq!(find("host_types").unwrap().modules().len(), 1);
+    }
+}
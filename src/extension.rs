@@ -0,0 +1,155 @@
+//! Host-defined extension instructions.
+//!
+//! The `EXTENSION` opcode reserves 256 extension ops for embedders. A
+//! [`CompilerPlugin`] emits them for engine-specific fast paths (e.g. fetching
+//! an ECS component without a native call), and the handler registered with
+//! [`Context::register_extension_op`] runs when the VM reaches one.
+//!
+//! Each op declares its stack effect: the VM pops [`ExtensionOp::pops`]
+//! operands and passes them to the handler as arguments, together with the
+//! instruction's 16-bit immediate. If the op [`pushes`](ExtensionOp::pushes),
+//! the value set with [`CallContext::set_return`] is pushed back.
+//!
+//! [`CompilerPlugin`]: crate::CompilerPlugin
+//! [`Context::register_extension_op`]: crate::Context::register_extension_op
+
+use angelscript_core::{CallContext, Dynamic, NativeError, ObjectHeap, catch_native_panic};
+use std::fmt;
+use std::sync::Arc;
+
+/// Handler of an extension op, called with the instruction's immediate.
+pub type ExtensionHandler =
+    Arc<dyn Fn(&mut CallContext<'_>, u16) -> Result<(), NativeError> + Send + Sync>;
+
+/// An extension instruction registered by the host.
+#[derive(Clone)]
+pub struct ExtensionOp {
+    name: String,
+    pops: u8,
+    pushes: bool,
+    handler: ExtensionHandler,
+}
+
+impl ExtensionOp {
+    /// Create an extension op taking `pops` operands from the stack and
+    /// pushing a result if `pushes` is set.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// // Entity@ on the stack, component type id as the immediate
+    /// let fetch = ExtensionOp::new("ecs_fetch", 1, true, |ctx, component| {
+    ///     let entity: u32 = ctx.arg(0)?;
+    ///     ctx.set_return(world().component(entity, component));
+    ///     Ok(())
+    /// });
+    /// ```
+    pub fn new<F>(name: impl Into<String>, pops: u8, pushes: bool, handler: F) -> Self
+    where
+        F: Fn(&mut CallContext<'_>, u16) -> Result<(), NativeError> + Send + Sync + 'static,
+    {
+        Self {
+            name: name.into(),
+            pops,
+            pushes,
+            handler: Arc::new(handler),
+        }
+    }
+
+    /// Name of the op, shown in diagnostics.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of operands popped from the stack.
+    pub fn pops(&self) -> u8 {
+        self.pops
+    }
+
+    /// Whether the op pushes a result.
+    pub fn pushes(&self) -> bool {
+        self.pushes
+    }
+
+    /// Run the handler on `operands`, as the VM does, returning the value to
+    /// push.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the number of operands does not match
+    /// [`pops`](Self::pops), if the handler fails or panics, or if an op that
+    /// pushes did not set a result.
+    pub fn invoke(
+        &self,
+        operands: &mut [Dynamic],
+        immediate: u16,
+        heap: &mut ObjectHeap,
+    ) -> Result<Option<Dynamic>, NativeError> {
+        if operands.len() != self.pops as usize {
+            return Err(NativeError::other(format!(
+                "extension op '{}' takes {} operand(s), got {}",
+                self.name,
+                self.pops,
+                operands.len()
+            )));
+        }
+
+        let mut result = Dynamic::Void;
+        let mut ctx = CallContext::new(operands, 0, &mut result, heap);
+        catch_native_panic(|| (self.handler)(&mut ctx, immediate))?;
+
+        match (self.pushes, result) {
+            (false, _) => Ok(None),
+            (true, Dynamic::Void) => Err(NativeError::other(format!(
+                "extension op '{}' did not produce a result",
+                self.name
+            ))),
+            (true, value) => Ok(Some(value)),
+        }
+    }
+}
+
+impl fmt::Debug for ExtensionOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtensionOp")
+            .field("name", &self.name)
+            .field("pops", &self.pops)
+            .field("pushes", &self.pushes)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_immediate() -> ExtensionOp {
+        ExtensionOp::new("add_imm", 1, true, |ctx, immediate| {
+            let value: i32 = ctx.arg(0)?;
+            ctx.set_return(value + immediate as i32);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn invoke_passes_operands_and_immediate() {
+        let mut heap = ObjectHeap::new();
+        let op = add_immediate();
+
+        let result = op.invoke(&mut [Dynamic::Int(40)], 2, &mut heap).unwrap();
+        assert!(matches!(result, Some(Dynamic::Int(42))));
+    }
+
+    #[test]
+    fn invoke_checks_stack_effect() {
+        let mut heap = ObjectHeap::new();
+        assert!(add_immediate().invoke(&mut [], 0, &mut heap).is_err());
+
+        let silent = ExtensionOp::new("silent", 0, true, |_, _| Ok(()));
+        assert!(silent.invoke(&mut [], 0, &mut heap).is_err());
+
+        let discard = ExtensionOp::new("discard", 1, false, |_, _| Ok(()));
+        let result = discard.invoke(&mut [Dynamic::Int(1)], 0, &mut heap);
+        assert!(matches!(result, Ok(None)));
+    }
+}
@@ -0,0 +1,290 @@
+//! Script global variable storage.
+//!
+//! Each built unit owns a [`GlobalTable`] holding the current value of every
+//! script-declared global variable. Build seeds the table from the compiled
+//! module: each global starts at its type's zero value, and constants at
+//! their folded value. The host accesses it through [`Unit::get_global`]
+//! and [`Unit::set_global`].
+//!
+//! Writes from the host are checked against the declared type of the
+//! variable, so a script `float` cannot be overwritten with a string and a
+//! `const` global cannot be modified.
+//!
+//! [`Unit::get_global`]: crate::Unit::get_global
+//! [`Unit::set_global`]: crate::Unit::set_global
+
+use angelscript_core::{ConversionError, DataType, Dynamic};
+
+use crate::value::{AssignError, check_assignable};
+use rustc_hash::FxHashMap;
+
+/// A script global variable.
+#[derive(Debug)]
+pub(crate) struct GlobalVar {
+    /// Declared name (including namespace, e.g. `game::gravity`).
+    pub name: String,
+    /// Declared type.
+    pub data_type: DataType,
+    /// Current value.
+    pub value: Dynamic,
+}
+
+/// Storage for the global variables of a unit.
+#[derive(Debug, Default)]
+pub(crate) struct GlobalTable {
+    entries: Vec<GlobalVar>,
+    by_name: FxHashMap<String, usize>,
+}
+
+impl GlobalTable {
+    /// Create an empty table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare a global with its initial value, returning its slot index.
+    ///
+    /// Redeclaring an existing name replaces its type and value.
+    pub fn declare(
+        &mut self,
+        name: impl Into<String>,
+        data_type: DataType,
+        value: Dynamic,
+    ) -> usize {
+        let name = name.into();
+        if let Some(&index) = self.by_name.get(&name) {
+            let entry = &mut self.entries[index];
+            entry.data_type = data_type;
+            entry.value = value;
+            return index;
+        }
+
+        let index = self.entries.len();
+        self.by_name.insert(name.clone(), index);
+        self.entries.push(GlobalVar {
+            name,
+            data_type,
+            value,
+        });
+        index
+    }
+
+    /// Look up a global by name.
+    pub fn get(&self, name: &str) -> Option<&GlobalVar> {
+        self.by_name.get(name).map(|&i| &self.entries[i])
+    }
+
+    /// Names of all globals, in declaration order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|e| e.name.as_str())
+    }
+
+    /// Number of declared globals.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Iterate over the current values in declaration order.
+    pub fn values(&self) -> impl Iterator<Item = &Dynamic> {
+        self.entries.iter().map(|e| &e.value)
+    }
+
+    /// Iterate over the globals in declaration order.
+    pub fn vars(&self) -> impl Iterator<Item = &GlobalVar> {
+        self.entries.iter()
+    }
+
+    /// Set a global's value without checking its type, declaring it if
+    /// needed, and return the value it replaces.
+    pub fn replace(&mut self, name: &str, data_type: DataType, value: Dynamic) -> Option<Dynamic> {
+        match self.by_name.get(name) {
+            Some(&index) => Some(std::mem::replace(&mut self.entries[index].value, value)),
+            None => {
+                self.declare(name, data_type, value);
+                None
+            }
+        }
+    }
+
+    /// Consume the table, yielding its globals in declaration order.
+    pub fn into_vars(self) -> impl Iterator<Item = GlobalVar> {
+        self.entries.into_iter()
+    }
+
+    /// Remove all globals.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.by_name.clear();
+    }
+
+    /// Assign a new value to a global, checking it against the declared type.
+    pub fn assign(&mut self, name: &str, value: Dynamic) -> Result<(), GlobalError> {
+        let index = *self
+            .by_name
+            .get(name)
+            .ok_or_else(|| GlobalError::NotFound(name.to_string()))?;
+        let entry = &mut self.entries[index];
+
+        if entry.data_type.is_const {
+            return Err(GlobalError::ReadOnly(name.to_string()));
+        }
+
+        check_assignable(&entry.data_type, &entry.value, &value).map_err(|e| match e {
+            AssignError::Mismatch { expected, actual } => GlobalError::TypeMismatch {
+                name: name.to_string(),
+                expected,
+                actual,
+            },
+            AssignError::Conversion(source) => GlobalError::Conversion {
+                name: name.to_string(),
+                source,
+            },
+        })?;
+        entry.value = value;
+        Ok(())
+    }
+}
+
+/// Errors that can occur when accessing script globals from Rust.
+#[derive(Debug, thiserror::Error)]
+pub enum GlobalError {
+    /// The unit has not been built yet
+    #[error("Unit has not been built")]
+    NotBuilt,
+
+    /// No global with this name exists
+    #[error("Global variable '{0}' not found")]
+    NotFound(String),
+
+    /// The global is declared const
+    #[error("Global variable '{0}' is const and cannot be assigned")]
+    ReadOnly(String),
+
+    /// The value's type does not match the declared type
+    #[error("Type mismatch for global '{name}': expected {expected}, got {actual}")]
+    TypeMismatch {
+        /// Name of the global.
+        name: String,
+        /// Expected value kind.
+        expected: &'static str,
+        /// Actual value kind.
+        actual: &'static str,
+    },
+
+    /// The value could not be converted to or from the requested Rust type
+    #[error("Cannot convert global '{name}': {source}")]
+    Conversion {
+        /// Name of the global.
+        name: String,
+        /// The underlying conversion error.
+        source: ConversionError,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_core::primitives;
+
+    fn table() -> GlobalTable {
+        let mut table = GlobalTable::new();
+        table.declare(
+            "gravity",
+            DataType::simple(primitives::FLOAT),
+            Dynamic::Float(9.81),
+        );
+        table.declare(
+            "lives",
+            DataType::simple(primitives::UINT8),
+            Dynamic::Int(3),
+        );
+        table.declare(
+            "MAX",
+            DataType::with_const(primitives::INT32),
+            Dynamic::Int(10),
+        );
+        table
+    }
+
+    #[test]
+    fn declare_and_lookup() {
+        let table = table();
+        assert_eq!(table.len(), 3);
+        assert!(matches!(
+            table.get("gravity").unwrap().value,
+            Dynamic::Float(_)
+        ));
+        assert_eq!(table.names().nth(1), Some("lives"));
+        assert!(table.get("missing").is_none());
+    }
+
+    #[test]
+    fn redeclare_replaces() {
+        let mut table = table();
+        let index = table.declare(
+            "lives",
+            DataType::simple(primitives::INT32),
+            Dynamic::Int(5),
+        );
+        assert_eq!(index, 1);
+        assert_eq!(table.len(), 3);
+        assert!(matches!(table.get("lives").unwrap().value, Dynamic::Int(5)));
+    }
+
+    #[test]
+    fn assign_checks_type() {
+        let mut table = table();
+        table.assign("gravity", Dynamic::Float(1.62)).unwrap();
+        assert!(matches!(
+            table.assign("gravity", Dynamic::Int(1)),
+            Err(GlobalError::TypeMismatch {
+                expected: "float",
+                actual: "int",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn assign_checks_range() {
+        let mut table = table();
+        table.assign("lives", Dynamic::Int(255)).unwrap();
+        assert!(matches!(
+            table.assign("lives", Dynamic::Int(256)),
+            Err(GlobalError::Conversion { .. })
+        ));
+        assert!(matches!(
+            table.assign("lives", Dynamic::Int(-1)),
+            Err(GlobalError::Conversion { .. })
+        ));
+    }
+
+    #[test]
+    fn assign_const_fails() {
+        let mut table = table();
+        assert!(matches!(
+            table.assign("MAX", Dynamic::Int(1)),
+            Err(GlobalError::ReadOnly(_))
+        ));
+    }
+
+    #[test]
+    fn assign_missing_fails() {
+        let mut table = table();
+        assert!(matches!(
+            table.assign("missing", Dynamic::Int(1)),
+            Err(GlobalError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn handles_accept_null() {
+        let mut table = GlobalTable::new();
+        let mut handle = DataType::simple(primitives::STRING);
+        handle.is_handle = true;
+        table.declare("player", handle, Dynamic::NullHandle);
+
+        table.assign("player", Dynamic::NullHandle).unwrap();
+        assert!(table.assign("player", Dynamic::Int(0)).is_err());
+    }
+}
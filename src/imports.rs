@@ -0,0 +1,176 @@
+//! Functions imported from other units.
+//!
+//! A script declares a function implemented by another module with
+//! `import void spawn(int) from "enemies";`. Building the unit records each
+//! import, unbound. [`Context::bind_imports`] then links the imports against
+//! the units that provide them, matched by the provider's
+//! [name](crate::Unit::set_name), the function name and the parameter count.
+//!
+//! Calling an import that is still unbound is a script exception, as in
+//! AngelScript; binding reports every import it could not resolve so the
+//! host can surface them together.
+//!
+//! [`Context::bind_imports`]: crate::Context::bind_imports
+
+use angelscript_core::{RegistrationError, Span};
+use std::fmt;
+
+/// A function declared with `import ... from "module"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedFunction {
+    name: String,
+    module: String,
+    param_count: usize,
+    file: String,
+    span: Span,
+    binding: Option<usize>,
+}
+
+impl ImportedFunction {
+    pub(crate) fn new(
+        name: impl Into<String>,
+        module: impl Into<String>,
+        param_count: usize,
+        file: impl Into<String>,
+        span: Span,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            module: module.into(),
+            param_count,
+            file: file.into(),
+            span,
+            binding: None,
+        }
+    }
+
+    /// Name of the imported function.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Name of the module the function is imported from.
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+
+    /// Number of declared parameters.
+    pub fn param_count(&self) -> usize {
+        self.param_count
+    }
+
+    /// File containing the import declaration.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// Location of the import declaration.
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Index of the bound function in the providing unit, if bound.
+    pub fn binding(&self) -> Option<usize> {
+        self.binding
+    }
+
+    /// Check if the import has been bound.
+    pub fn is_bound(&self) -> bool {
+        self.binding.is_some()
+    }
+
+    pub(crate) fn bind(&mut self, function: usize) {
+        self.binding = Some(function);
+    }
+
+    pub(crate) fn unbind(&mut self) {
+        self.binding = None;
+    }
+}
+
+/// Why an import could not be bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnresolvedReason {
+    /// No provided unit has the module's name.
+    ModuleNotFound,
+    /// The module has no function with this name and parameter count.
+    FunctionNotFound,
+    /// The module has several functions matching the import.
+    Ambiguous(usize),
+}
+
+/// An import that could not be bound.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedImport {
+    /// Name of the imported function.
+    pub name: String,
+    /// Module the function is imported from.
+    pub module: String,
+    /// File containing the import declaration.
+    pub file: String,
+    /// Location of the import declaration.
+    pub span: Span,
+    /// Why binding failed.
+    pub reason: UnresolvedReason,
+}
+
+impl UnresolvedImport {
+    pub(crate) fn new(import: &ImportedFunction, reason: UnresolvedReason) -> Self {
+        Self {
+            name: import.name.clone(),
+            module: import.module.clone(),
+            file: import.file.clone(),
+            span: import.span,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for UnresolvedImport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.file, self.span)?;
+        match self.reason {
+            UnresolvedReason::ModuleNotFound => {
+                write!(f, "module '{}' not found", self.module)
+            }
+            UnresolvedReason::FunctionNotFound => write!(
+                f,
+                "function '{}' not found in module '{}'",
+                self.name, self.module
+            ),
+            UnresolvedReason::Ambiguous(count) => write!(
+                f,
+                "import of '{}' from '{}' matches {} functions",
+                self.name, self.module, count
+            ),
+        }
+    }
+}
+
+/// Errors that can occur when binding imports.
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    /// A unit has not been built yet
+    #[error("Unit has not been built")]
+    NotBuilt,
+
+    /// A unit was created from a different context
+    #[error("Unit was not created from this context")]
+    ContextMismatch,
+
+    /// Some imports could not be bound; the rest were bound
+    #[error("{} unresolved import(s): {}", .0.len(), format_unresolved(.0))]
+    Unresolved(Vec<UnresolvedImport>),
+
+    /// The template instances of a provider conflict with the unit's
+    #[error("Conflicting template instances: {0}")]
+    Template(RegistrationError),
+}
+
+fn format_unresolved(unresolved: &[UnresolvedImport]) -> String {
+    unresolved
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
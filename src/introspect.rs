@@ -0,0 +1,162 @@
+//! Declarations of compiled functions, as scripts write them.
+//!
+//! The reflection module lets scripts list the global functions of their own
+//! unit and look them up by declaration, e.g. to register every `void
+//! on*(const string &in)` function as an event handler. Both sides need the
+//! same text for a function, built here from its runtime signature:
+//!
+//! ```text
+//! int calc(int, float)
+//! void onSave(const string &in)
+//! game::Player@ spawn(const game::Player@)
+//! ```
+//!
+//! Types are named by their qualified name; parameter names and default
+//! arguments are not part of a declaration. Declarations written by a host
+//! or script are parsed and brought to the same form by [`normalize`], so
+//! spacing, parameter names and `&` versus `&inout` do not matter.
+
+use angelscript_compiler::{CompiledFunction, CompiledModule};
+use angelscript_core::{DataType, RefModifier, TypeHash};
+use angelscript_parser::ast::{FunctionSignatureDecl, RefKind, TypeExpr, TypeSuffix};
+use angelscript_registry::SymbolRegistry;
+
+/// Names the types of a unit: its script classes and enums, and the types
+/// of the registry it was compiled against.
+pub(crate) struct TypeNames<'a> {
+    pub registry: &'a SymbolRegistry,
+    pub module: &'a CompiledModule,
+}
+
+impl TypeNames<'_> {
+    /// Qualified name of a type, or its hash if the unit does not know it.
+    pub fn name(&self, type_hash: TypeHash) -> String {
+        if let Some(entry) = self.registry.get(type_hash) {
+            return entry.qualified_name().to_string();
+        }
+        if let Some(class) = self.module.class_by_hash(type_hash) {
+            return class.name.clone();
+        }
+        self.module
+            .enums
+            .iter()
+            .find(|e| TypeHash::from_name(&e.name) == type_hash)
+            .map_or_else(|| type_hash.to_string(), |e| e.name.clone())
+    }
+
+    /// A type as written in a declaration, e.g. `const Player@ &in`.
+    pub fn data_type(&self, data_type: &DataType) -> String {
+        let name = self.name(data_type.type_hash);
+        let mut text = if data_type.is_handle {
+            format!(
+                "{}{}@{}",
+                if data_type.is_handle_to_const {
+                    "const "
+                } else {
+                    ""
+                },
+                name,
+                if data_type.is_const { " const" } else { "" }
+            )
+        } else if data_type.is_const {
+            format!("const {}", name)
+        } else {
+            name
+        };
+        if data_type.ref_modifier != RefModifier::None {
+            text.push(' ');
+            text.push_str(&data_type.ref_modifier.to_string());
+        }
+        text
+    }
+
+    /// Declaration of a function, e.g. `int calc(int, float)`.
+    pub fn declaration(&self, function: &CompiledFunction) -> String {
+        let params: Vec<String> = function
+            .signature
+            .params
+            .iter()
+            .map(|param| self.data_type(param))
+            .collect();
+        format!(
+            "{} {}({})",
+            self.data_type(&function.signature.return_type),
+            function.name,
+            params.join(", ")
+        )
+    }
+}
+
+/// Indices of the module's global functions, i.e. those that are not
+/// methods or constructors of a class, in declaration order.
+pub(crate) fn global_functions(module: &CompiledModule) -> impl Iterator<Item = usize> + '_ {
+    (0..module.functions.len()).filter(move |&index| {
+        !module.classes.iter().any(|class| {
+            class
+                .methods
+                .iter()
+                .chain(&class.constructors)
+                .any(|method| method.function == index)
+        })
+    })
+}
+
+/// A parsed declaration in the form [`TypeNames::declaration`] produces.
+pub(crate) fn normalize(decl: &FunctionSignatureDecl<'_>) -> String {
+    let params: Vec<String> = decl
+        .params
+        .iter()
+        .map(|param| with_ref(type_expr(&param.ty.ty), param.ty.ref_kind))
+        .collect();
+    let return_kind = if decl.return_type.is_ref {
+        RefKind::Ref
+    } else {
+        RefKind::None
+    };
+    format!(
+        "{} {}({})",
+        with_ref(type_expr(&decl.return_type.ty), return_kind),
+        decl.name.name,
+        params.join(", ")
+    )
+}
+
+fn type_expr(ty: &TypeExpr<'_>) -> String {
+    // Qualified names have no leading `::`
+    let mut name = String::new();
+    for segment in ty.scope.iter().flat_map(|scope| scope.segments) {
+        name.push_str(segment.name);
+        name.push_str("::");
+    }
+    name.push_str(&ty.base.to_string());
+    if !ty.template_args.is_empty() {
+        let args: Vec<String> = ty.template_args.iter().map(type_expr).collect();
+        name = format!("{}<{}>", name, args.join(", "));
+    }
+    let handle = ty.suffixes.iter().next().map(|suffix| match suffix {
+        TypeSuffix::Handle { is_const } => *is_const,
+    });
+    match handle {
+        Some(is_const) => format!(
+            "{}{}@{}",
+            if ty.is_const { "const " } else { "" },
+            name,
+            if is_const { " const" } else { "" }
+        ),
+        None if ty.is_const => format!("const {}", name),
+        None => name,
+    }
+}
+
+fn with_ref(mut text: String, kind: RefKind) -> String {
+    let modifier = match kind {
+        RefKind::None => return text,
+        RefKind::RefIn => RefModifier::In,
+        RefKind::RefOut => RefModifier::Out,
+        // A plain `&` is read-write
+        RefKind::Ref | RefKind::RefInOut => RefModifier::InOut,
+    };
+    text.push(' ');
+    text.push_str(&modifier.to_string());
+    text
+}
@@ -47,6 +47,19 @@
 //! println!("Registered {} types", unit.type_count());
 //! ```
 //!
+//! # Stability
+//!
+//! The embedding API is [`prelude`] plus the items it names, along with the
+//! rest of this page: diagnostics, warnings, access control, compiler
+//! plugins, profiling and tracing. These follow semver — they are only
+//! removed or changed in a breaking release, which CI enforces by diffing
+//! the public API against the base branch with `cargo public-api`.
+//!
+//! Items hidden from these docs exist for the proc macros and the crates of
+//! this workspace. They may change in any release; native types should be
+//! registered through the derive and attribute macros rather than by
+//! building their metadata by hand.
+//!
 //! # Example: Parse Only
 //!
 //! ```ignore
@@ -62,14 +75,99 @@
 //! }
 //! ```
 
+#[cfg(feature = "examples")]
+pub mod examples;
+pub mod prelude;
+
+mod builder;
 mod context;
+mod diagnostic;
+mod extension;
+mod globals;
+mod imports;
+mod introspect;
+mod preprocess;
+mod profiler;
+mod reload;
+mod resource;
+mod script_object;
+mod serializer;
+mod trace;
 mod unit;
+mod usage;
+mod validate;
+mod value;
 
 // Re-export compilation unit API (recommended for most users)
 pub use unit::{BuildError, Unit, UnitError};
 
+// Re-export global variable access errors
+pub use globals::GlobalError;
+
+// Re-export cross-unit import API
+pub use imports::{ImportError, ImportedFunction, UnresolvedImport, UnresolvedReason};
+
+// Re-export hot reload API
+pub use reload::{ClassChanges, MigrationIssue, ReloadReport};
+pub use serializer::{Serializer, UserType};
+
+// Re-export script object API
+pub use script_object::{ScriptError, ScriptObject};
+
 // Re-export context API
-pub use context::{Context, ContextError};
+pub use builder::ContextBuilder;
+pub use context::{Context, ContextError, DiscardError, DiscardReason, Strictness};
+
+// Re-export diagnostic types
+pub use diagnostic::{Diagnostic, DiagnosticHandler, Severity};
+
+// Re-export extension instruction API
+pub use extension::{ExtensionHandler, ExtensionOp};
+
+// Re-export preprocessing API
+pub use preprocess::{LineOrigin, SourceInfo, SourceMap};
+
+// Re-export per-section language options
+pub use angelscript_parser::directives::{Extension, SectionOptions};
+
+// Re-export runtime function signatures
+pub use angelscript_compiler::FunctionSignature;
+
+// Re-export integer overflow semantics
+pub use angelscript_compiler::IntegerOverflow;
+
+// Re-export compiler options
+pub use angelscript_compiler::CompilerOptions;
+pub use angelscript_compiler::bytecode::OptimizationLevel;
+
+// Re-export allocation failure handling
+pub use angelscript_core::{MemoryBudget, OutOfMemory};
+
+// Re-export compiler plugin API
+pub use angelscript_compiler::{CompilerPlugin, PluginContext, PluginFunction};
+
+// Re-export resource handle API
+pub use angelscript_modules::resource::{ResId, ResourceResolver};
+pub use resource::ResourcePaths;
+
+// Re-export API usage statistics
+pub use usage::{ApiUse, UsageReport, UsageStats};
+
+// Re-export call argument validation API
+pub use angelscript_compiler::ConstValue;
+pub use validate::{CallValidator, CallValidators};
+
+// Re-export access control API
+pub use angelscript_compiler::{AccessMask, AccessMasks};
+
+// Re-export compiler warning types
+pub use angelscript_compiler::{Warning, WarningCode, WarningConfig, WarningLevel};
+
+// Re-export profiling API
+pub use profiler::{FunctionProfile, OpCategoryProfile, ProfileReport, Profiler};
+
+// Re-export execution tracing API
+pub use trace::{TraceEvent, TraceEventKind, TraceRecorder};
 
 // Re-export error types from core for unified error handling
 pub use angelscript_core::{
@@ -80,33 +178,27 @@ pub use angelscript_core::{
 // Re-export common types
 pub use angelscript_core::{ReferenceKind, TypeKind};
 
-// Re-export types needed for proc macros
+// Re-export value conversion traits
+pub use angelscript_core::{
+    Dynamic, FromDynamic, IntoArgs, IntoDynamic, ObjectHandle, ScriptCallable, ScriptDispatch,
+    ScriptProxy,
+};
+
+// Re-export native function types for the generic calling convention
+pub use angelscript_core::{Any, CallArgs, CallContext, NativeError, StringFactory, TypeHash};
+
+// Re-export metadata types needed for proc macros
+#[doc(hidden)]
 pub use angelscript_core::{
-    Any,
-    Behavior,
-    // Native function types for generic calling convention
-    CallContext,
-    ClassMeta,
-    FuncdefMeta,
-    FunctionMeta,
-    GenericParamMeta,
-    InterfaceMeta,
-    InterfaceMethodMeta,
-    ListPatternMeta,
-    Operator,
-    ParamMeta,
-    PropertyMeta,
-    RefModifier,
-    ReturnMeta,
-    // Enhanced function metadata types
-    ReturnMode,
-    // String factory trait
-    StringFactory,
-    TypeHash,
+    Behavior, ClassMeta, FuncdefMeta, FunctionMeta, GenericParamMeta, InterfaceMeta,
+    InterfaceMethodMeta, ListPatternMeta, Operator, ParamMeta, PropertyMeta, RefModifier,
+    ReturnMeta, ReturnMode,
 };
 
 // Re-export proc macros
 pub use angelscript_macros::{Any, funcdef, function, interface};
 
 // Re-export Module and registry types
-pub use angelscript_registry::{HasClassMeta, HasFunctionMeta, Module, SymbolRegistry};
+pub use angelscript_registry::Module;
+#[doc(hidden)]
+pub use angelscript_registry::{HasClassMeta, HasFunctionMeta, SymbolRegistry};
@@ -0,0 +1,35 @@
+//! The types most embedders need, for a single glob import.
+//!
+//! ```ignore
+//! use angelscript::prelude::*;
+//! use std::sync::Arc;
+//!
+//! let mut ctx = Context::with_default_modules()?;
+//! ctx.install(Module::new().ty::<Player>().function(spawn))?;
+//! let ctx = Arc::new(ctx);
+//!
+//! let mut unit = ctx.create_unit()?;
+//! unit.add_source("main.as", source)?;
+//! unit.build()?;
+//! ```
+//!
+//! Everything exported here is covered by the crate's semver policy (see
+//! the [crate documentation](crate#stability)): it is only removed or
+//! changed in a breaking release. The prelude is not extended with names
+//! likely to clash with an embedder's own, so a glob import stays safe
+//! across minor releases.
+
+// Embedding
+pub use crate::{Context, Module, Strictness, Unit};
+
+// Script objects and callables
+pub use crate::{ScriptCallable, ScriptObject};
+
+// Values passed to and from scripts
+pub use crate::{Dynamic, FromDynamic, IntoArgs, IntoDynamic};
+
+// Registering native types and functions
+pub use crate::{Any, funcdef, function, interface};
+
+// Errors
+pub use crate::{AngelScriptError, BuildError, ContextError, GlobalError, ScriptError, UnitError};
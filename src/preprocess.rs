@@ -0,0 +1,200 @@
+//! Source preprocessing hook.
+//!
+//! A unit can be given a preprocessor that rewrites each source file before it
+//! is parsed. This enables host-side code generation, such as expanding a
+//! `STATE_MACHINE(...)` DSL block into ordinary class declarations.
+//!
+//! Because the parser only sees the expanded text, every preprocessed file gets
+//! a [`SourceMap`] that translates line numbers back to the original source.
+//! Parse and compilation diagnostics are remapped through it, so errors point
+//! at the line the user actually wrote.
+//!
+//! # Example
+//!
+//! ```ignore
+//! unit.set_preprocessor(|source, info| {
+//!     if info.filename.ends_with(".fsm.as") {
+//!         expand_state_machines(source)
+//!     } else {
+//!         source.to_string()
+//!     }
+//! });
+//! ```
+
+use std::sync::Arc;
+
+use angelscript_core::Span;
+
+/// Maximum number of original lines searched when aligning an expanded line.
+const ALIGN_LOOKAHEAD: usize = 64;
+
+/// A source transform applied before parsing.
+pub(crate) type Preprocessor = Arc<dyn Fn(&str, &SourceInfo<'_>) -> String + Send + Sync>;
+
+/// Information about the source file being preprocessed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourceInfo<'a> {
+    /// The filename the source was added under.
+    pub filename: &'a str,
+}
+
+/// Where a line of preprocessed source came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineOrigin {
+    /// Line in the original source (1-indexed).
+    pub line: u32,
+    /// Whether the line was produced by the preprocessor rather than copied.
+    pub generated: bool,
+}
+
+/// Maps lines of preprocessed source back to the original source.
+///
+/// The map is built by aligning the expanded text against the original:
+/// lines copied through unchanged map to themselves, and lines introduced by
+/// the preprocessor map to the original line they replaced.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceMap {
+    /// Origin of each expanded line, indexed by `line - 1`.
+    lines: Vec<LineOrigin>,
+}
+
+impl SourceMap {
+    /// Build a source map by aligning `expanded` against `original`.
+    pub fn from_expansion(original: &str, expanded: &str) -> Self {
+        let original: Vec<&str> = original.lines().collect();
+        let mut cursor = 0;
+        let mut lines = Vec::new();
+
+        for text in expanded.lines() {
+            let window = cursor..original.len().min(cursor + ALIGN_LOOKAHEAD);
+            let found = original[window.clone()]
+                .iter()
+                .position(|o| *o == text)
+                .map(|i| window.start + i);
+
+            let origin = match found {
+                Some(index) => {
+                    cursor = index + 1;
+                    LineOrigin {
+                        line: index as u32 + 1,
+                        generated: false,
+                    }
+                }
+                None => LineOrigin {
+                    line: cursor.min(original.len().saturating_sub(1)) as u32 + 1,
+                    generated: true,
+                },
+            };
+            lines.push(origin);
+        }
+
+        Self { lines }
+    }
+
+    /// Number of lines in the expanded source.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Check if the expanded source was empty.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Look up the origin of an expanded line (1-indexed).
+    pub fn origin(&self, line: u32) -> Option<LineOrigin> {
+        (line as usize)
+            .checked_sub(1)
+            .and_then(|i| self.lines.get(i))
+            .copied()
+    }
+
+    /// Translate an expanded line number to the original source.
+    ///
+    /// Lines outside the map are returned unchanged.
+    pub fn original_line(&self, line: u32) -> u32 {
+        self.origin(line).map_or(line, |o| o.line)
+    }
+
+    /// Translate a span in the expanded source to the original source.
+    ///
+    /// Spans on generated lines are moved to the start of the original line
+    /// they were expanded from, since their columns have no counterpart.
+    pub fn map_span(&self, span: Span) -> Span {
+        match self.origin(span.line) {
+            Some(LineOrigin {
+                line,
+                generated: false,
+            }) => Span::new(line, span.col, span.len),
+            Some(LineOrigin {
+                line,
+                generated: true,
+            }) => Span::point(line, 1),
+            None => span,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_expansion() {
+        let source = "void main() {\n    int x = 1;\n}\n";
+        let map = SourceMap::from_expansion(source, source);
+
+        assert_eq!(map.len(), 3);
+        for line in 1..=3 {
+            assert_eq!(
+                map.origin(line),
+                Some(LineOrigin {
+                    line,
+                    generated: false
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn generated_lines_map_to_macro() {
+        let original = "int a;\nSTATE_MACHINE(Door)\nint b;\n";
+        let expanded = "int a;\nclass Door {\n    int state;\n}\nint b;\n";
+        let map = SourceMap::from_expansion(original, expanded);
+
+        assert_eq!(map.original_line(1), 1);
+        assert_eq!(map.original_line(2), 2);
+        assert_eq!(map.original_line(3), 2);
+        assert_eq!(map.original_line(4), 2);
+        assert_eq!(map.original_line(5), 3);
+        assert!(map.origin(3).unwrap().generated);
+        assert!(!map.origin(5).unwrap().generated);
+    }
+
+    #[test]
+    fn removed_lines_are_skipped() {
+        let original = "// header\n// more\nvoid f() {}\n";
+        let expanded = "void f() {}\n";
+        let map = SourceMap::from_expansion(original, expanded);
+
+        assert_eq!(map.original_line(1), 3);
+    }
+
+    #[test]
+    fn map_span() {
+        let original = "int a;\nEXPAND\nint b;\n";
+        let expanded = "int a;\nint generated;\nint b;\n";
+        let map = SourceMap::from_expansion(original, expanded);
+
+        assert_eq!(map.map_span(Span::new(3, 5, 1)), Span::new(3, 5, 1));
+        assert_eq!(map.map_span(Span::new(2, 5, 9)), Span::point(2, 1));
+        assert_eq!(map.map_span(Span::new(10, 1, 1)), Span::new(10, 1, 1));
+    }
+
+    #[test]
+    fn expansion_past_end() {
+        let map = SourceMap::from_expansion("int a;\n", "int a;\nint b;\nint c;\n");
+        assert_eq!(map.original_line(3), 1);
+        assert!(map.origin(3).unwrap().generated);
+    }
+}
@@ -0,0 +1,324 @@
+//! Script execution profiler.
+//!
+//! The profiler records per-function call counts and timings, plus per-opcode
+//! category execution counts. It is driven by the VM through the `enter_function`,
+//! `exit_function` and `record_op` hooks, and queried by the host afterwards
+//! through a [`ProfileReport`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! unit.set_profiling(true);
+//! unit.call("main", &[])?;
+//!
+//! let report = unit.profile_report().unwrap();
+//! for func in report.hottest_functions(5) {
+//!     println!("{}: {} calls, {:?} self", func.name, func.calls, func.self_time);
+//! }
+//! ```
+//!
+//! # External profilers
+//!
+//! With the `profiling` feature, builds and script calls are also reported as
+//! scopes through the [`profiling`](https://docs.rs/profiling) crate. Script
+//! calls open a `script_call` scope labelled `unit::function`, so their cost
+//! shows up next to engine code. The backend is chosen by the host, e.g. to
+//! feed an existing `tracing` subscriber pipeline (Tracy, Perfetto):
+//!
+//! ```toml
+//! angelscript = { version = "0.1", features = ["profiling"] }
+//! profiling = { version = "1", features = ["profile-with-tracing"] }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use angelscript_compiler::bytecode::{OpCategory, OpCode};
+use rustc_hash::FxHashMap;
+
+/// Records timing and call counts during script execution.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    /// Per-function statistics, keyed by function name.
+    functions: FxHashMap<String, FunctionProfile>,
+    /// Per-category opcode statistics.
+    ops: FxHashMap<OpCategory, OpCategoryProfile>,
+    /// Active call frames (innermost last).
+    stack: Vec<ActiveFrame>,
+}
+
+/// A function currently executing under the profiler.
+#[derive(Debug)]
+struct ActiveFrame {
+    name: String,
+    started: Instant,
+    /// Time spent in callees, subtracted to compute self time.
+    child_time: Duration,
+}
+
+/// Profiling statistics for a single script function.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FunctionProfile {
+    /// Function name.
+    pub name: String,
+    /// Number of completed calls.
+    pub calls: u64,
+    /// Total time including callees.
+    pub total_time: Duration,
+    /// Time spent in the function itself, excluding callees.
+    pub self_time: Duration,
+}
+
+impl FunctionProfile {
+    /// Average total time per call.
+    pub fn average_time(&self) -> Duration {
+        if self.calls == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total_time.as_nanos() / u128::from(self.calls)) as u64)
+        }
+    }
+}
+
+/// Profiling statistics for an opcode category.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpCategoryProfile {
+    /// Number of executed instructions in this category.
+    pub count: u64,
+    /// Time attributed to this category (only when the VM samples timings).
+    pub time: Duration,
+}
+
+impl Profiler {
+    /// Create a new, empty profiler.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record entry into a script function.
+    pub fn enter_function(&mut self, name: &str) {
+        self.stack.push(ActiveFrame {
+            name: name.to_string(),
+            started: Instant::now(),
+            child_time: Duration::ZERO,
+        });
+    }
+
+    /// Record exit from the innermost script function.
+    ///
+    /// Unbalanced exits (with no active frame) are ignored.
+    pub fn exit_function(&mut self) {
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+
+        let elapsed = frame.started.elapsed();
+        if let Some(parent) = self.stack.last_mut() {
+            parent.child_time += elapsed;
+        }
+
+        let entry = self
+            .functions
+            .entry(frame.name)
+            .or_insert_with_key(|name| FunctionProfile {
+                name: name.clone(),
+                ..Default::default()
+            });
+        entry.calls += 1;
+        entry.total_time += elapsed;
+        entry.self_time += elapsed.saturating_sub(frame.child_time);
+    }
+
+    /// Record execution of a single instruction.
+    pub fn record_op(&mut self, op: OpCode) {
+        self.ops.entry(op.category()).or_default().count += 1;
+    }
+
+    /// Record execution of a single instruction with a measured duration.
+    pub fn record_op_timed(&mut self, op: OpCode, elapsed: Duration) {
+        let entry = self.ops.entry(op.category()).or_default();
+        entry.count += 1;
+        entry.time += elapsed;
+    }
+
+    /// Current call depth of the profiled script.
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    /// Discard all collected statistics.
+    pub fn reset(&mut self) {
+        self.functions.clear();
+        self.ops.clear();
+        self.stack.clear();
+    }
+
+    /// Build a report from the statistics collected so far.
+    ///
+    /// Functions still on the call stack are not included.
+    pub fn report(&self) -> ProfileReport {
+        let mut functions: Vec<FunctionProfile> = self.functions.values().cloned().collect();
+        functions.sort_by(|a, b| b.total_time.cmp(&a.total_time).then(a.name.cmp(&b.name)));
+
+        let ops = OpCategory::ALL
+            .iter()
+            .filter_map(|cat| self.ops.get(cat).map(|p| (*cat, *p)))
+            .collect();
+
+        ProfileReport { functions, ops }
+    }
+}
+
+/// Snapshot of profiling statistics.
+#[derive(Debug, Clone, Default)]
+pub struct ProfileReport {
+    /// Function statistics, sorted by descending total time.
+    functions: Vec<FunctionProfile>,
+    /// Opcode category statistics, in category order.
+    ops: Vec<(OpCategory, OpCategoryProfile)>,
+}
+
+impl ProfileReport {
+    /// All function statistics, sorted by descending total time.
+    pub fn functions(&self) -> &[FunctionProfile] {
+        &self.functions
+    }
+
+    /// Look up statistics for a function by name.
+    pub fn function(&self, name: &str) -> Option<&FunctionProfile> {
+        self.functions.iter().find(|f| f.name == name)
+    }
+
+    /// The `n` functions with the highest self time.
+    pub fn hottest_functions(&self, n: usize) -> Vec<&FunctionProfile> {
+        let mut sorted: Vec<&FunctionProfile> = self.functions.iter().collect();
+        sorted.sort_by(|a, b| b.self_time.cmp(&a.self_time).then(a.name.cmp(&b.name)));
+        sorted.truncate(n);
+        sorted
+    }
+
+    /// Opcode category statistics, in category order.
+    pub fn op_categories(&self) -> &[(OpCategory, OpCategoryProfile)] {
+        &self.ops
+    }
+
+    /// Statistics for a single opcode category.
+    pub fn op_category(&self, category: OpCategory) -> OpCategoryProfile {
+        self.ops
+            .iter()
+            .find(|(c, _)| *c == category)
+            .map(|(_, p)| *p)
+            .unwrap_or_default()
+    }
+
+    /// Total number of executed instructions.
+    pub fn total_ops(&self) -> u64 {
+        self.ops.iter().map(|(_, p)| p.count).sum()
+    }
+
+    /// Total number of completed function calls.
+    pub fn total_calls(&self) -> u64 {
+        self.functions.iter().map(|f| f.calls).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_calls() {
+        let mut profiler = Profiler::new();
+        for _ in 0..3 {
+            profiler.enter_function("update");
+            profiler.exit_function();
+        }
+
+        let report = profiler.report();
+        assert_eq!(report.function("update").unwrap().calls, 3);
+        assert_eq!(report.total_calls(), 3);
+    }
+
+    #[test]
+    fn self_time_excludes_callees() {
+        let mut profiler = Profiler::new();
+        profiler.enter_function("outer");
+        profiler.enter_function("inner");
+        std::thread::sleep(Duration::from_millis(5));
+        profiler.exit_function();
+        profiler.exit_function();
+
+        let report = profiler.report();
+        let outer = report.function("outer").unwrap();
+        let inner = report.function("inner").unwrap();
+        assert!(outer.total_time >= inner.total_time);
+        assert!(outer.self_time < inner.self_time);
+        assert_eq!(report.hottest_functions(1)[0].name, "inner");
+    }
+
+    #[test]
+    fn unbalanced_exit_is_ignored() {
+        let mut profiler = Profiler::new();
+        profiler.exit_function();
+        assert!(profiler.report().functions().is_empty());
+    }
+
+    #[test]
+    fn open_frames_not_reported() {
+        let mut profiler = Profiler::new();
+        profiler.enter_function("main");
+        assert_eq!(profiler.depth(), 1);
+        assert!(profiler.report().function("main").is_none());
+    }
+
+    #[test]
+    fn records_op_categories() {
+        let mut profiler = Profiler::new();
+        profiler.record_op(OpCode::Add);
+        profiler.record_op(OpCode::Sub);
+        profiler.record_op(OpCode::Call);
+        profiler.record_op_timed(OpCode::GetLocal, Duration::from_nanos(10));
+
+        let report = profiler.report();
+        assert_eq!(report.op_category(OpCategory::Arithmetic).count, 2);
+        assert_eq!(report.op_category(OpCategory::Call).count, 1);
+        assert_eq!(
+            report.op_category(OpCategory::Local).time,
+            Duration::from_nanos(10)
+        );
+        assert_eq!(report.op_category(OpCategory::Bitwise).count, 0);
+        assert_eq!(report.total_ops(), 4);
+    }
+
+    #[test]
+    fn reset_clears_stats() {
+        let mut profiler = Profiler::new();
+        profiler.enter_function("f");
+        profiler.exit_function();
+        profiler.record_op(OpCode::Pop);
+        profiler.reset();
+
+        let report = profiler.report();
+        assert!(report.functions().is_empty());
+        assert_eq!(report.total_ops(), 0);
+    }
+
+    #[test]
+    fn average_time() {
+        let profile = FunctionProfile {
+            name: "f".into(),
+            calls: 4,
+            total_time: Duration::from_millis(8),
+            self_time: Duration::from_millis(8),
+        };
+        assert_eq!(profile.average_time(), Duration::from_millis(2));
+        assert_eq!(FunctionProfile::default().average_time(), Duration::ZERO);
+
+        // Call counts past u32 are not truncated
+        let profile = FunctionProfile {
+            calls: 1 << 32,
+            total_time: Duration::from_secs(1 << 32),
+            ..profile
+        };
+        assert_eq!(profile.average_time(), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,641 @@
+//! State migration for hot reload.
+//!
+//! [`Unit::rebuild`] recompiles a unit in place. Global variables and live
+//! script objects created by the previous module are carried over to the new
+//! one when their declarations are still compatible:
+//!
+//! - a global keeps its value if the new module declares a global with the
+//!   same name and type; otherwise it is dropped and the new initializer runs
+//! - an object is moved to the new layout of its class; fields are matched by
+//!   name and type, new or changed fields start at their default value and
+//!   removed fields are discarded
+//! - a field declared with `[renamedFrom("old")]` takes the value of the
+//!   field `old`, unless the new layout still has a field of that name:
+//!
+//! ```angelscript
+//! class Player {
+//!     [renamedFrom("hp")] int health;   // keeps the value of `hp`
+//! }
+//! ```
+//!
+//! - an object whose class no longer exists cannot be migrated and is left
+//!   untouched, so the host can release it
+//!
+//! How the fields of each class changed is listed in the returned
+//! [`ReloadReport`], along with everything that could not be carried over.
+//!
+//! [`Unit::rebuild`]: crate::Unit::rebuild
+
+use angelscript_compiler::{CompiledClass, CompiledModule};
+use angelscript_core::{DataType, Dynamic, ObjectHandle, ObjectHeap, TypeHash};
+use rustc_hash::FxHashMap;
+use std::fmt;
+
+use crate::globals::GlobalTable;
+use crate::script_object::ScriptObjectData;
+use crate::value::default_value;
+
+/// State that could not be carried over by a rebuild.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationIssue {
+    /// The global is no longer declared.
+    GlobalRemoved {
+        /// Name of the global.
+        name: String,
+    },
+    /// The global is declared with a different type; its value was dropped.
+    GlobalTypeChanged {
+        /// Name of the global.
+        name: String,
+    },
+    /// The object's class no longer exists; the object was not migrated.
+    ClassRemoved {
+        /// Heap handle of the object.
+        object: ObjectHandle,
+        /// Name of the removed class.
+        class: String,
+    },
+    /// The field changed type; it was reset to its default value.
+    FieldReset {
+        /// Heap handle of the object.
+        object: ObjectHandle,
+        /// Name of the class.
+        class: String,
+        /// Name of the field.
+        field: String,
+    },
+    /// The field was removed from the class; its value was discarded.
+    FieldRemoved {
+        /// Heap handle of the object.
+        object: ObjectHandle,
+        /// Name of the class.
+        class: String,
+        /// Name of the field in the old layout.
+        field: String,
+    },
+    /// The value of a global or field (`Class::field`) could not be stored
+    /// by a [`Serializer`]; it kept its new initial value.
+    ///
+    /// [`Serializer`]: crate::Serializer
+    ValueNotStored {
+        /// Name of the global or field.
+        name: String,
+    },
+}
+
+impl fmt::Display for MigrationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GlobalRemoved { name } => write!(f, "global '{}' was removed", name),
+            Self::GlobalTypeChanged { name } => write!(f, "global '{}' changed type", name),
+            Self::ClassRemoved { class, .. } => {
+                write!(f, "object of removed class '{}' was not migrated", class)
+            }
+            Self::FieldReset { class, field, .. } => {
+                write!(f, "field '{}::{}' changed type and was reset", class, field)
+            }
+            Self::FieldRemoved { class, field, .. } => {
+                write!(
+                    f,
+                    "field '{}::{}' was removed and its value discarded",
+                    class, field
+                )
+            }
+            Self::ValueNotStored { name } => write!(f, "value of '{}' could not be stored", name),
+        }
+    }
+}
+
+/// How the fields of a class changed between two builds.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClassChanges {
+    /// Name of the class.
+    pub class: String,
+    /// Fields of the new layout without an old value; they start at their
+    /// default value.
+    pub added: Vec<String>,
+    /// Fields of the old layout without a new one; their values are
+    /// discarded.
+    pub removed: Vec<String>,
+    /// Fields that kept their value under a new name, as (old, new).
+    pub renamed: Vec<(String, String)>,
+    /// Fields that changed type; their values are reset.
+    pub retyped: Vec<String>,
+}
+
+impl ClassChanges {
+    /// Check if the layout did not change.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.retyped.is_empty()
+    }
+}
+
+/// Outcome of migrating state to a rebuilt module.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Number of globals that kept their value.
+    pub migrated_globals: usize,
+    /// Number of objects moved to the new class layouts.
+    pub migrated_objects: usize,
+    /// Classes whose fields changed, and how.
+    pub class_changes: Vec<ClassChanges>,
+    /// State that could not be fully carried over.
+    pub issues: Vec<MigrationIssue>,
+}
+
+impl ReloadReport {
+    /// Check if all state was carried over.
+    pub fn is_complete(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Handles of the objects that could not be migrated.
+    pub fn unmigrated_objects(&self) -> impl Iterator<Item = ObjectHandle> + '_ {
+        self.issues.iter().filter_map(|issue| match issue {
+            MigrationIssue::ClassRemoved { object, .. } => Some(*object),
+            _ => None,
+        })
+    }
+}
+
+/// Where the fields of a class's new layout take their values from.
+pub(crate) struct FieldMapping {
+    /// For each new field, the index of the old field whose value it keeps.
+    pub sources: Vec<Option<usize>>,
+    pub changes: ClassChanges,
+}
+
+impl FieldMapping {
+    /// Match the `old` fields, as names and types, to the fields of `new`
+    /// by name, or by the name they were renamed from.
+    pub fn new<'a>(
+        old: impl IntoIterator<Item = (&'a str, DataType)>,
+        new: &CompiledClass,
+    ) -> Self {
+        let old: Vec<_> = old.into_iter().collect();
+        let mut used = vec![false; old.len()];
+        let mut changes = ClassChanges {
+            class: new.name.clone(),
+            ..Default::default()
+        };
+        let position = |name: &str| old.iter().position(|(old_name, _)| *old_name == name);
+
+        let mut sources = Vec::with_capacity(new.fields.len());
+        for field in &new.fields {
+            let renamed = || {
+                let from = field.renamed_from.as_deref()?;
+                // A field still declared under the old name keeps its value
+                if new.field_index(from).is_some() {
+                    return None;
+                }
+                position(from)
+            };
+            let index = match position(&field.name) {
+                Some(index) => index,
+                None => match renamed().filter(|&index| !used[index]) {
+                    Some(index) => {
+                        changes
+                            .renamed
+                            .push((old[index].0.to_string(), field.name.clone()));
+                        index
+                    }
+                    None => {
+                        changes.added.push(field.name.clone());
+                        sources.push(None);
+                        continue;
+                    }
+                },
+            };
+            used[index] = true;
+            if old[index].1 == field.data_type {
+                sources.push(Some(index));
+            } else {
+                changes.retyped.push(field.name.clone());
+                sources.push(None);
+            }
+        }
+
+        changes.removed = old
+            .iter()
+            .zip(&used)
+            .filter(|(_, used)| !**used)
+            .map(|((name, _), _)| name.to_string())
+            .collect();
+        Self { sources, changes }
+    }
+
+    /// The issues of an object moved with this mapping.
+    pub fn issues(&self, object: ObjectHandle) -> impl Iterator<Item = MigrationIssue> + '_ {
+        let class = &self.changes.class;
+        let reset = self
+            .changes
+            .retyped
+            .iter()
+            .map(move |field| MigrationIssue::FieldReset {
+                object,
+                class: class.clone(),
+                field: field.clone(),
+            });
+        let removed = self
+            .changes
+            .removed
+            .iter()
+            .map(move |field| MigrationIssue::FieldRemoved {
+                object,
+                class: class.clone(),
+                field: field.clone(),
+            });
+        reset.chain(removed)
+    }
+}
+
+/// Carry state from `old` over to `new`.
+///
+/// `old_globals` is the global table of the previous module; compatible
+/// values are declared into `globals`, replacing the new initial values.
+/// Constants keep their new values.
+pub(crate) fn migrate(
+    old: &CompiledModule,
+    new: &CompiledModule,
+    old_globals: GlobalTable,
+    globals: &mut GlobalTable,
+    heap: &mut ObjectHeap,
+) -> ReloadReport {
+    let mut report = ReloadReport::default();
+
+    for var in old_globals.into_vars() {
+        match new.global(&var.name) {
+            // Constants take their value from the new source
+            Some(decl) if decl.data_type.is_const && decl.data_type == var.data_type => {
+                release_value(heap, var.value);
+            }
+            Some(decl) if decl.data_type == var.data_type => {
+                globals.declare(var.name, var.data_type, var.value);
+                report.migrated_globals += 1;
+            }
+            Some(_) => {
+                release_value(heap, var.value);
+                report
+                    .issues
+                    .push(MigrationIssue::GlobalTypeChanged { name: var.name });
+            }
+            None => {
+                release_value(heap, var.value);
+                report
+                    .issues
+                    .push(MigrationIssue::GlobalRemoved { name: var.name });
+            }
+        }
+    }
+
+    let mut mappings: FxHashMap<TypeHash, FieldMapping> = FxHashMap::default();
+    for new_class in &new.classes {
+        if let Some(old_class) = old.class_by_hash(new_class.type_hash) {
+            let old_fields = old_class
+                .fields
+                .iter()
+                .map(|field| (field.name.as_str(), field.data_type));
+            let mapping = FieldMapping::new(old_fields, new_class);
+            if !mapping.changes.is_empty() {
+                report.class_changes.push(mapping.changes.clone());
+            }
+            mappings.insert(new_class.type_hash, mapping);
+        }
+    }
+
+    for handle in heap.handles_of::<ScriptObjectData>() {
+        let Some(type_hash) = heap
+            .get::<ScriptObjectData>(handle)
+            .map(|data| data.type_hash)
+        else {
+            continue;
+        };
+        // Objects of classes the previous module did not declare are not ours
+        let Some(old_class) = old.class_by_hash(type_hash) else {
+            continue;
+        };
+        let (Some(new_class), Some(mapping)) =
+            (new.class_by_hash(type_hash), mappings.get(&type_hash))
+        else {
+            report.issues.push(MigrationIssue::ClassRemoved {
+                object: handle,
+                class: old_class.name.clone(),
+            });
+            continue;
+        };
+
+        let mut old_fields = match heap.get_mut::<ScriptObjectData>(handle) {
+            Some(data) => std::mem::take(&mut data.fields),
+            None => continue,
+        };
+
+        let fields = new_class
+            .fields
+            .iter()
+            .zip(&mapping.sources)
+            .map(|(field, source)| match source {
+                Some(index) => std::mem::replace(&mut old_fields[*index], Dynamic::Void),
+                None => default_value(&field.data_type),
+            })
+            .collect();
+        report.issues.extend(mapping.issues(handle));
+
+        if let Some(data) = heap.get_mut::<ScriptObjectData>(handle) {
+            data.fields = fields;
+        }
+        for value in old_fields {
+            release_value(heap, value);
+        }
+        report.migrated_objects += 1;
+    }
+
+    report
+}
+
+/// Drop a reference held by a value that is discarded.
+pub(crate) fn release_value(heap: &mut ObjectHeap, value: Dynamic) {
+    if let Dynamic::Object(handle) = value {
+        heap.release(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_compiler::{CompiledClass, CompiledField, CompiledGlobal};
+    use angelscript_core::{DataType, TypeHash, primitives};
+
+    fn field(name: &str, type_hash: TypeHash) -> CompiledField {
+        CompiledField {
+            name: name.into(),
+            data_type: DataType::simple(type_hash),
+            renamed_from: None,
+        }
+    }
+
+    fn class(name: &str, fields: Vec<CompiledField>) -> CompiledClass {
+        CompiledClass {
+            name: name.into(),
+            type_hash: TypeHash::from_name(name),
+            bases: Vec::new(),
+            interfaces: Vec::new(),
+            fields,
+            constructors: Vec::new(),
+            methods: Vec::new(),
+        }
+    }
+
+    fn global(name: &str, type_hash: TypeHash) -> CompiledGlobal {
+        CompiledGlobal {
+            name: name.into(),
+            data_type: DataType::simple(type_hash),
+        }
+    }
+
+    fn object(heap: &mut ObjectHeap, class: &CompiledClass, fields: Vec<Dynamic>) -> ObjectHandle {
+        heap.allocate(ScriptObjectData {
+            type_hash: class.type_hash,
+            fields,
+        })
+    }
+
+    #[test]
+    fn globals_keep_compatible_values() {
+        let old = CompiledModule {
+            globals: vec![
+                global("score", primitives::INT32),
+                global("speed", primitives::FLOAT),
+                global("name", primitives::STRING),
+            ],
+            ..Default::default()
+        };
+        let new = CompiledModule {
+            globals: vec![
+                global("score", primitives::INT32),
+                global("speed", primitives::INT32),
+                global("lives", primitives::INT32),
+            ],
+            ..Default::default()
+        };
+
+        let mut old_globals = GlobalTable::new();
+        old_globals.declare("score", old.globals[0].data_type, Dynamic::Int(420));
+        old_globals.declare("speed", old.globals[1].data_type, Dynamic::Float(1.5));
+        old_globals.declare(
+            "name",
+            old.globals[2].data_type,
+            Dynamic::String("a".into()),
+        );
+
+        let mut globals = GlobalTable::new();
+        globals.declare("score", new.globals[0].data_type, Dynamic::Int(0));
+        let mut heap = ObjectHeap::new();
+
+        let report = migrate(&old, &new, old_globals, &mut globals, &mut heap);
+
+        assert_eq!(report.migrated_globals, 1);
+        assert!(matches!(
+            globals.get("score").unwrap().value,
+            Dynamic::Int(420)
+        ));
+        assert!(globals.get("speed").is_none());
+        assert_eq!(
+            report.issues,
+            vec![
+                MigrationIssue::GlobalTypeChanged {
+                    name: "speed".into()
+                },
+                MigrationIssue::GlobalRemoved {
+                    name: "name".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn objects_are_remapped_to_new_layout() {
+        let old_player = class(
+            "Player",
+            vec![
+                field("health", primitives::INT32),
+                field("speed", primitives::FLOAT),
+                field("tag", primitives::STRING),
+            ],
+        );
+        let new_player = class(
+            "Player",
+            vec![
+                field("armor", primitives::INT32),
+                field("health", primitives::INT32),
+                field("speed", primitives::INT32),
+            ],
+        );
+        let old = CompiledModule {
+            classes: vec![old_player.clone()],
+            ..Default::default()
+        };
+        let new = CompiledModule {
+            classes: vec![new_player],
+            ..Default::default()
+        };
+
+        let mut heap = ObjectHeap::new();
+        let player = object(
+            &mut heap,
+            &old_player,
+            vec![
+                Dynamic::Int(75),
+                Dynamic::Float(2.5),
+                Dynamic::String("hero".into()),
+            ],
+        );
+
+        let report = migrate(
+            &old,
+            &new,
+            GlobalTable::new(),
+            &mut GlobalTable::new(),
+            &mut heap,
+        );
+
+        assert_eq!(report.migrated_objects, 1);
+        let fields = &heap.get::<ScriptObjectData>(player).unwrap().fields;
+        assert!(matches!(
+            fields.as_slice(),
+            [Dynamic::Int(0), Dynamic::Int(75), Dynamic::Int(0)]
+        ));
+        assert_eq!(
+            report.issues,
+            vec![
+                MigrationIssue::FieldReset {
+                    object: player,
+                    class: "Player".into(),
+                    field: "speed".into(),
+                },
+                MigrationIssue::FieldRemoved {
+                    object: player,
+                    class: "Player".into(),
+                    field: "tag".into(),
+                },
+            ]
+        );
+        assert_eq!(
+            report.class_changes,
+            vec![ClassChanges {
+                class: "Player".into(),
+                added: vec!["armor".into()],
+                removed: vec!["tag".into()],
+                renamed: Vec::new(),
+                retyped: vec!["speed".into()],
+            }]
+        );
+    }
+
+    #[test]
+    fn renamed_fields_keep_their_values() {
+        let renamed = |name: &str, from: &str| CompiledField {
+            renamed_from: Some(from.into()),
+            ..field(name, primitives::INT32)
+        };
+        let old_player = class(
+            "Player",
+            vec![
+                field("hp", primitives::INT32),
+                field("mana", primitives::INT32),
+                field("xp", primitives::INT32),
+            ],
+        );
+        let new_player = class(
+            "Player",
+            vec![
+                renamed("health", "hp"),
+                // The old name is still declared, so it keeps its value
+                renamed("energy", "mana"),
+                field("mana", primitives::INT32),
+                // Already taken by `health`
+                renamed("life", "hp"),
+                field("xp", primitives::INT32),
+            ],
+        );
+        let unchanged = class("Enemy", vec![field("hp", primitives::INT32)]);
+        let old = CompiledModule {
+            classes: vec![old_player.clone(), unchanged.clone()],
+            ..Default::default()
+        };
+        let new = CompiledModule {
+            classes: vec![new_player, unchanged],
+            ..Default::default()
+        };
+
+        let mut heap = ObjectHeap::new();
+        let player = object(
+            &mut heap,
+            &old_player,
+            vec![Dynamic::Int(75), Dynamic::Int(30), Dynamic::Int(900)],
+        );
+
+        let report = migrate(
+            &old,
+            &new,
+            GlobalTable::new(),
+            &mut GlobalTable::new(),
+            &mut heap,
+        );
+
+        assert!(report.is_complete());
+        let fields = &heap.get::<ScriptObjectData>(player).unwrap().fields;
+        assert!(matches!(
+            fields.as_slice(),
+            [
+                Dynamic::Int(75),
+                Dynamic::Int(0),
+                Dynamic::Int(30),
+                Dynamic::Int(0),
+                Dynamic::Int(900)
+            ]
+        ));
+        assert_eq!(
+            report.class_changes,
+            vec![ClassChanges {
+                class: "Player".into(),
+                added: vec!["energy".into(), "life".into()],
+                removed: Vec::new(),
+                renamed: vec![("hp".into(), "health".into())],
+                retyped: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn objects_of_removed_classes_are_reported() {
+        let enemy = class("Enemy", vec![field("hp", primitives::INT32)]);
+        let old = CompiledModule {
+            classes: vec![enemy.clone()],
+            ..Default::default()
+        };
+        let new = CompiledModule::default();
+
+        let mut heap = ObjectHeap::new();
+        let handle = object(&mut heap, &enemy, vec![Dynamic::Int(5)]);
+
+        let report = migrate(
+            &old,
+            &new,
+            GlobalTable::new(),
+            &mut GlobalTable::new(),
+            &mut heap,
+        );
+
+        assert!(!report.is_complete());
+        assert_eq!(report.migrated_objects, 0);
+        assert_eq!(
+            report.unmigrated_objects().collect::<Vec<_>>(),
+            vec![handle]
+        );
+        // The object is left alive for the host to release
+        assert!(heap.get::<ScriptObjectData>(handle).is_some());
+    }
+}
@@ -0,0 +1,154 @@
+//! Compile-time resolution of resource paths.
+//!
+//! [`ResourcePaths`] is a [`CompilerPlugin`] that resolves the literal paths
+//! passed to the `load` function of the resource module through the host's
+//! [`ResourceResolver`]. A path the resolver rejects fails the build:
+//!
+//! ```text
+//! ResId icon = load("ui/icon.png");   // resolved while compiling
+//! ResId tex = load(name + ".png");    // resolved at runtime
+//! ```
+//!
+//! Install it with [`ContextBuilder::with_resource_resolver`], or keep an
+//! `Arc` to read the resolved ids with [`ResourcePaths::resolved`].
+//!
+//! [`ContextBuilder::with_resource_resolver`]: crate::ContextBuilder::with_resource_resolver
+
+use std::sync::{Mutex, PoisonError};
+
+use angelscript_compiler::{CompilerPlugin, PluginContext};
+use angelscript_core::Span;
+use angelscript_modules::resource::{ResId, ResourceResolver};
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{CallExpr, Expr, LiteralKind, Script};
+use rustc_hash::FxHashMap;
+
+/// Resolves the literal paths passed to `load` while compiling.
+pub struct ResourcePaths {
+    resolver: Box<dyn ResourceResolver>,
+    resolved: Mutex<FxHashMap<String, ResId>>,
+}
+
+impl ResourcePaths {
+    /// Create the plugin resolving paths with `resolver`.
+    pub fn new(resolver: impl ResourceResolver + 'static) -> Self {
+        Self {
+            resolver: Box::new(resolver),
+            resolved: Mutex::new(FxHashMap::default()),
+        }
+    }
+
+    /// Get the id a literal path resolved to in any compiled unit.
+    pub fn resolved(&self, path: &str) -> Option<ResId> {
+        self.resolved
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(path)
+            .copied()
+    }
+}
+
+impl CompilerPlugin for ResourcePaths {
+    fn name(&self) -> &str {
+        "resources"
+    }
+
+    fn after_registration(&self, ctx: &mut PluginContext<'_>, script: &Script<'_>) {
+        let mut paths = LoadPaths { paths: Vec::new() };
+        paths.visit_script(script);
+
+        let mut resolved = self.resolved.lock().unwrap_or_else(PoisonError::into_inner);
+        for (path, span) in paths.paths {
+            if resolved.contains_key(&path) {
+                continue;
+            }
+            match self.resolver.resolve(&path) {
+                Ok(id) => {
+                    resolved.insert(path, id);
+                }
+                Err(reason) => ctx.error(format!("cannot load '{}': {}", path, reason), span),
+            }
+        }
+    }
+}
+
+/// Collects the literal paths of `load("...")` calls.
+struct LoadPaths {
+    paths: Vec<(String, Span)>,
+}
+
+impl<'ast> Visitor<'ast> for LoadPaths {
+    fn visit_call_expr(&mut self, expr: &CallExpr<'ast>) {
+        if let Expr::Ident(callee) = expr.callee
+            && callee.scope.is_none()
+            && callee.ident.name == "load"
+            && let [arg] = expr.args
+            && let Expr::Literal(literal) = arg.value
+            && let LiteralKind::String(bytes) = &literal.kind
+        {
+            let path = String::from_utf8_lossy(bytes).into_owned();
+            self.paths.push((path, literal.span));
+        }
+        visitor::walk_call_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildError, Context};
+    use angelscript_core::CompilationError;
+    use std::sync::Arc;
+
+    fn assets(path: &str) -> Result<ResId, String> {
+        match path {
+            "ui/icon.png" => Ok(ResId::new(1)),
+            "levels/intro.map" => Ok(ResId::new(2)),
+            _ => Err("no such asset".to_string()),
+        }
+    }
+
+    fn build(source: &str) -> (Arc<ResourcePaths>, Result<(), BuildError>) {
+        let paths = Arc::new(ResourcePaths::new(assets));
+        let ctx = Context::builder()
+            .with_default_modules()
+            .install(angelscript_modules::resource::module())
+            .plugin(Box::new(paths.clone()))
+            .build_shared()
+            .unwrap();
+        let mut unit = ctx.create_unit().unwrap();
+        unit.add_source("test.as", source).unwrap();
+        let result = unit.build();
+        (paths, result)
+    }
+
+    #[test]
+    fn literal_paths_resolve_while_compiling() {
+        let (paths, result) = build(
+            r#"
+            ResId icon = load("ui/icon.png");
+            void enter(const string &in name) {
+                ResId level = load("levels/intro.map");
+                ResId other = load(name);
+            }
+            "#,
+        );
+        result.unwrap();
+        assert_eq!(paths.resolved("ui/icon.png"), Some(ResId::new(1)));
+        assert_eq!(paths.resolved("levels/intro.map"), Some(ResId::new(2)));
+    }
+
+    #[test]
+    fn unknown_paths_fail_the_build() {
+        let (paths, result) = build(r#"void f() { ResId tex = load("ui/missing.png"); }"#);
+        let Err(BuildError::CompilationErrors(errors)) = result else {
+            panic!("expected the build to fail");
+        };
+        assert!(matches!(
+            &errors[..],
+            [CompilationError::Other { message, .. }]
+                if message == "[resources] cannot load 'ui/missing.png': no such asset"
+        ));
+        assert_eq!(paths.resolved("ui/missing.png"), None);
+    }
+}
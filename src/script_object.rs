@@ -0,0 +1,226 @@
+//! Script class instances owned by the host.
+//!
+//! Script objects live in the unit's object heap like any other reference
+//! type. A [`ScriptObject`] is the host's counted reference to one: it is
+//! created by [`Unit::instantiate`], duplicated with [`Unit::retain_object`]
+//! and given back with [`Unit::release_object`]. Following the runtime
+//! design, the handle is a plain index into the heap; all access goes
+//! through the owning [`Unit`].
+//!
+//! # Example
+//!
+//! ```ignore
+//! let player = unit.instantiate("Player", (100,))?;
+//! unit.call_method::<_, ()>(&player, "takeDamage", (25,))?;
+//! let health: i32 = unit.get_field(&player, "health")?;
+//! unit.release_object(player);
+//! ```
+//!
+//! [`Unit`]: crate::Unit
+//! [`Unit::instantiate`]: crate::Unit::instantiate
+//! [`Unit::retain_object`]: crate::Unit::retain_object
+//! [`Unit::release_object`]: crate::Unit::release_object
+
+use angelscript_core::{
+    ConversionError, Dynamic, NativeError, ObjectHandle, ParseError, RuntimeError, TypeHash, UnitId,
+};
+
+/// Heap representation of a script class instance.
+#[derive(Debug)]
+pub(crate) struct ScriptObjectData {
+    /// Type hash of the instantiated class.
+    pub type_hash: TypeHash,
+    /// Field values, in the class layout's slot order.
+    pub fields: Vec<Dynamic>,
+}
+
+/// A host-held reference to a script class instance.
+///
+/// Each `ScriptObject` accounts for one reference on the object. It is
+/// deliberately not `Clone`: use [`Unit::retain_object`] to take another
+/// reference and [`Unit::release_object`] to drop one.
+///
+/// [`Unit::retain_object`]: crate::Unit::retain_object
+/// [`Unit::release_object`]: crate::Unit::release_object
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScriptObject {
+    handle: ObjectHandle,
+    type_hash: TypeHash,
+    unit: UnitId,
+}
+
+impl ScriptObject {
+    pub(crate) fn new(handle: ObjectHandle, type_hash: TypeHash, unit: UnitId) -> Self {
+        Self {
+            handle,
+            type_hash,
+            unit,
+        }
+    }
+
+    /// The heap handle of the object, for passing to scripts as `Dynamic::Object`.
+    pub fn handle(&self) -> ObjectHandle {
+        self.handle
+    }
+
+    /// The type hash of the object's class.
+    pub fn type_hash(&self) -> TypeHash {
+        self.type_hash
+    }
+
+    /// The unit that created the object and holds it in its heap.
+    pub fn unit(&self) -> UnitId {
+        self.unit
+    }
+}
+
+/// Errors that can occur when working with script objects and functions from Rust.
+#[derive(Debug, thiserror::Error)]
+pub enum ScriptError {
+    /// The unit has not been built yet
+    #[error("Unit has not been built")]
+    NotBuilt,
+
+    /// No script class with this name exists
+    #[error("Script class '{0}' not found")]
+    ClassNotFound(String),
+
+    /// No global script function with this name exists
+    #[error("Script function '{0}' not found")]
+    FunctionNotFound(String),
+
+    /// A function declaration could not be parsed
+    #[error("Invalid function declaration '{decl}': {error}")]
+    InvalidDeclaration {
+        /// The declaration as given.
+        decl: String,
+        /// The first parse error.
+        error: ParseError,
+    },
+
+    /// No constructor takes the given number of arguments
+    #[error("Script class '{class}' has no constructor taking {arg_count} argument(s)")]
+    NoMatchingConstructor {
+        /// Class name.
+        class: String,
+        /// Number of arguments supplied.
+        arg_count: usize,
+    },
+
+    /// No method with this name takes the given number of arguments
+    #[error("Script class '{class}' has no method '{method}' taking {arg_count} argument(s)")]
+    MethodNotFound {
+        /// Class name.
+        class: String,
+        /// Method name.
+        method: String,
+        /// Number of arguments supplied.
+        arg_count: usize,
+    },
+
+    /// A function handle was invoked with the wrong number of arguments
+    #[error("Script function '{name}' takes {expected} argument(s), got {actual}")]
+    ArgumentCountMismatch {
+        /// Function name.
+        name: String,
+        /// Number of declared parameters.
+        expected: usize,
+        /// Number of arguments supplied.
+        actual: usize,
+    },
+
+    /// A function handle's signature differs from the funcdef it was
+    /// converted to
+    #[error("Script function '{function}' does not match funcdef '{funcdef}'")]
+    FuncdefMismatch {
+        /// Function name.
+        function: String,
+        /// Qualified funcdef name.
+        funcdef: String,
+    },
+
+    /// The class does not implement the interface a proxy was requested for
+    #[error("Script class '{class}' does not implement interface {interface}")]
+    InterfaceNotImplemented {
+        /// Class name.
+        class: String,
+        /// Type hash of the interface.
+        interface: TypeHash,
+    },
+
+    /// No field with this name exists
+    #[error("Script class '{class}' has no field '{field}'")]
+    FieldNotFound {
+        /// Class name.
+        class: String,
+        /// Field name.
+        field: String,
+    },
+
+    /// The object or function handle has been released or belongs to a different unit
+    #[error("Script object is no longer valid")]
+    InvalidObject,
+
+    /// The value's type does not match the declared type
+    #[error("Type mismatch for '{name}': expected {expected}, got {actual}")]
+    TypeMismatch {
+        /// Name of the field or function.
+        name: String,
+        /// Expected value kind.
+        expected: &'static str,
+        /// Actual value kind.
+        actual: &'static str,
+    },
+
+    /// A value could not be converted to or from the requested Rust type
+    #[error("Cannot convert '{name}': {source}")]
+    Conversion {
+        /// Name of the field or function.
+        name: String,
+        /// The underlying conversion error.
+        source: ConversionError,
+    },
+
+    /// The function has a script body but the unit cannot execute bytecode
+    #[error("Cannot execute script function '{0}': bytecode execution is not available")]
+    ExecutionUnavailable(String),
+
+    /// The script raised an exception it did not catch
+    #[error("Script exception: {0}")]
+    Exception(String),
+
+    /// A native function called by the script failed or panicked
+    #[error("Native call failed: {0}")]
+    Native(NativeError),
+
+    /// A value was rejected at runtime, e.g. a handle passed as a type its
+    /// object is not
+    #[error("Runtime error: {0}")]
+    Runtime(#[from] RuntimeError),
+}
+
+impl From<NativeError> for ScriptError {
+    /// Script exceptions unwinding through native frames stay exceptions.
+    fn from(err: NativeError) -> Self {
+        match err {
+            NativeError::Exception { message } => ScriptError::Exception(message),
+            other => ScriptError::Native(other),
+        }
+    }
+}
+
+impl From<ScriptError> for NativeError {
+    /// Errors of a call back into script, as seen by the native caller.
+    ///
+    /// Exceptions stay exceptions so they continue to unwind into the script
+    /// frame that called the native function.
+    fn from(err: ScriptError) -> Self {
+        match err {
+            ScriptError::Exception(message) => NativeError::Exception { message },
+            ScriptError::Native(native) => native,
+            other => NativeError::Other {
+                message: other.to_string(),
+            },
+        }
+    }
+}
@@ -0,0 +1,670 @@
+//! Capturing the state of a unit to restore it into another.
+//!
+//! A [`Serializer`] copies the values of a unit's global variables, and of
+//! the script objects reachable from them, into a store that no longer
+//! depends on the unit. The store can then be restored into a unit built
+//! from new sources, or into the same unit after [`Unit::clear`] and a new
+//! build:
+//!
+//! ```ignore
+//! let mut serializer = Serializer::new();
+//! serializer.add_extra_object(&player);
+//! serializer.store(&unit)?;
+//!
+//! let mut unit = ctx.create_unit()?;
+//! unit.add_source("game.as", new_source)?;
+//! unit.build()?;
+//! let report = serializer.restore(&mut unit)?;
+//! let player = serializer.take_restored_object(&player).unwrap();
+//! ```
+//!
+//! Globals and fields are matched by name and type as in [`Unit::rebuild`],
+//! fields following `[renamedFrom]` metadata, and handles that shared an
+//! object still share its restored copy. Native values and registered
+//! reference types are only stored if a [`UserType`] was added for their
+//! type; other values are reported as [`MigrationIssue::ValueNotStored`] and
+//! keep their new initial value.
+//!
+//! [`Unit::clear`]: crate::Unit::clear
+//! [`Unit::rebuild`]: crate::Unit::rebuild
+
+use angelscript_compiler::CompiledModule;
+use angelscript_core::{DataType, Dynamic, ObjectHandle, ObjectHeap, TypeHash, UnitId};
+use rustc_hash::FxHashMap;
+use std::any::Any;
+use std::fmt;
+
+use crate::globals::{GlobalError, GlobalTable};
+use crate::reload::{FieldMapping, MigrationIssue, ReloadReport, release_value};
+use crate::script_object::{ScriptObject, ScriptObjectData};
+use crate::unit::Unit;
+use crate::value::default_value;
+
+/// How a [`Serializer`] copies the values of a registered type.
+///
+/// ```ignore
+/// struct Vec3Type;
+///
+/// impl UserType for Vec3Type {
+///     fn store(&self, value: &Dynamic, _heap: &ObjectHeap) -> Option<Box<dyn Any + Send + Sync>> {
+///         match value {
+///             Dynamic::Native(v) => v.downcast_ref::<Vec3>().map(|v| Box::new(*v) as _),
+///             _ => None,
+///         }
+///     }
+///
+///     fn restore(&self, stored: &(dyn Any + Send + Sync), _heap: &mut ObjectHeap) -> Dynamic {
+///         Dynamic::Native(Box::new(*stored.downcast_ref::<Vec3>().unwrap()))
+///     }
+/// }
+///
+/// serializer.add_user_type(TypeHash::from_name("vec3"), Vec3Type);
+/// ```
+pub trait UserType: Send + Sync {
+    /// Copy `value`, reading any object it refers to from `heap`, or return
+    /// `None` if it cannot be stored.
+    fn store(&self, value: &Dynamic, heap: &ObjectHeap) -> Option<Box<dyn Any + Send + Sync>>;
+
+    /// Recreate a value from what [`store`](Self::store) returned,
+    /// allocating any object it needs in `heap`.
+    fn restore(&self, stored: &(dyn Any + Send + Sync), heap: &mut ObjectHeap) -> Dynamic;
+}
+
+/// A stored copy of a value.
+enum StoredValue {
+    /// A value without references, never `Object` or `Native`.
+    Plain(Dynamic),
+    /// A handle to the stored object at this index.
+    Object(usize),
+    /// A value copied by the user type of its type.
+    User(TypeHash, Box<dyn Any + Send + Sync>),
+}
+
+/// A stored global variable or field.
+struct StoredVar {
+    name: String,
+    data_type: DataType,
+    /// `None` if the value could not be stored.
+    value: Option<StoredValue>,
+}
+
+/// A stored script object.
+struct StoredObject {
+    /// Handle of the object in the unit it was stored from.
+    handle: ObjectHandle,
+    class: String,
+    fields: Vec<StoredVar>,
+}
+
+/// Stores the global variables and script objects of a unit and restores
+/// them into another.
+#[derive(Default)]
+pub struct Serializer {
+    user_types: FxHashMap<TypeHash, Box<dyn UserType>>,
+    /// Objects the host asked to store, by their handle in the stored unit.
+    extra_objects: Vec<ObjectHandle>,
+    globals: Vec<StoredVar>,
+    objects: Vec<StoredObject>,
+    /// Indices in `objects` of the stored extra objects.
+    extras: Vec<usize>,
+    /// Restored extra objects, by their handle in the stored unit.
+    restored: FxHashMap<ObjectHandle, ScriptObject>,
+}
+
+impl Serializer {
+    /// Create a serializer with an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy values of the type `type_hash` with `user_type`.
+    pub fn add_user_type(&mut self, type_hash: TypeHash, user_type: impl UserType + 'static) {
+        self.user_types.insert(type_hash, Box::new(user_type));
+    }
+
+    /// Also store `object`, which the host holds, with the next
+    /// [`store`](Self::store).
+    ///
+    /// Its restored copy is handed back by
+    /// [`take_restored_object`](Self::take_restored_object).
+    pub fn add_extra_object(&mut self, object: &ScriptObject) {
+        self.extra_objects.push(object.handle());
+    }
+
+    /// Store the global variables of `unit` and the script objects they and
+    /// the extra objects refer to, replacing what was stored before.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the unit has not been built.
+    pub fn store(&mut self, unit: &Unit) -> Result<(), GlobalError> {
+        let (module, globals, heap) = unit.state().ok_or(GlobalError::NotBuilt)?;
+        self.store_state(module, globals, heap);
+        Ok(())
+    }
+
+    fn store_state(&mut self, module: &CompiledModule, globals: &GlobalTable, heap: &ObjectHeap) {
+        let mut store = Store {
+            module,
+            heap,
+            user_types: &self.user_types,
+            objects: Vec::new(),
+            indices: FxHashMap::default(),
+        };
+        self.globals = globals
+            .vars()
+            .map(|var| StoredVar {
+                name: var.name.clone(),
+                data_type: var.data_type,
+                value: store.value(&var.data_type, &var.value),
+            })
+            .collect();
+        self.extras = self
+            .extra_objects
+            .iter()
+            .filter_map(|&handle| store.object(handle))
+            .collect();
+        self.objects = store.objects;
+    }
+
+    /// Restore the stored state into `unit`, which must have been built.
+    ///
+    /// Stored globals replace the values of the globals of the same name
+    /// and type, and stored objects are recreated with the new layouts of
+    /// their classes. Everything that could not be restored is listed in
+    /// the returned report.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the unit has not been built.
+    pub fn restore(&mut self, unit: &mut Unit) -> Result<ReloadReport, GlobalError> {
+        let unit_id = unit.id();
+        let (module, globals, heap) = unit.state_mut().ok_or(GlobalError::NotBuilt)?;
+        Ok(self.restore_state(module, globals, heap, unit_id))
+    }
+
+    fn restore_state(
+        &mut self,
+        module: &CompiledModule,
+        globals: &mut GlobalTable,
+        heap: &mut ObjectHeap,
+        unit_id: UnitId,
+    ) -> ReloadReport {
+        let mut report = ReloadReport::default();
+
+        // Allocate every object first so handles between them resolve
+        let mut handles = Vec::with_capacity(self.objects.len());
+        for object in &self.objects {
+            let Some(class) = module.class(&object.class) else {
+                report.issues.push(MigrationIssue::ClassRemoved {
+                    object: object.handle,
+                    class: object.class.clone(),
+                });
+                handles.push(None);
+                continue;
+            };
+            let handle = heap.allocate(ScriptObjectData {
+                type_hash: class.type_hash,
+                fields: class
+                    .fields
+                    .iter()
+                    .map(|field| default_value(&field.data_type))
+                    .collect(),
+            });
+            handles.push(Some((handle, class.type_hash)));
+        }
+
+        let mut restore = Restore {
+            heap,
+            user_types: &self.user_types,
+            handles: &handles,
+        };
+
+        // Layouts are compared once per class
+        let mut mappings: FxHashMap<&str, FieldMapping> = FxHashMap::default();
+        for (object, &handle) in self.objects.iter().zip(&handles) {
+            let (Some((handle, _)), Some(class)) = (handle, module.class(&object.class)) else {
+                continue;
+            };
+            let mapping = mappings.entry(&object.class).or_insert_with(|| {
+                let stored = object
+                    .fields
+                    .iter()
+                    .map(|field| (field.name.as_str(), field.data_type));
+                let mapping = FieldMapping::new(stored, class);
+                if !mapping.changes.is_empty() {
+                    report.class_changes.push(mapping.changes.clone());
+                }
+                mapping
+            });
+            for (index, source) in mapping.sources.iter().enumerate() {
+                let Some(stored) = source.map(|source| &object.fields[source]) else {
+                    continue;
+                };
+                let Some(value) = restore.value(stored) else {
+                    report.issues.push(MigrationIssue::ValueNotStored {
+                        name: format!("{}::{}", class.name, class.fields[index].name),
+                    });
+                    continue;
+                };
+                let old = restore
+                    .heap
+                    .get_mut::<ScriptObjectData>(handle)
+                    .map(|data| std::mem::replace(&mut data.fields[index], value));
+                if let Some(old) = old {
+                    release_value(restore.heap, old);
+                }
+            }
+            report.issues.extend(mapping.issues(handle));
+            report.migrated_objects += 1;
+        }
+
+        for stored in &self.globals {
+            match module.global(&stored.name) {
+                Some(decl) if decl.data_type == stored.data_type => {
+                    let Some(value) = restore.value(stored) else {
+                        report.issues.push(MigrationIssue::ValueNotStored {
+                            name: stored.name.clone(),
+                        });
+                        continue;
+                    };
+                    if let Some(old) = globals.replace(&stored.name, stored.data_type, value) {
+                        release_value(restore.heap, old);
+                    }
+                    report.migrated_globals += 1;
+                }
+                Some(_) => report.issues.push(MigrationIssue::GlobalTypeChanged {
+                    name: stored.name.clone(),
+                }),
+                None => report.issues.push(MigrationIssue::GlobalRemoved {
+                    name: stored.name.clone(),
+                }),
+            }
+        }
+
+        // The host takes over the allocation reference of extra objects;
+        // the others are only kept alive by the restored values
+        self.restored.clear();
+        let mut kept = vec![false; handles.len()];
+        for &index in &self.extras {
+            if let Some((handle, type_hash)) = handles[index]
+                && !kept[index]
+            {
+                kept[index] = true;
+                self.restored.insert(
+                    self.objects[index].handle,
+                    ScriptObject::new(handle, type_hash, unit_id),
+                );
+            }
+        }
+        for (&handle, kept) in handles.iter().zip(kept) {
+            if let (Some((handle, _)), false) = (handle, kept) {
+                restore.heap.release(handle);
+            }
+        }
+
+        report
+    }
+
+    /// Take the restored copy of an object added with
+    /// [`add_extra_object`](Self::add_extra_object).
+    ///
+    /// Returns `None` if the object was not restored, or was already taken.
+    /// Restored objects that are never taken stay alive until their unit is
+    /// cleared.
+    pub fn take_restored_object(&mut self, object: &ScriptObject) -> Option<ScriptObject> {
+        self.restored.remove(&object.handle())
+    }
+
+    /// Number of stored global variables.
+    pub fn global_count(&self) -> usize {
+        self.globals.len()
+    }
+
+    /// Number of stored script objects.
+    pub fn object_count(&self) -> usize {
+        self.objects.len()
+    }
+}
+
+impl fmt::Debug for Serializer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Serializer")
+            .field("user_types", &self.user_types.len())
+            .field("globals", &self.globals.len())
+            .field("objects", &self.objects.len())
+            .finish()
+    }
+}
+
+/// Copies values out of a unit.
+struct Store<'a> {
+    module: &'a CompiledModule,
+    heap: &'a ObjectHeap,
+    user_types: &'a FxHashMap<TypeHash, Box<dyn UserType>>,
+    objects: Vec<StoredObject>,
+    /// Indices in `objects` of the objects already stored.
+    indices: FxHashMap<ObjectHandle, usize>,
+}
+
+impl Store<'_> {
+    fn value(&mut self, data_type: &DataType, value: &Dynamic) -> Option<StoredValue> {
+        if let Some(user_type) = self.user_types.get(&data_type.type_hash) {
+            return user_type
+                .store(value, self.heap)
+                .map(|stored| StoredValue::User(data_type.type_hash, stored));
+        }
+        match value {
+            Dynamic::Object(handle) => self.object(*handle).map(StoredValue::Object),
+            Dynamic::Native(_) => None,
+            plain => plain.clone_if_possible().map(StoredValue::Plain),
+        }
+    }
+
+    /// Store the script object `handle`, returning its index.
+    fn object(&mut self, handle: ObjectHandle) -> Option<usize> {
+        if let Some(&index) = self.indices.get(&handle) {
+            return Some(index);
+        }
+        let (heap, module) = (self.heap, self.module);
+        let data = heap.get::<ScriptObjectData>(handle)?;
+        let class = module.class_by_hash(data.type_hash)?;
+
+        // Recorded before the fields so cycles end here
+        let index = self.objects.len();
+        self.indices.insert(handle, index);
+        self.objects.push(StoredObject {
+            handle,
+            class: class.name.clone(),
+            fields: Vec::new(),
+        });
+        let fields = class
+            .fields
+            .iter()
+            .zip(&data.fields)
+            .map(|(field, value)| StoredVar {
+                name: field.name.clone(),
+                data_type: field.data_type,
+                value: self.value(&field.data_type, value),
+            })
+            .collect();
+        self.objects[index].fields = fields;
+        Some(index)
+    }
+}
+
+/// Recreates stored values in a unit.
+struct Restore<'a> {
+    heap: &'a mut ObjectHeap,
+    user_types: &'a FxHashMap<TypeHash, Box<dyn UserType>>,
+    /// Restored objects by their index in the store; `None` if the class
+    /// no longer exists.
+    handles: &'a [Option<(ObjectHandle, TypeHash)>],
+}
+
+impl Restore<'_> {
+    /// Recreate the value of `stored`, taking a reference to any object it
+    /// refers to.
+    fn value(&mut self, stored: &StoredVar) -> Option<Dynamic> {
+        match stored.value.as_ref()? {
+            StoredValue::Plain(value) => value.clone_if_possible(),
+            StoredValue::Object(index) => Some(match self.handles[*index] {
+                Some((handle, _)) => {
+                    self.heap.add_ref(handle);
+                    Dynamic::Object(handle)
+                }
+                None => Dynamic::NullHandle,
+            }),
+            StoredValue::User(type_hash, value) => self
+                .user_types
+                .get(type_hash)
+                .map(|user_type| user_type.restore(value.as_ref(), self.heap)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use angelscript_compiler::{CompiledClass, CompiledField, CompiledGlobal};
+    use angelscript_core::primitives;
+
+    fn data_type(name: &str) -> DataType {
+        DataType::simple(TypeHash::from_name(name))
+    }
+
+    fn field(name: &str, data_type: DataType) -> CompiledField {
+        CompiledField {
+            name: name.into(),
+            data_type,
+            renamed_from: None,
+        }
+    }
+
+    fn class(name: &str, fields: Vec<CompiledField>) -> CompiledClass {
+        CompiledClass {
+            name: name.into(),
+            type_hash: TypeHash::from_name(name),
+            bases: Vec::new(),
+            interfaces: Vec::new(),
+            fields,
+            constructors: Vec::new(),
+            methods: Vec::new(),
+        }
+    }
+
+    fn global(name: &str, data_type: DataType) -> CompiledGlobal {
+        CompiledGlobal {
+            name: name.into(),
+            data_type,
+        }
+    }
+
+    fn node_module(value_type: TypeHash) -> CompiledModule {
+        CompiledModule {
+            classes: vec![class(
+                "Node",
+                vec![
+                    field("value", DataType::simple(value_type)),
+                    field("next", data_type("Node")),
+                ],
+            )],
+            globals: vec![
+                global("head", data_type("Node")),
+                global("alias", data_type("Node")),
+                global("score", DataType::simple(primitives::INT32)),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn node(heap: &mut ObjectHeap, value: Dynamic, next: Dynamic) -> ObjectHandle {
+        heap.allocate(ScriptObjectData {
+            type_hash: TypeHash::from_name("Node"),
+            fields: vec![value, next],
+        })
+    }
+
+    fn fields(heap: &ObjectHeap, handle: ObjectHandle) -> &[Dynamic] {
+        &heap.get::<ScriptObjectData>(handle).unwrap().fields
+    }
+
+    #[test]
+    fn globals_and_shared_objects_are_restored() {
+        let old = node_module(primitives::INT32);
+        let mut heap = ObjectHeap::new();
+        // A cycle of two nodes, with a second handle to the first
+        let first = node(&mut heap, Dynamic::Int(1), Dynamic::NullHandle);
+        let second = node(&mut heap, Dynamic::Int(2), Dynamic::Object(first));
+        heap.add_ref(first);
+        heap.get_mut::<ScriptObjectData>(first).unwrap().fields[1] = Dynamic::Object(second);
+        let mut globals = GlobalTable::new();
+        globals.declare("head", data_type("Node"), Dynamic::Object(first));
+        globals.declare("alias", data_type("Node"), Dynamic::Object(first));
+        globals.declare(
+            "score",
+            DataType::simple(primitives::INT32),
+            Dynamic::Int(7),
+        );
+
+        let mut serializer = Serializer::new();
+        serializer.store_state(&old, &globals, &heap);
+        assert_eq!(serializer.global_count(), 3);
+        assert_eq!(serializer.object_count(), 2);
+
+        let new = node_module(primitives::INT32);
+        let mut globals = GlobalTable::new();
+        let mut heap = ObjectHeap::new();
+        let report = serializer.restore_state(&new, &mut globals, &mut heap, UnitId::new(1));
+        assert!(report.is_complete(), "{:?}", report.issues);
+        assert_eq!(report.migrated_globals, 3);
+        assert_eq!(report.migrated_objects, 2);
+
+        let Dynamic::Object(head) = globals.get("head").unwrap().value else {
+            panic!("head is not an object");
+        };
+        assert_eq!(globals.get("alias").unwrap().value, Dynamic::Object(head));
+        assert_eq!(globals.get("score").unwrap().value, Dynamic::Int(7));
+        // Referenced by both globals and by the other node
+        assert_eq!(heap.ref_count(head), Some(3));
+        let Dynamic::Object(next) = fields(&heap, head)[1] else {
+            panic!("next is not an object");
+        };
+        assert_eq!(
+            fields(&heap, next),
+            [Dynamic::Int(2), Dynamic::Object(head)]
+        );
+        assert_eq!(heap.ref_count(next), Some(1));
+    }
+
+    #[test]
+    fn changed_declarations_are_reported() {
+        let old = node_module(primitives::INT32);
+        let mut heap = ObjectHeap::new();
+        let head = node(&mut heap, Dynamic::Int(5), Dynamic::NullHandle);
+        let mut globals = GlobalTable::new();
+        globals.declare("head", data_type("Node"), Dynamic::Object(head));
+        globals.declare("score", data_type("Score"), Dynamic::Int(1));
+        globals.declare(
+            "lives",
+            DataType::simple(primitives::INT32),
+            Dynamic::Int(3),
+        );
+        globals.declare(
+            "sprite",
+            data_type("Sprite"),
+            Dynamic::Native(Box::new(1u8)),
+        );
+
+        let mut serializer = Serializer::new();
+        serializer.store_state(&old, &globals, &heap);
+
+        let mut new = node_module(primitives::FLOAT);
+        new.globals.push(global("sprite", data_type("Sprite")));
+        let mut globals = GlobalTable::new();
+        let mut heap = ObjectHeap::new();
+        let report = serializer.restore_state(&new, &mut globals, &mut heap, UnitId::new(1));
+
+        let Dynamic::Object(head) = globals.get("head").unwrap().value else {
+            panic!("head is not an object");
+        };
+        assert_eq!(
+            fields(&heap, head),
+            [Dynamic::Float(0.0), Dynamic::NullHandle]
+        );
+        assert_eq!(report.class_changes.len(), 1);
+        assert_eq!(report.class_changes[0].retyped, ["value"]);
+        assert_eq!(
+            report.issues,
+            vec![
+                MigrationIssue::FieldReset {
+                    object: head,
+                    class: "Node".into(),
+                    field: "value".into(),
+                },
+                MigrationIssue::GlobalTypeChanged {
+                    name: "score".into()
+                },
+                MigrationIssue::GlobalRemoved {
+                    name: "lives".into()
+                },
+                MigrationIssue::ValueNotStored {
+                    name: "sprite".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn units_must_be_built() {
+        let mut serializer = Serializer::new();
+        let mut unit = Unit::new();
+        assert!(matches!(
+            serializer.store(&unit),
+            Err(GlobalError::NotBuilt)
+        ));
+        assert!(matches!(
+            serializer.restore(&mut unit),
+            Err(GlobalError::NotBuilt)
+        ));
+
+        unit.add_source("test.as", "void main() { }").unwrap();
+        unit.build().unwrap();
+        serializer.store(&unit).unwrap();
+        assert_eq!(
+            serializer.restore(&mut unit).unwrap(),
+            ReloadReport::default()
+        );
+    }
+
+    struct Sprite(u8);
+
+    struct SpriteType;
+
+    impl UserType for SpriteType {
+        fn store(&self, value: &Dynamic, heap: &ObjectHeap) -> Option<Box<dyn Any + Send + Sync>> {
+            match value {
+                Dynamic::Object(handle) => heap.get::<Sprite>(*handle).map(|s| Box::new(s.0) as _),
+                _ => None,
+            }
+        }
+
+        fn restore(&self, stored: &(dyn Any + Send + Sync), heap: &mut ObjectHeap) -> Dynamic {
+            let frame = *stored.downcast_ref::<u8>().unwrap();
+            Dynamic::Object(heap.allocate(Sprite(frame)))
+        }
+    }
+
+    #[test]
+    fn user_types_and_extra_objects() {
+        let mut module = node_module(primitives::INT32);
+        module.globals = vec![global("sprite", data_type("Sprite"))];
+        let mut heap = ObjectHeap::new();
+        let sprite = heap.allocate(Sprite(4));
+        let held = node(&mut heap, Dynamic::Int(9), Dynamic::NullHandle);
+        let mut globals = GlobalTable::new();
+        globals.declare("sprite", data_type("Sprite"), Dynamic::Object(sprite));
+
+        let mut serializer = Serializer::new();
+        serializer.add_user_type(TypeHash::from_name("Sprite"), SpriteType);
+        let held = ScriptObject::new(held, TypeHash::from_name("Node"), UnitId::new(0));
+        serializer.add_extra_object(&held);
+        serializer.store_state(&module, &globals, &heap);
+
+        let mut globals = GlobalTable::new();
+        let mut heap = ObjectHeap::new();
+        let report = serializer.restore_state(&module, &mut globals, &mut heap, UnitId::new(1));
+        assert!(report.is_complete(), "{:?}", report.issues);
+
+        let Dynamic::Object(sprite) = globals.get("sprite").unwrap().value else {
+            panic!("sprite is not an object");
+        };
+        assert_eq!(heap.get::<Sprite>(sprite).unwrap().0, 4);
+
+        let restored = serializer.take_restored_object(&held).unwrap();
+        assert_eq!(restored.unit(), UnitId::new(1));
+        assert_eq!(heap.ref_count(restored.handle()), Some(1));
+        assert_eq!(fields(&heap, restored.handle())[0], Dynamic::Int(9));
+        assert!(serializer.take_restored_object(&held).is_none());
+    }
+}
@@ -0,0 +1,262 @@
+//! Script execution trace recorder.
+//!
+//! Where the [`Profiler`](crate::Profiler) aggregates statistics, the
+//! [`TraceRecorder`] keeps a timeline of every script function entry and exit
+//! and every coroutine suspension. The timeline is exported in the Chrome
+//! trace event format, which chrome://tracing, Perfetto and Tracy's importer
+//! can open, so frame spikes caused by scripts can be inspected next to
+//! engine traces.
+//!
+//! # Example
+//!
+//! ```ignore
+//! unit.set_tracing(true);
+//! unit.call("update", (dt,))?;
+//!
+//! std::fs::write("frame.json", unit.trace_json().unwrap())?;
+//! ```
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use rustc_hash::FxHashMap;
+
+/// Records a timeline of script execution.
+#[derive(Debug)]
+pub struct TraceRecorder {
+    /// Time all timestamps are relative to.
+    origin: Instant,
+    /// Recorded events, in order.
+    events: Vec<TraceEvent>,
+    /// Functions currently executing (innermost last).
+    stack: Vec<String>,
+    /// Call stacks of suspended coroutines, restored on resume.
+    suspended: FxHashMap<String, Vec<String>>,
+}
+
+/// Kind of a recorded trace event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceEventKind {
+    /// A function started executing.
+    Begin,
+    /// The innermost executing function returned or was suspended.
+    End,
+    /// A coroutine was suspended.
+    Suspend,
+}
+
+/// A single point on the timeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEvent {
+    /// Function or coroutine name.
+    pub name: String,
+    /// What happened.
+    pub kind: TraceEventKind,
+    /// Time since the recorder was created.
+    pub timestamp: Duration,
+}
+
+impl Default for TraceRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TraceRecorder {
+    /// Create a new, empty recorder.
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            events: Vec::new(),
+            stack: Vec::new(),
+            suspended: FxHashMap::default(),
+        }
+    }
+
+    /// Record entry into a script function.
+    pub fn enter_function(&mut self, name: &str) {
+        self.push(name.to_string(), TraceEventKind::Begin);
+        self.stack.push(name.to_string());
+    }
+
+    /// Record exit from the innermost script function.
+    ///
+    /// Unbalanced exits (with no active function) are ignored.
+    pub fn exit_function(&mut self) {
+        if let Some(name) = self.stack.pop() {
+            self.push(name, TraceEventKind::End);
+        }
+    }
+
+    /// Record suspension of a coroutine.
+    ///
+    /// All executing functions end on the timeline until the coroutine is
+    /// resumed.
+    pub fn suspend(&mut self, coroutine: &str) {
+        let stack = std::mem::take(&mut self.stack);
+        for name in stack.iter().rev() {
+            self.push(name.clone(), TraceEventKind::End);
+        }
+        self.push(coroutine.to_string(), TraceEventKind::Suspend);
+        self.suspended.insert(coroutine.to_string(), stack);
+    }
+
+    /// Record resumption of a suspended coroutine, reopening its functions.
+    ///
+    /// Resuming a coroutine that was not suspended is ignored.
+    pub fn resume(&mut self, coroutine: &str) {
+        let Some(stack) = self.suspended.remove(coroutine) else {
+            return;
+        };
+        for name in &stack {
+            self.enter_function(name);
+        }
+    }
+
+    /// The recorded events, in order.
+    pub fn events(&self) -> &[TraceEvent] {
+        &self.events
+    }
+
+    /// Discard all recorded events.
+    pub fn reset(&mut self) {
+        self.events.clear();
+        self.stack.clear();
+        self.suspended.clear();
+    }
+
+    /// Export the timeline in the Chrome trace event format.
+    ///
+    /// Functions still executing are closed at the time of the export.
+    pub fn to_chrome_json(&self) -> String {
+        let now = self.origin.elapsed();
+        let open = self.stack.iter().rev().map(|name| TraceEvent {
+            name: name.clone(),
+            kind: TraceEventKind::End,
+            timestamp: now,
+        });
+
+        let mut out = String::from("{\"traceEvents\":[");
+        for (index, event) in self.events.iter().cloned().chain(open).enumerate() {
+            if index > 0 {
+                out.push(',');
+            }
+            out.push_str("{\"name\":");
+            write_json_string(&mut out, &event.name);
+            let phase = match event.kind {
+                TraceEventKind::Begin => "\"B\"",
+                TraceEventKind::End => "\"E\"",
+                // Thread-scoped instant event
+                TraceEventKind::Suspend => "\"i\",\"s\":\"t\"",
+            };
+            let _ = write!(
+                out,
+                ",\"cat\":\"script\",\"ph\":{},\"ts\":{:.3},\"pid\":1,\"tid\":1}}",
+                phase,
+                event.timestamp.as_secs_f64() * 1_000_000.0
+            );
+        }
+        out.push_str("]}");
+        out
+    }
+
+    fn push(&mut self, name: String, kind: TraceEventKind) {
+        self.events.push(TraceEvent {
+            name,
+            kind,
+            timestamp: self.origin.elapsed(),
+        });
+    }
+}
+
+fn write_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(recorder: &TraceRecorder) -> Vec<(&str, TraceEventKind)> {
+        recorder
+            .events()
+            .iter()
+            .map(|e| (e.name.as_str(), e.kind))
+            .collect()
+    }
+
+    #[test]
+    fn records_nested_calls() {
+        let mut recorder = TraceRecorder::new();
+        recorder.enter_function("update");
+        recorder.enter_function("move");
+        recorder.exit_function();
+        recorder.exit_function();
+        recorder.exit_function();
+
+        assert_eq!(
+            kinds(&recorder),
+            vec![
+                ("update", TraceEventKind::Begin),
+                ("move", TraceEventKind::Begin),
+                ("move", TraceEventKind::End),
+                ("update", TraceEventKind::End),
+            ]
+        );
+        let timestamps: Vec<_> = recorder.events().iter().map(|e| e.timestamp).collect();
+        assert!(timestamps.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn suspend_closes_and_resume_reopens_frames() {
+        let mut recorder = TraceRecorder::new();
+        recorder.enter_function("patrol");
+        recorder.enter_function("walk");
+        recorder.suspend("guard");
+        recorder.resume("guard");
+
+        assert_eq!(
+            kinds(&recorder),
+            vec![
+                ("patrol", TraceEventKind::Begin),
+                ("walk", TraceEventKind::Begin),
+                ("walk", TraceEventKind::End),
+                ("patrol", TraceEventKind::End),
+                ("guard", TraceEventKind::Suspend),
+                ("patrol", TraceEventKind::Begin),
+                ("walk", TraceEventKind::Begin),
+            ]
+        );
+    }
+
+    #[test]
+    fn chrome_json_closes_open_frames() {
+        let mut recorder = TraceRecorder::new();
+        recorder.enter_function("say \"hi\"");
+        recorder.suspend("co");
+
+        let json = recorder.to_chrome_json();
+        assert!(json.starts_with("{\"traceEvents\":[{\"name\":\"say \\\"hi\\\"\""));
+        assert!(json.contains("\"ph\":\"B\""));
+        assert!(json.contains("\"ph\":\"i\",\"s\":\"t\""));
+        assert_eq!(json.matches("\"ph\":\"E\"").count(), 1);
+
+        recorder.resume("co");
+        let json = recorder.to_chrome_json();
+        assert_eq!(json.matches("\"ph\":\"E\"").count(), 2);
+    }
+}
@@ -0,0 +1,278 @@
+//! Script API usage statistics.
+//!
+//! [`UsageStats`] is an opt-in [`CompilerPlugin`] counting how often the
+//! compiled scripts of a context name each registered type and global
+//! function. The VM may also count native calls through
+//! [`UsageStats::record_call`]. The [`UsageReport`] lists every registered
+//! entry, so what scripts never use is easy to find before deprecating it:
+//!
+//! ```ignore
+//! let stats = Arc::new(UsageStats::new());
+//! let ctx = Context::builder()
+//!     .with_default_modules()
+//!     .plugin(Box::new(stats.clone()))
+//!     .build_shared()?;
+//! // ... build units ...
+//! let report = stats.report(ctx.registry());
+//! for name in report.unused_functions() {
+//!     println!("never called: {name}");
+//! }
+//! ```
+
+use std::fmt;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use angelscript_compiler::usage::collect_usage;
+use angelscript_compiler::{CompilerPlugin, PluginContext};
+use angelscript_parser::ast::Script;
+use angelscript_registry::SymbolRegistry;
+use rustc_hash::FxHashMap;
+
+/// Counts references to the registered API across compiled scripts.
+#[derive(Debug, Default)]
+pub struct UsageStats {
+    counts: Mutex<Counts>,
+}
+
+#[derive(Debug, Default)]
+struct Counts {
+    types: FxHashMap<String, ApiUse>,
+    functions: FxHashMap<String, ApiUse>,
+}
+
+/// How much scripts use a registered type or function.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ApiUse {
+    /// Number of references in compiled scripts.
+    pub references: u64,
+    /// Number of compiled scripts referencing it.
+    pub scripts: u64,
+    /// Number of calls recorded at runtime.
+    pub calls: u64,
+}
+
+impl ApiUse {
+    /// Returns true if no script referenced or called it.
+    pub fn is_unused(&self) -> bool {
+        self.references == 0 && self.calls == 0
+    }
+}
+
+impl UsageStats {
+    /// Create empty statistics.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a runtime call of a registered function by qualified name.
+    pub fn record_call(&self, function: &str) {
+        let mut counts = self.counts();
+        match counts.functions.get_mut(function) {
+            Some(usage) => usage.calls += 1,
+            None => {
+                counts.functions.insert(
+                    function.to_string(),
+                    ApiUse {
+                        calls: 1,
+                        ..ApiUse::default()
+                    },
+                );
+            }
+        }
+    }
+
+    /// Forget everything recorded so far.
+    pub fn reset(&self) {
+        *self.counts() = Counts::default();
+    }
+
+    /// Snapshot the statistics, including the types and functions registered
+    /// in `registry` that were never used.
+    pub fn report(&self, registry: &SymbolRegistry) -> UsageReport {
+        let counts = self.counts();
+        let mut types = counts.types.clone();
+        for entry in registry.types() {
+            if entry.source().is_some_and(|source| source.is_ffi()) {
+                types.entry(entry.qualified_name().to_string()).or_default();
+            }
+        }
+        let mut functions = counts.functions.clone();
+        for function in registry.functions() {
+            if !function.is_script() && function.def.object_type.is_none() {
+                functions
+                    .entry(function.def.qualified_name().to_string())
+                    .or_default();
+            }
+        }
+        UsageReport {
+            types: sorted(types),
+            functions: sorted(functions),
+        }
+    }
+
+    fn counts(&self) -> MutexGuard<'_, Counts> {
+        self.counts.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl CompilerPlugin for UsageStats {
+    fn name(&self) -> &str {
+        "usage"
+    }
+
+    fn after_registration(&self, ctx: &mut PluginContext<'_>, script: &Script<'_>) {
+        let usage = collect_usage(script, ctx.registry());
+        let counts = &mut *self.counts();
+        for (found, total) in [
+            (usage.types, &mut counts.types),
+            (usage.functions, &mut counts.functions),
+        ] {
+            for (name, references) in found {
+                let entry = total.entry(name).or_default();
+                entry.references += references;
+                entry.scripts += 1;
+            }
+        }
+    }
+}
+
+/// Most used first, then by name.
+fn sorted(map: FxHashMap<String, ApiUse>) -> Vec<(String, ApiUse)> {
+    let mut entries: Vec<_> = map.into_iter().collect();
+    entries.sort_by(|(a_name, a), (b_name, b)| {
+        (b.references + b.calls)
+            .cmp(&(a.references + a.calls))
+            .then_with(|| a_name.cmp(b_name))
+    });
+    entries
+}
+
+/// Snapshot of API usage statistics.
+#[derive(Debug, Clone, Default)]
+pub struct UsageReport {
+    /// Types by qualified name, most used first.
+    types: Vec<(String, ApiUse)>,
+    /// Global functions by qualified name, most used first.
+    functions: Vec<(String, ApiUse)>,
+}
+
+impl UsageReport {
+    /// Usage of every registered type, most used first.
+    pub fn types(&self) -> &[(String, ApiUse)] {
+        &self.types
+    }
+
+    /// Usage of every registered global function, most used first.
+    pub fn functions(&self) -> &[(String, ApiUse)] {
+        &self.functions
+    }
+
+    /// Look up the usage of a type by qualified name.
+    pub fn type_usage(&self, name: &str) -> Option<ApiUse> {
+        find(&self.types, name)
+    }
+
+    /// Look up the usage of a function by qualified name.
+    pub fn function_usage(&self, name: &str) -> Option<ApiUse> {
+        find(&self.functions, name)
+    }
+
+    /// Registered types no script used, by name.
+    pub fn unused_types(&self) -> impl Iterator<Item = &str> {
+        unused(&self.types)
+    }
+
+    /// Registered functions no script used, by name.
+    pub fn unused_functions(&self) -> impl Iterator<Item = &str> {
+        unused(&self.functions)
+    }
+}
+
+fn find(entries: &[(String, ApiUse)], name: &str) -> Option<ApiUse> {
+    entries.iter().find(|(n, _)| n == name).map(|(_, u)| *u)
+}
+
+fn unused(entries: &[(String, ApiUse)]) -> impl Iterator<Item = &str> {
+    entries
+        .iter()
+        .filter(|(_, usage)| usage.is_unused())
+        .map(|(name, _)| name.as_str())
+}
+
+/// One line per entry: `kind name references scripts calls`.
+impl fmt::Display for UsageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<9} {:<40} {:>10} {:>8} {:>10}",
+            "kind", "name", "references", "scripts", "calls"
+        )?;
+        for (kind, entries) in [("type", &self.types), ("function", &self.functions)] {
+            for (name, usage) in entries {
+                writeln!(
+                    f,
+                    "{:<9} {:<40} {:>10} {:>8} {:>10}",
+                    kind, name, usage.references, usage.scripts, usage.calls
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Context;
+    use std::sync::Arc;
+
+    #[test]
+    fn counts_references_across_units() {
+        let stats = Arc::new(UsageStats::new());
+        let ctx = Context::builder()
+            .with_default_modules()
+            .plugin(Box::new(stats.clone()))
+            .build_shared()
+            .unwrap();
+        for source in [
+            "void f() { dictionary d; print(\"a\"); print(\"b\"); }",
+            "void g() { print(\"c\"); }",
+        ] {
+            let mut unit = ctx.create_unit().unwrap();
+            unit.add_source("test.as", source).unwrap();
+            unit.build().unwrap();
+        }
+        stats.record_call("print");
+
+        let report = stats.report(ctx.registry());
+        assert_eq!(
+            report.function_usage("print"),
+            Some(ApiUse {
+                references: 3,
+                scripts: 2,
+                calls: 1,
+            })
+        );
+        assert_eq!(report.functions()[0].0, "print");
+        assert_eq!(report.type_usage("dictionary").unwrap().references, 1);
+        assert!(report.unused_types().any(|name| name == "array"));
+        assert!(report.unused_functions().any(|name| name == "math::sqrt"));
+        assert!(
+            report
+                .to_string()
+                .lines()
+                .nth(1)
+                .unwrap()
+                .contains("dictionary")
+        );
+
+        stats.reset();
+        assert!(
+            stats
+                .report(ctx.registry())
+                .function_usage("print")
+                .unwrap()
+                .is_unused()
+        );
+    }
+}
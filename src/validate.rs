@@ -0,0 +1,198 @@
+//! Compile-time validation of call arguments.
+//!
+//! [`CallValidators`] is a [`CompilerPlugin`] running host checks on the
+//! arguments of calls to specific functions while a script compiles, so a
+//! mistyped event name or malformed format string is a compilation error
+//! rather than a runtime failure:
+//!
+//! ```ignore
+//! let ctx = Context::builder()
+//!     .with_default_modules()
+//!     .install(audio_module())
+//!     .validate_call("audio::play", |args| match &args[0] {
+//!         Some(ConstValue::String(name)) if !EVENTS.contains(&&name[..]) => {
+//!             Err(format!("unknown sound event '{}'", String::from_utf8_lossy(name)))
+//!         }
+//!         _ => Ok(()),
+//!     })
+//!     .build()?;
+//! ```
+//!
+//! Functions are matched by their name as written at the call, including
+//! its namespace (`audio::play`). Each argument is passed as its constant
+//! value, folded like any constant expression, or `None` if it is only
+//! known at runtime.
+
+use angelscript_compiler::const_eval::evaluate_constants;
+use angelscript_compiler::{
+    CompilerPlugin, ConstEvaluator, ConstValue, IntegerOverflow, PluginContext,
+};
+use angelscript_core::Span;
+use angelscript_parser::ast::visitor::{self, Visitor};
+use angelscript_parser::ast::{CallExpr, Expr, Script};
+use rustc_hash::FxHashMap;
+
+/// Checks the arguments of a call, returning why they are invalid.
+pub type CallValidator = Box<dyn Fn(&[Option<ConstValue>]) -> Result<(), String> + Send + Sync>;
+
+/// Validators of call arguments, by function name.
+#[derive(Default)]
+pub struct CallValidators {
+    validators: FxHashMap<String, Vec<CallValidator>>,
+}
+
+impl CallValidators {
+    /// Create a plugin without validators.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validate the arguments of every call to `function`.
+    pub fn add<F>(&mut self, function: impl Into<String>, validator: F)
+    where
+        F: Fn(&[Option<ConstValue>]) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.validators
+            .entry(function.into())
+            .or_default()
+            .push(Box::new(validator));
+    }
+
+    /// Returns true if no validator is registered.
+    pub fn is_empty(&self) -> bool {
+        self.validators.is_empty()
+    }
+}
+
+impl CompilerPlugin for CallValidators {
+    fn name(&self) -> &str {
+        "validate"
+    }
+
+    fn after_registration(&self, ctx: &mut PluginContext<'_>, script: &Script<'_>) {
+        if self.validators.is_empty() {
+            return;
+        }
+        // Arguments may name the script's constants as well as registered ones
+        let (constants, _) = evaluate_constants(script, ctx.registry(), IntegerOverflow::default());
+        let mut calls = Calls {
+            validators: &self.validators,
+            evaluator: constants.evaluator,
+            errors: Vec::new(),
+        };
+        calls.evaluator.set_namespace(Vec::new());
+        calls.visit_script(script);
+        for (message, span) in calls.errors {
+            ctx.error(message, span);
+        }
+    }
+}
+
+/// Runs the validators on the calls of a script.
+struct Calls<'a> {
+    validators: &'a FxHashMap<String, Vec<CallValidator>>,
+    evaluator: ConstEvaluator,
+    errors: Vec<(String, Span)>,
+}
+
+impl<'ast> Visitor<'ast> for Calls<'_> {
+    fn visit_call_expr(&mut self, expr: &CallExpr<'ast>) {
+        if let Expr::Ident(callee) = expr.callee {
+            let name = match &callee.scope {
+                Some(scope) if !scope.segments.is_empty() => {
+                    let mut path: Vec<&str> = scope.segments.iter().map(|s| s.name).collect();
+                    path.push(callee.ident.name);
+                    path.join("::")
+                }
+                _ => callee.ident.name.to_string(),
+            };
+            if let Some(validators) = self.validators.get(&name) {
+                // Errors in the arguments are reported by the compiler
+                let args: Vec<Option<ConstValue>> = expr
+                    .args
+                    .iter()
+                    .map(|arg| self.evaluator.eval(arg.value).ok().flatten())
+                    .collect();
+                for validator in validators {
+                    if let Err(reason) = validator(&args) {
+                        self.errors
+                            .push((format!("invalid call to '{}': {}", name, reason), expr.span));
+                    }
+                }
+            }
+        }
+        visitor::walk_call_expr(self, expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{BuildError, Context};
+    use angelscript_core::CompilationError;
+
+    fn play_sound(args: &[Option<ConstValue>]) -> Result<(), String> {
+        match args {
+            [Some(ConstValue::String(name)), ..] if !name.starts_with(b"sfx/") => Err(format!(
+                "unknown sound event '{}'",
+                String::from_utf8_lossy(name)
+            )),
+            [_, Some(ConstValue::Double(volume))] if !(0.0..=1.0).contains(volume) => {
+                Err(format!("volume {} is not between 0 and 1", volume))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn build(source: &str) -> Result<(), BuildError> {
+        let mut validators = CallValidators::new();
+        validators.add("audio::play", play_sound);
+        let ctx = Context::builder()
+            .plugin(Box::new(validators))
+            .build_shared()
+            .unwrap();
+        let mut unit = ctx.create_unit().unwrap();
+        unit.add_source("test.as", source).unwrap();
+        unit.build()
+    }
+
+    #[test]
+    fn constant_arguments_are_validated() {
+        build(
+            r#"
+            const double LOUD = 1.0;
+            void f(double volume) {
+                audio::play("sfx/jump", LOUD);
+                audio::play("sfx/land", volume);
+            }
+            "#,
+        )
+        .unwrap();
+
+        let Err(BuildError::CompilationErrors(errors)) = build(
+            r#"
+            const double TOO_LOUD = 0.5 * 4;
+            void f() {
+                audio::play("sfx/jump", TOO_LOUD);
+                audio::play("jump", 0.5);
+            }
+            "#,
+        ) else {
+            panic!("expected the build to fail");
+        };
+        let messages: Vec<String> = errors
+            .iter()
+            .map(|error| match error {
+                CompilationError::Other { message, .. } => message.clone(),
+                other => panic!("unexpected error {other}"),
+            })
+            .collect();
+        assert_eq!(
+            messages,
+            [
+                "[validate] invalid call to 'audio::play': volume 2 is not between 0 and 1",
+                "[validate] invalid call to 'audio::play': unknown sound event 'jump'",
+            ]
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Type checks for values written into script storage from Rust.
+//!
+//! Script globals and script object fields have declared [`DataType`]s, but
+//! are stored as [`Dynamic`] slots. Host writes go through
+//! [`check_assignable`] so that a slot never holds a value the compiled
+//! bytecode does not expect.
+
+use angelscript_compiler::ConstValue;
+use angelscript_core::{ConversionError, DataType, Dynamic, TypeHash, primitives};
+
+/// Why a value cannot be stored in a slot.
+#[derive(Debug)]
+pub(crate) enum AssignError {
+    /// The value has the wrong kind for the declared type.
+    Mismatch {
+        expected: &'static str,
+        actual: &'static str,
+    },
+    /// The value has the right kind but is out of range.
+    Conversion(ConversionError),
+}
+
+/// Check that `value` can be stored in a slot of type `data_type`.
+///
+/// `current` is the slot's existing value, used for types whose
+/// representation cannot be derived from the declared type alone.
+pub(crate) fn check_assignable(
+    data_type: &DataType,
+    current: &Dynamic,
+    value: &Dynamic,
+) -> Result<(), AssignError> {
+    let mismatch = |expected: &'static str| AssignError::Mismatch {
+        expected,
+        actual: value.type_name(),
+    };
+
+    if data_type.is_handle {
+        return match value {
+            Dynamic::Object(_) | Dynamic::NullHandle => Ok(()),
+            _ => Err(mismatch("object")),
+        };
+    }
+
+    let hash = data_type.type_hash;
+    if hash == primitives::BOOL {
+        return match value {
+            Dynamic::Bool(_) => Ok(()),
+            _ => Err(mismatch("bool")),
+        };
+    }
+    if hash == primitives::FLOAT || hash == primitives::DOUBLE {
+        return match value {
+            Dynamic::Float(_) => Ok(()),
+            _ => Err(mismatch("float")),
+        };
+    }
+    if hash == primitives::STRING {
+        return match value {
+            Dynamic::String(_) => Ok(()),
+            _ => Err(mismatch("string")),
+        };
+    }
+    if data_type.is_enum || int_range(hash).is_some() {
+        let Dynamic::Int(v) = value else {
+            return Err(mismatch("int"));
+        };
+        if let Some((min, max)) = int_range(hash)
+            && !(min..=max).contains(v)
+        {
+            return Err(AssignError::Conversion(ConversionError::IntegerOverflow {
+                value: *v,
+                target_type: "int",
+            }));
+        }
+        return Ok(());
+    }
+
+    // Value and native types: the new value must have the same representation,
+    // unless the slot has not been constructed yet
+    if matches!(current, Dynamic::NullHandle)
+        || std::mem::discriminant(current) == std::mem::discriminant(value)
+    {
+        Ok(())
+    } else {
+        Err(mismatch(current.type_name()))
+    }
+}
+
+/// The zero value of a declared type, used for uninitialized slots.
+///
+/// Handles and object-typed slots start out null until a constructor runs.
+pub(crate) fn default_value(data_type: &DataType) -> Dynamic {
+    let hash = data_type.type_hash;
+    if data_type.is_handle {
+        Dynamic::NullHandle
+    } else if hash == primitives::BOOL {
+        Dynamic::Bool(false)
+    } else if hash == primitives::FLOAT || hash == primitives::DOUBLE {
+        Dynamic::Float(0.0)
+    } else if hash == primitives::STRING {
+        Dynamic::String(String::new())
+    } else if data_type.is_enum || int_range(hash).is_some() {
+        Dynamic::Int(0)
+    } else {
+        Dynamic::NullHandle
+    }
+}
+
+/// The slot value of a constant folded by the compiler.
+pub(crate) fn const_value(value: &ConstValue) -> Dynamic {
+    match value {
+        ConstValue::Int(value) => Dynamic::Int(*value),
+        ConstValue::Float(value) => Dynamic::Float(f64::from(*value)),
+        ConstValue::Double(value) => Dynamic::Float(*value),
+        ConstValue::Bool(value) => Dynamic::Bool(*value),
+        ConstValue::String(bytes) => Dynamic::String(String::from_utf8_lossy(bytes).into_owned()),
+    }
+}
+
+/// Representable range of an integer primitive, stored as `i64`.
+fn int_range(hash: TypeHash) -> Option<(i64, i64)> {
+    let range = match hash {
+        h if h == primitives::INT8 => (i8::MIN as i64, i8::MAX as i64),
+        h if h == primitives::INT16 => (i16::MIN as i64, i16::MAX as i64),
+        h if h == primitives::INT32 => (i32::MIN as i64, i32::MAX as i64),
+        h if h == primitives::UINT8 => (0, u8::MAX as i64),
+        h if h == primitives::UINT16 => (0, u16::MAX as i64),
+        h if h == primitives::UINT32 => (0, u32::MAX as i64),
+        // 64-bit values use the full i64 bit pattern
+        h if h == primitives::INT64 || h == primitives::UINT64 => (i64::MIN, i64::MAX),
+        _ => return None,
+    };
+    Some(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults() {
+        assert!(matches!(
+            default_value(&DataType::simple(primitives::INT32)),
+            Dynamic::Int(0)
+        ));
+        assert!(matches!(
+            default_value(&DataType::simple(primitives::DOUBLE)),
+            Dynamic::Float(_)
+        ));
+        assert!(matches!(
+            default_value(&DataType::simple(primitives::BOOL)),
+            Dynamic::Bool(false)
+        ));
+        assert!(matches!(
+            default_value(&DataType::simple(primitives::STRING)),
+            Dynamic::String(_)
+        ));
+        assert!(matches!(
+            default_value(&DataType::simple(TypeHash::from_name("Player"))),
+            Dynamic::NullHandle
+        ));
+    }
+
+    #[test]
+    fn enum_accepts_any_int() {
+        let mut ty = DataType::simple(TypeHash::from_name("Color"));
+        ty.is_enum = true;
+        assert!(check_assignable(&ty, &Dynamic::Int(0), &Dynamic::Int(i64::MAX)).is_ok());
+        assert!(check_assignable(&ty, &Dynamic::Int(0), &Dynamic::Bool(true)).is_err());
+    }
+
+    #[test]
+    fn native_values_keep_representation() {
+        let ty = DataType::simple(TypeHash::from_name("Vec3"));
+        let current = Dynamic::Native(Box::new(1u8));
+        assert!(check_assignable(&ty, &current, &Dynamic::Native(Box::new(2u8))).is_ok());
+        assert!(matches!(
+            check_assignable(&ty, &current, &Dynamic::Int(1)),
+            Err(AssignError::Mismatch {
+                expected: "native",
+                ..
+            })
+        ));
+    }
+}
@@ -9,7 +9,8 @@
 #![allow(non_snake_case, dead_code, unused_variables)]
 
 use angelscript::{
-    Any, Behavior, HasClassMeta, HasFunctionMeta, TypeHash, funcdef, function, interface,
+    Any, Behavior, Dynamic, HasClassMeta, HasFunctionMeta, NativeError, ScriptDispatch,
+    ScriptProxy, TypeHash, funcdef, function, interface,
 };
 
 // ============================================================================
@@ -268,6 +269,16 @@ impl Counter {
         self.value += other.value;
     }
 
+    /// Generic method taking a variable-type argument by pointer to its slot
+    #[function(generic)]
+    #[param(variable, in)]
+    fn add_any(&mut self, value: *mut ()) {
+        // SAFETY: generic pointer params point to the argument's Dynamic slot
+        if let Dynamic::Int(amount) = unsafe { &*value.cast::<Dynamic>() } {
+            self.value += *amount as i32;
+        }
+    }
+
     /// Method that compares with another instance (owned Self)
     #[function]
     fn equals(&self, other: Self) -> bool {
@@ -585,6 +596,79 @@ fn interface_mut_method() {
     assert_eq!(update.param_types.len(), 1);
 }
 
+/// Interface with a generated proxy.
+#[interface(proxy)]
+trait Scoreboard {
+    fn score(&self) -> i32;
+
+    #[function(name = "addPoints")]
+    fn add_points(&mut self, points: i32);
+
+    fn rank(&self) -> Result<i32, NativeError>;
+}
+
+/// Records dispatched calls and keeps a running total.
+struct MockDispatch<'a> {
+    calls: &'a mut Vec<String>,
+    total: i64,
+}
+
+impl ScriptDispatch for MockDispatch<'_> {
+    fn dispatch(&mut self, method: &str, args: Vec<Dynamic>) -> Result<Dynamic, NativeError> {
+        self.calls.push(method.to_string());
+        match (method, args.as_slice()) {
+            ("score", []) => Ok(Dynamic::Int(self.total)),
+            ("addPoints", [Dynamic::Int(points)]) => {
+                self.total += points;
+                Ok(Dynamic::Void)
+            }
+            _ => Err(NativeError::Other {
+                message: format!("unexpected call to {}", method),
+            }),
+        }
+    }
+}
+
+#[test]
+fn interface_proxy_dispatches() {
+    let mut calls = Vec::new();
+    {
+        let mut board: Box<dyn Scoreboard + '_> =
+            Box::new(ScoreboardProxy::from_dispatch(Box::new(MockDispatch {
+                calls: &mut calls,
+                total: 0,
+            })));
+        board.add_points(5);
+        board.add_points(7);
+        assert_eq!(board.score(), 12);
+        // Failures are returned by methods declared fallible
+        assert!(board.rank().is_err());
+    }
+    assert_eq!(calls, ["addPoints", "addPoints", "score", "rank"]);
+
+    assert_eq!(
+        ScoreboardProxy::interface_hash(),
+        __as_Scoreboard_interface_meta().type_hash
+    );
+}
+
+#[test]
+fn interface_proxy_try_methods_return_failures() {
+    let mut calls = Vec::new();
+    let board = ScoreboardProxy::from_dispatch(Box::new(MockDispatch {
+        calls: &mut calls,
+        total: 3,
+    }));
+    assert_eq!(board.try_score().unwrap(), 3);
+    assert!(matches!(
+        board.try_rank(),
+        Err(NativeError::Other { message }) if message == "unexpected call to rank"
+    ));
+
+    let rank = &__as_Scoreboard_interface_meta().methods[2];
+    assert_eq!(rank.return_type, <i32 as angelscript::Any>::type_hash());
+}
+
 /// Interface with method name override.
 #[interface]
 trait Serializable {
@@ -2161,6 +2245,27 @@ fn native_fn_method_ref_self_param() {
     }
 }
 
+/// Verify that pointer params of generic methods point to their argument slots.
+#[test]
+fn native_fn_method_pointer_param() {
+    use angelscript_core::{CallContext, Dynamic, ObjectHeap};
+
+    let meta = Counter::add_any__meta();
+    let native = meta.native_fn.expect("native_fn should be Some");
+
+    let mut args = vec![
+        Dynamic::Native(Box::new(Counter { value: 1 })),
+        Dynamic::Int(41),
+    ];
+    let mut ret = Dynamic::Void;
+    let mut heap = ObjectHeap::new();
+    let mut ctx = CallContext::new(&mut args, 1, &mut ret, &mut heap);
+
+    native.call(&mut ctx).expect("call should succeed");
+    let this: &Counter = ctx.this().unwrap();
+    assert_eq!(this.value, 42);
+}
+
 /// Verify that method taking owned Self as parameter works correctly.
 #[test]
 fn native_fn_method_owned_self_param() {
@@ -0,0 +1,30 @@
+//! The embedding flow using only the prelude.
+//!
+//! This compiles against the stable API alone, so dropping an item the
+//! flow needs from the prelude fails here before it reaches embedders.
+
+use angelscript::prelude::*;
+use std::sync::Arc;
+
+#[derive(Any)]
+#[angelscript(name = "Player")]
+struct Player;
+
+#[test]
+fn embed_with_prelude() -> Result<(), Box<dyn std::error::Error>> {
+    let mut ctx = Context::with_default_modules()?;
+    ctx.install(Module::new().ty::<Player>().global("MAX_HEALTH", 100i32))?;
+    let ctx = Arc::new(ctx);
+
+    let mut unit = ctx.create_unit()?;
+    unit.add_source(
+        "main.as",
+        "const int START = MAX_HEALTH / 2;\nvoid spawn(Player@ p, int health = START) {}",
+    )?;
+    unit.build()?;
+    assert!(unit.is_built());
+
+    let value: Dynamic = 7i32.into_dynamic();
+    assert_eq!(i32::from_dynamic(&value)?, 7);
+    Ok(())
+}